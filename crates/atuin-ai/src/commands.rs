@@ -51,6 +51,7 @@ impl Command {
             Self::Inline { args, .. } => LogConfig {
                 file: FileConfig::from_settings(&settings.logs, &settings.logs.ai),
                 stderr: args.verbose.then(StderrConfig::default),
+                otel: None,
             },
             _ => LogConfig::stderr_only(),
         }
@@ -273,7 +273,7 @@ async fn run_inline_tui(
         .map(String::from);
 
     // ─── Snapshot store ─────────────────────────────────────────
-    let snapshot_dir = atuin_common::utils::data_dir()
+    let snapshot_dir = atuin_common::paths::data_dir()
         .join("ai")
         .join("snapshots")
         .join(session_mgr.session_id());
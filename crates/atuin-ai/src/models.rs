@@ -31,7 +31,8 @@ pub(crate) async fn fetch_models(endpoint: &reqwest::Url, token: &str) -> Result
     atuin_common::tls::ensure_crypto_provider();
     let url = endpoint.append_path("api/cli/models")?;
 
-    let mut request = reqwest::Client::new()
+    let mut request = atuin_client::network::connection(reqwest::Client::builder())?
+        .build()?
         .get(url)
         .header(USER_AGENT, crate::stream::APP_USER_AGENT)
         .timeout(Duration::from_secs(10));
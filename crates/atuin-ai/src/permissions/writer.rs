@@ -84,7 +84,7 @@ pub(crate) fn project_permissions_path(project_root: &Path) -> std::path::PathBu
 
 /// Build the path to the global permissions file (sibling of atuin config).
 pub(crate) fn global_permissions_path() -> std::path::PathBuf {
-    atuin_common::utils::config_dir().join("permissions.ai.toml")
+    atuin_common::paths::config_dir().join("permissions.ai.toml")
 }
 
 #[cfg(test)]
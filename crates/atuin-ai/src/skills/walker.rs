@@ -47,7 +47,7 @@ pub(crate) async fn discover(
 
 /// The default global skills directory (`~/.config/atuin/skills/`).
 pub(crate) fn global_skills_dir() -> PathBuf {
-    atuin_common::utils::config_dir().join("skills")
+    atuin_common::paths::config_dir().join("skills")
 }
 
 /// Given a project working directory, return the project skills directory.
@@ -174,7 +174,15 @@ pub(crate) fn create_chat_stream(
             request_body["session_id"] = serde_json::json!(sid);
         }
 
-        let client = reqwest::Client::new();
+        let client = match atuin_client::network::connection(reqwest::Client::builder())
+            .and_then(|builder| Ok(builder.build()?))
+        {
+            Ok(client) => client,
+            Err(e) => {
+                yield Err(e.wrap_err("Failed to set up the SSE request"));
+                return;
+            }
+        };
         let mut request_builder = client
             .post(endpoint.clone())
             .header("Accept", "text/event-stream")
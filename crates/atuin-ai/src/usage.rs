@@ -93,7 +93,8 @@ pub(crate) async fn fetch_usage(endpoint: &reqwest::Url, token: &str) -> Result<
     atuin_common::tls::ensure_crypto_provider();
     let url = endpoint.append_path("api/cli/usage")?;
 
-    let response = reqwest::Client::new()
+    let response = atuin_client::network::connection(reqwest::Client::builder())?
+        .build()?
         .get(url)
         .header(USER_AGENT, crate::stream::APP_USER_AGENT)
         .bearer_auth(token)
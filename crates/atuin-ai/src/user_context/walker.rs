@@ -72,7 +72,7 @@ pub(crate) async fn walk(start: &Path, global_path: Option<&Path>) -> Result<Vec
 
 /// The default global context file path (`~/.config/atuin/TERMINAL.md`).
 pub(crate) fn global_context_path() -> PathBuf {
-    atuin_common::utils::config_dir().join(CONTEXT_FILENAME)
+    atuin_common::paths::config_dir().join(CONTEXT_FILENAME)
 }
 
 async fn load_context_file(path: &Path, depth: usize) -> Result<Option<FoundFile>> {
@@ -0,0 +1,24 @@
+-- How often each command was directly followed by another in the same
+-- session. Interactive search uses this to boost likely follow-ups of the
+-- command that was just run (e.g. `cargo test` after `cargo build`).
+-- Maintained incrementally as history is saved, so it's seeded here from
+-- the existing history once.
+create table if not exists command_bigrams (
+    prev_command text not null,
+    command text not null,
+    count integer not null default 0,
+
+    primary key (prev_command, command)
+);
+
+insert into command_bigrams (prev_command, command, count)
+select prev_command, command, count(*)
+from (
+    select
+        command,
+        lag(command) over (partition by session order by timestamp) as prev_command
+    from history
+    where deleted_at is null
+)
+where prev_command is not null
+group by prev_command, command;
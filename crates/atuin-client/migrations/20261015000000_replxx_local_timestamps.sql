@@ -0,0 +1,7 @@
+-- The replxx importer used to read the local times in replxx history files as
+-- if they were UTC. Convert those entries to real UTC, using SQLite's view of
+-- the local timezone (which also accounts for DST at each entry's date).
+update history
+set timestamp = cast(strftime('%s', timestamp / 1000000000, 'unixepoch', 'utc') as integer) * 1000000000
+    + timestamp % 1000000000
+where shell = 'replxx';
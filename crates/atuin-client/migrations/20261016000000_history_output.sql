@@ -0,0 +1,9 @@
+-- Output captured by `atuin run`, gzip compressed. Kept out of the history
+-- table so that searching and syncing history never has to read it, and
+-- local only: output can hold anything, so it is never synced.
+create table if not exists history_output (
+    id text primary key not null,
+    output blob not null,
+    total_bytes integer not null,
+    truncated integer not null default 0
+);
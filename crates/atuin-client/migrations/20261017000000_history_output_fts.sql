@@ -0,0 +1,5 @@
+-- A full text index over the start of captured output, for `in:output`
+-- searches. Rows share their rowid with history_output. Only the first
+-- `output.search_max_bytes` of each output is indexed, which keeps the index
+-- a small fraction of the (compressed) output it covers.
+create virtual table if not exists history_output_fts using fts5(text);
@@ -0,0 +1,8 @@
+-- The original values of anything clamped when an entry was saved, such as a
+-- timestamp years in the future from a skewed clock. Local only, like the
+-- history table itself: the record store keeps the entry as it was recorded.
+create table if not exists history_raw (
+    id text primary key not null,
+    timestamp integer,
+    duration integer
+);
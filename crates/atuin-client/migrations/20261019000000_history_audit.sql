@@ -0,0 +1,8 @@
+-- The account that ran each command, noted in append-only mode. Taken from the
+-- OS rather than the hostname's user, which ATUIN_HOST_USER can change. Local
+-- only, and kept when an entry is deleted, as an audit trail should be.
+create table if not exists history_audit (
+    id text primary key not null,
+    user text not null,
+    uid integer
+);
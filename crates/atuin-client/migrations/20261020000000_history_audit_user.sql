@@ -0,0 +1,3 @@
+-- Every command now notes its account, not just in append-only mode, and the
+-- user: filter looks entries up by it.
+create index if not exists idx_history_audit_user on history_audit(user);
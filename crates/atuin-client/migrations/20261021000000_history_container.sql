@@ -0,0 +1,6 @@
+-- The container each command was run in, as runtime:name, for the container:
+-- filter. Local only, like the account in history_audit.
+create table if not exists history_container (
+    id text primary key not null,
+    container text not null
+);
@@ -0,0 +1,7 @@
+-- Commands pinned from the interactive search, which sort above everything
+-- else they match. Pinned by command rather than entry, so a pin holds however
+-- often the command is run again. Local only, like history_container.
+create table if not exists history_pinned (
+    command text primary key not null,
+    pinned_at integer not null
+);
@@ -0,0 +1,7 @@
+-- Names given to shell sessions, for the session: filter. Built from the
+-- session records in the record store, which are what sync, so this is only
+-- a copy of them.
+create table if not exists session_name (
+    session text primary key not null,
+    name text not null
+);
@@ -1,12 +1,15 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::env;
+use std::sync::Mutex;
 use std::time::Duration;
 
 use eyre::{Result, bail};
 use reqwest::{
     Response, StatusCode, Url,
-    header::{AUTHORIZATION, HeaderMap, HeaderName, HeaderValue, USER_AGENT},
+    header::{AUTHORIZATION, DATE, HeaderMap, HeaderName, HeaderValue, USER_AGENT},
 };
+use time::{OffsetDateTime, format_description::well_known::Rfc2822};
 
 use atuin_common::{
     api::{ATUIN_CARGO_VERSION, ATUIN_HEADER_VERSION, ATUIN_VERSION},
@@ -24,6 +27,9 @@ use atuin_common::{
 
 use semver::Version;
 
+use crate::error::ClientError;
+use crate::network::{self, RequestKind};
+
 static APP_USER_AGENT: &str = concat!("atuin/", env!("CARGO_PKG_VERSION"),);
 
 /// Authentication token for sync API requests.
@@ -53,8 +59,9 @@ impl AuthToken {
 }
 
 pub struct Client<'a> {
-    sync_addr: &'a Url,
+    sync_addr: Cow<'a, Url>,
     client: reqwest::Client,
+    clock_skew: Mutex<Option<time::Duration>>,
 }
 
 /// A [`reqwest::ClientBuilder`] appropriate for the given extra headers.
@@ -122,21 +129,22 @@ pub async fn register(
     headers.insert(USER_AGENT, APP_USER_AGENT.parse()?);
     headers.insert(ATUIN_HEADER_VERSION, ATUIN_CARGO_VERSION.parse()?);
 
-    let client = client_builder(extra_headers).build()?;
+    let (builder, address) = network::sync_server(client_builder(extra_headers), address)?;
+    let client = network::configure_builder(builder, RequestKind::Auth)?.build()?;
 
     let url = address.append(["user", username])?;
-    let resp = client.get(url).headers(headers.clone()).send().await?;
+    let resp = network::send(client.get(url).headers(headers.clone())).await?;
 
     if resp.status().is_success() {
         bail!("username already in use");
     }
 
     let url = address.append(["register"])?;
-    let resp = client.post(url).headers(headers).json(&map).send().await?;
+    let resp = network::send(client.post(url).headers(headers).json(&map)).await?;
     let resp = handle_resp_error(resp).await?;
 
     if !ensure_version(&resp)? {
-        bail!("could not register user due to version mismatch");
+        return Err(ClientError::VersionMismatch.into());
     }
 
     let session = resp.json::<RegisterResponse>().await?;
@@ -149,17 +157,18 @@ pub async fn login(
     extra_headers: &HashMap<String, String>,
 ) -> Result<LoginResponse> {
     ensure_crypto_provider();
+    let (builder, address) = network::sync_server(client_builder(extra_headers), address)?;
+    let client = network::configure_builder(builder, RequestKind::Auth)?.build()?;
     let url = address.append(["login"])?;
-    let client = client_builder(extra_headers).build()?;
 
     let mut headers = extra_headers_map(extra_headers)?;
     headers.insert(USER_AGENT, APP_USER_AGENT.parse()?);
 
-    let resp = client.post(url).headers(headers).json(&req).send().await?;
+    let resp = network::send(client.post(url).headers(headers).json(&req)).await?;
     let resp = handle_resp_error(resp).await?;
 
     if !ensure_version(&resp)? {
-        bail!("Could not login due to version mismatch");
+        return Err(ClientError::VersionMismatch.into());
     }
 
     let session = resp.json::<LoginResponse>().await?;
@@ -172,13 +181,9 @@ pub async fn latest_version() -> Result<Version> {
 
     ensure_crypto_provider();
     let url = crate::settings::DEFAULT_SYNC_URL.clone();
-    let client = reqwest::Client::new();
+    let client = network::client_builder(RequestKind::UpdateCheck)?.build()?;
 
-    let resp = client
-        .get(url)
-        .header(USER_AGENT, APP_USER_AGENT)
-        .send()
-        .await?;
+    let resp = network::send(client.get(url).header(USER_AGENT, APP_USER_AGENT)).await?;
     let resp = handle_resp_error(resp).await?;
 
     let index = resp.json::<IndexResponse>().await?;
@@ -215,6 +220,21 @@ pub fn ensure_version(response: &Response) -> Result<bool> {
     Ok(true)
 }
 
+/// How far the server's clock is ahead of ours (negative if it's behind), going
+/// by the `Date` header of a response to a request sent at `sent` and received
+/// at `received`. The header only has whole seconds, so this is no more precise
+/// than that.
+fn clock_skew(
+    date: &str,
+    sent: OffsetDateTime,
+    received: OffsetDateTime,
+) -> Option<time::Duration> {
+    let server = OffsetDateTime::parse(date, &Rfc2822).ok()?;
+
+    // The server stamped the response somewhere between sending and receiving.
+    Some(server - (sent + (received - sent) / 2))
+}
+
 async fn handle_resp_error(resp: Response) -> Result<Response> {
     let status = resp.status();
     let url = resp.url().to_string();
@@ -226,7 +246,7 @@ async fn handle_resp_error(resp: Response) -> Result<Response> {
     }
 
     if status == StatusCode::TOO_MANY_REQUESTS {
-        bail!("Rate limited; please wait before doing that again");
+        return Err(ClientError::RateLimited.into());
     }
 
     if !status.is_success() {
@@ -266,20 +286,23 @@ impl<'a> Client<'a> {
         // used for semver server check
         headers.insert(ATUIN_HEADER_VERSION, ATUIN_CARGO_VERSION.parse()?);
 
+        let (builder, sync_addr) = network::sync_server(client_builder(extra_headers), sync_addr)?;
+
         Ok(Client {
             sync_addr,
-            client: client_builder(extra_headers)
+            client: network::configure_builder(builder, RequestKind::Sync)?
                 .default_headers(headers)
                 .connect_timeout(Duration::new(connect_timeout, 0))
                 .timeout(Duration::new(timeout, 0))
                 .build()?,
+            clock_skew: Mutex::new(None),
         })
     }
 
     pub async fn me(&self) -> Result<MeResponse> {
         let url = self.sync_addr.append_path("api/v0/me")?;
 
-        let resp = self.client.get(url).send().await?;
+        let resp = network::send(self.client.get(url)).await?;
         let resp = handle_resp_error(resp).await?;
 
         let status = resp.json::<MeResponse>().await?;
@@ -290,7 +313,7 @@ impl<'a> Client<'a> {
     pub async fn delete_store(&self) -> Result<()> {
         let url = self.sync_addr.append_path("api/v0/store")?;
 
-        let resp = self.client.delete(url).send().await?;
+        let resp = network::send(self.client.delete(url)).await?;
 
         handle_resp_error(resp).await?;
 
@@ -302,7 +325,7 @@ impl<'a> Client<'a> {
 
         debug!("uploading {} records to {url}", records.len());
 
-        let resp = self.client.post(url).json(records).send().await?;
+        let resp = network::send(self.client.post(url).json(records)).await?;
         handle_resp_error(resp).await?;
 
         Ok(())
@@ -324,7 +347,7 @@ impl<'a> Client<'a> {
             .append_pair("count", &count.to_string())
             .append_pair("start", &start.to_string());
 
-        let resp = self.client.get(url).send().await?;
+        let resp = network::send(self.client.get(url)).await?;
         let resp = handle_resp_error(resp).await?;
 
         let records = resp.json::<Vec<Record<EncryptedData>>>().await?;
@@ -332,14 +355,28 @@ impl<'a> Client<'a> {
         Ok(records)
     }
 
+    /// The skew measured by the last call to [`Self::record_status`], if the
+    /// server said what time it was.
+    pub fn clock_skew(&self) -> Option<time::Duration> {
+        *self.clock_skew.lock().expect("clock skew lock poisoned")
+    }
+
     pub async fn record_status(&self) -> Result<RecordStatus> {
         let url = self.sync_addr.append_path("api/v0/record")?;
 
-        let resp = self.client.get(url).send().await?;
+        let sent = OffsetDateTime::now_utc();
+        let resp = network::send(self.client.get(url)).await?;
         let resp = handle_resp_error(resp).await?;
 
+        let received = OffsetDateTime::now_utc();
+        *self.clock_skew.lock().expect("clock skew lock poisoned") = resp
+            .headers()
+            .get(DATE)
+            .and_then(|date| date.to_str().ok())
+            .and_then(|date| clock_skew(date, sent, received));
+
         if !ensure_version(&resp)? {
-            bail!("could not sync records due to version mismatch");
+            return Err(ClientError::VersionMismatch.into());
         }
 
         let index = resp.json().await?;
@@ -352,10 +389,10 @@ impl<'a> Client<'a> {
     pub async fn delete(&self) -> Result<()> {
         let url = self.sync_addr.append(["account"])?;
 
-        let resp = self.client.delete(url).send().await?;
+        let resp = network::send(self.client.delete(url)).await?;
 
         if resp.status() == 403 {
-            bail!("invalid login details");
+            Err(ClientError::InvalidCredentials.into())
         } else if resp.status() == 200 {
             Ok(())
         } else {
@@ -370,20 +407,16 @@ impl<'a> Client<'a> {
     ) -> Result<()> {
         let url = self.sync_addr.append_path("account/password")?;
 
-        let resp = self
-            .client
-            .patch(url)
-            .json(&ChangePasswordRequest {
-                current_password,
-                new_password,
-            })
-            .send()
-            .await?;
+        let resp = network::send(self.client.patch(url).json(&ChangePasswordRequest {
+            current_password,
+            new_password,
+        }))
+        .await?;
 
         if resp.status() == 401 {
             bail!("current password is incorrect");
         } else if resp.status() == 403 {
-            bail!("invalid login details");
+            Err(ClientError::InvalidCredentials.into())
         } else if resp.status() == 200 {
             Ok(())
         } else {
@@ -396,6 +429,22 @@ impl<'a> Client<'a> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn clock_skew_from_date_header() {
+        let sent = time::macros::datetime!(2026-10-15 12:00:00 UTC);
+        let received = sent + time::Duration::seconds(2);
+
+        assert_eq!(
+            clock_skew("Thu, 15 Oct 2026 12:05:01 GMT", sent, received),
+            Some(time::Duration::minutes(5))
+        );
+        assert_eq!(
+            clock_skew("Thu, 15 Oct 2026 11:00:01 GMT", sent, received),
+            Some(-time::Duration::hours(1))
+        );
+        assert_eq!(clock_skew("yesterday", sent, received), None);
+    }
+
     #[test]
     fn extra_headers_map_parses_headers() {
         let mut extra = HashMap::new();
@@ -14,6 +14,8 @@ use atuin_common::{
     url::UrlAppendExt,
 };
 
+use crate::error::ClientError;
+use crate::network::{self, RequestKind};
 use crate::settings::Settings;
 
 static APP_USER_AGENT: &str = concat!("atuin/", env!("CARGO_PKG_VERSION"));
@@ -88,7 +90,7 @@ pub async fn auth_client(settings: &Settings) -> Box<dyn AuthClient> {
             &settings.sync_address,
             settings.session_token().await.ok(),
             settings.network_connect_timeout,
-            settings.network_timeout,
+            settings.network_timeout_for(RequestKind::Auth),
             settings.extra_headers.clone(),
         )) as Box<dyn AuthClient>
     }
@@ -123,7 +125,8 @@ impl LegacyAuthClient {
         }
     }
 
-    fn authenticated_client(&self) -> Result<reqwest::Client> {
+    /// A client logged in to the server, and the URL its requests go under
+    fn authenticated_client(&self) -> Result<(reqwest::Client, Url)> {
         let token = self
             .session_token
             .as_deref()
@@ -138,11 +141,17 @@ impl LegacyAuthClient {
         headers.insert(USER_AGENT, APP_USER_AGENT.parse()?);
         headers.insert(ATUIN_HEADER_VERSION, ATUIN_CARGO_VERSION.parse()?);
 
-        Ok(crate::api_client::client_builder(&self.extra_headers)
+        let (builder, address) = network::sync_server(
+            crate::api_client::client_builder(&self.extra_headers),
+            &self.address,
+        )?;
+        let client = network::configure_builder(builder, RequestKind::Auth)?
             .default_headers(headers)
             .connect_timeout(std::time::Duration::new(self.connect_timeout, 0))
             .timeout(std::time::Duration::new(self.timeout, 0))
-            .build()?)
+            .build()?;
+
+        Ok((client, address.into_owned()))
     }
 }
 
@@ -192,17 +201,14 @@ impl AuthClient for LegacyAuthClient {
         new_password: &str,
         _totp_code: Option<&str>,
     ) -> Result<MutateResponse> {
-        let client = self.authenticated_client()?;
-        let url = self.address.append_path("account/password")?;
-
-        let resp = client
-            .patch(url)
-            .json(&ChangePasswordRequest {
-                current_password: current_password.to_string(),
-                new_password: new_password.to_string(),
-            })
-            .send()
-            .await?;
+        let (client, address) = self.authenticated_client()?;
+        let url = address.append_path("account/password")?;
+
+        let resp = network::send(client.patch(url).json(&ChangePasswordRequest {
+            current_password: current_password.to_string(),
+            new_password: new_password.to_string(),
+        }))
+        .await?;
 
         match resp.status().as_u16() {
             200 => Ok(MutateResponse::Success),
@@ -210,7 +216,7 @@ impl AuthClient for LegacyAuthClient {
                 bail!("current password is incorrect");
             }
             403 => {
-                bail!("invalid login details");
+                return Err(ClientError::InvalidCredentials.into());
             }
             _ => {
                 bail!("unknown error");
@@ -223,14 +229,15 @@ impl AuthClient for LegacyAuthClient {
         password: &str,
         _totp_code: Option<&str>,
     ) -> Result<MutateResponse> {
-        let client = self.authenticated_client()?;
-        let url = self.address.append(["account"])?;
+        let (client, address) = self.authenticated_client()?;
+        let url = address.append(["account"])?;
 
-        let resp = client
-            .delete(url)
-            .json(&serde_json::json!({ "password": password }))
-            .send()
-            .await?;
+        let resp = network::send(
+            client
+                .delete(url)
+                .json(&serde_json::json!({ "password": password })),
+        )
+        .await?;
 
         match resp.status().as_u16() {
             200 => Ok(MutateResponse::Success),
@@ -238,7 +245,7 @@ impl AuthClient for LegacyAuthClient {
                 bail!("password is incorrect");
             }
             403 => {
-                bail!("invalid login details");
+                return Err(ClientError::InvalidCredentials.into());
             }
             _ => {
                 bail!("unknown error");
@@ -283,7 +290,7 @@ impl AuthClient for HubAuthClient {
     ) -> Result<AuthResponse> {
         ensure_crypto_provider();
         let url = self.address.append_path("api/v0/login")?;
-        let client = reqwest::Client::new();
+        let client = network::client_builder(RequestKind::Auth)?.build()?;
 
         let mut body = serde_json::json!({
             "username": username,
@@ -293,14 +300,15 @@ impl AuthClient for HubAuthClient {
             body["totp_code"] = serde_json::Value::String(code.to_string());
         }
 
-        let resp = client
-            .post(url)
-            .header(USER_AGENT, APP_USER_AGENT)
-            .header(ATUIN_HEADER_VERSION, ATUIN_CARGO_VERSION)
-            .json(&body)
-            .send()
-            .await
-            .context("failed to connect to Atuin Hub")?;
+        let resp = network::send(
+            client
+                .post(url)
+                .header(USER_AGENT, APP_USER_AGENT)
+                .header(ATUIN_HEADER_VERSION, ATUIN_CARGO_VERSION)
+                .json(&body),
+        )
+        .await
+        .context("failed to connect to Atuin Hub")?;
 
         let status = resp.status();
 
@@ -331,20 +339,21 @@ impl AuthClient for HubAuthClient {
     async fn register(&self, username: &str, email: &str, password: &str) -> Result<AuthResponse> {
         ensure_crypto_provider();
         let url = self.address.append_path("api/v0/register")?;
-        let client = reqwest::Client::new();
-
-        let resp = client
-            .post(url)
-            .header(USER_AGENT, APP_USER_AGENT)
-            .header(ATUIN_HEADER_VERSION, ATUIN_CARGO_VERSION)
-            .json(&serde_json::json!({
-                "email": email,
-                "username": username,
-                "password": password,
-            }))
-            .send()
-            .await
-            .context("failed to connect to Atuin Hub")?;
+        let client = network::client_builder(RequestKind::Auth)?.build()?;
+
+        let resp = network::send(
+            client
+                .post(url)
+                .header(USER_AGENT, APP_USER_AGENT)
+                .header(ATUIN_HEADER_VERSION, ATUIN_CARGO_VERSION)
+                .json(&serde_json::json!({
+                    "email": email,
+                    "username": username,
+                    "password": password,
+                })),
+        )
+        .await
+        .context("failed to connect to Atuin Hub")?;
 
         let status = resp.status();
 
@@ -385,7 +394,7 @@ impl AuthClient for HubAuthClient {
 
         ensure_crypto_provider();
         let url = self.address.append_path("api/v0/account/password")?;
-        let client = reqwest::Client::new();
+        let client = network::client_builder(RequestKind::Auth)?.build()?;
 
         let mut body = serde_json::json!({
             "current_password": current_password,
@@ -395,15 +404,16 @@ impl AuthClient for HubAuthClient {
             body["totp_code"] = serde_json::Value::String(code.to_string());
         }
 
-        let resp = client
-            .patch(url)
-            .header(USER_AGENT, APP_USER_AGENT)
-            .header(ATUIN_HEADER_VERSION, ATUIN_CARGO_VERSION)
-            .bearer_auth(hub_token)
-            .json(&body)
-            .send()
-            .await
-            .context("failed to connect to Atuin Hub")?;
+        let resp = network::send(
+            client
+                .patch(url)
+                .header(USER_AGENT, APP_USER_AGENT)
+                .header(ATUIN_HEADER_VERSION, ATUIN_CARGO_VERSION)
+                .bearer_auth(hub_token)
+                .json(&body),
+        )
+        .await
+        .context("failed to connect to Atuin Hub")?;
 
         let status = resp.status();
 
@@ -428,7 +438,7 @@ impl AuthClient for HubAuthClient {
                 bail!("current password is incorrect");
             }
             StatusCode::FORBIDDEN => {
-                bail!("invalid login details");
+                return Err(ClientError::InvalidCredentials.into());
             }
             _ => {
                 bail!("Hub password change failed with status {status}");
@@ -457,7 +467,7 @@ impl AuthClient for HubAuthClient {
 
         ensure_crypto_provider();
         let url = self.address.append_path("api/v0/account")?;
-        let client = reqwest::Client::new();
+        let client = network::client_builder(RequestKind::Auth)?.build()?;
 
         let mut body = serde_json::json!({
             "password": password,
@@ -466,15 +476,16 @@ impl AuthClient for HubAuthClient {
             body["totp_code"] = serde_json::Value::String(code.to_string());
         }
 
-        let resp = client
-            .delete(url)
-            .header(USER_AGENT, APP_USER_AGENT)
-            .header(ATUIN_HEADER_VERSION, ATUIN_CARGO_VERSION)
-            .bearer_auth(hub_token)
-            .json(&body)
-            .send()
-            .await
-            .context("failed to connect to Atuin Hub")?;
+        let resp = network::send(
+            client
+                .delete(url)
+                .header(USER_AGENT, APP_USER_AGENT)
+                .header(ATUIN_HEADER_VERSION, ATUIN_CARGO_VERSION)
+                .bearer_auth(hub_token)
+                .json(&body),
+        )
+        .await
+        .context("failed to connect to Atuin Hub")?;
 
         let status = resp.status();
 
@@ -499,7 +510,7 @@ impl AuthClient for HubAuthClient {
                 bail!("password is incorrect");
             }
             StatusCode::FORBIDDEN => {
-                bail!("invalid login details");
+                return Err(ClientError::InvalidCredentials.into());
             }
             _ => {
                 bail!("Hub account deletion failed with status {status}");
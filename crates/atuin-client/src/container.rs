@@ -0,0 +1,152 @@
+use std::path::Path;
+
+/// Detect the container this is running in, as `runtime:name`, such as
+/// `docker:3f4a5b6c7d8e` or `devcontainer:atuin`. The signs are checked from
+/// the most specific, the environment variables editors and orchestrators
+/// set, to the least, the cgroups of the container's first process.
+pub fn detect_container() -> Option<String> {
+    detect_from_codespaces()
+        .or_else(detect_from_devcontainer)
+        .or_else(detect_from_kubernetes)
+        .or_else(detect_from_containerenv)
+        .or_else(detect_from_dockerenv)
+        .or_else(detect_from_container_var)
+        .or_else(detect_from_cgroup)
+}
+
+fn hostname() -> String {
+    whoami::hostname().unwrap_or_else(|_| "unknown-host".to_string())
+}
+
+fn is_set(var: &str) -> bool {
+    std::env::var_os(var).is_some_and(|value| !value.is_empty())
+}
+
+fn detect_from_codespaces() -> Option<String> {
+    if std::env::var("CODESPACES").as_deref() != Ok("true") {
+        return None;
+    }
+    let name = std::env::var("CODESPACE_NAME").unwrap_or_else(|_| hostname());
+    Some(format!("codespaces:{name}"))
+}
+
+fn detect_from_devcontainer() -> Option<String> {
+    // VS Code's Dev Containers, and the devcontainer CLI
+    if std::env::var("REMOTE_CONTAINERS").as_deref() != Ok("true") && !is_set("DEVCONTAINER") {
+        return None;
+    }
+    let name = std::env::var("LOCAL_WORKSPACE_FOLDER")
+        .ok()
+        .and_then(|folder| Some(Path::new(&folder).file_name()?.to_str()?.to_string()))
+        .unwrap_or_else(hostname);
+    Some(format!("devcontainer:{name}"))
+}
+
+fn detect_from_kubernetes() -> Option<String> {
+    // A pod's hostname is its name
+    is_set("KUBERNETES_SERVICE_HOST").then(|| format!("kubernetes:{}", hostname()))
+}
+
+fn detect_from_containerenv() -> Option<String> {
+    // Podman's, and also Toolbx's and Distrobox's, which run on it
+    let content = std::fs::read_to_string("/run/.containerenv").ok()?;
+    let name = name_from_containerenv(&content).unwrap_or_else(hostname);
+    Some(format!("podman:{name}"))
+}
+
+fn detect_from_dockerenv() -> Option<String> {
+    if !Path::new("/.dockerenv").exists() {
+        return None;
+    }
+    let id = std::fs::read_to_string("/proc/self/cgroup")
+        .ok()
+        .and_then(|content| from_cgroup(&content))
+        .map_or_else(hostname, |(_, id)| id);
+    Some(format!("docker:{id}"))
+}
+
+fn detect_from_container_var() -> Option<String> {
+    // Set by systemd-nspawn, LXC and others
+    let runtime = std::env::var("container").ok().filter(|v| !v.is_empty())?;
+    Some(format!("{runtime}:{}", hostname()))
+}
+
+fn detect_from_cgroup() -> Option<String> {
+    let content = std::fs::read_to_string("/proc/1/cgroup").ok()?;
+    let (runtime, id) = from_cgroup(&content)?;
+    Some(format!("{runtime}:{id}"))
+}
+
+/// The runtime and short id of the container in the contents of a cgroup file.
+fn from_cgroup(content: &str) -> Option<(&'static str, String)> {
+    content.lines().find_map(|line| {
+        let path = line.rsplit(':').next()?;
+        let runtime = if path.contains("kubepods") {
+            "kubernetes"
+        } else if path.contains("docker") {
+            "docker"
+        } else if path.contains("libpod") {
+            "podman"
+        } else if path.contains("containerd") {
+            "containerd"
+        } else if path.starts_with("/lxc") {
+            "lxc"
+        } else {
+            return None;
+        };
+
+        let id = path
+            .split(['/', '-', '.'])
+            .find(|part| part.len() == 64 && part.chars().all(|c| c.is_ascii_hexdigit()))
+            .map_or_else(hostname, |id| id[..12].to_string());
+        Some((runtime, id))
+    })
+}
+
+/// The container's name in the contents of Podman's `/run/.containerenv`.
+fn name_from_containerenv(content: &str) -> Option<String> {
+    content
+        .lines()
+        .find_map(|line| line.strip_prefix("name="))
+        .map(|name| name.trim_matches('"').to_string())
+        .filter(|name| !name.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn containers_are_found_in_cgroups() {
+        let id = "3f4a5b6c7d8e9f0a1b2c3d4e5f6a7b8c9d0e1f2a3b4c5d6e7f8a9b0c1d2e3f4a";
+
+        let docker = format!("12:memory:/docker/{id}\n0::/");
+        assert_eq!(
+            from_cgroup(&docker),
+            Some(("docker", "3f4a5b6c7d8e".to_string()))
+        );
+
+        let systemd = format!("0::/system.slice/docker-{id}.scope");
+        assert_eq!(
+            from_cgroup(&systemd),
+            Some(("docker", "3f4a5b6c7d8e".to_string()))
+        );
+
+        let kubernetes = format!("0::/kubepods/besteffort/pod1234/{id}");
+        assert_eq!(from_cgroup(&kubernetes).map(|(r, _)| r), Some("kubernetes"));
+
+        // cgroup v2 on the host, and inside a container with its own namespace
+        assert_eq!(from_cgroup("0::/init.scope"), None);
+        assert_eq!(from_cgroup("0::/"), None);
+    }
+
+    #[test]
+    fn podman_names_its_containers() {
+        let content = "engine=\"podman-5.2.0\"\nname=\"toolbox\"\nid=\"abc\"\n";
+        assert_eq!(name_from_containerenv(content), Some("toolbox".to_string()));
+
+        // Rootless containers leave it empty
+        assert_eq!(name_from_containerenv(""), None);
+        assert_eq!(name_from_containerenv("name=\"\"\n"), None);
+    }
+}
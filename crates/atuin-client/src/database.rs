@@ -1,13 +1,15 @@
 use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
     env,
     path::{Path, PathBuf},
     str::FromStr,
     time::Duration,
 };
 
-use crate::history::{AUTHOR_FILTER_ALL_AGENT, AUTHOR_FILTER_ALL_USER, KNOWN_AGENTS};
+use crate::history::{AUTHOR_FILTER_ALL_AGENT, AUTHOR_FILTER_ALL_USER, KNOWN_AGENTS, host_of};
 use async_trait::async_trait;
-use atuin_common::utils;
+use atuin_common::{paths, utils};
 use fs_err as fs;
 use itertools::Itertools;
 use sql_builder::{SqlBuilder, SqlName, bind::Bind, esc, quote};
@@ -22,16 +24,21 @@ use time::OffsetDateTime;
 use uuid::Uuid;
 
 use crate::{
-    history::{HistoryId, HistoryStats},
+    date::parse_date,
+    history::{Audit, HistoryId, HistoryStats, Origin, RawValues, output::CapturedOutput},
+    read_only,
     utils::get_host_user,
 };
 
 use super::{
     history::History,
-    ordering,
-    settings::{FilterMode, SearchMode, Settings},
+    ordering::{self, ContextBoost},
+    settings::{Dialect, FilterMode, SearchMode, Settings},
 };
 
+pub mod lock;
+pub mod migrations;
+
 #[derive(Clone)]
 pub struct Context {
     pub session: String,
@@ -41,6 +48,20 @@ pub struct Context {
     pub git_root: Option<PathBuf>,
 }
 
+/// A session's history, totted up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionStats {
+    pub session: String,
+    /// The hosts the session's commands ran on, usually just the one
+    pub hostnames: Vec<String>,
+    pub commands: i64,
+    pub failures: i64,
+    /// When the first command started
+    pub start: OffsetDateTime,
+    /// When the last command started
+    pub last: OffsetDateTime,
+}
+
 #[derive(Default, Clone)]
 pub struct OptFilters {
     pub exit: Option<i64>,
@@ -60,8 +81,20 @@ pub struct OptFilters {
     /// Author filter. Supports special values `$all-user` and `$all-agent`.
     pub authors: Vec<String>,
     pub shells: Vec<String>,
+    /// Only commands run on these machines, by the host part of their hostname.
+    pub hosts: Vec<String>,
+    /// Search archived history too, if the archive is attached.
+    pub include_archive: bool,
+    /// Favour results run near the search's context.
+    pub context_boost: ContextBoost,
+    /// Leave out commands run in containers, unless the query has a `container:` filter.
+    pub exclude_containers: bool,
 }
 
+/// The machine part of the `host:user` hostname column, lowercased, as
+/// [`host_of`] finds it.
+const HOST_COLUMN: &str = "lower(substr(hostname, 1, instr(hostname || ':', ':') - 1))";
+
 /// Build a query [`Context`] without requiring a live shell session.
 ///
 /// Outside of an atuin-hooked shell (e.g. when running as an MCP server),
@@ -137,6 +170,15 @@ fn apply_author_filter(sql: &mut SqlBuilder, authors: &[String]) {
     }
 }
 
+fn apply_host_filter(sql: &mut SqlBuilder, hosts: &[String]) {
+    if hosts.is_empty() {
+        return;
+    }
+
+    let host_list = hosts.iter().map(|h| quote(h.to_lowercase())).join(", ");
+    sql.and_where(format!("{HOST_COLUMN} in ({host_list})"));
+}
+
 fn apply_shell_filter<S>(sql: &mut SqlBuilder, shells: S)
 where
     S: IntoIterator,
@@ -172,10 +214,45 @@ fn get_session_start_time(session_id: &str) -> Option<i64> {
     None
 }
 
+/// Narrow `sql` to the history `filter` takes in from `context`.
+fn apply_filter_mode(sql: &mut SqlBuilder, filter: FilterMode, context: &Context) {
+    let git_root = if let Some(git_root) = context.git_root.clone() {
+        git_root.to_str().unwrap_or("/").to_string()
+    } else {
+        context.cwd.clone()
+    };
+
+    let session_start = get_session_start_time(&context.session);
+
+    match filter {
+        FilterMode::Global => sql,
+        FilterMode::Host => {
+            sql.and_where_eq("lower(hostname)", quote(context.hostname.to_lowercase()))
+        }
+        FilterMode::Session => sql.and_where_eq("session", quote(&context.session)),
+        FilterMode::SessionPreload => {
+            sql.and_where_eq("session", quote(&context.session));
+            if let Some(session_start) = session_start {
+                sql.or_where_lt("timestamp", session_start);
+            }
+            sql
+        }
+        FilterMode::Directory => sql.and_where_eq("cwd", quote(&context.cwd)),
+        FilterMode::Workspace => sql.and_where_like_left("cwd", git_root),
+        FilterMode::OtherHosts => sql.and_where_ne(
+            HOST_COLUMN,
+            quote(host_of(&context.hostname).to_lowercase()),
+        ),
+    };
+}
+
 #[async_trait]
 pub trait Database: Send + Sync + 'static {
     async fn save(&self, h: &History) -> Result<()>;
     async fn save_bulk(&self, h: &[History]) -> Result<()>;
+    /// Save `h` and note where it was run, as [`save_audit`](Self::save_audit) and
+    /// [`save_container`](Self::save_container) do, in one transaction.
+    async fn save_with_origin(&self, h: &History, origin: &Origin) -> Result<()>;
 
     async fn load(&self, id: &str) -> Result<Option<History>>;
 
@@ -201,11 +278,18 @@ pub trait Database: Send + Sync + 'static {
     async fn history_count(&self, include_deleted: bool) -> Result<i64>;
 
     async fn last(&self) -> Result<Option<History>>;
+    /// The `count` most recently finished, non-deleted entries, newest first,
+    /// optionally only from one session.
+    async fn latest(&self, session: Option<&str>, count: i64) -> Result<Vec<History>>;
     async fn before(&self, timestamp: OffsetDateTime, count: i64) -> Result<Vec<History>>;
 
     async fn delete(&self, h: History) -> Result<()>;
     async fn delete_rows(&self, ids: &[HistoryId]) -> Result<()>;
 
+    /// Count which commands followed which again from the history that's left, after
+    /// removing a lot of it.
+    async fn rebuild_bigrams(&self) -> Result<()>;
+
     // Yes I know, it's a lot.
     // Could maybe break it down to a searchparams struct or smth but that feels a little... pointless.
     // Been debating maybe a DSL for search? eg "before:time limit:1 the query"
@@ -229,7 +313,102 @@ pub trait Database: Send + Sync + 'static {
 
     async fn get_dups(&self, before: i64, dupkeep: u32) -> Result<Vec<History>>;
 
+    /// The commands that most often directly followed `command` within a session,
+    /// paired with how many times they did, most frequent first.
+    async fn successors(&self, command: &str, limit: usize) -> Result<Vec<(String, i64)>>;
+
+    /// How many times each of `commands` was run, within the history `filter` takes in
+    /// from `context`. Commands never run there are left out.
+    async fn run_counts(
+        &self,
+        commands: &[String],
+        filter: FilterMode,
+        context: &Context,
+    ) -> Result<HashMap<String, i64>>;
+
+    /// The runs of `command`, newest first, within the history `filter` takes in from
+    /// `context`. At most `limit` of them.
+    async fn runs(
+        &self,
+        command: &str,
+        filter: FilterMode,
+        context: &Context,
+        limit: usize,
+    ) -> Result<Vec<History>>;
+
+    /// The sessions commands were most recently run in, newest first. At most `limit` of them.
+    async fn recent_sessions(&self, limit: usize) -> Result<Vec<String>>;
+
+    /// The `limit` most recently used sessions on any host, totted up, newest first.
+    async fn session_stats(&self, limit: usize) -> Result<Vec<SessionStats>>;
+
+    /// Every entry run in `session`, oldest first.
+    async fn session_history(&self, session: &str) -> Result<Vec<History>>;
+
+    /// The commands that most often ran right before or after `command` within a session,
+    /// paired with how many times they did, most frequent first.
+    async fn related(&self, command: &str, limit: usize) -> Result<Vec<(String, i64)>>;
+
+    /// IDs of entries whose ID starts with `prefix`, at most `limit` of them.
+    async fn ids_with_prefix(&self, prefix: &str, limit: usize) -> Result<Vec<HistoryId>>;
+
+    /// The shortest ID prefix length, at least `min`, that tells each of `ids` apart from
+    /// every other entry.
+    async fn unique_id_prefix_len(&self, ids: &[HistoryId], min: usize) -> Result<usize>;
+
+    /// Store the captured output of an entry, replacing any already stored.
+    /// The first `searchable_bytes` of it are indexed for `in:output` searches.
+    async fn save_output(
+        &self,
+        id: &HistoryId,
+        output: &CapturedOutput,
+        searchable_bytes: usize,
+    ) -> Result<()>;
+
+    async fn load_output(&self, id: &HistoryId) -> Result<Option<CapturedOutput>>;
+
+    /// What an entry's timestamp and duration were before saving clamped
+    /// them, if it did.
+    async fn raw_values(&self, id: &HistoryId) -> Result<Option<RawValues>>;
+
+    /// Note the OS account that ran an entry, for the `user:` filter and append-only mode's
+    /// audit trail. The first noted is kept.
+    async fn save_audit(&self, id: &HistoryId, audit: &Audit) -> Result<()>;
+
+    async fn audit(&self, id: &HistoryId) -> Result<Option<Audit>>;
+
+    /// The commands the OS account `user` has run, as far as they were noted.
+    async fn commands_run_by(&self, user: &str) -> Result<HashSet<String>>;
+
+    /// Note the container, as `runtime:name`, that an entry was run in.
+    async fn save_container(&self, id: &HistoryId, container: &str) -> Result<()>;
+
+    /// The commands run in a container whose `runtime:name` starts with `container`, or run
+    /// outside any container for `None`.
+    async fn commands_in_container(&self, container: Option<&str>) -> Result<HashSet<String>>;
+
+    /// Pin `command`, so it sorts above everything else it matches, or unpin it if it already
+    /// is. Returns whether it's pinned now.
+    async fn toggle_pin(&self, command: &str) -> Result<bool>;
+
+    /// The pinned commands, in the order they were pinned.
+    async fn pinned_commands(&self) -> Result<Vec<String>>;
+
+    /// Replace the names of sessions with `names`, by session ID.
+    async fn save_session_names(&self, names: &HashMap<String, String>) -> Result<()>;
+
+    /// The name of every named session, by its ID.
+    async fn session_names(&self) -> Result<HashMap<String, String>>;
+
+    /// The commands run in a session whose name starts with `name`, ignoring case.
+    async fn commands_in_session(&self, name: &str) -> Result<HashSet<String>>;
+
     fn clone_boxed(&self) -> Box<dyn Database + 'static>;
+
+    /// Whether there's an archive of old history that searches can include.
+    fn has_archive(&self) -> bool {
+        false
+    }
 }
 
 // Intended for use on a developer machine and not a sync server.
@@ -237,11 +416,95 @@ pub trait Database: Send + Sync + 'static {
 #[derive(Debug, Clone)]
 pub struct Sqlite {
     pub pool: SqlitePool,
+
+    /// Whether the archive database is attached, as `archive`
+    archive: bool,
 }
 
 impl Sqlite {
     pub async fn new(path: impl AsRef<Path>, timeout: f64) -> Result<Self> {
         let path = path.as_ref();
+        let db = Self::connect(path, timeout).await?;
+
+        // Migrating is a write like any other
+        if read_only::is_enabled() {
+            return Ok(db);
+        }
+
+        if !lock::migrations_pending(&db.pool, &migrations::MIGRATOR).await? {
+            return Ok(db);
+        }
+
+        // Every shell starts at once after an upgrade, and only one of them should migrate
+        let _lock = lock::MigrationLock::acquire(path, Duration::from_secs_f64(timeout)).await?;
+        lock::check_wal(&db.pool, path).await?;
+
+        if let Err(e) = migrations::backup_before_migrating(&db.pool, path).await {
+            warn!("could not back up {path:?} before migrating it: {e}");
+        }
+
+        Self::setup_db(&db.pool).await?;
+        Ok(db)
+    }
+
+    /// Open the history database `settings` names, with the archive attached
+    /// once there is one. Only for commands that need the archive, as attaching
+    /// it costs every connection.
+    pub async fn from_settings(settings: &Settings) -> Result<Self> {
+        if settings.archive.db_path.exists() {
+            Self::with_archive(
+                &settings.db_path,
+                &settings.archive.db_path,
+                settings.local_timeout,
+            )
+            .await
+        } else {
+            Self::new(&settings.db_path, settings.local_timeout).await
+        }
+    }
+
+    /// Open the database with the archive of its old history attached, so
+    /// searches can include it.
+    pub async fn with_archive(
+        path: impl AsRef<Path>,
+        archive_path: impl AsRef<Path>,
+        timeout: f64,
+    ) -> Result<Self> {
+        let path = path.as_ref();
+        let archive_path = archive_path.as_ref();
+
+        let read_only = read_only::is_enabled();
+        if !read_only
+            && !archive_path.exists()
+            && let Some(dir) = archive_path.parent()
+        {
+            fs::create_dir_all(dir)?;
+        }
+
+        let db = Self::open(path, timeout, Some(archive_path)).await?;
+        if read_only
+            || !(lock::migrations_pending_in(&db.pool, &migrations::MIGRATOR, "main").await?
+                || lock::migrations_pending_in(&db.pool, &migrations::MIGRATOR, "archive").await?)
+        {
+            return Ok(db);
+        }
+
+        // Keep the archive's schema in step with the history database, so rows
+        // can move between the two as they are. Both are migrated apart: once
+        // attached, unqualified names can resolve to either.
+        db.pool.close().await;
+        Self::new(archive_path, timeout).await?.pool.close().await;
+        Self::new(path, timeout).await?.pool.close().await;
+
+        Self::open(path, timeout, Some(archive_path)).await
+    }
+
+    /// Open the database as it is, without bringing its schema up to date.
+    pub async fn connect(path: impl AsRef<Path>, timeout: f64) -> Result<Self> {
+        Self::open(path.as_ref(), timeout, None).await
+    }
+
+    async fn open(path: &Path, timeout: f64, archive: Option<&Path>) -> Result<Self> {
         debug!("opening sqlite database at {path:?}");
 
         if utils::broken_symlink(path) {
@@ -251,26 +514,82 @@ impl Sqlite {
             std::process::exit(1);
         }
 
-        if !path.exists()
+        let read_only = read_only::is_enabled();
+        if !read_only
+            && !path.exists()
             && let Some(dir) = path.parent()
         {
             fs::create_dir_all(dir)?;
         }
 
         let opts = SqliteConnectOptions::from_str(path.as_os_str().to_str().unwrap())?
-            .journal_mode(SqliteJournalMode::Wal)
-            .optimize_on_close(true, None)
-            .synchronous(SqliteSynchronous::Normal)
-            .with_regexp()
-            .create_if_missing(true);
-
-        let pool = SqlitePoolOptions::new()
-            .acquire_timeout(Duration::from_secs_f64(timeout))
-            .connect_with(opts)
-            .await?;
+            .busy_timeout(Duration::from_secs_f64(timeout))
+            .with_regexp();
+        let opts = if read_only {
+            opts.read_only(true)
+        } else {
+            opts.journal_mode(SqliteJournalMode::Wal)
+                .optimize_on_close(true, None)
+                .synchronous(SqliteSynchronous::Normal)
+                .create_if_missing(true)
+        };
+
+        let mut pool = SqlitePoolOptions::new().acquire_timeout(Duration::from_secs_f64(timeout));
+
+        // Attaching is per connection, so every connection the pool opens needs it
+        if let Some(archive) = archive {
+            let archive = archive.to_string_lossy().into_owned();
+            pool = pool.after_connect(move |conn, _| {
+                let archive = archive.clone();
+                Box::pin(async move {
+                    sqlx::query("attach database ?1 as archive")
+                        .bind(archive)
+                        .execute(conn)
+                        .await?;
+                    Ok(())
+                })
+            });
+        }
+
+        let pool = pool.connect_with(opts).await?;
+
+        Ok(Self {
+            pool,
+            archive: archive.is_some(),
+        })
+    }
+
+    /// Move history from before `before` into the archive, returning how many
+    /// entries were moved. Captured output and the like stay where they are,
+    /// still found by the entry's ID.
+    pub async fn archive(&self, before: OffsetDateTime) -> Result<u64> {
+        if !self.archive {
+            return Err(sqlx::Error::Configuration(
+                "the history archive isn't attached".into(),
+            ));
+        }
+
+        let before = before.unix_timestamp_nanos() as i64;
+        let mut tx = self.pool.begin().await?;
+
+        // Deleted entries have nothing left worth keeping
+        sqlx::query(
+            "insert or ignore into archive.history
+                select * from main.history where timestamp < ?1 and deleted_at is null",
+        )
+        .bind(before)
+        .execute(&mut *tx)
+        .await?;
+
+        let moved = sqlx::query("delete from main.history where timestamp < ?1")
+            .bind(before)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+
+        tx.commit().await?;
 
-        Self::setup_db(&pool).await?;
-        Ok(Self { pool })
+        Ok(moved)
     }
 
     pub async fn sqlite_version(&self) -> Result<String> {
@@ -282,18 +601,33 @@ impl Sqlite {
     async fn setup_db(pool: &SqlitePool) -> Result<()> {
         debug!("running sqlite database setup");
 
-        sqlx::migrate!("./migrations").run(pool).await?;
+        migrations::MIGRATOR.run(pool).await?;
 
         Ok(())
     }
 
-    async fn save_raw(tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>, h: &History) -> Result<()> {
-        sqlx::query(
+    /// Save `h` unless it's saved already. With the archive attached, that includes being in
+    /// the archive: rebuilding from the record store would otherwise bring back every archived
+    /// entry.
+    async fn save_raw(
+        tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+        h: &History,
+        archive: bool,
+    ) -> Result<()> {
+        let mut h = h.clone();
+        let raw = h.clamp(OffsetDateTime::now_utc());
+
+        let unarchived = if archive {
+            " where not exists (select 1 from archive.history where id = ?1)"
+        } else {
+            ""
+        };
+        let res = sqlx::query(sqlx::AssertSqlSafe(format!(
             "insert or ignore into history(
                 id, timestamp, duration, exit, command, cwd, session, hostname, author, intent,
                 deleted_at, shell
-            ) values(?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
-        )
+            ) select ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12{unarchived}"
+        )))
         .bind(h.id.0.as_str())
         .bind(h.timestamp.unix_timestamp_nanos() as i64)
         .bind(h.duration)
@@ -309,18 +643,217 @@ impl Sqlite {
         .execute(&mut **tx)
         .await?;
 
+        // Only count a transition the first time we see an entry, so re-saving
+        // (e.g. when rebuilding from the record store) doesn't inflate counts.
+        if res.rows_affected() == 1 {
+            Self::record_bigram(tx, &h).await?;
+            if let Some(raw) = raw {
+                Self::save_raw_values(tx, &h.id, &raw).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn save_origin(
+        tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+        id: &HistoryId,
+        origin: &Origin,
+    ) -> Result<()> {
+        sqlx::query("insert or ignore into history_audit(id, user, uid) values(?1, ?2, ?3)")
+            .bind(id.0.as_str())
+            .bind(origin.audit.user.as_str())
+            .bind(origin.audit.uid)
+            .execute(&mut **tx)
+            .await?;
+
+        if let Some(container) = &origin.container {
+            sqlx::query("insert or ignore into history_container(id, container) values(?1, ?2)")
+                .bind(id.0.as_str())
+                .bind(container.as_str())
+                .execute(&mut **tx)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn save_raw_values(
+        tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+        id: &HistoryId,
+        raw: &RawValues,
+    ) -> Result<()> {
+        // Keep the first raw value seen for each field, so clamping an
+        // already clamped entry again doesn't lose the original.
+        sqlx::query(
+            "insert into history_raw(id, timestamp, duration) values(?1, ?2, ?3)
+                on conflict(id) do update set
+                    timestamp = coalesce(history_raw.timestamp, excluded.timestamp),
+                    duration = coalesce(history_raw.duration, excluded.duration)",
+        )
+        .bind(id.0.as_str())
+        .bind(raw.timestamp.map(|t| t.unix_timestamp_nanos() as i64))
+        .bind(raw.duration)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn record_bigram(
+        tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+        h: &History,
+    ) -> Result<()> {
+        if h.session.is_empty() {
+            return Ok(());
+        }
+
+        let prev: Option<String> = sqlx::query_scalar(
+            "select command from history
+                where session = ?1 and timestamp < ?2 and deleted_at is null
+                order by timestamp desc limit 1",
+        )
+        .bind(h.session.as_str())
+        .bind(h.timestamp.unix_timestamp_nanos() as i64)
+        .fetch_optional(&mut **tx)
+        .await?;
+
+        let Some(prev) = prev else {
+            return Ok(());
+        };
+
+        sqlx::query(
+            "insert into command_bigrams(prev_command, command, count) values(?1, ?2, 1)
+                on conflict(prev_command, command) do update set count = count + 1",
+        )
+        .bind(prev.as_str())
+        .bind(h.command.as_str())
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Take a live entry about to be deleted out of the follow-up counts: its neighbours in the
+    /// session now follow each other instead. Once no live entry has its command, nothing of it
+    /// is left there either, so a command deleted for holding a secret doesn't linger.
+    async fn forget_bigrams(
+        tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+        id: &HistoryId,
+    ) -> Result<()> {
+        let entry: Option<(String, String, i64)> = sqlx::query_as(
+            "select command, session, timestamp from history
+                where id = ?1 and deleted_at is null",
+        )
+        .bind(id.0.as_str())
+        .fetch_optional(&mut **tx)
+        .await?;
+
+        let Some((command, session, timestamp)) = entry else {
+            return Ok(());
+        };
+
+        if !session.is_empty() {
+            let prev: Option<String> = sqlx::query_scalar(
+                "select command from history
+                    where session = ?1 and timestamp < ?2 and deleted_at is null
+                    order by timestamp desc limit 1",
+            )
+            .bind(session.as_str())
+            .bind(timestamp)
+            .fetch_optional(&mut **tx)
+            .await?;
+
+            let next: Option<String> = sqlx::query_scalar(
+                "select command from history
+                    where session = ?1 and timestamp > ?2 and deleted_at is null
+                    order by timestamp asc limit 1",
+            )
+            .bind(session.as_str())
+            .bind(timestamp)
+            .fetch_optional(&mut **tx)
+            .await?;
+
+            for (prev, next) in [
+                (prev.as_ref(), Some(&command)),
+                (Some(&command), next.as_ref()),
+            ] {
+                if let (Some(prev), Some(next)) = (prev, next) {
+                    sqlx::query(
+                        "update command_bigrams set count = count - 1
+                            where prev_command = ?1 and command = ?2",
+                    )
+                    .bind(prev.as_str())
+                    .bind(next.as_str())
+                    .execute(&mut **tx)
+                    .await?;
+                }
+            }
+
+            if let (Some(prev), Some(next)) = (prev, next) {
+                sqlx::query(
+                    "insert into command_bigrams(prev_command, command, count) values(?1, ?2, 1)
+                        on conflict(prev_command, command) do update set count = count + 1",
+                )
+                .bind(prev.as_str())
+                .bind(next.as_str())
+                .execute(&mut **tx)
+                .await?;
+            }
+        }
+
+        sqlx::query(
+            "delete from command_bigrams
+                where count <= 0
+                    or ((prev_command = ?1 or command = ?1) and not exists (
+                        select 1 from history where command = ?1 and deleted_at is null and id != ?2
+                    ))",
+        )
+        .bind(command.as_str())
+        .bind(id.0.as_str())
+        .execute(&mut **tx)
+        .await?;
+
         Ok(())
     }
 
     async fn delete_row_raw(
         tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
         id: HistoryId,
+        archive: bool,
     ) -> Result<()> {
+        Self::forget_bigrams(tx, &id).await?;
+
         sqlx::query("delete from history where id = ?1")
             .bind(id.0.as_str())
             .execute(&mut **tx)
             .await?;
 
+        if archive {
+            sqlx::query("delete from archive.history where id = ?1")
+                .bind(id.0.as_str())
+                .execute(&mut **tx)
+                .await?;
+        }
+
+        sqlx::query(
+            "delete from history_output_fts
+                where rowid in (select rowid from history_output where id = ?1)",
+        )
+        .bind(id.0.as_str())
+        .execute(&mut **tx)
+        .await?;
+
+        sqlx::query("delete from history_output where id = ?1")
+            .bind(id.0.as_str())
+            .execute(&mut **tx)
+            .await?;
+
+        sqlx::query("delete from history_raw where id = ?1")
+            .bind(id.0.as_str())
+            .execute(&mut **tx)
+            .await?;
+
         Ok(())
     }
 
@@ -363,7 +896,17 @@ impl Database for Sqlite {
     async fn save(&self, h: &History) -> Result<()> {
         debug!("saving history to sqlite");
         let mut tx = self.pool.begin().await?;
-        Self::save_raw(&mut tx, h).await?;
+        Self::save_raw(&mut tx, h, self.archive).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn save_with_origin(&self, h: &History, origin: &Origin) -> Result<()> {
+        debug!("saving history to sqlite");
+        let mut tx = self.pool.begin().await?;
+        Self::save_raw(&mut tx, h, self.archive).await?;
+        Self::save_origin(&mut tx, &h.id, origin).await?;
         tx.commit().await?;
 
         Ok(())
@@ -375,7 +918,7 @@ impl Database for Sqlite {
         let mut tx = self.pool.begin().await?;
 
         for i in h {
-            Self::save_raw(&mut tx, i).await?;
+            Self::save_raw(&mut tx, i, self.archive).await?;
         }
 
         tx.commit().await?;
@@ -425,6 +968,11 @@ impl Database for Sqlite {
     async fn update(&self, h: &History) -> Result<()> {
         debug!("updating sqlite history");
 
+        let mut h = h.clone();
+        let raw = h.clamp(OffsetDateTime::now_utc());
+
+        let mut tx = self.pool.begin().await?;
+
         sqlx::query(
             "update history
                 set timestamp = ?2, duration = ?3, exit = ?4, command = ?5, cwd = ?6, session = ?7, hostname = ?8, author = ?9, intent = ?10, deleted_at = ?11
@@ -441,9 +989,15 @@ impl Database for Sqlite {
         .bind(h.author.as_str())
         .bind(h.intent.as_deref())
         .bind(h.deleted_at.map(|t|t.unix_timestamp_nanos() as i64))
-        .execute(&self.pool)
+        .execute(&mut *tx)
         .await?;
 
+        if let Some(raw) = raw {
+            Self::save_raw_values(&mut tx, &h.id, &raw).await?;
+        }
+
+        tx.commit().await?;
+
         Ok(())
     }
 
@@ -492,6 +1046,10 @@ impl Database for Sqlite {
                 }
                 FilterMode::Directory => query.and_where_eq("cwd", quote(&context.cwd)),
                 FilterMode::Workspace => query.and_where_like_left("cwd", &git_root),
+                FilterMode::OtherHosts => query.and_where_ne(
+                    HOST_COLUMN,
+                    quote(host_of(&context.hostname).to_lowercase()),
+                ),
             };
         }
 
@@ -546,6 +1104,23 @@ impl Database for Sqlite {
         Ok(res)
     }
 
+    async fn latest(&self, session: Option<&str>, count: i64) -> Result<Vec<History>> {
+        let res = sqlx::query(
+            "select * from history
+                where duration >= 0 and deleted_at is null
+                    and (?1 is null or session = ?1)
+                order by timestamp desc
+                limit ?2",
+        )
+        .bind(session)
+        .bind(count)
+        .map(Self::query_history)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(res)
+    }
+
     async fn before(&self, timestamp: OffsetDateTime, count: i64) -> Result<Vec<History>> {
         let res = sqlx::query(
             "select * from history where timestamp < ?1 order by timestamp desc limit ?2",
@@ -582,85 +1157,94 @@ impl Database for Sqlite {
         // fuzzy/regex command matches, exit/cwd/date filters, author, deleted_at).
         // Deduplication, ordering and limiting are applied by the outer query
         // built below, so that the timestamp-ordered scan can early-terminate.
-        let mut sql = SqlBuilder::select_from("history");
-
-        let git_root = if let Some(git_root) = context.git_root.clone() {
-            git_root.to_str().unwrap_or("/").to_string()
+        let mut sql = SqlBuilder::select_from(if filter_options.include_archive && self.archive {
+            // An entry can be in both if it was saved again without the archive attached
+            "(select * from main.history union all
+                select * from archive.history where id not in (select id from main.history)
+            ) history"
         } else {
-            context.cwd.clone()
-        };
+            "history"
+        });
 
-        let session_start = get_session_start_time(&context.session);
+        apply_filter_mode(&mut sql, filter, context);
 
-        match filter {
-            FilterMode::Global => &mut sql,
-            FilterMode::Host => {
-                sql.and_where_eq("lower(hostname)", quote(context.hostname.to_lowercase()))
-            }
-            FilterMode::Session => sql.and_where_eq("session", quote(&context.session)),
-            FilterMode::SessionPreload => {
-                sql.and_where_eq("session", quote(&context.session));
-                if let Some(session_start) = session_start {
-                    sql.or_where_lt("timestamp", session_start);
-                }
-                &mut sql
-            }
-            FilterMode::Directory => sql.and_where_eq("cwd", quote(&context.cwd)),
-            FilterMode::Workspace => sql.and_where_like_left("cwd", git_root),
+        // A regex is matched as written
+        let (mut query_filters, query) = if search_mode == SearchMode::Regex {
+            (Vec::new(), Cow::Borrowed(query))
+        } else {
+            split_query_filters(query)
         };
+        if filter_options.exclude_containers {
+            exclude_containers(&mut query_filters);
+        }
+        let query = query.as_ref();
+        for filter in &query_filters {
+            filter.apply(&mut sql);
+        }
 
-        let orig_query = query;
+        let output_terms = strip_output_qualifier(query);
+        let orig_query = if output_terms.is_some() { "" } else { query };
 
         let mut regexes = Vec::new();
-        match search_mode {
-            SearchMode::Prefix => sql.and_where_like_left("command", query.replace('*', "%")),
-            _ => {
-                let mut is_or = false;
-                for token in QueryTokenizer::new(query) {
-                    // TODO smart case mode could be made configurable like in fzf
-                    let (is_glob, glob) = if token.has_uppercase() {
-                        (true, "*")
-                    } else {
-                        (false, "%")
-                    };
-                    let param = match token {
-                        QueryToken::Regex(r) => {
-                            regexes.push(String::from(r));
-                            continue;
-                        }
-                        QueryToken::Or => {
-                            if !is_or {
-                                is_or = true;
+        if let Some(terms) = &output_terms {
+            apply_output_filter(&mut sql, terms);
+        } else {
+            match search_mode {
+                SearchMode::Prefix => sql.and_where_like_left("command", query.replace('*', "%")),
+                SearchMode::Regex => {
+                    // Checked here, as sqlite's error for a bad pattern says little
+                    regex::Regex::new(query)
+                        .map_err(|e| sqlx::Error::Decode(format!("invalid regex: {e}").into()))?;
+                    sql.and_where("command regexp ?".bind(&query))
+                }
+                _ => {
+                    let mut is_or = false;
+                    for token in QueryTokenizer::new(query) {
+                        // TODO smart case mode could be made configurable like in fzf
+                        let (is_glob, glob) = if token.has_uppercase() {
+                            (true, "*")
+                        } else {
+                            (false, "%")
+                        };
+                        let param = match token {
+                            QueryToken::Regex(r) => {
+                                regexes.push(String::from(r));
                                 continue;
-                            } else {
-                                format!("{glob}|{glob}")
                             }
-                        }
-                        QueryToken::MatchStart(term, _) => {
-                            format!("{term}{glob}")
-                        }
-                        QueryToken::MatchEnd(term, _) => {
-                            format!("{glob}{term}")
-                        }
-                        QueryToken::MatchFull(term, _) => {
-                            format!("{glob}{term}{glob}")
-                        }
-                        QueryToken::Match(term, _) => {
-                            if search_mode == SearchMode::FullText {
+                            QueryToken::Or => {
+                                if !is_or {
+                                    is_or = true;
+                                    continue;
+                                } else {
+                                    format!("{glob}|{glob}")
+                                }
+                            }
+                            QueryToken::MatchStart(term, _) => {
+                                format!("{term}{glob}")
+                            }
+                            QueryToken::MatchEnd(term, _) => {
+                                format!("{glob}{term}")
+                            }
+                            QueryToken::MatchFull(term, _) => {
                                 format!("{glob}{term}{glob}")
-                            } else {
-                                term.split("").join(glob)
                             }
-                        }
-                    };
+                            QueryToken::Match(term, _) => {
+                                if search_mode == SearchMode::FullText {
+                                    format!("{glob}{term}{glob}")
+                                } else {
+                                    term.split("").join(glob)
+                                }
+                            }
+                        };
 
-                    sql.fuzzy_condition("command", param, token.is_inverse(), is_glob, is_or);
-                    is_or = false;
-                }
+                        sql.fuzzy_condition("command", param, token.is_inverse(), is_glob, is_or);
+                        is_or = false;
+                    }
 
-                &mut sql
-            }
-        };
+                    &mut sql
+                }
+            };
+        }
 
         for regex in regexes {
             sql.and_where("command regexp ?".bind(&regex));
@@ -687,26 +1271,14 @@ impl Database for Sqlite {
             .map(|exclude_cwd| sql.and_where_ne("cwd", quote(exclude_cwd)));
 
         if let Some(before) = filter_options.before {
-            let parsed = interim::parse_date_string(
-                before.as_str(),
-                OffsetDateTime::now_utc(),
-                interim::Dialect::Uk,
-            )
-            .map_err(|e| {
-                sqlx::Error::Decode(format!("invalid `before` filter {before:?}: {e}").into())
-            })?;
+            let parsed = parse_date(before.as_str(), utils::now(), Dialect::Uk)
+                .map_err(|e| sqlx::Error::Decode(format!("invalid `before` filter: {e}").into()))?;
             sql.and_where_lt("timestamp", quote(parsed.unix_timestamp_nanos() as i64));
         }
 
         if let Some(after) = filter_options.after {
-            let parsed = interim::parse_date_string(
-                after.as_str(),
-                OffsetDateTime::now_utc(),
-                interim::Dialect::Uk,
-            )
-            .map_err(|e| {
-                sqlx::Error::Decode(format!("invalid `after` filter {after:?}: {e}").into())
-            })?;
+            let parsed = parse_date(after.as_str(), utils::now(), Dialect::Uk)
+                .map_err(|e| sqlx::Error::Decode(format!("invalid `after` filter: {e}").into()))?;
             sql.and_where_gt("timestamp", quote(parsed.unix_timestamp_nanos() as i64));
         }
 
@@ -714,6 +1286,7 @@ impl Database for Sqlite {
             apply_author_filter(&mut sql, &filter_options.authors);
         }
         apply_shell_filter(&mut sql, &filter_options.shells);
+        apply_host_filter(&mut sql, &filter_options.hosts);
 
         sql.and_where_is_null("deleted_at");
 
@@ -762,7 +1335,12 @@ impl Database for Sqlite {
             .fetch_all(&self.pool)
             .await?;
 
-        Ok(ordering::reorder_fuzzy(search_mode, orig_query, res))
+        let res = ordering::reorder_fuzzy(search_mode, orig_query, res);
+        Ok(ordering::boost_context(
+            res,
+            context,
+            filter_options.context_boost,
+        ))
     }
 
     async fn query_history(&self, query: &str) -> Result<Vec<History>> {
@@ -829,7 +1407,7 @@ impl Database for Sqlite {
         let mut tx = self.pool.begin().await?;
 
         for id in ids {
-            Self::delete_row_raw(&mut tx, id.clone()).await?;
+            Self::delete_row_raw(&mut tx, id.clone(), self.archive).await?;
         }
 
         tx.commit().await?;
@@ -837,14 +1415,44 @@ impl Database for Sqlite {
         Ok(())
     }
 
-    async fn stats(&self, h: &History) -> Result<HistoryStats> {
-        // We select the previous in the session by time. Excluding deleted
-        // history matches every other read path, and lets the query use the
-        // partial (session, timestamp) index.
-        let mut prev = SqlBuilder::select_from("history");
-        prev.field("*")
-            .and_where("timestamp < ?1")
-            .and_where("session = ?2")
+    async fn rebuild_bigrams(&self) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("delete from command_bigrams")
+            .execute(&mut *tx)
+            .await?;
+
+        // As the migration that added the table seeded it, but leaving out entries without a
+        // session, as saving does
+        sqlx::query(
+            "insert into command_bigrams (prev_command, command, count)
+                select prev_command, command, count(*)
+                from (
+                    select
+                        command,
+                        lag(command) over (partition by session order by timestamp) as prev_command
+                    from history
+                    where deleted_at is null and session != ''
+                )
+                where prev_command is not null
+                group by prev_command, command",
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn stats(&self, h: &History) -> Result<HistoryStats> {
+        // We select the previous in the session by time. Excluding deleted
+        // history matches every other read path, and lets the query use the
+        // partial (session, timestamp) index.
+        let mut prev = SqlBuilder::select_from("history");
+        prev.field("*")
+            .and_where("timestamp < ?1")
+            .and_where("session = ?2")
             .and_where_is_null("deleted_at")
             .order_by("timestamp", true)
             .limit(1);
@@ -940,6 +1548,8 @@ impl Database for Sqlite {
                 .fetch_all(&self.pool),
         )?;
 
+        let output = self.load_output(&h.id).await?;
+
         let duration_over_time = duration_over_time
             .iter()
             .map(|f| (f.0.clone(), f.1.round() as i64))
@@ -953,6 +1563,8 @@ impl Database for Sqlite {
             exits,
             day_of_week,
             duration_over_time,
+            output,
+            sync: None,
         })
     }
 
@@ -976,9 +1588,408 @@ impl Database for Sqlite {
         Ok(res)
     }
 
+    async fn successors(&self, command: &str, limit: usize) -> Result<Vec<(String, i64)>> {
+        sqlx::query_as(
+            "select command, count from command_bigrams
+                where prev_command = ?1
+                order by count desc, command asc
+                limit ?2",
+        )
+        .bind(command)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn run_counts(
+        &self,
+        commands: &[String],
+        filter: FilterMode,
+        context: &Context,
+    ) -> Result<HashMap<String, i64>> {
+        if commands.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let mut sql = SqlBuilder::select_from("history");
+        sql.field("command").field("count(*)");
+        apply_filter_mode(&mut sql, filter, context);
+        sql.and_where_in_quoted("command", commands)
+            .and_where_is_null("deleted_at")
+            .group_by("command");
+        let query = sql.sql().expect("bug in run count query. please report");
+
+        let counts: Vec<(String, i64)> = sqlx::query_as(sqlx::AssertSqlSafe(query))
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(counts.into_iter().collect())
+    }
+
+    async fn runs(
+        &self,
+        command: &str,
+        filter: FilterMode,
+        context: &Context,
+        limit: usize,
+    ) -> Result<Vec<History>> {
+        let mut sql = SqlBuilder::select_from("history");
+        apply_filter_mode(&mut sql, filter, context);
+        sql.and_where_eq("command", quote(command))
+            .and_where_is_null("deleted_at")
+            .order_desc("timestamp")
+            .limit(limit);
+        let query = sql.sql().expect("bug in runs query. please report");
+
+        sqlx::query(sqlx::AssertSqlSafe(query))
+            .map(Self::query_history)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    async fn recent_sessions(&self, limit: usize) -> Result<Vec<String>> {
+        let sessions: Vec<(String,)> = sqlx::query_as(
+            "select session from history \
+            where deleted_at is null and session != '' \
+            group by session \
+            order by max(timestamp) desc \
+            limit ?1",
+        )
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(sessions.into_iter().map(|(session,)| session).collect())
+    }
+
+    async fn session_stats(&self, limit: usize) -> Result<Vec<SessionStats>> {
+        let rows: Vec<(String, String, i64, i64, i64, i64)> = sqlx::query_as(
+            "select session, group_concat(distinct hostname), count(*), \
+            sum(exit != 0 and duration != -1), min(timestamp), max(timestamp) from history \
+            where deleted_at is null and session != '' \
+            group by session \
+            order by max(timestamp) desc \
+            limit ?1",
+        )
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(
+                |(session, hostnames, commands, failures, start, last)| SessionStats {
+                    session,
+                    hostnames: hostnames.split(',').map(str::to_string).collect(),
+                    commands,
+                    failures,
+                    start: OffsetDateTime::from_unix_timestamp_nanos(start.into())
+                        .unwrap_or(OffsetDateTime::UNIX_EPOCH),
+                    last: OffsetDateTime::from_unix_timestamp_nanos(last.into())
+                        .unwrap_or(OffsetDateTime::UNIX_EPOCH),
+                },
+            )
+            .collect())
+    }
+
+    async fn session_history(&self, session: &str) -> Result<Vec<History>> {
+        sqlx::query(
+            "select * from history \
+            where session = ?1 and deleted_at is null \
+            order by timestamp asc",
+        )
+        .bind(session)
+        .map(Self::query_history)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn related(&self, command: &str, limit: usize) -> Result<Vec<(String, i64)>> {
+        // Only commands still in the history: entries older versions marked deleted, rather
+        // than deleting, are still counted
+        sqlx::query_as(
+            "select command, sum(count) as count from (
+                    select command, count from command_bigrams where prev_command = ?1
+                    union all
+                    select prev_command as command, count from command_bigrams where command = ?1
+                ) as related
+                where command != ?1
+                    and exists (
+                        select 1 from history
+                        where history.command = related.command and deleted_at is null
+                    )
+                group by command
+                order by count desc, command asc
+                limit ?2",
+        )
+        .bind(command)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    async fn ids_with_prefix(&self, prefix: &str, limit: usize) -> Result<Vec<HistoryId>> {
+        // A range rather than substr(), so the primary key's index finds them. IDs are hex,
+        // so every one starting with the prefix sorts before the prefix and a 'g'.
+        let ids: Vec<(String,)> = sqlx::query_as(
+            "select id from history
+                where id >= ?1 and id < ?1 || 'g'
+                order by id
+                limit ?2",
+        )
+        .bind(prefix)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(ids.into_iter().map(|(id,)| HistoryId(id)).collect())
+    }
+
+    async fn unique_id_prefix_len(&self, ids: &[HistoryId], min: usize) -> Result<usize> {
+        let ids = serde_json::to_string(&ids.iter().map(|id| id.0.as_str()).collect::<Vec<_>>())?;
+
+        // An ID shares its longest prefix with one of its neighbours in sorted order, which
+        // the index finds without reading the rest.
+        let neighbours: Vec<(String, String, String)> = sqlx::query_as(
+            "select ids.value,
+                coalesce((select max(id) from history where id < ids.value), ''),
+                coalesce((select min(id) from history where id > ids.value), '')
+            from json_each(?1) as ids",
+        )
+        .bind(ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let shared =
+            |a: &str, b: &str| a.bytes().zip(b.bytes()).take_while(|(a, b)| a == b).count();
+        let longest_shared = neighbours
+            .iter()
+            .map(|(id, before, after)| shared(id, before).max(shared(id, after)))
+            .max()
+            .unwrap_or(0);
+
+        Ok(min.max(longest_shared + 1))
+    }
+
+    async fn save_output(
+        &self,
+        id: &HistoryId,
+        output: &CapturedOutput,
+        searchable_bytes: usize,
+    ) -> Result<()> {
+        let compressed = output.compress()?;
+
+        let mut tx = self.pool.begin().await?;
+
+        // An upsert rather than a replace, so the rowid the index refers to
+        // stays put.
+        let rowid: i64 = sqlx::query_scalar(
+            "insert into history_output(id, output, total_bytes, truncated)
+                values(?1, ?2, ?3, ?4)
+                on conflict(id) do update set
+                    output = excluded.output,
+                    total_bytes = excluded.total_bytes,
+                    truncated = excluded.truncated
+                returning rowid",
+        )
+        .bind(id.0.as_str())
+        .bind(compressed)
+        .bind(output.total_bytes as i64)
+        .bind(output.truncated)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query("delete from history_output_fts where rowid = ?1")
+            .bind(rowid)
+            .execute(&mut *tx)
+            .await?;
+
+        let searchable = &output.text[..output.text.floor_char_boundary(searchable_bytes)];
+        if !searchable.is_empty() {
+            sqlx::query("insert into history_output_fts(rowid, text) values(?1, ?2)")
+                .bind(rowid)
+                .bind(searchable)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn load_output(&self, id: &HistoryId) -> Result<Option<CapturedOutput>> {
+        let row: Option<(Vec<u8>, i64, bool)> = sqlx::query_as(
+            "select output, total_bytes, truncated from history_output where id = ?1",
+        )
+        .bind(id.0.as_str())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|(data, total_bytes, truncated)| {
+            CapturedOutput::decompress(&data, total_bytes as u64, truncated)
+        })
+        .transpose()
+        .map_err(sqlx::Error::Io)
+    }
+
+    async fn raw_values(&self, id: &HistoryId) -> Result<Option<RawValues>> {
+        let row: Option<(Option<i64>, Option<i64>)> =
+            sqlx::query_as("select timestamp, duration from history_raw where id = ?1")
+                .bind(id.0.as_str())
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(row.map(|(timestamp, duration)| RawValues {
+            timestamp: timestamp
+                .and_then(|t| OffsetDateTime::from_unix_timestamp_nanos(t.into()).ok()),
+            duration,
+        }))
+    }
+
+    async fn save_audit(&self, id: &HistoryId, audit: &Audit) -> Result<()> {
+        sqlx::query("insert or ignore into history_audit(id, user, uid) values(?1, ?2, ?3)")
+            .bind(id.0.as_str())
+            .bind(audit.user.as_str())
+            .bind(audit.uid)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn audit(&self, id: &HistoryId) -> Result<Option<Audit>> {
+        let row: Option<(String, Option<u32>)> =
+            sqlx::query_as("select user, uid from history_audit where id = ?1")
+                .bind(id.0.as_str())
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(row.map(|(user, uid)| Audit { user, uid }))
+    }
+
+    async fn commands_run_by(&self, user: &str) -> Result<HashSet<String>> {
+        let commands: Vec<(String,)> = sqlx::query_as(
+            "select distinct h.command from history h \
+            join history_audit a on a.id = h.id \
+            where a.user = ?1 and h.deleted_at is null",
+        )
+        .bind(user)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(commands.into_iter().map(|(command,)| command).collect())
+    }
+
+    async fn save_container(&self, id: &HistoryId, container: &str) -> Result<()> {
+        sqlx::query("insert or ignore into history_container(id, container) values(?1, ?2)")
+            .bind(id.0.as_str())
+            .bind(container)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn commands_in_container(&self, container: Option<&str>) -> Result<HashSet<String>> {
+        let commands: Vec<(String,)> = match container {
+            Some(container) => {
+                sqlx::query_as(
+                    "select distinct h.command from history h \
+                    join history_container c on c.id = h.id \
+                    where substr(c.container, 1, length(?1)) = ?1 and h.deleted_at is null",
+                )
+                .bind(container)
+                .fetch_all(&self.pool)
+                .await?
+            }
+            None => {
+                sqlx::query_as(
+                    "select distinct command from history \
+                    where id not in (select id from history_container) and deleted_at is null",
+                )
+                .fetch_all(&self.pool)
+                .await?
+            }
+        };
+
+        Ok(commands.into_iter().map(|(command,)| command).collect())
+    }
+
+    async fn toggle_pin(&self, command: &str) -> Result<bool> {
+        let unpinned = sqlx::query("delete from history_pinned where command = ?1")
+            .bind(command)
+            .execute(&self.pool)
+            .await?
+            .rows_affected()
+            > 0;
+
+        if !unpinned {
+            sqlx::query("insert into history_pinned(command, pinned_at) values(?1, ?2)")
+                .bind(command)
+                .bind(utils::now().unix_timestamp_nanos() as i64)
+                .execute(&self.pool)
+                .await?;
+        }
+
+        Ok(!unpinned)
+    }
+
+    async fn pinned_commands(&self) -> Result<Vec<String>> {
+        let commands: Vec<(String,)> =
+            sqlx::query_as("select command from history_pinned order by pinned_at, rowid")
+                .fetch_all(&self.pool)
+                .await?;
+
+        Ok(commands.into_iter().map(|(command,)| command).collect())
+    }
+
+    async fn save_session_names(&self, names: &HashMap<String, String>) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("delete from session_name")
+            .execute(&mut *tx)
+            .await?;
+        for (session, name) in names {
+            sqlx::query("insert into session_name(session, name) values(?1, ?2)")
+                .bind(session)
+                .bind(name)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn session_names(&self) -> Result<HashMap<String, String>> {
+        let names: Vec<(String, String)> = sqlx::query_as("select session, name from session_name")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(names.into_iter().collect())
+    }
+
+    async fn commands_in_session(&self, name: &str) -> Result<HashSet<String>> {
+        let commands: Vec<(String,)> = sqlx::query_as(
+            "select distinct h.command from history h \
+            join session_name s on s.session = h.session \
+            where substr(lower(s.name), 1, length(?1)) = ?1 and h.deleted_at is null",
+        )
+        .bind(name.to_lowercase())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(commands.into_iter().map(|(command,)| command).collect())
+    }
+
     fn clone_boxed(&self) -> Box<dyn Database + 'static> {
         Box::new(self.clone())
     }
+
+    fn has_archive(&self) -> bool {
+        self.archive
+    }
 }
 
 pub struct Paged {
@@ -1086,6 +2097,297 @@ pub struct QueryTokenizer<'a> {
     last_pos: usize,
 }
 
+/// Searches captured command output instead of the command line, e.g.
+/// `in:output connection refused`.
+pub const OUTPUT_QUALIFIER: &str = "in:output";
+
+/// If `query` contains [`OUTPUT_QUALIFIER`], the rest of the query.
+pub fn strip_output_qualifier(query: &str) -> Option<String> {
+    let mut found = false;
+    let rest = query
+        .split(' ')
+        .filter(|word| {
+            let qualifier = *word == OUTPUT_QUALIFIER;
+            found |= qualifier;
+            !qualifier && !word.is_empty()
+        })
+        .join(" ");
+
+    found.then_some(rest)
+}
+
+/// Only entries whose output has every word of `terms` (as a prefix, so
+/// a half-typed word still matches), and none of the `!`-prefixed ones.
+fn apply_output_filter(sql: &mut SqlBuilder, terms: &str) {
+    let (excluded, included): (Vec<_>, Vec<_>) = terms
+        .split_whitespace()
+        .partition(|term| excluded_term(term).is_some());
+
+    let phrase = |term: &str| format!("\"{}\"*", term.replace('"', "\"\""));
+    let matching = |query: String| {
+        format!(
+            "select id from history_output where rowid in \
+                (select rowid from history_output_fts where history_output_fts match {})",
+            quote(query)
+        )
+    };
+
+    if included.is_empty() {
+        sql.and_where("id in (select id from history_output)");
+    } else {
+        let query = included.iter().map(|term| phrase(term)).join(" ");
+        sql.and_where(format!("id in ({})", matching(query)));
+    }
+
+    let excluded: Vec<_> = excluded
+        .iter()
+        .filter_map(|term| excluded_term(term))
+        .map(phrase)
+        .collect();
+    if !excluded.is_empty() {
+        sql.and_where(format!("id not in ({})", matching(excluded.join(" OR "))));
+    }
+}
+
+/// The keys of the filters a query can hold, like `exit:0`.
+const QUERY_FILTER_KEYS: [&str; 7] = ["exit", "host", "cwd", "git", "user", "container", "session"];
+
+/// A filter written into the query as `key:value`, alongside the words to search for:
+/// `exit:0 host:laptop cwd:~/src git:atuin user:root session:incident docker`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum QueryFilter {
+    /// Exited with this code
+    Exit(i64),
+    /// Run on a host whose name starts with this, in lowercase
+    Host(String),
+    /// Run in this directory, or one below it
+    Cwd(String),
+    /// Run in a directory with this name, or one below it, as in a checkout of the repository
+    Git(String),
+    /// Run by this OS account, such as `root` under `sudo`
+    User(String),
+    /// Run in a container whose `runtime:name` starts with this, or outside any for `None`,
+    /// written `container:none`
+    Container(Option<String>),
+    /// Run in a session whose name starts with this, in lowercase
+    Session(String),
+}
+
+impl QueryFilter {
+    /// The filter `word` spells out, if it's a complete one.
+    pub fn parse(word: &str) -> Option<Self> {
+        let (key, value) = word.split_once(':')?;
+        if value.is_empty() {
+            return None;
+        }
+
+        match key {
+            "exit" => value.parse().ok().map(Self::Exit),
+            "host" => Some(Self::Host(value.to_lowercase())),
+            "cwd" => {
+                let path = match value.strip_prefix('~') {
+                    Some(rest) => format!("{}{rest}", paths::home_dir().display()),
+                    None => value.to_string(),
+                };
+                let trimmed = path.trim_end_matches('/');
+                Some(Self::Cwd(if trimmed.is_empty() {
+                    path
+                } else {
+                    trimmed.to_string()
+                }))
+            }
+            "git" => Some(Self::Git(value.trim_matches('/').to_string())),
+            "user" => Some(Self::User(value.to_string())),
+            "container" => Some(Self::Container(
+                (value != "none").then(|| value.to_string()),
+            )),
+            "session" => Some(Self::Session(value.to_lowercase())),
+            _ => None,
+        }
+    }
+
+    /// Whether `h` passes the filter, for searches that don't go through the database. Who ran
+    /// an entry, where, and the name of its session aren't part of it, so `user:`, `container:`
+    /// and `session:` pass everything here; see [`allowed_by_filters`].
+    pub fn matches(&self, h: &History) -> bool {
+        match self {
+            Self::Exit(exit) => h.exit == *exit,
+            Self::Host(host) => host_of(&h.hostname).to_lowercase().starts_with(host),
+            // Only "/" keeps its trailing slash
+            Self::Cwd(cwd) if cwd.ends_with('/') => h.cwd.starts_with(cwd.as_str()),
+            Self::Cwd(cwd) => h
+                .cwd
+                .strip_prefix(cwd.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/')),
+            Self::Git(name) => {
+                h.cwd.ends_with(&format!("/{name}")) || h.cwd.contains(&format!("/{name}/"))
+            }
+            Self::User(_) | Self::Container(_) | Self::Session(_) => true,
+        }
+    }
+
+    fn apply(&self, sql: &mut SqlBuilder) {
+        match self {
+            Self::Exit(exit) => sql.and_where_eq("exit", exit),
+            // Compared exactly rather than with `like`, which would take `_` and `%` in them as
+            // wildcards and ignore case, so they match what `matches` does
+            Self::Host(host) => sql.and_where(format!(
+                "substr({HOST_COLUMN}, 1, length({0})) = {0}",
+                quote(host)
+            )),
+            Self::Cwd(cwd) if cwd.ends_with('/') => {
+                sql.and_where(format!("substr(cwd, 1, length({0})) = {0}", quote(cwd)))
+            }
+            Self::Cwd(cwd) => sql.and_where(format!(
+                "(cwd = {0} or substr(cwd, 1, length({0}) + 1) = {0} || '/')",
+                quote(cwd)
+            )),
+            Self::Git(name) => sql.and_where(format!(
+                "(substr(cwd, -length({0})) = {0} or instr(cwd, {1}) > 0)",
+                quote(format!("/{name}")),
+                quote(format!("/{name}/"))
+            )),
+            Self::User(user) => sql.and_where(format!(
+                "id in (select id from history_audit where user = {})",
+                quote(user)
+            )),
+            Self::Container(None) => sql.and_where("id not in (select id from history_container)"),
+            Self::Container(Some(container)) => sql.and_where(format!(
+                "id in (select id from history_container \
+                where substr(container, 1, length({0})) = {0})",
+                quote(container)
+            )),
+            Self::Session(name) => sql.and_where(format!(
+                "session in (select session from session_name \
+                where substr(lower(name), 1, length({0})) = {0})",
+                quote(name)
+            )),
+        };
+    }
+}
+
+/// Add `container:none` to `filters`, for searches that leave containers out, unless they pick
+/// containers of their own.
+pub fn exclude_containers(filters: &mut Vec<QueryFilter>) {
+    if !filters
+        .iter()
+        .any(|filter| matches!(filter, QueryFilter::Container(_)))
+    {
+        filters.push(QueryFilter::Container(None));
+    }
+}
+
+/// The commands that pass the `user:`, `container:` and `session:` filters in `filters`, for
+/// searches that don't go through the database, or `None` without any.
+pub async fn allowed_by_filters(
+    db: &dyn Database,
+    filters: &[QueryFilter],
+) -> Result<Option<HashSet<String>>> {
+    let mut allowed: Option<HashSet<String>> = None;
+    for filter in filters {
+        let commands = match filter {
+            QueryFilter::User(user) => db.commands_run_by(user).await?,
+            QueryFilter::Container(container) => {
+                db.commands_in_container(container.as_deref()).await?
+            }
+            QueryFilter::Session(name) => db.commands_in_session(name).await?,
+            _ => continue,
+        };
+        allowed = Some(match allowed {
+            Some(allowed) => allowed.intersection(&commands).cloned().collect(),
+            None => commands,
+        });
+    }
+
+    Ok(allowed)
+}
+
+/// Whether `word` is a filter, or the start of one, rather than something to search for.
+pub fn is_query_filter(word: &str) -> bool {
+    word == OUTPUT_QUALIFIER
+        || word
+            .split_once(':')
+            .is_some_and(|(key, _)| QUERY_FILTER_KEYS.contains(&key))
+}
+
+/// The filters in `query`, and the rest of it to search for. Filters that aren't finished, or
+/// don't make sense, like `exit:` or `exit:zero`, are left out of both.
+pub fn split_query_filters(query: &str) -> (Vec<QueryFilter>, Cow<'_, str>) {
+    let words = query.split(' ');
+    if !words
+        .clone()
+        .any(|word| word != OUTPUT_QUALIFIER && is_query_filter(word))
+    {
+        return (Vec::new(), Cow::Borrowed(query));
+    }
+
+    let (filters, rest): (Vec<_>, Vec<_>) = words
+        .filter(|word| !word.is_empty())
+        .partition(|word| *word != OUTPUT_QUALIFIER && is_query_filter(word));
+
+    (
+        filters.into_iter().filter_map(QueryFilter::parse).collect(),
+        Cow::Owned(rest.join(" ")),
+    )
+}
+
+/// The term `word` leaves out of results, if it's written as an exclusion: `!test` or `-test`.
+/// Words starting `--` aren't exclusions, so long flags can still be searched for.
+pub fn excluded_term(word: &str) -> Option<&str> {
+    if word.starts_with("--") {
+        return None;
+    }
+    word.strip_prefix('!')
+        .or_else(|| word.strip_prefix('-'))
+        .filter(|term| !term.is_empty())
+}
+
+/// The words of `query` to search for, and the terms it excludes, as in `cargo !test`.
+pub fn split_excluded_terms(query: &str) -> (Cow<'_, str>, Vec<&str>) {
+    if !query.split(' ').any(|word| excluded_term(word).is_some()) {
+        return (Cow::Borrowed(query), Vec::new());
+    }
+
+    let (excluded, included): (Vec<_>, Vec<_>) = query
+        .split(' ')
+        .filter(|word| !word.is_empty())
+        .partition(|word| excluded_term(word).is_some());
+
+    (
+        Cow::Owned(included.join(" ")),
+        excluded.into_iter().filter_map(excluded_term).collect(),
+    )
+}
+
+/// `query` with its exclusions all written as `!term`, for matchers that only know fzf's syntax.
+pub fn normalize_exclusions(query: &str) -> Cow<'_, str> {
+    if !query
+        .split(' ')
+        .any(|word| word.starts_with('-') && excluded_term(word).is_some())
+    {
+        return Cow::Borrowed(query);
+    }
+
+    Cow::Owned(
+        query
+            .split(' ')
+            .map(|word| match excluded_term(word) {
+                Some(term) => Cow::Owned(format!("!{term}")),
+                None => Cow::Borrowed(word),
+            })
+            .join(" "),
+    )
+}
+
+/// Whether `command` has `term` in it, ignoring case unless the term has capitals in it.
+pub fn contains_term(command: &str, term: &str) -> bool {
+    if term.contains(char::is_uppercase) {
+        command.contains(term)
+    } else {
+        command.to_lowercase().contains(term)
+    }
+}
+
 pub enum QueryToken<'a> {
     Match(&'a str, bool),
     MatchStart(&'a str, bool),
@@ -1154,7 +2456,7 @@ impl<'a> Iterator for QueryTokenizer<'a> {
             }
 
             let mut is_inverse = false;
-            if let Some(s) = part.strip_prefix('!') {
+            if let Some(s) = excluded_term(part) {
                 part = s;
                 is_inverse = true;
             }
@@ -1411,16 +2713,543 @@ mod test {
         assert_eq!(loaded.len(), 1200);
     }
 
-    async fn db_with(commands: &[&str]) -> Sqlite {
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_successors_counts_session_transitions() {
         let mut db = Sqlite::new("sqlite::memory:", test_local_timeout())
             .await
             .unwrap();
 
-        for command in commands {
-            new_history_item(&mut db, command).await.unwrap();
+        let start = OffsetDateTime::from_unix_timestamp(1_708_330_400).unwrap();
+        let commands = [
+            "cargo build",
+            "cargo test",
+            "cargo build",
+            "cargo test",
+            "cargo build",
+            "cargo run",
+        ];
+        for (i, cmd) in commands.iter().enumerate() {
+            let at = start + time::Duration::seconds(i as i64);
+            new_history_item_at(&mut db, cmd, Some(at)).await.unwrap();
         }
 
-        db
+        let successors = db.successors("cargo build", 10).await.unwrap();
+        assert_eq!(
+            successors,
+            vec![("cargo test".to_string(), 2), ("cargo run".to_string(), 1)]
+        );
+
+        // Saving an entry that already exists must not count the transition again.
+        let all = db
+            .list(&[], &new_context(), None, false, false, None)
+            .await
+            .unwrap();
+        db.save_bulk(&all).await.unwrap();
+
+        let successors = db.successors("cargo build", 1).await.unwrap();
+        assert_eq!(successors, vec![("cargo test".to_string(), 2)]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_deleted_commands_leave_successors() {
+        let mut db = Sqlite::new("sqlite::memory:", test_local_timeout())
+            .await
+            .unwrap();
+
+        let start = OffsetDateTime::from_unix_timestamp(1_708_330_400).unwrap();
+        for (i, cmd) in ["login", "export TOKEN=hunter2", "deploy"]
+            .iter()
+            .enumerate()
+        {
+            let at = start + time::Duration::seconds(i as i64);
+            new_history_item_at(&mut db, cmd, Some(at)).await.unwrap();
+        }
+
+        let secret = db
+            .search(
+                SearchMode::FullText,
+                FilterMode::Global,
+                &new_context(),
+                "hunter2",
+                OptFilters::default(),
+            )
+            .await
+            .unwrap();
+        db.delete(secret[0].clone()).await.unwrap();
+
+        let successors = db.successors("login", 10).await.unwrap();
+        assert_eq!(successors, vec![("deploy".to_string(), 1)]);
+        assert!(
+            db.successors("export TOKEN=hunter2", 10)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        // Rebuilding from what's left agrees
+        db.rebuild_bigrams().await.unwrap();
+        let successors = db.successors("login", 10).await.unwrap();
+        assert_eq!(successors, vec![("deploy".to_string(), 1)]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_runs_of_a_command_are_counted_and_listed() {
+        let mut db = Sqlite::new("sqlite::memory:", test_local_timeout())
+            .await
+            .unwrap();
+
+        let start = OffsetDateTime::from_unix_timestamp(1_708_330_400).unwrap();
+        for (i, cmd) in ["make", "ls", "make", "make"].iter().enumerate() {
+            let at = start + time::Duration::seconds(i as i64);
+            new_history_item_at(&mut db, cmd, Some(at)).await.unwrap();
+        }
+        let mut elsewhere: History = History::capture()
+            .timestamp(start + time::Duration::seconds(10))
+            .command("make")
+            .cwd("/tmp")
+            .build()
+            .into();
+        elsewhere.exit = 0;
+        db.save(&elsewhere).await.unwrap();
+
+        let context = new_context();
+        let commands = ["make".to_string(), "ls".to_string(), "pwd".to_string()];
+        let counts = db
+            .run_counts(&commands, FilterMode::Global, &context)
+            .await
+            .unwrap();
+        assert_eq!(counts.get("make"), Some(&4));
+        assert_eq!(counts.get("ls"), Some(&1));
+        assert_eq!(counts.get("pwd"), None);
+
+        let counts = db
+            .run_counts(&commands, FilterMode::Directory, &context)
+            .await
+            .unwrap();
+        assert_eq!(counts.get("make"), Some(&3));
+
+        let runs = db
+            .runs("make", FilterMode::Global, &context, 3)
+            .await
+            .unwrap();
+        assert_eq!(runs.len(), 3);
+        assert_eq!(runs[0].cwd, "/tmp");
+        assert!(runs[1].timestamp > runs[2].timestamp);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_sessions_are_listed_newest_first() {
+        let db = Sqlite::new("sqlite::memory:", test_local_timeout())
+            .await
+            .unwrap();
+
+        let start = OffsetDateTime::from_unix_timestamp(1_708_330_400).unwrap();
+        for (i, (cmd, session)) in [("ls", "a"), ("make", "b"), ("pwd", "a"), ("cd", "c")]
+            .iter()
+            .enumerate()
+        {
+            let h: History = History::import()
+                .timestamp(start + time::Duration::seconds(i as i64))
+                .command(*cmd)
+                .session(*session)
+                .build()
+                .into();
+            db.save(&h).await.unwrap();
+        }
+
+        assert_eq!(db.recent_sessions(2).await.unwrap(), ["c", "a"]);
+
+        let history = db.session_history("a").await.unwrap();
+        let commands: Vec<&str> = history.iter().map(|h| h.command.as_str()).collect();
+        assert_eq!(commands, ["ls", "pwd"]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_session_stats_are_totted_up() {
+        let db = Sqlite::new("sqlite::memory:", test_local_timeout())
+            .await
+            .unwrap();
+
+        let start = OffsetDateTime::from_unix_timestamp(1_708_330_400).unwrap();
+        for (i, (cmd, session, host, exit)) in [
+            ("ls", "a", "laptop:ellie", 0),
+            ("make", "b", "laptop:ellie", 2),
+            ("false", "a", "laptop:ellie", 1),
+            ("ssh", "a", "server:ellie", 0),
+        ]
+        .iter()
+        .enumerate()
+        {
+            let h: History = History::import()
+                .timestamp(start + time::Duration::seconds(i as i64))
+                .command(*cmd)
+                .session(*session)
+                .hostname(*host)
+                .exit(*exit)
+                .duration(1)
+                .build()
+                .into();
+            db.save(&h).await.unwrap();
+        }
+
+        let stats = db.session_stats(10).await.unwrap();
+        assert_eq!(stats.len(), 2);
+
+        let mut hostnames = stats[0].hostnames.clone();
+        hostnames.sort();
+        assert_eq!(stats[0].session, "a");
+        assert_eq!(hostnames, ["laptop:ellie", "server:ellie"]);
+        assert_eq!((stats[0].commands, stats[0].failures), (3, 1));
+        assert_eq!(stats[0].start, start);
+        assert_eq!(stats[0].last, start + time::Duration::seconds(3));
+
+        assert_eq!(stats[1].session, "b");
+        assert_eq!((stats[1].commands, stats[1].failures), (1, 1));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_related_counts_both_directions() {
+        let mut db = Sqlite::new("sqlite::memory:", test_local_timeout())
+            .await
+            .unwrap();
+
+        let start = OffsetDateTime::from_unix_timestamp(1_708_330_400).unwrap();
+        let commands = [
+            "git add .",
+            "git commit",
+            "git push",
+            "git add .",
+            "git commit",
+            "git commit",
+            "git log",
+        ];
+        for (i, cmd) in commands.iter().enumerate() {
+            let at = start + time::Duration::seconds(i as i64);
+            new_history_item_at(&mut db, cmd, Some(at)).await.unwrap();
+        }
+
+        // `git add .` came before it twice, the others after it once each, and following
+        // itself doesn't count
+        let related = db.related("git commit", 10).await.unwrap();
+        assert_eq!(
+            related,
+            vec![
+                ("git add .".to_string(), 2),
+                ("git log".to_string(), 1),
+                ("git push".to_string(), 1),
+            ]
+        );
+
+        // Nor are commands that are only left deleted
+        let mut push = db
+            .search(
+                SearchMode::FullText,
+                FilterMode::Global,
+                &new_context(),
+                "git push",
+                OptFilters::default(),
+            )
+            .await
+            .unwrap()
+            .remove(0);
+        push.deleted_at = Some(OffsetDateTime::now_utc());
+        db.update(&push).await.unwrap();
+
+        let related = db.related("git commit", 10).await.unwrap();
+        assert_eq!(
+            related,
+            vec![("git add .".to_string(), 2), ("git log".to_string(), 1)]
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_latest_skips_running_and_deleted() {
+        let db = Sqlite::new("sqlite::memory:", test_local_timeout())
+            .await
+            .unwrap();
+        let start = OffsetDateTime::now_utc() - time::Duration::minutes(1);
+
+        let mut entries = Vec::new();
+        for (i, cmd) in ["ls", "pwd", "whoami", "sleep 100"].iter().enumerate() {
+            let mut h: History = History::capture()
+                .timestamp(start + time::Duration::seconds(i as i64))
+                .command(*cmd)
+                .cwd("/")
+                .build()
+                .into();
+            h.duration = 1;
+            entries.push(h);
+        }
+        // Still running.
+        entries[3].duration = -1;
+        db.save_bulk(&entries).await.unwrap();
+        db.delete(entries[2].clone()).await.unwrap();
+
+        let latest = db.latest(None, 5).await.unwrap();
+        let commands: Vec<&str> = latest.iter().map(|h| h.command.as_str()).collect();
+        assert_eq!(commands, vec!["pwd", "ls"]);
+
+        assert_eq!(db.latest(None, 1).await.unwrap()[0].command, "pwd");
+        assert!(db.latest(Some("elsewhere"), 1).await.unwrap().is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_ids_with_prefix_and_unique_prefix_len() {
+        let db = Sqlite::new("sqlite::memory:", test_local_timeout())
+            .await
+            .unwrap();
+
+        assert_eq!(db.unique_id_prefix_len(&[], 8).await.unwrap(), 8);
+
+        let mut ids = Vec::new();
+        for cmd in ["ls", "pwd", "whoami"] {
+            ids.push(save_history_item(&db, cmd).await.id.0);
+        }
+
+        let all: Vec<HistoryId> = ids.iter().cloned().map(HistoryId).collect();
+        let len = db.unique_id_prefix_len(&all, 8).await.unwrap();
+        // Only what's printed is looked at, but told apart from everything else
+        for id in &all {
+            let alone = db
+                .unique_id_prefix_len(std::slice::from_ref(id), 8)
+                .await
+                .unwrap();
+            assert!(alone <= len);
+            let matches = db.ids_with_prefix(&id.0[..alone], 10).await.unwrap();
+            assert_eq!(matches, vec![id.clone()]);
+        }
+
+        for id in &ids {
+            let matches = db.ids_with_prefix(&id[..len], 10).await.unwrap();
+            assert_eq!(matches, vec![HistoryId(id.clone())]);
+        }
+
+        // Entries captured moments apart share their leading timestamp bits.
+        let everything = db.ids_with_prefix("", 10).await.unwrap();
+        assert_eq!(everything.len(), 3);
+        assert!(db.ids_with_prefix(&ids[0][..1], 2).await.unwrap().len() <= 2);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_output_is_stored_and_deleted_with_its_entry() {
+        let db = Sqlite::new("sqlite::memory:", test_local_timeout())
+            .await
+            .unwrap();
+        let h = save_history_item(&db, "cargo build").await;
+
+        assert_eq!(db.load_output(&h.id).await.unwrap(), None);
+
+        let output = CapturedOutput {
+            text: "Compiling atuin\nFinished\n".to_string(),
+            total_bytes: 25,
+            truncated: false,
+        };
+        db.save_output(&h.id, &output, 1024).await.unwrap();
+        assert_eq!(db.load_output(&h.id).await.unwrap(), Some(output.clone()));
+        assert_eq!(db.stats(&h).await.unwrap().output, Some(output));
+
+        db.delete(h.clone()).await.unwrap();
+        assert_eq!(db.load_output(&h.id).await.unwrap(), None);
+        assert_search_eq(
+            &db,
+            SearchMode::FullText,
+            FilterMode::Global,
+            "in:output",
+            0,
+        )
+        .await
+        .unwrap();
+        let indexed: i64 = sqlx::query_scalar("select count(*) from history_output_fts")
+            .fetch_one(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(indexed, 0);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_bogus_values_are_clamped_and_kept() {
+        let db = Sqlite::new("sqlite::memory:", test_local_timeout())
+            .await
+            .unwrap();
+
+        let future = OffsetDateTime::now_utc() + time::Duration::days(3650);
+        let mut h: History = History::import()
+            .timestamp(future)
+            .command("ls")
+            .duration(-5_000)
+            .build()
+            .into();
+        db.save(&h).await.unwrap();
+
+        let saved = db.load(&h.id.0).await.unwrap().unwrap();
+        assert!(saved.timestamp < future - time::Duration::days(1));
+        assert_eq!(saved.duration, -1);
+        assert_eq!(
+            db.raw_values(&h.id).await.unwrap(),
+            Some(RawValues {
+                timestamp: Some(future),
+                duration: Some(-5_000),
+            })
+        );
+
+        // A later bad update doesn't replace the original raw values.
+        h.duration = -7;
+        db.update(&h).await.unwrap();
+        assert_eq!(
+            db.raw_values(&h.id).await.unwrap().unwrap().duration,
+            Some(-5_000)
+        );
+
+        let fine = save_history_item(&db, "pwd").await;
+        assert_eq!(db.raw_values(&fine.id).await.unwrap(), None);
+
+        db.delete(h.clone()).await.unwrap();
+        assert_eq!(db.raw_values(&h.id).await.unwrap(), None);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_audit_is_kept_after_deleting() {
+        let db = Sqlite::new("sqlite::memory:", test_local_timeout())
+            .await
+            .unwrap();
+        let h = save_history_item(&db, "ls").await;
+        assert_eq!(db.audit(&h.id).await.unwrap(), None);
+
+        let audit = Audit {
+            user: "ellie".to_string(),
+            uid: Some(1000),
+        };
+        db.save_audit(&h.id, &audit).await.unwrap();
+        // The first account noted stays
+        db.save_audit(
+            &h.id,
+            &Audit {
+                user: "mallory".to_string(),
+                uid: Some(0),
+            },
+        )
+        .await
+        .unwrap();
+
+        db.delete(h.clone()).await.unwrap();
+        assert_eq!(db.audit(&h.id).await.unwrap(), Some(audit));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_save_with_origin() {
+        let db = Sqlite::new("sqlite::memory:", test_local_timeout())
+            .await
+            .unwrap();
+        let h: History = History::capture()
+            .timestamp(OffsetDateTime::now_utc())
+            .command("ls")
+            .cwd("/home/ellie")
+            .build()
+            .into();
+        let origin = Origin {
+            audit: Audit {
+                user: "ellie".to_string(),
+                uid: Some(1000),
+            },
+            container: Some("docker:3f4a5b6c7d8e".to_string()),
+        };
+
+        db.save_with_origin(&h, &origin).await.unwrap();
+
+        assert_eq!(db.load(&h.id.0).await.unwrap().unwrap().command, "ls");
+        assert_eq!(db.audit(&h.id).await.unwrap(), Some(origin.audit));
+        assert_eq!(
+            db.commands_in_container(Some("docker:")).await.unwrap(),
+            HashSet::from(["ls".to_string()])
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_search_in_output() {
+        let db = Sqlite::new("sqlite::memory:", test_local_timeout())
+            .await
+            .unwrap();
+
+        for (cmd, text, searchable) in [
+            ("cargo build", "error: linker `cc` not found\n", 1024),
+            ("ls", "Cargo.toml\nsrc\n", 1024),
+            ("make", "linker warnings past the index limit\n", 0),
+        ] {
+            let h = save_history_item(&db, cmd).await;
+            let output = CapturedOutput {
+                text: text.to_string(),
+                total_bytes: text.len() as u64,
+                truncated: false,
+            };
+            db.save_output(&h.id, &output, searchable).await.unwrap();
+        }
+        save_history_item(&db, "pwd").await;
+
+        let results = assert_search_eq(
+            &db,
+            SearchMode::FullText,
+            FilterMode::Global,
+            "in:output linker",
+            1,
+        )
+        .await
+        .unwrap();
+        assert_eq!(results[0].command, "cargo build");
+
+        // Words match by prefix, so results keep up with typing.
+        assert_search_eq(
+            &db,
+            SearchMode::Fuzzy,
+            FilterMode::Global,
+            "in:output lin",
+            1,
+        )
+        .await
+        .unwrap();
+        assert_search_eq(
+            &db,
+            SearchMode::FullText,
+            FilterMode::Global,
+            "in:output",
+            3,
+        )
+        .await
+        .unwrap();
+        let results = assert_search_eq(
+            &db,
+            SearchMode::FullText,
+            FilterMode::Global,
+            "!linker in:output",
+            2,
+        )
+        .await
+        .unwrap();
+        assert!(results.iter().all(|h| h.command != "cargo build"));
+        assert_search_eq(&db, SearchMode::FullText, FilterMode::Global, "linker", 0)
+            .await
+            .unwrap();
+    }
+
+    #[rstest]
+    #[case("in:output error", Some("error"))]
+    #[case("cargo in:output  build", Some("cargo build"))]
+    #[case("in:output", Some(""))]
+    #[case("in:outputs", None)]
+    #[case("cargo build", None)]
+    fn test_strip_output_qualifier(#[case] query: &str, #[case] expected: Option<&str>) {
+        assert_eq!(strip_output_qualifier(query).as_deref(), expected);
+    }
+
+    async fn db_with(commands: &[&str]) -> Sqlite {
+        let mut db = Sqlite::new("sqlite::memory:", test_local_timeout())
+            .await
+            .unwrap();
+
+        for command in commands {
+            new_history_item(&mut db, command).await.unwrap();
+        }
+
+        db
     }
 
     #[rstest]
@@ -1511,6 +3340,61 @@ mod test {
         assert_eq!(hits.len(), expected);
     }
 
+    /// A database with `uname -a` run as each of `hostnames`.
+    async fn db_on_hosts(hostnames: &[&str]) -> Sqlite {
+        let db = Sqlite::new("sqlite::memory:", test_local_timeout())
+            .await
+            .unwrap();
+
+        for hostname in hostnames {
+            let mut captured: History = History::capture()
+                .timestamp(OffsetDateTime::now_utc())
+                .command("uname -a")
+                .cwd("/home/ellie")
+                .build()
+                .into();
+            captured.hostname = (*hostname).to_string();
+            db.save(&captured).await.unwrap();
+        }
+
+        db
+    }
+
+    #[rstest]
+    #[case::other_hosts_skip_every_user_here(FilterMode::OtherHosts, &[], &["desktop:ellie", "booop"])]
+    #[case::hosts_match_the_machine_part(FilterMode::Global, &["Desktop"], &["desktop:ellie"])]
+    #[case::hosts_can_be_repeated(FilterMode::Global, &["desktop", "test"], &["TEST:root", "test:ellie", "desktop:ellie"])]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_search_by_host(
+        #[case] filter_mode: FilterMode,
+        #[case] hosts: &[&str],
+        #[case] expected: &[&str],
+    ) {
+        // The context runs on `test:host`
+        let db = db_on_hosts(&["desktop:ellie", "test:ellie", "TEST:root", "booop"]).await;
+
+        let hits = db
+            .search(
+                SearchMode::FullText,
+                filter_mode,
+                &new_context(),
+                "",
+                OptFilters {
+                    include_duplicates: true,
+                    hosts: hosts.iter().map(ToString::to_string).collect(),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+
+        let mut found: Vec<&str> = hits.iter().map(|h| h.hostname.as_str()).collect();
+        let mut expected = expected.to_vec();
+        found.sort_unstable();
+        expected.sort_unstable();
+        assert_eq!(found, expected);
+    }
+
     #[rstest]
     #[case::before("before")]
     #[case::after("after")]
@@ -1552,6 +3436,182 @@ mod test {
             .unwrap();
     }
 
+    async fn db_with_filterable() -> Sqlite {
+        let db = Sqlite::new("sqlite::memory:", test_local_timeout())
+            .await
+            .unwrap();
+
+        for (command, exit, hostname, cwd) in [
+            ("cargo build", 0, "laptop:ellie", "/home/ellie/src/atuin"),
+            (
+                "cargo test",
+                101,
+                "laptop:ellie",
+                "/home/ellie/src/atuin/crates",
+            ),
+            ("docker ps", 0, "server:ellie", "/srv/atuin-backup"),
+            ("docker ps -a", 1, "Server:root", "/"),
+        ] {
+            let mut h: History = History::capture()
+                .timestamp(OffsetDateTime::now_utc())
+                .command(command)
+                .cwd(cwd)
+                .build()
+                .into();
+            h.exit = exit;
+            h.hostname = hostname.to_string();
+            h.session = host_of(hostname).to_lowercase();
+            db.save(&h).await.unwrap();
+
+            let user = hostname.split_once(':').unwrap().1.to_string();
+            let in_container = user == "root";
+            db.save_audit(&h.id, &Audit { user, uid: None })
+                .await
+                .unwrap();
+            if in_container {
+                db.save_container(&h.id, "docker:3f4a5b6c7d8e")
+                    .await
+                    .unwrap();
+            }
+        }
+        db.save_session_names(&HashMap::from([(
+            "laptop".to_string(),
+            "Incident-4123".to_string(),
+        )]))
+        .await
+        .unwrap();
+
+        db
+    }
+
+    #[rstest]
+    #[case::exit("exit:0", 2)]
+    #[case::exit_and_text("exit:0 docker", 1)]
+    #[case::host_prefix_any_case("host:SERV", 2)]
+    #[case::cwd_and_below("cwd:/home/ellie/src/atuin", 2)]
+    #[case::cwd_trailing_slash("cwd:/home/ellie/src/atuin/", 2)]
+    #[case::cwd_not_a_sibling("cwd:/home/ellie/src/at", 0)]
+    #[case::cwd_root("cwd:/ docker", 2)]
+    #[case::git_name("git:atuin", 2)]
+    #[case::git_whole_name_only("git:atuin-back", 0)]
+    #[case::git_underscore_is_not_a_wildcard("git:atuin_backup", 0)]
+    #[case::git_percent_is_not_a_wildcard("git:atuin%", 0)]
+    #[case::cwd_case_matters("cwd:/home/ellie/src/Atuin", 0)]
+    #[case::cwd_underscore_is_not_a_wildcard("cwd:/home/ellie/src/atui_", 0)]
+    #[case::combined("exit:0 host:laptop git:atuin cargo", 1)]
+    #[case::unfinished_filter_is_ignored("exit: docker", 2)]
+    #[case::unknown_key_is_text("port:80", 0)]
+    #[case::user("user:root", 1)]
+    #[case::user_exactly("user:ell", 0)]
+    #[case::user_and_text("user:ellie docker", 1)]
+    #[case::container("container:docker", 1)]
+    #[case::container_by_name("container:docker:3f4a", 1)]
+    #[case::other_container("container:podman", 0)]
+    #[case::no_container("container:none", 3)]
+    #[case::session_name_prefix_any_case("session:incident", 2)]
+    #[case::session_and_text("session:incident-4123 test", 1)]
+    #[case::unnamed_session("session:server", 0)]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_search_query_filters(#[case] query: &str, #[case] expected: usize) {
+        let db = db_with_filterable().await;
+
+        let results = assert_search_eq(&db, SearchMode::Fuzzy, FilterMode::Global, query, expected)
+            .await
+            .unwrap();
+
+        // Searches that don't go through the database filter the same way
+        let (filters, _) = split_query_filters(query);
+        let run_by = allowed_by_filters(&db, &filters).await.unwrap();
+        assert!(results.iter().all(|h| filters.iter().all(|f| f.matches(h))
+            && run_by.as_ref().is_none_or(|c| c.contains(&h.command))));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_search_excluding_containers() {
+        let db = db_with_filterable().await;
+        let context = new_context();
+        let search = |query: &'static str| {
+            db.search(
+                SearchMode::Fuzzy,
+                FilterMode::Global,
+                &context,
+                query,
+                OptFilters {
+                    exclude_containers: true,
+                    ..Default::default()
+                },
+            )
+        };
+
+        assert_eq!(search("docker").await.unwrap().len(), 1);
+        // Unless the query asks for them
+        assert_eq!(search("container:docker").await.unwrap().len(), 1);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_toggle_pin() {
+        let db = Sqlite::new("sqlite::memory:", test_local_timeout())
+            .await
+            .unwrap();
+
+        assert!(db.toggle_pin("git status").await.unwrap());
+        assert!(db.toggle_pin("cargo test").await.unwrap());
+        assert_eq!(
+            db.pinned_commands().await.unwrap(),
+            ["git status", "cargo test"]
+        );
+
+        assert!(!db.toggle_pin("git status").await.unwrap());
+        assert_eq!(db.pinned_commands().await.unwrap(), ["cargo test"]);
+    }
+
+    #[test]
+    fn query_filters_are_split_from_the_text() {
+        let (filters, rest) = split_query_filters("exit:0  docker host:Laptop cwd:~/src ps");
+        assert_eq!(
+            filters,
+            [
+                QueryFilter::Exit(0),
+                QueryFilter::Host("laptop".to_string()),
+                QueryFilter::Cwd(format!("{}/src", paths::home_dir().display())),
+            ]
+        );
+        assert_eq!(rest, "docker ps");
+
+        // Without any filters, the query is left exactly as it was
+        assert!(matches!(split_query_filters("ls  "), (f, Cow::Borrowed("ls  ")) if f.is_empty()));
+        assert!(is_query_filter("exit:"));
+        assert!(is_query_filter(OUTPUT_QUALIFIER));
+        assert!(!is_query_filter("https://atuin.sh"));
+    }
+
+    #[rstest]
+    #[case::substring("home", 1)]
+    #[case::anchored_start("^ls /", 1)]
+    #[case::anchored_wrong_start("^/home", 0)]
+    #[case::alternation("frank|ellie$", 1)]
+    #[case::spaces_are_literal("ls  /home", 0)]
+    #[case::case_sensitive("Ellie", 0)]
+    #[case::case_insensitive_flag("(?i)Ellie", 1)]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_search_regex(#[case] query: &str, #[case] expected: usize) {
+        let db = db_with(&["ls /home/ellie"]).await;
+
+        assert_search_eq(&db, SearchMode::Regex, FilterMode::Global, query, expected)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_search_invalid_regex() {
+        let db = db_with(&["ls /home/ellie"]).await;
+
+        let err = assert_search_eq(&db, SearchMode::Regex, FilterMode::Global, "ls (", 0)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("invalid regex"));
+    }
+
     #[rstest]
     #[case::matches_command("ls", 1)]
     #[case::matches_arg("/home", 1)]
@@ -1567,6 +3627,10 @@ mod test {
     #[case::regex_triple_slash("r//home///", 0)]
     #[case::plain_query_looks_like_regex("/home.*e", 0)]
     #[case::regex_wildcard("r/home.*e", 1)]
+    // exclusions
+    #[case::excluded_term("ls !ellie", 0)]
+    #[case::dash_excluded_term("ls -ellie", 0)]
+    #[case::excluded_other_term("ls -frank", 1)]
     #[tokio::test(flavor = "multi_thread")]
     async fn test_search_fulltext(#[case] query: &str, #[case] expected: usize) {
         let db = db_with(&["ls /home/ellie"]).await;
@@ -1598,6 +3662,8 @@ mod test {
     #[case::negated_term("!ellie", 1)]
     #[case::negated_ends_with("!ellie$", 2)]
     #[case::term_and_negated_term("ls !ellie", 1)]
+    #[case::dash_negated_term("-ellie", 1)]
+    #[case::term_and_dash_negated_term("ls -ellie", 1)]
     #[case::starts_with_and_negated_ends_with("^ls !e$", 1)]
     #[case::term_and_negated_starts_with("home !^ls", 2)]
     #[case::or_exact_terms("'frank | 'rustup", 2)]
@@ -1621,6 +3687,29 @@ mod test {
             .unwrap();
     }
 
+    #[rstest]
+    #[case::bang("!test", Some("test"))]
+    #[case::dash("-test", Some("test"))]
+    #[case::long_flag("--test", None)]
+    #[case::bare_bang("!", None)]
+    #[case::bare_dash("-", None)]
+    #[case::plain("test", None)]
+    fn test_excluded_term(#[case] word: &str, #[case] expected: Option<&str>) {
+        assert_eq!(excluded_term(word), expected);
+    }
+
+    #[test]
+    fn test_split_excluded_terms() {
+        let (query, excluded) = split_excluded_terms("cargo !test -bench --release");
+        assert_eq!(query, "cargo --release");
+        assert_eq!(excluded, ["test", "bench"]);
+
+        assert_eq!(
+            normalize_exclusions("cargo -test --release"),
+            "cargo !test --release"
+        );
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn test_search_reordered_fuzzy() {
         let mut db = Sqlite::new("sqlite::memory:", test_local_timeout())
@@ -1817,4 +3906,189 @@ mod test {
 
         assert!(duration < Duration::from_secs(15));
     }
+
+    async fn search_commands(db: &Sqlite, include_archive: bool) -> Vec<String> {
+        let mut found: Vec<String> = db
+            .search(
+                SearchMode::FullText,
+                FilterMode::Global,
+                &new_context(),
+                "",
+                OptFilters {
+                    include_archive,
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|h| h.command)
+            .collect();
+        found.sort_unstable();
+        found
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_archive_moves_old_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = Sqlite::with_archive(
+            dir.path().join("history.db"),
+            dir.path().join("archive.db"),
+            test_local_timeout(),
+        )
+        .await
+        .unwrap();
+        assert!(db.has_archive());
+
+        let old = OffsetDateTime::now_utc() - time::Duration::days(800);
+        new_history_item_at(&mut db, "make old", Some(old))
+            .await
+            .unwrap();
+        new_history_item(&mut db, "make new").await.unwrap();
+
+        let moved = db
+            .archive(OffsetDateTime::now_utc() - time::Duration::days(365))
+            .await
+            .unwrap();
+        assert_eq!(moved, 1);
+        assert_eq!(db.history_count(true).await.unwrap(), 1);
+
+        // Only searched when asked for
+        assert_eq!(search_commands(&db, false).await, ["make new"]);
+        assert_eq!(search_commands(&db, true).await, ["make new", "make old"]);
+
+        // Archiving again finds nothing more to move
+        let moved = db
+            .archive(OffsetDateTime::now_utc() - time::Duration::days(365))
+            .await
+            .unwrap();
+        assert_eq!(moved, 0);
+
+        // Deleting reaches into the archive
+        let archived = db
+            .search(
+                SearchMode::FullText,
+                FilterMode::Global,
+                &new_context(),
+                "old",
+                OptFilters {
+                    include_archive: true,
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        db.delete_rows(&[archived[0].id.clone()]).await.unwrap();
+        assert_eq!(search_commands(&db, true).await, ["make new"]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_archived_history_stays_archived() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = Sqlite::with_archive(
+            dir.path().join("history.db"),
+            dir.path().join("archive.db"),
+            test_local_timeout(),
+        )
+        .await
+        .unwrap();
+
+        let old = OffsetDateTime::now_utc() - time::Duration::days(800);
+        new_history_item_at(&mut db, "make old", Some(old))
+            .await
+            .unwrap();
+        db.archive(OffsetDateTime::now_utc() - time::Duration::days(365))
+            .await
+            .unwrap();
+        let archived = db
+            .search(
+                SearchMode::FullText,
+                FilterMode::Global,
+                &new_context(),
+                "old",
+                OptFilters {
+                    include_archive: true,
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+
+        // Saved again, as rebuilding from the record store does
+        db.save_bulk(&archived).await.unwrap();
+        assert_eq!(db.history_count(true).await.unwrap(), 0);
+        assert_eq!(search_commands(&db, true).await, ["make old"]);
+
+        // Saved without the archive attached, it's still only found once
+        let unattached = Sqlite::new(dir.path().join("history.db"), test_local_timeout())
+            .await
+            .unwrap();
+        unattached.save(&archived[0]).await.unwrap();
+        assert_eq!(search_commands(&db, true).await, ["make old"]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_archive_needs_attaching() {
+        let db = db_with(&["ls"]).await;
+
+        assert!(!db.has_archive());
+        assert!(db.archive(OffsetDateTime::now_utc()).await.is_err());
+
+        // Asking for the archive without one just searches the history
+        assert_eq!(search_commands(&db, true).await, ["ls"]);
+    }
+
+    // Like 20 shells sharing one history database: each opens it at the same time, as after an
+    // upgrade, then records commands while searching. None should see a locked database.
+    #[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+    async fn test_concurrent_shells() {
+        const SHELLS: usize = 20;
+        const COMMANDS: usize = 25;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.db");
+
+        let shells = futures::future::try_join_all((0..SHELLS).map(|_| Sqlite::new(&path, 10.0)))
+            .await
+            .unwrap();
+
+        let mut tasks = tokio::task::JoinSet::new();
+        for (shell, mut db) in shells.into_iter().enumerate() {
+            tasks.spawn(async move {
+                for i in 0..COMMANDS {
+                    new_history_item(&mut db, &format!("shell-{shell}-command-{i}")).await?;
+
+                    let found = db
+                        .search(
+                            SearchMode::FullText,
+                            FilterMode::Global,
+                            &new_context(),
+                            &format!("shell-{shell}-"),
+                            OptFilters::default(),
+                        )
+                        .await?;
+                    assert_eq!(found.len(), i + 1, "shell {shell} lost history");
+                }
+
+                Ok::<_, sqlx::Error>(())
+            });
+        }
+
+        while let Some(result) = tasks.join_next().await {
+            result.unwrap().unwrap();
+        }
+
+        let db = Sqlite::new(&path, test_local_timeout()).await.unwrap();
+        assert_eq!(
+            db.history_count(true).await.unwrap(),
+            (SHELLS * COMMANDS) as i64
+        );
+
+        // Each migration was applied once, by whichever shell got the lock first
+        let applied: i64 = sqlx::query_scalar("select count(*) from _sqlx_migrations")
+            .fetch_one(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(applied, migrations::MIGRATOR.iter().count() as i64);
+    }
 }
@@ -0,0 +1,192 @@
+//! Sharing the local databases between processes: the daemon, every shell's hooks and the
+//! search TUI all open them at once.
+//!
+//! SQLite does most of the work. In WAL mode readers never block the writer, and a
+//! connection that finds the database locked waits up to its busy timeout for the lock
+//! rather than failing. What it doesn't cover is two processes upgrading the schema at the
+//! same time, so migrating takes an advisory lock first. Only when there's migrating to do:
+//! opening the databases is on the hot path of every prompt.
+
+use std::{
+    fs::{File, OpenOptions, TryLockError},
+    io,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use sqlx::{Result, migrate::Migrator, sqlite::SqlitePool};
+
+/// How often to try again for a lock another process holds
+const RETRY: Duration = Duration::from_millis(50);
+
+/// Held while migrating a database, so that only one process at a time does it. Released
+/// when dropped.
+#[derive(Debug)]
+pub struct MigrationLock {
+    _file: Option<File>,
+}
+
+impl MigrationLock {
+    /// Wait up to `timeout` for any other process migrating the database at `db_path` to
+    /// finish, then take the lock. Databases that aren't files, like in-memory ones, aren't
+    /// shared, so there's nothing to lock.
+    pub async fn acquire(db_path: &Path, timeout: Duration) -> io::Result<Self> {
+        let Some(path) = lock_path(db_path) else {
+            return Ok(Self { _file: None });
+        };
+
+        debug!("waiting for the migration lock at {path:?}");
+
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)?;
+        let started = Instant::now();
+
+        loop {
+            match file.try_lock() {
+                Ok(()) => return Ok(Self { _file: Some(file) }),
+                Err(TryLockError::WouldBlock) if started.elapsed() < timeout => {
+                    tokio::time::sleep(RETRY).await;
+                }
+                Err(TryLockError::WouldBlock) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::TimedOut,
+                        format!(
+                            "another process has been migrating {db_path:?} for over {}s",
+                            timeout.as_secs_f64()
+                        ),
+                    ));
+                }
+                Err(TryLockError::Error(e)) => return Err(e),
+            }
+        }
+    }
+}
+
+/// Whether the database has migrations from `migrator` still to apply, or that failed. Cheap
+/// enough to ask every time it's opened, so that only a database needing it is locked.
+pub async fn migrations_pending(pool: &SqlitePool, migrator: &Migrator) -> Result<bool> {
+    migrations_pending_in(pool, migrator, "main").await
+}
+
+/// As [`migrations_pending`], for the database attached to `pool` as `schema`.
+pub async fn migrations_pending_in(
+    pool: &SqlitePool,
+    migrator: &Migrator,
+    schema: &'static str,
+) -> Result<bool> {
+    let exists: bool = sqlx::query_scalar(sqlx::AssertSqlSafe(format!(
+        "select exists(select 1 from {schema}.sqlite_master where type = 'table' and name = '_sqlx_migrations')"
+    )))
+    .fetch_one(pool)
+    .await?;
+
+    let applied: Vec<i64> = if exists {
+        sqlx::query_scalar(sqlx::AssertSqlSafe(format!(
+            "select version from {schema}._sqlx_migrations where success"
+        )))
+        .fetch_all(pool)
+        .await?
+    } else {
+        Vec::new()
+    };
+
+    Ok(migrator
+        .iter()
+        .filter(|migration| migration.migration_type.is_up_migration())
+        .any(|migration| !applied.contains(&migration.version)))
+}
+
+/// Where the lock for migrating `db_path` lives: next to it, as `<name>.lock`
+pub fn lock_path(db_path: &Path) -> Option<PathBuf> {
+    if !db_path.is_file() {
+        return None;
+    }
+
+    let name = db_path.file_name()?.to_str()?;
+    Some(db_path.with_file_name(format!("{name}.lock")))
+}
+
+/// Warn if the database isn't in WAL mode. It's asked for on every connection, but some
+/// filesystems (network ones, mostly) can't support it, and SQLite quietly keeps the old mode.
+/// Everything still works, but writers block readers, so busy shells slow each other down.
+pub async fn check_wal(pool: &SqlitePool, db_path: &Path) -> Result<()> {
+    if !db_path.is_file() {
+        return Ok(());
+    }
+
+    let mode: String = sqlx::query_scalar("pragma journal_mode")
+        .fetch_one(pool)
+        .await?;
+
+    if !mode.eq_ignore_ascii_case("wal") {
+        warn!(
+            "{db_path:?} is in {mode} journal mode rather than WAL, so processes sharing it \
+             will wait on each other more. Is it on a network filesystem?"
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn lock_path_is_next_to_the_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = dir.path().join("history.db");
+        assert_eq!(lock_path(&db), None);
+
+        File::create(&db).unwrap();
+        assert_eq!(lock_path(&db), Some(dir.path().join("history.db.lock")));
+        assert_eq!(lock_path(Path::new("sqlite::memory:")), None);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn migration_lock_is_exclusive() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = dir.path().join("history.db");
+        File::create(&db).unwrap();
+
+        let held = MigrationLock::acquire(&db, Duration::from_secs(5))
+            .await
+            .unwrap();
+
+        let waiting = tokio::spawn({
+            let db = db.clone();
+            async move { MigrationLock::acquire(&db, Duration::from_secs(5)).await }
+        });
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(!waiting.is_finished(), "the lock was taken twice");
+
+        drop(held);
+        tokio::time::timeout(Duration::from_secs(5), waiting)
+            .await
+            .expect("the lock wasn't released")
+            .unwrap()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn migration_lock_waits_no_longer_than_the_timeout() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = dir.path().join("history.db");
+        File::create(&db).unwrap();
+
+        let _held = MigrationLock::acquire(&db, Duration::from_secs(5))
+            .await
+            .unwrap();
+
+        let err = MigrationLock::acquire(&db, Duration::from_millis(200))
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+    }
+}
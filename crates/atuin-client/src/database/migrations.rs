@@ -0,0 +1,555 @@
+//! The history database's schema migrations: what's been applied, a backup before applying
+//! more, and a way back for when developing a new one.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use eyre::{Result, WrapErr, bail};
+use fs_err as fs;
+use sqlx::{
+    Row,
+    migrate::Migrator,
+    sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions},
+};
+use time::{OffsetDateTime, PrimitiveDateTime};
+
+use super::lock::MigrationLock;
+
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+/// How many pre-migration backups to keep next to the database
+const KEEP_BACKUPS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationState {
+    Applied,
+    Pending,
+    /// Started but didn't finish
+    Failed,
+    /// Applied, but the migration has since been edited
+    Modified,
+    /// Applied by a newer version of atuin
+    Unknown,
+}
+
+impl MigrationState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Applied => "applied",
+            Self::Pending => "pending",
+            Self::Failed => "failed",
+            Self::Modified => "modified",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub state: MigrationState,
+    pub installed_on: Option<OffsetDateTime>,
+    /// How long the migration took to apply, in nanoseconds
+    pub execution_time: Option<i64>,
+}
+
+/// A copy of the database taken before migrations newer than `version` were applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Backup {
+    pub version: i64,
+    pub path: PathBuf,
+}
+
+struct Applied {
+    version: i64,
+    description: String,
+    installed_on: Option<OffsetDateTime>,
+    success: bool,
+    checksum: Vec<u8>,
+    execution_time: i64,
+}
+
+async fn applied(pool: &SqlitePool) -> Result<Vec<Applied>> {
+    let exists: bool = sqlx::query_scalar(
+        "select exists(select 1 from sqlite_master where type = 'table' and name = '_sqlx_migrations')",
+    )
+    .fetch_one(pool)
+    .await?;
+
+    if !exists {
+        return Ok(Vec::new());
+    }
+
+    let rows = sqlx::query(
+        "select version, description, installed_on, success, checksum, execution_time
+        from _sqlx_migrations order by version",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| Applied {
+            version: row.get("version"),
+            description: row.get("description"),
+            installed_on: row
+                .try_get::<PrimitiveDateTime, _>("installed_on")
+                .ok()
+                .map(PrimitiveDateTime::assume_utc),
+            success: row.get("success"),
+            checksum: row.get("checksum"),
+            execution_time: row.get("execution_time"),
+        })
+        .collect())
+}
+
+/// Every migration this version of atuin knows about, plus any applied by a newer one, oldest first
+pub async fn status(pool: &SqlitePool) -> Result<Vec<MigrationStatus>> {
+    let applied = applied(pool).await?;
+
+    let mut status: Vec<MigrationStatus> = MIGRATOR
+        .iter()
+        .filter(|migration| migration.migration_type.is_up_migration())
+        .map(|migration| {
+            let done = applied.iter().find(|a| a.version == migration.version);
+            let state = match done {
+                None => MigrationState::Pending,
+                Some(a) if !a.success => MigrationState::Failed,
+                Some(a) if a.checksum != *migration.checksum => MigrationState::Modified,
+                Some(_) => MigrationState::Applied,
+            };
+
+            MigrationStatus {
+                version: migration.version,
+                description: migration.description.to_string(),
+                state,
+                installed_on: done.and_then(|a| a.installed_on),
+                execution_time: done.map(|a| a.execution_time),
+            }
+        })
+        .collect();
+
+    status.extend(
+        applied
+            .into_iter()
+            .filter(|a| !MIGRATOR.version_exists(a.version))
+            .map(|a| MigrationStatus {
+                version: a.version,
+                description: a.description,
+                state: MigrationState::Unknown,
+                installed_on: a.installed_on,
+                execution_time: Some(a.execution_time),
+            }),
+    );
+    status.sort_by_key(|migration| migration.version);
+
+    Ok(status)
+}
+
+fn backup_prefix(db_path: &Path) -> Option<String> {
+    let name = db_path.file_name()?.to_str()?;
+    Some(format!("{name}.pre-"))
+}
+
+/// Where the backup taken at `version` lives
+pub fn backup_path(db_path: &Path, version: i64) -> Option<PathBuf> {
+    let prefix = backup_prefix(db_path)?;
+    Some(db_path.with_file_name(format!("{prefix}{version}.bak")))
+}
+
+/// The backups next to the database, oldest first
+pub fn backups(db_path: &Path) -> Result<Vec<Backup>> {
+    let (Some(prefix), Some(dir)) = (backup_prefix(db_path), db_path.parent()) else {
+        return Ok(Vec::new());
+    };
+    let dir = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups: Vec<Backup> = fs::read_dir(dir)?
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let version = path
+                .file_name()?
+                .to_str()?
+                .strip_prefix(&prefix)?
+                .strip_suffix(".bak")?
+                .parse()
+                .ok()?;
+
+            Some(Backup { version, path })
+        })
+        .collect();
+    backups.sort_by_key(|backup| backup.version);
+
+    Ok(backups)
+}
+
+/// Copy the database aside if there are migrations waiting to change its schema. Only called
+/// once there are, holding the migration lock, as it reads the applied migrations itself.
+///
+/// Fresh databases have nothing worth keeping, so are left alone. Returns the backup, if one
+/// was taken.
+pub async fn backup_before_migrating(pool: &SqlitePool, db_path: &Path) -> Result<Option<Backup>> {
+    let Some(current) = applied(pool).await?.last().map(|a| a.version) else {
+        return Ok(None);
+    };
+
+    let pending = MIGRATOR
+        .iter()
+        .any(|migration| migration.migration_type.is_up_migration() && migration.version > current);
+    if !pending {
+        return Ok(None);
+    }
+
+    let Some(path) = backup_path(db_path, current) else {
+        return Ok(None);
+    };
+
+    debug!("backing up {db_path:?} to {path:?} before migrating");
+
+    // VACUUM INTO won't overwrite, and a leftover from an earlier attempt is stale anyway
+    if path.exists() {
+        fs::remove_file(&path)?;
+    }
+
+    sqlx::query("vacuum into ?1")
+        .bind(path.to_string_lossy())
+        .execute(pool)
+        .await?;
+
+    let backups = backups(db_path)?;
+    for old in backups
+        .iter()
+        .take(backups.len().saturating_sub(KEEP_BACKUPS))
+    {
+        fs::remove_file(&old.path)?;
+    }
+
+    Ok(Some(Backup {
+        version: current,
+        path,
+    }))
+}
+
+/// The migration [`revert_last`] would undo, the one before it, and the backup it would restore
+struct Revert {
+    last: MigrationStatus,
+    previous: Option<i64>,
+    backup: Backup,
+}
+
+fn plan_revert(db_path: &Path, status: &[MigrationStatus]) -> Result<Revert> {
+    let applied: Vec<&MigrationStatus> = status
+        .iter()
+        .filter(|m| m.state != MigrationState::Pending)
+        .collect();
+
+    let Some(last) = applied.last().copied() else {
+        bail!("no migrations have been applied");
+    };
+
+    if last.state == MigrationState::Unknown {
+        bail!(
+            "the last migration ({}) was applied by a newer version of atuin, which is needed to revert it",
+            last.version
+        );
+    }
+
+    let previous = applied.iter().rev().nth(1).map(|m| m.version);
+    let Some(backup) = backups(db_path)?
+        .into_iter()
+        .rev()
+        .find(|backup| backup.version < last.version && Some(backup.version) <= previous)
+    else {
+        bail!(
+            "there is no backup from before migration {} to restore",
+            last.version
+        );
+    };
+
+    Ok(Revert {
+        last: last.clone(),
+        previous,
+        backup,
+    })
+}
+
+/// How many history entries [`revert_last`] would lose: those in the database that the backup
+/// it would restore doesn't have.
+pub async fn revert_last_loses(db_path: &Path, timeout: Duration) -> Result<u64> {
+    // A single connection, as the backup is only attached to the one that attaches it
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(
+            SqliteConnectOptions::new()
+                .filename(db_path)
+                .read_only(true)
+                .busy_timeout(timeout),
+        )
+        .await?;
+    let revert = plan_revert(db_path, &status(&pool).await?)?;
+
+    sqlx::query("attach database ?1 as backup")
+        .bind(revert.backup.path.to_string_lossy())
+        .execute(&pool)
+        .await?;
+    let lost: i64 = sqlx::query_scalar(
+        "select count(*) from main.history where id not in (select id from backup.history)",
+    )
+    .fetch_one(&pool)
+    .await?;
+    pool.close().await;
+
+    Ok(lost.try_into().unwrap_or_default())
+}
+
+/// Undo the most recently applied migration by restoring the backup taken before it, then
+/// re-applying anything older that the backup is missing.
+///
+/// This throws away everything written to the database since the backup, so is only meant for
+/// developing migrations. Nothing else may have the database open, which SQLite checks when
+/// it's taken out of WAL mode: if anything does, this fails before touching it. Waits up to
+/// `timeout` for any process migrating it.
+pub async fn revert_last(db_path: &Path, timeout: Duration) -> Result<MigrationStatus> {
+    crate::read_only::ensure_writable("revert a migration")?;
+
+    let _lock = MigrationLock::acquire(db_path, timeout).await?;
+
+    // A single connection, as SQLite counts any others as the database being in use
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(
+            SqliteConnectOptions::new()
+                .filename(db_path)
+                .busy_timeout(timeout),
+        )
+        .await?;
+    let Revert {
+        last,
+        previous,
+        backup,
+    } = plan_revert(db_path, &status(&pool).await?)?;
+
+    // Fold the WAL into the database and leave WAL mode, which SQLite removes the WAL files
+    // for. It refuses while another connection has the database open.
+    let in_use = || format!("{db_path:?} is in use; close atuin everywhere else and try again");
+    sqlx::query("pragma wal_checkpoint(truncate)")
+        .execute(&pool)
+        .await
+        .wrap_err_with(in_use)?;
+    let mode: String = sqlx::query_scalar("pragma journal_mode = delete")
+        .fetch_one(&pool)
+        .await
+        .wrap_err_with(in_use)?;
+    if !mode.eq_ignore_ascii_case("delete") {
+        bail!(in_use());
+    }
+    pool.close().await;
+
+    // Swapped in whole, so that nothing opening the database meanwhile sees half of it
+    let mut restoring = db_path.as_os_str().to_owned();
+    restoring.push(".restoring");
+    let restoring = PathBuf::from(restoring);
+    fs::copy(&backup.path, &restoring)?;
+    fs::rename(&restoring, db_path)?;
+
+    if let Some(previous) = previous
+        && previous > backup.version
+    {
+        let pool = SqlitePool::connect_with(SqliteConnectOptions::new().filename(db_path)).await?;
+        MIGRATOR.run_to(previous, &pool).await?;
+        pool.close().await;
+    }
+
+    Ok(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::{Database, Sqlite},
+        history::History,
+        settings::test_local_timeout,
+    };
+
+    /// A database on disk, migrated up to the second-to-last migration
+    async fn outdated_db(dir: &Path) -> (PathBuf, i64) {
+        let path = dir.join("history.db");
+        let versions: Vec<i64> = MIGRATOR.iter().map(|m| m.version).collect();
+        let previous = versions[versions.len() - 2];
+
+        let pool = Sqlite::connect(&path, test_local_timeout())
+            .await
+            .unwrap()
+            .pool;
+        MIGRATOR.run_to(previous, &pool).await.unwrap();
+        pool.close().await;
+
+        (path, previous)
+    }
+
+    #[tokio::test]
+    async fn fresh_databases_are_not_backed_up() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.db");
+
+        Sqlite::new(&path, test_local_timeout()).await.unwrap();
+
+        assert!(backups(&path).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn migrating_takes_a_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let (path, previous) = outdated_db(dir.path()).await;
+
+        let db = Sqlite::new(&path, test_local_timeout()).await.unwrap();
+
+        let backups = backups(&path).unwrap();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(backups[0].version, previous);
+        assert!(
+            status(&db.pool)
+                .await
+                .unwrap()
+                .iter()
+                .all(|m| m.state == MigrationState::Applied)
+        );
+
+        // Already up to date, so nothing more to back up
+        drop(db);
+        Sqlite::new(&path, test_local_timeout()).await.unwrap();
+        assert_eq!(super::backups(&path).unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn status_shows_pending_migrations() {
+        let dir = tempfile::tempdir().unwrap();
+        let (path, previous) = outdated_db(dir.path()).await;
+
+        let db = Sqlite::connect(&path, test_local_timeout()).await.unwrap();
+        let status = status(&db.pool).await.unwrap();
+
+        let last = status.last().unwrap();
+        assert_eq!(last.state, MigrationState::Pending);
+        assert!(last.installed_on.is_none());
+
+        let applied = &status[status.len() - 2];
+        assert_eq!(applied.version, previous);
+        assert_eq!(applied.state, MigrationState::Applied);
+        assert!(applied.installed_on.is_some());
+    }
+
+    #[tokio::test]
+    async fn reverting_restores_the_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let (path, previous) = outdated_db(dir.path()).await;
+
+        Sqlite::new(&path, test_local_timeout())
+            .await
+            .unwrap()
+            .pool
+            .close()
+            .await;
+        let reverted = revert_last(&path, Duration::from_secs(2)).await.unwrap();
+        assert_eq!(reverted.version, MIGRATOR.iter().last().unwrap().version);
+
+        let db = Sqlite::connect(&path, test_local_timeout()).await.unwrap();
+        let status = status(&db.pool).await.unwrap();
+        assert_eq!(status.last().unwrap().state, MigrationState::Pending);
+        assert_eq!(status[status.len() - 2].version, previous);
+        assert_eq!(status[status.len() - 2].state, MigrationState::Applied);
+    }
+
+    #[tokio::test]
+    async fn reverting_counts_what_it_loses() {
+        let dir = tempfile::tempdir().unwrap();
+        let (path, _) = outdated_db(dir.path()).await;
+
+        let db = Sqlite::new(&path, test_local_timeout()).await.unwrap();
+        let history: History = History::capture()
+            .timestamp(OffsetDateTime::now_utc())
+            .command("ls")
+            .cwd("/home/ellie")
+            .build()
+            .into();
+        db.save(&history).await.unwrap();
+        db.pool.close().await;
+
+        let lost = revert_last_loses(&path, Duration::from_secs(2))
+            .await
+            .unwrap();
+        assert_eq!(lost, 1);
+    }
+
+    #[tokio::test]
+    async fn reverting_needs_a_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.db");
+
+        Sqlite::new(&path, test_local_timeout())
+            .await
+            .unwrap()
+            .pool
+            .close()
+            .await;
+        let err = revert_last(&path, Duration::from_secs(2))
+            .await
+            .unwrap_err();
+
+        assert!(err.to_string().contains("no backup"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn reverting_refuses_a_database_in_use() {
+        let dir = tempfile::tempdir().unwrap();
+        let (path, _) = outdated_db(dir.path()).await;
+
+        // Like a shell's search holding it open
+        let db = Sqlite::new(&path, test_local_timeout()).await.unwrap();
+        sqlx::query("select count(*) from history")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+
+        let err = revert_last(&path, Duration::from_millis(200))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("in use"), "{err}");
+
+        // Still there, and still migrated
+        let status = status(&db.pool).await.unwrap();
+        assert_eq!(status.last().unwrap().state, MigrationState::Applied);
+    }
+
+    #[test]
+    fn backups_are_found_by_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.db");
+
+        for name in [
+            "history.db.pre-20.bak",
+            "history.db.pre-3.bak",
+            "history.db.pre-x.bak",
+            "other.db.pre-5.bak",
+        ] {
+            std::fs::write(dir.path().join(name), "").unwrap();
+        }
+
+        let versions: Vec<i64> = backups(&path).unwrap().iter().map(|b| b.version).collect();
+        assert_eq!(versions, [3, 20]);
+        assert_eq!(
+            backup_path(&path, 7).unwrap(),
+            dir.path().join("history.db.pre-7.bak")
+        );
+    }
+}
@@ -0,0 +1,274 @@
+//! Human date expressions, as accepted by `atuin search --before`, `--after`
+//! and `--at`.
+//!
+//! Anything [`interim`] understands works ("2 weeks ago", "last tuesday",
+//! "yesterday 3pm", "01/04/2021"), as do RFC 3339 timestamps and calendar
+//! boundaries such as "start of month" or "end of last week". Phrases that
+//! read like the past but resolve to the future (a bare "tuesday", or "2 days"
+//! without "ago") are rejected with a suggestion rather than silently matching
+//! nothing.
+
+use time::{Date, Duration, Month, OffsetDateTime, Time, format_description::well_known::Rfc3339};
+
+use crate::settings::Dialect;
+
+#[derive(Debug, thiserror::Error)]
+pub enum DateError {
+    #[error(
+        "{input:?} is ambiguous: it was read as {resolved}, which is in the future. Did you mean {suggestion:?}?"
+    )]
+    Ambiguous {
+        input: String,
+        resolved: String,
+        suggestion: String,
+    },
+
+    #[error(
+        "could not understand the date {input:?} ({reason}). Try something like \"2 weeks ago\", \"last tuesday\", \"start of month\" or \"2024-01-31\""
+    )]
+    Unparsable { input: String, reason: String },
+}
+
+const WEEKDAYS: &[&str] = &[
+    "monday",
+    "tuesday",
+    "wednesday",
+    "thursday",
+    "friday",
+    "saturday",
+    "sunday",
+];
+
+const MONTHS: &[&str] = &[
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+];
+
+#[derive(Clone, Copy)]
+enum Period {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+/// Parse `input` relative to `now`. Calendar boundaries are computed in the
+/// offset of `now`, so pass it in the user's configured timezone.
+pub fn parse_date(
+    input: &str,
+    now: OffsetDateTime,
+    dialect: Dialect,
+) -> Result<OffsetDateTime, DateError> {
+    let trimmed = input.trim();
+
+    if let Ok(date) = OffsetDateTime::parse(trimmed, &Rfc3339) {
+        return Ok(date);
+    }
+
+    let normalized = trimmed
+        .split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    if let Some(date) = parse_boundary(&normalized, now) {
+        return Ok(date);
+    }
+
+    let date = interim::parse_date_string(&normalized, now, dialect.into()).map_err(|e| {
+        DateError::Unparsable {
+            input: input.to_string(),
+            reason: e.to_string(),
+        }
+    })?;
+
+    if date > now
+        && let Some(suggestion) = past_suggestion(&normalized)
+    {
+        return Err(DateError::Ambiguous {
+            input: input.to_string(),
+            resolved: date.format(&Rfc3339).unwrap_or_else(|_| date.to_string()),
+            suggestion,
+        });
+    }
+
+    Ok(date)
+}
+
+/// "start of month", "beginning of last week", "end of this year", ...
+///
+/// The end of a period is the instant the next one starts, which is what an
+/// exclusive `--before` wants.
+fn parse_boundary(input: &str, now: OffsetDateTime) -> Option<OffsetDateTime> {
+    let (end, rest) = if let Some(rest) = input
+        .strip_prefix("start of ")
+        .or_else(|| input.strip_prefix("beginning of "))
+    {
+        (false, rest)
+    } else {
+        (true, input.strip_prefix("end of ")?)
+    };
+
+    let rest = rest.strip_prefix("the ").unwrap_or(rest);
+
+    let (shift, period) = match rest.split_once(' ') {
+        Some(("this", period)) => (0, period),
+        Some(("last" | "previous", period)) => (-1, period),
+        Some(("next", period)) => (1, period),
+        Some(_) => return None,
+        None => match rest {
+            "today" => (0, "day"),
+            "yesterday" => (-1, "day"),
+            "tomorrow" => (1, "day"),
+            period => (0, period),
+        },
+    };
+
+    let period = match period {
+        "day" => Period::Day,
+        "week" => Period::Week,
+        "month" => Period::Month,
+        "year" => Period::Year,
+        _ => return None,
+    };
+
+    let start = shift_period(period_start(period, now), period, shift);
+
+    Some(if end {
+        shift_period(start, period, 1)
+    } else {
+        start
+    })
+}
+
+fn period_start(period: Period, now: OffsetDateTime) -> OffsetDateTime {
+    let midnight = now.replace_time(Time::MIDNIGHT);
+
+    match period {
+        Period::Day => midnight,
+        Period::Week => {
+            midnight - Duration::days(i64::from(now.weekday().number_days_from_monday()))
+        }
+        Period::Month => midnight.replace_day(1).expect("every month has a 1st"),
+        Period::Year => midnight.replace_date(
+            Date::from_calendar_date(now.year(), Month::January, 1).expect("valid date"),
+        ),
+    }
+}
+
+/// Move a period start by `by` whole periods.
+fn shift_period(start: OffsetDateTime, period: Period, by: i32) -> OffsetDateTime {
+    match period {
+        Period::Day => start + Duration::days(i64::from(by)),
+        Period::Week => start + Duration::weeks(i64::from(by)),
+        Period::Month => {
+            let months = start.year() * 12 + i32::from(u8::from(start.month())) - 1 + by;
+            let month = Month::try_from((months.rem_euclid(12) + 1) as u8).expect("valid month");
+            start.replace_date(
+                Date::from_calendar_date(months.div_euclid(12), month, 1).expect("valid date"),
+            )
+        }
+        Period::Year => start.replace_date(
+            Date::from_calendar_date(start.year() + by, Month::January, 1).expect("valid date"),
+        ),
+    }
+}
+
+/// If a phrase that resolved to the future was probably meant as the past,
+/// the phrasing that would have said so.
+fn past_suggestion(input: &str) -> Option<String> {
+    if WEEKDAYS.contains(&input) || MONTHS.contains(&input) {
+        return Some(format!("last {input}"));
+    }
+
+    // "2 days", "3 weeks", "1h" and so on, missing their "ago".
+    let starts_with_number = input.starts_with(|c: char| c.is_ascii_digit());
+    let is_time_of_day = input.contains(':') || input.ends_with("am") || input.ends_with("pm");
+    let is_calendar_date = input.contains(['/', '-', '.']);
+
+    (starts_with_number && !is_time_of_day && !is_calendar_date).then(|| format!("{input} ago"))
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use time::{Weekday, macros::datetime};
+
+    use super::*;
+
+    // A Thursday.
+    const NOW: OffsetDateTime = datetime!(2026-10-15 12:30 UTC);
+
+    #[rstest]
+    #[case::rfc3339("2024-01-31T10:00:00Z", datetime!(2024-01-31 10:00 UTC))]
+    #[case::weeks_ago("2 weeks ago", datetime!(2026-10-01 12:30 UTC))]
+    #[case::start_of_day("start of today", datetime!(2026-10-15 0:00 UTC))]
+    #[case::start_of_week("start of week", datetime!(2026-10-12 0:00 UTC))]
+    #[case::start_of_month("start of month", datetime!(2026-10-01 0:00 UTC))]
+    #[case::beginning_of_the_month("Beginning of the month", datetime!(2026-10-01 0:00 UTC))]
+    #[case::start_of_last_month("start of last month", datetime!(2026-09-01 0:00 UTC))]
+    #[case::end_of_last_month("end of last month", datetime!(2026-10-01 0:00 UTC))]
+    #[case::end_of_month("end of month", datetime!(2026-11-01 0:00 UTC))]
+    #[case::start_of_next_month("start of next month", datetime!(2026-11-01 0:00 UTC))]
+    #[case::start_of_year("start of year", datetime!(2026-01-01 0:00 UTC))]
+    #[case::end_of_last_year("end of last year", datetime!(2026-01-01 0:00 UTC))]
+    #[case::end_of_yesterday("end of yesterday", datetime!(2026-10-15 0:00 UTC))]
+    fn parses(#[case] input: &str, #[case] expected: OffsetDateTime) {
+        assert_eq!(parse_date(input, NOW, Dialect::Uk).unwrap(), expected);
+    }
+
+    #[test]
+    fn last_weekday_is_in_the_past() {
+        let date = parse_date("last tuesday", NOW, Dialect::Uk).unwrap();
+        assert!(date < NOW);
+        assert_eq!(date.weekday(), Weekday::Tuesday);
+    }
+
+    #[test]
+    fn boundaries_use_the_offset_of_now() {
+        let now = datetime!(2026-10-01 01:00 +02:00);
+        let date = parse_date("start of month", now, Dialect::Uk).unwrap();
+        assert_eq!(date, datetime!(2026-10-01 0:00 +02:00));
+    }
+
+    #[test]
+    fn dialect_decides_numeric_dates() {
+        let uk = parse_date("01/04/2021", NOW, Dialect::Uk).unwrap();
+        let us = parse_date("01/04/2021", NOW, Dialect::Us).unwrap();
+        assert_eq!(uk.month(), Month::April);
+        assert_eq!(us.month(), Month::January);
+    }
+
+    #[rstest]
+    #[case::bare_weekday("tuesday", "last tuesday")]
+    #[case::bare_month("december", "last december")]
+    #[case::missing_ago("2 days", "2 days ago")]
+    fn future_looking_phrases_are_ambiguous(#[case] input: &str, #[case] expected: &str) {
+        match parse_date(input, NOW, Dialect::Uk) {
+            Err(DateError::Ambiguous { suggestion, .. }) => assert_eq!(suggestion, expected),
+            other => panic!("expected {input:?} to be ambiguous, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn explicit_future_is_allowed() {
+        assert!(parse_date("tomorrow", NOW, Dialect::Uk).unwrap() > NOW);
+    }
+
+    #[test]
+    fn nonsense_is_unparsable() {
+        let err = parse_date("not a date", NOW, Dialect::Uk).unwrap_err();
+        assert!(matches!(err, DateError::Unparsable { .. }));
+        assert!(err.to_string().contains("start of month"));
+    }
+}
@@ -0,0 +1,193 @@
+//! Failures the client can name, so the CLI and TUI can say what to do about them rather
+//! than print a chain of causes.
+//!
+//! Most of the library returns `eyre` reports. Errors raised here go into those reports as
+//! a [`ClientError`], and the database and network errors that have a clear cause are
+//! recognised on the way out by [`ClientError::find`].
+
+use eyre::Report;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ClientError {
+    #[error("the database is locked by another process")]
+    DbLocked,
+
+    #[error("could not decrypt data with this machine's encryption key")]
+    DecryptFailed,
+
+    #[error("timed out waiting for {url}")]
+    NetworkTimeout { url: String },
+
+    #[error("the database has migration {version}, which this version of atuin doesn't know")]
+    SchemaMismatch { version: i64 },
+
+    #[error("the sync server runs an older, incompatible version of atuin")]
+    VersionMismatch,
+
+    #[error("invalid login details")]
+    InvalidCredentials,
+
+    #[error("rate limited by the sync server")]
+    RateLimited,
+}
+
+impl ClientError {
+    /// A few words on what went wrong, for a heading.
+    pub fn title(&self) -> &'static str {
+        match self {
+            ClientError::DbLocked => "Database locked",
+            ClientError::DecryptFailed => "Could not decrypt",
+            ClientError::NetworkTimeout { .. } => "Network timeout",
+            ClientError::SchemaMismatch { .. } => "Database from a newer atuin",
+            ClientError::VersionMismatch => "Server version mismatch",
+            ClientError::InvalidCredentials => "Invalid login",
+            ClientError::RateLimited => "Rate limited",
+        }
+    }
+
+    /// What the user can do about it.
+    pub fn hint(&self) -> String {
+        match self {
+            ClientError::DbLocked => "Another atuin process held the database for longer than \
+                 `local_timeout` allows. Try again, or raise `local_timeout` in your config if \
+                 this keeps happening while many shells are busy."
+                .to_string(),
+            ClientError::DecryptFailed => "Your encryption key doesn't match the one this data \
+                 was written with. Run `atuin key` on a machine that syncs correctly, then \
+                 `atuin store rekey <key>` here."
+                .to_string(),
+            ClientError::NetworkTimeout { url } => format!(
+                "{url} didn't answer in time. Check your connection, or raise \
+                 `network_timeout` in your config if the server is just slow."
+            ),
+            ClientError::SchemaMismatch { version } => format!(
+                "A newer version of atuin upgraded this database (to migration {version}). \
+                 Upgrade atuin again, or run `atuin migrations status` to see the backups \
+                 taken before each migration."
+            ),
+            ClientError::VersionMismatch => "The sync server needs to run a newer version of \
+                 atuin. Upgrade the server, or get in touch with whoever runs it."
+                .to_string(),
+            ClientError::InvalidCredentials => "Your session has expired or your login is \
+                 wrong. Run `atuin login` again."
+                .to_string(),
+            ClientError::RateLimited => {
+                "The sync server is limiting requests. Wait a little, then try again.".to_string()
+            }
+        }
+    }
+
+    /// The first failure in `report` that has a name, if there is one.
+    pub fn find(report: &Report) -> Option<Self> {
+        report.chain().find_map(|cause| {
+            if let Some(e) = cause.downcast_ref::<ClientError>() {
+                return Some(e.clone());
+            }
+            if let Some(e) = cause.downcast_ref::<sqlx::Error>() {
+                return Self::from_sqlx(e);
+            }
+            #[cfg(feature = "reqwest")]
+            if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+                return Self::from_reqwest(e);
+            }
+            None
+        })
+    }
+
+    pub fn from_sqlx(e: &sqlx::Error) -> Option<Self> {
+        match e {
+            // SQLITE_BUSY and SQLITE_LOCKED, and their extended codes
+            sqlx::Error::Database(e) => e
+                .code()
+                .and_then(|code| code.parse::<i32>().ok())
+                .filter(|code| matches!(code & 0xff, 5 | 6))
+                .map(|_| ClientError::DbLocked),
+            sqlx::Error::Migrate(e) => match **e {
+                sqlx::migrate::MigrateError::VersionMissing(version) => {
+                    Some(ClientError::SchemaMismatch { version })
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "reqwest")]
+    pub fn from_reqwest(e: &reqwest::Error) -> Option<Self> {
+        e.is_timeout().then(|| ClientError::NetworkTimeout {
+            url: e
+                .url()
+                .map_or_else(|| "the server".to_string(), ToString::to_string),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use eyre::WrapErr;
+
+    use super::*;
+
+    #[test]
+    fn found_through_context() {
+        let report = Err::<(), _>(ClientError::RateLimited)
+            .wrap_err("failed to sync")
+            .unwrap_err();
+
+        assert_eq!(ClientError::find(&report), Some(ClientError::RateLimited));
+        assert_eq!(ClientError::find(&eyre::eyre!("something else")), None);
+    }
+
+    #[test]
+    fn unknown_migrations_mean_a_newer_schema() {
+        let e = sqlx::Error::Migrate(Box::new(sqlx::migrate::MigrateError::VersionMissing(
+            20300101000000,
+        )));
+
+        assert_eq!(
+            ClientError::find(&Report::new(e)),
+            Some(ClientError::SchemaMismatch {
+                version: 20300101000000
+            })
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn busy_databases_are_locked() {
+        use std::time::Duration;
+
+        use sqlx::{
+            Connection,
+            sqlite::{SqliteConnectOptions, SqliteConnection},
+        };
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("locked.db");
+        let opts = SqliteConnectOptions::new()
+            .filename(&path)
+            .create_if_missing(true)
+            .busy_timeout(Duration::ZERO);
+
+        let mut holder = SqliteConnection::connect_with(&opts).await.unwrap();
+        sqlx::query("create table t (x)")
+            .execute(&mut holder)
+            .await
+            .unwrap();
+        sqlx::query("begin exclusive")
+            .execute(&mut holder)
+            .await
+            .unwrap();
+
+        let mut waiter = SqliteConnection::connect_with(&opts).await.unwrap();
+        let e = sqlx::query("insert into t values (1)")
+            .execute(&mut waiter)
+            .await
+            .unwrap_err();
+
+        assert_eq!(ClientError::from_sqlx(&e), Some(ClientError::DbLocked));
+        assert_eq!(
+            ClientError::find(&Report::new(e).wrap_err("could not save history")),
+            Some(ClientError::DbLocked)
+        );
+    }
+}
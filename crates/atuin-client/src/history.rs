@@ -1,19 +1,22 @@
 use rmp::decode::{self, Bytes};
 use rmp::encode;
 use std::env;
+use std::sync::OnceLock;
 
 use atuin_common::record::DecryptedData;
 use atuin_common::utils::{normalize_optional_string, uuid_v7};
 
 use eyre::{Result, bail};
 
+use crate::container::detect_container;
 use crate::secrets::SECRET_PATTERNS_RE;
 use crate::settings::Settings;
 use crate::utils::get_host_user;
 use crate::utils::rmp::{DecodeError, EncodeError, read_optional, read_string, write_optional};
-use time::OffsetDateTime;
+use time::{Duration, OffsetDateTime};
 
 pub(crate) mod builder;
+pub mod output;
 pub mod store;
 
 /// Known AI agent author values. Used to expand `$all-agent` and `$all-user` filters.
@@ -34,10 +37,20 @@ pub fn author_matches_filters(author: &str, filters: &[String]) -> bool {
         })
 }
 
+/// The machine part of a history entry's `host:user` hostname.
+pub fn host_of(hostname: &str) -> &str {
+    hostname.split_once(':').map_or(hostname, |(host, _)| host)
+}
+
 pub const HISTORY_TAG: &str = "history";
 const HISTORY_AUTHOR_ENV: &str = "ATUIN_HISTORY_AUTHOR";
 const HISTORY_INTENT_ENV: &str = "ATUIN_HISTORY_INTENT";
 
+/// How far ahead of the local clock an entry's timestamp can be before it's
+/// treated as bogus. Generous, since entries synced from other machines can
+/// legitimately be a little ahead.
+pub const MAX_CLOCK_SKEW: Duration = Duration::days(1);
+
 #[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, derive_more::Display)]
 #[display("{}", self.name())]
 #[repr(u16)]
@@ -136,6 +149,57 @@ pub struct History {
     pub shell: Option<String>,
 }
 
+/// The values an entry arrived with, for whichever of them [`History::clamp`]
+/// had to replace.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RawValues {
+    pub timestamp: Option<OffsetDateTime>,
+    pub duration: Option<i64>,
+}
+
+/// The account that ran an entry's command, as the OS knows it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Audit {
+    pub user: String,
+    pub uid: Option<u32>,
+}
+
+impl Audit {
+    /// The account running this process. Unlike the hostname's user, no environment variable
+    /// changes it.
+    pub fn current() -> Self {
+        #[cfg(unix)]
+        let uid = Some(rustix::process::getuid().as_raw());
+        #[cfg(not(unix))]
+        let uid = None;
+
+        Self {
+            user: whoami::username().unwrap_or_else(|_| "unknown-user".to_string()),
+            uid,
+        }
+    }
+}
+
+/// Where an entry was run: the account, and the container as `runtime:name`, if any.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Origin {
+    pub audit: Audit,
+    pub container: Option<String>,
+}
+
+impl Origin {
+    /// This process's, worked out the first time it's asked for, as neither changes while it
+    /// runs.
+    pub fn current() -> &'static Self {
+        static CURRENT: OnceLock<Origin> = OnceLock::new();
+
+        CURRENT.get_or_init(|| Self {
+            audit: Audit::current(),
+            container: detect_container(),
+        })
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow)]
 pub struct HistoryStats {
     /// The command that was ran after this one in the session
@@ -154,6 +218,46 @@ pub struct HistoryStats {
     pub day_of_week: Vec<(String, i64)>,
 
     pub duration_over_time: Vec<(String, i64)>,
+
+    /// What the command printed, if it was run through `atuin run`.
+    #[sqlx(skip)]
+    pub output: Option<output::CapturedOutput>,
+
+    /// Whether the entry has reached the sync server. The database can't tell, so this is left
+    /// for callers that know when this machine last synced.
+    #[sqlx(skip)]
+    pub sync: Option<SyncStatus>,
+}
+
+/// Whether an entry has been sent to the sync server, as far as this machine knows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncStatus {
+    /// Sync isn't set up here
+    Off,
+    /// Run since this machine last synced
+    Pending,
+    /// Run before this machine last synced, so sent then, or brought here by a sync
+    Synced,
+}
+
+impl SyncStatus {
+    /// The status of `history`, given when this machine last synced, or `None` if it isn't
+    /// logged in to a sync server.
+    pub fn of(history: &History, last_sync: Option<OffsetDateTime>) -> Self {
+        match last_sync {
+            None => Self::Off,
+            Some(last_sync) if history.timestamp <= last_sync => Self::Synced,
+            Some(_) => Self::Pending,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Off => "sync isn't set up",
+            Self::Pending => "waiting for the next sync",
+            Self::Synced => "synced",
+        }
+    }
 }
 
 impl History {
@@ -467,11 +571,37 @@ impl History {
     }
 
     pub fn should_save(&self, settings: &Settings) -> bool {
+        let secret = settings.secrets_filter && SECRET_PATTERNS_RE.is_match(&self.command);
+        // Append-only mode records everything it can, but not secrets, which it could never
+        // remove again
+        if settings.compliance.append_only {
+            return !(self.command.is_empty() || secret);
+        }
+
         !(self.command.starts_with(' ')
             || self.command.is_empty()
             || settings.history_filter.is_match(&self.command)
             || settings.cwd_filter.is_match(&self.cwd)
-            || (settings.secrets_filter && SECRET_PATTERNS_RE.is_match(&self.command)))
+            || secret)
+    }
+
+    /// Replace values nothing downstream can make sense of: durations below
+    /// -1 become unknown (-1), and timestamps more than [`MAX_CLOCK_SKEW`]
+    /// ahead of `now` become `now`. Returns the originals of anything that
+    /// was replaced.
+    pub fn clamp(&mut self, now: OffsetDateTime) -> Option<RawValues> {
+        let mut raw = RawValues::default();
+
+        if self.duration < -1 {
+            raw.duration = Some(self.duration);
+            self.duration = -1;
+        }
+        if self.timestamp > now + MAX_CLOCK_SKEW {
+            raw.timestamp = Some(self.timestamp);
+            self.timestamp = now;
+        }
+
+        (raw != RawValues::default()).then_some(raw)
     }
 }
 
@@ -485,7 +615,7 @@ mod tests {
         settings::Settings,
     };
 
-    use super::{History, author_matches_filters, is_known_agent};
+    use super::{History, MAX_CLOCK_SKEW, RawValues, author_matches_filters, is_known_agent};
 
     // Test that we don't save history where necessary
     #[test]
@@ -546,6 +676,32 @@ mod tests {
         assert!(!with_psql.should_save(&settings));
     }
 
+    #[test]
+    fn append_only_records_everything_but_secrets() {
+        let mut settings = Settings {
+            history_filter: RegexSet::new(["^psql"]).unwrap(),
+            ..Settings::utc()
+        };
+        settings.compliance.append_only = true;
+
+        let entry = |command: &str| -> History {
+            History::capture()
+                .timestamp(time::OffsetDateTime::now_utc())
+                .command(command)
+                .cwd("/")
+                .build()
+                .into()
+        };
+
+        assert!(entry(" echo bar").should_save(&settings));
+        assert!(entry("psql").should_save(&settings));
+        assert!(!entry("").should_save(&settings));
+        assert!(
+            !entry("curl foo.com/bar?key=sk_test_1234567890abcdefghijklmnop")
+                .should_save(&settings)
+        );
+    }
+
     #[test]
     fn known_agents_include_pi() {
         assert!(is_known_agent("pi"));
@@ -576,6 +732,36 @@ mod tests {
         assert!(stripe_key.should_save(&settings));
     }
 
+    #[test]
+    fn clamp_replaces_bogus_values() {
+        let now = datetime!(2024-06-01 12:00:00 +00:00);
+        let entry = |timestamp, duration| -> History {
+            History::import()
+                .timestamp(timestamp)
+                .command("ls")
+                .duration(duration)
+                .build()
+                .into()
+        };
+
+        // Unknown durations and a little clock skew are left alone.
+        let mut fine = entry(now + time::Duration::hours(1), -1);
+        assert_eq!(fine.clamp(now), None);
+        assert_eq!(fine.duration, -1);
+
+        let future = now + MAX_CLOCK_SKEW + time::Duration::days(365);
+        let mut bogus = entry(future, -5_000);
+        assert_eq!(
+            bogus.clamp(now),
+            Some(RawValues {
+                timestamp: Some(future),
+                duration: Some(-5_000),
+            })
+        );
+        assert_eq!(bogus.timestamp, now);
+        assert_eq!(bogus.duration, -1);
+    }
+
     #[test]
     fn test_serialize_deserialize() {
         let history = History {
@@ -0,0 +1,142 @@
+//! Output captured by `atuin run`, stored compressed alongside its entry.
+
+use std::io::{self, Read, Write};
+
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+
+/// The combined stdout and stderr of a command, as it was shown.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CapturedOutput {
+    pub text: String,
+
+    /// How much output the command actually wrote, in bytes.
+    pub total_bytes: u64,
+
+    /// Whether the middle of the output was dropped to stay under the cap.
+    pub truncated: bool,
+}
+
+impl CapturedOutput {
+    pub fn compress(&self) -> io::Result<Vec<u8>> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(self.text.as_bytes())?;
+        encoder.finish()
+    }
+
+    pub fn decompress(data: &[u8], total_bytes: u64, truncated: bool) -> io::Result<Self> {
+        let mut text = String::new();
+        GzDecoder::new(data).read_to_string(&mut text)?;
+
+        Ok(Self {
+            text,
+            total_bytes,
+            truncated,
+        })
+    }
+}
+
+/// Collects output as it streams past, keeping at most `max_bytes` of it: the
+/// first half, and a rolling window of the last half.
+#[derive(Debug)]
+pub struct OutputBuffer {
+    head: Vec<u8>,
+    tail: Vec<u8>,
+    half: usize,
+    total: u64,
+}
+
+impl OutputBuffer {
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            head: Vec::new(),
+            tail: Vec::new(),
+            half: max_bytes / 2,
+            total: 0,
+        }
+    }
+
+    pub fn push(&mut self, mut bytes: &[u8]) {
+        self.total += bytes.len() as u64;
+
+        let room = self.half.saturating_sub(self.head.len());
+        let taken = room.min(bytes.len());
+        self.head.extend_from_slice(&bytes[..taken]);
+        bytes = &bytes[taken..];
+
+        if bytes.is_empty() {
+            return;
+        }
+
+        // Only compact once the tail has doubled, so pushes stay cheap.
+        self.tail.extend_from_slice(bytes);
+        if self.tail.len() > self.half * 2 {
+            self.tail.drain(..self.tail.len() - self.half);
+        }
+    }
+
+    pub fn finish(mut self) -> CapturedOutput {
+        if self.tail.len() > self.half {
+            self.tail.drain(..self.tail.len() - self.half);
+        }
+
+        let dropped = self.total - (self.head.len() + self.tail.len()) as u64;
+
+        let mut text = String::from_utf8_lossy(&self.head).into_owned();
+        if dropped > 0 {
+            text.push_str(&format!("\n[... {dropped} bytes omitted ...]\n"));
+        }
+        text.push_str(&String::from_utf8_lossy(&self.tail));
+
+        CapturedOutput {
+            text,
+            total_bytes: self.total,
+            truncated: dropped > 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_output_is_kept_whole() {
+        let mut buffer = OutputBuffer::new(64);
+        buffer.push(b"hello ");
+        buffer.push(b"world\n");
+
+        let output = buffer.finish();
+        assert_eq!(output.text, "hello world\n");
+        assert_eq!(output.total_bytes, 12);
+        assert!(!output.truncated);
+    }
+
+    #[test]
+    fn large_output_keeps_head_and_tail() {
+        let mut buffer = OutputBuffer::new(8);
+        for chunk in [b"abcd".as_slice(), b"efgh", b"ijkl", b"mnop"] {
+            buffer.push(chunk);
+        }
+
+        let output = buffer.finish();
+        assert_eq!(output.text, "abcd\n[... 8 bytes omitted ...]\nmnop");
+        assert_eq!(output.total_bytes, 16);
+        assert!(output.truncated);
+    }
+
+    #[test]
+    fn round_trips_through_compression() {
+        let output = CapturedOutput {
+            text: "line\n".repeat(1000),
+            total_bytes: 5000,
+            truncated: false,
+        };
+
+        let compressed = output.compress().unwrap();
+        assert!(compressed.len() < output.text.len());
+        assert_eq!(
+            CapturedOutput::decompress(&compressed, 5000, false).unwrap(),
+            output
+        );
+    }
+}
@@ -0,0 +1,452 @@
+// A synced registry of the machines that share a record store.
+// Record store hosts are only known by a random ID, and history only by the `host:user`
+// hostname it was run under. Each machine describes itself here, and any machine can give
+// any other a friendly name, so that they can be shown as something more helpful.
+
+use std::collections::{BTreeMap, HashMap};
+
+use atuin_common::record::{DecryptedData, Host, HostId, Record};
+use eyre::{Result, bail, ensure, eyre};
+use serde::Serialize;
+
+use crate::{
+    history::host_of,
+    record::{encryption::PASETO_V4, sqlite_store::SqliteStore, store::Store},
+    utils::get_hostname,
+};
+
+pub const HOST_TAG: &str = "host";
+const HOST_VERSION: &str = "v0";
+const HOST_FIELD_MAX_LEN: usize = 1000;
+
+/// What a machine says about itself.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HostDetails {
+    /// The host part of the hostname its history is recorded under
+    pub hostname: String,
+    pub os: String,
+    pub shell: String,
+}
+
+impl HostDetails {
+    /// Details of the machine we're running on
+    pub fn current() -> Self {
+        let shell = std::env::var("SHELL")
+            .ok()
+            .and_then(|path| {
+                std::path::Path::new(&path)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+            })
+            .or_else(|| std::env::var("ATUIN_SHELL").ok())
+            .unwrap_or_default();
+
+        HostDetails {
+            hostname: get_hostname(),
+            os: sysinfo::System::long_os_version()
+                .unwrap_or_else(|| std::env::consts::OS.to_string()),
+            shell,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HostRecord {
+    /// A machine describing itself. Only ever written by the machine it describes
+    Register(HostDetails),
+    /// A friendly name for a machine, or `None` to go back to showing its hostname
+    Rename(HostId, Option<String>),
+}
+
+impl HostRecord {
+    pub fn serialize(&self) -> Result<DecryptedData> {
+        use rmp::encode;
+
+        let mut output = vec![];
+
+        match self {
+            HostRecord::Register(details) => {
+                encode::write_u8(&mut output, 0)?; // register
+                encode::write_array_len(&mut output, 3)?; // 3 fields
+
+                encode::write_str(&mut output, &details.hostname)?;
+                encode::write_str(&mut output, &details.os)?;
+                encode::write_str(&mut output, &details.shell)?;
+            }
+            HostRecord::Rename(host, name) => {
+                encode::write_u8(&mut output, 1)?; // rename
+                encode::write_array_len(&mut output, 2)?; // 2 fields
+
+                encode::write_str(&mut output, &host.0.as_simple().to_string())?;
+                encode::write_str(&mut output, name.as_deref().unwrap_or_default())?;
+            }
+        }
+
+        Ok(DecryptedData(output))
+    }
+
+    pub fn deserialize(data: &DecryptedData, version: &str) -> Result<Self> {
+        use rmp::decode;
+
+        fn error_report<E: std::fmt::Debug>(err: E) -> eyre::Report {
+            eyre!("{err:?}")
+        }
+
+        if version != HOST_VERSION {
+            bail!("unknown version {version:?}");
+        }
+
+        let mut bytes = decode::Bytes::new(&data.0);
+        let record_type = decode::read_u8(&mut bytes).map_err(error_report)?;
+        let nfields = decode::read_array_len(&mut bytes).map_err(error_report)?;
+        let bytes = bytes.remaining_slice();
+
+        let (record, bytes) = match record_type {
+            // register
+            0 => {
+                ensure!(nfields == 3, "wrong number of entries in v0 host register");
+
+                let (hostname, bytes) = decode::read_str_from_slice(bytes).map_err(error_report)?;
+                let (os, bytes) = decode::read_str_from_slice(bytes).map_err(error_report)?;
+                let (shell, bytes) = decode::read_str_from_slice(bytes).map_err(error_report)?;
+
+                let details = HostDetails {
+                    hostname: hostname.to_owned(),
+                    os: os.to_owned(),
+                    shell: shell.to_owned(),
+                };
+
+                (HostRecord::Register(details), bytes)
+            }
+
+            // rename
+            1 => {
+                ensure!(nfields == 2, "wrong number of entries in v0 host rename");
+
+                let (host, bytes) = decode::read_str_from_slice(bytes).map_err(error_report)?;
+                let (name, bytes) = decode::read_str_from_slice(bytes).map_err(error_report)?;
+
+                let host = HostId(host.parse()?);
+                let name = (!name.is_empty()).then(|| name.to_owned());
+
+                (HostRecord::Rename(host, name), bytes)
+            }
+
+            n => bail!("unknown host record type {n}"),
+        };
+
+        if !bytes.is_empty() {
+            bail!("trailing bytes in encoded host record. malformed");
+        }
+
+        Ok(record)
+    }
+}
+
+/// Everything known about one machine.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HostInfo {
+    pub id: HostId,
+    pub name: Option<String>,
+    /// Unset for machines that have records in the store, but have not described
+    /// themselves (yet)
+    #[serde(flatten)]
+    pub details: Option<HostDetails>,
+}
+
+impl HostInfo {
+    fn new(id: HostId) -> Self {
+        HostInfo {
+            id,
+            name: None,
+            details: None,
+        }
+    }
+
+    /// The friendly name if it has one, otherwise its hostname, or failing that the start of
+    /// its ID
+    pub fn display_name(&self) -> String {
+        match (&self.name, &self.details) {
+            (Some(name), _) => name.clone(),
+            (None, Some(details)) => details.hostname.clone(),
+            (None, None) => self.id.0.as_simple().to_string()[..8].to_string(),
+        }
+    }
+}
+
+/// Find a host by its name, hostname, ID, or a prefix of its ID.
+pub fn find_host<'a>(hosts: &'a [HostInfo], query: &str) -> Result<&'a HostInfo> {
+    let query = query.trim();
+    let id = query.replace('-', "").to_lowercase();
+
+    let named = |host: &&HostInfo| {
+        host.name
+            .as_deref()
+            .is_some_and(|name| name.eq_ignore_ascii_case(query))
+    };
+    let hostname = |host: &&HostInfo| {
+        host.details
+            .as_ref()
+            .is_some_and(|details| details.hostname.eq_ignore_ascii_case(query))
+    };
+    let id_prefix =
+        |host: &&HostInfo| !id.is_empty() && host.id.0.as_simple().to_string().starts_with(&id);
+
+    // A name the user gave beats a hostname, which beats an ID
+    for matches in [&named as &dyn Fn(&&HostInfo) -> bool, &hostname, &id_prefix] {
+        match hosts.iter().filter(matches).collect::<Vec<_>>().as_slice() {
+            [] => {}
+            [host] => return Ok(host),
+            many => bail!(
+                "{query:?} matches {} hosts, use one of their IDs instead: {}",
+                many.len(),
+                many.iter()
+                    .map(|host| host.id.0.as_hyphenated().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+
+    bail!("no host called {query:?}, see `atuin host list`")
+}
+
+/// Friendly names for the machines history was run on, by their hostname.
+#[derive(Debug, Clone, Default)]
+pub struct HostNames(HashMap<String, String>);
+
+impl HostNames {
+    /// What to show for `hostname`, either a history entry's `host:user` or just the host part
+    pub fn display<'a>(&'a self, hostname: &'a str) -> &'a str {
+        let host = host_of(hostname);
+
+        if self.0.is_empty() {
+            return host;
+        }
+
+        self.0
+            .get(&host.to_lowercase())
+            .map_or(host, String::as_str)
+    }
+}
+
+impl FromIterator<HostInfo> for HostNames {
+    fn from_iter<I: IntoIterator<Item = HostInfo>>(hosts: I) -> Self {
+        HostNames(
+            hosts
+                .into_iter()
+                .filter_map(|host| Some((host.details?.hostname.to_lowercase(), host.name?)))
+                .collect(),
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HostStore {
+    pub store: SqliteStore,
+    pub host_id: HostId,
+    pub encryption_key: [u8; 32],
+}
+
+impl HostStore {
+    pub fn new(store: SqliteStore, host_id: HostId, encryption_key: [u8; 32]) -> HostStore {
+        HostStore {
+            store,
+            host_id,
+            encryption_key,
+        }
+    }
+
+    async fn push(&self, record: HostRecord) -> Result<()> {
+        let bytes = record.serialize()?;
+
+        let idx = self
+            .store
+            .last(self.host_id, HOST_TAG)
+            .await?
+            .map_or(0, |entry| entry.idx + 1);
+
+        let record = Record::builder()
+            .host(Host::new(self.host_id))
+            .version(HOST_VERSION.to_string())
+            .tag(HOST_TAG.to_string())
+            .idx(idx)
+            .data(bytes)
+            .build();
+
+        self.store
+            .push(&record.encrypt::<PASETO_V4>(&self.encryption_key))
+            .await
+    }
+
+    /// Describe this machine to the others, if it hasn't already or anything has changed.
+    /// Returns whether a record was written.
+    pub async fn register(&self) -> Result<bool> {
+        self.register_as(HostDetails::current()).await
+    }
+
+    async fn register_as(&self, details: HostDetails) -> Result<bool> {
+        let hosts = self.hosts().await?;
+        let known = hosts
+            .iter()
+            .find(|host| host.id == self.host_id)
+            .and_then(|host| host.details.as_ref());
+
+        if known == Some(&details) {
+            return Ok(false);
+        }
+
+        self.push(HostRecord::Register(details)).await?;
+
+        Ok(true)
+    }
+
+    /// Give `host` a friendly name, or with `None` go back to showing its hostname
+    pub async fn rename(&self, host: HostId, name: Option<&str>) -> Result<()> {
+        let name = name.map(str::trim).filter(|name| !name.is_empty());
+
+        if let Some(name) = name
+            && name.len() > HOST_FIELD_MAX_LEN
+        {
+            bail!("host name too long: max len {HOST_FIELD_MAX_LEN} bytes");
+        }
+
+        self.push(HostRecord::Rename(host, name.map(str::to_owned)))
+            .await
+    }
+
+    /// Every machine in the registry or with records in the store, ordered by ID
+    pub async fn hosts(&self) -> Result<Vec<HostInfo>> {
+        let mut hosts = BTreeMap::new();
+
+        // this is sorted, oldest to newest, so later renames win
+        let tagged = self.store.all_tagged(HOST_TAG).await?;
+
+        for record in tagged {
+            let version = record.version.clone();
+
+            // Skip records we can't decrypt or decode, rather than failing the entire build.
+            let decoded = record
+                .decrypt::<PASETO_V4>(&self.encryption_key)
+                .and_then(|decrypted| {
+                    let record = HostRecord::deserialize(&decrypted.data, &version)?;
+                    Ok((decrypted.host.id, record))
+                });
+
+            let (author, record) = match decoded {
+                Ok(decoded) => decoded,
+                Err(e) => {
+                    tracing::warn!("failed to decode host record, skipping: {e}");
+                    continue;
+                }
+            };
+
+            match record {
+                HostRecord::Register(details) => {
+                    hosts
+                        .entry(author)
+                        .or_insert_with(|| HostInfo::new(author))
+                        .details = Some(details);
+                }
+                HostRecord::Rename(host, name) => {
+                    hosts
+                        .entry(host)
+                        .or_insert_with(|| HostInfo::new(host))
+                        .name = name;
+                }
+            }
+        }
+
+        for host in self.store.status().await?.hosts.into_keys() {
+            hosts.entry(host).or_insert_with(|| HostInfo::new(host));
+        }
+
+        Ok(hosts.into_values().collect())
+    }
+
+    /// Friendly names for showing where history was run
+    pub async fn names(&self) -> Result<HostNames> {
+        Ok(self.hosts().await?.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use atuin_common::utils::uuid_v7;
+
+    use super::*;
+    use crate::settings::test_local_timeout;
+
+    fn details(hostname: &str) -> HostDetails {
+        HostDetails {
+            hostname: hostname.to_owned(),
+            os: "Linux (Arch Linux)".to_owned(),
+            shell: "zsh".to_owned(),
+        }
+    }
+
+    #[test]
+    fn encode_decode() {
+        let records = [
+            HostRecord::Register(details("laptop")),
+            HostRecord::Rename(HostId(uuid_v7()), Some("work laptop".to_owned())),
+            HostRecord::Rename(HostId(uuid_v7()), None),
+        ];
+
+        for record in records {
+            let encoded = record.serialize().unwrap();
+            let decoded = HostRecord::deserialize(&encoded, HOST_VERSION).unwrap();
+
+            assert_eq!(decoded, record);
+        }
+    }
+
+    #[tokio::test]
+    async fn registry_tracks_details_and_names() {
+        let store = SqliteStore::new(":memory:", test_local_timeout())
+            .await
+            .unwrap();
+        let key = [0u8; 32];
+
+        let laptop = HostStore::new(store.clone(), HostId(uuid_v7()), key);
+        let desktop = HostStore::new(store, HostId(uuid_v7()), key);
+
+        assert!(laptop.register_as(details("laptop")).await.unwrap());
+        assert!(!laptop.register_as(details("laptop")).await.unwrap());
+        assert!(desktop.register_as(details("desktop")).await.unwrap());
+
+        // any machine can name any other
+        desktop
+            .rename(laptop.host_id, Some("work laptop"))
+            .await
+            .unwrap();
+
+        let hosts = laptop.hosts().await.unwrap();
+        let host = find_host(&hosts, "laptop").unwrap();
+        assert_eq!(host.id, laptop.host_id);
+        assert_eq!(host.display_name(), "work laptop");
+        assert_eq!(find_host(&hosts, "Work Laptop").unwrap().id, laptop.host_id);
+        assert_eq!(
+            find_host(&hosts, "desktop").unwrap().display_name(),
+            "desktop"
+        );
+
+        let names = laptop.names().await.unwrap();
+        assert_eq!(names.display("LAPTOP:ellie"), "work laptop");
+        assert_eq!(names.display("desktop:ellie"), "desktop");
+
+        laptop.rename(laptop.host_id, None).await.unwrap();
+        let names = laptop.names().await.unwrap();
+        assert_eq!(names.display("laptop:ellie"), "laptop");
+    }
+
+    #[test]
+    fn find_host_by_id_prefix() {
+        let hosts = [HostInfo::new(HostId(uuid_v7()))];
+        let id = hosts[0].id.0.as_hyphenated().to_string();
+
+        assert_eq!(find_host(&hosts, &id[..8]).unwrap().id, hosts[0].id);
+        assert_eq!(find_host(&hosts, &id).unwrap().id, hosts[0].id);
+        assert!(find_host(&hosts, "nope").is_err());
+    }
+}
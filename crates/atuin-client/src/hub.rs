@@ -22,6 +22,8 @@ use atuin_common::{
     url::UrlAppendExt,
 };
 
+use crate::error::ClientError;
+use crate::network::{self, RequestKind};
 use crate::settings::Settings;
 
 static APP_USER_AGENT: &str = concat!("atuin/", env!("CARGO_PKG_VERSION"));
@@ -196,16 +198,17 @@ pub async fn link_account(hub_address: &Url, cli_token: &str) -> Result<()> {
     debug!("Linking CLI account to Hub at {}", hub_address);
 
     ensure_crypto_provider();
-    let client = reqwest::Client::new();
-
-    let resp = client
-        .post(url)
-        .header(USER_AGENT, APP_USER_AGENT)
-        .header(ATUIN_HEADER_VERSION, ATUIN_CARGO_VERSION)
-        .bearer_auth(&hub_token)
-        .json(&serde_json::json!({ "token": cli_token }))
-        .send()
-        .await?;
+    let client = network::client_builder(RequestKind::Auth)?.build()?;
+
+    let resp = network::send(
+        client
+            .post(url)
+            .header(USER_AGENT, APP_USER_AGENT)
+            .header(ATUIN_HEADER_VERSION, ATUIN_CARGO_VERSION)
+            .bearer_auth(&hub_token)
+            .json(&serde_json::json!({ "token": cli_token })),
+    )
+    .await?;
 
     let status = resp.status();
 
@@ -233,7 +236,7 @@ async fn handle_resp_error(resp: reqwest::Response) -> Result<reqwest::Response>
 
     if status == StatusCode::TOO_MANY_REQUESTS {
         error!("Rate limited; please wait before trying again");
-        bail!("Rate limited; please wait before trying again");
+        return Err(ClientError::RateLimited.into());
     }
 
     if !status.is_success() {
@@ -252,16 +255,17 @@ async fn handle_resp_error(resp: reqwest::Response) -> Result<reqwest::Response>
 async fn request_code(address: &Url) -> Result<CliCodeResponse> {
     ensure_crypto_provider();
     let url = address.append_path("auth/cli/code")?;
-    let client = reqwest::Client::new();
+    let client = network::client_builder(RequestKind::Auth)?.build()?;
 
     debug!("Requesting code from Hub at {url}");
 
-    let resp = client
-        .post(url)
-        .header(USER_AGENT, APP_USER_AGENT)
-        .header(ATUIN_HEADER_VERSION, ATUIN_CARGO_VERSION)
-        .send()
-        .await?;
+    let resp = network::send(
+        client
+            .post(url)
+            .header(USER_AGENT, APP_USER_AGENT)
+            .header(ATUIN_HEADER_VERSION, ATUIN_CARGO_VERSION),
+    )
+    .await?;
     let resp = handle_resp_error(resp).await?;
 
     let code_response = resp.json::<CliCodeResponse>().await?;
@@ -272,19 +276,20 @@ async fn request_code(address: &Url) -> Result<CliCodeResponse> {
 async fn verify_code(address: &Url, code: &str) -> Result<CliVerifyResponse> {
     ensure_crypto_provider();
     let mut url = address.append_path("auth/cli/verify")?;
-    let client = reqwest::Client::new();
+    let client = network::client_builder(RequestKind::Auth)?.build()?;
 
     // Logged before the code is appended, so the secret stays out of the logs.
     debug!("Verifying code with Hub at {url}");
 
     url.query_pairs_mut().append_pair("code", code);
 
-    let resp = client
-        .post(url)
-        .header(USER_AGENT, APP_USER_AGENT)
-        .header(ATUIN_HEADER_VERSION, ATUIN_CARGO_VERSION)
-        .send()
-        .await?;
+    let resp = network::send(
+        client
+            .post(url)
+            .header(USER_AGENT, APP_USER_AGENT)
+            .header(ATUIN_HEADER_VERSION, ATUIN_CARGO_VERSION),
+    )
+    .await?;
     let resp = handle_resp_error(resp).await?;
 
     let verify_response = resp.json::<CliVerifyResponse>().await?;
@@ -3,7 +3,7 @@ use std::{path::PathBuf, str};
 use async_trait::async_trait;
 use directories::UserDirs;
 use eyre::{Result, eyre};
-use time::{OffsetDateTime, PrimitiveDateTime, macros::format_description};
+use time::{OffsetDateTime, PrimitiveDateTime, UtcOffset, macros::format_description};
 
 use super::{Importer, Loader, get_histfile_path, unix_byte_lines};
 use crate::history::History;
@@ -12,6 +12,17 @@ use crate::import::read_to_end;
 #[derive(Debug)]
 pub struct Replxx {
     bytes: Vec<u8>,
+    /// The offset of the local time replxx writes
+    offset: UtcOffset,
+}
+
+impl Replxx {
+    /// Read the history's times as being at `offset`, rather than UTC. Set from the configured
+    /// timezone, as the local offset can't be looked up once the runtime has started threads.
+    #[must_use]
+    pub fn local_offset(self, offset: UtcOffset) -> Self {
+        Self { offset, ..self }
+    }
 }
 
 fn default_histpath() -> Result<PathBuf> {
@@ -44,7 +55,10 @@ impl Importer for Replxx {
 
     async fn new() -> Result<Self> {
         let bytes = read_to_end(get_histfile_path(default_histpath)?)?;
-        Ok(Self { bytes })
+        Ok(Self {
+            bytes,
+            offset: UtcOffset::UTC,
+        })
     }
 
     async fn entries(&mut self) -> Result<usize> {
@@ -56,7 +70,7 @@ impl Importer for Replxx {
 
         for b in unix_byte_lines(&self.bytes) {
             let s = std::str::from_utf8(b)?;
-            match try_parse_line_as_timestamp(s) {
+            match try_parse_line_as_timestamp(s, self.offset) {
                 Some(t) => timestamp = t,
                 None => {
                     // replxx uses ETB character (0x17) as line breaker
@@ -75,21 +89,21 @@ impl Importer for Replxx {
     }
 }
 
-fn try_parse_line_as_timestamp(line: &str) -> Option<OffsetDateTime> {
+fn try_parse_line_as_timestamp(line: &str, offset: UtcOffset) -> Option<OffsetDateTime> {
     // replxx history date time format: ### yyyy-mm-dd hh:mm:ss.xxx
     let date_time_str = line.strip_prefix("### ")?;
     let format =
         format_description!("[year]-[month]-[day] [hour]:[minute]:[second].[subsecond digits:3]");
 
     let primitive_date_time = PrimitiveDateTime::parse(date_time_str, format).ok()?;
-    // There is no safe way to get local time offset.
-    // For simplicity let's just assume UTC.
-    Some(primitive_date_time.assume_utc())
+    Some(primitive_date_time.assume_offset(offset))
 }
 
 #[cfg(test)]
 mod test {
 
+    use time::{UtcOffset, macros::offset};
+
     use crate::import::{Importer, tests::TestLoader};
 
     use super::Replxx;
@@ -110,7 +124,10 @@ CREATE TABLE test( stamp DateTime('UTC'))ENGINE = MergeTreePARTITION BY toDat
         .as_bytes()
         .to_owned();
 
-        let replxx = Replxx { bytes };
+        let replxx = Replxx {
+            bytes,
+            offset: UtcOffset::UTC,
+        };
 
         let mut loader = TestLoader::default();
         replxx.load(&mut loader).await.unwrap();
@@ -137,4 +154,26 @@ CREATE TABLE test( stamp DateTime('UTC'))ENGINE = MergeTreePARTITION BY toDat
             "CREATE TABLE test\n( stamp DateTime('UTC'))\nENGINE = MergeTree\nPARTITION BY toDate(stamp)\norder by tuple() as select toDateTime('2020-01-01')+number*60 from numbers(80000);"
         );
     }
+
+    #[tokio::test]
+    async fn reads_local_times_at_the_offset() {
+        let bytes = "### 2024-02-10 22:16:28.302\nselect 1\n"
+            .as_bytes()
+            .to_owned();
+
+        let replxx = Replxx {
+            bytes,
+            offset: UtcOffset::UTC,
+        }
+        .local_offset(offset!(+2));
+
+        let mut loader = TestLoader::default();
+        replxx.load(&mut loader).await.unwrap();
+
+        // 22:16:28 at +02:00 is 20:16:28 UTC
+        assert_eq!(
+            loader.buf[0].timestamp.unix_timestamp(),
+            1707603388 - 2 * 3600
+        );
+    }
 }
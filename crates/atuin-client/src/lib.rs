@@ -14,19 +14,32 @@ pub mod login;
 #[cfg(feature = "sync")]
 pub mod register;
 
+pub mod container;
 pub mod database;
+pub mod date;
 pub mod distro;
 pub mod encryption;
+pub mod error;
 pub mod history;
+pub mod host;
 pub mod import;
 pub mod logout;
 pub mod logs;
 pub mod meta;
+pub mod network;
 pub mod ordering;
 pub mod plugin;
+#[cfg(feature = "prompt")]
+pub mod prompt;
+pub mod read_only;
 pub mod record;
 pub mod secrets;
+pub mod session;
 pub mod settings;
+#[cfg(feature = "synonyms")]
+pub mod synonyms;
 pub mod theme;
+#[cfg(feature = "check-update")]
+pub mod update;
 
 mod utils;
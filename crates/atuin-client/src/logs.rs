@@ -17,6 +17,7 @@ impl FromSettings for FileConfig {
         Some(Self {
             path: PathBuf::from_iter([&settings.dir, &child.file]),
             level: child.level.unwrap_or(settings.level),
+            format: child.format.unwrap_or(settings.format),
             retention_days: child.retention.unwrap_or(settings.retention),
         })
     }
@@ -29,6 +30,7 @@ impl FromSettings for LogConfig {
         Self {
             file: FileConfig::from_settings(settings, child),
             stderr: None,
+            otel: None,
         }
     }
 }
@@ -3,12 +3,16 @@ use std::str::FromStr;
 use std::time::Duration;
 
 use atuin_common::record::HostId;
+use clap::ValueEnum;
 use eyre::{Result, eyre};
 use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePool, SqlitePoolOptions};
 use time::{OffsetDateTime, format_description::well_known::Rfc3339};
 use tokio::sync::OnceCell;
 use uuid::Uuid;
 
+use crate::read_only;
+use crate::settings::FilterMode;
+
 // Filenames for the legacy plain-text files that we migrate from.
 const LEGACY_HOST_ID_FILENAME: &str = "host_id";
 const LEGACY_LAST_SYNC_FILENAME: &str = "last_sync_time";
@@ -18,11 +22,17 @@ const LEGACY_SESSION_FILENAME: &str = "session";
 
 const KEY_HOST_ID: &str = "host_id";
 const KEY_LAST_SYNC: &str = "last_sync_time";
+const KEY_LAST_SYNC_STARTED: &str = "last_sync_started_time";
+const KEY_CLOCK_SKEW: &str = "clock_skew_secs";
 const KEY_LAST_VERSION_CHECK: &str = "last_version_check_time";
 const KEY_LATEST_VERSION: &str = "latest_version";
 const KEY_SESSION: &str = "session";
 const KEY_HUB_SESSION: &str = "hub_session";
 const KEY_FILES_MIGRATED: &str = "files_migrated";
+const KEY_SESSION_FILTER_MODES: &str = "session_filter_modes";
+
+// How many shell sessions' filter modes are kept; the oldest are forgotten first.
+const SESSION_FILTER_MODES_KEPT: usize = 50;
 
 pub struct MetaStore {
     pool: SqlitePool,
@@ -40,6 +50,19 @@ impl MetaStore {
 
         let is_memory = path_str.contains(":memory:");
 
+        if read_only::is_enabled() && !is_memory {
+            let opts = SqliteConnectOptions::from_str(path_str)?.read_only(true);
+            let pool = SqlitePoolOptions::new()
+                .acquire_timeout(Duration::from_secs_f64(timeout))
+                .connect_with(opts)
+                .await?;
+
+            return Ok(Self {
+                pool,
+                cached_host_id: OnceCell::const_new(),
+            });
+        }
+
         if !is_memory
             && !path.exists()
             && let Some(dir) = path.parent()
@@ -126,9 +149,12 @@ impl MetaStore {
                     return Ok(HostId(parsed));
                 }
 
+                // Read-only mode gets by with an ID for this run alone
                 let uuid = atuin_common::utils::uuid_v7();
-                self.set(KEY_HOST_ID, uuid.as_simple().to_string().as_ref())
-                    .await?;
+                if !read_only::is_enabled() {
+                    self.set(KEY_HOST_ID, uuid.as_simple().to_string().as_ref())
+                        .await?;
+                }
 
                 Ok(HostId(uuid))
             })
@@ -151,6 +177,36 @@ impl MetaStore {
         .await
     }
 
+    /// When a background sync was last started, whether or not it succeeded.
+    pub async fn last_sync_started(&self) -> Result<OffsetDateTime> {
+        match self.get(KEY_LAST_SYNC_STARTED).await? {
+            Some(v) => Ok(OffsetDateTime::parse(v.as_str(), &Rfc3339)?),
+            None => Ok(OffsetDateTime::UNIX_EPOCH),
+        }
+    }
+
+    pub async fn save_sync_started_time(&self) -> Result<()> {
+        self.set(
+            KEY_LAST_SYNC_STARTED,
+            OffsetDateTime::now_utc().format(&Rfc3339)?.as_str(),
+        )
+        .await
+    }
+
+    /// How far the sync server's clock was ahead of ours at the last sync, if
+    /// it has been measured.
+    pub async fn clock_skew(&self) -> Result<Option<time::Duration>> {
+        match self.get(KEY_CLOCK_SKEW).await? {
+            Some(v) => Ok(Some(time::Duration::seconds(v.parse()?))),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn save_clock_skew(&self, skew: time::Duration) -> Result<()> {
+        self.set(KEY_CLOCK_SKEW, &skew.whole_seconds().to_string())
+            .await
+    }
+
     pub async fn last_version_check(&self) -> Result<OffsetDateTime> {
         match self.get(KEY_LAST_VERSION_CHECK).await? {
             Some(v) => Ok(OffsetDateTime::parse(v.as_str(), &Rfc3339)?),
@@ -208,6 +264,42 @@ impl MetaStore {
         Ok(self.hub_session_token().await?.is_some())
     }
 
+    // The filter mode each shell session last searched with, newest first, one
+    // `session mode` pair a line.
+
+    pub async fn session_filter_mode(&self, session: &str) -> Result<Option<FilterMode>> {
+        let modes = self
+            .get(KEY_SESSION_FILTER_MODES)
+            .await?
+            .unwrap_or_default();
+
+        Ok(modes
+            .lines()
+            .find_map(|line| line.strip_prefix(session)?.strip_prefix(' '))
+            .and_then(|mode| FilterMode::from_str(mode, false).ok()))
+    }
+
+    pub async fn save_session_filter_mode(&self, session: &str, mode: FilterMode) -> Result<()> {
+        let Some(name) = mode.to_possible_value() else {
+            return Ok(());
+        };
+        let modes = self
+            .get(KEY_SESSION_FILTER_MODES)
+            .await?
+            .unwrap_or_default();
+
+        let others = modes
+            .lines()
+            .filter(|line| line.split(' ').next() != Some(session))
+            .take(SESSION_FILTER_MODES_KEPT - 1);
+        let modes = std::iter::once(format!("{session} {}", name.get_name()))
+            .chain(others.map(str::to_string))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        self.set(KEY_SESSION_FILTER_MODES, &modes).await
+    }
+
     // File migration: on first open, migrate old plain-text files into the database.
     // Old files are left in place for safe downgrades.
 
@@ -317,6 +409,43 @@ mod tests {
         assert_eq!(store.get("foo").await.unwrap(), None);
     }
 
+    #[tokio::test]
+    async fn test_session_filter_modes() {
+        let store = new_test_store().await;
+
+        assert_eq!(store.session_filter_mode("a").await.unwrap(), None);
+
+        store
+            .save_session_filter_mode("a", FilterMode::Directory)
+            .await
+            .unwrap();
+        store
+            .save_session_filter_mode("b", FilterMode::SessionPreload)
+            .await
+            .unwrap();
+        store
+            .save_session_filter_mode("a", FilterMode::Host)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            store.session_filter_mode("a").await.unwrap(),
+            Some(FilterMode::Host)
+        );
+        assert_eq!(
+            store.session_filter_mode("b").await.unwrap(),
+            Some(FilterMode::SessionPreload)
+        );
+
+        for i in 0..SESSION_FILTER_MODES_KEPT {
+            store
+                .save_session_filter_mode(&i.to_string(), FilterMode::Global)
+                .await
+                .unwrap();
+        }
+        assert_eq!(store.session_filter_mode("a").await.unwrap(), None);
+    }
+
     #[tokio::test]
     async fn test_host_id_generation_and_stability() {
         let store = new_test_store().await;
@@ -339,6 +468,21 @@ mod tests {
         assert!(t > OffsetDateTime::UNIX_EPOCH);
     }
 
+    #[tokio::test]
+    async fn test_sync_started_time() {
+        let store = new_test_store().await;
+
+        assert_eq!(
+            store.last_sync_started().await.unwrap(),
+            OffsetDateTime::UNIX_EPOCH
+        );
+
+        // Starting a sync isn't finishing one
+        store.save_sync_started_time().await.unwrap();
+        assert!(store.last_sync_started().await.unwrap() > OffsetDateTime::UNIX_EPOCH);
+        assert_eq!(store.last_sync().await.unwrap(), OffsetDateTime::UNIX_EPOCH);
+    }
+
     #[tokio::test]
     async fn test_version_check_time() {
         let store = new_test_store().await;
@@ -351,6 +495,22 @@ mod tests {
         assert!(t > OffsetDateTime::UNIX_EPOCH);
     }
 
+    #[tokio::test]
+    async fn test_clock_skew() {
+        let store = new_test_store().await;
+
+        assert_eq!(store.clock_skew().await.unwrap(), None);
+
+        store
+            .save_clock_skew(-time::Duration::minutes(3))
+            .await
+            .unwrap();
+        assert_eq!(
+            store.clock_skew().await.unwrap(),
+            Some(-time::Duration::minutes(3))
+        );
+    }
+
     #[tokio::test]
     async fn test_session_crud() {
         let store = new_test_store().await;
@@ -0,0 +1,703 @@
+//! Using the network: whether to at all, and how.
+//!
+//! Before syncing or checking for updates, Atuin makes sure it isn't offline:
+//! either because it was told to be (`--offline`, `offline = true` or
+//! `ATUIN_OFFLINE=true`), or because the sync server can't be reached. A dead
+//! network or a flaky VPN then costs one short connection attempt, instead of
+//! a request waiting out its full timeout.
+//!
+//! It's also where requests get their timeouts and are retried: every HTTP
+//! request Atuin makes goes through [`send`], which retries those that failed
+//! in a way that may not happen again, backing off (with jitter) between
+//! attempts, as set in the `[network]` settings. Clients are built here too,
+//! so that they all go through the configured proxy and trust the configured
+//! certificates (the `[tls]` settings). The sync server may also be reached
+//! over a unix socket, or insist on a client certificate: see [`sync_server`].
+
+use std::sync::RwLock;
+use std::time::Duration;
+
+use rand::Rng;
+use tokio::net::TcpStream;
+use url::Url;
+
+use crate::settings::{Network, Settings, Tls};
+
+/// How long to give the sync server to accept a connection before deciding we're offline
+const PROBE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The settings requests follow, for those made without settings to hand
+static CONFIG: RwLock<Option<Config>> = RwLock::new(None);
+
+/// What a request is for, which decides its timeout
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RequestKind {
+    Sync,
+    Auth,
+    UpdateCheck,
+    Download,
+}
+
+#[derive(Clone, Debug)]
+struct Config {
+    connect_timeout: Duration,
+    timeouts: [Duration; 4],
+    retry: Retry,
+    proxy: String,
+    no_proxy: String,
+    tls: Tls,
+}
+
+impl Config {
+    fn new(settings: &Settings) -> Self {
+        let timeout = |kind| Duration::from_secs(settings.network_timeout_for(kind));
+
+        Self {
+            connect_timeout: Duration::from_secs(settings.network_connect_timeout),
+            timeouts: [
+                timeout(RequestKind::Sync),
+                timeout(RequestKind::Auth),
+                timeout(RequestKind::UpdateCheck),
+                timeout(RequestKind::Download),
+            ],
+            retry: Retry::new(&settings.network),
+            proxy: settings.network.proxy.clone(),
+            no_proxy: settings.network.no_proxy.clone(),
+            tls: settings.tls.clone(),
+        }
+    }
+
+    fn get() -> Self {
+        CONFIG
+            .read()
+            .ok()
+            .and_then(|config| config.clone())
+            .unwrap_or_else(|| Self {
+                connect_timeout: Duration::from_secs(5),
+                timeouts: [Duration::from_secs(30); 4],
+                retry: Retry::new(&Network::default()),
+                proxy: String::new(),
+                no_proxy: String::new(),
+                tls: Tls::default(),
+            })
+    }
+}
+
+/// Make requests follow `settings`. Done whenever settings are loaded.
+pub fn configure(settings: &Settings) {
+    if let Ok(mut config) = CONFIG.write() {
+        *config = Some(Config::new(settings));
+    }
+}
+
+/// How to retry failed requests
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Retry {
+    pub retries: u32,
+    pub backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Retry {
+    pub fn new(network: &Network) -> Self {
+        let seconds = |s: f64| Duration::try_from_secs_f64(s).unwrap_or_default();
+
+        Self {
+            retries: network.retries,
+            backoff: seconds(network.retry_backoff),
+            max_backoff: seconds(network.retry_backoff_max),
+        }
+    }
+
+    /// How long to wait before retry number `attempt` (counting from 0): the
+    /// backoff, doubled for each retry before it and capped, less up to half
+    /// at random. A wait the server asked for is honoured, up to the cap.
+    pub fn delay(&self, attempt: u32, requested: Option<Duration>) -> Duration {
+        if let Some(requested) = requested {
+            return requested.min(self.max_backoff);
+        }
+
+        let full = self
+            .backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_backoff);
+
+        rand::thread_rng().gen_range(full / 2..=full)
+    }
+}
+
+/// Why Atuin isn't using the network
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Offline {
+    /// Offline mode is on
+    Configured,
+    /// The sync server couldn't be reached
+    Unreachable,
+}
+
+impl Offline {
+    pub fn describe(self) -> &'static str {
+        match self {
+            Self::Configured => "offline mode is on",
+            Self::Unreachable => "the sync server can't be reached",
+        }
+    }
+}
+
+/// Whether Atuin is offline, and why. With `offline_detection` on, this tries
+/// to connect to the sync server (or the proxy in front of it), for up to a
+/// second.
+pub async fn check(settings: &Settings) -> Option<Offline> {
+    if settings.offline {
+        return Some(Offline::Configured);
+    }
+
+    if !settings.offline_detection {
+        return None;
+    }
+
+    #[cfg(unix)]
+    if let Ok(Some(socket)) = unix_socket(&settings.sync_address) {
+        let connect = tokio::net::UnixStream::connect(&socket);
+        return match tokio::time::timeout(PROBE_TIMEOUT, connect).await {
+            Ok(Ok(_)) => None,
+            Ok(Err(e)) => {
+                debug!(socket = %socket.display(), "offline: could not connect to the sync server: {e}");
+                Some(Offline::Unreachable)
+            }
+            Err(_) => {
+                debug!(socket = %socket.display(), "offline: timed out connecting to the sync server");
+                Some(Offline::Unreachable)
+            }
+        };
+    }
+
+    // Behind a proxy, the sync server may well not accept connections from us directly
+    let proxy = proxy_for(&settings.sync_address, &settings.network, |name| {
+        std::env::var(name).ok()
+    });
+    let (url, what) = match &proxy {
+        Some(proxy) => (proxy, "the proxy"),
+        None => (&settings.sync_address, "the sync server"),
+    };
+
+    let port = url
+        .port_or_known_default()
+        .or_else(|| url.scheme().starts_with("socks").then_some(1080));
+    let (Some(host), Some(port)) = (url.host_str(), port) else {
+        return None;
+    };
+
+    match tokio::time::timeout(PROBE_TIMEOUT, TcpStream::connect((host, port))).await {
+        Ok(Ok(_)) => None,
+        Ok(Err(e)) => {
+            debug!(%host, port, "offline: could not connect to {what}: {e}");
+            Some(Offline::Unreachable)
+        }
+        Err(_) => {
+            debug!(%host, port, "offline: timed out connecting to {what}");
+            Some(Offline::Unreachable)
+        }
+    }
+}
+
+/// The proxy requests to `url` go through, if any: `network.proxy` when it's
+/// set, otherwise the one in the environment (looked up with `env`), the same
+/// way requests pick one.
+fn proxy_for(url: &Url, network: &Network, env: impl Fn(&str) -> Option<String>) -> Option<Url> {
+    let first = |names: &[&str]| {
+        names
+            .iter()
+            .find_map(|name| env(name).filter(|value| !value.is_empty()))
+    };
+    let env_no_proxy = || first(&["NO_PROXY", "no_proxy"]).unwrap_or_default();
+
+    let (proxy, no_proxy) = if network.proxy.is_empty() {
+        let proxy = match url.scheme() {
+            "https" => first(&["HTTPS_PROXY", "https_proxy"]),
+            "http" => first(&["HTTP_PROXY", "http_proxy"]),
+            _ => None,
+        }
+        .or_else(|| first(&["ALL_PROXY", "all_proxy"]))?;
+
+        (proxy, env_no_proxy())
+    } else if network.no_proxy.is_empty() {
+        (network.proxy.clone(), env_no_proxy())
+    } else {
+        (network.proxy.clone(), network.no_proxy.clone())
+    };
+
+    let host = url.host_str()?;
+    let direct = no_proxy
+        .split(',')
+        .map(|entry| entry.trim().trim_start_matches('.'))
+        .filter(|entry| !entry.is_empty())
+        .any(|entry| {
+            entry == "*"
+                || host == entry
+                || host
+                    .strip_suffix(entry)
+                    .is_some_and(|sub| sub.ends_with('.'))
+        });
+    if direct {
+        return None;
+    }
+
+    // A proxy without a scheme is an HTTP one
+    if proxy.contains("://") {
+        Url::parse(&proxy).ok()
+    } else {
+        Url::parse(&format!("http://{proxy}")).ok()
+    }
+}
+
+/// A client builder set up for a `kind` of request: see [`configure_builder`]
+#[cfg(feature = "reqwest")]
+pub fn client_builder(kind: RequestKind) -> eyre::Result<reqwest::ClientBuilder> {
+    configure_builder(reqwest::Client::builder(), kind)
+}
+
+/// Set up `builder` to connect as configured (see [`connection`]), with the
+/// connect timeout and the timeout for a `kind` of request. Downloads may take
+/// as long as they like, as long as they don't stall.
+#[cfg(feature = "reqwest")]
+pub fn configure_builder(
+    builder: reqwest::ClientBuilder,
+    kind: RequestKind,
+) -> eyre::Result<reqwest::ClientBuilder> {
+    let config = Config::get();
+    let timeout = config.timeouts[kind as usize];
+    let builder = connection(builder)?.connect_timeout(config.connect_timeout);
+
+    Ok(if kind == RequestKind::Download {
+        builder.read_timeout(timeout)
+    } else {
+        builder.timeout(timeout)
+    })
+}
+
+/// Set up `builder` to go through the configured proxy, if any, and to trust
+/// the configured certificates. Proxies from the environment (`HTTPS_PROXY`
+/// and the like) are used when none is configured.
+#[cfg(feature = "reqwest")]
+pub fn connection(builder: reqwest::ClientBuilder) -> eyre::Result<reqwest::ClientBuilder> {
+    use eyre::WrapErr;
+
+    let config = Config::get();
+    let mut builder = builder;
+
+    if !config.proxy.is_empty() {
+        let no_proxy = if config.no_proxy.is_empty() {
+            reqwest::NoProxy::from_env()
+        } else {
+            reqwest::NoProxy::from_string(&config.no_proxy)
+        };
+        let proxy = reqwest::Proxy::all(&config.proxy)
+            .wrap_err_with(|| format!("network.proxy {:?} is not a proxy URL", config.proxy))?
+            .no_proxy(no_proxy);
+
+        builder = builder.proxy(proxy);
+    }
+
+    if !config.tls.ca_file.is_empty() {
+        let pem = fs_err::read(&config.tls.ca_file).wrap_err("could not read tls.ca_file")?;
+        let certs = reqwest::Certificate::from_pem_bundle(&pem)
+            .ok()
+            .filter(|certs| !certs.is_empty())
+            .ok_or_else(|| eyre::eyre!("{} has no PEM certificates in it", config.tls.ca_file))?;
+
+        builder = builder.tls_certs_merge(certs);
+    }
+
+    if config.tls.insecure {
+        static WARNED: std::sync::Once = std::sync::Once::new();
+        WARNED.call_once(|| warn!("tls.insecure is on: certificates are not being checked"));
+
+        builder = builder.tls_danger_accept_invalid_certs(true);
+    }
+
+    Ok(builder)
+}
+
+/// Set up `builder` to talk to the sync server at `address`, and return the
+/// URL its requests go under. A `unix:` address is a socket on this machine,
+/// which requests go over as `http://localhost/`. If `tls.client_cert` is set,
+/// it's presented to the server when asked for one.
+#[cfg(feature = "reqwest")]
+pub fn sync_server(
+    builder: reqwest::ClientBuilder,
+    address: &Url,
+) -> eyre::Result<(reqwest::ClientBuilder, std::borrow::Cow<'_, Url>)> {
+    use std::borrow::Cow;
+
+    use eyre::WrapErr;
+
+    let config = Config::get();
+    let mut builder = builder;
+
+    if !config.tls.client_cert.is_empty() {
+        let mut pem =
+            fs_err::read(&config.tls.client_cert).wrap_err("could not read tls.client_cert")?;
+        if !config.tls.client_key.is_empty() {
+            pem.push(b'\n');
+            pem.extend(
+                fs_err::read(&config.tls.client_key).wrap_err("could not read tls.client_key")?,
+            );
+        }
+        let identity = reqwest::Identity::from_pem(&pem).wrap_err(
+            "tls.client_cert and tls.client_key must hold a PEM certificate and its private key",
+        )?;
+
+        builder = builder.identity(identity);
+    }
+
+    let Some(socket) = unix_socket(address)? else {
+        return Ok((builder, Cow::Borrowed(address)));
+    };
+
+    #[cfg(unix)]
+    {
+        let base = Url::parse("http://localhost/").expect("a valid URL");
+        Ok((builder.unix_socket(socket), Cow::Owned(base)))
+    }
+
+    #[cfg(not(unix))]
+    {
+        eyre::bail!(
+            "sync_address {address} is a unix socket, which isn't supported on this platform"
+        )
+    }
+}
+
+/// The socket a `unix:` sync address points at, such as `/run/atuin.sock` for
+/// `unix:///run/atuin.sock`
+fn unix_socket(address: &Url) -> eyre::Result<Option<std::path::PathBuf>> {
+    if address.scheme() != "unix" {
+        return Ok(None);
+    }
+
+    address
+        .to_file_path()
+        .ok()
+        .filter(|path| path.is_absolute())
+        .map(Some)
+        .ok_or_else(|| {
+            eyre::eyre!("sync_address {address} should be the full path of a socket, such as unix:///run/atuin.sock")
+        })
+}
+
+/// Send `request`, retrying as configured
+#[cfg(feature = "reqwest")]
+pub async fn send(request: reqwest::RequestBuilder) -> reqwest::Result<reqwest::Response> {
+    send_with(Config::get().retry, request).await
+}
+
+/// Send `request`, retrying it as `retry` says when it fails in a way that
+/// may not happen again. Requests that aren't safe to repeat, such as a
+/// `POST`, are only retried if they can't have reached the server.
+#[cfg(feature = "reqwest")]
+pub async fn send_with(
+    retry: Retry,
+    request: reqwest::RequestBuilder,
+) -> reqwest::Result<reqwest::Response> {
+    let (client, request) = request.build_split();
+    let request = request?;
+    let idempotent = request.method().is_idempotent();
+
+    let mut attempt = 0;
+    loop {
+        // Streamed bodies can't be sent twice
+        let Some(this) = (attempt < retry.retries)
+            .then(|| request.try_clone())
+            .flatten()
+        else {
+            return client.execute(request).await;
+        };
+
+        let result = client.execute(this).await;
+        let Some(requested) = should_retry(&result, idempotent) else {
+            return result;
+        };
+
+        let delay = retry.delay(attempt, requested);
+        match &result {
+            Ok(resp) => debug!(url = %resp.url(), status = %resp.status(), "retrying in {delay:?}"),
+            Err(e) => debug!("retrying in {delay:?}: {e}"),
+        }
+
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+/// Whether to retry a request that got `result`, and if so how long the
+/// server asked us to wait, if it did
+#[cfg(feature = "reqwest")]
+fn should_retry(
+    result: &reqwest::Result<reqwest::Response>,
+    idempotent: bool,
+) -> Option<Option<Duration>> {
+    use reqwest::{StatusCode, header::RETRY_AFTER};
+
+    match result {
+        Err(e) if e.is_connect() || (idempotent && e.is_timeout()) => Some(None),
+        Err(_) => None,
+        Ok(resp) => {
+            let status = resp.status();
+
+            // The server turned these away without doing anything with them
+            let refused = matches!(
+                status,
+                StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE
+            );
+            let transient = matches!(
+                status,
+                StatusCode::BAD_GATEWAY | StatusCode::GATEWAY_TIMEOUT
+            );
+
+            (refused || (idempotent && transient)).then(|| {
+                resp.headers()
+                    .get(RETRY_AFTER)
+                    .and_then(|after| after.to_str().ok())
+                    .and_then(|after| after.parse().ok())
+                    .map(Duration::from_secs)
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::net::TcpListener;
+
+    use super::{Offline, Retry, check, proxy_for};
+    use crate::settings::{Network, Settings};
+
+    fn settings(sync_address: &str) -> Settings {
+        let mut settings = Settings::utc();
+        settings.sync_address = sync_address.parse().unwrap();
+        settings
+    }
+
+    #[tokio::test]
+    async fn configured_offline_skips_the_probe() {
+        let mut settings = settings("http://127.0.0.1:1");
+        settings.offline = true;
+
+        assert_eq!(check(&settings).await, Some(Offline::Configured));
+    }
+
+    #[tokio::test]
+    async fn probes_the_sync_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let settings = settings(&format!("http://127.0.0.1:{port}"));
+        assert_eq!(check(&settings).await, None);
+
+        drop(listener);
+        assert_eq!(check(&settings).await, Some(Offline::Unreachable));
+    }
+
+    #[tokio::test]
+    async fn detection_can_be_turned_off() {
+        let mut settings = settings("http://127.0.0.1:1");
+        settings.offline_detection = false;
+
+        assert_eq!(check(&settings).await, None);
+    }
+
+    #[test]
+    fn finds_the_proxy_for_the_sync_server() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(var, _)| *var == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+        let https = "https://api.atuin.sh".parse().unwrap();
+        let http = "http://atuin.internal:8888".parse().unwrap();
+        let network = Network::default();
+
+        assert_eq!(proxy_for(&https, &network, env(&[])), None);
+
+        let vars = env(&[
+            ("HTTPS_PROXY", "http://proxy:3128"),
+            ("ALL_PROXY", "socks5h://localhost:1080"),
+            ("NO_PROXY", "localhost, .internal"),
+        ]);
+        assert_eq!(
+            proxy_for(&https, &network, vars).unwrap().as_str(),
+            "http://proxy:3128/"
+        );
+        assert_eq!(proxy_for(&http, &network, vars), None);
+
+        let vars = env(&[("all_proxy", "socks5://localhost:1080")]);
+        assert_eq!(
+            proxy_for(&http, &network, vars).unwrap().as_str(),
+            "socks5://localhost:1080"
+        );
+
+        // The setting wins over the environment, and can be given without a scheme
+        let network = Network {
+            proxy: "corp-proxy:8080".to_string(),
+            no_proxy: "example.com".to_string(),
+            ..Network::default()
+        };
+        assert_eq!(
+            proxy_for(&http, &network, env(&[("NO_PROXY", "*")]))
+                .unwrap()
+                .as_str(),
+            "http://corp-proxy:8080/"
+        );
+    }
+
+    #[test]
+    fn backoff_doubles_with_jitter_up_to_the_cap() {
+        let retry = Retry {
+            retries: 5,
+            backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(500),
+        };
+
+        for _ in 0..100 {
+            let first = retry.delay(0, None);
+            assert!(first >= Duration::from_millis(50) && first <= Duration::from_millis(100));
+
+            let third = retry.delay(2, None);
+            assert!(third >= Duration::from_millis(200) && third <= Duration::from_millis(400));
+
+            let capped = retry.delay(30, None);
+            assert!(capped >= Duration::from_millis(250) && capped <= Duration::from_millis(500));
+        }
+
+        assert_eq!(
+            retry.delay(0, Some(Duration::from_secs(60))),
+            Duration::from_millis(500)
+        );
+    }
+
+    /// Answer each connection with the next of `responses`, returning how many were served
+    #[cfg(feature = "reqwest")]
+    async fn serve(listener: TcpListener, responses: Vec<&'static str>) -> usize {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let mut served = 0;
+        for response in responses {
+            let Ok(Ok((mut sock, _))) =
+                tokio::time::timeout(Duration::from_millis(500), listener.accept()).await
+            else {
+                break;
+            };
+            let mut buf = [0u8; 4096];
+            let _ = sock.read(&mut buf).await;
+            sock.write_all(response.as_bytes()).await.unwrap();
+            served += 1;
+        }
+        served
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[tokio::test]
+    async fn retries_requests_that_may_succeed_next_time() {
+        const BUSY: &str =
+            "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+        const BAD_GATEWAY: &str =
+            "HTTP/1.1 502 Bad Gateway\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+        const OK: &str = "HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+
+        let retry = Retry {
+            retries: 2,
+            backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(1),
+        };
+        atuin_common::tls::ensure_crypto_provider();
+        let client = reqwest::Client::new();
+
+        // Busy twice, then fine
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let server = tokio::spawn(serve(listener, vec![BUSY, BUSY, OK]));
+        let resp = super::send_with(retry, client.post(&url)).await.unwrap();
+        assert_eq!(resp.status(), 200);
+        assert_eq!(server.await.unwrap(), 3);
+
+        // Gives up after the configured retries
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let server = tokio::spawn(serve(listener, vec![BUSY, BUSY, BUSY, OK]));
+        let resp = super::send_with(retry, client.get(&url)).await.unwrap();
+        assert_eq!(resp.status(), 503);
+        assert_eq!(server.await.unwrap(), 3);
+
+        // A POST that may have been handled isn't repeated
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let server = tokio::spawn(serve(listener, vec![BAD_GATEWAY, OK]));
+        let resp = super::send_with(retry, client.post(&url)).await.unwrap();
+        assert_eq!(resp.status(), 502);
+        assert_eq!(server.await.unwrap(), 1);
+
+        // But a GET is
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let server = tokio::spawn(serve(listener, vec![BAD_GATEWAY, OK]));
+        let resp = super::send_with(retry, client.get(&url)).await.unwrap();
+        assert_eq!(resp.status(), 200);
+        assert_eq!(server.await.unwrap(), 2);
+    }
+
+    #[cfg(all(unix, feature = "reqwest"))]
+    #[tokio::test]
+    async fn talks_to_the_sync_server_over_a_unix_socket() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        use tokio::net::UnixListener;
+
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("atuin.sock");
+        let settings = settings(&format!("unix://{}", socket.display()));
+
+        assert_eq!(check(&settings).await, Some(Offline::Unreachable));
+
+        let listener = UnixListener::bind(&socket).unwrap();
+        assert_eq!(check(&settings).await, None);
+        // The probe's connection
+        drop(listener.accept().await.unwrap());
+
+        let server = tokio::spawn(async move {
+            let (mut sock, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 4096];
+            let n = sock.read(&mut buf).await.unwrap();
+            sock.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                .await
+                .unwrap();
+            String::from_utf8_lossy(&buf[..n]).into_owned()
+        });
+
+        atuin_common::tls::ensure_crypto_provider();
+        let (builder, base) =
+            super::sync_server(reqwest::Client::builder(), &settings.sync_address).unwrap();
+        assert_eq!(base.as_str(), "http://localhost/");
+
+        let client = builder.build().unwrap();
+        let resp = super::send(client.get(base.join("api/v0/me").unwrap()))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 200);
+        assert!(server.await.unwrap().starts_with("GET /api/v0/me HTTP/1.1"));
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[test]
+    fn unix_addresses_need_a_full_path() {
+        let address = "unix:atuin.sock".parse().unwrap();
+        assert!(super::sync_server(reqwest::Client::builder(), &address).is_err());
+
+        let address = "https://api.atuin.sh".parse().unwrap();
+        let (_, base) = super::sync_server(reqwest::Client::builder(), &address).unwrap();
+        assert_eq!(base.as_str(), "https://api.atuin.sh/");
+    }
+}
@@ -1,6 +1,12 @@
+use std::path::Path;
+
 use minspan::minspan;
 
-use super::{history::History, settings::SearchMode};
+use super::{
+    database::Context,
+    history::History,
+    settings::{self, SearchMode},
+};
 
 pub fn reorder_fuzzy(mode: SearchMode, query: &str, res: Vec<History>) -> Vec<History> {
     match mode {
@@ -30,3 +36,141 @@ where
     });
     r
 }
+
+/// How much to favour results run near where the search is run from.
+///
+/// Each boost raises the score of a result by that fraction: a boost of 1.0 doubles it, which
+/// halves its rank. A result in the current directory gets only the directory boost, even
+/// though it's in the workspace too.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ContextBoost {
+    pub directory: f64,
+    pub workspace: f64,
+}
+
+impl ContextBoost {
+    pub fn from_settings(search: &settings::Search) -> Self {
+        Self {
+            directory: search.directory_boost,
+            workspace: search.workspace_boost,
+        }
+    }
+
+    fn factor(&self, h: &History, context: &Context) -> f64 {
+        // Results grouped by command join their directories with ':'
+        let mut dirs = h.cwd.split(':');
+        if dirs.clone().any(|dir| dir == context.cwd) {
+            return 1.0 + self.directory.max(0.0);
+        }
+
+        match &context.git_root {
+            Some(root) if dirs.any(|dir| Path::new(dir).starts_with(root)) => {
+                1.0 + self.workspace.max(0.0)
+            }
+            _ => 1.0,
+        }
+    }
+}
+
+/// Reorder `res`, ranked best first, so that results run in the current directory, or
+/// elsewhere in the same git repository, move up. Each result scores the reciprocal of its
+/// rank times its boost, so a boost moves a result past its neighbours, not straight to the
+/// top.
+pub fn boost_context(res: Vec<History>, context: &Context, boost: ContextBoost) -> Vec<History> {
+    if boost == ContextBoost::default() || context.cwd.is_empty() {
+        return res;
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    let mut scored: Vec<(f64, History)> = res
+        .into_iter()
+        .enumerate()
+        .map(|(rank, h)| (boost.factor(&h, context) / (rank + 1) as f64, h))
+        .collect();
+
+    // Stable, so results with the same score keep their order
+    scored.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+    scored.into_iter().map(|(_, h)| h).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn history(command: &str, cwd: &str) -> History {
+        History::import()
+            .timestamp(time::OffsetDateTime::now_utc())
+            .command(command)
+            .cwd(cwd)
+            .build()
+            .into()
+    }
+
+    fn context() -> Context {
+        Context {
+            session: String::new(),
+            cwd: "/code/atuin/crates".to_string(),
+            hostname: String::new(),
+            host_id: String::new(),
+            git_root: Some(PathBuf::from("/code/atuin")),
+        }
+    }
+
+    fn commands(res: &[History]) -> Vec<&str> {
+        res.iter().map(|h| h.command.as_str()).collect()
+    }
+
+    #[test]
+    fn nearby_results_move_up() {
+        let res = vec![
+            history("ls", "/tmp"),
+            history("top", "/"),
+            history("cargo test", "/code/atuin"),
+            history("cargo build", "/code/atuin/crates"),
+        ];
+        let boost = ContextBoost {
+            directory: 1.0,
+            workspace: 0.5,
+        };
+
+        // 1, 1/2, 1.5/3 and 2/4: the boosted results catch up with the one above them
+        let boosted = boost_context(res.clone(), &context(), boost);
+        assert_eq!(
+            commands(&boosted),
+            ["ls", "top", "cargo test", "cargo build"]
+        );
+
+        let boost = ContextBoost {
+            directory: 4.0,
+            workspace: 2.0,
+        };
+        let boosted = boost_context(res.clone(), &context(), boost);
+        assert_eq!(
+            commands(&boosted),
+            ["cargo build", "ls", "cargo test", "top"]
+        );
+
+        let boosted = boost_context(res, &context(), ContextBoost::default());
+        assert_eq!(
+            commands(&boosted),
+            ["ls", "top", "cargo test", "cargo build"]
+        );
+    }
+
+    #[test]
+    fn grouped_directories_are_checked_one_by_one() {
+        let res = vec![
+            history("ls", "/tmp"),
+            history("make", "/tmp:/code/atuin/crates"),
+        ];
+        let boost = ContextBoost {
+            directory: 2.0,
+            workspace: 0.0,
+        };
+
+        let boosted = boost_context(res, &context(), boost);
+        assert_eq!(commands(&boosted), ["make", "ls"]);
+    }
+}
@@ -0,0 +1,166 @@
+//! A small, read-only API for shell prompts.
+//!
+//! Prompt tools (starship, powerlevel10k, ...) render on every prompt, so
+//! fork/exec-ing `atuin` each time adds up. Linking this module instead gives
+//! them the last command's metadata and the sync status straight from the
+//! local databases. Databases are opened read-only and never migrated or
+//! created; if one doesn't exist yet, the answer is simply "nothing".
+
+use std::{path::Path, str::FromStr, time::Duration as StdDuration};
+
+use eyre::Result;
+use sqlx::{
+    Row,
+    sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions},
+};
+use time::{Duration, OffsetDateTime, format_description::well_known::Rfc3339};
+
+use crate::settings::Settings;
+
+/// The most recently finished command.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LastCommand {
+    pub command: String,
+    pub exit: i64,
+    pub duration: Duration,
+    pub started_at: OffsetDateTime,
+}
+
+impl LastCommand {
+    pub fn success(&self) -> bool {
+        self.exit == 0
+    }
+}
+
+/// The most recently finished command, optionally only from `session` (the
+/// `ATUIN_SESSION` of the shell the prompt is for).
+pub async fn last_command(
+    settings: &Settings,
+    session: Option<&str>,
+) -> Result<Option<LastCommand>> {
+    let Some(pool) = open_read_only(&settings.db_path, settings.local_timeout).await? else {
+        return Ok(None);
+    };
+
+    let row = sqlx::query(
+        "select command, exit, duration, timestamp from history
+            where duration >= 0 and deleted_at is null
+                and (?1 is null or session = ?1)
+            order by timestamp desc
+            limit 1",
+    )
+    .bind(session)
+    .fetch_optional(&pool)
+    .await?;
+
+    pool.close().await;
+
+    let Some(row) = row else {
+        return Ok(None);
+    };
+
+    let timestamp: i64 = row.get("timestamp");
+
+    Ok(Some(LastCommand {
+        command: row.get("command"),
+        exit: row.get("exit"),
+        duration: Duration::nanoseconds(row.get("duration")),
+        started_at: OffsetDateTime::from_unix_timestamp_nanos(i128::from(timestamp))?,
+    }))
+}
+
+/// When history last synced successfully, or `None` if it never has.
+pub async fn last_sync(settings: &Settings) -> Result<Option<OffsetDateTime>> {
+    let Some(pool) = open_read_only(&settings.meta.db_path, settings.local_timeout).await? else {
+        return Ok(None);
+    };
+
+    let value: Option<String> =
+        sqlx::query_scalar("select value from meta where key = 'last_sync_time'")
+            .fetch_optional(&pool)
+            .await?;
+
+    pool.close().await;
+
+    Ok(value
+        .map(|v| OffsetDateTime::parse(&v, &Rfc3339))
+        .transpose()?)
+}
+
+async fn open_read_only(path: impl AsRef<Path>, timeout: f64) -> Result<Option<SqlitePool>> {
+    let path = path.as_ref();
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let opts = SqliteConnectOptions::from_str(&path.to_string_lossy())?.read_only(true);
+
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .acquire_timeout(StdDuration::from_secs_f64(timeout))
+        .connect_with(opts)
+        .await?;
+
+    Ok(Some(pool))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::{Database, Sqlite},
+        history::History,
+        meta::MetaStore,
+    };
+
+    fn settings_in(dir: &Path) -> Settings {
+        let mut settings = Settings::utc();
+        settings.db_path = dir.join("history.db");
+        settings.meta.db_path = dir.join("meta.db").to_string_lossy().to_string();
+        settings
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn missing_databases_mean_nothing_yet() {
+        let dir = tempfile::tempdir().unwrap();
+        let settings = settings_in(dir.path());
+
+        assert_eq!(last_command(&settings, None).await.unwrap(), None);
+        assert_eq!(last_sync(&settings).await.unwrap(), None);
+        assert!(!settings.db_path.exists(), "must not create the database");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn reads_last_command_and_sync_time() {
+        let dir = tempfile::tempdir().unwrap();
+        let settings = settings_in(dir.path());
+
+        let db = Sqlite::new(&settings.db_path, 2.0).await.unwrap();
+        let start = OffsetDateTime::now_utc() - Duration::minutes(1);
+        for (i, (command, session, exit)) in [("make", "a", 2), ("ls", "b", 0)].iter().enumerate() {
+            let mut h: History = History::capture()
+                .timestamp(start + Duration::seconds(i as i64))
+                .command(*command)
+                .cwd("/")
+                .build()
+                .into();
+            h.session = (*session).to_string();
+            h.exit = *exit;
+            h.duration = 1_500_000_000;
+            db.save(&h).await.unwrap();
+        }
+
+        let last = last_command(&settings, Some("a")).await.unwrap().unwrap();
+        assert_eq!(last.command, "make");
+        assert!(!last.success());
+        assert_eq!(last.duration, Duration::milliseconds(1500));
+
+        let last = last_command(&settings, None).await.unwrap().unwrap();
+        assert_eq!(last.command, "ls");
+
+        let meta = MetaStore::new(&settings.meta.db_path, 2.0).await.unwrap();
+        assert_eq!(last_sync(&settings).await.unwrap(), None);
+        meta.save_sync_time().await.unwrap();
+        assert!(last_sync(&settings).await.unwrap().is_some());
+    }
+}
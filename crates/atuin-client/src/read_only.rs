@@ -0,0 +1,53 @@
+//! Read-only mode, for looking through a copy of someone else's database, or a snapshot kept for
+//! forensics, without changing it.
+//!
+//! Turned on with `--read-only` or `ATUIN_READONLY`, it opens the databases read-only and
+//! without migrating them, records no history, doesn't sync and leaves files where they are.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use eyre::{Result, bail};
+
+static READ_ONLY: AtomicBool = AtomicBool::new(false);
+
+/// Turns read-only mode on when set to anything but empty or `false`.
+pub const ENV_VAR: &str = "ATUIN_READONLY";
+
+pub fn enable() {
+    READ_ONLY.store(true, Ordering::Relaxed);
+}
+
+/// Whether read-only mode is on, from [`enable`] or [`ENV_VAR`].
+pub fn is_enabled() -> bool {
+    READ_ONLY.load(Ordering::Relaxed) || std::env::var(ENV_VAR).is_ok_and(|value| is_truthy(&value))
+}
+
+/// Fail with an explanation if read-only mode is on, before `what` is attempted.
+pub fn ensure_writable(what: &str) -> Result<()> {
+    if is_enabled() {
+        bail!("can't {what} in read-only mode");
+    }
+    Ok(())
+}
+
+fn is_truthy(value: &str) -> bool {
+    let value = value.trim();
+    !value.is_empty() && value != "0" && !value.eq_ignore_ascii_case("false")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn env_values() {
+        assert!(is_truthy("1"));
+        assert!(is_truthy("true"));
+        assert!(is_truthy("yes"));
+
+        assert!(!is_truthy(""));
+        assert!(!is_truthy(" "));
+        assert!(!is_truthy("0"));
+        assert!(!is_truthy("False"));
+    }
+}
@@ -2,13 +2,15 @@ use atuin_common::record::{
     AdditionalData, DecryptedData, EncryptedData, Encryption, HostId, RecordId, RecordIdx,
 };
 use base64::{Engine, engine::general_purpose};
-use eyre::{Context, Result, ensure};
+use eyre::{Context, Report, Result};
 use rusty_paserk::{Key, KeyId, Local, PieWrappedKey};
 use rusty_paseto::core::{
     ImplicitAssertion, Key as DataKey, Local as LocalPurpose, Paseto, PasetoNonce, Payload, V4,
 };
 use serde::{Deserialize, Serialize};
 
+use crate::error::ClientError;
+
 /// Use PASETO V4 Local encryption using the additional data as an implicit assertion.
 #[allow(non_camel_case_types)]
 pub struct PASETO_V4;
@@ -105,7 +107,7 @@ impl Encryption for PASETO_V4 {
             None,
             ImplicitAssertion::from(&*assertions),
         )
-        .context("could not decrypt entry")?;
+        .map_err(|_| ClientError::DecryptFailed)?;
 
         let payload: AtuinPayload = serde_json::from_str(&payload)?;
         let data = general_purpose::URL_SAFE_NO_PAD.decode(payload.data)?;
@@ -129,13 +131,16 @@ impl PASETO_V4 {
         // have to be a hard reset
         let current_kid = wrapping_key.to_id();
 
-        ensure!(
-            current_kid == kid,
-            "attempting to decrypt with incorrect key. currently using {current_kid}, expecting {kid}"
-        );
+        if current_kid != kid {
+            return Err(Report::new(ClientError::DecryptFailed).wrap_err(format!(
+                "attempting to decrypt with incorrect key. currently using {current_kid}, expecting {kid}"
+            )));
+        }
 
         // decrypt the random key
-        Ok(wpk.unwrap_key(&wrapping_key)?)
+        Ok(wpk
+            .unwrap_key(&wrapping_key)
+            .map_err(|_| ClientError::DecryptFailed)?)
     }
 
     fn encrypt_cek(cek: Key<V4, Local>, key: &[u8; 32]) -> String {
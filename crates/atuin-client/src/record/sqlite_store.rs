@@ -11,6 +11,7 @@ use fs_err as fs;
 
 use sqlx::{
     Row,
+    migrate::Migrator,
     sqlite::{
         SqliteConnectOptions, SqliteJournalMode, SqlitePool, SqlitePoolOptions, SqliteRow,
         SqliteSynchronous,
@@ -25,6 +26,10 @@ use uuid::Uuid;
 
 use super::encryption::PASETO_V4;
 use super::store::Store;
+use crate::database::lock;
+use crate::read_only;
+
+static MIGRATOR: Migrator = sqlx::migrate!("./record-migrations");
 
 #[derive(Debug, Clone)]
 pub struct SqliteStore {
@@ -44,24 +49,37 @@ impl SqliteStore {
             std::process::exit(1);
         }
 
-        if !path.exists()
+        let read_only = read_only::is_enabled();
+        if !read_only
+            && !path.exists()
             && let Some(dir) = path.parent()
         {
             fs::create_dir_all(dir)?;
         }
 
         let opts = SqliteConnectOptions::from_str(path.as_os_str().to_str().unwrap())?
-            .journal_mode(SqliteJournalMode::Wal)
-            .optimize_on_close(true, None)
-            .synchronous(SqliteSynchronous::Normal)
-            .foreign_keys(true)
-            .create_if_missing(true);
+            .busy_timeout(Duration::from_secs_f64(timeout))
+            .foreign_keys(true);
+        let opts = if read_only {
+            opts.read_only(true)
+        } else {
+            opts.journal_mode(SqliteJournalMode::Wal)
+                .optimize_on_close(true, None)
+                .synchronous(SqliteSynchronous::Normal)
+                .create_if_missing(true)
+        };
 
         let pool = SqlitePoolOptions::new()
             .acquire_timeout(Duration::from_secs_f64(timeout))
             .connect_with(opts)
             .await?;
 
+        if read_only || !lock::migrations_pending(&pool, &MIGRATOR).await? {
+            return Ok(Self { pool });
+        }
+
+        let _lock = lock::MigrationLock::acquire(path, Duration::from_secs_f64(timeout)).await?;
+        lock::check_wal(&pool, path).await?;
         Self::setup_db(&pool).await?;
 
         Ok(Self { pool })
@@ -70,7 +88,7 @@ impl SqliteStore {
     async fn setup_db(pool: &SqlitePool) -> Result<()> {
         debug!("running sqlite database setup");
 
-        sqlx::migrate!("./record-migrations").run(pool).await?;
+        MIGRATOR.run(pool).await?;
 
         Ok(())
     }
@@ -5,10 +5,16 @@ use eyre::Result;
 use thiserror::Error;
 
 use super::{encryption::PASETO_V4, store::Store};
-use crate::{api_client::Client, settings::Settings};
+use crate::{api_client::Client, network::RequestKind, settings::Settings};
 
 use atuin_common::record::{Diff, HostId, RecordId, RecordIdx, RecordStatus};
 use indicatif::{ProgressBar, ProgressState, ProgressStyle};
+use time::Duration;
+use tracing::{debug, instrument};
+
+/// Clock skew with the sync server beyond which `atuin sync` warns. Skew makes
+/// "ago" times and recency ranking wrong for entries from other machines.
+pub const CLOCK_SKEW_WARNING: Duration = Duration::minutes(1);
 
 #[derive(Error, Debug)]
 pub enum SyncError {
@@ -65,12 +71,13 @@ pub async fn build_client(settings: &Settings) -> Result<Client<'_>, SyncError>
             .await
             .map_err(|e| SyncError::RemoteRequestError { msg: e.to_string() })?,
         settings.network_connect_timeout,
-        settings.network_timeout,
+        settings.network_timeout_for(RequestKind::Sync),
         &settings.extra_headers,
     )
     .map_err(|e| SyncError::OperationalError { msg: e.to_string() })
 }
 
+#[instrument(skip_all)]
 pub async fn diff(
     client: &Client<'_>,
     store: &impl Store,
@@ -167,6 +174,7 @@ pub async fn operations(
     Ok(operations)
 }
 
+#[instrument(skip_all, fields(host = %host.0.as_simple(), %tag))]
 async fn sync_upload(
     store: &impl Store,
     client: &Client<'_>,
@@ -226,6 +234,7 @@ async fn sync_upload(
     Ok(progress as i64)
 }
 
+#[instrument(skip_all, fields(host = %host.0.as_simple(), %tag))]
 async fn sync_download(
     store: &impl Store,
     client: &Client<'_>,
@@ -283,6 +292,7 @@ async fn sync_download(
     Ok(ret)
 }
 
+#[instrument(skip_all)]
 pub async fn sync_remote(
     client: &Client<'_>,
     operations: Vec<Operation>,
@@ -354,6 +364,7 @@ pub async fn check_encryption_key(
     Ok(())
 }
 
+#[instrument(skip_all)]
 pub async fn sync(
     settings: &Settings,
     store: &impl Store,
@@ -362,6 +373,13 @@ pub async fn sync(
     let client = build_client(settings).await?;
     let (diff, remote_index) = diff(&client, store).await?;
 
+    if let Some(skew) = client.clock_skew() {
+        // Only informational, so not worth failing the sync over.
+        if let Err(e) = Settings::save_clock_skew(skew).await {
+            debug!("failed to save clock skew: {e}");
+        }
+    }
+
     // Bail before mutating either side if the local key can't read the remote.
     check_encryption_key(&client, &remote_index, encryption_key).await?;
 
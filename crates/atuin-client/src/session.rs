@@ -0,0 +1,227 @@
+// Names for shell sessions, synced like the host registry.
+// A session is only known by the random ID its shell was given at startup, which says nothing
+// about what it was for. Any machine can name any session, like `incident-4123`, so its history
+// can be found again with a `session:` filter, and is labelled with the name when searching it.
+
+use std::collections::{BTreeMap, HashMap};
+
+use atuin_common::record::{DecryptedData, Host, HostId, Record};
+use eyre::{Result, bail, ensure, eyre};
+
+use crate::{
+    database::Database,
+    record::{encryption::PASETO_V4, sqlite_store::SqliteStore, store::Store},
+};
+
+pub const SESSION_TAG: &str = "session";
+const SESSION_VERSION: &str = "v0";
+const SESSION_NAME_MAX_LEN: usize = 1000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionRecord {
+    /// A name for a session, by its ID, or `None` to take its name away
+    Name(String, Option<String>),
+}
+
+impl SessionRecord {
+    pub fn serialize(&self) -> Result<DecryptedData> {
+        use rmp::encode;
+
+        let mut output = vec![];
+
+        match self {
+            SessionRecord::Name(session, name) => {
+                encode::write_u8(&mut output, 0)?; // name
+                encode::write_array_len(&mut output, 2)?; // 2 fields
+
+                encode::write_str(&mut output, session)?;
+                encode::write_str(&mut output, name.as_deref().unwrap_or_default())?;
+            }
+        }
+
+        Ok(DecryptedData(output))
+    }
+
+    pub fn deserialize(data: &DecryptedData, version: &str) -> Result<Self> {
+        use rmp::decode;
+
+        fn error_report<E: std::fmt::Debug>(err: E) -> eyre::Report {
+            eyre!("{err:?}")
+        }
+
+        if version != SESSION_VERSION {
+            bail!("unknown version {version:?}");
+        }
+
+        let mut bytes = decode::Bytes::new(&data.0);
+        let record_type = decode::read_u8(&mut bytes).map_err(error_report)?;
+        let nfields = decode::read_array_len(&mut bytes).map_err(error_report)?;
+        let bytes = bytes.remaining_slice();
+
+        let (record, bytes) = match record_type {
+            // name
+            0 => {
+                ensure!(nfields == 2, "wrong number of entries in v0 session name");
+
+                let (session, bytes) = decode::read_str_from_slice(bytes).map_err(error_report)?;
+                let (name, bytes) = decode::read_str_from_slice(bytes).map_err(error_report)?;
+
+                let name = (!name.is_empty()).then(|| name.to_owned());
+
+                (SessionRecord::Name(session.to_owned(), name), bytes)
+            }
+
+            n => bail!("unknown session record type {n}"),
+        };
+
+        if !bytes.is_empty() {
+            bail!("trailing bytes in encoded session record. malformed");
+        }
+
+        Ok(record)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SessionStore {
+    pub store: SqliteStore,
+    pub host_id: HostId,
+    pub encryption_key: [u8; 32],
+}
+
+impl SessionStore {
+    pub fn new(store: SqliteStore, host_id: HostId, encryption_key: [u8; 32]) -> SessionStore {
+        SessionStore {
+            store,
+            host_id,
+            encryption_key,
+        }
+    }
+
+    async fn push(&self, record: SessionRecord) -> Result<()> {
+        let bytes = record.serialize()?;
+
+        let idx = self
+            .store
+            .last(self.host_id, SESSION_TAG)
+            .await?
+            .map_or(0, |entry| entry.idx + 1);
+
+        let record = Record::builder()
+            .host(Host::new(self.host_id))
+            .version(SESSION_VERSION.to_string())
+            .tag(SESSION_TAG.to_string())
+            .idx(idx)
+            .data(bytes)
+            .build();
+
+        self.store
+            .push(&record.encrypt::<PASETO_V4>(&self.encryption_key))
+            .await
+    }
+
+    /// Give `session` a name, or with `None` take its name away
+    pub async fn name(&self, session: &str, name: Option<&str>) -> Result<()> {
+        let name = name.map(str::trim).filter(|name| !name.is_empty());
+
+        if session.is_empty() {
+            bail!("no session to name: $ATUIN_SESSION isn't set");
+        }
+        if let Some(name) = name
+            && name.len() > SESSION_NAME_MAX_LEN
+        {
+            bail!("session name too long: max len {SESSION_NAME_MAX_LEN} bytes");
+        }
+
+        self.push(SessionRecord::Name(
+            session.to_owned(),
+            name.map(str::to_owned),
+        ))
+        .await
+    }
+
+    /// The name of every named session, by its ID
+    pub async fn names(&self) -> Result<HashMap<String, String>> {
+        let mut names = BTreeMap::new();
+
+        // this is sorted, oldest to newest, so later names win
+        let tagged = self.store.all_tagged(SESSION_TAG).await?;
+
+        for record in tagged {
+            let version = record.version.clone();
+
+            // Skip records we can't decrypt or decode, rather than failing the entire build.
+            let decoded = record
+                .decrypt::<PASETO_V4>(&self.encryption_key)
+                .and_then(|decrypted| SessionRecord::deserialize(&decrypted.data, &version));
+
+            match decoded {
+                Ok(SessionRecord::Name(session, name)) => {
+                    names.insert(session, name);
+                }
+                Err(e) => tracing::warn!("failed to decode session record, skipping: {e}"),
+            }
+        }
+
+        Ok(names
+            .into_iter()
+            .filter_map(|(session, name)| Some((session, name?)))
+            .collect())
+    }
+
+    /// Copy the session names into the history database, where searches filter by them
+    pub async fn build(&self, database: &dyn Database) -> Result<()> {
+        database.save_session_names(&self.names().await?).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use atuin_common::utils::uuid_v7;
+
+    use super::*;
+    use crate::settings::test_local_timeout;
+
+    #[test]
+    fn encode_decode() {
+        let records = [
+            SessionRecord::Name(
+                uuid_v7().as_simple().to_string(),
+                Some("incident-4123".into()),
+            ),
+            SessionRecord::Name(uuid_v7().as_simple().to_string(), None),
+        ];
+
+        for record in records {
+            let encoded = record.serialize().unwrap();
+            let decoded = SessionRecord::deserialize(&encoded, SESSION_VERSION).unwrap();
+
+            assert_eq!(decoded, record);
+        }
+    }
+
+    #[tokio::test]
+    async fn later_names_win() {
+        let store = SqliteStore::new(":memory:", test_local_timeout())
+            .await
+            .unwrap();
+        let key = [0u8; 32];
+
+        let laptop = SessionStore::new(store.clone(), HostId(uuid_v7()), key);
+        let desktop = SessionStore::new(store, HostId(uuid_v7()), key);
+
+        laptop.name("abc", Some("incident-4123")).await.unwrap();
+        laptop.name("def", Some(" deploy ")).await.unwrap();
+        // any machine can name any session
+        desktop.name("abc", Some("incident-4124")).await.unwrap();
+        desktop.name("def", None).await.unwrap();
+
+        let names = laptop.names().await.unwrap();
+        assert_eq!(
+            names,
+            HashMap::from([("abc".to_string(), "incident-4124".to_string())])
+        );
+
+        assert!(laptop.name("", Some("nothing")).await.is_err());
+    }
+}
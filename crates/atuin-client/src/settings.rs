@@ -1,14 +1,14 @@
-use atuin_common::logs::LogLevel;
+use atuin_common::logs::{LogFormat, LogLevel};
+use atuin_common::otel::OtelConfig;
+use atuin_common::paths::{self, Relocation};
 use atuin_common::record::HostId;
 use atuin_common::utils;
 use clap::ValueEnum;
-use config::{
-    Config, ConfigBuilder, Environment, File as ConfigFile, FileFormat, builder::DefaultState,
-};
+use config::{Config, ConfigBuilder, Environment, builder::DefaultState};
 use eyre::{Context, Error, Result, bail, eyre};
 use fs_err::{File, create_dir_all};
 use humantime::parse_duration;
-use regex::RegexSet;
+use regex::{Regex, RegexSet};
 use semver::Version;
 use serde::{Deserialize, Serialize};
 use serde_with::DeserializeFromStr;
@@ -29,8 +29,12 @@ static DATA_DIR: OnceLock<PathBuf> = OnceLock::new();
 static META_CONFIG: OnceLock<(String, f64)> = OnceLock::new();
 static META_STORE: OnceCell<crate::meta::MetaStore> = OnceCell::const_new();
 
+/// Left in the state directory once files older versions kept elsewhere have been moved.
+const RELOCATED_MARKER: &str = ".relocated";
+
 mod dotfiles;
 mod kv;
+mod layers;
 pub(crate) mod meta;
 mod scripts;
 pub mod watcher;
@@ -61,6 +65,20 @@ pub enum SearchMode {
     #[serde(rename = "daemon-fuzzy")]
     #[clap(aliases = &["daemon-fuzzy"])]
     DaemonFuzzy,
+
+    /// Experimental: match words from a built-in thesaurus of shell terms, so "compress"
+    /// finds `tar`. Requires atuin to be built with the `synonyms` feature, and falls back
+    /// to fuzzy otherwise.
+    #[serde(rename = "synonym")]
+    Synonym,
+
+    /// Fuzzy matching, ranked by how often and how recently each command was run.
+    #[serde(rename = "frecency")]
+    Frecency,
+
+    /// The whole query is a regular expression.
+    #[serde(rename = "regex")]
+    Regex,
 }
 
 impl SearchMode {
@@ -71,6 +89,9 @@ impl SearchMode {
             SearchMode::Fuzzy => "FUZZY",
             SearchMode::Skim => "SKIM",
             SearchMode::DaemonFuzzy => "DAEMON",
+            SearchMode::Synonym => "SYNONYM",
+            SearchMode::Frecency => "FRECENCY",
+            SearchMode::Regex => "REGEX",
         }
     }
     pub fn next(&self, settings: &Settings) -> Self {
@@ -82,14 +103,29 @@ impl SearchMode {
             SearchMode::FullText if settings.search_mode == SearchMode::DaemonFuzzy => {
                 SearchMode::DaemonFuzzy
             }
+            // if the user is using synonym, we go to synonym
+            SearchMode::FullText if settings.search_mode == SearchMode::Synonym => {
+                SearchMode::Synonym
+            }
+            // if the user is using frecency, we go to frecency
+            SearchMode::FullText if settings.search_mode == SearchMode::Frecency => {
+                SearchMode::Frecency
+            }
+            // if the user is using regex, we go to regex
+            SearchMode::FullText if settings.search_mode == SearchMode::Regex => SearchMode::Regex,
             // otherwise fuzzy.
             SearchMode::FullText => SearchMode::Fuzzy,
-            SearchMode::Fuzzy | SearchMode::Skim | SearchMode::DaemonFuzzy => SearchMode::Prefix,
+            SearchMode::Fuzzy
+            | SearchMode::Skim
+            | SearchMode::DaemonFuzzy
+            | SearchMode::Synonym
+            | SearchMode::Frecency
+            | SearchMode::Regex => SearchMode::Prefix,
         }
     }
 }
 
-#[derive(Clone, Debug, Deserialize, Copy, PartialEq, Eq, ValueEnum, Serialize)]
+#[derive(Clone, Debug, Deserialize, Copy, PartialEq, Eq, Hash, ValueEnum, Serialize)]
 pub enum FilterMode {
     #[serde(rename = "global")]
     Global = 0,
@@ -108,6 +144,10 @@ pub enum FilterMode {
 
     #[serde(rename = "session-preload")]
     SessionPreload = 5,
+
+    /// Everything run on machines other than this one
+    #[serde(rename = "other-hosts")]
+    OtherHosts = 6,
 }
 
 impl FilterMode {
@@ -119,6 +159,7 @@ impl FilterMode {
             FilterMode::Directory => "DIRECTORY",
             FilterMode::Workspace => "WORKSPACE",
             FilterMode::SessionPreload => "SESSION+",
+            FilterMode::OtherHosts => "ELSEWHERE",
         }
     }
 }
@@ -534,6 +575,19 @@ pub struct Daemon {
 
     /// The port that should be used for TCP on non unix systems
     pub tcp_port: u64,
+
+    /// Serve search, stats and history over HTTP on localhost, for GUI front-ends.
+    pub api_enabled: bool,
+
+    /// The port the HTTP API listens on, on 127.0.0.1.
+    pub api_port: u16,
+
+    /// The file holding the token HTTP API clients must send.
+    pub api_token_path: String,
+
+    /// Exporting traces and metrics over OTLP, with the `otel` feature.
+    #[serde(default)]
+    pub otel: OtelConfig,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -552,6 +606,138 @@ pub struct Search {
     /// The overall frecency score multiplier for the search index (default: 1.0).
     /// Applied after combining recency and frequency scores.
     pub frecency_score_multiplier: f64,
+
+    /// How many days it takes the `frecency` search mode to halve the weight of a
+    /// command that hasn't been run since (default: 7.0). 0.0 disables the decay.
+    pub frecency_half_life: f64,
+
+    /// How much the `frecency` search mode favours often-run commands: their run count
+    /// is raised to this power (default: 1.0). 0.0 ranks by recency alone.
+    pub frecency_frequency_weight: f64,
+
+    /// Boost the commands that most often followed the previously run command
+    /// in a session to the top of interactive search results (default: false).
+    pub sequence_boost: bool,
+
+    /// How much interactive search favours commands run in the current directory
+    /// (default: 1.0, which halves their rank). 0.0 disables it.
+    pub directory_boost: f64,
+
+    /// How much interactive search favours commands run elsewhere in the current
+    /// git repository (default: 0.5). 0.0 disables it.
+    pub workspace_boost: f64,
+
+    /// Tab marks entries in interactive search, to act on several at once,
+    /// rather than returning the selection.
+    pub multi_select: bool,
+
+    /// How marked commands are joined when put on the command line.
+    pub join_marked: JoinMarked,
+
+    /// How a command is written when it's copied to the clipboard.
+    pub copy_format: CopyFormat,
+
+    /// Show how many times each result was run, and let its runs be listed under it.
+    pub group_duplicates: bool,
+
+    /// Leave commands run in containers out of interactive search, unless the query has a
+    /// `container:` filter.
+    pub exclude_containers: bool,
+
+    /// Start interactive search in the filter mode it was last left in within the same shell
+    /// session.
+    pub remember_filter_mode: bool,
+
+    /// Use the mouse in interactive search: click to select, double-click to accept, and
+    /// scroll the list with the wheel. Off by default, as it takes over the terminal's own
+    /// selection for copy and paste.
+    pub mouse: bool,
+
+    /// How many milliseconds interactive search waits for typing to pause before querying
+    /// (default: 0, querying on every keystroke).
+    pub debounce_ms: u64,
+
+    /// How what a `>` search finds is weighed and ordered, by where it's from.
+    #[serde(default)]
+    pub sources: SourceRanking,
+}
+
+/// How a `>` search ranks what it finds, by kind (`task`, `snippet`, `alias`, `var`, `kv` or
+/// `source`) or by the name of the source it's from, which goes first. Names are matched
+/// without regard to case.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SourceRanking {
+    /// How much each counts for: above 1 ranks it higher, below 1 lower, and 0 leaves it
+    /// out. Any not named count 1.
+    pub weights: HashMap<String, f64>,
+
+    /// Listed before the rest, in this order.
+    pub pinned: Vec<String>,
+
+    /// Weights and pins for a filter mode, over the ones above.
+    pub filter_modes: HashMap<FilterMode, SourceRanking>,
+}
+
+impl SourceRanking {
+    /// How much what's from `names`, most specific first, counts for in `mode`.
+    pub fn weight(&self, mode: FilterMode, names: &[&str]) -> f64 {
+        let in_mode = self.filter_modes.get(&mode);
+        names
+            .iter()
+            .find_map(|name| {
+                in_mode
+                    .and_then(|ranking| named(&ranking.weights, name))
+                    .or_else(|| named(&self.weights, name))
+            })
+            .unwrap_or(1.0)
+    }
+
+    /// Where what's from `names` is pinned in `mode`, if it is.
+    pub fn pinned(&self, mode: FilterMode, names: &[&str]) -> Option<usize> {
+        let pinned = self
+            .filter_modes
+            .get(&mode)
+            .filter(|ranking| !ranking.pinned.is_empty())
+            .map_or(&self.pinned, |ranking| &ranking.pinned);
+
+        names
+            .iter()
+            .find_map(|name| pinned.iter().position(|pin| pin.eq_ignore_ascii_case(name)))
+    }
+}
+
+fn named(weights: &HashMap<String, f64>, name: &str) -> Option<f64> {
+    weights
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, weight)| *weight)
+}
+
+#[derive(Clone, Debug, Deserialize, Copy, PartialEq, Eq, Serialize)]
+pub enum JoinMarked {
+    /// On one line, each run only if the one before succeeded
+    #[serde(rename = "and")]
+    And,
+
+    /// One per line
+    #[serde(rename = "newline")]
+    Newline,
+}
+
+#[derive(Clone, Debug, Deserialize, Copy, PartialEq, Eq, Serialize)]
+pub enum CopyFormat {
+    /// As it was run
+    #[serde(rename = "raw")]
+    Raw,
+
+    /// Quoted as one word for the shell, to paste into another command
+    #[serde(rename = "escaped")]
+    Escaped,
+
+    /// After a `cd` to the directory it was run in
+    #[serde(rename = "cd")]
+    Cd,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -566,6 +752,210 @@ pub struct Tmux {
     pub height: String,
 }
 
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Output {
+    /// The most output `atuin run` keeps per command, in bytes (before
+    /// compression). Past this, only the start and end of the output are kept.
+    pub max_bytes: usize,
+
+    /// How much of the start of each output is indexed for `in:output`
+    /// searches, in bytes. 0 turns indexing off.
+    pub search_max_bytes: usize,
+}
+
+/// For shared and admin machines that need an audit trail of the commands run on them.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Compliance {
+    /// Never delete history, record every command (bar those that look like secrets), and
+    /// note the account that ran each one.
+    pub append_only: bool,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Archive {
+    /// Move history older than this (eg "1y") out of the history database and
+    /// into the archive. Empty turns archiving off.
+    pub after: String,
+
+    /// Path to the archive database.
+    pub db_path: PathBuf,
+}
+
+impl Archive {
+    /// How old history has to be to be archived, if archiving is on
+    pub fn age(&self) -> Result<Option<time::Duration>> {
+        if self.after.is_empty() {
+            return Ok(None);
+        }
+
+        let age = parse_duration(&self.after)
+            .map_err(|e| eyre!("invalid archive.after {:?}: {e}", self.after))?;
+        Ok(Some(time::Duration::try_from(age)?))
+    }
+}
+
+/// How long a request may take, by what it's for, in seconds. Those not set
+/// use `network_timeout`.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct NetworkTimeouts {
+    /// Syncing records, and the sync status
+    pub sync: Option<u64>,
+
+    /// Logging in, registering and changing account details
+    pub auth: Option<u64>,
+
+    /// Looking up the latest version of Atuin
+    pub update_check: Option<u64>,
+
+    /// Downloading a release for `atuin self-update`. This bounds how long
+    /// the download may go without receiving anything, rather than how long
+    /// it takes altogether.
+    pub download: Option<u64>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Network {
+    /// How many times to retry a request that failed in a way that may not
+    /// happen again: it couldn't connect, timed out, or the server was busy.
+    pub retries: u32,
+
+    /// Seconds to wait before the first retry. Each retry waits twice as long
+    /// as the one before, up to `retry_backoff_max`, less a random amount of up
+    /// to half so that clients don't all retry at once.
+    pub retry_backoff: f64,
+
+    /// The longest to wait between retries, in seconds.
+    pub retry_backoff_max: f64,
+
+    /// A proxy to send requests through, such as `http://proxy:3128` or
+    /// `socks5h://localhost:1080`. Empty uses the `HTTPS_PROXY`,
+    /// `HTTP_PROXY` and `ALL_PROXY` environment variables.
+    pub proxy: String,
+
+    /// Hosts to connect to directly rather than through `proxy`, separated
+    /// by commas. Empty uses the `NO_PROXY` environment variable.
+    pub no_proxy: String,
+
+    #[serde(default)]
+    pub timeouts: NetworkTimeouts,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Tls {
+    /// A file of PEM certificates to trust as well as the system's, such as
+    /// the root of a company's TLS-inspecting proxy.
+    pub ca_file: String,
+
+    /// A PEM client certificate to present to the sync server, for servers
+    /// that only let in clients they issued a certificate to.
+    pub client_cert: String,
+
+    /// The PEM private key for `client_cert`, if it isn't in the same file.
+    pub client_key: String,
+
+    /// Accept any certificate at all. Only for testing: anyone in between
+    /// can read and change what's sent.
+    pub insecure: bool,
+}
+
+/// Where `atuin self-update` looks for releases by default: the latest one on GitHub.
+pub const DEFAULT_SELF_UPDATE_ENDPOINT: &str =
+    "https://api.github.com/repos/atuinsh/atuin/releases/latest";
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SelfUpdate {
+    /// A URL serving the latest release as JSON, in the shape of GitHub's
+    /// `releases/latest` API.
+    pub endpoint: String,
+
+    /// The minisign public key releases must be signed with. Empty uses the
+    /// key Atuin was built with, if any.
+    pub public_key: String,
+}
+
+/// Patterns matching the parts of a command that usually differ between runs
+/// (UUIDs, dates, temporary paths).
+pub const DEFAULT_PLACEHOLDER_PATTERNS: &[&str] = &[
+    r"\b[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}\b",
+    r"\b\d{4}-\d{2}-\d{2}(?:[T ]\d{2}:\d{2}(?::\d{2})?(?:Z|[+-]\d{2}:?\d{2})?)?\b",
+    r#"(?:/tmp|/var/tmp|/private/var/folders|/var/folders)/[^\s'"]+"#,
+];
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Placeholders {
+    /// Stop at each placeholder in an accepted command so it can be replaced
+    /// before the command is returned to the shell.
+    pub enabled: bool,
+
+    /// Ask for the value of each `{{name}}` field of an accepted command before
+    /// it's returned to the shell.
+    pub templates: bool,
+
+    /// What counts as a placeholder.
+    #[serde(with = "serde_regex", skip_serializing)]
+    pub patterns: Vec<Regex>,
+}
+
+/// A command the interactive search's "open with" menu can run for the selected entry.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct OpenWith {
+    /// What the menu shows.
+    pub name: String,
+
+    /// Run with `sh -c` in the entry's directory. `{cwd}`, `{files}` and
+    /// `{command}` are replaced with the entry's directory, the arguments of its
+    /// command that are files there, and the command itself, each quoted for the
+    /// shell.
+    pub command: String,
+}
+
+impl OpenWith {
+    fn new(name: &str, command: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            command: command.to_string(),
+        }
+    }
+
+    fn defaults() -> Vec<Self> {
+        let file_manager = if cfg!(target_os = "macos") {
+            "open {cwd}"
+        } else {
+            "xdg-open {cwd}"
+        };
+
+        vec![
+            Self::new("Directory in editor", "${VISUAL:-${EDITOR:-vi}} {cwd}"),
+            Self::new("Files in editor", "${VISUAL:-${EDITOR:-vi}} {files}"),
+            Self::new("File manager", file_manager),
+        ]
+    }
+}
+
+/// An external source of commands for interactive search, such as a team's runbooks or the
+/// targets of the Makefile in the directory.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Source {
+    /// What its commands are badged with in the search.
+    pub name: String,
+
+    /// Run with `sh -c`, or `cmd /C` on Windows, in the directory searched from. Each
+    /// line it prints is a JSON object with a `command`, and optionally a `description`
+    /// of it.
+    pub command: String,
+
+    /// How many seconds what it printed is kept for, per directory, before it's
+    /// run again (default: 300).
+    #[serde(default = "Source::default_cache_ttl")]
+    pub cache_ttl: u64,
+}
+
+impl Source {
+    const fn default_cache_ttl() -> u64 {
+        300
+    }
+}
+
 /// Configuration for a specific log type (search or daemon).
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct LogConfig {
@@ -578,6 +968,9 @@ pub struct LogConfig {
     /// Override global level setting for this log type.
     pub level: Option<LogLevel>,
 
+    /// Override global format setting for this log type.
+    pub format: Option<LogFormat>,
+
     /// Override global retention days setting for this log type.
     pub retention: Option<u64>,
 }
@@ -597,7 +990,7 @@ pub struct Logs {
     #[serde(default = "Logs::default_enabled")]
     pub enabled: bool,
 
-    /// Directory for log files. Defaults to ~/.atuin/logs
+    /// Directory for log files. Defaults to ~/.local/state/atuin/logs
     pub dir: String,
 
     /// Default log level for file logging. Defaults to "info".
@@ -605,6 +998,10 @@ pub struct Logs {
     #[serde(default)]
     pub level: LogLevel,
 
+    /// Default format for log files: text, or JSON for log collectors.
+    #[serde(default)]
+    pub format: LogFormat,
+
     /// Default retention days for log files. Defaults to 4.
     #[serde(default = "Logs::default_retention")]
     pub retention: u64,
@@ -733,6 +1130,54 @@ impl Default for Daemon {
             pidfile_path: "".to_string(),
             systemd_socket: false,
             tcp_port: 8889,
+            api_enabled: false,
+            api_port: 8890,
+            api_token_path: "".to_string(),
+            otel: OtelConfig::default(),
+        }
+    }
+}
+
+impl Default for Placeholders {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            templates: true,
+            patterns: DEFAULT_PLACEHOLDER_PATTERNS
+                .iter()
+                .map(|p| Regex::new(p).expect("default placeholder patterns are valid"))
+                .collect(),
+        }
+    }
+}
+
+impl Default for Output {
+    fn default() -> Self {
+        Self {
+            max_bytes: 1 << 20,
+            search_max_bytes: 16 << 10,
+        }
+    }
+}
+
+impl Default for Network {
+    fn default() -> Self {
+        Self {
+            retries: 2,
+            retry_backoff: 0.5,
+            retry_backoff_max: 8.0,
+            proxy: String::new(),
+            no_proxy: String::new(),
+            timeouts: NetworkTimeouts::default(),
+        }
+    }
+}
+
+impl Default for SelfUpdate {
+    fn default() -> Self {
+        Self {
+            endpoint: DEFAULT_SELF_UPDATE_ENDPOINT.to_string(),
+            public_key: String::new(),
         }
     }
 }
@@ -743,6 +1188,7 @@ impl Default for Logs {
             enabled: true,
             dir: "".to_string(),
             level: LogLevel::default(),
+            format: LogFormat::default(),
             retention: Self::default_retention(),
             search: LogConfig::new("search.log"),
             daemon: LogConfig::new("daemon.log"),
@@ -767,6 +1213,7 @@ impl Default for Search {
             filters: vec![
                 FilterMode::Global,
                 FilterMode::Host,
+                FilterMode::OtherHosts,
                 FilterMode::Session,
                 FilterMode::SessionPreload,
                 FilterMode::Workspace,
@@ -776,6 +1223,20 @@ impl Default for Search {
             recency_score_multiplier: 1.0,
             frequency_score_multiplier: 1.0,
             frecency_score_multiplier: 1.0,
+            frecency_half_life: 7.0,
+            frecency_frequency_weight: 1.0,
+            sequence_boost: false,
+            directory_boost: 1.0,
+            workspace_boost: 0.5,
+            multi_select: false,
+            join_marked: JoinMarked::And,
+            copy_format: CopyFormat::Raw,
+            group_duplicates: false,
+            exclude_containers: false,
+            remember_filter_mode: true,
+            mouse: false,
+            debounce_ms: 0,
+            sources: SourceRanking::default(),
         }
     }
 }
@@ -961,6 +1422,16 @@ pub struct Ui {
     /// Syntax highlight commands in the interactive search results.
     #[serde(default = "Ui::default_syntax_highlight")]
     pub syntax_highlight: bool,
+
+    /// Wrap the selected command onto as many rows as it needs, rather than
+    /// shortening it to fit on one.
+    #[serde(default)]
+    pub wrap_selected: bool,
+
+    /// Show a panel beside the results with the commands most often run right before or
+    /// after the selected one.
+    #[serde(default)]
+    pub show_related: bool,
 }
 
 impl Ui {
@@ -995,6 +1466,8 @@ impl Default for Ui {
         Self {
             columns: Self::default_columns(),
             syntax_highlight: Self::default_syntax_highlight(),
+            wrap_selected: false,
+            show_related: false,
         }
     }
 }
@@ -1033,6 +1506,7 @@ pub struct Settings {
     pub max_preview_height: u16,
     pub show_help: bool,
     pub show_tabs: bool,
+    pub show_status: bool,
     pub show_numeric_shortcuts: bool,
     pub auto_hide_height: u16,
     pub exit_mode: ExitMode,
@@ -1054,6 +1528,10 @@ pub struct Settings {
     #[serde(with = "serde_regex", default = "RegexSet::empty", skip_serializing)]
     pub cwd_filter: RegexSet,
 
+    /// Commands the interactive search won't accept, only copy or edit
+    #[serde(with = "serde_regex", default = "RegexSet::empty", skip_serializing)]
+    pub blocked_commands: RegexSet,
+
     pub secrets_filter: bool,
     pub workspaces: bool,
     pub ctrl_n_shortcuts: bool,
@@ -1062,6 +1540,13 @@ pub struct Settings {
     pub network_timeout: u64,
     pub local_timeout: f64,
 
+    /// Never use the network: no syncing, and no update checks.
+    pub offline: bool,
+
+    /// Check the sync server can be reached before using the network, and
+    /// behave as if offline when it can't.
+    pub offline_detection: bool,
+
     /// Extra HTTP headers to send on every request to the sync server, e.g.
     /// for services like Cloudflare Access that sit in front of a self-hosted
     /// server. Headers that Atuin sets itself (e.g. Authorization) win over
@@ -1109,6 +1594,35 @@ pub struct Settings {
     #[serde(default)]
     pub tmux: Tmux,
 
+    #[serde(default)]
+    pub output: Output,
+
+    #[serde(default)]
+    pub archive: Archive,
+
+    #[serde(default)]
+    pub compliance: Compliance,
+
+    #[serde(default)]
+    pub network: Network,
+
+    #[serde(default)]
+    pub tls: Tls,
+
+    #[serde(default)]
+    pub self_update: SelfUpdate,
+
+    #[serde(default)]
+    pub placeholders: Placeholders,
+
+    /// The commands in the interactive search's "open with" menu.
+    #[serde(default = "OpenWith::defaults")]
+    pub open_with: Vec<OpenWith>,
+
+    /// External commands whose output interactive search suggests commands from.
+    #[serde(default)]
+    pub sources: Vec<Source>,
+
     #[serde(default)]
     pub logs: Logs,
 
@@ -1135,7 +1649,7 @@ impl Settings {
         DATA_DIR
             .get()
             .cloned()
-            .unwrap_or_else(atuin_common::utils::data_dir)
+            .unwrap_or_else(atuin_common::paths::data_dir)
     }
 
     // -- Meta store: lazily initialized on first access --
@@ -1163,6 +1677,22 @@ impl Settings {
         Self::meta_store().await?.save_sync_time().await
     }
 
+    pub async fn last_sync_started() -> Result<OffsetDateTime> {
+        Self::meta_store().await?.last_sync_started().await
+    }
+
+    pub async fn save_sync_started_time() -> Result<()> {
+        Self::meta_store().await?.save_sync_started_time().await
+    }
+
+    pub async fn clock_skew() -> Result<Option<time::Duration>> {
+        Self::meta_store().await?.clock_skew().await
+    }
+
+    pub async fn save_clock_skew(skew: time::Duration) -> Result<()> {
+        Self::meta_store().await?.save_clock_skew(skew).await
+    }
+
     pub async fn last_version_check() -> Result<OffsetDateTime> {
         Self::meta_store().await?.last_version_check().await
     }
@@ -1171,8 +1701,34 @@ impl Settings {
         Self::meta_store().await?.save_version_check_time().await
     }
 
-    pub async fn should_sync(&self) -> Result<bool> {
-        if !self.auto_sync || !Self::meta_store().await?.logged_in().await? {
+    /// Refuses, in append-only mode, anything that would remove history: deleting entries,
+    /// pruning, deduplicating, rewriting and purging.
+    ///
+    /// This is a guard in the client, not a security boundary. It stops history being removed
+    /// through Atuin, by accident or to tidy up, and history removed from the history database
+    /// behind its back is still in the record store, which is what syncs. It can't stop someone
+    /// who can write to the config or the database files, so for auditing those should belong
+    /// to an admin.
+    pub fn ensure_history_removable(&self) -> Result<()> {
+        if self.compliance.append_only {
+            bail!("history can't be removed while `compliance.append_only` is set");
+        }
+        Ok(())
+    }
+
+    /// Whether a command that failed is kept, as it always is in append-only mode.
+    pub fn keeps_failed(&self, exit: i64) -> bool {
+        self.store_failed || exit <= 0 || self.compliance.append_only
+    }
+
+    /// Whether it's time for an automatic sync, without checking the sync
+    /// server can be reached
+    pub async fn sync_due(&self) -> Result<bool> {
+        if !self.auto_sync
+            || self.offline
+            || crate::read_only::is_enabled()
+            || !Self::meta_store().await?.logged_in().await?
+        {
             return Ok(false);
         }
 
@@ -1189,6 +1745,54 @@ impl Settings {
         }
     }
 
+    /// Whether to start a sync in the background after a command: one is due, and none was
+    /// started within `sync_frequency`. The last sync is only recorded once it succeeds, so
+    /// without this, every command would start another while the server can't be reached.
+    pub async fn background_sync_due(&self) -> Result<bool> {
+        if !self.sync_due().await? {
+            return Ok(false);
+        }
+
+        if self.sync_frequency == "0" {
+            return Ok(true);
+        }
+
+        match parse_duration(self.sync_frequency.as_str()) {
+            Ok(d) => {
+                let d = time::Duration::try_from(d)?;
+                Ok(OffsetDateTime::now_utc() - Settings::last_sync_started().await? >= d)
+            }
+            Err(e) => Err(eyre!("failed to check sync: {}", e)),
+        }
+    }
+
+    pub async fn should_sync(&self) -> Result<bool> {
+        if !self.sync_due().await? {
+            return Ok(false);
+        }
+
+        if let Some(offline) = crate::network::check(self).await {
+            debug!("not syncing: {}", offline.describe());
+            return Ok(false);
+        }
+
+        Ok(true)
+    }
+
+    /// The timeout for a `kind` of request, in seconds
+    pub fn network_timeout_for(&self, kind: crate::network::RequestKind) -> u64 {
+        use crate::network::RequestKind;
+
+        let timeouts = &self.network.timeouts;
+        match kind {
+            RequestKind::Sync => timeouts.sync,
+            RequestKind::Auth => timeouts.auth,
+            RequestKind::UpdateCheck => timeouts.update_check,
+            RequestKind::Download => timeouts.download,
+        }
+        .unwrap_or(self.network_timeout)
+    }
+
     pub async fn logged_in(&self) -> Result<bool> {
         Self::meta_store().await?.logged_in().await
     }
@@ -1365,7 +1969,7 @@ impl Settings {
     // Return Some(latest version) if an update is needed. Otherwise, none.
     #[cfg(feature = "check-update")]
     pub async fn needs_update(&self) -> Option<Version> {
-        if !self.update_check {
+        if !self.update_check || self.offline {
             return None;
         }
 
@@ -1410,7 +2014,7 @@ impl Settings {
     }
 
     pub fn builder() -> Result<ConfigBuilder<DefaultState>> {
-        Self::builder_with_data_dir(&atuin_common::utils::data_dir())
+        Self::builder_with_data_dir(&atuin_common::paths::data_dir())
     }
 
     fn builder_with_data_dir(data_dir: &std::path::Path) -> Result<ConfigBuilder<DefaultState>> {
@@ -1418,10 +2022,12 @@ impl Settings {
         let record_store_path = data_dir.join("records.db");
         let kv_path = data_dir.join("kv.db");
         let scripts_path = data_dir.join("scripts.db");
+        let archive_path = data_dir.join("archive.db");
         let ai_sessions_path = data_dir.join("ai_sessions.db");
-        let socket_path = atuin_common::utils::runtime_dir().join("atuin.sock");
+        let socket_path = atuin_common::paths::runtime_dir().join("atuin.sock");
         let pidfile_path = data_dir.join("atuin-daemon.pid");
-        let logs_dir = atuin_common::utils::logs_dir();
+        let api_token_path = data_dir.join("daemon_api_token");
+        let logs_dir = atuin_common::paths::logs_dir();
 
         let key_path = data_dir.join("key");
         let meta_path = data_dir.join("meta.db");
@@ -1430,6 +2036,8 @@ impl Settings {
             .set_default("history_format", "{time}\t{command}\t{duration}")?
             .set_default("db_path", db_path.to_str())?
             .set_default("record_store_path", record_store_path.to_str())?
+            .set_default("archive.db_path", archive_path.to_str())?
+            .set_default("archive.after", "")?
             .set_default("key_path", key_path.to_str())?
             .set_default("dialect", "us")?
             .set_default("timezone", "local")?
@@ -1446,6 +2054,7 @@ impl Settings {
             .set_default("max_preview_height", 4)?
             .set_default("show_help", true)?
             .set_default("show_tabs", true)?
+            .set_default("show_status", true)?
             .set_default("show_numeric_shortcuts", true)?
             .set_default("auto_hide_height", 8)?
             .set_default("invert", false)?
@@ -1463,6 +2072,8 @@ impl Settings {
             .set_default("strip_trailing_whitespace", true)?
             .set_default("network_connect_timeout", 5)?
             .set_default("network_timeout", 30)?
+            .set_default("offline", false)?
+            .set_default("offline_detection", true)?
             .set_default("extra_headers", HashMap::<String, String>::new())?
             .set_default("local_timeout", 2.0)?
             // enter_accept defaults to false here, but true in the default config file. The dissonance is
@@ -1490,9 +2101,32 @@ impl Settings {
             .set_default("daemon.pidfile_path", pidfile_path.to_str())?
             .set_default("daemon.systemd_socket", false)?
             .set_default("daemon.tcp_port", 8889)?
+            .set_default("daemon.api_enabled", false)?
+            .set_default("daemon.api_port", 8890)?
+            .set_default("daemon.api_token_path", api_token_path.to_str())?
+            .set_default("output.max_bytes", 1 << 20)?
+            .set_default("output.search_max_bytes", 16 << 10)?
+            .set_default("network.retries", 2)?
+            .set_default("network.retry_backoff", 0.5)?
+            .set_default("network.retry_backoff_max", 8.0)?
+            .set_default("network.proxy", "")?
+            .set_default("network.no_proxy", "")?
+            .set_default("tls.ca_file", "")?
+            .set_default("tls.client_cert", "")?
+            .set_default("tls.client_key", "")?
+            .set_default("tls.insecure", false)?
+            .set_default("self_update.endpoint", DEFAULT_SELF_UPDATE_ENDPOINT)?
+            .set_default("self_update.public_key", "")?
+            .set_default("placeholders.enabled", false)?
+            .set_default("placeholders.templates", true)?
+            .set_default(
+                "placeholders.patterns",
+                DEFAULT_PLACEHOLDER_PATTERNS.to_vec(),
+            )?
             .set_default("logs.enabled", true)?
             .set_default("logs.dir", logs_dir.to_str())?
             .set_default("logs.level", "info")?
+            .set_default("logs.format", "text")?
             .set_default("logs.search.file", "search.log")?
             .set_default("logs.daemon.file", "daemon.log")?
             .set_default("logs.ai.file", "ai.log")?
@@ -1501,6 +2135,19 @@ impl Settings {
             .set_default("search.recency_score_multiplier", 1.0)?
             .set_default("search.frequency_score_multiplier", 1.0)?
             .set_default("search.frecency_score_multiplier", 1.0)?
+            .set_default("search.frecency_half_life", 7.0)?
+            .set_default("search.frecency_frequency_weight", 1.0)?
+            .set_default("search.sequence_boost", false)?
+            .set_default("search.directory_boost", 1.0)?
+            .set_default("search.workspace_boost", 0.5)?
+            .set_default("search.multi_select", false)?
+            .set_default("search.join_marked", "and")?
+            .set_default("search.copy_format", "raw")?
+            .set_default("search.group_duplicates", false)?
+            .set_default("search.exclude_containers", false)?
+            .set_default("search.remember_filter_mode", true)?
+            .set_default("search.mouse", false)?
+            .set_default("search.debounce_ms", 0)?
             .set_default("meta.db_path", meta_path.to_str())?
             .set_default("ai.db_path", ai_sessions_path.to_str())?
             .set_default("ai.session_continue_minutes", 60)?
@@ -1508,6 +2155,8 @@ impl Settings {
             .set_default("ai.opening.send_cwd", false)?
             .set_default("ai.opening.send_last_command", false)?
             .set_default("ui.syntax_highlight", true)?
+            .set_default("ui.wrap_selected", false)?
+            .set_default("ui.show_related", false)?
             .set_default(
                 "search.filters",
                 vec![
@@ -1540,7 +2189,7 @@ impl Settings {
     }
 
     pub fn get_config_path() -> Result<PathBuf> {
-        let config_dir = atuin_common::utils::config_dir();
+        let config_dir = atuin_common::paths::config_dir();
 
         create_dir_all(&config_dir)
             .wrap_err_with(|| format!("could not create dir {config_dir:?}"))?;
@@ -1561,10 +2210,12 @@ impl Settings {
     /// Build a merged `Config` from defaults, config file, and environment.
     ///
     /// This resolves `data_dir`, initializes the data directory on disk,
-    /// and layers defaults → config file → env overrides. Both `new()` and
-    /// `get_config_value()` use this so the resolution logic lives in one place.
+    /// and layers defaults → config file, with its includes and host sections
+    /// → env overrides. Both `new()` and `get_config_value()` use this so the
+    /// resolution logic lives in one place.
     fn build_config() -> Result<Config> {
         let config_file = Self::get_config_path()?;
+        let hostname = crate::utils::get_hostname();
 
         // extract data_dir first so we can use it as the base for other path defaults
         let effective_data_dir = if config_file.exists() {
@@ -1573,19 +2224,22 @@ impl Settings {
                 data_dir: Option<String>,
             }
 
-            let config_file_str = config_file
-                .to_str()
-                .ok_or_else(|| eyre!("config file path is not valid UTF-8"))?;
-
-            let partial_config = Config::builder()
-                .add_source(ConfigFile::new(config_file_str, FileFormat::Toml))
-                .add_source(
-                    Environment::with_prefix("atuin")
-                        .prefix_separator("_")
-                        .separator("__"),
-                )
-                .build()
-                .ok();
+            let partial_config = layers::config_layers(&config_file, &hostname)
+                .ok()
+                .and_then(|layers| {
+                    layers
+                        .into_iter()
+                        .fold(Config::builder(), |builder, layer| {
+                            builder.add_source(layer)
+                        })
+                        .add_source(
+                            Environment::with_prefix("atuin")
+                                .prefix_separator("_")
+                                .separator("__"),
+                        )
+                        .build()
+                        .ok()
+                });
 
             let custom_data_dir = partial_config
                 .and_then(|c| c.try_deserialize::<DataDirOnly>().ok())
@@ -1597,10 +2251,10 @@ impl Settings {
                         .map_err(|e| eyre!("failed to expand data_dir path: {}", e))?;
                     PathBuf::from(expanded.as_ref())
                 }
-                None => atuin_common::utils::data_dir(),
+                None => atuin_common::paths::data_dir(),
             }
         } else {
-            atuin_common::utils::data_dir()
+            atuin_common::paths::data_dir()
         };
 
         DATA_DIR.set(effective_data_dir.clone()).ok();
@@ -1611,10 +2265,9 @@ impl Settings {
         let mut config_builder = Self::builder_with_data_dir(&effective_data_dir)?;
 
         config_builder = if config_file.exists() {
-            let config_file_str = config_file
-                .to_str()
-                .ok_or_else(|| eyre!("config file path is not valid UTF-8"))?;
-            config_builder.add_source(ConfigFile::new(config_file_str, FileFormat::Toml))
+            layers::config_layers(&config_file, &hostname)?
+                .into_iter()
+                .fold(config_builder, |builder, layer| builder.add_source(layer))
         } else {
             let mut file = File::create(config_file).wrap_err("could not create config file")?;
             file.write_all(EXAMPLE_CONFIG.as_bytes())
@@ -1628,12 +2281,17 @@ impl Settings {
         config_builder = [
             "db_path",
             "record_store_path",
+            "archive.db_path",
             "key_path",
             "daemon.socket_path",
             "daemon.pidfile_path",
+            "daemon.api_token_path",
             "logs.dir",
             "logs.search.file",
             "logs.daemon.file",
+            "tls.ca_file",
+            "tls.client_cert",
+            "tls.client_key",
         ]
         .iter()
         .map(|key| (key, built.get_string(key).unwrap_or_default()))
@@ -1731,9 +2389,74 @@ impl Settings {
             .set((settings.meta.db_path.clone(), settings.local_timeout))
             .ok();
 
+        // Requests made without settings to hand follow these too
+        crate::network::configure(&settings);
+
+        // Files older versions kept elsewhere move to where they're looked for now
+        if !crate::read_only::is_enabled() {
+            settings.relocate_once();
+        }
+
         Ok(settings)
     }
 
+    /// Move the files in [`Self::relocations`] to their new homes. This happens once: a marker
+    /// in the state directory records that it's done, so the shell hooks, which load the
+    /// settings for every command, don't have to look for old files each time. Anything left
+    /// behind is listed by `atuin doctor paths`.
+    fn relocate_once(&self) {
+        let marker = paths::state_dir().join(RELOCATED_MARKER);
+        if marker.exists() {
+            return;
+        }
+
+        let mut done = true;
+        for relocation in self.relocations() {
+            match paths::relocate(&relocation) {
+                Ok(0) => {}
+                Ok(moved) => tracing::info!(
+                    "moved {moved} {} files from {:?} to {:?}",
+                    relocation.name,
+                    relocation.from,
+                    relocation.to
+                ),
+                Err(e) => {
+                    // Try again next time
+                    done = false;
+                    tracing::warn!(
+                        "could not move {} from {:?} to {:?}: {e}",
+                        relocation.name,
+                        relocation.from,
+                        relocation.to
+                    );
+                }
+            }
+        }
+
+        if done {
+            let written = create_dir_all(paths::state_dir()).and_then(|()| File::create(&marker));
+            if let Err(e) = written {
+                tracing::debug!("could not record that old files were moved: {e}");
+            }
+        }
+    }
+
+    /// Where older versions kept files that live somewhere else now, and where they go.
+    pub fn relocations(&self) -> Vec<Relocation> {
+        vec![
+            Relocation {
+                name: "log",
+                from: paths::legacy_logs_dir(),
+                to: PathBuf::from(&self.logs.dir),
+            },
+            Relocation {
+                name: "dotfiles cache",
+                from: paths::legacy_dotfiles_cache_dir(),
+                to: paths::dotfiles_cache_dir(),
+            },
+        ]
+    }
+
     fn expand_path(path: String) -> Result<String> {
         shellexpand::full(&path)
             .map(|p| p.to_string())
@@ -1799,7 +2522,7 @@ mod tests {
     use eyre::Result;
     use rstest::rstest;
 
-    use super::{AiEndpointProtocol, Settings, Timezone};
+    use super::{AiEndpointProtocol, FilterMode, Settings, Timezone};
     use url::Url;
 
     #[rstest]
@@ -1933,10 +2656,12 @@ mod tests {
         let key_path: String = config.get("key_path")?;
         let record_store_path: String = config.get("record_store_path")?;
         let kv_db_path: String = config.get("kv.db_path")?;
+        let archive_db_path: String = config.get("archive.db_path")?;
         let scripts_db_path: String = config.get("scripts.db_path")?;
         let meta_db_path: String = config.get("meta.db_path")?;
         let daemon_socket_path: String = config.get("daemon.socket_path")?;
         let daemon_pidfile_path: String = config.get("daemon.pidfile_path")?;
+        let daemon_api_token_path: String = config.get("daemon.api_token_path")?;
         let daemon_autostart: bool = config.get("daemon.autostart")?;
 
         assert_eq!(db_path, custom_dir.join("history.db").to_str().unwrap());
@@ -1946,6 +2671,10 @@ mod tests {
             custom_dir.join("records.db").to_str().unwrap()
         );
         assert_eq!(kv_db_path, custom_dir.join("kv.db").to_str().unwrap());
+        assert_eq!(
+            archive_db_path,
+            custom_dir.join("archive.db").to_str().unwrap()
+        );
         assert_eq!(
             scripts_db_path,
             custom_dir.join("scripts.db").to_str().unwrap()
@@ -1953,7 +2682,7 @@ mod tests {
         assert_eq!(meta_db_path, custom_dir.join("meta.db").to_str().unwrap());
         assert_eq!(
             daemon_socket_path,
-            atuin_common::utils::runtime_dir()
+            atuin_common::paths::runtime_dir()
                 .join("atuin.sock")
                 .to_str()
                 .unwrap()
@@ -1962,6 +2691,10 @@ mod tests {
             daemon_pidfile_path,
             custom_dir.join("atuin-daemon.pid").to_str().unwrap()
         );
+        assert_eq!(
+            daemon_api_token_path,
+            custom_dir.join("daemon_api_token").to_str().unwrap()
+        );
         assert!(!daemon_autostart);
 
         Ok(())
@@ -1970,7 +2703,7 @@ mod tests {
     #[test]
     fn effective_data_dir_returns_default_when_not_set() {
         let effective = super::Settings::effective_data_dir();
-        let default = atuin_common::utils::data_dir();
+        let default = atuin_common::paths::data_dir();
 
         assert!(effective.to_str().is_some());
         assert!(effective.ends_with("atuin") || effective == default);
@@ -2039,4 +2772,33 @@ mod tests {
         assert!(config.vim_insert.is_empty());
         assert!(config.prefix.is_empty());
     }
+
+    #[test]
+    fn source_ranking_is_overridden_by_filter_mode() {
+        let json = r#"{
+            "weights": {"task": 2.0, "kv": 0, "Runbooks": 3.0},
+            "pinned": ["snippet"],
+            "filter_modes": {"directory": {"weights": {"task": 4.0}, "pinned": ["make", "task"]}}
+        }"#;
+        let ranking: super::SourceRanking = serde_json::from_str(json).unwrap();
+
+        assert_eq!(ranking.weight(FilterMode::Global, &["make", "task"]), 2.0);
+        assert_eq!(
+            ranking.weight(FilterMode::Directory, &["make", "task"]),
+            4.0
+        );
+        assert_eq!(ranking.weight(FilterMode::Directory, &["kv"]), 0.0);
+        assert_eq!(
+            ranking.weight(FilterMode::Global, &["runbooks", "source"]),
+            3.0
+        );
+        assert_eq!(ranking.weight(FilterMode::Global, &["alias"]), 1.0);
+
+        assert_eq!(ranking.pinned(FilterMode::Global, &["snippet"]), Some(0));
+        assert_eq!(ranking.pinned(FilterMode::Directory, &["snippet"]), None);
+        assert_eq!(
+            ranking.pinned(FilterMode::Directory, &["just", "task"]),
+            Some(1)
+        );
+    }
 }
@@ -0,0 +1,218 @@
+//! The files a config is read from, so one set of dotfiles can serve many machines.
+//!
+//! Besides its own settings, a config file can list others to read first with
+//! `include = ["~/.config/atuin/work.toml"]`, and hold `[host."laptop-*"]` sections that only
+//! apply on machines whose name matches. From lowest precedence to highest, a file's layers are
+//! the files it includes, in the order they're listed, then the file itself, then its matching
+//! host sections, from the least specific pattern to the most.
+
+use std::path::{Path, PathBuf};
+
+use config::{ConfigError, File as ConfigFile, FileFormat, Map, Source, Value};
+use eyre::{Result, bail, eyre};
+
+/// Settings from one config file, or one of its host sections.
+#[derive(Clone, Debug)]
+pub(super) struct Layer(Map<String, Value>);
+
+impl Source for Layer {
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn collect(&self) -> Result<Map<String, Value>, ConfigError> {
+        Ok(self.0.clone())
+    }
+}
+
+/// The layers of the config file at `path` on the machine called `hostname`, lowest
+/// precedence first.
+pub(super) fn config_layers(path: &Path, hostname: &str) -> Result<Vec<Layer>> {
+    let mut layers = Vec::new();
+    let mut reading = Vec::new();
+    file_layers(path, hostname, &mut reading, &mut layers)?;
+    Ok(layers)
+}
+
+fn file_layers(
+    path: &Path,
+    hostname: &str,
+    reading: &mut Vec<PathBuf>,
+    layers: &mut Vec<Layer>,
+) -> Result<()> {
+    let canonical = fs_err::canonicalize(path)?;
+    if reading.contains(&canonical) {
+        bail!("config file {path:?} includes itself");
+    }
+
+    let mut settings = ConfigFile::from(path)
+        .format(FileFormat::Toml)
+        .collect()
+        .map_err(|e| eyre!("failed to read config file {path:?}: {e}"))?;
+
+    let includes: Vec<String> = match settings.remove("include") {
+        Some(include) => include
+            .into_array()
+            .and_then(|paths| paths.into_iter().map(Value::into_string).collect())
+            .map_err(|e| eyre!("`include` in {path:?} should be a list of paths: {e}"))?,
+        None => Vec::new(),
+    };
+    let hosts = match settings.remove("host") {
+        Some(hosts) => hosts
+            .into_table()
+            .map_err(|e| eyre!("`host` in {path:?} should be a table of sections: {e}"))?,
+        None => Map::new(),
+    };
+
+    reading.push(canonical);
+    for include in includes {
+        let expanded = shellexpand::full(&include)
+            .map_err(|e| eyre!("failed to expand include path {include:?}: {e}"))?;
+
+        // Relative paths are from the including file, wherever atuin is run
+        let included = path
+            .parent()
+            .unwrap_or(Path::new(""))
+            .join(expanded.as_ref());
+
+        // Files only some machines have, like a work config, can be included everywhere
+        if !included.exists() {
+            tracing::debug!("skipping missing config include {included:?}");
+            continue;
+        }
+
+        file_layers(&included, hostname, reading, layers)?;
+    }
+    reading.pop();
+
+    layers.push(Layer(settings));
+
+    let mut matching = Vec::new();
+    for (pattern, section) in hosts {
+        if host_matches(&pattern, hostname) {
+            let section = section
+                .into_table()
+                .map_err(|e| eyre!("[host.{pattern:?}] in {path:?} should be a table: {e}"))?;
+            matching.push((pattern, section));
+        }
+    }
+    matching.sort_by_cached_key(|(pattern, _)| (specificity(pattern), pattern.clone()));
+    layers.extend(matching.into_iter().map(|(_, section)| Layer(section)));
+
+    Ok(())
+}
+
+/// Whether `hostname` matches `pattern`, where `*` stands for any run of characters and `?` for
+/// any one. Host names aren't case sensitive.
+fn host_matches(pattern: &str, hostname: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let hostname: Vec<char> = hostname.to_lowercase().chars().collect();
+
+    let (mut p, mut h) = (0, 0);
+    // Where the last `*` was, and how much of the host name it's taken so far
+    let mut star = None;
+    while h < hostname.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, h));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == hostname[h] => {
+                p += 1;
+                h += 1;
+            }
+            _ => match star {
+                Some((star_p, star_h)) => {
+                    star = Some((star_p, star_h + 1));
+                    p = star_p + 1;
+                    h = star_h + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// How much of a host name `pattern` pins down, so that `laptop-work` wins over `laptop-*`,
+/// which wins over `*`.
+fn specificity(pattern: &str) -> usize {
+    pattern.chars().filter(|&c| c != '*').count()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    fn layer_values(layers: &[Layer], key: &str) -> Vec<String> {
+        layers
+            .iter()
+            .filter_map(|layer| layer.0.get(key))
+            .map(|value| value.clone().into_string().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn host_patterns() {
+        assert!(host_matches("laptop-*", "laptop-work"));
+        assert!(host_matches("laptop-*", "Laptop-Home"));
+        assert!(host_matches("*", "anything"));
+        assert!(host_matches("build-??", "build-01"));
+        assert!(host_matches("*.example.com", "ci.eu.example.com"));
+        assert!(host_matches("desktop", "desktop"));
+
+        assert!(!host_matches("laptop-*", "desktop"));
+        assert!(!host_matches("build-??", "build-001"));
+        assert!(!host_matches("desktop", "desktop-2"));
+    }
+
+    #[test]
+    fn layers_are_merged_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("config.toml"),
+            r#"
+include = ["base.toml", "missing.toml", "more/work.toml"]
+search_mode = "main"
+
+[host."laptop-*"]
+search_mode = "laptops"
+
+[host."laptop-work"]
+search_mode = "this laptop"
+
+[host."desktop"]
+search_mode = "desktop"
+"#,
+        )
+        .unwrap();
+        fs::write(dir.path().join("base.toml"), "search_mode = \"base\"\n").unwrap();
+        fs::create_dir(dir.path().join("more")).unwrap();
+        fs::write(
+            dir.path().join("more/work.toml"),
+            "include = [\"../base.toml\"]\nsearch_mode = \"work\"\n",
+        )
+        .unwrap();
+
+        let layers = config_layers(&dir.path().join("config.toml"), "laptop-work").unwrap();
+
+        assert_eq!(
+            layer_values(&layers, "search_mode"),
+            ["base", "base", "work", "main", "laptops", "this laptop"]
+        );
+        assert!(layers.iter().all(|layer| !layer.0.contains_key("include")));
+        assert!(layers.iter().all(|layer| !layer.0.contains_key("host")));
+    }
+
+    #[test]
+    fn include_cycles_are_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.toml"), "include = [\"b.toml\"]\n").unwrap();
+        fs::write(dir.path().join("b.toml"), "include = [\"a.toml\"]\n").unwrap();
+
+        assert!(config_layers(&dir.path().join("a.toml"), "host").is_err());
+    }
+}
@@ -103,7 +103,7 @@ impl SettingsWatcher {
         let config_dir = if let Ok(p) = std::env::var("ATUIN_CONFIG_DIR") {
             PathBuf::from(p)
         } else {
-            atuin_common::utils::config_dir()
+            atuin_common::paths::config_dir()
         };
         config_dir.join("config.toml")
     }
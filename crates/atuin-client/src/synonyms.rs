@@ -0,0 +1,496 @@
+//! Experimental synonym search over history.
+//!
+//! There's no model here: a built-in thesaurus groups command names with the plain words
+//! people search with, so `tar`, `zip` and "compress" all belong to "archive". Commands and
+//! queries are each turned into a small vector of the groups their words belong to, the
+//! words themselves and their character trigrams, and ranked by the cosine between them.
+//! So `compress folder` finds `tar -czvf out.tgz src/` even though they share no
+//! characters, but only for words the thesaurus knows.
+//!
+//! [`Vectorizer`] is the extension point should a learned model be wired in later.
+
+use std::collections::HashMap;
+
+use eyre::Result;
+
+use crate::{
+    database::{Context, Database, OptFilters},
+    history::History,
+    settings::{FilterMode, SearchMode},
+};
+
+/// Number of dimensions in a [`WordVector`].
+pub const DIMENSIONS: usize = 256;
+
+/// How many of the most recent unique commands are considered as candidates.
+const CANDIDATE_LIMIT: i64 = 10_000;
+
+/// Candidates less similar than this to the query are dropped.
+const MIN_SIMILARITY: f32 = 0.15;
+
+const CONCEPT_WEIGHT: f32 = 1.0;
+const WORD_WEIGHT: f32 = 0.5;
+const TRIGRAM_WEIGHT: f32 = 0.15;
+
+/// Groups of words that mean roughly the same thing at the shell, mixing
+/// command names with the plain-language words people search with.
+static CONCEPTS: &[(&str, &[&str])] = &[
+    (
+        "archive",
+        &[
+            "tar",
+            "zip",
+            "unzip",
+            "gzip",
+            "gunzip",
+            "gz",
+            "tgz",
+            "bzip2",
+            "bz2",
+            "xz",
+            "zstd",
+            "zst",
+            "7z",
+            "rar",
+            "unrar",
+            "compress",
+            "decompress",
+            "extract",
+            "archive",
+            "unpack",
+            "pack",
+        ],
+    ),
+    (
+        "directory",
+        &[
+            "dir",
+            "directory",
+            "directories",
+            "folder",
+            "folders",
+            "mkdir",
+            "rmdir",
+            "cd",
+            "ls",
+            "tree",
+            "pwd",
+        ],
+    ),
+    (
+        "network",
+        &[
+            "curl", "wget", "http", "https", "download", "upload", "request", "ping", "nc",
+            "netstat", "ss", "dig", "nslookup", "port", "ports", "network",
+        ],
+    ),
+    (
+        "remote",
+        &[
+            "ssh", "scp", "rsync", "sftp", "remote", "server", "login", "mosh",
+        ],
+    ),
+    (
+        "delete",
+        &[
+            "rm", "rmdir", "delete", "remove", "erase", "unlink", "prune", "clean", "purge",
+        ],
+    ),
+    (
+        "copy",
+        &["cp", "rsync", "scp", "copy", "duplicate", "clone"],
+    ),
+    ("move", &["mv", "move", "rename"]),
+    (
+        "search",
+        &[
+            "grep", "rg", "ag", "ack", "find", "fd", "locate", "search", "look", "which", "where",
+        ],
+    ),
+    (
+        "vcs",
+        &[
+            "git", "commit", "branch", "merge", "rebase", "checkout", "push", "pull", "stash",
+            "diff", "blame", "jj", "hg",
+        ],
+    ),
+    (
+        "process",
+        &[
+            "ps",
+            "kill",
+            "killall",
+            "pkill",
+            "pgrep",
+            "top",
+            "htop",
+            "btop",
+            "process",
+            "processes",
+            "pid",
+            "jobs",
+        ],
+    ),
+    (
+        "disk",
+        &[
+            "df", "du", "disk", "space", "usage", "size", "mount", "umount", "lsblk", "free",
+        ],
+    ),
+    (
+        "permissions",
+        &[
+            "chmod",
+            "chown",
+            "chgrp",
+            "permission",
+            "permissions",
+            "owner",
+            "sudo",
+            "executable",
+        ],
+    ),
+    (
+        "container",
+        &[
+            "docker",
+            "podman",
+            "kubectl",
+            "k8s",
+            "helm",
+            "container",
+            "containers",
+            "image",
+            "images",
+            "pod",
+            "pods",
+            "compose",
+        ],
+    ),
+    (
+        "package",
+        &[
+            "apt",
+            "brew",
+            "dnf",
+            "yum",
+            "pacman",
+            "npm",
+            "pnpm",
+            "yarn",
+            "pip",
+            "install",
+            "uninstall",
+            "package",
+            "packages",
+            "upgrade",
+            "dependency",
+            "dependencies",
+        ],
+    ),
+    (
+        "edit",
+        &[
+            "vi", "vim", "nvim", "nano", "emacs", "code", "hx", "edit", "editor", "open",
+        ],
+    ),
+    (
+        "view",
+        &[
+            "cat", "less", "more", "head", "tail", "bat", "view", "show", "print", "read",
+        ],
+    ),
+    (
+        "build",
+        &[
+            "make", "cargo", "build", "compile", "cmake", "gcc", "clang", "go", "test", "tests",
+            "run",
+        ],
+    ),
+    (
+        "service",
+        &[
+            "systemctl",
+            "service",
+            "journalctl",
+            "launchctl",
+            "daemon",
+            "start",
+            "stop",
+            "restart",
+            "enable",
+            "disable",
+            "logs",
+        ],
+    ),
+    (
+        "environment",
+        &[
+            "export",
+            "env",
+            "set",
+            "unset",
+            "variable",
+            "variables",
+            "path",
+        ],
+    ),
+    (
+        "time",
+        &[
+            "date", "cal", "time", "sleep", "watch", "cron", "crontab", "schedule",
+        ],
+    ),
+    (
+        "text",
+        &[
+            "sed", "awk", "sort", "uniq", "wc", "cut", "tr", "jq", "replace", "count", "lines",
+            "words", "json",
+        ],
+    ),
+    ("link", &["ln", "link", "symlink", "symlinks"]),
+    (
+        "user",
+        &[
+            "whoami", "id", "useradd", "passwd", "su", "user", "users", "groups",
+        ],
+    ),
+];
+
+/// A unit-length vector; the dot product of two of them is their cosine
+/// similarity.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WordVector(Vec<f32>);
+
+impl WordVector {
+    pub fn similarity(&self, other: &Self) -> f32 {
+        self.0.iter().zip(&other.0).map(|(a, b)| a * b).sum()
+    }
+}
+
+/// Turns text into a [`WordVector`].
+pub trait Vectorizer: Send + Sync {
+    fn vectorize(&self, text: &str) -> WordVector;
+}
+
+/// The built-in vectorizer described in the module docs.
+#[derive(Debug)]
+pub struct Thesaurus {
+    concepts: HashMap<&'static str, Vec<&'static str>>,
+}
+
+impl Default for Thesaurus {
+    fn default() -> Self {
+        let mut concepts: HashMap<&'static str, Vec<&'static str>> = HashMap::new();
+
+        for (concept, words) in CONCEPTS {
+            for word in *words {
+                concepts.entry(word).or_default().push(concept);
+            }
+        }
+
+        Self { concepts }
+    }
+}
+
+impl Vectorizer for Thesaurus {
+    fn vectorize(&self, text: &str) -> WordVector {
+        let mut v = vec![0.0; DIMENSIONS];
+
+        for word in words(text) {
+            if let Some(concepts) = self.concepts.get(word.as_str()) {
+                for concept in concepts {
+                    v[bucket("c", concept)] += CONCEPT_WEIGHT;
+                }
+            }
+
+            v[bucket("w", &word)] += WORD_WEIGHT;
+
+            let chars: Vec<char> = format!(" {word} ").chars().collect();
+            for trigram in chars.windows(3) {
+                let trigram: String = trigram.iter().collect();
+                v[bucket("t", &trigram)] += TRIGRAM_WEIGHT;
+            }
+        }
+
+        let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            v.iter_mut().for_each(|x| *x /= norm);
+        }
+
+        WordVector(v)
+    }
+}
+
+/// Lowercased alphanumeric words; a flag like `-czvf` becomes `czvf`.
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+}
+
+/// FNV-1a, so buckets are stable across runs and platforms.
+fn bucket(namespace: &str, feature: &str) -> usize {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in namespace.bytes().chain([0]).chain(feature.bytes()) {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    (hash % DIMENSIONS as u64) as usize
+}
+
+/// Ranks history by similarity to a query, caching the vectors of commands so
+/// that re-ranking on every keystroke only turns the query into one.
+pub struct SynonymIndex {
+    vectorizer: Box<dyn Vectorizer>,
+    cache: HashMap<String, WordVector>,
+}
+
+impl Default for SynonymIndex {
+    fn default() -> Self {
+        Self::new(Box::new(Thesaurus::default()))
+    }
+}
+
+impl SynonymIndex {
+    pub fn new(vectorizer: Box<dyn Vectorizer>) -> Self {
+        Self {
+            vectorizer,
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Order `candidates` by similarity to `query`, most similar first,
+    /// dropping those that aren't similar at all. With nothing to go by in
+    /// the query, they're left as they came, most recent first.
+    pub fn rank(&mut self, query: &str, candidates: Vec<History>) -> Vec<History> {
+        if words(query).next().is_none() {
+            return candidates;
+        }
+
+        let query = self.vectorizer.vectorize(query);
+
+        let mut scored: Vec<(f32, History)> = candidates
+            .into_iter()
+            .filter_map(|h| {
+                let vector = self
+                    .cache
+                    .entry(h.command.clone())
+                    .or_insert_with(|| self.vectorizer.vectorize(&h.command));
+                let score = query.similarity(vector);
+                (score >= MIN_SIMILARITY).then_some((score, h))
+            })
+            .collect();
+
+        // Stable, so equally similar commands stay most recent first.
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        scored.into_iter().map(|(_, h)| h).collect()
+    }
+
+    /// Search history by synonyms, honouring the same filters as
+    /// [`Database::search`].
+    pub async fn search(
+        &mut self,
+        db: &dyn Database,
+        filter: FilterMode,
+        context: &Context,
+        query: &str,
+        filter_options: OptFilters,
+    ) -> Result<Vec<History>> {
+        let limit = filter_options.limit;
+        let offset = filter_options.offset;
+
+        let candidates = db
+            .search(
+                SearchMode::FullText,
+                filter,
+                context,
+                "",
+                OptFilters {
+                    limit: Some(CANDIDATE_LIMIT),
+                    offset: None,
+                    ..filter_options
+                },
+            )
+            .await?;
+
+        let ranked = self.rank(query, candidates).into_iter();
+        let ranked = ranked.skip(offset.map_or(0, |o| o.max(0) as usize));
+
+        Ok(match limit {
+            Some(limit) => ranked.take(limit.max(0) as usize).collect(),
+            None => ranked.collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(command: &str) -> History {
+        History::capture()
+            .timestamp(time::OffsetDateTime::now_utc())
+            .command(command)
+            .cwd("/")
+            .build()
+            .into()
+    }
+
+    #[test]
+    fn vectors_are_unit_length() {
+        let e = Thesaurus::default().vectorize("tar -czvf out.tgz src/");
+        assert!((e.similarity(&e) - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn empty_text_has_no_similarity() {
+        let vectorizer = Thesaurus::default();
+        let e = vectorizer.vectorize("");
+        assert!(e.similarity(&vectorizer.vectorize("ls")).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn natural_language_finds_commands() {
+        let mut index = SynonymIndex::default();
+        let candidates = vec![
+            history("echo hello"),
+            history("tar -czvf backup.tgz projects/"),
+            history("git status"),
+        ];
+
+        let ranked = index.rank("compress folder", candidates);
+
+        assert_eq!(ranked[0].command, "tar -czvf backup.tgz projects/");
+        assert!(!ranked.iter().any(|h| h.command == "echo hello"));
+    }
+
+    #[test]
+    fn kill_process_prefers_kill() {
+        let mut index = SynonymIndex::default();
+        let candidates = vec![
+            history("cargo build --release"),
+            history("pkill -9 firefox"),
+            history("cat README.md"),
+        ];
+
+        let ranked = index.rank("stop a running process", candidates);
+
+        assert_eq!(ranked[0].command, "pkill -9 firefox");
+    }
+
+    #[test]
+    fn empty_query_keeps_recency_order() {
+        let mut index = SynonymIndex::default();
+        let candidates = vec![
+            history("git status"),
+            history("cargo build --release"),
+            history("ls -la"),
+        ];
+
+        for query in ["", "  ", "--"] {
+            let ranked = index.rank(query, candidates.clone());
+            assert_eq!(ranked, candidates);
+        }
+    }
+}
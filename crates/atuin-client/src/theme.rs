@@ -7,8 +7,11 @@ use std::error;
 use std::io::{Error, ErrorKind};
 use std::path::PathBuf;
 use std::sync::LazyLock;
+use std::time::Duration;
 use strum_macros;
 
+pub mod watcher;
+
 static DEFAULT_MAX_DEPTH: u8 = 10;
 
 // Collection of settable "meanings" that can have colors set.
@@ -33,12 +36,19 @@ pub enum Meaning {
     Muted,
     Highlight,
     Selection,
+    Shortcut,
+    OtherHost,
+    SearchFilter,
+    Pinned,
+    DurationFast,
+    DurationSlow,
     SyntaxCommand,
     SyntaxFlag,
     SyntaxString,
     SyntaxVariable,
     SyntaxOperator,
     SyntaxComment,
+    SyntaxPath,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -48,6 +58,19 @@ pub struct ThemeConfig {
 
     // Colors
     pub colors: HashMap<Meaning, String>,
+
+    // Thresholds for coloring durations on a gradient, if wanted
+    #[serde(default)]
+    pub durations: Option<DurationsConfigBlock>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DurationsConfigBlock {
+    /// Durations up to this are colored as DurationFast (e.g. "1s")
+    pub fast: String,
+
+    /// Durations from this up are colored as DurationSlow (e.g. "1m")
+    pub slow: String,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -59,14 +82,129 @@ pub struct ThemeDefinitionConfigBlock {
     pub parent: Option<String>,
 }
 
-use crossterm::style::{Attribute, Attributes, Color, ContentStyle};
+use crossterm::style::{Attribute, Attributes, Color, Colored, ContentStyle};
+
+// How many colors the terminal can show. Themes are written with RGB colors, and brought down
+// to what the terminal has when they're loaded, so theme authors don't have to.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ColorSupport {
+    // No color at all, as asked for by NO_COLOR or `--no-color`
+    None,
+    Ansi16,
+    Ansi256,
+    #[default]
+    TrueColor,
+}
+
+impl ColorSupport {
+    // What this terminal supports. Colors are off when crossterm has been told not to use them,
+    // which covers both NO_COLOR and `--no-color`.
+    pub fn detect() -> Self {
+        if Colored::ansi_color_disabled_memoized() {
+            return Self::None;
+        }
+        Self::from_env(
+            std::env::var("COLORTERM").ok().as_deref(),
+            std::env::var("TERM").ok().as_deref(),
+            Self::from_terminfo,
+        )
+    }
+
+    // COLORTERM is the surest sign of RGB, then the terminfo entry for TERM, and only then
+    // TERM's name, as terminals without an entry here can still be named after one that has
+    fn from_env(
+        colorterm: Option<&str>,
+        term: Option<&str>,
+        terminfo: impl FnOnce() -> Option<Self>,
+    ) -> Self {
+        match (colorterm, term) {
+            (Some("truecolor" | "24bit"), _) => Self::TrueColor,
+            (_, Some("dumb")) => Self::None,
+            // Windows terminals don't set TERM, and all support RGB
+            (_, None) => Self::TrueColor,
+            (_, Some(term)) => terminfo().unwrap_or(if term.contains("256color") {
+                Self::Ansi256
+            } else {
+                Self::Ansi16
+            }),
+        }
+    }
+
+    // What the terminfo entry for TERM says: RGB if it has the RGB or Tc flag, otherwise
+    // whatever its number of colors allows
+    #[cfg(unix)]
+    fn from_terminfo() -> Option<Self> {
+        use terminfo::{Database, capability as cap};
+
+        let info = Database::from_env().ok()?;
+        if info.raw("RGB").is_some() || info.raw("Tc").is_some() {
+            return Some(Self::TrueColor);
+        }
+        let cap::MaxColors(colors) = info.get::<cap::MaxColors>()?;
+        Some(Self::from_max_colors(colors))
+    }
+
+    #[cfg(not(unix))]
+    fn from_terminfo() -> Option<Self> {
+        None
+    }
+
+    fn from_max_colors(colors: i32) -> Self {
+        match colors {
+            0x0100_0000.. => Self::TrueColor,
+            256.. => Self::Ansi256,
+            8.. => Self::Ansi16,
+            _ => Self::None,
+        }
+    }
+}
+
+// Where a theme's duration gradient starts and ends. Durations between the two are colored
+// between DurationFast and DurationSlow, on a log scale, as they span orders of magnitude.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct DurationGradient {
+    pub fast: Duration,
+    pub slow: Duration,
+}
+
+impl DurationGradient {
+    fn from_config(config: &DurationsConfigBlock) -> Result<Self, String> {
+        let parse = |s: &str| {
+            humantime::parse_duration(s).map_err(|e| format!("Invalid duration {s}: {e}"))
+        };
+        let (fast, slow) = (parse(&config.fast)?, parse(&config.slow)?);
+        if fast >= slow {
+            return Err(format!(
+                "Fast duration {} must be less than slow duration {}",
+                config.fast, config.slow
+            ));
+        }
+        Ok(Self { fast, slow })
+    }
+
+    // How far along the gradient `duration` is, from 0.0 (fast) to 1.0 (slow)
+    fn position(&self, duration: Duration) -> f64 {
+        if duration <= self.fast {
+            return 0.0;
+        }
+        if duration >= self.slow {
+            return 1.0;
+        }
+        // Zero-length fast thresholds would put everything at the slow end on a log scale
+        let fast = self.fast.as_secs_f64().max(1e-3);
+        let span = (self.slow.as_secs_f64() / fast).ln();
+        ((duration.as_secs_f64() / fast).ln() / span).clamp(0.0, 1.0)
+    }
+}
 
 // For now, a theme is loaded as a mapping of meanings to colors, but it may be desirable to
 // expand that in the future to general styles, so we populate a Meaning->ContentStyle hashmap.
+#[derive(Clone)]
 pub struct Theme {
     pub name: String,
     pub parent: Option<String>,
     pub styles: HashMap<Meaning, ContentStyle>,
+    pub durations: Option<DurationGradient>,
 }
 
 // Themes have a number of convenience functions for the most commonly used meanings.
@@ -113,9 +251,38 @@ impl Theme {
             name,
             parent,
             styles,
+            durations: None,
         }
     }
 
+    pub fn with_durations(mut self, durations: Option<DurationGradient>) -> Theme {
+        self.durations = durations;
+        self
+    }
+
+    // The style for a command that took `duration`, if the theme colors durations on a
+    // gradient. Colors are blended in the palette the theme's endpoints come from, so a
+    // downconverted theme stays within what the terminal can show; if they can't be blended,
+    // the nearer end is used.
+    pub fn duration_style(&self, duration: Duration) -> Option<ContentStyle> {
+        let position = self.durations?.position(duration);
+        let fast = self.as_style(Meaning::DurationFast);
+        let slow = self.as_style(Meaning::DurationSlow);
+        let nearer = if position < 0.5 { fast } else { slow };
+
+        let blended = match (fast.foreground_color, slow.foreground_color) {
+            (Some(from), Some(to)) => blend(from, to, position),
+            _ => None,
+        };
+        Some(match blended {
+            Some(color) => ContentStyle {
+                foreground_color: Some(color),
+                ..nearer
+            },
+            None => nearer,
+        })
+    }
+
     pub fn closest_meaning<'a>(&self, meaning: &'a Meaning) -> &'a Meaning {
         if self.styles.contains_key(meaning) {
             meaning
@@ -131,6 +298,36 @@ impl Theme {
         self.styles[self.closest_meaning(&meaning)]
     }
 
+    // This theme as the terminal can show it: RGB colors become the nearest palette colors,
+    // or go entirely without color support. The selection is drawn as a background, which
+    // either loses or turns into a heavy block of color, so it's shown reversed instead.
+    pub fn for_color_support(&self, support: ColorSupport) -> Theme {
+        let styles = self
+            .styles
+            .iter()
+            .map(|(meaning, style)| {
+                let mut style = ContentStyle {
+                    foreground_color: style.foreground_color.and_then(|c| downconvert(c, support)),
+                    background_color: style.background_color.and_then(|c| downconvert(c, support)),
+                    underline_color: style.underline_color.and_then(|c| downconvert(c, support)),
+                    attributes: style.attributes,
+                };
+                match (meaning, support) {
+                    (Meaning::Selection, ColorSupport::None | ColorSupport::Ansi16) => {
+                        style.background_color = None;
+                        style.attributes.set(Attribute::Reverse);
+                    }
+                    (Meaning::Highlight, ColorSupport::None) => {
+                        style.attributes.set(Attribute::Underlined);
+                    }
+                    _ => {}
+                }
+                (*meaning, style)
+            })
+            .collect();
+        Theme::new(self.name.clone(), self.parent.clone(), styles).with_durations(self.durations)
+    }
+
     // Turns a map of meanings to colornames into a theme
     // If theme-debug is on, then we will print any colornames that we cannot load,
     // but we do not have this on in general, as it could print unfiltered text to the terminal
@@ -145,11 +342,12 @@ impl Theme {
         let styles: HashMap<Meaning, ContentStyle> = foreground_colors
             .iter()
             .map(|(meaning, color)| {
-                // Selection is special: parse as background color, not foreground
-                let result = if *meaning == Meaning::Selection {
-                    StyleFactory::from_bg_string(color)
-                } else {
-                    StyleFactory::from_fg_string(color)
+                // "white on #313244" sets both colors. Otherwise, Selection is special: a lone
+                // color is its background, not its foreground
+                let result = match color.split_once(" on ") {
+                    Some((fg, bg)) => StyleFactory::from_fg_and_bg_strings(fg, bg),
+                    None if *meaning == Meaning::Selection => StyleFactory::from_bg_string(color),
+                    None => StyleFactory::from_fg_string(color),
                 };
                 (
                     *meaning,
@@ -183,6 +381,7 @@ impl Theme {
         })
         .collect();
         Theme::new(name, parent.map(|p| p.name.clone()), styles)
+            .with_durations(parent.and_then(|p| p.durations))
     }
 }
 
@@ -228,6 +427,129 @@ fn from_string(name: &str) -> Result<Color, String> {
     }
 }
 
+// The xterm defaults for the 16 ANSI colors, which most terminals start from
+const ANSI_16: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::DarkRed, (205, 0, 0)),
+    (Color::DarkGreen, (0, 205, 0)),
+    (Color::DarkYellow, (205, 205, 0)),
+    (Color::DarkBlue, (0, 0, 238)),
+    (Color::DarkMagenta, (205, 0, 205)),
+    (Color::DarkCyan, (0, 205, 205)),
+    (Color::Grey, (229, 229, 229)),
+    (Color::DarkGrey, (127, 127, 127)),
+    (Color::Red, (255, 0, 0)),
+    (Color::Green, (0, 255, 0)),
+    (Color::Yellow, (255, 255, 0)),
+    (Color::Blue, (92, 92, 255)),
+    (Color::Magenta, (255, 0, 255)),
+    (Color::Cyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+// The channel levels of the 6x6x6 color cube in the 256-color palette
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+// The closest color to `color` that the terminal can show, or None without color support.
+// Named colors are left alone, as the terminal's own palette decides what they look like.
+fn downconvert(color: Color, support: ColorSupport) -> Option<Color> {
+    match (support, color) {
+        (ColorSupport::None, _) => None,
+        (ColorSupport::TrueColor, _) | (_, Color::Reset) => Some(color),
+        (ColorSupport::Ansi256, Color::Rgb { r, g, b }) => Some(nearest_256((r, g, b))),
+        (ColorSupport::Ansi16, Color::Rgb { r, g, b }) => Some(nearest_16((r, g, b))),
+        (ColorSupport::Ansi16, Color::AnsiValue(value)) => Some(nearest_16(ansi_to_rgb(value))),
+        _ => Some(color),
+    }
+}
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let d = |x: u8, y: u8| u32::from(x.abs_diff(y)).pow(2);
+    d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)
+}
+
+fn nearest_16(rgb: (u8, u8, u8)) -> Color {
+    ANSI_16
+        .iter()
+        .min_by_key(|(_, ansi)| distance(rgb, *ansi))
+        .map(|(color, _)| *color)
+        .unwrap()
+}
+
+// The nearest of the color cube and the grayscale ramp
+fn nearest_256(rgb: (u8, u8, u8)) -> Color {
+    let level = |c: u8| {
+        (0..CUBE_LEVELS.len())
+            .min_by_key(|&i| CUBE_LEVELS[i].abs_diff(c))
+            .unwrap()
+    };
+    let (r, g, b) = (level(rgb.0), level(rgb.1), level(rgb.2));
+    let cube = (CUBE_LEVELS[r], CUBE_LEVELS[g], CUBE_LEVELS[b]);
+
+    let average = (u16::from(rgb.0) + u16::from(rgb.1) + u16::from(rgb.2)) / 3;
+    let step = u8::try_from((average.saturating_sub(3) / 10).min(23)).unwrap();
+    let gray = 8 + 10 * step;
+
+    let index = if distance(rgb, (gray, gray, gray)) < distance(rgb, cube) {
+        232 + step
+    } else {
+        16 + u8::try_from(36 * r + 6 * g + b).unwrap()
+    };
+    Color::AnsiValue(index)
+}
+
+// The color `position` of the way from `from` to `to`, in the palette `to` is in.
+// None for colors the terminal decides on, such as Reset.
+fn blend(from: Color, to: Color, position: f64) -> Option<Color> {
+    let (a, b) = (color_to_rgb(from)?, color_to_rgb(to)?);
+    // Clamped to the channel range, so the cast can't truncate
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let mix = |x: u8, y: u8| {
+        (f64::from(x) + (f64::from(y) - f64::from(x)) * position)
+            .round()
+            .clamp(0.0, 255.0) as u8
+    };
+    let rgb = (mix(a.0, b.0), mix(a.1, b.1), mix(a.2, b.2));
+    Some(match to {
+        Color::Rgb { .. } => Color::Rgb {
+            r: rgb.0,
+            g: rgb.1,
+            b: rgb.2,
+        },
+        Color::AnsiValue(_) => nearest_256(rgb),
+        _ => nearest_16(rgb),
+    })
+}
+
+fn color_to_rgb(color: Color) -> Option<(u8, u8, u8)> {
+    match color {
+        Color::Rgb { r, g, b } => Some((r, g, b)),
+        Color::AnsiValue(value) => Some(ansi_to_rgb(value)),
+        _ => ANSI_16
+            .iter()
+            .find(|(ansi, _)| *ansi == color)
+            .map(|(_, rgb)| *rgb),
+    }
+}
+
+fn ansi_to_rgb(value: u8) -> (u8, u8, u8) {
+    match value {
+        0..=15 => ANSI_16[usize::from(value)].1,
+        16..=231 => {
+            let i = usize::from(value - 16);
+            (
+                CUBE_LEVELS[i / 36],
+                CUBE_LEVELS[(i / 6) % 6],
+                CUBE_LEVELS[i % 6],
+            )
+        }
+        _ => {
+            let gray = 8 + 10 * (value - 232);
+            (gray, gray, gray)
+        }
+    }
+}
+
 pub struct StyleFactory {}
 
 impl StyleFactory {
@@ -272,6 +594,14 @@ impl StyleFactory {
             Err(err) => Err(err),
         }
     }
+
+    fn from_fg_and_bg_strings(fg: &str, bg: &str) -> Result<ContentStyle, String> {
+        Ok(ContentStyle {
+            foreground_color: Some(from_string(fg.trim())?),
+            background_color: Some(from_string(bg.trim())?),
+            ..ContentStyle::default()
+        })
+    }
 }
 
 // Built-in themes. Rather than having extra files added before any theming
@@ -293,6 +623,12 @@ static MEANING_FALLBACKS: LazyLock<HashMap<Meaning, Meaning>> = LazyLock::new(||
         (Meaning::Title, Meaning::Important),
         (Meaning::Highlight, Meaning::Base),
         (Meaning::Selection, Meaning::Base),
+        (Meaning::Shortcut, Meaning::Annotation),
+        (Meaning::OtherHost, Meaning::Annotation),
+        (Meaning::SearchFilter, Meaning::Guidance),
+        (Meaning::Pinned, Meaning::AlertWarn),
+        (Meaning::DurationFast, Meaning::AlertInfo),
+        (Meaning::DurationSlow, Meaning::AlertError),
         (Meaning::SyntaxComment, Meaning::Annotation),
     ])
 });
@@ -332,10 +668,7 @@ static DEFAULT_THEME: LazyLock<Theme> = LazyLock::new(|| {
             (Meaning::Muted, StyleFactory::from_fg_color(Color::Grey)),
             (Meaning::Border, StyleFactory::from_fg_color(Color::White)),
             (Meaning::Base, ContentStyle::default()),
-            (
-                Meaning::Highlight,
-                StyleFactory::from_fg_color(Color::Blue),
-            ),
+            (Meaning::Highlight, StyleFactory::from_fg_color(Color::Blue)),
             // #313244 - Catppuccin surface0
             (
                 Meaning::Selection,
@@ -369,6 +702,14 @@ static DEFAULT_THEME: LazyLock<Theme> = LazyLock::new(|| {
                 Meaning::SyntaxComment,
                 StyleFactory::from_fg_color(Color::DarkGrey),
             ),
+            // Paths are underlined in the default foreground, as fish does
+            (
+                Meaning::SyntaxPath,
+                ContentStyle {
+                    attributes: Attributes::from(Attribute::Underlined),
+                    ..ContentStyle::default()
+                },
+            ),
         ]),
     )
 });
@@ -437,6 +778,7 @@ pub struct ThemeManager {
     loaded_themes: HashMap<String, Theme>,
     debug: bool,
     override_theme_dir: Option<String>,
+    color_support: ColorSupport,
 }
 
 // Theme-loading logic
@@ -449,35 +791,43 @@ impl ThemeManager {
                 Some(theme_dir) => Some(theme_dir),
                 None => std::env::var("ATUIN_THEME_DIR").ok(),
             },
+            color_support: ColorSupport::default(),
+        }
+    }
+
+    // Load themes as a terminal with `color_support` can show them
+    pub fn with_color_support(mut self, color_support: ColorSupport) -> Self {
+        self.color_support = color_support;
+        self
+    }
+
+    // The directory holding theme files: the override if one is set (via ATUIN_THEME_DIR env),
+    // otherwise `themes` in the config directory. None if the override is empty
+    fn theme_dir(&self) -> Option<PathBuf> {
+        if let Some(p) = &self.override_theme_dir {
+            return (!p.is_empty()).then(|| PathBuf::from(p));
         }
+
+        let mut theme_dir = if let Ok(p) = std::env::var("ATUIN_CONFIG_DIR") {
+            PathBuf::from(p)
+        } else {
+            atuin_common::paths::config_dir()
+        };
+        theme_dir.push("themes");
+        Some(theme_dir)
     }
 
-    // Try to load a theme from a `{name}.toml` file in the theme directory. If an override is set
-    // for the theme dir (via ATUIN_THEME_DIR env) we should load the theme from there
+    // Try to load a theme from a `{name}.toml` file in the theme directory
     pub fn load_theme_from_file(
         &mut self,
         name: &str,
         max_depth: u8,
     ) -> Result<&Theme, Box<dyn error::Error>> {
-        let mut theme_file = if let Some(p) = &self.override_theme_dir {
-            if p.is_empty() {
-                return Err(Box::new(Error::new(
-                    ErrorKind::NotFound,
-                    "Empty theme directory override and could not find theme elsewhere",
-                )));
-            }
-            PathBuf::from(p)
-        } else {
-            let config_dir = atuin_common::utils::config_dir();
-            let mut theme_file = if let Ok(p) = std::env::var("ATUIN_CONFIG_DIR") {
-                PathBuf::from(p)
-            } else {
-                let mut theme_file = PathBuf::new();
-                theme_file.push(config_dir);
-                theme_file
-            };
-            theme_file.push("themes");
-            theme_file
+        let Some(mut theme_file) = self.theme_dir() else {
+            return Err(Box::new(Error::new(
+                ErrorKind::NotFound,
+                "Empty theme directory override and could not find theme elsewhere",
+            )));
         };
 
         let theme_toml = format!["{name}.toml"];
@@ -539,16 +889,75 @@ impl ThemeManager {
             );
         }
 
-        let theme = Theme::from_foreground_colors(theme_config.theme.name, parent, colors, debug);
+        let mut theme =
+            Theme::from_foreground_colors(theme_config.theme.name, parent, colors, debug);
+        if let Some(durations) = &theme_config.durations {
+            match DurationGradient::from_config(durations) {
+                Ok(gradient) => theme.durations = Some(gradient),
+                Err(err) if debug => tracing::warn!("Ignoring duration gradient: {err}"),
+                Err(_) => {}
+            }
+        }
         let name = name.to_string();
         self.loaded_themes.insert(name.clone(), theme);
         let theme = self.loaded_themes.get(&name).unwrap();
         Ok(theme)
     }
 
+    // Watch the theme directory for theme files being saved. None if there's no directory
+    pub fn watch(&self) -> Option<watcher::ThemeWatcher> {
+        let dir = self.theme_dir().filter(|dir| dir.is_dir())?;
+        match watcher::ThemeWatcher::new(&dir) {
+            Ok(watcher) => Some(watcher),
+            Err(err) => {
+                tracing::warn!("Could not watch for theme changes: {err}");
+                None
+            }
+        }
+    }
+
+    // Forget the themes loaded so far, so theme files are read again when next asked for
+    pub fn forget_loaded(&mut self) {
+        self.loaded_themes.clear();
+    }
+
+    // The names of the built-in themes and of the theme files in the theme directory, sorted
+    pub fn available_themes(&self) -> Vec<String> {
+        let mut names: Vec<String> = BUILTIN_THEMES.keys().map(|name| name.to_string()).collect();
+
+        if let Some(Ok(entries)) = self.theme_dir().map(std::fs::read_dir) {
+            names.extend(entries.filter_map(|entry| {
+                let path = entry.ok()?.path();
+                if path.extension()? != "toml" {
+                    return None;
+                }
+                Some(path.file_stem()?.to_str()?.to_string())
+            }));
+        }
+
+        names.sort();
+        names.dedup();
+        names
+    }
+
     // Check if the requested theme is loaded and, if not, then attempt to get it
-    // from the builtins or, if not there, from file
+    // from the builtins or, if not there, from file. Then bring it down to the colors the
+    // terminal supports.
     pub fn load_theme(&mut self, name: &str, max_depth: Option<u8>) -> &Theme {
+        let color_support = self.color_support;
+        if color_support == ColorSupport::TrueColor {
+            return self.find_theme(name, max_depth);
+        }
+
+        // Converting is idempotent, so a theme converted earlier is safe to convert again
+        let theme = self
+            .find_theme(name, max_depth)
+            .for_color_support(color_support);
+        self.loaded_themes.insert(name.to_string(), theme);
+        self.loaded_themes.get(name).unwrap()
+    }
+
+    fn find_theme(&mut self, name: &str, max_depth: Option<u8>) -> &Theme {
         if self.loaded_themes.contains_key(name) {
             return self.loaded_themes.get(name).unwrap();
         }
@@ -582,6 +991,50 @@ mod theme_tests {
         );
     }
 
+    #[test]
+    fn test_available_themes_include_theme_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("mine.toml"), "").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "").unwrap();
+
+        let manager = ThemeManager::new(Some(false), Some(dir.path().display().to_string()));
+        assert_eq!(
+            manager.available_themes(),
+            ["(none)", "autumn", "default", "marine", "mine"]
+        );
+
+        let manager = ThemeManager::new(Some(false), Some(String::new()));
+        assert!(!manager.available_themes().contains(&"mine".to_string()));
+    }
+
+    #[test]
+    fn test_forgotten_themes_are_read_again() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("mine.toml");
+        let write = |color: &str| {
+            std::fs::write(
+                &path,
+                format!("[theme]\nname = \"mine\"\n\n[colors]\nGuidance = \"{color}\"\n"),
+            )
+            .unwrap();
+        };
+        let guidance = |manager: &mut ThemeManager| {
+            manager
+                .load_theme("mine", None)
+                .as_style(Meaning::Guidance)
+                .foreground_color
+        };
+        let mut manager = ThemeManager::new(Some(false), Some(dir.path().display().to_string()));
+
+        write("teal");
+        assert_eq!(guidance(&mut manager), from_string("teal").ok());
+
+        write("brown");
+        assert_eq!(guidance(&mut manager), from_string("teal").ok());
+        manager.forget_loaded();
+        assert_eq!(guidance(&mut manager), from_string("brown").ok());
+    }
+
     #[test]
     fn test_can_create_theme() {
         let mut manager = ThemeManager::new(Some(false), Some("".to_string()));
@@ -850,6 +1303,247 @@ mod theme_tests {
         })
     }
 
+    #[rstest]
+    #[case::no_term(None, None, ColorSupport::TrueColor)]
+    #[case::truecolor(Some("truecolor"), Some("xterm-256color"), ColorSupport::TrueColor)]
+    #[case::bit24(Some("24bit"), Some("xterm"), ColorSupport::TrueColor)]
+    #[case::xterm_256(None, Some("xterm-256color"), ColorSupport::Ansi256)]
+    #[case::tmux_256(None, Some("tmux-256color"), ColorSupport::Ansi256)]
+    #[case::xterm(None, Some("xterm"), ColorSupport::Ansi16)]
+    #[case::dumb(None, Some("dumb"), ColorSupport::None)]
+    fn detects_color_support(
+        #[case] colorterm: Option<&str>,
+        #[case] term: Option<&str>,
+        #[case] expected: ColorSupport,
+    ) {
+        assert_eq!(ColorSupport::from_env(colorterm, term, || None), expected);
+    }
+
+    #[rstest]
+    #[case::direct(
+        None,
+        Some("xterm-direct"),
+        ColorSupport::TrueColor,
+        ColorSupport::TrueColor
+    )]
+    #[case::fewer_than_named(
+        None,
+        Some("screen-256color"),
+        ColorSupport::Ansi16,
+        ColorSupport::Ansi16
+    )]
+    #[case::colorterm_first(
+        Some("truecolor"),
+        Some("xterm"),
+        ColorSupport::Ansi16,
+        ColorSupport::TrueColor
+    )]
+    #[case::dumb_first(None, Some("dumb"), ColorSupport::TrueColor, ColorSupport::None)]
+    fn detects_color_support_from_terminfo(
+        #[case] colorterm: Option<&str>,
+        #[case] term: Option<&str>,
+        #[case] terminfo: ColorSupport,
+        #[case] expected: ColorSupport,
+    ) {
+        assert_eq!(
+            ColorSupport::from_env(colorterm, term, || Some(terminfo)),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case::monochrome(2, ColorSupport::None)]
+    #[case::eight(8, ColorSupport::Ansi16)]
+    #[case::eighty_eight(88, ColorSupport::Ansi16)]
+    #[case::two_fifty_six(256, ColorSupport::Ansi256)]
+    #[case::direct(0x0100_0000, ColorSupport::TrueColor)]
+    fn color_support_from_terminfo_colors(#[case] colors: i32, #[case] expected: ColorSupport) {
+        assert_eq!(ColorSupport::from_max_colors(colors), expected);
+    }
+
+    #[rstest]
+    #[case::red(Color::Rgb { r: 255, g: 0, b: 0 }, ColorSupport::Ansi256, Some(Color::AnsiValue(196)))]
+    #[case::gray(Color::Rgb { r: 128, g: 128, b: 128 }, ColorSupport::Ansi256, Some(Color::AnsiValue(244)))]
+    #[case::surface(Color::Rgb { r: 49, g: 50, b: 68 }, ColorSupport::Ansi256, Some(Color::AnsiValue(237)))]
+    #[case::brown(Color::Rgb { r: 165, g: 42, b: 42 }, ColorSupport::Ansi16, Some(Color::DarkRed))]
+    #[case::ansi_value(Color::AnsiValue(196), ColorSupport::Ansi16, Some(Color::Red))]
+    #[case::named(Color::DarkCyan, ColorSupport::Ansi16, Some(Color::DarkCyan))]
+    #[case::truecolor(Color::Rgb { r: 1, g: 2, b: 3 }, ColorSupport::TrueColor, Some(Color::Rgb { r: 1, g: 2, b: 3 }))]
+    #[case::no_color(Color::DarkCyan, ColorSupport::None, None)]
+    fn downconverts_colors(
+        #[case] color: Color,
+        #[case] support: ColorSupport,
+        #[case] expected: Option<Color>,
+    ) {
+        assert_eq!(downconvert(color, support), expected);
+    }
+
+    #[test]
+    fn themes_without_color_keep_selection_and_matches_visible() {
+        let mut manager = ThemeManager::new(Some(false), Some("".to_string()))
+            .with_color_support(ColorSupport::None);
+        let theme = manager.load_theme("autumn", None);
+
+        assert!(
+            theme
+                .styles
+                .values()
+                .all(|style| style.foreground_color.is_none() && style.background_color.is_none())
+        );
+        assert!(theme.get_selection().attributes.has(Attribute::Reverse));
+        assert!(
+            theme
+                .as_style(Meaning::Highlight)
+                .attributes
+                .has(Attribute::Underlined)
+        );
+        // Attributes survive
+        assert!(
+            theme
+                .as_style(Meaning::Important)
+                .attributes
+                .has(Attribute::Bold)
+        );
+
+        // Loading again doesn't change anything
+        let again = manager.load_theme("autumn", None).clone();
+        assert_eq!(again.styles, manager.load_theme("autumn", None).styles);
+    }
+
+    #[test]
+    fn theme_files_are_downconverted() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("mine.toml"),
+            "[theme]\nname = \"mine\"\n\n[colors]\nGuidance = \"#ff0000\"\n",
+        )
+        .unwrap();
+        let mut manager = ThemeManager::new(Some(false), Some(dir.path().display().to_string()))
+            .with_color_support(ColorSupport::Ansi256);
+
+        let theme = manager.load_theme("mine", None);
+        assert_eq!(
+            theme.as_style(Meaning::Guidance).foreground_color,
+            Some(Color::AnsiValue(196))
+        );
+        assert_eq!(
+            theme.get_selection().background_color,
+            Some(Color::AnsiValue(237))
+        );
+    }
+
+    #[test]
+    fn colors_durations_on_a_gradient() {
+        let mut manager = ThemeManager::new(Some(false), Some("".to_string()));
+        let config = Config::builder()
+            .add_source(ConfigFile::from_str(
+                "
+        [theme]
+        name = \"gradient\"
+
+        [colors]
+        DurationFast = \"#00c800\"
+        DurationSlow = \"#c80000\"
+
+        [durations]
+        fast = \"1s\"
+        slow = \"100s\"
+        ",
+                FileFormat::Toml,
+            ))
+            .build()
+            .unwrap();
+        let theme = manager
+            .load_theme_from_config("gradient", config, 1)
+            .unwrap();
+
+        let color = |secs| {
+            theme
+                .duration_style(Duration::from_secs(secs))
+                .unwrap()
+                .foreground_color
+        };
+        assert_eq!(color(0), Some(Color::Rgb { r: 0, g: 200, b: 0 }));
+        // Halfway on a log scale
+        assert_eq!(
+            color(10),
+            Some(Color::Rgb {
+                r: 100,
+                g: 100,
+                b: 0
+            })
+        );
+        assert_eq!(color(1000), Some(Color::Rgb { r: 200, g: 0, b: 0 }));
+    }
+
+    #[test]
+    fn durations_keep_their_palette() {
+        let theme = DEFAULT_THEME.clone().with_durations(Some(DurationGradient {
+            fast: Duration::from_secs(1),
+            slow: Duration::from_secs(100),
+        }));
+
+        // The default theme's named colors blend to the nearest named color
+        let style = theme.duration_style(Duration::from_secs(2)).unwrap();
+        assert_eq!(style.foreground_color, Some(Color::DarkGreen));
+
+        // Themes without a gradient leave durations to be colored by outcome
+        assert!(
+            DEFAULT_THEME
+                .duration_style(Duration::from_secs(2))
+                .is_none()
+        );
+    }
+
+    #[rstest]
+    #[case::backwards("1m", "1s")]
+    #[case::not_a_duration("soon", "1m")]
+    fn rejects_duration_thresholds(#[case] fast: &str, #[case] slow: &str) {
+        let config = DurationsConfigBlock {
+            fast: fast.to_string(),
+            slow: slow.to_string(),
+        };
+        assert!(DurationGradient::from_config(&config).is_err());
+    }
+
+    #[test]
+    fn colors_can_set_a_background_too() {
+        let mut manager = ThemeManager::new(Some(false), Some("".to_string()));
+        let config = Config::builder()
+            .add_source(ConfigFile::from_str(
+                "
+        [theme]
+        name = \"backgrounds\"
+
+        [colors]
+        Selection = \"white on #45475a\"
+        Highlight = \"black on yellow\"
+        Guidance = \"teal\"
+        ",
+                FileFormat::Toml,
+            ))
+            .build()
+            .unwrap();
+        let theme = manager
+            .load_theme_from_config("backgrounds", config, 1)
+            .unwrap();
+
+        let selection = theme.get_selection();
+        assert_eq!(selection.foreground_color, from_string("white").ok());
+        assert_eq!(
+            selection.background_color,
+            Some(Color::Rgb {
+                r: 69,
+                g: 71,
+                b: 90
+            })
+        );
+        let highlight = theme.as_style(Meaning::Highlight);
+        assert_eq!(highlight.foreground_color, from_string("black").ok());
+        assert_eq!(highlight.background_color, from_string("yellow").ok());
+        assert_eq!(theme.as_style(Meaning::Guidance).background_color, None);
+    }
+
     #[rstest]
     #[case::palette_name("brown", Color::Rgb { r: 165, g: 42, b: 42 })]
     #[case::hex("#ff1122", Color::Rgb { r: 255, g: 17, b: 34 })]
@@ -0,0 +1,109 @@
+//! Theme file watching, so a theme being written is shown as it's saved.
+//!
+//! The interactive search checks [`ThemeWatcher::take_changed`] as it redraws, and loads its
+//! theme again when it's set.
+
+use std::{
+    path::Path,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use eyre::{Result, WrapErr};
+use notify::{
+    Config as NotifyConfig, RecommendedWatcher, RecursiveMode, Watcher,
+    event::{EventKind, ModifyKind},
+};
+use tracing::{debug, error};
+
+/// Watches the theme directory for theme files being saved.
+pub struct ThemeWatcher {
+    changed: Arc<AtomicBool>,
+    /// Keeps the file watcher alive for the lifetime of this struct.
+    _watcher: RecommendedWatcher,
+}
+
+impl ThemeWatcher {
+    /// Start watching the `.toml` files in `dir`, which has to exist.
+    pub fn new(dir: &Path) -> Result<Self> {
+        let changed = Arc::new(AtomicBool::new(false));
+
+        let flag = changed.clone();
+        let mut watcher = RecommendedWatcher::new(
+            move |res: Result<notify::Event, notify::Error>| match res {
+                Ok(event) if is_theme_saved(&event) => {
+                    debug!("theme file event detected: {:?}", event);
+                    flag.store(true, Ordering::Relaxed);
+                }
+                Ok(_) => {}
+                Err(e) => error!("theme watcher error: {}", e),
+            },
+            NotifyConfig::default(),
+        )
+        .wrap_err("failed to create theme watcher")?;
+
+        watcher
+            .watch(dir, RecursiveMode::NonRecursive)
+            .wrap_err_with(|| format!("failed to watch theme directory: {dir:?}"))?;
+
+        Ok(Self {
+            changed,
+            _watcher: watcher,
+        })
+    }
+
+    /// Whether a theme file has been saved since this was last asked.
+    pub fn take_changed(&self) -> bool {
+        self.changed.swap(false, Ordering::Relaxed)
+    }
+}
+
+/// Whether `event` wrote a theme file. Editors that save by renaming a temporary file over the
+/// theme show up as a rename or a create, rather than a write.
+fn is_theme_saved(event: &notify::Event) -> bool {
+    matches!(
+        event.kind,
+        EventKind::Modify(ModifyKind::Data(_) | ModifyKind::Name(_) | ModifyKind::Any)
+            | EventKind::Create(_)
+    ) && event
+        .paths
+        .iter()
+        .any(|path| path.extension().is_some_and(|ext| ext == "toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use notify::event::{CreateKind, DataChange, RenameMode};
+
+    use super::*;
+
+    fn event(kind: EventKind, path: &str) -> notify::Event {
+        notify::Event::new(kind).add_path(PathBuf::from(path))
+    }
+
+    #[test]
+    fn theme_files_being_saved_are_noticed() {
+        let written = EventKind::Modify(ModifyKind::Data(DataChange::Content));
+        assert!(is_theme_saved(&event(written, "/themes/mine.toml")));
+        assert!(is_theme_saved(&event(
+            EventKind::Modify(ModifyKind::Name(RenameMode::To)),
+            "/themes/mine.toml"
+        )));
+        assert!(is_theme_saved(&event(
+            EventKind::Create(CreateKind::File),
+            "/themes/mine.toml"
+        )));
+
+        // Editors' swap and backup files, and reads
+        assert!(!is_theme_saved(&event(written, "/themes/.mine.toml.swp")));
+        assert!(!is_theme_saved(&event(written, "/themes/mine.toml~")));
+        assert!(!is_theme_saved(&event(
+            EventKind::Access(notify::event::AccessKind::Any),
+            "/themes/mine.toml"
+        )));
+    }
+}
@@ -0,0 +1,327 @@
+//! Finding, downloading and verifying Atuin releases, for `atuin self-update`.
+//!
+//! A release is described by JSON in the shape of GitHub's releases API, with
+//! an archive per platform (`atuin-<target>.tar.gz`), its SHA-256 checksum
+//! (`.sha256`) and a minisign signature (`.minisig`). Nothing is handed back to
+//! be installed unless the checksum matches and the signature was made with the
+//! configured public key, for that archive of that release.
+
+use std::io::Read;
+use std::time::Duration;
+
+use eyre::{Context, Result, bail, eyre};
+use minisign_verify::{PublicKey, Signature};
+use reqwest::{Url, header::USER_AGENT};
+use semver::Version;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use atuin_common::tls::ensure_crypto_provider;
+
+use crate::network::{self, RequestKind};
+use crate::settings::Settings;
+
+static APP_USER_AGENT: &str = concat!("atuin/", env!("CARGO_PKG_VERSION"),);
+
+/// The key releases are signed with, set when building Atuin. Used when
+/// `self_update.public_key` isn't configured.
+pub const BUILT_IN_PUBLIC_KEY: Option<&str> = option_env!("ATUIN_UPDATE_PUBLIC_KEY");
+
+/// How long a release may take to download in all. The download timeout only
+/// bounds each read, so a server trickling bytes could otherwise hold it forever.
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Release {
+    pub tag_name: String,
+    #[serde(default)]
+    pub html_url: Option<String>,
+    pub assets: Vec<Asset>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Asset {
+    pub name: String,
+    pub browser_download_url: Url,
+}
+
+impl Release {
+    pub fn version(&self) -> Result<Version> {
+        let tag = self.tag_name.trim_start_matches('v');
+        Version::parse(tag).wrap_err_with(|| format!("release {} is not a version", self.tag_name))
+    }
+
+    fn asset(&self, name: &str) -> Result<&Asset> {
+        self.assets
+            .iter()
+            .find(|asset| asset.name == name)
+            .ok_or_else(|| eyre!("release {} has no {name}", self.tag_name))
+    }
+}
+
+/// The name of the release archive for the `target` platform
+pub fn archive_name(target: &str) -> String {
+    format!("atuin-{target}.tar.gz")
+}
+
+/// The public key to verify releases with: the configured one, or the one built in
+pub fn public_key(settings: &Settings) -> Option<&str> {
+    let configured = settings.self_update.public_key.trim();
+
+    if configured.is_empty() {
+        BUILT_IN_PUBLIC_KEY
+    } else {
+        Some(configured)
+    }
+}
+
+async fn get(client: &reqwest::Client, url: Url) -> Result<reqwest::Response> {
+    let resp = network::send(client.get(url.clone()).header(USER_AGENT, APP_USER_AGENT)).await?;
+
+    if !resp.status().is_success() {
+        bail!("could not fetch {url}: {}", resp.status());
+    }
+
+    Ok(resp)
+}
+
+/// The latest release, from `self_update.endpoint`
+pub async fn latest_release(settings: &Settings) -> Result<Release> {
+    let url = Url::parse(&settings.self_update.endpoint)
+        .wrap_err("self_update.endpoint is not a valid URL")?;
+
+    ensure_crypto_provider();
+    let client = network::client_builder(RequestKind::UpdateCheck)?.build()?;
+
+    get(&client, url)
+        .await?
+        .json::<Release>()
+        .await
+        .wrap_err("could not read the latest release")
+}
+
+/// Download the `target` archive of `release`, check it against its checksum
+/// and signature, and return the `atuin` binary inside it.
+pub async fn download(settings: &Settings, release: &Release, target: &str) -> Result<Vec<u8>> {
+    let Some(public_key) = public_key(settings) else {
+        bail!(
+            "there is no public key to verify the release with: set self_update.public_key in your config"
+        );
+    };
+
+    let name = archive_name(target);
+
+    ensure_crypto_provider();
+    let client = network::client_builder(RequestKind::Download)?.build()?;
+
+    let archive = tokio::time::timeout(
+        DOWNLOAD_TIMEOUT,
+        download_verified(&client, release, target, &name, public_key),
+    )
+    .await
+    .map_err(|_| eyre!("downloading {name} took too long"))??;
+
+    extract_binary(&archive)
+}
+
+async fn download_verified(
+    client: &reqwest::Client,
+    release: &Release,
+    target: &str,
+    name: &str,
+    public_key: &str,
+) -> Result<Vec<u8>> {
+    let archive = release.asset(name).wrap_err_with(|| {
+        format!("there is no release of atuin for {target}; you'll need to build it yourself")
+    })?;
+    let checksum = release.asset(&format!("{name}.sha256"))?;
+    let signature = release.asset(&format!("{name}.minisig"))?;
+
+    let checksum = get(client, checksum.browser_download_url.clone())
+        .await?
+        .text()
+        .await?;
+    let signature = get(client, signature.browser_download_url.clone())
+        .await?
+        .text()
+        .await?;
+    let archive = get(client, archive.browser_download_url.clone())
+        .await?
+        .bytes()
+        .await?;
+
+    verify(
+        &archive,
+        &checksum,
+        &signature,
+        public_key,
+        name,
+        &release.tag_name,
+    )?;
+
+    Ok(archive.to_vec())
+}
+
+/// Check `archive` against `checksum` (a `.sha256` file: the hex digest,
+/// optionally followed by the file name) and a minisign `signature` by
+/// `public_key`. The signature's trusted comment must name the archive as
+/// `file:<name>` and the release as `tag:<tag>`, so a signed archive of an older
+/// release can't be passed off as this one.
+pub fn verify(
+    archive: &[u8],
+    checksum: &str,
+    signature: &str,
+    public_key: &str,
+    name: &str,
+    tag: &str,
+) -> Result<()> {
+    let expected = checksum
+        .split_whitespace()
+        .next()
+        .ok_or_else(|| eyre!("the release's checksum is empty"))?;
+    let actual: String = Sha256::digest(archive)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+
+    if !expected.eq_ignore_ascii_case(&actual) {
+        bail!("the download doesn't match the release's checksum");
+    }
+
+    let public_key = PublicKey::from_base64(public_key)
+        .map_err(|e| eyre!("self_update.public_key is not a minisign public key: {e}"))?;
+    let signature = Signature::decode(signature)
+        .map_err(|e| eyre!("the release's signature is not valid: {e}"))?;
+
+    public_key
+        .verify(archive, &signature, false)
+        .map_err(|e| eyre!("the release's signature doesn't match: {e}"))?;
+
+    let signed: Vec<&str> = signature.trusted_comment().split_whitespace().collect();
+    if !signed.contains(&format!("file:{name}").as_str())
+        || !signed.contains(&format!("tag:{tag}").as_str())
+    {
+        bail!(
+            "the release's signature is for something else: {}",
+            signature.trusted_comment()
+        );
+    }
+
+    Ok(())
+}
+
+/// The `atuin` binary inside a release archive
+pub fn extract_binary(archive: &[u8]) -> Result<Vec<u8>> {
+    let mut tar = tar::Archive::new(flate2::read::GzDecoder::new(archive));
+
+    for entry in tar.entries()? {
+        let mut entry = entry?;
+        let is_binary = entry.header().entry_type().is_file()
+            && entry
+                .path()?
+                .file_name()
+                .is_some_and(|name| name == "atuin");
+
+        if is_binary {
+            let mut binary = Vec::new();
+            entry.read_to_end(&mut binary)?;
+            return Ok(binary);
+        }
+    }
+
+    bail!("the release archive has no atuin binary in it")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Release, extract_binary, verify};
+
+    // Made with a throwaway minisign key, over `DATA`, as the release workflow
+    // signs: `minisign -S -t "file:$name<tab>tag:$tag"`
+    const PUBLIC_KEY: &str = "RWQBI0VniavN7/yqQB8J25Pue18v6C8fxYOQIeQA86txxFgyFIwWkVi8";
+    const SIGNATURE: &str = "untrusted comment: signature from minisign secret key
+RUQBI0VniavN74LZdkyjtG5QfXwYMqI2kEKcsgNovCpSC0n4brsQX3hFcnHpXv9avKYlcufAEdCFLBcqDmXfueWrTw5WsNTAbg0=
+trusted comment: file:atuin-x86_64-unknown-linux-gnu.tar.gz\ttag:v18.19.0
+2XerR+kFIBOpE7J7frn3tu8gtGShWm2r0R+BzM6pXQ1S7+PReqgKvVgsrbQfvU50qKnw/p9DzjQ2B/UjBJCqAQ==
+";
+    const CHECKSUM: &str = "0c9949cbcbfaea6020aa31dc3938e38c83a52f3a35df28937e07a8bdc9cf361a  atuin-x86_64-unknown-linux-gnu.tar.gz\n";
+    const DATA: &[u8] = b"atuin 18.19.0 for x86_64-unknown-linux-gnu";
+    const NAME: &str = "atuin-x86_64-unknown-linux-gnu.tar.gz";
+    const TAG: &str = "v18.19.0";
+
+    #[test]
+    fn verifies_checksum_and_signature() {
+        verify(DATA, CHECKSUM, SIGNATURE, PUBLIC_KEY, NAME, TAG).unwrap();
+
+        let tampered = b"atuin 18.19.0 for x86_64-unknown-linux-gnu!";
+        let err = verify(tampered, CHECKSUM, SIGNATURE, PUBLIC_KEY, NAME, TAG).unwrap_err();
+        assert!(err.to_string().contains("checksum"), "{err}");
+
+        // A matching checksum doesn't make up for the signature
+        let checksum = "8465b1fb0ca4a57197180b276274c63b0b8f78942b71a26383c3cea8e0d1f8a3";
+        let err = verify(tampered, checksum, SIGNATURE, PUBLIC_KEY, NAME, TAG).unwrap_err();
+        assert!(err.to_string().contains("signature"), "{err}");
+
+        let other_key = "RWQBI0VniavN7wH09eT6ADFQLv8Vc3AJnNChE+GrkW2PEYeo6j3MvfTL";
+        let err = verify(DATA, CHECKSUM, SIGNATURE, other_key, NAME, TAG).unwrap_err();
+        assert!(err.to_string().contains("signature"), "{err}");
+    }
+
+    #[test]
+    fn signatures_are_bound_to_their_archive_and_release() {
+        // A genuine signature of an older release, replayed for a newer one
+        let older = "untrusted comment: signature from minisign secret key
+RUQBI0VniavN74LZdkyjtG5QfXwYMqI2kEKcsgNovCpSC0n4brsQX3hFcnHpXv9avKYlcufAEdCFLBcqDmXfueWrTw5WsNTAbg0=
+trusted comment: file:atuin-x86_64-unknown-linux-gnu.tar.gz\ttag:v18.18.0
+62baoH8wwzcwmA76w6oBD1wfKYwpcFEaMfX5iAPDnX6rGDShj0OvX8JPZNLx9mfqiYvuqi7krAoxXSdNSoyzAA==
+";
+        verify(DATA, CHECKSUM, older, PUBLIC_KEY, NAME, "v18.18.0").unwrap();
+        let err = verify(DATA, CHECKSUM, older, PUBLIC_KEY, NAME, TAG).unwrap_err();
+        assert!(err.to_string().contains("something else"), "{err}");
+
+        let other = "atuin-aarch64-apple-darwin.tar.gz";
+        let err = verify(DATA, CHECKSUM, SIGNATURE, PUBLIC_KEY, other, TAG).unwrap_err();
+        assert!(err.to_string().contains("something else"), "{err}");
+    }
+
+    #[test]
+    fn finds_the_binary_in_an_archive() {
+        let mut tar = tar::Builder::new(flate2::write::GzEncoder::new(
+            Vec::new(),
+            flate2::Compression::fast(),
+        ));
+        for (path, contents) in [
+            ("atuin-x86_64-unknown-linux-gnu/README.md", &b"# atuin"[..]),
+            ("atuin-x86_64-unknown-linux-gnu/atuin", &b"\x7fELF"[..]),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o755);
+            header.set_cksum();
+            tar.append_data(&mut header, path, contents).unwrap();
+        }
+        let archive = tar.into_inner().unwrap().finish().unwrap();
+
+        assert_eq!(extract_binary(&archive).unwrap(), b"\x7fELF");
+    }
+
+    #[test]
+    fn release_version() {
+        let release: Release = serde_json::from_str(
+            r#"{"tag_name": "v18.19.0", "assets": [{"name": "atuin-x86_64-unknown-linux-gnu.tar.gz", "browser_download_url": "https://example.com/atuin.tar.gz"}]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(release.version().unwrap(), semver::Version::new(18, 19, 0));
+        assert!(
+            release
+                .asset("atuin-x86_64-unknown-linux-gnu.tar.gz")
+                .is_ok()
+        );
+        assert!(
+            release
+                .asset("atuin-riscv64gc-unknown-linux-gnu.tar.gz")
+                .is_err()
+        );
+    }
+}
@@ -1,8 +1,23 @@
 pub(crate) mod rmp;
 
 pub(crate) fn get_hostname() -> String {
-    std::env::var("ATUIN_HOST_NAME")
-        .unwrap_or_else(|_| whoami::hostname().unwrap_or_else(|_| "unknown-host".to_string()))
+    std::env::var("ATUIN_HOST_NAME").unwrap_or_else(|_| {
+        let hostname = whoami::hostname().unwrap_or_else(|_| "unknown-host".to_string());
+        // A WSL distro takes Windows' name unless it's given its own, and its history
+        // shouldn't pass for the Windows host's
+        match atuin_common::wsl::distro() {
+            Some(distro) if atuin_common::wsl::conf("network", "hostname").is_none() => {
+                wsl_hostname(&hostname, &distro)
+            }
+            _ => hostname,
+        }
+    })
+}
+
+/// The host name of a WSL distro on the Windows machine `hostname`, such as `DESKTOP-1-wsl-Ubuntu`.
+fn wsl_hostname(hostname: &str, distro: &str) -> String {
+    // Colons separate the host from the user
+    format!("{hostname}-wsl-{}", distro.replace(':', "-"))
 }
 
 pub(crate) fn get_username() -> String {
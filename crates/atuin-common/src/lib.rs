@@ -62,7 +62,9 @@ pub mod ansi;
 pub mod api;
 pub mod docs;
 pub mod logs;
+pub mod otel;
 pub mod path;
+pub mod paths;
 pub mod record;
 pub mod shell;
 pub mod string;
@@ -71,3 +73,4 @@ pub mod test_utils;
 pub mod tls;
 pub mod url;
 pub mod utils;
+pub mod wsl;
@@ -2,6 +2,8 @@ use serde::{Deserialize, Serialize};
 use std::ffi::OsStr;
 use std::path::{Path, PathBuf};
 
+use crate::otel::OtelConfig;
+
 /// Log level for file logging. Maps to tracing's LevelFilter.
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "lowercase")]
@@ -28,10 +30,22 @@ impl LogLevel {
     }
 }
 
+/// How log files are written.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// A line of text per event, as on the console.
+    #[default]
+    Text,
+    /// A JSON object per event, for log collectors.
+    Json,
+}
+
 #[derive(Debug)]
 pub struct FileConfig {
     pub path: PathBuf,
     pub level: LogLevel,
+    pub format: LogFormat,
     pub retention_days: u64,
 }
 
@@ -64,6 +78,8 @@ impl StderrConfig {
 pub struct LogConfig {
     pub file: Option<FileConfig>,
     pub stderr: Option<StderrConfig>,
+    /// Export traces as well, for the daemon
+    pub otel: Option<OtelConfig>,
 }
 
 impl LogConfig {
@@ -71,6 +87,7 @@ impl LogConfig {
         Self {
             file: Some(file),
             stderr: None,
+            otel: None,
         }
     }
 
@@ -78,6 +95,7 @@ impl LogConfig {
         Self {
             file: None,
             stderr: Some(StderrConfig::default()),
+            otel: None,
         }
     }
 }
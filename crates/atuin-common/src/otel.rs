@@ -0,0 +1,229 @@
+//! Exporting traces and metrics over OTLP, for operators who want to see where
+//! sync time goes.
+//!
+//! The settings are always here, so a config with an `[otel]` section loads
+//! whatever Atuin was built with; the exporting itself needs the `otel`
+//! feature. Spans become traces, sampled as configured. The few metrics there
+//! are (request and sync timings) are recorded through [`record_request`] and
+//! [`record_sync`], which do nothing until [`init`] has been called.
+
+use serde::{Deserialize, Serialize};
+
+/// Where, and whether, to send traces and metrics.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct OtelConfig {
+    /// Export traces and metrics.
+    pub enabled: bool,
+
+    /// The OTLP/gRPC endpoint to send them to. Empty uses
+    /// `OTEL_EXPORTER_OTLP_ENDPOINT`, or `http://localhost:4317`.
+    pub endpoint: String,
+
+    /// The service to report as. Empty uses `OTEL_SERVICE_NAME`, or the
+    /// program's own name (`atuin-server` or `atuin-daemon`).
+    pub service_name: String,
+
+    /// The share of traces to keep, from 0 to 1. Traces carried on from a
+    /// sampled parent are always kept.
+    pub sample_ratio: f64,
+}
+
+impl Default for OtelConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: String::new(),
+            service_name: String::new(),
+            sample_ratio: 1.0,
+        }
+    }
+}
+
+#[cfg(feature = "otel")]
+pub use export::{OtelError, init, record_request, record_sync, shutdown};
+
+#[cfg(feature = "otel")]
+mod export {
+    use std::sync::OnceLock;
+    use std::time::Duration;
+
+    use opentelemetry::metrics::{Counter, Histogram, MeterProvider as _};
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry::{KeyValue, global};
+    use opentelemetry_otlp::{MetricExporter, SpanExporter, WithExportConfig};
+    use opentelemetry_sdk::Resource;
+    use opentelemetry_sdk::metrics::{PeriodicReader, SdkMeterProvider};
+    use opentelemetry_sdk::trace::{Sampler, SdkTracerProvider};
+    use tracing::Subscriber;
+    use tracing_subscriber::Layer;
+    use tracing_subscriber::registry::LookupSpan;
+
+    use super::OtelConfig;
+
+    #[derive(Debug, thiserror::Error)]
+    pub enum OtelError {
+        #[error("OpenTelemetry export is already set up")]
+        AlreadyStarted,
+        #[error("could not set up the OTLP exporter: {0}")]
+        Exporter(#[from] opentelemetry_otlp::ExporterBuildError),
+    }
+
+    struct Exporting {
+        tracer_provider: SdkTracerProvider,
+        meter_provider: SdkMeterProvider,
+        request_duration: Histogram<f64>,
+        sync_duration: Histogram<f64>,
+        sync_records: Counter<u64>,
+    }
+
+    static EXPORTING: OnceLock<Exporting> = OnceLock::new();
+
+    /// Start exporting as `config` says, reporting as `service` unless it names
+    /// another. Returns the layer that turns spans into traces, to add to the
+    /// subscriber.
+    ///
+    /// Must be called from within a Tokio runtime, which the exporter's
+    /// connection runs on.
+    pub fn init<S>(
+        config: &OtelConfig,
+        service: &'static str,
+    ) -> Result<impl Layer<S> + use<S>, OtelError>
+    where
+        S: Subscriber + for<'span> LookupSpan<'span>,
+    {
+        let mut resource = Resource::builder();
+        if !config.service_name.is_empty() {
+            resource = resource.with_service_name(config.service_name.clone());
+        } else if std::env::var_os("OTEL_SERVICE_NAME").is_none() {
+            resource = resource.with_service_name(service);
+        }
+        let resource = resource.build();
+
+        let mut spans = SpanExporter::builder().with_tonic();
+        let mut metrics = MetricExporter::builder().with_tonic();
+        if !config.endpoint.is_empty() {
+            spans = spans.with_endpoint(&config.endpoint);
+            metrics = metrics.with_endpoint(&config.endpoint);
+        }
+
+        let sampler = Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+            config.sample_ratio.clamp(0.0, 1.0),
+        )));
+        let tracer_provider = SdkTracerProvider::builder()
+            .with_batch_exporter(spans.build()?)
+            .with_sampler(sampler)
+            .with_resource(resource.clone())
+            .build();
+
+        let meter_provider = SdkMeterProvider::builder()
+            .with_reader(PeriodicReader::builder(metrics.build()?).build())
+            .with_resource(resource)
+            .build();
+
+        let tracer = tracer_provider.tracer(service);
+        let meter = meter_provider.meter(service);
+
+        let exporting = Exporting {
+            request_duration: meter
+                .f64_histogram("http.server.request.duration")
+                .with_unit("s")
+                .with_description("How long the sync server took to answer requests")
+                .build(),
+            sync_duration: meter
+                .f64_histogram("atuin.sync.duration")
+                .with_unit("s")
+                .with_description("How long syncs with the sync server took")
+                .build(),
+            sync_records: meter
+                .u64_counter("atuin.sync.records")
+                .with_unit("{record}")
+                .with_description("Records uploaded and downloaded by syncs")
+                .build(),
+            tracer_provider: tracer_provider.clone(),
+            meter_provider: meter_provider.clone(),
+        };
+        EXPORTING
+            .set(exporting)
+            .map_err(|_| OtelError::AlreadyStarted)?;
+
+        global::set_tracer_provider(tracer_provider);
+        global::set_meter_provider(meter_provider);
+
+        Ok(tracing_opentelemetry::layer().with_tracer(tracer))
+    }
+
+    /// Send whatever hasn't been exported yet, and stop exporting
+    pub async fn shutdown() {
+        let Some(exporting) = EXPORTING.get() else {
+            return;
+        };
+        let tracer_provider = exporting.tracer_provider.clone();
+        let meter_provider = exporting.meter_provider.clone();
+
+        // The exporters wait on the runtime to send, so this mustn't block it
+        let _ = tokio::task::spawn_blocking(move || {
+            if let Err(e) = tracer_provider.shutdown() {
+                tracing::debug!("failed to flush traces: {e}");
+            }
+            if let Err(e) = meter_provider.shutdown() {
+                tracing::debug!("failed to flush metrics: {e}");
+            }
+        })
+        .await;
+    }
+
+    /// Record a request the sync server answered
+    pub fn record_request(method: &str, route: &str, status: u16, duration: Duration) {
+        if let Some(exporting) = EXPORTING.get() {
+            exporting.request_duration.record(
+                duration.as_secs_f64(),
+                &[
+                    KeyValue::new("http.request.method", method.to_string()),
+                    KeyValue::new("http.route", route.to_string()),
+                    KeyValue::new("http.response.status_code", i64::from(status)),
+                ],
+            );
+        }
+    }
+
+    /// Record a sync with the sync server, and the records it moved if it
+    /// succeeded
+    pub fn record_sync(duration: Duration, moved: Option<(u64, u64)>) {
+        let Some(exporting) = EXPORTING.get() else {
+            return;
+        };
+
+        let outcome = if moved.is_some() {
+            "success"
+        } else {
+            "failure"
+        };
+        exporting
+            .sync_duration
+            .record(duration.as_secs_f64(), &[KeyValue::new("outcome", outcome)]);
+
+        if let Some((uploaded, downloaded)) = moved {
+            exporting
+                .sync_records
+                .add(uploaded, &[KeyValue::new("direction", "upload")]);
+            exporting
+                .sync_records
+                .add(downloaded, &[KeyValue::new("direction", "download")]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OtelConfig;
+
+    #[test]
+    fn a_partial_section_keeps_the_defaults() {
+        let config: OtelConfig = serde_json::from_str(r#"{"enabled": true}"#).unwrap();
+
+        assert!(config.enabled);
+        assert!(config.endpoint.is_empty());
+        assert!((config.sample_ratio - 1.0).abs() < f64::EPSILON);
+    }
+}
@@ -0,0 +1,326 @@
+//! Where atuin keeps its files.
+//!
+//! Everything follows the XDG base directory spec: config in `$XDG_CONFIG_HOME/atuin`, data such
+//! as the databases and key in `$XDG_DATA_HOME/atuin`, logs in `$XDG_STATE_HOME/atuin`, caches in
+//! `$XDG_CACHE_HOME/atuin` and the daemon's socket in `$XDG_RUNTIME_DIR`. macOS and Windows use
+//! the same layout under the home directory, rather than `~/Library` or `AppData`, as other
+//! command line tools do there, so one set of dotfiles works everywhere. Each file can also be
+//! moved with its own setting.
+//!
+//! Older versions kept some files elsewhere; [`relocate`] moves them on.
+
+use std::env;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// I don't want to use ProjectDirs, it puts config in awkward places on
+// mac. Data too. Seems to be more intended for GUI apps.
+
+pub fn home_dir() -> PathBuf {
+    let home = directories::BaseDirs::new()
+        .map(|d| d.home_dir().to_path_buf())
+        .expect("could not determine home directory");
+
+    #[cfg(unix)]
+    if let Some(own_home) = home_under_sudo(&home) {
+        return own_home;
+    }
+
+    home
+}
+
+/// Under `sudo`, `HOME` can still be the invoking user's, as with `sudo -E` or a sudoers file
+/// that keeps it. Using it would mix root's history into theirs, in files root then owns, so
+/// the target account's own home, from the user database, is used instead.
+#[cfg(unix)]
+fn home_under_sudo(home: &Path) -> Option<PathBuf> {
+    use std::os::unix::fs::MetadataExt;
+
+    let sudo_uid = sudo_uid()?;
+    if std::fs::metadata(home).ok()?.uid() != sudo_uid {
+        return None;
+    }
+
+    nix::unistd::User::from_uid(nix::unistd::Uid::effective())
+        .ok()
+        .flatten()
+        .map(|user| user.dir)
+}
+
+/// The uid of whoever ran `sudo`, when we're running as someone else.
+#[cfg(unix)]
+fn sudo_uid() -> Option<u32> {
+    let sudo_uid: u32 = env::var("SUDO_UID").ok()?.parse().ok()?;
+    (sudo_uid != rustix::process::geteuid().as_raw()).then_some(sudo_uid)
+}
+
+/// `$var`, unless it's empty, or it was set by whoever ran `sudo` and so points into their
+/// directories rather than ours, as with `sudo -E`.
+fn xdg_var(var: &str) -> Option<PathBuf> {
+    #[cfg(unix)]
+    if sudo_uid().is_some() {
+        return None;
+    }
+
+    env::var(var)
+        .ok()
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+}
+
+/// `$var/atuin`, or `~/<fallback>/atuin` when it isn't set.
+fn xdg_dir(var: &str, fallback: &[&str]) -> PathBuf {
+    xdg_var(var)
+        .unwrap_or_else(|| {
+            fallback
+                .iter()
+                .fold(home_dir(), |path, segment| path.join(segment))
+        })
+        .join("atuin")
+}
+
+/// Settings, themes and the like: `~/.config/atuin`.
+pub fn config_dir() -> PathBuf {
+    xdg_dir("XDG_CONFIG_HOME", &[".config"])
+}
+
+/// The databases and the encryption key: `~/.local/share/atuin`.
+pub fn data_dir() -> PathBuf {
+    xdg_dir("XDG_DATA_HOME", &[".local", "share"])
+}
+
+/// What's worth keeping between runs but not worth backing up, like logs:
+/// `~/.local/state/atuin`.
+pub fn state_dir() -> PathBuf {
+    xdg_dir("XDG_STATE_HOME", &[".local", "state"])
+}
+
+/// What can be built again whenever it's missing: `~/.cache/atuin`.
+pub fn cache_dir() -> PathBuf {
+    xdg_dir("XDG_CACHE_HOME", &[".cache"])
+}
+
+/// Where the daemon's socket goes. Without `XDG_RUNTIME_DIR`, as on macOS or under `sudo`,
+/// that's the data directory.
+pub fn runtime_dir() -> PathBuf {
+    xdg_var("XDG_RUNTIME_DIR").unwrap_or_else(data_dir)
+}
+
+pub fn logs_dir() -> PathBuf {
+    state_dir().join("logs")
+}
+
+/// The shell config built from synced aliases and vars, for init to read quickly.
+pub fn dotfiles_cache_dir() -> PathBuf {
+    cache_dir().join("dotfiles")
+}
+
+/// Where logs were kept before they moved to [`logs_dir`].
+pub fn legacy_logs_dir() -> PathBuf {
+    home_dir().join(".atuin").join("logs")
+}
+
+/// Where the dotfiles cache was kept before it moved to [`dotfiles_cache_dir`].
+pub fn legacy_dotfiles_cache_dir() -> PathBuf {
+    data_dir().join("dotfiles").join("cache")
+}
+
+/// Files that have a new home, and the directory they were in before.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Relocation {
+    /// What the files are, for messages
+    pub name: &'static str,
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+impl Relocation {
+    /// Whether there's anything left at the old location to move.
+    pub fn pending(&self) -> bool {
+        self.from != self.to && self.from.is_dir()
+    }
+}
+
+/// Move what's in `relocation.from` into `relocation.to`, returning how many files were moved.
+/// Files already at the new location are kept, and the old ones left where they were, so
+/// nothing is overwritten. The old directory, and its parent, are removed once they're empty.
+pub fn relocate(relocation: &Relocation) -> io::Result<usize> {
+    if !relocation.pending() {
+        return Ok(0);
+    }
+
+    std::fs::create_dir_all(&relocation.to)?;
+
+    let mut moved = 0;
+    for entry in std::fs::read_dir(&relocation.from)? {
+        let entry = entry?;
+        let to = relocation.to.join(entry.file_name());
+        if to.exists() {
+            continue;
+        }
+
+        // Renaming fails across filesystems, where a copy has to do
+        if std::fs::rename(entry.path(), &to).is_err() {
+            if !entry.file_type()?.is_file() {
+                continue;
+            }
+            std::fs::copy(entry.path(), &to)?;
+            std::fs::remove_file(entry.path())?;
+        }
+        moved += 1;
+    }
+
+    // Only removes them if they're empty, which is all that's wanted
+    if std::fs::remove_dir(&relocation.from).is_ok()
+        && let Some(parent) = relocation.from.parent()
+    {
+        let _ = std::fs::remove_dir(parent);
+    }
+
+    Ok(moved)
+}
+
+#[allow(unsafe_code)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_dirs() {
+        // these tests need to be run sequentially to prevent race condition
+        test_config_dir_xdg();
+        test_config_dir();
+        test_data_dir_xdg();
+        test_data_dir();
+        test_state_and_cache_dirs();
+        #[cfg(unix)]
+        test_xdg_dirs_under_sudo();
+    }
+
+    #[cfg(unix)]
+    fn test_xdg_dirs_under_sudo() {
+        let euid = rustix::process::geteuid().as_raw();
+        // TODO: Audit that the environment access only happens in single-threaded code.
+        unsafe { env::set_var("XDG_DATA_HOME", "/home/user/custom_data") };
+
+        // TODO: Audit that the environment access only happens in single-threaded code.
+        unsafe { env::set_var("SUDO_UID", euid.to_string()) };
+        assert_eq!(data_dir(), PathBuf::from("/home/user/custom_data/atuin"));
+
+        // TODO: Audit that the environment access only happens in single-threaded code.
+        unsafe { env::set_var("SUDO_UID", (euid + 1).to_string()) };
+        assert_ne!(data_dir(), PathBuf::from("/home/user/custom_data/atuin"));
+
+        // TODO: Audit that the environment access only happens in single-threaded code.
+        unsafe { env::remove_var("SUDO_UID") };
+        // TODO: Audit that the environment access only happens in single-threaded code.
+        unsafe { env::remove_var("XDG_DATA_HOME") };
+    }
+
+    #[cfg(not(windows))]
+    fn test_config_dir_xdg() {
+        // TODO: Audit that the environment access only happens in single-threaded code.
+        unsafe { env::remove_var("HOME") };
+        // TODO: Audit that the environment access only happens in single-threaded code.
+        unsafe { env::set_var("XDG_CONFIG_HOME", "/home/user/custom_config") };
+        assert_eq!(
+            config_dir(),
+            PathBuf::from("/home/user/custom_config/atuin")
+        );
+        // TODO: Audit that the environment access only happens in single-threaded code.
+        unsafe { env::remove_var("XDG_CONFIG_HOME") };
+    }
+
+    #[cfg(not(windows))]
+    fn test_config_dir() {
+        // TODO: Audit that the environment access only happens in single-threaded code.
+        unsafe { env::set_var("HOME", "/home/user") };
+        // TODO: Audit that the environment access only happens in single-threaded code.
+        unsafe { env::remove_var("XDG_CONFIG_HOME") };
+
+        assert_eq!(config_dir(), PathBuf::from("/home/user/.config/atuin"));
+
+        // TODO: Audit that the environment access only happens in single-threaded code.
+        unsafe { env::remove_var("HOME") };
+    }
+
+    #[cfg(not(windows))]
+    fn test_data_dir_xdg() {
+        // TODO: Audit that the environment access only happens in single-threaded code.
+        unsafe { env::remove_var("HOME") };
+        // TODO: Audit that the environment access only happens in single-threaded code.
+        unsafe { env::set_var("XDG_DATA_HOME", "/home/user/custom_data") };
+        assert_eq!(data_dir(), PathBuf::from("/home/user/custom_data/atuin"));
+        // TODO: Audit that the environment access only happens in single-threaded code.
+        unsafe { env::remove_var("XDG_DATA_HOME") };
+    }
+
+    #[cfg(not(windows))]
+    fn test_data_dir() {
+        // TODO: Audit that the environment access only happens in single-threaded code.
+        unsafe { env::set_var("HOME", "/home/user") };
+        // TODO: Audit that the environment access only happens in single-threaded code.
+        unsafe { env::remove_var("XDG_DATA_HOME") };
+        assert_eq!(data_dir(), PathBuf::from("/home/user/.local/share/atuin"));
+        // TODO: Audit that the environment access only happens in single-threaded code.
+        unsafe { env::remove_var("HOME") };
+    }
+
+    #[cfg(not(windows))]
+    fn test_state_and_cache_dirs() {
+        // TODO: Audit that the environment access only happens in single-threaded code.
+        unsafe { env::set_var("HOME", "/home/user") };
+        // TODO: Audit that the environment access only happens in single-threaded code.
+        unsafe { env::remove_var("XDG_STATE_HOME") };
+        // An empty variable counts as unset, as the spec says
+        // TODO: Audit that the environment access only happens in single-threaded code.
+        unsafe { env::set_var("XDG_CACHE_HOME", "") };
+
+        assert_eq!(
+            logs_dir(),
+            PathBuf::from("/home/user/.local/state/atuin/logs")
+        );
+        assert_eq!(
+            dotfiles_cache_dir(),
+            PathBuf::from("/home/user/.cache/atuin/dotfiles")
+        );
+
+        // TODO: Audit that the environment access only happens in single-threaded code.
+        unsafe { env::remove_var("XDG_CACHE_HOME") };
+        // TODO: Audit that the environment access only happens in single-threaded code.
+        unsafe { env::remove_var("HOME") };
+    }
+
+    #[test]
+    fn relocating_keeps_what_is_already_there() {
+        let root = env::temp_dir().join(format!("atuin-test-relocate-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let relocation = Relocation {
+            name: "logs",
+            from: root.join(".atuin").join("logs"),
+            to: root.join("state").join("logs"),
+        };
+        std::fs::create_dir_all(&relocation.from).unwrap();
+        std::fs::create_dir_all(&relocation.to).unwrap();
+        std::fs::write(relocation.from.join("search.log"), "old").unwrap();
+        std::fs::write(relocation.from.join("daemon.log"), "old").unwrap();
+        std::fs::write(relocation.to.join("daemon.log"), "new").unwrap();
+
+        assert!(relocation.pending());
+        assert_eq!(relocate(&relocation).unwrap(), 1);
+
+        let read = |path: PathBuf| std::fs::read_to_string(path).unwrap();
+        assert_eq!(read(relocation.to.join("search.log")), "old");
+        assert_eq!(read(relocation.to.join("daemon.log")), "new");
+        // The clash stays behind, so the old directory does too
+        assert_eq!(read(relocation.from.join("daemon.log")), "old");
+
+        std::fs::remove_file(relocation.from.join("daemon.log")).unwrap();
+        assert_eq!(relocate(&relocation).unwrap(), 0);
+        assert!(!root.join(".atuin").exists());
+        assert!(!relocation.pending());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
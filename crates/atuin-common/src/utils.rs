@@ -1,12 +1,18 @@
 use std::env;
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
 use eyre::{Result, eyre};
 
 use base64::prelude::{BASE64_URL_SAFE_NO_PAD, Engine};
 use getrandom::fill;
+use time::{OffsetDateTime, format_description::well_known::Rfc3339};
 use uuid::Uuid;
 
+/// Set to an RFC 3339 time to pin [`now`] to it, so integration tests and
+/// screenshots of the TUI show the same relative times on every run.
+pub const FAKE_TIME_ENV: &str = "ATUIN_FAKE_TIME";
+
 /// Generate N random bytes, using a cryptographically secure source
 pub fn crypto_random_bytes<const N: usize>() -> [u8; N] {
     // rand say they are in principle safe for crypto purposes, but that it is perhaps a better
@@ -35,6 +41,18 @@ pub fn uuid_v4() -> String {
     Uuid::new_v4().as_simple().to_string()
 }
 
+/// The current time as search and stats see it: the time in
+/// [`FAKE_TIME_ENV`] if that's set, otherwise the system clock.
+pub fn now() -> OffsetDateTime {
+    static FAKE: OnceLock<Option<OffsetDateTime>> = OnceLock::new();
+
+    FAKE.get_or_init(|| {
+        let fake = env::var(FAKE_TIME_ENV).ok()?;
+        OffsetDateTime::parse(fake.trim(), &Rfc3339).ok()
+    })
+    .unwrap_or_else(OffsetDateTime::now_utc)
+}
+
 pub fn has_git_dir(path: &str) -> bool {
     let mut gitdir = PathBuf::from(path);
     gitdir.push(".git");
@@ -96,53 +114,57 @@ pub fn in_git_repo(path: &str) -> Option<PathBuf> {
     None
 }
 
-// TODO: more reliable, more tested
-// I don't want to use ProjectDirs, it puts config in awkward places on
-// mac. Data too. Seems to be more intended for GUI apps.
+// These moved to `paths`, and are kept here for crates that still call them.
 
+#[deprecated(note = "use `atuin_common::paths::home_dir`")]
 pub fn home_dir() -> PathBuf {
-    directories::BaseDirs::new()
-        .map(|d| d.home_dir().to_path_buf())
-        .expect("could not determine home directory")
+    crate::paths::home_dir()
 }
 
+#[deprecated(note = "use `atuin_common::paths::config_dir`")]
 pub fn config_dir() -> PathBuf {
-    let config_dir =
-        std::env::var("XDG_CONFIG_HOME").map_or_else(|_| home_dir().join(".config"), PathBuf::from);
-    config_dir.join("atuin")
+    crate::paths::config_dir()
 }
 
+#[deprecated(note = "use `atuin_common::paths::data_dir`")]
 pub fn data_dir() -> PathBuf {
-    let data_dir = std::env::var("XDG_DATA_HOME")
-        .map_or_else(|_| home_dir().join(".local").join("share"), PathBuf::from);
+    crate::paths::data_dir()
+}
 
-    data_dir.join("atuin")
+#[deprecated(note = "use `atuin_common::paths::cache_dir`")]
+pub fn cache_dir() -> PathBuf {
+    crate::paths::cache_dir()
 }
 
+#[deprecated(note = "use `atuin_common::paths::runtime_dir`")]
 pub fn runtime_dir() -> PathBuf {
-    std::env::var("XDG_RUNTIME_DIR").map_or_else(|_| data_dir(), PathBuf::from)
+    crate::paths::runtime_dir()
 }
 
+#[deprecated(note = "use `atuin_common::paths::logs_dir`")]
 pub fn logs_dir() -> PathBuf {
-    home_dir().join(".atuin").join("logs")
+    crate::paths::logs_dir()
 }
 
+#[deprecated(note = "use `atuin_common::paths::dotfiles_cache_dir`")]
 pub fn dotfiles_cache_dir() -> PathBuf {
-    // In most cases, this will be  ~/.local/share/atuin/dotfiles/cache
-    let data_dir = std::env::var("XDG_DATA_HOME")
-        .map_or_else(|_| home_dir().join(".local").join("share"), PathBuf::from);
-
-    data_dir.join("atuin").join("dotfiles").join("cache")
+    crate::paths::dotfiles_cache_dir()
 }
 
 pub fn get_current_dir() -> String {
     // Prefer PWD environment variable over cwd if available to better support symbolic links
-    match env::var("PWD") {
+    let dir = match env::var("PWD") {
         Ok(v) => v,
         Err(_) => match env::current_dir() {
             Ok(dir) => dir.display().to_string(),
             Err(_) => String::from(""),
         },
+    };
+
+    if crate::wsl::is_wsl() {
+        crate::wsl::normalize_path(&dir, &crate::wsl::mount_root())
+    } else {
+        dir
     }
 }
 
@@ -202,65 +224,6 @@ mod tests {
 
     use super::*;
 
-    #[cfg(not(windows))]
-    #[test]
-    fn test_dirs() {
-        // these tests need to be run sequentially to prevent race condition
-        test_config_dir_xdg();
-        test_config_dir();
-        test_data_dir_xdg();
-        test_data_dir();
-    }
-
-    #[cfg(not(windows))]
-    fn test_config_dir_xdg() {
-        // TODO: Audit that the environment access only happens in single-threaded code.
-        unsafe { env::remove_var("HOME") };
-        // TODO: Audit that the environment access only happens in single-threaded code.
-        unsafe { env::set_var("XDG_CONFIG_HOME", "/home/user/custom_config") };
-        assert_eq!(
-            config_dir(),
-            PathBuf::from("/home/user/custom_config/atuin")
-        );
-        // TODO: Audit that the environment access only happens in single-threaded code.
-        unsafe { env::remove_var("XDG_CONFIG_HOME") };
-    }
-
-    #[cfg(not(windows))]
-    fn test_config_dir() {
-        // TODO: Audit that the environment access only happens in single-threaded code.
-        unsafe { env::set_var("HOME", "/home/user") };
-        // TODO: Audit that the environment access only happens in single-threaded code.
-        unsafe { env::remove_var("XDG_CONFIG_HOME") };
-
-        assert_eq!(config_dir(), PathBuf::from("/home/user/.config/atuin"));
-
-        // TODO: Audit that the environment access only happens in single-threaded code.
-        unsafe { env::remove_var("HOME") };
-    }
-
-    #[cfg(not(windows))]
-    fn test_data_dir_xdg() {
-        // TODO: Audit that the environment access only happens in single-threaded code.
-        unsafe { env::remove_var("HOME") };
-        // TODO: Audit that the environment access only happens in single-threaded code.
-        unsafe { env::set_var("XDG_DATA_HOME", "/home/user/custom_data") };
-        assert_eq!(data_dir(), PathBuf::from("/home/user/custom_data/atuin"));
-        // TODO: Audit that the environment access only happens in single-threaded code.
-        unsafe { env::remove_var("XDG_DATA_HOME") };
-    }
-
-    #[cfg(not(windows))]
-    fn test_data_dir() {
-        // TODO: Audit that the environment access only happens in single-threaded code.
-        unsafe { env::set_var("HOME", "/home/user") };
-        // TODO: Audit that the environment access only happens in single-threaded code.
-        unsafe { env::remove_var("XDG_DATA_HOME") };
-        assert_eq!(data_dir(), PathBuf::from("/home/user/.local/share/atuin"));
-        // TODO: Audit that the environment access only happens in single-threaded code.
-        unsafe { env::remove_var("HOME") };
-    }
-
     #[cfg(not(windows))]
     #[test]
     fn in_git_repo_regular() {
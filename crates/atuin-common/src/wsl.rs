@@ -0,0 +1,173 @@
+//! Running under the Windows Subsystem for Linux.
+//!
+//! A WSL distro shares its host's name and drives with Windows, and an atuin running on either
+//! side can sync with the other, so the directories and host names it records are made to tell
+//! the two apart.
+
+use std::sync::OnceLock;
+
+/// Whether this is running under WSL, going by the kernel Microsoft builds for it.
+pub fn is_wsl() -> bool {
+    static IS_WSL: OnceLock<bool> = OnceLock::new();
+
+    *IS_WSL.get_or_init(|| version().is_some())
+}
+
+/// 1 or 2 for the version of WSL this is running under, or `None` outside it.
+pub fn version() -> Option<u8> {
+    let release = std::fs::read_to_string("/proc/sys/kernel/osrelease").ok()?;
+    version_from_osrelease(&release)
+}
+
+fn version_from_osrelease(release: &str) -> Option<u8> {
+    let release = release.to_lowercase();
+    if release.contains("wsl2") {
+        Some(2)
+    } else if release.contains("microsoft") {
+        Some(1)
+    } else {
+        None
+    }
+}
+
+/// The name of the WSL distro this is running in, as Windows knows it.
+pub fn distro() -> Option<String> {
+    if !is_wsl() {
+        return None;
+    }
+
+    // `sudo` clears the variable WSL sets, so fall back to the distro's own name for itself
+    std::env::var("WSL_DISTRO_NAME")
+        .ok()
+        .filter(|name| !name.is_empty())
+        .or_else(|| {
+            let os_release = std::fs::read_to_string("/etc/os-release").ok()?;
+            os_release
+                .lines()
+                .find_map(|line| line.strip_prefix("ID="))
+                .map(|id| id.trim_matches('"').to_string())
+        })
+}
+
+/// A setting from the `[section]` of the distro's `/etc/wsl.conf`.
+pub fn conf(section: &str, key: &str) -> Option<String> {
+    conf_value(
+        &std::fs::read_to_string("/etc/wsl.conf").ok()?,
+        section,
+        key,
+    )
+}
+
+fn conf_value(conf: &str, section: &str, key: &str) -> Option<String> {
+    let mut in_section = false;
+    for line in conf.lines().map(str::trim) {
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            in_section = name.trim() == section;
+        } else if in_section
+            && let Some((k, v)) = line.split_once('=')
+            && k.trim() == key
+        {
+            return Some(v.trim().trim_matches('"').to_string());
+        }
+    }
+    None
+}
+
+/// Where Windows' drives are mounted, `/mnt/` unless `wsl.conf` moves them.
+pub fn mount_root() -> String {
+    let root = conf("automount", "root").unwrap_or_else(|| "/mnt/".to_string());
+    if root.ends_with('/') {
+        root
+    } else {
+        format!("{root}/")
+    }
+}
+
+/// `path` as this distro sees it: Windows paths such as `C:\Users\me` become
+/// `/mnt/c/Users/me`, and drives are always lower case, as WSL mounts them, so the same
+/// directory is recorded the same way however it was reached.
+pub fn normalize_path(path: &str, mount_root: &str) -> String {
+    let mut chars = path.chars();
+    if let (Some(drive), Some(':')) = (chars.next(), chars.next())
+        && drive.is_ascii_alphabetic()
+    {
+        let rest = chars.as_str().replace('\\', "/");
+        let rest = rest.trim_end_matches('/');
+        return format!("{mount_root}{}{rest}", drive.to_ascii_lowercase());
+    }
+
+    if let Some(rest) = path.strip_prefix(mount_root) {
+        let mut chars = rest.chars();
+        if let Some(drive) = chars.next()
+            && drive.is_ascii_uppercase()
+            && matches!(chars.next(), None | Some('/'))
+        {
+            return format!("{mount_root}{}{}", drive.to_ascii_lowercase(), &rest[1..]);
+        }
+    }
+
+    path.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wsl_is_told_from_its_kernel() {
+        assert_eq!(
+            version_from_osrelease("5.15.153.1-microsoft-standard-WSL2\n"),
+            Some(2)
+        );
+        assert_eq!(version_from_osrelease("4.4.0-19041-Microsoft\n"), Some(1));
+        assert_eq!(version_from_osrelease("6.8.0-45-generic\n"), None);
+    }
+
+    #[test]
+    fn windows_paths_become_wsl_ones() {
+        assert_eq!(
+            normalize_path(r"C:\Users\me\src", "/mnt/"),
+            "/mnt/c/Users/me/src"
+        );
+        assert_eq!(normalize_path("D:/games/", "/mnt/"), "/mnt/d/games");
+        assert_eq!(normalize_path(r"C:\", "/mnt/"), "/mnt/c");
+        assert_eq!(
+            normalize_path("/mnt/C/Users/me", "/mnt/"),
+            "/mnt/c/Users/me"
+        );
+        assert_eq!(normalize_path("/mnt/C", "/mnt/"), "/mnt/c");
+        assert_eq!(normalize_path(r"E:\work", "/win/"), "/win/e/work");
+
+        // Already as WSL sees them, or not on a Windows drive at all
+        assert_eq!(
+            normalize_path("/mnt/c/Users/me", "/mnt/"),
+            "/mnt/c/Users/me"
+        );
+        assert_eq!(normalize_path("/mnt/Data/x", "/mnt/"), "/mnt/Data/x");
+        assert_eq!(normalize_path("/home/me", "/mnt/"), "/home/me");
+    }
+
+    #[test]
+    fn wsl_conf_settings_are_read_from_their_section() {
+        let conf = "\
+[boot]
+systemd=true
+
+[automount]
+root = /win/
+options = \"metadata\"
+
+[network]
+hostname = devbox
+";
+        assert_eq!(
+            conf_value(conf, "automount", "root"),
+            Some("/win/".to_string())
+        );
+        assert_eq!(
+            conf_value(conf, "network", "hostname"),
+            Some("devbox".to_string())
+        );
+        assert_eq!(conf_value(conf, "boot", "hostname"), None);
+    }
+}
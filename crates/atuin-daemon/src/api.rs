@@ -0,0 +1,616 @@
+//! Local HTTP API for GUI front-ends.
+//!
+//! With `daemon.api_enabled` set, the daemon serves JSON on
+//! `127.0.0.1:<daemon.api_port>` so launchers and browser extensions can query
+//! history without running the CLI for every keystroke:
+//!
+//! - `GET /search?q=<query>&limit=<n>&filter_mode=<mode>`: matching history,
+//!   best match first, from the daemon's search index
+//! - `GET /stats?count=<n>&ngram_size=<n>&from=<time>&to=<time>`: the same
+//!   numbers as `atuin stats`
+//! - `GET /history/{id}`: one entry, with its neighbours and run statistics
+//!
+//! Every request must send `Authorization: Bearer <token>`, where the token is
+//! the contents of `daemon.api_token_path`. The daemon writes a random token
+//! there, readable only by the user, if the file doesn't exist yet.
+
+use std::{borrow::Cow, path::Path, sync::Arc};
+
+use atuin_client::{
+    database::Database,
+    history::{History, HistoryId, HistoryStats},
+};
+use atuin_common::{api::ErrorResponse, utils::crypto_random_string};
+use axum::{
+    Json, Router,
+    extract::{FromRequestParts, Path as UrlPath, Query, State},
+    http::{StatusCode, header, request::Parts},
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use eyre::{Context, Result};
+use serde::{Deserialize, Serialize};
+use time::{OffsetDateTime, format_description::well_known::Rfc3339};
+use tokio::{net::TcpListener, sync::RwLock};
+use uuid::Uuid;
+
+use crate::{
+    daemon::DaemonHandle,
+    events::DaemonEvent,
+    search::{IndexFilterMode, QueryContext, SearchIndex},
+};
+
+const TOKEN_BYTES: usize = 32;
+const DEFAULT_SEARCH_LIMIT: u32 = 50;
+/// The most results a single search returns, matching the gRPC search service.
+const MAX_SEARCH_LIMIT: u32 = 200;
+
+#[derive(Clone)]
+struct ApiState {
+    handle: DaemonHandle,
+    index: Arc<RwLock<SearchIndex>>,
+    token: Arc<str>,
+}
+
+/// An error sent back to the client as `{"reason": "..."}`.
+#[derive(Debug)]
+struct ApiError {
+    status: StatusCode,
+    reason: Cow<'static, str>,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, reason: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            status,
+            reason: reason.into(),
+        }
+    }
+
+    fn internal(err: &impl std::fmt::Debug) -> Self {
+        tracing::error!(error = ?err, "http api request failed");
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, "internal error")
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = ErrorResponse {
+            reason: self.reason,
+        };
+        (self.status, Json(body)).into_response()
+    }
+}
+
+/// Rejects requests that don't carry the API token.
+struct Authorized;
+
+impl FromRequestParts<ApiState> for Authorized {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &ApiState) -> Result<Self, ApiError> {
+        let token = parts
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or_else(|| ApiError::new(StatusCode::UNAUTHORIZED, "missing bearer token"))?;
+
+        if !constant_time_eq(token.as_bytes(), state.token.as_bytes()) {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED, "invalid token"));
+        }
+
+        Ok(Authorized)
+    }
+}
+
+/// Compare without bailing at the first difference, so response times don't
+/// leak how much of a guessed token was right.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// A history entry as the API returns it.
+#[derive(Debug, Serialize)]
+struct HistoryJson {
+    id: String,
+    /// RFC 3339, in UTC.
+    timestamp: String,
+    /// Nanoseconds, or -1 if the command hasn't finished.
+    duration: i64,
+    exit: i64,
+    command: String,
+    cwd: String,
+    session: String,
+    hostname: String,
+    author: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    intent: Option<String>,
+}
+
+impl From<History> for HistoryJson {
+    fn from(h: History) -> Self {
+        Self {
+            id: h.id.0,
+            timestamp: h.timestamp.format(&Rfc3339).unwrap_or_default(),
+            duration: h.duration,
+            exit: h.exit,
+            command: h.command,
+            cwd: h.cwd,
+            session: h.session,
+            hostname: h.hostname,
+            author: h.author,
+            intent: h.intent,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum FilterMode {
+    Global,
+    Host,
+    Session,
+    Directory,
+    Workspace,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchParams {
+    #[serde(default)]
+    q: String,
+    limit: Option<u32>,
+    filter_mode: Option<FilterMode>,
+    cwd: Option<String>,
+    git_root: Option<String>,
+    hostname: Option<String>,
+    session: Option<String>,
+}
+
+impl SearchParams {
+    fn filter(&self) -> Result<IndexFilterMode, ApiError> {
+        let needs = |value: &Option<String>, name: &'static str| {
+            value.clone().ok_or_else(|| {
+                ApiError::new(
+                    StatusCode::BAD_REQUEST,
+                    format!("this filter_mode needs the {name} parameter"),
+                )
+            })
+        };
+
+        Ok(match self.filter_mode {
+            None | Some(FilterMode::Global) => IndexFilterMode::Global,
+            Some(FilterMode::Host) => IndexFilterMode::Host(needs(&self.hostname, "hostname")?),
+            Some(FilterMode::Session) => IndexFilterMode::Session(needs(&self.session, "session")?),
+            Some(FilterMode::Directory) => {
+                IndexFilterMode::Directory(with_trailing_slash(needs(&self.cwd, "cwd")?))
+            }
+            Some(FilterMode::Workspace) => {
+                IndexFilterMode::Workspace(with_trailing_slash(needs(&self.git_root, "git_root")?))
+            }
+        })
+    }
+}
+
+/// The search index stores directories with a trailing slash.
+fn with_trailing_slash(mut dir: String) -> String {
+    if !dir.ends_with('/') {
+        dir.push('/');
+    }
+    dir
+}
+
+#[derive(Debug, Serialize)]
+struct SearchResponse {
+    results: Vec<HistoryJson>,
+}
+
+async fn search(
+    _: Authorized,
+    State(state): State<ApiState>,
+    Query(params): Query<SearchParams>,
+) -> Result<Json<SearchResponse>, ApiError> {
+    let filter = params.filter()?;
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_SEARCH_LIMIT)
+        .min(MAX_SEARCH_LIMIT);
+    let context = QueryContext {
+        cwd: params.cwd.clone(),
+        git_root: params.git_root.clone(),
+        hostname: params.hostname.clone(),
+        session_id: params.session.clone(),
+    };
+
+    let ids = state
+        .index
+        .read()
+        .await
+        .search(&params.q, filter, &context, limit)
+        .await;
+    // The index hands out hyphenated UUIDs; history IDs are stored without
+    // hyphens.
+    let ids: Vec<HistoryId> = ids
+        .iter()
+        .filter_map(|id| Uuid::parse_str(id).ok())
+        .map(|id| HistoryId::from(id.as_simple().to_string()))
+        .collect();
+
+    let mut histories = state
+        .handle
+        .history_db()
+        .load_active(&ids)
+        .await
+        .map_err(|e| ApiError::internal(&e))?;
+    // `load_active` doesn't keep the order we asked in, and that order is the
+    // ranking.
+    histories.sort_by_key(|h| ids.iter().position(|id| *id == h.id));
+
+    Ok(Json(SearchResponse {
+        results: histories.into_iter().map(HistoryJson::from).collect(),
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+struct StatsParams {
+    count: Option<usize>,
+    ngram_size: Option<usize>,
+    /// RFC 3339. Defaults to the start of history.
+    from: Option<String>,
+    /// RFC 3339. Defaults to now.
+    to: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct StatsResponse {
+    total_commands: usize,
+    unique_commands: usize,
+    top: Vec<(Vec<String>, usize)>,
+}
+
+fn parse_time(value: Option<&str>, name: &str) -> Result<Option<OffsetDateTime>, ApiError> {
+    value
+        .map(|value| {
+            OffsetDateTime::parse(value, &Rfc3339).map_err(|_| {
+                ApiError::new(
+                    StatusCode::BAD_REQUEST,
+                    format!("{name} must be an RFC 3339 time"),
+                )
+            })
+        })
+        .transpose()
+}
+
+async fn stats(
+    _: Authorized,
+    State(state): State<ApiState>,
+    Query(params): Query<StatsParams>,
+) -> Result<Json<StatsResponse>, ApiError> {
+    let from = parse_time(params.from.as_deref(), "from")?.unwrap_or(OffsetDateTime::UNIX_EPOCH);
+    let to = parse_time(params.to.as_deref(), "to")?.unwrap_or_else(atuin_common::utils::now);
+
+    let history = state
+        .handle
+        .history_db()
+        .range(from, to)
+        .await
+        .map_err(|e| ApiError::internal(&e))?;
+
+    let settings = state.handle.settings().await;
+    let stats = atuin_history::stats::compute(
+        &settings,
+        &history,
+        params.count.unwrap_or(10),
+        params.ngram_size.unwrap_or(1).max(1),
+    );
+
+    Ok(Json(stats.map_or(
+        StatsResponse {
+            total_commands: 0,
+            unique_commands: 0,
+            top: Vec::new(),
+        },
+        |stats| StatsResponse {
+            total_commands: stats.total_commands,
+            unique_commands: stats.unique_commands,
+            top: stats.top,
+        },
+    )))
+}
+
+#[derive(Debug, Serialize)]
+struct HistoryResponse {
+    history: HistoryJson,
+    previous: Option<HistoryJson>,
+    next: Option<HistoryJson>,
+    /// How many times this command has been run.
+    total: u64,
+    /// Nanoseconds.
+    average_duration: u64,
+    /// Pairs of exit code and how many runs exited with it.
+    exits: Vec<(i64, i64)>,
+}
+
+async fn history(
+    _: Authorized,
+    State(state): State<ApiState>,
+    UrlPath(id): UrlPath<String>,
+) -> Result<Json<HistoryResponse>, ApiError> {
+    let db = state.handle.history_db();
+    let history = db
+        .load(&id)
+        .await
+        .map_err(|e| ApiError::internal(&e))?
+        .filter(|h| h.deleted_at.is_none())
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "no such history entry"))?;
+
+    let HistoryStats {
+        previous,
+        next,
+        total,
+        average_duration,
+        exits,
+        ..
+    } = db
+        .stats(&history)
+        .await
+        .map_err(|e| ApiError::internal(&e))?;
+
+    Ok(Json(HistoryResponse {
+        history: history.into(),
+        previous: previous.map(Into::into),
+        next: next.map(Into::into),
+        total,
+        average_duration,
+        exits,
+    }))
+}
+
+fn router(state: ApiState) -> Router {
+    Router::new()
+        .route("/search", get(search))
+        .route("/stats", get(stats))
+        .route("/history/{id}", get(history))
+        .with_state(state)
+}
+
+/// Read the API token from `path`, creating it if it doesn't exist.
+pub fn load_or_create_token(path: &Path) -> Result<String> {
+    match std::fs::read_to_string(path) {
+        Ok(token) if !token.trim().is_empty() => return Ok(token.trim().to_string()),
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => {
+            return Err(e).wrap_err_with(|| format!("could not read {}", path.display()));
+        }
+    }
+
+    let token = crypto_random_string::<TOKEN_BYTES>();
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    let mut file = options
+        .open(path)
+        .wrap_err_with(|| format!("could not create {}", path.display()))?;
+    std::io::Write::write_all(&mut file, token.as_bytes())?;
+
+    Ok(token)
+}
+
+/// Run the HTTP API with the given search index.
+///
+/// This binds the port and then serves in the background, returning
+/// immediately. The server shuts down when a ShutdownRequested event is
+/// received.
+pub async fn run_api_server(handle: DaemonHandle, index: Arc<RwLock<SearchIndex>>) -> Result<()> {
+    let (port, token_path) = {
+        let settings = handle.settings().await;
+        (
+            settings.daemon.api_port,
+            settings.daemon.api_token_path.clone(),
+        )
+    };
+
+    let token = load_or_create_token(Path::new(&token_path))?;
+    let listener = TcpListener::bind(("127.0.0.1", port))
+        .await
+        .wrap_err_with(|| format!("could not bind the http api to 127.0.0.1:{port}"))?;
+    tracing::info!("http api listening on 127.0.0.1:{port}");
+
+    let app = router(ApiState {
+        handle: handle.clone(),
+        index,
+        token: token.into(),
+    });
+
+    let shutdown_signal = async move {
+        let mut rx = handle.subscribe();
+        loop {
+            match rx.recv().await {
+                Ok(DaemonEvent::ShutdownRequested) | Err(_) => break,
+                Ok(_) => {}
+            }
+        }
+    };
+
+    tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, app)
+            .with_graceful_shutdown(shutdown_signal)
+            .await
+        {
+            tracing::error!("http api error: {e}");
+        }
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use atuin_client::{
+        database::{Database, Sqlite},
+        history::History,
+        record::sqlite_store::SqliteStore,
+        settings::Settings,
+    };
+    use axum::{
+        body::{Body, to_bytes},
+        http::{Request, StatusCode, header},
+    };
+    use time::macros::datetime;
+    use tokio::sync::RwLock;
+    use tower::ServiceExt;
+
+    use super::{ApiState, constant_time_eq, load_or_create_token, router};
+    use crate::{Daemon, search::SearchIndex};
+
+    const TOKEN: &str = "let-me-in";
+
+    async fn test_router(tmp: &tempfile::TempDir) -> (axum::Router, History) {
+        let db_path = tmp.path().join("history.db");
+        let record_path = tmp.path().join("records.db");
+
+        let settings: Settings = Settings::builder()
+            .unwrap()
+            .set_override("db_path", db_path.to_str().unwrap())
+            .unwrap()
+            .set_override("record_store_path", record_path.to_str().unwrap())
+            .unwrap()
+            .set_override("key_path", tmp.path().join("key").to_str().unwrap())
+            .unwrap()
+            .build()
+            .unwrap()
+            .try_deserialize()
+            .unwrap();
+
+        let history_db = Sqlite::new(&db_path, 5.0).await.unwrap();
+        let store = SqliteStore::new(&record_path, 5.0).await.unwrap();
+
+        let history: History = History::import()
+            .timestamp(datetime!(2026-10-15 12:00 UTC))
+            .command("cargo test --workspace")
+            .cwd("/src/atuin")
+            .exit(0)
+            .duration(1_000_000)
+            .build()
+            .into();
+        history_db.save(&history).await.unwrap();
+
+        let index = SearchIndex::new();
+        index.add_history(&history);
+
+        let daemon = Daemon::builder(settings)
+            .store(store)
+            .history_db(history_db)
+            .build()
+            .await
+            .unwrap();
+
+        let app = router(ApiState {
+            handle: daemon.handle(),
+            index: Arc::new(RwLock::new(index)),
+            token: TOKEN.into(),
+        });
+
+        (app, history)
+    }
+
+    async fn get(app: axum::Router, uri: &str, token: Option<&str>) -> (StatusCode, String) {
+        let mut request = Request::get(uri);
+        if let Some(token) = token {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {token}"));
+        }
+
+        let response = app
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn requests_need_the_token() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (app, _) = test_router(&tmp).await;
+
+        let (status, _) = get(app.clone(), "/search?q=cargo", None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let (status, body) = get(app, "/stats", Some("guess")).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body, r#"{"reason":"invalid token"}"#);
+    }
+
+    #[tokio::test]
+    async fn search_stats_and_history() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (app, history) = test_router(&tmp).await;
+
+        let (status, body) = get(app.clone(), "/search?q=cargo", Some(TOKEN)).await;
+        assert_eq!(status, StatusCode::OK);
+        let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["results"][0]["id"], history.id.0);
+        assert_eq!(body["results"][0]["command"], "cargo test --workspace");
+        assert_eq!(body["results"][0]["timestamp"], "2026-10-15T12:00:00Z");
+
+        let (status, _) = get(
+            app.clone(),
+            "/search?q=cargo&filter_mode=directory",
+            Some(TOKEN),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, body) = get(app.clone(), "/stats", Some(TOKEN)).await;
+        assert_eq!(status, StatusCode::OK);
+        let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["total_commands"], 1);
+        assert_eq!(body["top"][0][0][0], "cargo test");
+
+        let uri = format!("/history/{}", history.id.0);
+        let (status, body) = get(app.clone(), &uri, Some(TOKEN)).await;
+        assert_eq!(status, StatusCode::OK);
+        let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["history"]["cwd"], "/src/atuin");
+        assert_eq!(body["total"], 1);
+
+        let (status, _) = get(app, "/history/nope", Some(TOKEN)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn token_is_created_once_and_private() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("daemon_api_token");
+
+        let token = load_or_create_token(&path).unwrap();
+        assert_eq!(token.len(), 43);
+        assert_eq!(load_or_create_token(&path).unwrap(), token);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn token_comparison() {
+        assert!(constant_time_eq(b"abc", b"abc"));
+        assert!(!constant_time_eq(b"abc", b"abd"));
+        assert!(!constant_time_eq(b"abc", b"abcd"));
+    }
+}
@@ -12,7 +12,7 @@ use hyper_util::rt::TokioIo;
 #[cfg(unix)]
 use tokio::net::UnixStream;
 
-use atuin_client::history::History;
+use atuin_client::history::{History, Origin};
 use tracing::{Level, instrument, span};
 
 use crate::control::HistoryRebuiltEvent;
@@ -22,13 +22,14 @@ use crate::control::{
 };
 use crate::events::DaemonEvent;
 use crate::history::{
-    CancelHistoryReply, CancelHistoryRequest, EndHistoryReply, EndHistoryRequest, ShutdownRequest,
-    StartHistoryReply, StartHistoryRequest, StatusReply, StatusRequest, TailHistoryReply,
-    TailHistoryRequest, history_client::HistoryClient as HistoryServiceClient,
+    CancelHistoryReply, CancelHistoryRequest, EndHistoryReply, EndHistoryRequest, PromptInfoReply,
+    PromptInfoRequest, ShutdownRequest, StartHistoryReply, StartHistoryRequest, StatusReply,
+    StatusRequest, TailHistoryReply, TailHistoryRequest,
+    history_client::HistoryClient as HistoryServiceClient,
 };
 use crate::search::{
-    FilterMode as RpcFilterMode, SearchContext as RpcSearchContext, SearchRequest, SearchResponse,
-    search_client::SearchClient as SearchServiceClient,
+    FilterMode as RpcFilterMode, FirstPageRequest, SearchContext as RpcSearchContext,
+    SearchRequest, SearchResponse, search_client::SearchClient as SearchServiceClient,
 };
 use crate::semantic::{
     CommandCapture, CommandOutputReply, CommandOutputRequest, OutputRange, RecordCommandsReply,
@@ -117,6 +118,17 @@ impl HistoryClient {
     }
 
     pub async fn start_history(&mut self, h: History) -> Result<StartHistoryReply> {
+        self.start_history_from(h, None).await
+    }
+
+    /// Start `h`, noted as run by the account and in the container `origin` says rather than
+    /// the daemon's own.
+    pub async fn start_history_from(
+        &mut self,
+        h: History,
+        origin: Option<Origin>,
+    ) -> Result<StartHistoryReply> {
+        let origin = origin.unwrap_or_default();
         let req = StartHistoryRequest {
             command: h.command,
             cwd: h.cwd,
@@ -126,6 +138,9 @@ impl HistoryClient {
             author: h.author,
             intent: h.intent.unwrap_or_default(),
             shell: h.shell.unwrap_or_default(),
+            user: origin.audit.user,
+            uid: origin.audit.uid,
+            container: origin.container.unwrap_or_default(),
         };
 
         Ok(self.client.start_history(req).await?.into_inner())
@@ -152,6 +167,15 @@ impl HistoryClient {
         Ok(self.client.status(StatusRequest {}).await?.into_inner())
     }
 
+    /// `session` may be empty to report the last command of any session.
+    pub async fn prompt_info(&mut self, session: String) -> Result<PromptInfoReply> {
+        Ok(self
+            .client
+            .prompt_info(PromptInfoRequest { session })
+            .await?
+            .into_inner())
+    }
+
     pub async fn tail_history(&mut self) -> Result<tonic::Streaming<TailHistoryReply>> {
         Ok(self
             .client
@@ -238,6 +262,28 @@ impl SearchClient {
 
         Ok(response.into_inner())
     }
+
+    /// The results for an empty query, from the daemon's warm cache where it has them.
+    #[instrument(skip_all, level = Level::TRACE, name = "daemon_client_first_page")]
+    pub async fn first_page(
+        &mut self,
+        filter_mode: FilterMode,
+        context: Context,
+    ) -> Result<Vec<History>> {
+        let request = FirstPageRequest {
+            filter_mode: RpcFilterMode::from(filter_mode).into(),
+            context: Some(RpcSearchContext::from(context)),
+        };
+
+        self.client
+            .first_page(request)
+            .await?
+            .into_inner()
+            .entries
+            .into_iter()
+            .map(History::try_from)
+            .collect()
+    }
 }
 
 impl From<FilterMode> for RpcFilterMode {
@@ -249,6 +295,7 @@ impl From<FilterMode> for RpcFilterMode {
             FilterMode::Directory => RpcFilterMode::Directory,
             FilterMode::Workspace => RpcFilterMode::Workspace,
             FilterMode::SessionPreload => RpcFilterMode::SessionPreload,
+            FilterMode::OtherHosts => RpcFilterMode::OtherHosts,
         }
     }
 }
@@ -6,7 +6,7 @@ use std::{pin::Pin, sync::Arc};
 
 use atuin_client::{
     database::Database,
-    history::{History, HistoryId, store::HistoryStore},
+    history::{Audit, History, HistoryId, Origin, store::HistoryStore},
     settings::Settings,
 };
 use dashmap::DashMap;
@@ -20,15 +20,20 @@ use crate::{
     daemon::{Component, DaemonHandle},
     events::DaemonEvent,
     history::{
-        CancelHistoryReply, CancelHistoryRequest, EndHistoryReply, EndHistoryRequest, HistoryEntry,
-        HistoryEventKind, ShutdownReply, ShutdownRequest, StartHistoryReply, StartHistoryRequest,
-        StatusReply, StatusRequest, TailHistoryReply, TailHistoryRequest,
+        CancelHistoryReply, CancelHistoryRequest, EndHistoryReply, EndHistoryRequest,
+        HistoryEventKind, PromptInfoReply, PromptInfoRequest, ShutdownReply, ShutdownRequest,
+        StartHistoryReply, StartHistoryRequest, StatusReply, StatusRequest, TailHistoryReply,
+        TailHistoryRequest,
         history_server::{History as HistorySvc, HistoryServer},
     },
 };
 
 const DAEMON_PROTOCOL_VERSION: u32 = 1;
 
+/// How many sessions' last commands are kept in memory. Past that the oldest is forgotten,
+/// and its prompt read from the database again if it's ever asked for.
+const MAX_LAST_ENDED: usize = 1024;
+
 /// History component - manages command history lifecycle.
 ///
 /// This component:
@@ -41,8 +46,13 @@ pub struct HistoryComponent {
 }
 
 struct HistoryComponentInner {
-    /// Commands currently running (not yet completed).
-    running: DashMap<HistoryId, History>,
+    /// Commands currently running (not yet completed), with where the client said each was
+    /// run, if it did.
+    running: DashMap<HistoryId, (History, Option<Origin>)>,
+
+    /// The most recently completed command of each session, so prompts can be
+    /// served without touching the database. At most `MAX_LAST_ENDED` of them.
+    last_ended: DashMap<String, History>,
 
     /// Handle to the daemon (set during start).
     handle: tokio::sync::RwLock<Option<DaemonHandle>>,
@@ -57,6 +67,7 @@ impl HistoryComponent {
         Self {
             inner: Arc::new(HistoryComponentInner {
                 running: DashMap::new(),
+                last_ended: DashMap::new(),
                 handle: tokio::sync::RwLock::new(None),
                 history_store: tokio::sync::RwLock::new(None),
             }),
@@ -119,19 +130,7 @@ pub struct HistoryGrpcService {
 fn history_to_tail_reply(kind: HistoryEventKind, history: History) -> TailHistoryReply {
     TailHistoryReply {
         kind: kind as i32,
-        history: Some(HistoryEntry {
-            timestamp: history.timestamp.unix_timestamp_nanos() as u64,
-            id: history.id.0,
-            command: history.command,
-            cwd: history.cwd,
-            session: history.session,
-            hostname: history.hostname,
-            author: history.author,
-            intent: history.intent.unwrap_or_default(),
-            exit: history.exit,
-            duration: history.duration,
-            shell: history.shell.unwrap_or_default(),
-        }),
+        history: Some(history.into()),
     }
 }
 
@@ -146,6 +145,14 @@ impl HistorySvc for HistoryGrpcService {
     ) -> Result<Response<StartHistoryReply>, Status> {
         let req = request.into_inner();
 
+        let origin = (!req.user.is_empty()).then(|| Origin {
+            audit: Audit {
+                user: req.user,
+                uid: req.uid,
+            },
+            container: (!req.container.is_empty()).then_some(req.container),
+        });
+
         let timestamp =
             OffsetDateTime::from_unix_timestamp_nanos(req.timestamp as i128).map_err(|_| {
                 Status::invalid_argument(
@@ -172,7 +179,7 @@ impl HistorySvc for HistoryGrpcService {
 
         let id = h.id.clone();
         tracing::info!(id = id.to_string(), "start history");
-        self.inner.running.insert(id.clone(), h);
+        self.inner.running.insert(id.clone(), (h, origin));
 
         let reply = StartHistoryReply {
             id: id.to_string(),
@@ -191,7 +198,7 @@ impl HistorySvc for HistoryGrpcService {
         let req = request.into_inner();
         let id = HistoryId(req.id);
 
-        if let Some((_, mut history)) = self.inner.running.remove(&id) {
+        if let Some((_, (mut history, origin))) = self.inner.running.remove(&id) {
             history.exit = req.exit;
             history.duration = match req.duration {
                 0 => i64::try_from(
@@ -212,10 +219,11 @@ impl HistorySvc for HistoryGrpcService {
                 .as_ref()
                 .ok_or_else(|| Status::internal("component not initialized"))?;
 
-            // Save to database
+            // Save to database. Clients only say which account they run as, and where, in
+            // append-only mode, so otherwise this notes the daemon's own.
             handle
                 .history_db()
-                .save(&history)
+                .save_with_origin(&history, origin.as_ref().unwrap_or_else(Origin::current))
                 .await
                 .map_err(|e| Status::internal(format!("failed to write to db: {e:?}")))?;
 
@@ -231,6 +239,8 @@ impl HistorySvc for HistoryGrpcService {
                 .await
                 .map_err(|e| Status::internal(format!("failed to push record to store: {e:?}")))?;
 
+            remember_ended(&self.inner.last_ended, history.clone(), MAX_LAST_ENDED);
+
             // Emit the event
             handle.emit(DaemonEvent::HistoryEnded(history));
 
@@ -297,20 +307,35 @@ impl HistorySvc for HistoryGrpcService {
                     Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
                 };
 
-                let reply = match event {
+                let replies = match event {
                     DaemonEvent::HistoryStarted(history) => {
-                        Some(history_to_tail_reply(HistoryEventKind::Started, history))
+                        vec![history_to_tail_reply(HistoryEventKind::Started, history)]
                     }
                     DaemonEvent::HistoryEnded(history) => {
-                        Some(history_to_tail_reply(HistoryEventKind::Ended, history))
+                        vec![history_to_tail_reply(HistoryEventKind::Ended, history)]
+                    }
+                    DaemonEvent::HistorySynced(ids) => {
+                        let mut histories = match handle.history_db().load_active(&ids).await {
+                            Ok(histories) => histories,
+                            Err(e) => {
+                                tracing::warn!("could not load synced history to tail: {e}");
+                                continue;
+                            }
+                        };
+                        histories.sort_by_key(|history| history.timestamp);
+
+                        histories
+                            .into_iter()
+                            .map(|history| history_to_tail_reply(HistoryEventKind::Synced, history))
+                            .collect()
                     }
-                    _ => None,
+                    _ => Vec::new(),
                 };
 
-                if let Some(reply) = reply
-                    && tx.send(Ok(reply)).await.is_err()
-                {
-                    break;
+                for reply in replies {
+                    if tx.send(Ok(reply)).await.is_err() {
+                        return;
+                    }
                 }
             }
         });
@@ -334,6 +359,60 @@ impl HistorySvc for HistoryGrpcService {
         Ok(Response::new(reply))
     }
 
+    #[instrument(skip_all, level = Level::DEBUG)]
+    async fn prompt_info(
+        &self,
+        request: Request<PromptInfoRequest>,
+    ) -> Result<Response<PromptInfoReply>, Status> {
+        let session = request.into_inner().session;
+
+        let cached = if session.is_empty() {
+            self.inner
+                .last_ended
+                .iter()
+                .max_by_key(|entry| entry.timestamp)
+                .map(|entry| entry.value().clone())
+        } else {
+            self.inner
+                .last_ended
+                .get(&session)
+                .map(|entry| entry.value().clone())
+        };
+
+        // Nothing has ended in this session since the daemon started.
+        let last = match cached {
+            Some(last) => Some(last),
+            None => {
+                let handle_guard = self.inner.handle.read().await;
+                let handle = handle_guard
+                    .as_ref()
+                    .ok_or_else(|| Status::internal("component not initialized"))?;
+
+                let session = (!session.is_empty()).then_some(session.as_str());
+                handle
+                    .history_db()
+                    .latest(session, 1)
+                    .await
+                    .map_err(|e| Status::internal(format!("failed to read history: {e:?}")))?
+                    .pop()
+            }
+        };
+
+        // Never having synced isn't an error worth failing the prompt over.
+        let last_sync = Settings::last_sync()
+            .await
+            .map_or(0, |t| t.unix_timestamp());
+
+        Ok(Response::new(PromptInfoReply {
+            has_last: last.is_some(),
+            last_exit: last.as_ref().map_or(0, |h| h.exit),
+            last_duration: last.as_ref().map_or(0, |h| h.duration),
+            last_sync,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            protocol: DAEMON_PROTOCOL_VERSION,
+        }))
+    }
+
     #[instrument(skip_all, level = Level::INFO)]
     async fn shutdown(
         &self,
@@ -346,3 +425,48 @@ impl HistorySvc for HistoryGrpcService {
         Ok(Response::new(ShutdownReply { accepted: true }))
     }
 }
+
+/// Keep `history` as the last command of its session, forgetting the session that ended a
+/// command longest ago once there are more than `max`.
+fn remember_ended(last_ended: &DashMap<String, History>, history: History, max: usize) {
+    last_ended.insert(history.session.clone(), history);
+
+    if last_ended.len() > max
+        && let Some(oldest) = last_ended
+            .iter()
+            .min_by_key(|entry| entry.timestamp)
+            .map(|entry| entry.key().clone())
+    {
+        last_ended.remove(&oldest);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ended(session: &str, minutes_ago: i64) -> History {
+        History::import()
+            .timestamp(OffsetDateTime::now_utc() - time::Duration::minutes(minutes_ago))
+            .command("ls")
+            .session(session)
+            .build()
+            .into()
+    }
+
+    #[test]
+    fn only_the_latest_sessions_are_remembered() {
+        let last_ended = DashMap::new();
+
+        remember_ended(&last_ended, ended("a", 3), 2);
+        remember_ended(&last_ended, ended("b", 2), 2);
+        remember_ended(&last_ended, ended("a", 1), 2);
+        assert_eq!(last_ended.len(), 2);
+
+        remember_ended(&last_ended, ended("c", 0), 2);
+        assert_eq!(last_ended.len(), 2);
+        assert!(last_ended.contains_key("a"));
+        assert!(!last_ended.contains_key("b"));
+        assert!(last_ended.contains_key("c"));
+    }
+}
@@ -5,7 +5,9 @@
 
 use std::{pin::Pin, sync::Arc};
 
-use atuin_client::database::Database;
+use atuin_client::database::{Context, Database};
+use atuin_client::history::host_of;
+use atuin_client::settings::FilterMode as ClientFilterMode;
 use atuin_common::path::DisplayRichExt;
 use eyre::Result;
 use tokio::sync::RwLock;
@@ -18,7 +20,8 @@ use crate::{
     daemon::{Component, DaemonHandle},
     events::DaemonEvent,
     search::{
-        FilterMode, IndexFilterMode, QueryContext, SearchIndex, SearchRequest, SearchResponse,
+        FilterMode, FirstPageRequest, FirstPageResponse, FirstPages, IndexFilterMode, QueryContext,
+        SearchIndex, SearchRequest, SearchResponse,
         search_server::{Search as SearchSvc, SearchServer},
     },
 };
@@ -34,10 +37,12 @@ const FRECENCY_REFRESH_INTERVAL_SECS: u64 = 60;
 /// - Maintains a deduplicated search index with frecency ranking
 /// - Loads history from the database on startup
 /// - Updates the index when history events occur
+/// - Keeps the TUI's first page of results warm for recently used contexts
 /// - Provides the Search gRPC service
 pub struct SearchComponent {
     index: Arc<RwLock<SearchIndex>>,
-    handle: tokio::sync::RwLock<Option<DaemonHandle>>,
+    first_pages: Arc<FirstPages>,
+    handle: Arc<RwLock<Option<DaemonHandle>>>,
     loader_handle: Option<tokio::task::JoinHandle<()>>,
     frecency_handle: Option<tokio::task::JoinHandle<()>>,
 }
@@ -47,7 +52,8 @@ impl SearchComponent {
     pub fn new() -> Self {
         Self {
             index: Arc::new(RwLock::new(SearchIndex::new())),
-            handle: tokio::sync::RwLock::new(None),
+            first_pages: Arc::new(FirstPages::new()),
+            handle: Arc::new(RwLock::new(None)),
             loader_handle: None,
             frecency_handle: None,
         }
@@ -57,9 +63,42 @@ impl SearchComponent {
     pub fn grpc_service(&self) -> SearchServer<SearchGrpcService> {
         SearchServer::new(SearchGrpcService {
             index: self.index.clone(),
+            first_pages: self.first_pages.clone(),
+            handle: self.handle.clone(),
         })
     }
 
+    /// The search index, for serving searches outside gRPC.
+    pub fn index(&self) -> Arc<RwLock<SearchIndex>> {
+        self.index.clone()
+    }
+
+    /// Compute a first page in the background, so it's ready by the time the user next opens
+    /// search there. Without a `mode`, the one the user's settings start search in.
+    fn warm_first_page(
+        &self,
+        handle: &DaemonHandle,
+        mode: Option<ClientFilterMode>,
+        context: Context,
+    ) {
+        let first_pages = self.first_pages.clone();
+        let handle = handle.clone();
+
+        tokio::spawn(async move {
+            let mode = match mode {
+                Some(mode) => mode,
+                None => handle
+                    .settings()
+                    .await
+                    .default_filter_mode(context.git_root.is_some()),
+            };
+
+            if let Err(e) = first_pages.load(handle.history_db(), mode, &context).await {
+                debug!("failed to warm the first page of search results: {e}");
+            }
+        });
+    }
+
     /// Rebuild the entire search index from the database.
     async fn rebuild_index(&self) -> Result<()> {
         let handle_guard = self.handle.read().await;
@@ -176,11 +215,25 @@ impl Component for SearchComponent {
             }
         }));
 
+        // Warm the page most users open search on, so even the first Ctrl-R is quick
+        let anywhere = Context {
+            session: String::new(),
+            cwd: String::new(),
+            hostname: String::new(),
+            host_id: String::new(),
+            git_root: None,
+        };
+        self.warm_first_page(&handle, Some(ClientFilterMode::Global), anywhere);
+
         tracing::info!("search component started");
         Ok(())
     }
 
     async fn handle_event(&mut self, event: &DaemonEvent) -> Result<()> {
+        if event.changes_history() {
+            self.first_pages.clear();
+        }
+
         match event {
             DaemonEvent::HistorySynced(ids) => {
                 debug!(count = ids.len(), "Indexing synced history entries");
@@ -202,6 +255,10 @@ impl Component for SearchComponent {
                         self.index.read().await.add_history(history);
                     })
                     .await;
+
+                if let Some(handle) = self.handle.read().await.as_ref() {
+                    self.warm_first_page(handle, None, Context::from_history(history));
+                }
             }
             DaemonEvent::HistoryPruned | DaemonEvent::HistoryRebuilt => {
                 info!("History store pruned or rebuilt, rebuilding search index");
@@ -256,6 +313,8 @@ impl Component for SearchComponent {
 /// The gRPC service implementation.
 pub struct SearchGrpcService {
     index: Arc<RwLock<SearchIndex>>,
+    first_pages: Arc<FirstPages>,
+    handle: Arc<RwLock<Option<DaemonHandle>>>,
 }
 
 #[tonic::async_trait]
@@ -346,6 +405,43 @@ impl SearchSvc for SearchGrpcService {
         let out_stream = tokio_stream::wrappers::ReceiverStream::new(rx);
         Ok(Response::new(Box::pin(out_stream)))
     }
+
+    #[instrument(skip_all, level = Level::TRACE, name = "first_page_rpc")]
+    async fn first_page(
+        &self,
+        request: Request<FirstPageRequest>,
+    ) -> Result<Response<FirstPageResponse>, Status> {
+        let req = request.into_inner();
+        let filter_mode = FilterMode::try_from(req.filter_mode).unwrap_or(FilterMode::Global);
+        let context = req
+            .context
+            .ok_or_else(|| Status::invalid_argument("a search context is required"))?;
+
+        let handle = self
+            .handle
+            .read()
+            .await
+            .clone()
+            .ok_or_else(|| Status::unavailable("search component not started"))?;
+
+        let context = Context {
+            session: context.session_id,
+            cwd: context.cwd,
+            hostname: context.hostname,
+            host_id: context.host_id,
+            git_root: context.git_root.map(Into::into),
+        };
+
+        let page = self
+            .first_pages
+            .load(handle.history_db(), filter_mode.into(), &context)
+            .await
+            .map_err(|e| Status::internal(format!("failed to load first page: {e}")))?;
+
+        Ok(Response::new(FirstPageResponse {
+            entries: page.iter().cloned().map(Into::into).collect(),
+        }))
+    }
 }
 
 /// Convert proto FilterMode and context to IndexFilterMode.
@@ -367,6 +463,9 @@ fn convert_filter_mode(
             }
         }
         (FilterMode::Host, Some(ctx)) => IndexFilterMode::Host(ctx.hostname.clone()),
+        (FilterMode::OtherHosts, Some(ctx)) => {
+            IndexFilterMode::OtherHosts(host_of(&ctx.hostname).to_string())
+        }
         (FilterMode::Session, Some(ctx)) => IndexFilterMode::Session(ctx.session_id.clone()),
         (FilterMode::SessionPreload, Some(ctx)) => {
             // SessionPreload is similar to Session - filter by session
@@ -12,7 +12,9 @@ use tokio::time::{self, MissedTickBehavior};
 
 use atuin_client::{
     history::{HistoryId, store::HistoryStore},
+    host::HostStore,
     record::sync,
+    session::SessionStore,
     settings::Settings,
 };
 use atuin_dotfiles::store::{AliasStore, var::VarStore};
@@ -131,6 +133,14 @@ async fn sync_loop(handle: DaemonHandle, mut cmd_rx: mpsc::Receiver<SyncCommand>
     let alias_store = AliasStore::new(handle.store().clone(), host_id, encryption_key);
     let var_store = VarStore::new(handle.store().clone(), host_id, encryption_key);
 
+    // Describe this machine to the others, ready for the first sync
+    if let Err(e) = HostStore::new(handle.store().clone(), host_id, encryption_key)
+        .register()
+        .await
+    {
+        tracing::warn!("failed to register host: {e}");
+    }
+
     // Don't backoff by more than 30 mins (with a random jitter of up to 1 min)
     let max_interval: f64 = 60.0 * 30.0 + rand::thread_rng().gen_range(0.0..60.0);
 
@@ -192,6 +202,7 @@ async fn sync_loop(handle: DaemonHandle, mut cmd_rx: mpsc::Receiver<SyncCommand>
 /// Execute a single sync tick.
 ///
 /// Returns the new sync state: `Idle` on success, `Retrying` on failure.
+#[tracing::instrument(skip_all)]
 async fn do_sync_tick(
     handle: &DaemonHandle,
     history_store: &HistoryStore,
@@ -217,9 +228,27 @@ async fn do_sync_tick(
         return SyncState::Idle;
     }
 
+    if let Some(offline) = atuin_client::network::check(settings).await {
+        tracing::debug!("{}, skipping sync tick", offline.describe());
+        return SyncState::Idle;
+    }
+
     // Perform the sync
+    #[cfg(feature = "otel")]
+    let started = std::time::Instant::now();
     let res = sync::sync(settings, handle.store(), handle.encryption_key()).await;
 
+    #[cfg(feature = "otel")]
+    atuin_common::otel::record_sync(
+        started.elapsed(),
+        res.as_ref().ok().map(|(uploaded, downloaded)| {
+            (
+                u64::try_from(*uploaded).unwrap_or_default(),
+                downloaded.len() as u64,
+            )
+        }),
+    );
+
     match res {
         Err(e) => {
             tracing::error!("sync tick failed with {e}");
@@ -298,6 +327,16 @@ async fn do_sync_tick(
             if let Err(e) = var_store.build().await {
                 tracing::error!("failed to rebuild var store: {e}");
             }
+            if let Err(e) = SessionStore::new(
+                handle.store().clone(),
+                history_store.host_id,
+                *handle.encryption_key(),
+            )
+            .build(handle.history_db())
+            .await
+            {
+                tracing::error!("failed to rebuild session names: {e}");
+            }
 
             // Reset backoff on success
             if ticker.period().as_secs() != settings.daemon.sync_frequency {
@@ -74,3 +74,18 @@ pub enum DaemonEvent {
     /// Request graceful shutdown of the daemon.
     ShutdownRequested,
 }
+
+impl DaemonEvent {
+    /// Whether the event means the saved history changed, so anything derived from it is
+    /// out of date.
+    pub fn changes_history(&self) -> bool {
+        matches!(
+            self,
+            DaemonEvent::HistoryEnded(_)
+                | DaemonEvent::HistorySynced(_)
+                | DaemonEvent::HistoryPruned
+                | DaemonEvent::HistoryRebuilt
+                | DaemonEvent::HistoryDeleted { .. }
+        )
+    }
+}
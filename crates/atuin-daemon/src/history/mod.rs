@@ -2,6 +2,11 @@
 //!
 //! This module contains the proto-generated types for the history gRPC service.
 
+use atuin_client::history::History;
+use atuin_common::utils::normalize_optional_string;
+use eyre::WrapErr;
+use time::OffsetDateTime;
+
 // Include the generated proto code
 tonic::include_proto!("history");
 
@@ -27,4 +32,51 @@ macro_rules! impl_versioned_reply {
     };
 }
 
-impl_versioned_reply!(StartHistoryReply, EndHistoryReply, CancelHistoryReply);
+impl_versioned_reply!(
+    StartHistoryReply,
+    EndHistoryReply,
+    CancelHistoryReply,
+    PromptInfoReply
+);
+
+impl From<History> for HistoryEntry {
+    fn from(history: History) -> Self {
+        HistoryEntry {
+            timestamp: history.timestamp.unix_timestamp_nanos() as u64,
+            id: history.id.0,
+            command: history.command,
+            cwd: history.cwd,
+            session: history.session,
+            hostname: history.hostname,
+            author: history.author,
+            intent: history.intent.unwrap_or_default(),
+            exit: history.exit,
+            duration: history.duration,
+            shell: history.shell.unwrap_or_default(),
+        }
+    }
+}
+
+impl TryFrom<HistoryEntry> for History {
+    type Error = eyre::Report;
+
+    fn try_from(entry: HistoryEntry) -> eyre::Result<Self> {
+        let timestamp = OffsetDateTime::from_unix_timestamp_nanos(i128::from(entry.timestamp))
+            .wrap_err("invalid daemon history timestamp")?;
+
+        Ok(History {
+            id: entry.id.into(),
+            timestamp,
+            duration: entry.duration,
+            exit: entry.exit,
+            command: entry.command,
+            cwd: entry.cwd,
+            session: entry.session,
+            hostname: entry.hostname,
+            author: entry.author,
+            intent: normalize_optional_string(entry.intent),
+            shell: normalize_optional_string(entry.shell),
+            deleted_at: None,
+        })
+    }
+}
@@ -3,6 +3,7 @@ use atuin_client::record::sqlite_store::SqliteStore;
 use atuin_client::settings::{Settings, watcher::global_settings_watcher};
 use eyre::Result;
 
+pub mod api;
 pub mod client;
 pub mod components;
 pub mod control;
@@ -43,6 +44,7 @@ pub async fn boot(
     let history_service = history_component.grpc_service();
     let search_service = search_component.grpc_service();
     let semantic_service = semantic_component.grpc_service();
+    let search_index = search_component.index();
 
     // Build the daemon
     let mut daemon = Daemon::builder(settings.clone())
@@ -92,6 +94,8 @@ pub async fn boot(
         signal_handle.shutdown();
     });
 
+    let api_enabled = settings.daemon.api_enabled;
+
     // Start the gRPC server in the background
     server::run_grpc_server(
         settings,
@@ -99,10 +103,15 @@ pub async fn boot(
         search_service,
         semantic_service,
         control_service.into_server(),
-        handle,
+        handle.clone(),
     )
     .await?;
 
+    // The HTTP API is an optional extra, so the daemon carries on without it
+    if api_enabled && let Err(e) = api::run_api_server(handle, search_index).await {
+        tracing::error!("failed to start the http api: {e:?}");
+    }
+
     // Run the daemon event loop
     daemon.run_event_loop().await?;
 
@@ -0,0 +1,288 @@
+//! Warm first pages for the search TUI.
+//!
+//! Opening the TUI runs an empty query, which on a large history means a grouped scan of the
+//! whole table from a cold connection. The daemon already has the database open, so it keeps
+//! the result of that query for the contexts it has seen recently and hands it over in one
+//! round trip.
+//!
+//! Pages are only ever served for exactly the history they were computed from: any change to
+//! history clears the cache, and a page computed while a change landed is thrown away rather
+//! than stored.
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use atuin_client::{
+    database::{Context, Database, OptFilters},
+    history::{AUTHOR_FILTER_ALL_USER, History, host_of},
+    settings::{FilterMode, SearchMode},
+};
+use eyre::Result;
+
+/// How many results a first page holds, matching what the TUI asks the database for.
+pub const FIRST_PAGE_LIMIT: i64 = 200;
+
+/// How many pages to keep. One per recently used directory and filter mode is plenty.
+const CAPACITY: usize = 64;
+
+/// What a first page depends on: the filter mode, and the one part of the context that mode
+/// filters by. Two shells in the same directory share a directory page, but not a session one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PageKey {
+    mode: FilterMode,
+    scope: String,
+}
+
+impl PageKey {
+    pub fn new(mode: FilterMode, context: &Context) -> Self {
+        let scope = match mode {
+            FilterMode::Global => String::new(),
+            FilterMode::Host => context.hostname.to_lowercase(),
+            FilterMode::Session | FilterMode::SessionPreload => context.session.clone(),
+            FilterMode::Directory => context.cwd.clone(),
+            FilterMode::Workspace => context.git_root.as_ref().map_or_else(
+                || context.cwd.clone(),
+                |root| root.to_string_lossy().into_owned(),
+            ),
+            FilterMode::OtherHosts => host_of(&context.hostname).to_lowercase(),
+        };
+
+        Self { mode, scope }
+    }
+}
+
+#[derive(Default)]
+struct Pages {
+    /// Bumped whenever history changes, so that pages computed before the change can be told
+    /// apart from ones computed after it.
+    generation: u64,
+    /// Most recently used first.
+    pages: VecDeque<(PageKey, Arc<Vec<History>>)>,
+}
+
+/// A small most-recently-used cache of first pages.
+#[derive(Default)]
+pub struct FirstPages {
+    inner: Mutex<Pages>,
+}
+
+impl FirstPages {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The cached page for `key`, if there is one.
+    pub fn get(&self, key: &PageKey) -> Option<Arc<Vec<History>>> {
+        let mut inner = self.inner.lock().unwrap();
+        let pos = inner.pages.iter().position(|(k, _)| k == key)?;
+        let entry = inner.pages.remove(pos)?;
+        let page = entry.1.clone();
+        inner.pages.push_front(entry);
+        Some(page)
+    }
+
+    /// Forget every page. Called whenever history changes.
+    pub fn clear(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.generation += 1;
+        inner.pages.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().pages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn generation(&self) -> u64 {
+        self.inner.lock().unwrap().generation
+    }
+
+    /// Store a page computed at `generation`, unless history has changed since.
+    fn insert(&self, key: PageKey, page: Arc<Vec<History>>, generation: u64) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.generation != generation {
+            return;
+        }
+
+        inner.pages.retain(|(k, _)| *k != key);
+        inner.pages.push_front((key, page));
+        inner.pages.truncate(CAPACITY);
+    }
+
+    /// The first page for `mode` in `context`, from the cache if it's there and from `db`
+    /// otherwise.
+    pub async fn load(
+        &self,
+        db: &impl Database,
+        mode: FilterMode,
+        context: &Context,
+    ) -> Result<Arc<Vec<History>>> {
+        let key = PageKey::new(mode, context);
+        if let Some(page) = self.get(&key) {
+            return Ok(page);
+        }
+
+        let generation = self.generation();
+        let page = Arc::new(first_page(db, mode, context).await?);
+        self.insert(key, page.clone(), generation);
+
+        Ok(page)
+    }
+}
+
+/// Run the query the TUI opens with: the most recent unique commands, no search term.
+async fn first_page(
+    db: &impl Database,
+    mode: FilterMode,
+    context: &Context,
+) -> Result<Vec<History>> {
+    db.search(
+        SearchMode::FullText,
+        mode,
+        context,
+        "",
+        OptFilters {
+            limit: Some(FIRST_PAGE_LIMIT),
+            authors: vec![AUTHOR_FILTER_ALL_USER.to_string()],
+            ..Default::default()
+        },
+    )
+    .await
+    .map_err(Into::into)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use atuin_client::database::Sqlite;
+
+    use super::*;
+
+    fn context(cwd: &str, session: &str) -> Context {
+        Context {
+            session: session.to_string(),
+            cwd: cwd.to_string(),
+            hostname: "Desktop:ellie".to_string(),
+            host_id: "host".to_string(),
+            git_root: None,
+        }
+    }
+
+    fn history(command: &str, cwd: &str) -> History {
+        History::import()
+            .timestamp(time::OffsetDateTime::now_utc())
+            .command(command)
+            .cwd(cwd)
+            .build()
+            .into()
+    }
+
+    #[test]
+    fn keys_only_depend_on_what_the_mode_filters_by() {
+        let here = context("/home/ellie/code", "one");
+        let other_session = context("/home/ellie/code", "two");
+        let elsewhere = context("/tmp", "one");
+
+        let key = PageKey::new;
+
+        assert_eq!(
+            key(FilterMode::Global, &here),
+            key(FilterMode::Global, &elsewhere)
+        );
+        assert_eq!(
+            key(FilterMode::Directory, &here),
+            key(FilterMode::Directory, &other_session)
+        );
+        assert_ne!(
+            key(FilterMode::Directory, &here),
+            key(FilterMode::Directory, &elsewhere)
+        );
+        assert_ne!(
+            key(FilterMode::Session, &here),
+            key(FilterMode::Session, &other_session)
+        );
+        assert_ne!(
+            key(FilterMode::Global, &here),
+            key(FilterMode::Directory, &here)
+        );
+        assert_eq!(
+            key(FilterMode::Host, &here),
+            key(
+                FilterMode::Host,
+                &Context {
+                    hostname: "desktop:ellie".to_string(),
+                    ..here.clone()
+                }
+            )
+        );
+
+        let in_repo = Context {
+            git_root: Some(PathBuf::from("/home/ellie/code")),
+            ..context("/home/ellie/code/src", "one")
+        };
+        assert_eq!(
+            key(FilterMode::Workspace, &in_repo),
+            key(FilterMode::Workspace, &here)
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn pages_are_cached_until_history_changes() {
+        let db = Sqlite::new("sqlite::memory:", 2.0).await.unwrap();
+        db.save(&history("ls", "/tmp")).await.unwrap();
+
+        let pages = FirstPages::new();
+        let ctx = context("/tmp", "one");
+
+        let page = pages.load(&db, FilterMode::Global, &ctx).await.unwrap();
+        assert_eq!(page.len(), 1);
+
+        // A new command doesn't show up until the cache is told about it
+        db.save(&history("pwd", "/tmp")).await.unwrap();
+        let page = pages.load(&db, FilterMode::Global, &ctx).await.unwrap();
+        assert_eq!(page.len(), 1);
+
+        pages.clear();
+        assert!(pages.is_empty());
+        let page = pages.load(&db, FilterMode::Global, &ctx).await.unwrap();
+        assert_eq!(page.len(), 2);
+        assert_eq!(page[0].command, "pwd");
+    }
+
+    #[test]
+    fn stale_pages_are_not_stored() {
+        let pages = FirstPages::new();
+        let key = PageKey::new(FilterMode::Global, &context("/tmp", "one"));
+
+        let generation = pages.generation();
+        pages.clear();
+        pages.insert(key.clone(), Arc::new(Vec::new()), generation);
+        assert!(pages.get(&key).is_none());
+
+        pages.insert(key.clone(), Arc::new(Vec::new()), pages.generation());
+        assert!(pages.get(&key).is_some());
+    }
+
+    #[test]
+    fn least_recently_used_pages_are_evicted() {
+        let pages = FirstPages::new();
+        let key = |i: usize| PageKey::new(FilterMode::Directory, &context(&format!("/{i}"), "s"));
+
+        for i in 0..CAPACITY {
+            pages.insert(key(i), Arc::new(Vec::new()), 0);
+        }
+        // Touch the oldest so it survives the next insert
+        assert!(pages.get(&key(0)).is_some());
+        pages.insert(key(CAPACITY), Arc::new(Vec::new()), 0);
+
+        assert_eq!(pages.len(), CAPACITY);
+        assert!(pages.get(&key(0)).is_some());
+        assert!(pages.get(&key(1)).is_none());
+    }
+}
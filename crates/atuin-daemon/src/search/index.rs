@@ -12,7 +12,7 @@ use std::{
     sync::Arc,
 };
 
-use atuin_client::history::{History, is_known_agent};
+use atuin_client::history::{History, host_of, is_known_agent};
 use atuin_client::settings::Search;
 use atuin_common::path::DisplayRichExt;
 use atuin_nucleo::{Injector, Nucleo, pattern};
@@ -209,6 +209,14 @@ impl CommandData {
             .is_some_and(|spur| self.hosts.contains(&spur))
     }
 
+    /// Check if any invocation ran on a machine other than `host`.
+    /// O(n) where n = number of unique hostnames for this command.
+    pub fn has_invocation_off_host(&self, host: &str, interner: &ThreadedRodeo) -> bool {
+        self.hosts
+            .iter()
+            .any(|&spur| !host_of(interner.resolve(&spur)).eq_ignore_ascii_case(host))
+    }
+
     /// Check if any invocation matches a session.
     /// O(1) lookup using pre-computed index.
     pub fn has_invocation_in_session(&self, session: &str) -> bool {
@@ -227,6 +235,8 @@ pub enum IndexFilterMode {
     Workspace(String),
     /// Filter to commands run on a specific host.
     Host(String),
+    /// Filter to commands run on any machine but this one (by its host part).
+    OtherHosts(String),
     /// Filter to commands run in a specific session.
     Session(String),
 }
@@ -446,6 +456,9 @@ impl SearchIndex {
                     IndexFilterMode::Host(hostname) => {
                         entry.has_invocation_on_host(hostname, &self.interner)
                     }
+                    IndexFilterMode::OtherHosts(host) => {
+                        entry.has_invocation_off_host(host, &self.interner)
+                    }
                     IndexFilterMode::Session(session) => entry.has_invocation_in_session(session),
                 };
                 if passes {
@@ -664,6 +677,23 @@ mod tests {
         assert!(!data.has_invocation_in_workspace(&check3, &interner));
     }
 
+    #[test]
+    fn command_data_host_filters() {
+        let interner = ThreadedRodeo::new();
+
+        let mut h1 = make_history("uptime", "/", datetime!(2024-01-01 10:00 UTC));
+        h1.hostname = "laptop:ellie".to_string();
+        let mut h2 = make_history("uptime", "/", datetime!(2024-01-01 12:00 UTC));
+        h2.hostname = "LAPTOP:root".to_string();
+
+        let mut data = CommandData::new(&h1, &interner).unwrap();
+        data.add_invocation(&h2, &interner);
+
+        assert!(data.has_invocation_on_host("laptop:ellie", &interner));
+        assert!(!data.has_invocation_off_host("laptop", &interner));
+        assert!(data.has_invocation_off_host("desktop", &interner));
+    }
+
     #[tokio::test]
     async fn search_index_add_and_search() {
         let index = SearchIndex::new();
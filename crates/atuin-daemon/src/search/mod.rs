@@ -2,10 +2,28 @@
 //!
 //! This module provides fuzzy search over command history using Nucleo.
 
+mod first_page;
 mod index;
 
 // Include the generated proto code
 tonic::include_proto!("search");
 
 // Re-export the service and index
+pub use first_page::{FIRST_PAGE_LIMIT, FirstPages, PageKey};
 pub use index::{IndexFilterMode, QueryContext, SearchIndex};
+
+impl From<FilterMode> for atuin_client::settings::FilterMode {
+    fn from(mode: FilterMode) -> Self {
+        use atuin_client::settings::FilterMode as Client;
+
+        match mode {
+            FilterMode::Global => Client::Global,
+            FilterMode::Host => Client::Host,
+            FilterMode::Session => Client::Session,
+            FilterMode::Directory => Client::Directory,
+            FilterMode::Workspace => Client::Workspace,
+            FilterMode::SessionPreload => Client::SessionPreload,
+            FilterMode::OtherHosts => Client::OtherHosts,
+        }
+    }
+}
@@ -87,14 +87,14 @@ pub async fn run_grpc_server(
             }
         }
         if cleanup {
-            eprintln!("Removing socket...");
+            tracing::info!("removing socket {socket_path:?}");
             if let Err(e) = std::fs::remove_file(&socket_path)
                 && e.kind() != std::io::ErrorKind::NotFound
             {
-                eprintln!("failed to remove socket: {e}");
+                tracing::warn!("failed to remove socket: {e}");
             }
         }
-        eprintln!("Shutting down gRPC server...");
+        tracing::info!("shutting down gRPC server");
     };
 
     // Spawn the server in the background
@@ -149,7 +149,7 @@ pub async fn run_grpc_server(
                 Err(_) => break, // Channel closed
             }
         }
-        eprintln!("Shutting down gRPC server...");
+        tracing::info!("shutting down gRPC server");
     };
 
     // Spawn the server in the background
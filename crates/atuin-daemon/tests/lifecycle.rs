@@ -10,8 +10,8 @@ mod unix {
     use atuin_client::database::Sqlite;
     use atuin_client::record::sqlite_store::SqliteStore;
     use atuin_client::settings::{Settings, init_meta_config_for_testing};
-    use atuin_daemon::client::HistoryClient;
-    use atuin_daemon::components::HistoryComponent;
+    use atuin_daemon::client::{HistoryClient, SearchClient};
+    use atuin_daemon::components::{HistoryComponent, SearchComponent};
     use atuin_daemon::{Daemon, DaemonHandle};
     use tempfile::TempDir;
     use tokio::net::UnixListener;
@@ -57,12 +57,15 @@ mod unix {
         // Create the history component and get its gRPC service
         let history_component = HistoryComponent::new();
         let history_service = history_component.grpc_service();
+        let search_component = SearchComponent::new();
+        let search_service = search_component.grpc_service();
 
         // Build and start the daemon
         let mut daemon = Daemon::builder(settings)
             .store(store)
             .history_db(history_db)
             .component(history_component)
+            .component(search_component)
             .build()
             .await
             .unwrap();
@@ -81,6 +84,7 @@ mod unix {
             let mut rx = server_handle.subscribe();
             Server::builder()
                 .add_service(history_service)
+                .add_service(search_service)
                 .serve_with_incoming_shutdown(stream, async move {
                     loop {
                         match rx.recv().await {
@@ -145,6 +149,87 @@ mod unix {
         assert!(!end_reply.id.is_empty());
     }
 
+    #[tokio::test]
+    async fn test_client_origin_is_saved_with_history() {
+        use atuin_client::database::Database;
+        use atuin_client::history::{Audit, History, HistoryId, Origin};
+
+        let (mut client, _handle, tmp) = start_test_daemon().await;
+
+        let history = History::daemon()
+            .timestamp(time::OffsetDateTime::now_utc())
+            .command("whoami".to_string())
+            .cwd("/tmp".to_string())
+            .session("test-session".to_string())
+            .hostname("test-host".to_string())
+            .build()
+            .into();
+        let origin = Origin {
+            audit: Audit {
+                user: "ellie".to_string(),
+                uid: Some(1000),
+            },
+            container: Some("docker:3f4a5b6c7d8e".to_string()),
+        };
+
+        let start_reply = client
+            .start_history_from(history, Some(origin.clone()))
+            .await
+            .unwrap();
+        client
+            .end_history(start_reply.id.clone(), 1_000_000, 0)
+            .await
+            .unwrap();
+
+        let db = Sqlite::new(tmp.path().join("history.db"), 5.0)
+            .await
+            .unwrap();
+        let audit = db.audit(&HistoryId(start_reply.id)).await.unwrap();
+        assert_eq!(audit, Some(origin.audit));
+    }
+
+    #[tokio::test]
+    async fn test_prompt_info_reports_last_command_of_session() {
+        use atuin_client::history::History;
+
+        let (mut client, _handle, _tmp) = start_test_daemon().await;
+
+        let info = client
+            .prompt_info("prompt-session".to_string())
+            .await
+            .unwrap();
+        assert!(!info.has_last);
+        assert_eq!(info.last_sync, 0);
+
+        for (session, exit, duration) in [("prompt-session", 2, 5_000_000), ("other", 0, 1)] {
+            let history = History::daemon()
+                .timestamp(time::OffsetDateTime::now_utc())
+                .command("false".to_string())
+                .cwd("/tmp".to_string())
+                .session(session.to_string())
+                .hostname("test-host".to_string())
+                .build()
+                .into();
+
+            let start_reply = client.start_history(history).await.unwrap();
+            client
+                .end_history(start_reply.id, duration, exit)
+                .await
+                .unwrap();
+        }
+
+        let info = client
+            .prompt_info("prompt-session".to_string())
+            .await
+            .unwrap();
+        assert!(info.has_last);
+        assert_eq!(info.last_exit, 2);
+        assert_eq!(info.last_duration, 5_000_000);
+
+        let info = client.prompt_info(String::new()).await.unwrap();
+        assert_eq!(info.last_exit, 0);
+    }
+
     #[tokio::test]
     async fn test_tail_history_streams_started_and_ended_events() {
         use atuin_client::history::History;
@@ -196,6 +281,70 @@ mod unix {
         assert_eq!(ended_history.duration, 1_000_000);
     }
 
+    #[tokio::test]
+    async fn test_first_page_follows_new_history() {
+        use atuin_client::database::Context;
+        use atuin_client::history::History;
+        use atuin_client::settings::FilterMode;
+
+        let (mut client, _handle, tmp) = start_test_daemon().await;
+        let mut search =
+            SearchClient::new(tmp.path().join("test.sock").to_string_lossy().to_string())
+                .await
+                .unwrap();
+
+        let context = Context {
+            session: "first-page-session".to_string(),
+            cwd: "/tmp".to_string(),
+            hostname: "test-host".to_string(),
+            host_id: String::new(),
+            git_root: None,
+        };
+
+        let page = search
+            .first_page(FilterMode::Directory, context.clone())
+            .await
+            .unwrap();
+        assert!(page.is_empty());
+
+        let history = History::daemon()
+            .timestamp(time::OffsetDateTime::now_utc())
+            .command("echo warm".to_string())
+            .cwd("/tmp".to_string())
+            .session("first-page-session".to_string())
+            .hostname("test-host".to_string())
+            .build()
+            .into();
+        let start_reply = client.start_history(history).await.unwrap();
+        client.end_history(start_reply.id, 1, 0).await.unwrap();
+
+        // The cached empty page is dropped once the daemon handles the new command
+        let mut page = Vec::new();
+        for _ in 0..50 {
+            page = search
+                .first_page(FilterMode::Directory, context.clone())
+                .await
+                .unwrap();
+            if !page.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].command, "echo warm");
+        assert_eq!(page[0].session, "first-page-session");
+
+        let elsewhere = Context {
+            cwd: "/elsewhere".to_string(),
+            ..context
+        };
+        let page = search
+            .first_page(FilterMode::Directory, elsewhere)
+            .await
+            .unwrap();
+        assert!(page.is_empty());
+    }
+
     #[tokio::test]
     async fn test_end_unknown_history_fails() {
         let (mut client, _handle, _tmp) = start_test_daemon().await;
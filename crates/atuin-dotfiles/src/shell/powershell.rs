@@ -39,7 +39,7 @@ async fn cached_vars(path: PathBuf, store: &VarStore) -> String {
 /// While currently this only returns aliases, it will be extended to also return other synced dotfiles
 pub async fn alias_config(store: &AliasStore) -> String {
     // First try to read the cached config
-    let aliases = atuin_common::utils::dotfiles_cache_dir().join("aliases.ps1");
+    let aliases = atuin_common::paths::dotfiles_cache_dir().join("aliases.ps1");
 
     if aliases.exists() {
         return cached_aliases(aliases, store).await;
@@ -54,7 +54,7 @@ pub async fn alias_config(store: &AliasStore) -> String {
 
 pub async fn var_config(store: &VarStore) -> String {
     // First try to read the cached config
-    let vars = atuin_common::utils::dotfiles_cache_dir().join("vars.ps1");
+    let vars = atuin_common::paths::dotfiles_cache_dir().join("vars.ps1");
 
     if vars.exists() {
         return cached_vars(vars, store).await;
@@ -14,7 +14,7 @@ fn smart_sort(lines: usize) {
     // benchmark a few different sizes of "history"
     // first we need to generate some history. This will use a whole bunch of memory, sorry
     let mut rng = rand::thread_rng();
-    let now = time::OffsetDateTime::now_utc().unix_timestamp();
+    let now = time::OffsetDateTime::now_utc();
 
     let possible_commands = ["echo", "ls", "cd", "grep", "atuin", "curl"];
     let mut commands = Vec::<History>::with_capacity(lines);
@@ -24,12 +24,15 @@ fn smart_sort(lines: usize) {
 
         let command = History::import()
             .command(command)
-            .timestamp(time::OffsetDateTime::from_unix_timestamp(rng.gen_range(0..now)).unwrap())
+            .timestamp(
+                time::OffsetDateTime::from_unix_timestamp(rng.gen_range(0..now.unix_timestamp()))
+                    .unwrap(),
+            )
             .build()
             .into();
 
         commands.push(command);
     }
 
-    let _ = sort("curl", commands);
+    let _ = sort("curl", commands, now);
 }
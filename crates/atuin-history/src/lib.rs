@@ -1,2 +1,3 @@
 pub mod sort;
 pub mod stats;
+pub mod summary;
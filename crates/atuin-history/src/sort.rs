@@ -1,4 +1,5 @@
 use atuin_client::history::History;
+use time::OffsetDateTime;
 
 type ScoredHistory = (f64, History);
 
@@ -6,7 +7,8 @@ type ScoredHistory = (f64, History);
 // This sorting should be applicable to all search modes, and solve the more "obvious" issues
 // first.
 // Later on, we can pass in context and do some boosts there too.
-pub fn sort(query: &str, input: Vec<History>) -> Vec<History> {
+// `now` is when recency is measured from.
+pub fn sort(query: &str, input: Vec<History>, now: OffsetDateTime) -> Vec<History> {
     // This can totally be extended. We need to be _careful_ that it's not slow.
     // We also need to balance sorting db-side with sorting here. SQLite can do a lot,
     // but some things are just much easier/more doable in Rust.
@@ -24,9 +26,7 @@ pub fn sort(query: &str, input: Vec<History>) -> Vec<History> {
             };
 
             // calculate how long ago the history was, in seconds
-            let now = time::OffsetDateTime::now_utc().unix_timestamp();
-            let time = h.timestamp.unix_timestamp();
-            let diff = std::cmp::max(1, now - time); // no /0 please
+            let diff = std::cmp::max(1, (now - h.timestamp).whole_seconds()); // no /0 please
 
             // prefer newer history, but not hugely so as to offset the other scoring
             // the numbers will get super small over time, but I don't want time to overpower other
@@ -44,3 +44,126 @@ pub fn sort(query: &str, input: Vec<History>) -> Vec<History> {
     // Remove the scores and return the history
     scored.into_iter().map(|(_, h)| h).collect::<Vec<History>>()
 }
+
+/// Move the commands that most often followed the previous command to the top,
+/// in the order given by `successors` (most frequent first).
+///
+/// Everything else keeps its relative order, so this can be layered on top of
+/// any other sort.
+pub fn boost_successors(input: Vec<History>, successors: &[(String, i64)]) -> Vec<History> {
+    if successors.is_empty() {
+        return input;
+    }
+
+    let mut boosted = input;
+    boosted.sort_by_key(|h| {
+        successors
+            .iter()
+            .position(|(command, _)| *command == h.command)
+            .unwrap_or(usize::MAX)
+    });
+
+    boosted
+}
+
+/// Move the pinned commands above everything else, however they ranked.
+///
+/// Pinned and unpinned entries each keep their relative order, so this goes on
+/// top of every other sort.
+pub fn pin_first(input: Vec<History>, pinned: &[String]) -> Vec<History> {
+    if pinned.is_empty() {
+        return input;
+    }
+
+    let mut sorted = input;
+    sorted.sort_by_key(|h| !pinned.contains(&h.command));
+
+    sorted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{boost_successors, pin_first, sort};
+    use atuin_client::history::History;
+    use time::{Duration, macros::datetime};
+
+    fn history(command: &str) -> History {
+        History::capture()
+            .timestamp(time::OffsetDateTime::now_utc())
+            .command(command)
+            .cwd("/")
+            .build()
+            .into()
+    }
+
+    fn commands(h: &[History]) -> Vec<&str> {
+        h.iter().map(|h| h.command.as_str()).collect()
+    }
+
+    #[test]
+    fn ranks_recent_matches_higher() {
+        let now = datetime!(2026-10-15 12:00 UTC);
+        let at = |command: &str, ago: Duration| {
+            let mut h = history(command);
+            h.timestamp = now - ago;
+            h
+        };
+
+        let sorted = sort(
+            "cargo",
+            vec![
+                at("cargo build", Duration::days(30)),
+                at("cargo test", Duration::seconds(1)),
+                at("ls", Duration::seconds(1)),
+            ],
+            now,
+        );
+
+        assert_eq!(commands(&sorted), ["cargo test", "cargo build", "ls"]);
+    }
+
+    #[test]
+    fn boosts_successors_in_frequency_order() {
+        let input = vec![
+            history("ls"),
+            history("cargo run"),
+            history("git status"),
+            history("cargo test"),
+        ];
+        let successors = vec![("cargo test".to_string(), 5), ("cargo run".to_string(), 2)];
+
+        let sorted = boost_successors(input, &successors);
+
+        assert_eq!(
+            commands(&sorted),
+            vec!["cargo test", "cargo run", "ls", "git status"]
+        );
+    }
+
+    #[test]
+    fn no_successors_keeps_order() {
+        let input = vec![history("b"), history("a"), history("c")];
+
+        let sorted = boost_successors(input, &[]);
+
+        assert_eq!(commands(&sorted), vec!["b", "a", "c"]);
+    }
+
+    #[test]
+    fn pinned_commands_come_first() {
+        let input = vec![
+            history("ls"),
+            history("cargo test"),
+            history("git status"),
+            history("cargo run"),
+        ];
+        let pinned = vec!["cargo run".to_string(), "cargo test".to_string()];
+
+        let sorted = pin_first(boost_successors(input, &[("ls".to_string(), 1)]), &pinned);
+
+        assert_eq!(
+            commands(&sorted),
+            vec!["cargo test", "cargo run", "ls", "git status"]
+        );
+    }
+}
@@ -29,7 +29,7 @@ fn first_whitespace(s: &str) -> usize {
         .map_or(s.len(), |(i, _)| i)
 }
 
-fn interesting_command<'a>(settings: &Settings, mut command: &'a str) -> &'a str {
+pub(crate) fn interesting_command<'a>(settings: &Settings, mut command: &'a str) -> &'a str {
     // Sort by length so that we match the longest prefix first
     let mut common_prefix = settings.stats.common_prefix.clone();
     common_prefix.sort_by_key(|b| std::cmp::Reverse(b.len()));
@@ -70,7 +70,7 @@ fn interesting_command<'a>(settings: &Settings, mut command: &'a str) -> &'a str
     &command[..first_whitespace(command)]
 }
 
-fn split_at_pipe(command: &str) -> Vec<&str> {
+pub(crate) fn split_at_pipe(command: &str) -> Vec<&str> {
     let mut result = vec![];
     let mut quoted = false;
     let mut start = 0;
@@ -103,7 +103,7 @@ fn split_at_pipe(command: &str) -> Vec<&str> {
     result
 }
 
-fn strip_leading_env_vars(command: &str) -> &str {
+pub(crate) fn strip_leading_env_vars(command: &str) -> &str {
     // fast path: no equals sign, no environment variable
     if !command.contains('=') {
         return command;
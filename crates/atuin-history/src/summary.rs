@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+use time::OffsetDateTime;
+
+use atuin_client::{history::History, settings::Settings};
+
+use crate::stats::{interesting_command, split_at_pipe, strip_leading_env_vars};
+
+/// What went on in a shell session, worked out from its history.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SessionSummary {
+    pub session: String,
+    /// When the first command started
+    pub start: OffsetDateTime,
+    /// When the last command finished, or started if it never did
+    pub end: OffsetDateTime,
+    pub commands: usize,
+    pub failures: usize,
+    /// The directories commands ran in, in the order they were first used
+    pub directories: Vec<String>,
+    /// The programs run, with how many times each was, most used first
+    pub tools: Vec<(String, usize)>,
+    pub first_command: String,
+    pub last_command: String,
+}
+
+impl SessionSummary {
+    /// Summarize `session` from its `history`, in any order. `None` if it has none.
+    pub fn compute(settings: &Settings, session: &str, history: &[History]) -> Option<Self> {
+        let mut history: Vec<&History> = history.iter().collect();
+        history.sort_by_key(|h| h.timestamp);
+        let (first, last) = (*history.first()?, *history.last()?);
+
+        let end = history
+            .iter()
+            .map(|h| h.timestamp + time::Duration::nanoseconds(h.duration.max(0)))
+            .max()
+            .unwrap_or(last.timestamp);
+
+        let mut directories: Vec<String> = Vec::new();
+        let mut tools: HashMap<&str, usize> = HashMap::new();
+        for h in &history {
+            if !directories.contains(&h.cwd) {
+                directories.push(h.cwd.clone());
+            }
+
+            for part in split_at_pipe(strip_leading_env_vars(h.command.trim())) {
+                let command = strip_leading_env_vars(part.trim());
+                let tool = interesting_command(settings, command)
+                    .split_whitespace()
+                    .next()
+                    .unwrap_or_default();
+                if !tool.is_empty() {
+                    *tools.entry(tool).or_default() += 1;
+                }
+            }
+        }
+
+        let mut tools: Vec<(String, usize)> = tools
+            .into_iter()
+            .map(|(tool, count)| (tool.to_string(), count))
+            .collect();
+        tools.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        Some(SessionSummary {
+            session: session.to_string(),
+            start: first.timestamp,
+            end,
+            commands: history.len(),
+            failures: history.iter().filter(|h| !h.success()).count(),
+            directories,
+            tools,
+            first_command: first.command.clone(),
+            last_command: last.command.clone(),
+        })
+    }
+
+    /// How long the session ran, from the start of its first command to the end of its last
+    pub fn duration(&self) -> std::time::Duration {
+        (self.end - self.start).try_into().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use atuin_client::history::History;
+    use atuin_client::settings::Settings;
+    use time::{Duration, macros::datetime};
+
+    use super::SessionSummary;
+
+    fn entry(command: &str, minute: i64, cwd: &str, exit: i64) -> History {
+        History::import()
+            .timestamp(datetime!(2026-10-16 09:00 UTC) + Duration::minutes(minute))
+            .command(command)
+            .cwd(cwd)
+            .exit(exit)
+            .duration(30_000_000_000)
+            .session("abc")
+            .build()
+            .into()
+    }
+
+    #[test]
+    fn sessions_are_summarized() {
+        let settings = Settings::utc();
+        let history = [
+            entry("git push", 40, "/home/ellie/src/atuin", 1),
+            entry("ssh prod-1", 0, "/home/ellie", 0),
+            entry("cd src/atuin", 5, "/home/ellie", 0),
+            entry("git status | grep main", 10, "/home/ellie/src/atuin", 0),
+            entry(
+                "RUST_LOG=debug cargo test",
+                20,
+                "/home/ellie/src/atuin",
+                101,
+            ),
+        ];
+
+        let summary = SessionSummary::compute(&settings, "abc", &history).unwrap();
+        assert_eq!(summary.start, datetime!(2026-10-16 09:00 UTC));
+        assert_eq!(summary.end, datetime!(2026-10-16 09:40:30 UTC));
+        assert_eq!(
+            summary.duration(),
+            std::time::Duration::from_secs(40 * 60 + 30)
+        );
+        assert_eq!(summary.commands, 5);
+        assert_eq!(summary.failures, 2);
+        assert_eq!(
+            summary.directories,
+            ["/home/ellie", "/home/ellie/src/atuin"]
+        );
+        assert_eq!(summary.tools[0], ("git".to_string(), 2));
+        assert!(summary.tools.contains(&("cargo".to_string(), 1)));
+        assert!(summary.tools.contains(&("grep".to_string(), 1)));
+        assert_eq!(summary.first_command, "ssh prod-1");
+        assert_eq!(summary.last_command, "git push");
+
+        assert!(SessionSummary::compute(&settings, "abc", &[]).is_none());
+    }
+}
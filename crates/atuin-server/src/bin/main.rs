@@ -2,6 +2,7 @@
 
 use std::net::SocketAddr;
 
+use atuin_common::otel::OtelConfig;
 use atuin_server::{Settings, example_config, launch, launch_metrics_server};
 use atuin_server_database::DbType;
 use atuin_server_postgres::Postgres;
@@ -34,20 +35,45 @@ enum Cmd {
     DefaultConfig,
 }
 
+/// Log to the console as `RUST_LOG` says, and export traces if `otel` is enabled
+fn init_tracing(otel: &OtelConfig) {
+    let registry = tracing_subscriber::registry()
+        .with(fmt::layer().with_filter(EnvFilter::from_default_env()));
+
+    if !otel.enabled {
+        registry.init();
+        return;
+    }
+
+    #[cfg(feature = "otel")]
+    match atuin_common::otel::init(otel, "atuin-server") {
+        // Requests and database queries, whatever the console shows
+        Ok(layer) => registry
+            .with(layer.with_filter(EnvFilter::new("info,tower_http=debug")))
+            .init(),
+        Err(e) => {
+            registry.init();
+            tracing::error!("not exporting traces: {e}");
+        }
+    }
+
+    #[cfg(not(feature = "otel"))]
+    {
+        registry.init();
+        tracing::warn!("otel is enabled, but atuin-server was built without the otel feature");
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let cmd = Cmd::parse();
 
-    tracing_subscriber::registry()
-        .with(fmt::layer())
-        .with(EnvFilter::from_default_env())
-        .init();
-
-    tracing::trace!(command = ?cmd, "server command");
-
-    match cmd {
+    match &cmd {
         Cmd::Start { host, port } => {
             let settings = Settings::new().wrap_err("could not load server settings")?;
+            init_tracing(&settings.otel);
+            tracing::trace!(command = ?cmd, "server command");
+
             let host = host.as_ref().unwrap_or(&settings.host).clone();
             let port = port.unwrap_or(settings.port);
             let addr = SocketAddr::new(host.parse()?, port);
@@ -59,11 +85,16 @@ async fn main() -> Result<()> {
                 ));
             }
 
-            match settings.db_settings.db_type() {
+            let res = match settings.db_settings.db_type() {
                 DbType::Postgres => launch::<Postgres>(settings, addr).await,
                 DbType::Sqlite => launch::<Sqlite>(settings, addr).await,
                 DbType::Unknown => Err(eyre!("db_uri must start with postgres:// or sqlite://")),
-            }
+            };
+
+            #[cfg(feature = "otel")]
+            atuin_common::otel::shutdown().await;
+
+            res
         }
         Cmd::DefaultConfig => {
             println!("{}", example_config());
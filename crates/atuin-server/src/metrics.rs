@@ -49,6 +49,14 @@ pub async fn track_metrics(req: Request, next: Next) -> impl IntoResponse {
         ("status", status),
     ];
 
+    #[cfg(feature = "otel")]
+    atuin_common::otel::record_request(
+        method.as_str(),
+        &labels[1].1,
+        response.status().as_u16(),
+        start.elapsed(),
+    );
+
     metrics::counter!("http_requests_total", &labels).increment(1);
     metrics::histogram!("http_requests_duration_seconds", &labels).record(latency);
 
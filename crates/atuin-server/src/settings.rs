@@ -1,5 +1,6 @@
 use std::{io::prelude::*, path::PathBuf};
 
+use atuin_common::otel::OtelConfig;
 use atuin_server_database::DbSettings;
 use config::{Config, Environment, File as ConfigFile, FileFormat};
 use eyre::{Result, eyre};
@@ -37,6 +38,10 @@ pub struct Settings {
     pub register_webhook_username: String,
     pub metrics: Metrics,
 
+    /// Exporting traces and metrics over OTLP, with the `otel` feature
+    #[serde(default)]
+    pub otel: OtelConfig,
+
     /// Advertise a version that is not what we are _actually_ running
     /// Many clients compare their version with api.atuin.sh, and if they differ, notify the user
     /// that an update is available.
@@ -54,7 +59,7 @@ impl Settings {
             PathBuf::from(p)
         } else {
             let mut config_file = PathBuf::new();
-            let config_dir = atuin_common::utils::config_dir();
+            let config_dir = atuin_common::paths::config_dir();
             config_file.push(config_dir);
             config_file
         };
@@ -8,4 +8,9 @@ fn main() {
     };
 
     println!("cargo:rustc-env=GIT_HASH={sha}");
+
+    // Release archives are named after the platform they're for, so `atuin self-update` needs
+    // to know which one it was built for
+    let target = std::env::var("TARGET").unwrap();
+    println!("cargo:rustc-env=ATUIN_TARGET={target}");
 }
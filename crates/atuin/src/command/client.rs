@@ -1,11 +1,13 @@
+use std::path::Path;
+
 use clap::Subcommand;
 use eyre::{Result, WrapErr};
 
 use atuin_client::logs::FromSettings;
 use atuin_client::{
-    database::Sqlite, record::sqlite_store::SqliteStore, settings::Settings, theme,
+    database::Sqlite, read_only, record::sqlite_store::SqliteStore, settings::Settings, theme,
 };
-use atuin_common::logs::{self, LogConfig};
+use atuin_common::logs::LogConfig;
 
 #[cfg(feature = "sync")]
 mod sync;
@@ -16,18 +18,27 @@ mod account;
 #[cfg(feature = "daemon")]
 mod daemon;
 
+mod complete;
 mod config;
 mod default_config;
 mod doctor;
 mod dotfiles;
 mod history;
 mod hook;
+mod host;
 mod import;
 mod info;
 mod init;
 mod kv;
+mod migrations;
+mod prompt_info;
+mod run;
 mod scripts;
 mod search;
+#[cfg(feature = "check-update")]
+mod self_update;
+mod session;
+mod sessions;
 mod setup;
 mod stats;
 mod store;
@@ -57,6 +68,9 @@ pub enum Cmd {
     /// Interactive history search
     Search(search::Cmd),
 
+    /// Run a command and save its output with its history entry
+    Run(run::Cmd),
+
     #[cfg(feature = "sync")]
     #[command(flatten)]
     Sync(sync::Cmd),
@@ -73,6 +87,21 @@ pub enum Cmd {
     #[command(subcommand)]
     Store(store::Cmd),
 
+    /// List and name the machines you sync with
+    #[command(subcommand)]
+    Host(host::Cmd),
+
+    /// Name shell sessions, to find their history again
+    #[command(subcommand)]
+    Session(session::Cmd),
+
+    /// Browse the recent sessions on every host, and replay what ran in them
+    Sessions(sessions::Cmd),
+
+    /// Inspect the history database's schema migrations
+    #[command(subcommand)]
+    Migrations(migrations::Cmd),
+
     /// Manage your dotfiles with Atuin
     #[command(subcommand)]
     Dotfiles(dotfiles::Cmd),
@@ -91,7 +120,12 @@ pub enum Cmd {
 
     /// Run the doctor to check for common issues
     #[command()]
-    Doctor,
+    Doctor(doctor::Cmd),
+
+    /// Print the last command's exit code and duration, sync age and daemon status as JSON,
+    /// for shell prompts
+    #[command()]
+    PromptInfo,
 
     #[command()]
     Wrapped { year: Option<i32> },
@@ -108,6 +142,14 @@ pub enum Cmd {
     #[command(subcommand)]
     Config(config::Cmd),
 
+    #[cfg(feature = "check-update")]
+    #[command()]
+    SelfUpdate(self_update::Cmd),
+
+    /// Print completion candidates for the shell completions (used by `atuin gen-completions`)
+    #[command(hide = true)]
+    Complete(complete::Cmd),
+
     /// Run the AI assistant
     #[cfg(feature = "ai")]
     #[command(subcommand)]
@@ -120,7 +162,7 @@ pub enum Cmd {
 }
 
 impl Cmd {
-    pub fn run(self) -> Result<()> {
+    pub fn run(self, offline: bool) -> Result<()> {
         // Daemonize before creating the async runtime – fork() inside a live
         // tokio runtime corrupts its internal state.
         #[cfg(all(unix, feature = "daemon"))]
@@ -145,9 +187,16 @@ impl Cmd {
         // For non-history commands, we want to initialize logging and the theme manager before
         // doing anything else. History commands are performance-sensitive and run before and after
         // every shell command, so we want to skip any unnecessary initialization for them.
-        let settings = Settings::new().wrap_err("could not load client settings")?;
-        self.init_logging(&settings);
-        let theme_manager = theme::ThemeManager::new(settings.theme.debug, None);
+        let mut settings = Settings::new().wrap_err("could not load client settings")?;
+        settings.offline |= offline;
+        crate::crash::install(Path::new(&settings.logs.dir));
+        {
+            // Exporting traces needs a runtime for its connection
+            let _runtime = runtime.enter();
+            self.init_logging(&settings);
+        }
+        let theme_manager = theme::ThemeManager::new(settings.theme.debug, None)
+            .with_color_support(theme::ColorSupport::detect());
         let res = runtime.block_on(self.run_inner(settings, theme_manager));
 
         runtime.shutdown_timeout(std::time::Duration::from_millis(50));
@@ -163,6 +212,10 @@ impl Cmd {
     ) -> Result<()> {
         tracing::trace!(command = ?self, "client command");
 
+        if let Some(what) = self.only_writes() {
+            read_only::ensure_writable(what)?;
+        }
+
         // Skip initializing any databases for history
         // This is a pretty hot path, as it runs before and after every single command the user
         // runs
@@ -170,23 +223,36 @@ impl Cmd {
             Self::History(history) => return history.run(&settings).await,
             Self::Hook(hook) => return hook.run(&settings).await,
             Self::Init(init) => return init.run(&settings).await,
-            Self::Doctor => return doctor::run(&settings).await,
+            Self::Doctor(doctor) => return doctor.run(&settings).await,
+            Self::PromptInfo => return prompt_info::run(&settings).await,
+            Self::Run(run) => return run.run(&settings).await,
             Self::Config(config) => return config.run(&settings).await,
+            Self::Migrations(migrations) => return migrations.run(&settings).await,
+            #[cfg(feature = "check-update")]
+            Self::SelfUpdate(self_update) => return self_update.run(&settings).await,
             _ => {}
         }
 
-        let db_path = &settings.db_path;
         let record_store_path = &settings.record_store_path;
 
-        let db = Sqlite::new(db_path, settings.local_timeout).await?;
+        let db = if self.uses_archive() {
+            Sqlite::from_settings(&settings).await?
+        } else {
+            Sqlite::new(&settings.db_path, settings.local_timeout).await?
+        };
         let sqlite_store = SqliteStore::new(record_store_path, settings.local_timeout).await?;
 
+        // Completion lists every theme, so it needs the manager before one is loaded
+        if let Self::Complete(complete) = &self {
+            return complete.run(&db, &theme_manager).await;
+        }
+
         let theme_name = settings.theme.name.clone();
         let theme = theme_manager.load_theme(theme_name.as_str(), settings.theme.max_depth);
 
         match self {
             Self::Setup => setup::run(&settings).await,
-            Self::Import(import) => import.run(&db).await,
+            Self::Import(import) => import.run(&db, &settings).await,
             Self::Stats(stats) => stats.run(&db, &settings, theme).await,
             Self::Search(search) => search.run(db, &mut settings, sqlite_store, theme).await,
 
@@ -200,6 +266,12 @@ impl Cmd {
 
             Self::Store(store) => store.run(&settings, &db, sqlite_store).await,
 
+            Self::Host(host) => host.run(&settings, sqlite_store).await,
+
+            Self::Session(session) => session.run(&settings, &db, sqlite_store).await,
+
+            Self::Sessions(sessions) => sessions.run(&settings, &db).await,
+
             Self::Dotfiles(dotfiles) => dotfiles.run(&settings, sqlite_store).await,
 
             Self::Scripts(scripts) => scripts.run(&settings, sqlite_store, &db).await,
@@ -219,10 +291,21 @@ impl Cmd {
             #[cfg(feature = "daemon")]
             Self::Daemon(cmd) => cmd.run(settings, sqlite_store, db).await,
 
-            Self::History(_) | Self::Hook(_) | Self::Init(_) | Self::Doctor | Self::Config(_) => {
+            Self::History(_)
+            | Self::Hook(_)
+            | Self::Init(_)
+            | Self::Doctor(_)
+            | Self::PromptInfo
+            | Self::Run(_)
+            | Self::Config(_)
+            | Self::Migrations(_)
+            | Self::Complete(_) => {
                 unreachable!()
             }
 
+            #[cfg(feature = "check-update")]
+            Self::SelfUpdate(_) => unreachable!(),
+
             #[cfg(feature = "ai")]
             Self::Ai(cli) => atuin_ai::commands::run(cli, &settings).await,
 
@@ -231,6 +314,35 @@ impl Cmd {
         }
     }
 
+    /// What this command is for, when that can't be done without writing, so read-only mode
+    /// can refuse it up front.
+    fn only_writes(&self) -> Option<&'static str> {
+        match self {
+            Self::Setup => Some("set up atuin"),
+            Self::Import(_) => Some("import history"),
+            Self::Run(_) => Some("run and record a command"),
+            Self::Migrations(migrations::Cmd::RevertLast { .. }) => Some("revert a migration"),
+            #[cfg(feature = "sync")]
+            Self::Sync(_) | Self::Account(_) => Some("sync"),
+            #[cfg(feature = "daemon")]
+            Self::Daemon(_) => Some("run the daemon"),
+            _ => None,
+        }
+    }
+
+    /// Whether this command searches the history archive, or saves history from the record
+    /// store that the archive may already have, and so needs it attached.
+    fn uses_archive(&self) -> bool {
+        match self {
+            Self::Search(_) | Self::Store(_) => true,
+            #[cfg(feature = "sync")]
+            Self::Sync(_) => true,
+            #[cfg(feature = "daemon")]
+            Self::Daemon(_) => true,
+            _ => false,
+        }
+    }
+
     fn log_config(&self, settings: &Settings) -> Option<LogConfig> {
         match self {
             Self::History(cmd) => cmd.log_config(),
@@ -241,10 +353,7 @@ impl Cmd {
             )),
 
             #[cfg(feature = "daemon")]
-            Self::Daemon(cmd) => Some(LogConfig {
-                file: logs::FileConfig::from_settings(&settings.logs, &settings.logs.daemon),
-                stderr: cmd.show_logs().then(logs::StderrConfig::verbose),
-            }),
+            Self::Daemon(cmd) => Some(cmd.log_config(settings)),
 
             #[cfg(feature = "ai")]
             Self::Ai(cmd) => Some(cmd.log_config(settings)),
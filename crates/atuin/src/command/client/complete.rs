@@ -0,0 +1,194 @@
+use std::collections::HashSet;
+
+use clap::{Args, ValueEnum};
+use eyre::Result;
+
+use atuin_client::{database::Database, history::History, theme::ThemeManager};
+
+use super::search::{find_paths, one_line};
+
+/// How many history IDs to offer at most
+const MAX_HISTORY_IDS: usize = 50;
+
+/// How many of the latest commands paths are looked for in
+const MAX_PATH_COMMANDS: i64 = 1000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Kind {
+    /// IDs of recent history entries, with their commands
+    HistoryId,
+    /// Built-in themes and theme files
+    Theme,
+    /// Files and directories named in recent commands that are still there, with the
+    /// latest command to name each
+    Path,
+}
+
+/// Print the candidates for the dynamic parts of the completions from `atuin gen-completions`,
+/// one per line, as the value and a description separated by a tab
+#[derive(Args, Debug)]
+pub struct Cmd {
+    kind: Kind,
+
+    /// Only print values starting with this
+    #[arg(default_value = "", allow_hyphen_values = true)]
+    prefix: String,
+}
+
+impl Cmd {
+    pub async fn run(&self, db: &impl Database, themes: &ThemeManager) -> Result<()> {
+        for candidate in self.candidates(db, themes).await? {
+            println!("{candidate}");
+        }
+
+        Ok(())
+    }
+
+    async fn candidates(&self, db: &impl Database, themes: &ThemeManager) -> Result<Vec<String>> {
+        match self.kind {
+            Kind::HistoryId => {
+                let mut entries: Vec<History> = if self.prefix.is_empty() {
+                    db.latest(None, i64::try_from(MAX_HISTORY_IDS)?).await?
+                } else {
+                    let ids = db
+                        .ids_with_prefix(&self.prefix.to_lowercase(), MAX_HISTORY_IDS)
+                        .await?;
+                    db.load_active(&ids).await?
+                };
+                entries.sort_by_key(|h| std::cmp::Reverse(h.timestamp));
+
+                Ok(entries
+                    .iter()
+                    .map(|h| format!("{}\t{}", h.id, one_line(&h.command)))
+                    .collect())
+            }
+            Kind::Path => {
+                let mut entries = db.latest(None, MAX_PATH_COMMANDS).await?;
+                entries.sort_by_key(|h| std::cmp::Reverse(h.timestamp));
+
+                let mut seen = HashSet::new();
+                let mut candidates = Vec::new();
+                for h in &entries {
+                    for path in find_paths(&h.command, &h.cwd) {
+                        let Some(resolved) = path.resolved.to_str() else {
+                            continue;
+                        };
+                        if resolved.starts_with(&self.prefix)
+                            && seen.insert(resolved.to_string())
+                            && path.exists()
+                        {
+                            candidates.push(format!("{resolved}\t{}", one_line(&h.command)));
+                        }
+                    }
+                }
+                Ok(candidates)
+            }
+            Kind::Theme => Ok(themes
+                .available_themes()
+                .into_iter()
+                .filter(|name| name.starts_with(&self.prefix))
+                .collect()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use atuin_client::{
+        database::{Database, Sqlite},
+        history::History,
+        theme::ThemeManager,
+    };
+    use time::macros::datetime;
+
+    use super::{Cmd, Kind};
+
+    fn cmd(kind: Kind, prefix: &str) -> Cmd {
+        Cmd {
+            kind,
+            prefix: prefix.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn history_ids_newest_first() {
+        let db = Sqlite::new("sqlite::memory:", 2.0).await.unwrap();
+        let mut ids = Vec::new();
+        for (minute, command) in [(1, "ls"), (2, "cat <<EOF\nhi\nEOF")] {
+            let h: History = History::import()
+                .timestamp(datetime!(2026-10-15 12:00 UTC) + time::Duration::minutes(minute))
+                .command(command)
+                .cwd("/")
+                .exit(0)
+                .duration(1)
+                .build()
+                .into();
+            db.save(&h).await.unwrap();
+            ids.push(h.id.0);
+        }
+        let themes = ThemeManager::new(Some(false), Some(String::new()));
+
+        let all = cmd(Kind::HistoryId, "")
+            .candidates(&db, &themes)
+            .await
+            .unwrap();
+        assert_eq!(
+            all,
+            [
+                format!("{}\tcat <<EOF ⏎ hi ⏎ EOF", ids[1]),
+                format!("{}\tls", ids[0])
+            ]
+        );
+
+        let prefixed = cmd(Kind::HistoryId, &ids[0][..30].to_uppercase())
+            .candidates(&db, &themes)
+            .await
+            .unwrap();
+        assert_eq!(prefixed, [format!("{}\tls", ids[0])]);
+    }
+
+    #[tokio::test]
+    async fn paths_that_are_still_there() {
+        let db = Sqlite::new("sqlite::memory:", 2.0).await.unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("notes.md"), "").unwrap();
+        let cwd = dir.path().to_str().unwrap();
+        for (minute, command) in [(1, "vim notes.md"), (2, "cat ./notes.md gone.md")] {
+            let h: History = History::import()
+                .timestamp(datetime!(2026-10-15 12:00 UTC) + time::Duration::minutes(minute))
+                .command(command)
+                .cwd(cwd)
+                .exit(0)
+                .duration(1)
+                .build()
+                .into();
+            db.save(&h).await.unwrap();
+        }
+        let themes = ThemeManager::new(Some(false), Some(String::new()));
+
+        let notes = dir.path().join("notes.md");
+        let found = cmd(Kind::Path, cwd).candidates(&db, &themes).await.unwrap();
+        assert_eq!(
+            found,
+            [format!("{}\tcat ./notes.md gone.md", notes.display())]
+        );
+
+        let elsewhere = cmd(Kind::Path, "/nowhere")
+            .candidates(&db, &themes)
+            .await
+            .unwrap();
+        assert!(elsewhere.is_empty());
+    }
+
+    #[tokio::test]
+    async fn themes_by_prefix() {
+        let db = Sqlite::new("sqlite::memory:", 2.0).await.unwrap();
+        let themes = ThemeManager::new(Some(false), Some(String::new()));
+
+        let found = cmd(Kind::Theme, "ma")
+            .candidates(&db, &themes)
+            .await
+            .unwrap();
+        assert_eq!(found, ["marine"]);
+    }
+}
@@ -312,6 +312,23 @@ fn detect_existing_type(doc: &DocumentMut, key: &str) -> Option<ValueType> {
     }
 }
 
+/// Set `key` to `value` in the config file, keeping the rest of the file as it is. For
+/// settings changed from inside the TUI, which can't await.
+pub fn set_value(key: &str, value: impl Into<Value>) -> Result<()> {
+    let config_file = Settings::get_config_path()?;
+    let config_str = match std::fs::read_to_string(&config_file) {
+        Ok(s) => s,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+    let mut doc: DocumentMut = config_str.parse()?;
+
+    set_deep_key(&mut doc, key, value.into())?;
+    std::fs::write(&config_file, doc.to_string())?;
+
+    Ok(())
+}
+
 fn set_deep_key(doc: &mut DocumentMut, key: &str, value: Value) -> Result<()> {
     let parts: Vec<&str> = key.split('.').collect();
 
@@ -1,5 +1,6 @@
+use std::collections::VecDeque;
 use std::fs::{self, File, OpenOptions};
-use std::io::{ErrorKind, Write};
+use std::io::{BufRead, BufReader, ErrorKind, Read, Seek, SeekFrom, Write};
 #[cfg(unix)]
 use std::os::unix::net::UnixStream as StdUnixStream;
 use std::path::{Path, PathBuf};
@@ -7,10 +8,16 @@ use std::process::{Command, Stdio};
 use std::time::{Duration, Instant};
 
 use atuin_client::{
-    database::Sqlite, history::History, record::sqlite_store::SqliteStore, settings::Settings,
+    database::Sqlite,
+    history::{History, Origin},
+    logs::FromSettings,
+    record::sqlite_store::SqliteStore,
+    settings::Settings,
 };
+use atuin_common::logs::{FileConfig, LogConfig, StderrConfig};
 use atuin_daemon::DaemonEvent;
 use atuin_daemon::client::{ControlClient, DaemonClientErrorKind, HistoryClient, classify_error};
+use atuin_daemon::history::PromptInfoReply;
 use clap::Subcommand;
 #[cfg(unix)]
 use daemonize::Daemonize;
@@ -57,6 +64,17 @@ pub enum SubCmd {
 
     /// Restart the daemon (stop, then start in background)
     Restart,
+
+    /// Show the end of the daemon's log
+    Logs {
+        /// Keep printing new log lines as they're written, across log rotations
+        #[arg(short, long)]
+        follow: bool,
+
+        /// How many lines to show from the end of the log
+        #[arg(short = 'n', long, default_value_t = 50)]
+        lines: usize,
+    },
 }
 
 impl Cmd {
@@ -80,6 +98,21 @@ impl Cmd {
         }
     }
 
+    pub fn log_config(&self, settings: &Settings) -> LogConfig {
+        // Reading the logs shouldn't add to them
+        if let Some(SubCmd::Logs { .. }) = self.subcmd {
+            return LogConfig::stderr_only();
+        }
+
+        let running = matches!(self.subcmd, None | Some(SubCmd::Start { .. }));
+
+        LogConfig {
+            file: FileConfig::from_settings(&settings.logs, &settings.logs.daemon),
+            stderr: self.show_logs().then(StderrConfig::verbose),
+            otel: (running && settings.daemon.otel.enabled).then(|| settings.daemon.otel.clone()),
+        }
+    }
+
     pub async fn run(
         self,
         settings: Settings,
@@ -95,6 +128,7 @@ impl Cmd {
             Some(SubCmd::Status) => status_cmd(&settings).await,
             Some(SubCmd::Stop) => stop_cmd(&settings).await,
             Some(SubCmd::Restart) => restart_cmd(&settings).await,
+            Some(SubCmd::Logs { follow, lines }) => logs_cmd(&settings, follow, lines).await,
         }
     }
 }
@@ -458,11 +492,15 @@ where
     Ok(resp)
 }
 
-pub async fn start_history(settings: &Settings, history: History) -> Result<String> {
+pub async fn start_history(
+    settings: &Settings,
+    history: History,
+    origin: Option<Origin>,
+) -> Result<String> {
     let resp = try_with_restart(
         settings,
-        async |client, history| client.start_history(history).await,
-        history,
+        async |client, (history, origin)| client.start_history_from(history, origin).await,
+        (history, origin),
         TryWithRestartOptions {
             retry_on_version_mismatch: true,
         },
@@ -536,6 +574,14 @@ pub async fn emit_event(settings: &Settings, event: DaemonEvent) {
     }
 }
 
+/// Ask an already running daemon for prompt info. Unlike the history hooks this never
+/// starts the daemon, as it runs on every prompt and has a cheap fallback.
+pub async fn prompt_info(settings: &Settings, session: String) -> Result<PromptInfoReply> {
+    let reply = connect_client(settings).await?.prompt_info(session).await?;
+    ensure_reply_compatible(settings, &reply.version, reply.protocol)?;
+    Ok(reply)
+}
+
 pub async fn tail_client(settings: &Settings) -> Result<HistoryClient> {
     match probe(settings).await {
         Probe::Ready(client) => return Ok(client),
@@ -637,6 +683,103 @@ async fn restart_cmd(settings: &Settings) -> Result<()> {
     Ok(())
 }
 
+/// The daemon's current log file. Logs rotate daily, into files named after
+/// `path` with the date added, so this is the newest of those.
+fn current_log_file(path: &Path) -> Option<PathBuf> {
+    let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty())?;
+    let prefix = path.file_name()?.to_str()?;
+
+    fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| name.starts_with(prefix))
+        })
+        .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+        .max()
+        .map(|(_, path)| path)
+}
+
+/// The last `count` lines of `reader`
+fn last_lines(reader: impl BufRead, count: usize) -> std::io::Result<VecDeque<String>> {
+    let mut lines = VecDeque::with_capacity(count);
+
+    for line in reader.lines() {
+        if lines.len() == count {
+            lines.pop_front();
+        }
+        lines.push_back(line?);
+    }
+
+    if count == 0 {
+        lines.clear();
+    }
+
+    Ok(lines)
+}
+
+async fn logs_cmd(settings: &Settings, follow: bool, lines: usize) -> Result<()> {
+    const FOLLOW_POLL: Duration = Duration::from_millis(250);
+
+    let path = PathBuf::from_iter([&settings.logs.dir, &settings.logs.daemon.file]);
+    let Some(mut current) = current_log_file(&path) else {
+        if !settings
+            .logs
+            .daemon
+            .enabled
+            .unwrap_or(settings.logs.enabled)
+        {
+            bail!("daemon logs are turned off; set logs.daemon.enabled = true to write them");
+        }
+        bail!(
+            "the daemon hasn't written any logs to {} yet",
+            path.display()
+        );
+    };
+
+    let mut file =
+        File::open(&current).wrap_err_with(|| format!("could not open {}", current.display()))?;
+    let mut stdout = std::io::stdout();
+    for line in last_lines(BufReader::new(&mut file), lines)? {
+        writeln!(stdout, "{line}")?;
+    }
+    stdout.flush()?;
+
+    if !follow {
+        return Ok(());
+    }
+
+    let mut buf = vec![0; 8192];
+    loop {
+        let read = file.read(&mut buf)?;
+        if read > 0 {
+            stdout.write_all(&buf[..read])?;
+            stdout.flush()?;
+            continue;
+        }
+
+        sleep(FOLLOW_POLL).await;
+
+        // Start over if the log was cut short, and move on to the next one when it rotates
+        let position = file.stream_position()?;
+        if fs::metadata(&current).is_ok_and(|meta| meta.len() < position) {
+            file.seek(SeekFrom::Start(0))?;
+        }
+        if let Some(newest) = current_log_file(&path)
+            && newest != current
+        {
+            // Whatever was written to the old file since the last read
+            std::io::copy(&mut file, &mut stdout)?;
+            file = File::open(&newest)
+                .wrap_err_with(|| format!("could not open {}", newest.display()))?;
+            current = newest;
+        }
+    }
+}
+
 /// Daemonize the current process. Must be called before creating the tokio
 /// runtime or opening database connections, since `fork()` inside an async
 /// runtime corrupts its internal state.
@@ -666,9 +809,12 @@ async fn run(
     let pidfile_path = PathBuf::from(&settings.daemon.pidfile_path);
     let _pidfile_guard = PidfileGuard::acquire(&pidfile_path)?;
 
-    atuin_daemon::boot(settings, store, history_db).await?;
+    let res = atuin_daemon::boot(settings, store, history_db).await;
 
-    Ok(())
+    #[cfg(feature = "otel")]
+    atuin_common::otel::shutdown().await;
+
+    res
 }
 
 /// Force cleanup: kill existing daemon process and remove socket.
@@ -790,6 +936,40 @@ mod tests {
         let _guard2 = PidfileGuard::acquire(&pidfile).unwrap();
     }
 
+    #[test]
+    fn test_last_lines() {
+        let log = "one\ntwo\nthree\n";
+        assert_eq!(last_lines(log.as_bytes(), 2).unwrap(), ["two", "three"]);
+        assert_eq!(
+            last_lines(log.as_bytes(), 10).unwrap(),
+            ["one", "two", "three"]
+        );
+        assert!(last_lines(log.as_bytes(), 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_current_log_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("daemon.log");
+        assert_eq!(current_log_file(&path), None);
+
+        let old = tmp.path().join("daemon.log.2026-10-14");
+        std::fs::write(&old, "old\n").unwrap();
+        std::fs::write(tmp.path().join("search.log.2026-10-15"), "search\n").unwrap();
+        assert_eq!(current_log_file(&path), Some(old.clone()));
+
+        let new = tmp.path().join("daemon.log.2026-10-15");
+        std::fs::write(&new, "new\n").unwrap();
+        let earlier = std::time::SystemTime::now() - Duration::from_hours(1);
+        File::options()
+            .write(true)
+            .open(&old)
+            .unwrap()
+            .set_modified(earlier)
+            .unwrap();
+        assert_eq!(current_log_file(&path), Some(new));
+    }
+
     #[test]
     fn test_pidfile_guard_prevents_double_acquire() {
         let tmp = tempfile::tempdir().unwrap();
@@ -2,10 +2,15 @@ use std::path::PathBuf;
 use std::process::Command;
 use std::{env, str::FromStr};
 
+mod history;
+mod paths;
+
 use atuin_client::database::Sqlite;
+use atuin_client::record::sync::CLOCK_SKEW_WARNING;
 use atuin_client::settings::Settings;
 use atuin_common::shell::{Shell, shell_name};
 use atuin_common::utils;
+use clap::{Args, Subcommand};
 use colored::Colorize;
 use eyre::Result;
 use serde::Serialize;
@@ -248,6 +253,64 @@ impl SystemInfo {
     }
 }
 
+#[derive(Debug, Serialize)]
+struct WslInfo {
+    pub distro: String,
+    pub version: u8,
+
+    /// How far the sync server's clock was ahead of this one when it was last synced with
+    pub clock_skew_secs: Option<i64>,
+
+    /// The locale numbers and times are formatted in, and whether the distro has it
+    pub locale: Option<String>,
+    pub locale_installed: Option<bool>,
+
+    /// Whether a timezone is set, rather than left at UTC
+    pub timezone_set: bool,
+}
+
+impl WslInfo {
+    pub async fn new() -> Option<Self> {
+        let version = atuin_common::wsl::version()?;
+
+        let locale = ["LC_ALL", "LC_TIME", "LANG"]
+            .into_iter()
+            .find_map(|var| env::var(var).ok().filter(|v| !v.is_empty()));
+        let locale_installed = locale.as_deref().and_then(|locale| {
+            let output = Command::new("locale").arg("-a").output().ok()?;
+            Some(locale_listed(
+                &String::from_utf8_lossy(&output.stdout),
+                locale,
+            ))
+        });
+
+        Some(Self {
+            distro: atuin_common::wsl::distro().unwrap_or_else(|| "unknown".to_string()),
+            version,
+            clock_skew_secs: Settings::clock_skew()
+                .await
+                .ok()
+                .flatten()
+                .map(time::Duration::whole_seconds),
+            locale,
+            locale_installed,
+            timezone_set: env::var_os("TZ").is_some() || PathBuf::from("/etc/localtime").exists(),
+        })
+    }
+}
+
+/// Whether `locale` is among those `locale -a` lists, which spells the encoding its own way:
+/// `en_US.UTF-8` is listed as `en_US.utf8`.
+fn locale_listed(available: &str, locale: &str) -> bool {
+    let normalize = |name: &str| name.to_lowercase().replace(['-', '_'], "");
+    let locale = normalize(locale);
+    locale == "c"
+        || locale == "posix"
+        || available
+            .lines()
+            .any(|name| normalize(name.trim()) == locale)
+}
+
 #[derive(Debug, Serialize)]
 struct SyncInfo {
     pub auth_state: String,
@@ -381,6 +444,7 @@ struct DoctorDump {
     pub atuin: AtuinInfo,
     pub shell: ShellInfo,
     pub system: SystemInfo,
+    pub wsl: Option<WslInfo>,
 }
 
 impl DoctorDump {
@@ -389,6 +453,7 @@ impl DoctorDump {
             atuin: AtuinInfo::new(settings).await,
             shell: ShellInfo::new(),
             system: SystemInfo::new(),
+            wsl: WslInfo::new().await,
         }
     }
 }
@@ -417,6 +482,10 @@ fn checks(info: &DoctorDump) {
 
     info.atuin.setting_paths.verify();
 
+    if let Some(wsl) = &info.wsl {
+        wsl_checks(wsl);
+    }
+
     // Shell
     if info.shell.name == "bash" {
         if !info
@@ -437,6 +506,67 @@ fn checks(info: &DoctorDump) {
     }
 }
 
+/// WSL's clock falls behind Windows' while the machine sleeps, and a fresh distro often has no
+/// timezone or its locale generated, which throws off the times and durations atuin records.
+fn wsl_checks(wsl: &WslInfo) {
+    if wsl
+        .clock_skew_secs
+        .is_some_and(|skew| skew.abs() > CLOCK_SKEW_WARNING.whole_seconds())
+    {
+        println!(
+            "{}",
+            "[WSL] This distro's clock is out of step with the sync server's. WSL's clock can fall behind while Windows sleeps, which throws off the times and durations of commands: run `sudo hwclock -s`, or `wsl --shutdown` from Windows, to set it again."
+                .bold()
+                .red()
+        );
+    }
+
+    if let (Some(locale), Some(false)) = (&wsl.locale, wsl.locale_installed) {
+        println!(
+            "{}",
+            format!(
+                "[WSL] The locale {locale} isn't installed in this distro, so programs fall back to another and may format times and numbers differently from how they're read back. Install it, for example with `sudo locale-gen {locale}`."
+            )
+            .bold()
+            .red()
+        );
+    }
+
+    if !wsl.timezone_set {
+        println!(
+            "{}",
+            "[WSL] No timezone is set in this distro, so times are shown in UTC rather than Windows' time. Set one with `sudo dpkg-reconfigure tzdata`, or the TZ environment variable."
+                .bold()
+                .red()
+        );
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct Cmd {
+    #[command(subcommand)]
+    subcmd: Option<SubCmd>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SubCmd {
+    /// Look for history entries that are likely to render or count strangely
+    History(history::Cmd),
+
+    /// List where each of atuin's files is kept, and anything left where older versions kept them
+    Paths(paths::Cmd),
+}
+
+impl Cmd {
+    pub async fn run(self, settings: &Settings) -> Result<()> {
+        match self.subcmd {
+            Some(SubCmd::History(cmd)) => cmd.run(settings).await,
+            Some(SubCmd::Paths(cmd)) => cmd.run(settings),
+            None => run(settings).await,
+        }
+    }
+}
+
 pub async fn run(settings: &Settings) -> Result<()> {
     println!("{}", "Atuin Doctor".bold());
     println!("Checking for diagnostics");
@@ -451,3 +581,18 @@ pub async fn run(settings: &Settings) -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locales_are_found_however_their_encoding_is_spelled() {
+        let available = "C\nC.utf8\nen_US.utf8\nPOSIX\n";
+
+        assert!(locale_listed(available, "en_US.UTF-8"));
+        assert!(locale_listed(available, "C.UTF-8"));
+        assert!(locale_listed(available, "POSIX"));
+        assert!(!locale_listed(available, "de_DE.UTF-8"));
+    }
+}
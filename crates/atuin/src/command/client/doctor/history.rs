@@ -0,0 +1,305 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use clap::{Args, ValueEnum};
+use colored::Colorize;
+use eyre::{Context, Result};
+use time::{Duration, OffsetDateTime};
+
+use atuin_client::{
+    database::{Database, Sqlite, current_context},
+    encryption,
+    history::{
+        History, HistoryId,
+        store::{HistoryRecord, HistoryStore},
+    },
+    record::sqlite_store::SqliteStore,
+    settings::{FilterMode, Settings},
+};
+
+use super::super::search::one_line;
+
+/// How far ahead of this machine's clock a timestamp can be before it counts
+/// as being in the future, to allow for a little skew between hosts.
+const FUTURE_GRACE: Duration = Duration::minutes(5);
+
+/// How many entries of each kind to list.
+const EXAMPLES: usize = 5;
+
+/// Look for history entries that are likely to render or count strangely
+#[derive(Args, Debug)]
+pub struct Cmd {
+    /// Fix entries of this kind. Can be given more than once
+    #[arg(long, value_enum)]
+    fix: Vec<Anomaly>,
+
+    /// Commands longer than this many bytes are reported as huge
+    #[arg(long, default_value_t = 16 * 1024)]
+    max_command_bytes: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, ValueEnum)]
+pub enum Anomaly {
+    ZeroDuration,
+    NegativeDuration,
+    FutureTimestamp,
+    EmptyCommand,
+    HugeCommand,
+    DuplicateId,
+}
+
+impl Anomaly {
+    fn describe(self) -> &'static str {
+        match self {
+            Self::ZeroDuration => "finished in exactly zero time",
+            Self::NegativeDuration => "have a negative duration",
+            Self::FutureTimestamp => "are timestamped in the future",
+            Self::EmptyCommand => "have an empty command",
+            Self::HugeCommand => "have a huge command",
+            Self::DuplicateId => "were recorded more than once in the record store",
+        }
+    }
+
+    /// What `--fix` does, or `None` if it can't do anything.
+    fn fix(self) -> Option<&'static str> {
+        match self {
+            Self::ZeroDuration | Self::NegativeDuration => Some("marks the duration as unknown"),
+            Self::FutureTimestamp => Some("moves the timestamp to now"),
+            Self::EmptyCommand | Self::HugeCommand => Some("deletes the entries"),
+            Self::DuplicateId => None,
+        }
+    }
+}
+
+/// Sort `history` into the anomalies it shows. `created` is every history
+/// entry created in the record store, in store order.
+fn find(
+    history: &[History],
+    created: &[History],
+    now: OffsetDateTime,
+    max_command_bytes: usize,
+) -> BTreeMap<Anomaly, Vec<History>> {
+    let mut found: BTreeMap<Anomaly, Vec<History>> = BTreeMap::new();
+
+    for h in history {
+        let mut note = |anomaly| found.entry(anomaly).or_default().push(h.clone());
+
+        // -1 is what an entry has until its command finishes, or if the shell
+        // it came from never said how long it took.
+        match h.duration {
+            0 => note(Anomaly::ZeroDuration),
+            ..=-2 => note(Anomaly::NegativeDuration),
+            _ => {}
+        }
+        if h.timestamp > now + FUTURE_GRACE {
+            note(Anomaly::FutureTimestamp);
+        }
+        if h.command.trim().is_empty() {
+            note(Anomaly::EmptyCommand);
+        } else if h.command.len() > max_command_bytes {
+            note(Anomaly::HugeCommand);
+        }
+    }
+
+    let mut seen = HashSet::new();
+    for h in created {
+        if !seen.insert(&h.id) {
+            found
+                .entry(Anomaly::DuplicateId)
+                .or_default()
+                .push(h.clone());
+        }
+    }
+
+    found
+}
+
+fn print_report(total: usize, found: &BTreeMap<Anomaly, Vec<History>>) {
+    println!("Checked {total} history entries\n");
+
+    for anomaly in Anomaly::value_variants() {
+        let name = anomaly.to_possible_value().expect("no skipped variants");
+        let name = name.get_name();
+
+        let Some(entries) = found.get(anomaly) else {
+            println!("{} {name}", "✓".green().bold());
+            continue;
+        };
+
+        println!(
+            "{} {name}: {} {}",
+            "✗".red().bold(),
+            entries.len(),
+            anomaly.describe()
+        );
+        for h in entries.iter().take(EXAMPLES) {
+            let command = one_line(&h.command);
+            let command = match command.char_indices().nth(60) {
+                Some((end, _)) => format!("{}…", &command[..end]),
+                None => command,
+            };
+            println!("    {} {} {command}", h.id, h.timestamp.date());
+        }
+        if entries.len() > EXAMPLES {
+            println!("    … and {} more", entries.len() - EXAMPLES);
+        }
+
+        match anomaly.fix() {
+            Some(fix) => println!("  `atuin doctor history --fix {name}` {fix}"),
+            None => println!(
+                "  the newest copy is used when the database is rebuilt; `atuin store verify` checks the store"
+            ),
+        }
+    }
+}
+
+impl Cmd {
+    pub async fn run(self, settings: &Settings) -> Result<()> {
+        println!("{}", "Atuin Doctor: history".bold());
+
+        let db = Sqlite::new(&settings.db_path, settings.local_timeout).await?;
+        let store = SqliteStore::new(&settings.record_store_path, settings.local_timeout).await?;
+        let encryption_key: [u8; 32] = encryption::load_key(settings)
+            .context("could not load encryption key")?
+            .into();
+        let host_id = Settings::host_id().await?;
+        let history_store = HistoryStore::new(store, host_id, encryption_key);
+
+        let context = current_context().await?;
+        let history = db
+            .list(&[FilterMode::Global], &context, None, false, false, None)
+            .await?;
+        let created: Vec<History> = history_store
+            .history()
+            .await?
+            .into_iter()
+            .filter_map(|record| match record {
+                HistoryRecord::Create(h) => Some(h),
+                HistoryRecord::Delete(_) => None,
+            })
+            .collect();
+
+        let now = OffsetDateTime::now_utc();
+        let found = find(&history, &created, now, self.max_command_bytes);
+        print_report(history.len(), &found);
+
+        let fix: HashSet<Anomaly> = self.fix.into_iter().collect();
+        if fix.is_empty() {
+            return Ok(());
+        }
+        // Fixing deletes or rewrites entries
+        settings.ensure_history_removable()?;
+        println!();
+
+        let to_fix = |anomaly| {
+            found
+                .get(&anomaly)
+                .filter(|_| fix.contains(&anomaly))
+                .map_or(&[][..], Vec::as_slice)
+        };
+
+        // Deleting wins over rewriting an entry that's in both.
+        let mut deleted = HashSet::new();
+        for anomaly in [Anomaly::EmptyCommand, Anomaly::HugeCommand] {
+            for h in to_fix(anomaly) {
+                if deleted.insert(h.id.clone()) {
+                    let (id, _) = history_store.delete(h.id.clone()).await?;
+                    history_store.build_all(&db, &[id]).await?;
+                }
+            }
+        }
+
+        let mut updated: HashMap<HistoryId, History> = HashMap::new();
+        for anomaly in [
+            Anomaly::ZeroDuration,
+            Anomaly::NegativeDuration,
+            Anomaly::FutureTimestamp,
+        ] {
+            for h in to_fix(anomaly) {
+                if deleted.contains(&h.id) {
+                    continue;
+                }
+                let h = updated.entry(h.id.clone()).or_insert_with(|| h.clone());
+                if anomaly == Anomaly::FutureTimestamp {
+                    h.timestamp = now;
+                } else {
+                    h.duration = -1;
+                }
+            }
+        }
+        for h in updated.values() {
+            db.update(h).await?;
+        }
+
+        if fix.contains(&Anomaly::DuplicateId) {
+            println!("duplicate-id can't be fixed here; the newest copy is already the one in use");
+        }
+        println!(
+            "Deleted {} entries, updated {}",
+            deleted.len(),
+            updated.len()
+        );
+        if !updated.is_empty() {
+            println!(
+                "Updates only change this machine's database, and are undone if it's rebuilt from the record store"
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(command: &str, duration: i64, timestamp: OffsetDateTime) -> History {
+        History::import()
+            .timestamp(timestamp)
+            .command(command)
+            .duration(duration)
+            .build()
+            .into()
+    }
+
+    fn commands(found: &BTreeMap<Anomaly, Vec<History>>, anomaly: Anomaly) -> Vec<&str> {
+        found
+            .get(&anomaly)
+            .map(|entries| entries.iter().map(|h| h.command.as_str()).collect())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn sorts_entries_by_anomaly() {
+        let now = OffsetDateTime::now_utc();
+        let history = vec![
+            entry("ls", 1_000, now),
+            entry("running", -1, now),
+            entry("instant", 0, now),
+            entry("backwards", -5_000, now),
+            entry("tomorrow", 1_000, now + Duration::days(1)),
+            entry("skewed", 1_000, now + Duration::minutes(1)),
+            entry("   ", 1_000, now),
+            entry(&"x".repeat(20), 1_000, now),
+        ];
+        let found = find(&history, &[], now, 16);
+
+        assert_eq!(commands(&found, Anomaly::ZeroDuration), ["instant"]);
+        assert_eq!(commands(&found, Anomaly::NegativeDuration), ["backwards"]);
+        assert_eq!(commands(&found, Anomaly::FutureTimestamp), ["tomorrow"]);
+        assert_eq!(commands(&found, Anomaly::EmptyCommand), ["   "]);
+        assert_eq!(commands(&found, Anomaly::HugeCommand), ["x".repeat(20)]);
+        assert!(!found.contains_key(&Anomaly::DuplicateId));
+    }
+
+    #[test]
+    fn finds_ids_created_twice() {
+        let now = OffsetDateTime::now_utc();
+        let first = entry("ls", 1_000, now);
+        let mut again = first.clone();
+        again.command = "ls -la".to_string();
+        let other = entry("pwd", 1_000, now);
+
+        let found = find(&[], &[first, other, again], now, 1024);
+        assert_eq!(commands(&found, Anomaly::DuplicateId), ["ls -la"]);
+    }
+}
@@ -0,0 +1,140 @@
+use std::path::{Path, PathBuf};
+
+use clap::Args;
+use colored::Colorize;
+use eyre::Result;
+
+use atuin_client::settings::Settings;
+use atuin_common::paths;
+
+/// List where each of atuin's files is kept, and anything left where older versions kept them
+#[derive(Args, Debug)]
+pub struct Cmd {}
+
+/// One of atuin's files, and the setting that moves it, if there is one.
+struct Location {
+    name: &'static str,
+    setting: Option<&'static str>,
+    path: PathBuf,
+}
+
+fn location(name: &'static str, setting: Option<&'static str>, path: impl AsRef<Path>) -> Location {
+    Location {
+        name,
+        setting,
+        path: path.as_ref().to_path_buf(),
+    }
+}
+
+fn locations(settings: &Settings) -> Result<Vec<Location>> {
+    Ok(vec![
+        location("config file", None, Settings::get_config_path()?),
+        location("history database", Some("db_path"), &settings.db_path),
+        location(
+            "record store",
+            Some("record_store_path"),
+            &settings.record_store_path,
+        ),
+        location(
+            "archive",
+            Some("archive.db_path"),
+            &settings.archive.db_path,
+        ),
+        location("encryption key", Some("key_path"), &settings.key_path),
+        location(
+            "session and host id",
+            Some("meta.db_path"),
+            &settings.meta.db_path,
+        ),
+        location("key-value store", Some("kv.db_path"), &settings.kv.db_path),
+        location(
+            "scripts",
+            Some("scripts.db_path"),
+            &settings.scripts.db_path,
+        ),
+        location("AI sessions", Some("ai.db_path"), &settings.ai.db_path),
+        location(
+            "daemon socket",
+            Some("daemon.socket_path"),
+            &settings.daemon.socket_path,
+        ),
+        location(
+            "daemon pidfile",
+            Some("daemon.pidfile_path"),
+            &settings.daemon.pidfile_path,
+        ),
+        location(
+            "daemon API token",
+            Some("daemon.api_token_path"),
+            &settings.daemon.api_token_path,
+        ),
+        location("logs", Some("logs.dir"), &settings.logs.dir),
+        location("dotfiles cache", None, paths::dotfiles_cache_dir()),
+    ])
+}
+
+impl Cmd {
+    pub fn run(self, settings: &Settings) -> Result<()> {
+        println!("{}", "Atuin Doctor: paths".bold());
+
+        println!("\n{}", "Base directories".bold());
+        for (name, var, dir) in [
+            ("config", "XDG_CONFIG_HOME", paths::config_dir()),
+            ("data", "XDG_DATA_HOME", paths::data_dir()),
+            ("state", "XDG_STATE_HOME", paths::state_dir()),
+            ("cache", "XDG_CACHE_HOME", paths::cache_dir()),
+            ("runtime", "XDG_RUNTIME_DIR", paths::runtime_dir()),
+        ] {
+            let from = if std::env::var_os(var).is_some_and(|v| !v.is_empty()) {
+                format!("from {var}")
+            } else {
+                "default".to_string()
+            };
+            println!("  {name:<8} {} ({})", dir.display(), from.dimmed());
+        }
+        if let Ok(dir) = std::env::var("ATUIN_CONFIG_DIR") {
+            println!("  {} ATUIN_CONFIG_DIR is set to {dir}", "note".yellow());
+        }
+
+        println!("\n{}", "Files".bold());
+        for location in locations(settings)? {
+            let exists = if location.path.exists() {
+                "exists".green()
+            } else {
+                "not created yet".dimmed()
+            };
+            let setting = location
+                .setting
+                .map(|setting| format!(", set with {setting}"))
+                .unwrap_or_default();
+            println!(
+                "  {:<20} {} ({exists}{})",
+                location.name,
+                location.path.display(),
+                setting.dimmed()
+            );
+        }
+
+        // Anything still pending couldn't be moved the first time atuin ran after upgrading
+        let left: Vec<_> = settings
+            .relocations()
+            .into_iter()
+            .filter(paths::Relocation::pending)
+            .collect();
+        println!("\n{}", "Old locations".bold());
+        if left.is_empty() {
+            println!("  Nothing is left where older versions kept it");
+        }
+        for relocation in left {
+            println!(
+                "  {} {} files are still in {}, as files of the same name are already in {}. Move or remove them by hand.",
+                "warning".yellow().bold(),
+                relocation.name,
+                relocation.from.display(),
+                relocation.to.display()
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -20,25 +20,23 @@ use colored::Colorize;
 #[cfg(feature = "daemon")]
 use serde::Serialize;
 
-#[cfg(feature = "daemon")]
-use atuin_common::utils::normalize_optional_string;
 #[cfg(feature = "daemon")]
 use atuin_daemon::history::{HistoryEventKind, TailHistoryReply};
 
 use atuin_client::{
     database::{Database, Sqlite, current_context},
     encryption,
-    history::{History, store::HistoryStore},
+    history::{History, Origin, store::HistoryStore},
+    host::{HostNames, HostStore},
+    read_only,
     record::sqlite_store::SqliteStore,
     settings::{
+        Archive,
         FilterMode::{Directory, Global, Session},
         Settings, Timezone,
     },
 };
 
-#[cfg(feature = "sync")]
-use atuin_client::record;
-
 use time::{OffsetDateTime, macros::format_description};
 use tracing::{debug, warn};
 
@@ -70,24 +68,64 @@ pub enum Cmd {
         /// to minimize the amount of time the command takes to run.
         #[arg(long, hide = true)]
         hook: bool,
+
+        /// Print a monotonic clock reading after the ID, separated by a space, to pass back to
+        /// `end --started-clock`
+        #[arg(long, hide = true)]
+        clock: bool,
     },
 
     /// Finishes a new command in the history (adds time, exit code)
     End {
+        /// The entry's ID, or a unique prefix of it
         id: String,
         #[arg(long, short)]
         exit: i64,
         #[arg(long, short)]
         duration: Option<u64>,
 
+        /// The clock reading printed by `start --clock`. When given, the duration is measured
+        /// from it instead of trusting `--duration` or the wall clock
+        #[arg(long, hide = true)]
+        started_clock: Option<u64>,
+
         /// Passed by shell hooks; this flag disables logging to avoid corrupting the terminal and
         /// to minimize the amount of time the command takes to run.
         #[arg(long, hide = true)]
         hook: bool,
     },
 
-    /// Stream history events from the daemon as they are received
-    Tail,
+    /// Print the most recent history entries, and with `--follow`, stream new ones from the
+    /// daemon as they are run here or synced from other machines
+    Tail {
+        /// How many of the most recent entries to print first
+        #[arg(long, short = 'n', default_value_t = 10)]
+        lines: usize,
+
+        /// Keep streaming entries as commands start and end, or arrive by sync
+        #[arg(long, short)]
+        follow: bool,
+
+        /// Show only the text of the command. Like `--format`, prints only finished commands
+        #[arg(long)]
+        cmd_only: bool,
+
+        /// Display the command time in another timezone other than the configured default.
+        ///
+        /// This option takes one of the following kinds of values:
+        ///
+        /// - the special value "local" (or "l") which refers to the system time zone
+        /// - an offset from UTC (e.g. "+9", "-2:30")
+        #[arg(long, visible_alias = "tz", verbatim_doc_comment)]
+        timezone: Option<Timezone>,
+
+        /// Print each finished command in this format instead of a block per event (or JSON
+        /// lines, when not writing to a terminal). Takes the same variables as `list --format`
+        ///
+        /// Example: --format "{time} {host} - {command}"
+        #[arg(long)]
+        format: Option<String>,
+    },
 
     /// List all items in history
     List {
@@ -124,7 +162,7 @@ pub enum Cmd {
         #[arg(long, visible_alias = "tz", verbatim_doc_comment)]
         timezone: Option<Timezone>,
 
-        /// Available variables: {command}, {directory}, {duration}, {user}, {host}, {author}, {intent}, {exit}, {time}, {session}, and {uuid}
+        /// Available variables: {command}, {directory}, {duration}, {user}, {host}, {author}, {intent}, {exit}, {time}, {session}, {uuid} and {shortid}
         ///
         /// Example: --format "{time} - [{duration}] - {directory}$\t{command}"
         #[arg(long, short)]
@@ -133,6 +171,10 @@ pub enum Cmd {
 
     /// Get the last command that was run
     Last {
+        /// How many of the most recent commands to show, oldest first
+        #[arg(long, short, default_value_t = 1)]
+        n: usize,
+
         #[arg(long)]
         human: bool,
 
@@ -149,7 +191,7 @@ pub enum Cmd {
         #[arg(long, visible_alias = "tz", verbatim_doc_comment)]
         timezone: Option<Timezone>,
 
-        /// Available variables: {command}, {directory}, {duration}, {user}, {host}, {author}, {intent}, {time}, {session}, {uuid} and {relativetime}.
+        /// Available variables: {command}, {directory}, {duration}, {user}, {host}, {author}, {intent}, {time}, {session}, {uuid}, {shortid} and {relativetime}.
         ///
         /// Example: --format "{time} - [{duration}] - {directory}$\t{command}"
         #[arg(long, short)]
@@ -158,6 +200,12 @@ pub enum Cmd {
 
     InitStore,
 
+    /// Add a command to the history without running it, such as a template with `{{name}}`
+    /// fields to fill in when it's accepted from the search
+    Save {
+        command: Vec<String>,
+    },
+
     /// Delete history entries matching the configured exclusion filters
     Prune {
         /// List matching history lines without performing the actual deletion.
@@ -165,6 +213,13 @@ pub enum Cmd {
         dry_run: bool,
     },
 
+    /// Move old history into the archive, which is only searched when asked for
+    Archive {
+        /// Archive history older than this (eg "6months"), instead of `archive.after`
+        #[arg(long)]
+        older_than: Option<String>,
+    },
+
     /// Delete duplicate history entries (that have the same command, cwd and hostname)
     Dedup {
         /// List matching history lines without performing the actual deletion.
@@ -200,7 +255,7 @@ impl ListMode {
     }
 }
 
-#[allow(clippy::cast_sign_loss)]
+#[allow(clippy::cast_sign_loss, clippy::too_many_arguments)]
 pub fn print_list(
     h: &[History],
     list_mode: ListMode,
@@ -208,6 +263,8 @@ pub fn print_list(
     print0: bool,
     reverse: bool,
     tz: Timezone,
+    short_id_len: usize,
+    hosts: &HostNames,
 ) {
     let w = std::io::stdout();
     let mut w = w.lock();
@@ -242,6 +299,8 @@ pub fn print_list(
             history,
             cmd_format: CmdFormat::for_output(&w),
             tz: &tz,
+            short_id_len,
+            hosts,
         };
         let args = parsed_fmt.with_args(&fh);
 
@@ -302,6 +361,8 @@ struct FmtHistory<'a> {
     history: &'a History,
     cmd_format: CmdFormat,
     tz: &'a Timezone,
+    short_id_len: usize,
+    hosts: &'a HostNames,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -352,12 +413,7 @@ impl FormatKey for FmtHistory<'_> {
                 let d = Duration::try_from(since).unwrap_or_default();
                 format_duration_into(d, f)?;
             }
-            "host" => f.write_str(
-                self.history
-                    .hostname
-                    .split_once(':')
-                    .map_or(&self.history.hostname, |(host, _)| host),
-            )?,
+            "host" => f.write_str(self.hosts.display(&self.history.hostname))?,
             "author" => f.write_str(&self.history.author)?,
             "intent" => f.write_str(self.history.intent.as_deref().unwrap_or_default())?,
             "user" => f.write_str(
@@ -368,12 +424,118 @@ impl FormatKey for FmtHistory<'_> {
             )?,
             "session" => f.write_str(&self.history.session)?,
             "uuid" => f.write_str(&self.history.id.0)?,
+            "shortid" => f.write_str(
+                self.history
+                    .id
+                    .0
+                    .get(..self.short_id_len)
+                    .unwrap_or(&self.history.id.0),
+            )?,
             _ => return Err(FormatKeyError::UnknownKey),
         }
         Ok(())
     }
 }
 
+/// The fewest characters `{shortid}` shows, like git's abbreviated hashes.
+const MIN_SHORT_ID_LEN: usize = 8;
+
+/// IDs are 32 hex characters; anything shorter is a prefix.
+const FULL_ID_LEN: usize = 32;
+
+/// How much of the ID of each of `history` `{shortid}` shows: enough to tell them apart from
+/// every other entry. Only worth a query when the format actually uses it.
+pub(super) async fn short_id_len(
+    db: &impl Database,
+    format: &str,
+    history: &[History],
+) -> Result<usize> {
+    if format.contains("{shortid}") {
+        let ids: Vec<_> = history.iter().map(|h| h.id.clone()).collect();
+        Ok(db.unique_id_prefix_len(&ids, MIN_SHORT_ID_LEN).await?)
+    } else {
+        Ok(MIN_SHORT_ID_LEN)
+    }
+}
+
+/// Friendly names for `{host}`. Only worth reading the host registry when the format
+/// actually uses it.
+pub(super) async fn host_names(
+    settings: &Settings,
+    store: &SqliteStore,
+    format: &str,
+) -> HostNames {
+    if !format.contains("{host}") {
+        return HostNames::default();
+    }
+
+    let names = async {
+        let encryption_key: [u8; 32] = encryption::load_key(settings)?.into();
+        let host_id = Settings::host_id().await?;
+
+        HostStore::new(store.clone(), host_id, encryption_key)
+            .names()
+            .await
+    };
+
+    names.await.unwrap_or_else(|e| {
+        warn!("could not load host names, showing hostnames instead: {e}");
+        HostNames::default()
+    })
+}
+
+/// Move history older than `archive.after` into the archive, returning how
+/// many entries moved, or `None` if archiving is off.
+pub(super) async fn archive_history(settings: &Settings, archive: &Archive) -> Result<Option<u64>> {
+    let Some(age) = archive.age()? else {
+        return Ok(None);
+    };
+
+    let db =
+        Sqlite::with_archive(&settings.db_path, &archive.db_path, settings.local_timeout).await?;
+    let moved = db.archive(OffsetDateTime::now_utc() - age).await?;
+
+    Ok(Some(moved))
+}
+
+/// Resolve a full history ID, or a prefix of one that only a single entry has.
+pub(super) async fn resolve_history_id(db: &impl Database, id: &str) -> Result<String> {
+    const MIN_PREFIX_LEN: usize = 4;
+    const SHOW_MATCHES: usize = 5;
+
+    if id.len() >= FULL_ID_LEN {
+        return Ok(id.to_string());
+    }
+
+    if id.len() < MIN_PREFIX_LEN {
+        bail!("history ID prefix {id:?} is too short, use at least {MIN_PREFIX_LEN} characters");
+    }
+
+    let matches = db
+        .ids_with_prefix(&id.to_lowercase(), SHOW_MATCHES + 1)
+        .await?;
+
+    match matches.as_slice() {
+        [] => bail!("no history entry has an ID starting with {id:?}"),
+        [only] => Ok(only.0.clone()),
+        _ => {
+            let mut shown = matches
+                .iter()
+                .take(SHOW_MATCHES)
+                .map(|m| format!("  {m}"))
+                .collect::<Vec<_>>();
+            if matches.len() > SHOW_MATCHES {
+                shown.push("  ...".to_string());
+            }
+
+            bail!(
+                "history ID prefix {id:?} is ambiguous, it matches:\n{}\nUse a longer prefix.",
+                shown.join("\n")
+            )
+        }
+    }
+}
+
 fn parse_fmt(format: &str) -> ParsedFmt<'_> {
     match ParsedFmt::new(format) {
         Ok(fmt) => fmt,
@@ -466,8 +628,10 @@ async fn handle_start(
     };
 
     // Silently ignore database errors to avoid breaking the shell
-    // This is important when disk is full or database is locked
-    if let Err(e) = db.save(&h).await {
+    // This is important when disk is full or database is locked.
+    // Where it was run is noted with it: the OS account, for the `user:` filter and
+    // append-only mode, and the container, if any, for `container:`.
+    if let Err(e) = db.save_with_origin(&h, Origin::current()).await {
         debug!("failed to save history: {e}");
     }
 
@@ -487,9 +651,16 @@ async fn handle_daemon_start(
 
     let local_id = h.id.0.clone();
 
+    // The daemon doesn't know who its clients are, and notes its own account unless told,
+    // which append-only mode's audit needs it to be
+    let origin = settings
+        .compliance
+        .append_only
+        .then(|| Origin::current().clone());
+
     // Attempt to start history via daemon, but silently ignore errors
     // to avoid breaking the shell when the daemon is unavailable or disk is full
-    let resp = match daemon::start_history(settings, h).await {
+    let resp = match daemon::start_history(settings, h, origin).await {
         Ok(id) => id,
         Err(e) => {
             debug!("failed to start history via daemon: {e}");
@@ -500,10 +671,33 @@ async fn handle_daemon_start(
     Ok(Some(resp))
 }
 
-#[allow(unused_variables)]
+/// Nanoseconds on a clock that never jumps, shared by every process on this
+/// machine, so readings from the start and end hooks can be subtracted without
+/// NTP or DST changes in between skewing the result. On Linux it keeps counting
+/// while the machine is suspended, as wall-clock durations did.
+#[cfg(unix)]
+fn monotonic_clock() -> Option<u64> {
+    use rustix::time::{ClockId, clock_gettime};
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    let id = ClockId::Boottime;
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    let id = ClockId::Monotonic;
+
+    let now = clock_gettime(id);
+    u64::try_from(now.tv_sec)
+        .ok()?
+        .checked_mul(1_000_000_000)?
+        .checked_add(u64::try_from(now.tv_nsec).ok()?)
+}
+
+#[cfg(not(unix))]
+fn monotonic_clock() -> Option<u64> {
+    None
+}
+
 async fn handle_end(
     db: &impl Database,
-    store: SqliteStore,
     history_store: HistoryStore,
     settings: &Settings,
     id: &str,
@@ -526,7 +720,7 @@ async fn handle_end(
         return Ok(());
     }
 
-    if !settings.store_failed && exit > 0 {
+    if !settings.keeps_failed(exit) {
         debug!("history has non-zero exit code, and store_failed is false");
 
         // the history has already been inserted half complete. remove it
@@ -545,15 +739,11 @@ async fn handle_end(
     db.update(&h).await?;
     history_store.push(h).await?;
 
-    if settings.should_sync().await? {
+    // Sync in another process, so that a slow or unreachable server never
+    // holds up the shell waiting on this one
+    if settings.background_sync_due().await? {
         #[cfg(feature = "sync")]
-        {
-            let (_, downloaded) =
-                record::sync::sync(settings, &store, &history_store.encryption_key).await?;
-            Settings::save_sync_time().await?;
-
-            crate::sync::build(settings, &store, db, Some(&downloaded)).await?;
-        }
+        super::sync::spawn_background().await?;
         #[cfg(not(feature = "sync"))]
         debug!("not compiled with sync support");
     } else {
@@ -570,7 +760,7 @@ async fn handle_daemon_end(
     exit: i64,
     duration: Option<u64>,
 ) -> Result<()> {
-    if !settings.store_failed && exit > 0 {
+    if !settings.keeps_failed(exit) {
         debug!("history has non-zero exit code, and store_failed is false");
         daemon::cancel_history(settings, id.to_string()).await?;
     } else {
@@ -619,7 +809,8 @@ pub(super) async fn end_history_entry(
     let host_id = Settings::host_id().await?;
     let history_store = HistoryStore::new(store.clone(), host_id, encryption_key);
 
-    handle_end(&db, store, history_store, settings, id, exit, duration).await
+    let id = resolve_history_id(&db, id).await?;
+    handle_end(&db, history_store, settings, &id, exit, duration).await
 }
 
 #[cfg(feature = "daemon")]
@@ -627,6 +818,8 @@ pub(super) async fn end_history_entry(
 enum TailKind {
     Started,
     Ended,
+    /// Finished on another machine, and synced here
+    Synced,
 }
 
 #[cfg(feature = "daemon")]
@@ -676,32 +869,18 @@ impl TailEvent {
         let history = reply
             .history
             .ok_or_else(|| eyre::eyre!("daemon sent a history tail event without history"))?;
-        let timestamp = OffsetDateTime::from_unix_timestamp_nanos(i128::from(history.timestamp))
-            .context("invalid daemon history timestamp")?;
         let kind = match HistoryEventKind::try_from(reply.kind)
             .unwrap_or(HistoryEventKind::Unspecified)
         {
             HistoryEventKind::Started => TailKind::Started,
             HistoryEventKind::Ended => TailKind::Ended,
+            HistoryEventKind::Synced => TailKind::Synced,
             HistoryEventKind::Unspecified => bail!("daemon sent an unspecified history tail event"),
         };
 
         Ok(Self {
             kind,
-            history: History {
-                id: history.id.into(),
-                timestamp,
-                duration: history.duration,
-                exit: history.exit,
-                command: history.command,
-                cwd: history.cwd,
-                session: history.session,
-                hostname: history.hostname,
-                author: history.author,
-                intent: normalize_optional_string(history.intent),
-                shell: normalize_optional_string(history.shell),
-                deleted_at: None,
-            },
+            history: History::try_from(history)?,
         })
     }
 
@@ -749,8 +928,10 @@ impl TailEvent {
         let mut out = String::new();
         let border = match self.kind {
             TailKind::Started => "-".repeat(72).bright_blue().to_string(),
-            TailKind::Ended if self.history.exit == 0 => "-".repeat(72).bright_green().to_string(),
-            TailKind::Ended => "-".repeat(72).bright_red().to_string(),
+            TailKind::Ended | TailKind::Synced if self.history.exit == 0 => {
+                "-".repeat(72).bright_green().to_string()
+            }
+            TailKind::Ended | TailKind::Synced => "-".repeat(72).bright_red().to_string(),
         };
 
         out.push_str(&border);
@@ -822,15 +1003,15 @@ impl TailEvent {
     }
 
     fn exit_value(&self) -> Option<i64> {
-        matches!(self.kind, TailKind::Ended).then_some(self.history.exit)
+        self.kind.finished().then_some(self.history.exit)
     }
 
     fn duration_value(&self) -> Option<i64> {
-        matches!(self.kind, TailKind::Ended).then_some(self.history.duration)
+        self.kind.finished().then_some(self.history.duration)
     }
 
     fn success_value(&self) -> Option<bool> {
-        matches!(self.kind, TailKind::Ended).then_some(self.history.exit == 0)
+        self.kind.finished().then_some(self.history.exit == 0)
     }
 
     fn finished_at(&self) -> Option<OffsetDateTime> {
@@ -863,14 +1044,21 @@ impl TailKind {
         match self {
             Self::Started => "started",
             Self::Ended => "ended",
+            Self::Synced => "synced",
         }
     }
 
+    const fn finished(self) -> bool {
+        matches!(self, Self::Ended | Self::Synced)
+    }
+
     fn badge(self, exit: i64) -> colored::ColoredString {
         match self {
             Self::Started => "STARTED".bold().bright_blue(),
             Self::Ended if exit == 0 => "ENDED".bold().bright_green(),
             Self::Ended => "ENDED".bold().bright_red(),
+            Self::Synced if exit == 0 => "SYNCED".bold().bright_green(),
+            Self::Synced => "SYNCED".bold().bright_red(),
         }
     }
 }
@@ -914,23 +1102,120 @@ fn push_pretty_field(out: &mut String, label: &str, value: &str) {
     }
 }
 
+/// How `atuin history tail` prints each event.
+#[cfg(feature = "daemon")]
+enum TailOutput {
+    /// A block per event on a terminal, and a JSON line otherwise
+    Events { tty: bool },
+    /// A line per finished command, as `list` prints them
+    List {
+        mode: ListMode,
+        format: String,
+        short_id_len: usize,
+        hosts: HostNames,
+    },
+}
+
+#[cfg(feature = "daemon")]
+impl TailOutput {
+    /// Print `event`, returning false once stdout has been closed.
+    fn print(&self, event: &TailEvent, tz: Timezone) -> Result<bool> {
+        match self {
+            Self::Events { tty } => {
+                let rendered = event.render(*tty, tz)?;
+                let mut out = std::io::stdout().lock();
+
+                match out.write_all(rendered.as_bytes()) {
+                    Ok(()) => out.flush()?,
+                    Err(err) if err.kind() == io::ErrorKind::BrokenPipe => return Ok(false),
+                    Err(err) => return Err(err.into()),
+                }
+            }
+            Self::List {
+                mode,
+                format,
+                short_id_len,
+                hosts,
+            } => {
+                if event.kind.finished() {
+                    print_list(
+                        std::slice::from_ref(&event.history),
+                        *mode,
+                        Some(format),
+                        false,
+                        false,
+                        tz,
+                        *short_id_len,
+                        hosts,
+                    );
+                }
+            }
+        }
+
+        Ok(true)
+    }
+}
+
 impl Cmd {
     #[cfg(feature = "daemon")]
-    async fn handle_tail(settings: &Settings) -> Result<()> {
-        let tty = std::io::stdout().is_terminal();
-        let mut client = daemon::tail_client(settings).await?;
-        let mut stream = client.tail_history().await?;
-        let stdout = std::io::stdout();
+    async fn handle_tail(
+        settings: &Settings,
+        lines: usize,
+        follow: bool,
+        cmd_only: bool,
+        timezone: Option<Timezone>,
+        format: Option<String>,
+    ) -> Result<()> {
+        let tz = timezone.unwrap_or(settings.timezone);
+        let db = Sqlite::new(&settings.db_path, settings.local_timeout).await?;
+
+        // Subscribe before reading the backlog, so nothing falls between the two
+        let mut stream = if follow {
+            let mut client = daemon::tail_client(settings).await?;
+            Some(client.tail_history().await?)
+        } else {
+            None
+        };
+
+        // Only finished commands, oldest first
+        let mut recent = db.latest(None, i64::try_from(lines)?).await?;
+        recent.reverse();
+
+        // New commands are told apart by as much of their IDs as the backlog is
+        let output = if cmd_only || format.is_some() {
+            let format = format.unwrap_or_else(|| settings.history_format.clone());
+            let store =
+                SqliteStore::new(&settings.record_store_path, settings.local_timeout).await?;
+            TailOutput::List {
+                mode: ListMode::from_flags(false, cmd_only),
+                short_id_len: short_id_len(&db, &format, &recent).await?,
+                hosts: host_names(settings, &store, &format).await,
+                format,
+            }
+        } else {
+            TailOutput::Events {
+                tty: std::io::stdout().is_terminal(),
+            }
+        };
+
+        for history in recent {
+            let event = TailEvent {
+                kind: TailKind::Ended,
+                history,
+            };
+            if !output.print(&event, tz)? {
+                return Ok(());
+            }
+        }
+
+        let Some(stream) = stream.as_mut() else {
+            return Ok(());
+        };
 
         while let Some(reply) = stream.message().await? {
             let event = TailEvent::from_proto(reply)?;
-            let rendered = event.render(tty, settings.timezone)?;
-            let mut out = stdout.lock();
-
-            match out.write_all(rendered.as_bytes()) {
-                Ok(()) => out.flush()?,
-                Err(err) if err.kind() == io::ErrorKind::BrokenPipe => break,
-                Err(err) => return Err(err.into()),
+            if !output.print(&event, tz)? {
+                break;
             }
         }
 
@@ -943,6 +1228,7 @@ impl Cmd {
     async fn handle_list(
         db: &impl Database,
         settings: &Settings,
+        store: &SqliteStore,
         context: atuin_client::database::Context,
         session: bool,
         cwd: bool,
@@ -967,21 +1253,53 @@ impl Cmd {
             .list(&filters, &context, None, false, include_deleted, None)
             .await?;
 
+        let format = format.unwrap_or_else(|| settings.history_format.clone());
+        let short_id_len = short_id_len(db, &format, &history).await?;
+        let hosts = host_names(settings, store, &format).await;
+
         print_list(
             &history,
             mode,
-            match format {
-                None => Some(settings.history_format.as_str()),
-                _ => format.as_deref(),
-            },
+            Some(&format),
             print0,
             reverse,
             tz,
+            short_id_len,
+            &hosts,
         );
 
         Ok(())
     }
 
+    async fn handle_save(
+        db: &impl Database,
+        history_store: &HistoryStore,
+        settings: &Settings,
+        command: &str,
+    ) -> Result<()> {
+        if command.trim().is_empty() {
+            bail!("no command to save");
+        }
+
+        let mut h: History = History::capture()
+            .timestamp(OffsetDateTime::now_utc())
+            .command(command)
+            .cwd(utils::get_current_dir())
+            .shell_opt(std::env::var("ATUIN_SHELL").ok())
+            .build()
+            .into();
+        if !h.should_save(settings) {
+            bail!("not saving a command your history filters leave out");
+        }
+        h.exit = 0;
+        h.duration = 0;
+
+        db.save_with_origin(&h, Origin::current()).await?;
+        history_store.push(h).await?;
+
+        Ok(())
+    }
+
     async fn handle_prune(
         db: &impl Database,
         settings: &Settings,
@@ -1015,8 +1333,12 @@ impl Cmd {
                 false,
                 false,
                 settings.timezone,
+                short_id_len(db, &settings.history_format, &matches).await?,
+                &host_names(settings, &store, &settings.history_format).await,
             );
         } else {
+            settings.ensure_history_removable()?;
+
             let encryption_key: [u8; 32] = encryption::load_key(settings)
                 .context("could not load encryption key")?
                 .into();
@@ -1028,6 +1350,7 @@ impl Cmd {
                 let (id, _) = history_store.delete(entry.id.clone()).await?;
                 history_store.build_all(db, &[id]).await?;
             }
+            db.rebuild_bigrams().await?;
 
             #[cfg(feature = "daemon")]
             daemon_cmd::emit_event(settings, atuin_daemon::DaemonEvent::HistoryPruned).await;
@@ -1069,8 +1392,12 @@ impl Cmd {
                 false,
                 false,
                 settings.timezone,
+                short_id_len(db, &settings.history_format, &matches).await?,
+                &host_names(settings, &store, &settings.history_format).await,
             );
         } else {
+            settings.ensure_history_removable()?;
+
             let encryption_key: [u8; 32] = encryption::load_key(settings)
                 .context("could not load encryption key")?
                 .into();
@@ -1085,6 +1412,7 @@ impl Cmd {
                 let (id, _) = history_store.delete(entry.id).await?;
                 history_store.build_all(db, &[id]).await?;
             }
+            db.rebuild_bigrams().await?;
 
             #[cfg(feature = "daemon")]
             daemon_cmd::emit_event(settings, atuin_daemon::DaemonEvent::HistoryDeleted { ids })
@@ -1095,12 +1423,22 @@ impl Cmd {
 
     #[allow(clippy::too_many_lines)]
     pub async fn run(self, settings: &Settings) -> Result<()> {
+        if read_only::is_enabled() {
+            match &self {
+                // Shell hooks run these around every command, and mustn't fail them
+                Self::Start { .. } | Self::End { .. } => return Ok(()),
+                Self::Tail { .. } | Self::List { .. } | Self::Last { .. } => {}
+                _ => read_only::ensure_writable("change the history")?,
+            }
+        }
+
         match self {
             Self::Start {
                 cmd_env,
                 author,
                 intent,
                 command,
+                clock,
                 ..
             } => {
                 let command = if cmd_env {
@@ -1113,22 +1451,62 @@ impl Cmd {
                     start_history_entry(settings, &command, author.as_deref(), intent.as_deref())
                         .await?
                 {
-                    println!("{id}");
+                    match monotonic_clock().filter(|_| clock) {
+                        Some(now) => println!("{id} {now}"),
+                        None => println!("{id}"),
+                    }
                 }
 
                 Ok(())
             }
             Self::End {
-                id, exit, duration, ..
-            } => end_history_entry(settings, &id, exit, duration).await,
-            Self::Tail => {
+                id,
+                exit,
+                duration,
+                started_clock,
+                ..
+            } => {
+                let measured =
+                    started_clock.and_then(|started| monotonic_clock()?.checked_sub(started));
+                end_history_entry(settings, &id, exit, measured.or(duration)).await
+            }
+            Self::Tail {
+                lines,
+                follow,
+                cmd_only,
+                timezone,
+                format,
+            } => {
                 #[cfg(feature = "daemon")]
                 {
-                    return Self::handle_tail(settings).await;
+                    return Self::handle_tail(settings, lines, follow, cmd_only, timezone, format)
+                        .await;
                 }
 
                 #[cfg(not(feature = "daemon"))]
-                bail!("`atuin history tail` requires Atuin to be built with the `daemon` feature");
+                {
+                    let _ = (lines, follow, cmd_only, timezone, format);
+                    bail!(
+                        "`atuin history tail` requires Atuin to be built with the `daemon` feature"
+                    );
+                }
+            }
+            Self::Archive { older_than } => {
+                let archive = Archive {
+                    after: older_than.unwrap_or_else(|| settings.archive.after.clone()),
+                    ..settings.archive.clone()
+                };
+
+                match archive_history(settings, &archive).await? {
+                    Some(0) => println!("No history to archive"),
+                    Some(1) => println!("Archived 1 entry"),
+                    Some(n) => println!("Archived {n} entries"),
+                    None => bail!(
+                        "archiving is off. Set `archive.after` in your config, or pass --older-than"
+                    ),
+                }
+
+                Ok(())
             }
             cmd => {
                 let context = current_context().await?;
@@ -1160,31 +1538,32 @@ impl Cmd {
                         let mode = ListMode::from_flags(human, cmd_only);
                         let tz = timezone.unwrap_or(settings.timezone);
                         Self::handle_list(
-                            &db, settings, context, session, cwd, mode, format, false, print0,
-                            reverse, tz,
+                            &db, settings, &store, context, session, cwd, mode, format, false,
+                            print0, reverse, tz,
                         )
                         .await
                     }
 
                     Self::Last {
+                        n,
                         human,
                         cmd_only,
                         timezone,
                         format,
                     } => {
-                        let last = db.last().await?;
+                        let last = db.latest(None, i64::try_from(n)?).await?;
                         let last = last.as_slice();
                         let tz = timezone.unwrap_or(settings.timezone);
+                        let format = format.unwrap_or_else(|| settings.history_format.clone());
                         print_list(
                             last,
                             ListMode::from_flags(human, cmd_only),
-                            match format {
-                                None => Some(settings.history_format.as_str()),
-                                _ => format.as_deref(),
-                            },
+                            Some(&format),
                             false,
                             true,
                             tz,
+                            short_id_len(&db, &format, last).await?,
+                            &host_names(settings, &store, &format).await,
                         );
 
                         Ok(())
@@ -1192,6 +1571,10 @@ impl Cmd {
 
                     Self::InitStore => history_store.init_store(&db).await,
 
+                    Self::Save { command } => {
+                        Self::handle_save(&db, &history_store, settings, &command.join(" ")).await
+                    }
+
                     Self::Prune { dry_run } => {
                         Self::handle_prune(&db, settings, store, context, dry_run).await
                     }
@@ -1212,7 +1595,10 @@ impl Cmd {
                         Self::handle_dedup(&db, settings, store, before, dupkeep, dry_run).await
                     }
 
-                    Self::Start { .. } | Self::End { .. } | Self::Tail => unreachable!(),
+                    Self::Start { .. }
+                    | Self::End { .. }
+                    | Self::Tail { .. }
+                    | Self::Archive { .. } => unreachable!(),
                 }
             }
         }
@@ -1236,8 +1622,53 @@ mod tests {
     #[cfg(feature = "daemon")]
     use time::macros::datetime;
 
+    use atuin_client::history::HistoryId;
+
     use super::*;
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn resolve_history_id_accepts_unique_prefixes() {
+        let db = Sqlite::new("sqlite::memory:", 2.0).await.unwrap();
+
+        let mut ids = Vec::new();
+        for command in ["ls", "pwd"] {
+            let h: History = History::capture()
+                .timestamp(OffsetDateTime::now_utc())
+                .command(command)
+                .cwd("/")
+                .build()
+                .into();
+            db.save(&h).await.unwrap();
+            ids.push(h.id.0);
+        }
+
+        let saved: Vec<HistoryId> = ids.iter().cloned().map(HistoryId).collect();
+        let len = db
+            .unique_id_prefix_len(&saved, MIN_SHORT_ID_LEN)
+            .await
+            .unwrap();
+        assert_eq!(
+            resolve_history_id(&db, &ids[1][..len]).await.unwrap(),
+            ids[1]
+        );
+        assert_eq!(
+            resolve_history_id(&db, &ids[0][..len].to_uppercase())
+                .await
+                .unwrap(),
+            ids[0]
+        );
+        assert_eq!(resolve_history_id(&db, &ids[0]).await.unwrap(), ids[0]);
+
+        // Both were created in the same second, so share their leading characters.
+        let err = resolve_history_id(&db, &ids[0][..6]).await.unwrap_err();
+        assert!(err.to_string().contains("ambiguous"), "{err}");
+
+        let err = resolve_history_id(&db, "ffffffff").await.unwrap_err();
+        assert!(err.to_string().contains("no history entry"), "{err}");
+
+        assert!(resolve_history_id(&db, "ab").await.is_err());
+    }
+
     #[test]
     fn normalize_command_strips_trailing_spaces_and_tabs() {
         let settings = Settings::utc();
@@ -1318,6 +1749,15 @@ mod tests {
         assert!(stored.is_empty());
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn monotonic_clock_moves_forward() {
+        let start = monotonic_clock().unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        let elapsed = monotonic_clock().unwrap() - start;
+        assert!(elapsed >= 5_000_000, "{elapsed}ns");
+    }
+
     #[test]
     fn test_format_string_no_panic() {
         // Don't panic but provide helpful output (issue #2776)
@@ -1386,4 +1826,18 @@ mod tests {
         assert!(plain.contains("duration:"));
         assert!(plain.contains("running"));
     }
+
+    #[cfg(feature = "daemon")]
+    #[test]
+    fn test_tail_synced_events_are_finished() {
+        let json = sample_tail_event(TailKind::Synced)
+            .render(false, Timezone(time::UtcOffset::UTC))
+            .unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(value["event"], "synced");
+        assert_eq!(value["history"]["exit"], 0);
+        assert_eq!(value["history"]["duration_ns"], 12_345_678);
+        assert!(value["history"]["finished_at"].is_string());
+    }
 }
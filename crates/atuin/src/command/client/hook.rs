@@ -2,7 +2,7 @@ use std::io::Read;
 use std::path::PathBuf;
 
 use atuin_client::settings::Settings;
-use atuin_common::utils::home_dir;
+use atuin_common::paths::home_dir;
 use clap::{Parser, Subcommand};
 use eyre::{Result, bail};
 use serde_json::Value;
@@ -349,7 +349,7 @@ mod tests {
 
         assert!(matches!(
             cmd.atuin,
-            AtuinCmd::Client(client::Cmd::Hook(Cmd { action: None, agent: Some(agent) }))
+            Some(AtuinCmd::Client(client::Cmd::Hook(Cmd { action: None, agent: Some(agent) })))
                 if agent == "codex"
         ));
     }
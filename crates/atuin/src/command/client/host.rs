@@ -0,0 +1,114 @@
+use clap::Subcommand;
+use eyre::{Context, Result};
+
+use atuin_client::{
+    encryption,
+    host::{HostInfo, HostStore, find_host},
+    record::sqlite_store::SqliteStore,
+    settings::Settings,
+};
+
+#[derive(Subcommand, Debug)]
+#[command(infer_subcommands = true)]
+pub enum Cmd {
+    /// List the machines that share this record store
+    #[command(alias = "ls")]
+    List {
+        /// Print the hosts as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Give a machine a friendly name, shown instead of its hostname
+    Rename {
+        /// The machine to rename: its current name, hostname, or (a prefix of) its ID
+        host: String,
+
+        /// The new name. Leave out to go back to showing the hostname
+        name: Option<String>,
+    },
+}
+
+impl Cmd {
+    pub async fn run(self, settings: &Settings, store: SqliteStore) -> Result<()> {
+        let encryption_key: [u8; 32] = encryption::load_key(settings)
+            .context("could not load encryption key")?
+            .into();
+
+        let host_id = Settings::host_id().await?;
+        let host_store = HostStore::new(store, host_id, encryption_key);
+
+        // So this machine is listed with its details before it has synced them
+        host_store.register().await?;
+
+        match self {
+            Self::List { json } => {
+                let hosts = host_store.hosts().await?;
+
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&hosts)?);
+                } else {
+                    print_hosts(&hosts, host_id);
+                }
+
+                Ok(())
+            }
+
+            Self::Rename { host, name } => {
+                let hosts = host_store.hosts().await?;
+                let host = find_host(&hosts, &host)?;
+
+                host_store.rename(host.id, name.as_deref()).await?;
+
+                match name {
+                    Some(name) => println!("Renamed {} to {name}", host.display_name()),
+                    None => println!("Cleared the name of {}", host.display_name()),
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+fn print_hosts(hosts: &[HostInfo], current: atuin_common::record::HostId) {
+    let rows = hosts
+        .iter()
+        .map(|host| {
+            let details = host.details.as_ref();
+            [
+                host.id.0.as_simple().to_string()[..8].to_string(),
+                host.display_name(),
+                details.map_or_else(String::new, |d| d.hostname.clone()),
+                details.map_or_else(String::new, |d| d.os.clone()),
+                details.map_or_else(String::new, |d| d.shell.clone()),
+                if host.id == current {
+                    "<- CURRENT HOST".to_string()
+                } else {
+                    String::new()
+                },
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    let header = ["ID", "NAME", "HOSTNAME", "OS", "SHELL", ""].map(String::from);
+    let widths: Vec<usize> = (0..header.len())
+        .map(|col| {
+            std::iter::once(&header)
+                .chain(&rows)
+                .map(|row| row[col].chars().count())
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+
+    for row in std::iter::once(&header).chain(&rows) {
+        let line = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell:width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        println!("{}", line.trim_end());
+    }
+}
@@ -13,6 +13,7 @@ use atuin_client::{
         powershell::PowerShell, replxx::Replxx, resh::Resh, xonsh::Xonsh,
         xonsh_sqlite::XonshSqlite, zsh::Zsh, zsh_histdb::ZshHistDb,
     },
+    settings::Settings,
 };
 
 #[derive(Parser, Debug)]
@@ -49,7 +50,7 @@ const BATCH_SIZE: usize = 100;
 
 impl Cmd {
     #[allow(clippy::cognitive_complexity)]
-    pub async fn run<DB: Database>(&self, db: &DB) -> Result<()> {
+    pub async fn run<DB: Database>(&self, db: &DB, settings: &Settings) -> Result<()> {
         println!("        Atuin         ");
         println!("======================");
         println!("          \u{1f30d}          ");
@@ -123,7 +124,11 @@ impl Cmd {
             Self::Zsh => import::<Zsh, DB>(db).await,
             Self::ZshHistDb => import::<ZshHistDb, DB>(db).await,
             Self::Bash => import::<Bash, DB>(db).await,
-            Self::Replxx => import::<Replxx, DB>(db).await,
+            Self::Replxx => {
+                // replxx writes local times, read at the configured timezone
+                let replxx = Replxx::new().await?.local_offset(settings.timezone.0);
+                import_from(replxx, db).await
+            }
             Self::Resh => import::<Resh, DB>(db).await,
             Self::Fish => import::<Fish, DB>(db).await,
             Self::Nu => import::<Nu, DB>(db).await,
@@ -173,9 +178,12 @@ impl<DB: Database> Loader for HistoryImporter<'_, DB> {
 }
 
 async fn import<I: Importer + Send, DB: Database>(db: &DB) -> Result<()> {
+    import_from(I::new().await?, db).await
+}
+
+async fn import_from<I: Importer + Send, DB: Database>(mut importer: I, db: &DB) -> Result<()> {
     println!("Importing history from {}", I::NAME);
 
-    let mut importer = I::new().await?;
     let len = importer.entries().await.unwrap();
     let mut loader = HistoryImporter::new(db, len);
     importer.load(&mut loader).await?;
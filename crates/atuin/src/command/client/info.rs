@@ -3,7 +3,7 @@ use atuin_client::settings::Settings;
 use crate::{SHA, VERSION};
 
 pub fn run(settings: &Settings) {
-    let config = atuin_common::utils::config_dir();
+    let config = atuin_common::paths::config_dir();
     let mut config_file = config.clone();
     config_file.push("config.toml");
     let mut sever_config = config;
@@ -9,6 +9,7 @@ use eyre::{Result, WrapErr};
 
 mod bash;
 mod fish;
+mod home_manager;
 mod nu;
 mod powershell;
 mod xonsh;
@@ -16,7 +17,8 @@ mod zsh;
 
 #[derive(Parser, Debug)]
 pub struct Cmd {
-    shell: Shell,
+    #[clap(required_unless_present = "print_home_manager")]
+    shell: Option<Shell>,
 
     /// Disable the binding of CTRL-R to atuin
     #[clap(long)]
@@ -29,6 +31,11 @@ pub struct Cmd {
     /// Disable the binding of ? to Atuin AI
     #[clap(long)]
     disable_ai: bool,
+
+    /// Print a home-manager module with the settings in your config.toml, and the flags given
+    /// here, rather than the shell setup
+    #[clap(long)]
+    print_home_manager: bool,
 }
 
 #[derive(Clone, Copy, ValueEnum, Debug)]
@@ -58,10 +65,10 @@ struct StaticInitOptions<'a> {
 }
 
 impl Cmd {
-    fn static_init(&self, settings: &Settings) {
+    fn static_init(&self, shell: Shell, settings: &Settings) {
         let options = self.to_options(settings);
 
-        match self.shell {
+        match shell {
             Shell::Zsh => {
                 zsh::init_static(&options);
             }
@@ -83,7 +90,7 @@ impl Cmd {
         }
     }
 
-    async fn dotfiles_init(&self, settings: &Settings) -> Result<()> {
+    async fn dotfiles_init(&self, shell: Shell, settings: &Settings) -> Result<()> {
         let record_store_path = &settings.record_store_path;
         let sqlite_store = SqliteStore::new(record_store_path, settings.local_timeout).await?;
 
@@ -97,7 +104,7 @@ impl Cmd {
 
         let options = self.to_options(settings);
 
-        match self.shell {
+        match shell {
             Shell::Zsh => {
                 zsh::init(alias_store, var_store, &options).await?;
             }
@@ -128,7 +135,40 @@ impl Cmd {
         }
     }
 
+    fn print_home_manager(&self) -> Result<()> {
+        let config_file = Settings::get_config_path()?;
+        let config = match std::fs::read_to_string(&config_file) {
+            Ok(config) => config
+                .parse()
+                .wrap_err_with(|| format!("could not parse {}", config_file.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => toml_edit::DocumentMut::new(),
+            Err(e) => {
+                return Err(e)
+                    .wrap_err_with(|| format!("could not read {}", config_file.display()));
+            }
+        };
+
+        let flags = [
+            (self.disable_ctrl_r, "--disable-ctrl-r"),
+            (self.disable_up_arrow, "--disable-up-arrow"),
+            (self.disable_ai, "--disable-ai"),
+        ]
+        .into_iter()
+        .filter_map(|(set, flag)| set.then_some(flag))
+        .collect::<Vec<_>>();
+
+        print!("{}", home_manager::module(&config, &flags));
+        Ok(())
+    }
+
     pub async fn run(self, settings: &Settings) -> Result<()> {
+        if self.print_home_manager {
+            return self.print_home_manager();
+        }
+        let Some(shell) = self.shell else {
+            eyre::bail!("a shell to set up is required");
+        };
+
         if !settings.paths_ok() {
             eprintln!(
                 "Atuin settings paths are broken. Disabling atuin shell hooks. Run `atuin doctor` to diagnose."
@@ -137,9 +177,9 @@ impl Cmd {
         }
 
         if settings.dotfiles.enabled {
-            self.dotfiles_init(settings).await?;
+            self.dotfiles_init(shell, settings).await?;
         } else {
-            self.static_init(settings);
+            self.static_init(shell, settings);
         }
 
         Ok(())
@@ -0,0 +1,242 @@
+//! A home-manager module for the settings in config.toml, so they can be managed declaratively.
+//!
+//! home-manager's `programs.atuin.settings` is written out as config.toml again, so only what's
+//! set in the file is carried over, rather than every default, which would pin them.
+
+use std::fmt::Write as _;
+
+use toml_edit::{DocumentMut, Item, TableLike, Value};
+
+const INDENT: &str = "  ";
+
+/// Words Nix reserves, which can only be attribute names in quotes.
+const KEYWORDS: [&str; 10] = [
+    "assert", "else", "if", "in", "inherit", "let", "or", "rec", "then", "with",
+];
+
+/// A `programs.atuin` module with `config` as its settings and `flags` passed to `atuin init`.
+pub fn module(config: &DocumentMut, flags: &[&str]) -> String {
+    let mut out = String::new();
+    out.push_str("# Generated by `atuin init --print-home-manager`. home-manager writes\n");
+    out.push_str("# config.toml from `settings`, so move your own out of the way first.\n");
+    out.push_str("{\n");
+    out.push_str("  programs.atuin = {\n");
+    out.push_str("    enable = true;\n");
+
+    if !flags.is_empty() {
+        let flags: Vec<String> = flags.iter().map(|flag| string(flag)).collect();
+        let _ = writeln!(out, "    flags = [ {} ];", flags.join(" "));
+    }
+
+    // The module runs the daemon as a service, and turns it on in the settings itself
+    let daemon = config
+        .get("daemon")
+        .and_then(|daemon| daemon.get("enabled"))
+        .and_then(Item::as_bool)
+        == Some(true);
+    if daemon {
+        out.push_str("    daemon.enable = true;\n");
+    }
+
+    if !config.is_empty() {
+        out.push_str("    settings = ");
+        attrs(&mut out, config.as_table(), 2);
+        out.push_str(";\n");
+    }
+
+    out.push_str("  };\n");
+    out.push_str("}\n");
+    out
+}
+
+fn attrs(out: &mut String, table: &dyn TableLike, depth: usize) {
+    let entries: Vec<(&str, &Item)> = table.iter().filter(|(_, item)| !item.is_none()).collect();
+    if entries.is_empty() {
+        out.push_str("{ }");
+        return;
+    }
+
+    out.push_str("{\n");
+    for (key, item) in entries {
+        out.push_str(&INDENT.repeat(depth + 1));
+        out.push_str(&attr_name(key));
+        out.push_str(" = ");
+        self::item(out, item, depth + 1);
+        out.push_str(";\n");
+    }
+    out.push_str(&INDENT.repeat(depth));
+    out.push('}');
+}
+
+fn item(out: &mut String, item: &Item, depth: usize) {
+    match item {
+        Item::None => out.push_str("null"),
+        Item::Value(v) => value(out, v, depth),
+        Item::Table(table) => attrs(out, table, depth),
+        Item::ArrayOfTables(tables) => {
+            out.push_str("[\n");
+            for table in tables {
+                out.push_str(&INDENT.repeat(depth + 1));
+                attrs(out, table, depth + 1);
+                out.push('\n');
+            }
+            out.push_str(&INDENT.repeat(depth));
+            out.push(']');
+        }
+    }
+}
+
+fn value(out: &mut String, v: &Value, depth: usize) {
+    match v {
+        Value::String(s) => out.push_str(&string(s.value())),
+        Value::Integer(i) => {
+            let _ = write!(out, "{}", i.value());
+        }
+        // Nix has no infinity or NaN, and reads a float without a point as an integer
+        Value::Float(f) if f.value().is_finite() => {
+            let _ = write!(out, "{:?}", f.value());
+        }
+        Value::Float(f) => out.push_str(&string(&f.value().to_string())),
+        Value::Boolean(b) => {
+            let _ = write!(out, "{}", b.value());
+        }
+        Value::Datetime(d) => out.push_str(&string(&d.value().to_string())),
+        Value::Array(array) => {
+            if array.iter().all(|v| !v.is_inline_table() && !v.is_array()) {
+                out.push('[');
+                for v in array {
+                    out.push(' ');
+                    value(out, v, depth);
+                }
+                out.push_str(" ]");
+            } else {
+                out.push_str("[\n");
+                for v in array {
+                    out.push_str(&INDENT.repeat(depth + 1));
+                    value(out, v, depth + 1);
+                    out.push('\n');
+                }
+                out.push_str(&INDENT.repeat(depth));
+                out.push(']');
+            }
+        }
+        Value::InlineTable(table) => attrs(out, table, depth),
+    }
+}
+
+/// `s` as a Nix string, where `${` would otherwise start an interpolation.
+fn string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            '$' if chars.peek() == Some(&'{') => quoted.push_str("\\$"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn attr_name(key: &str) -> String {
+    let mut chars = key.chars();
+    let identifier = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '\''))
+        && !KEYWORDS.contains(&key);
+
+    if identifier {
+        key.to_string()
+    } else {
+        string(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_become_nix_attrs() {
+        let config: DocumentMut = r#"
+auto_sync = true
+sync_frequency = "10m"
+search_mode = "fuzzy"
+inline_height = 20
+frequency_score_multiplier = 1.0
+history_filter = ["^secret", "^ls$"]
+
+[sync]
+records = true
+
+[keys]
+"ctrl-r" = "cycle-filter-mode"
+
+[[dotfiles.aliases]]
+name = "ll"
+"#
+        .parse()
+        .unwrap();
+
+        assert_eq!(
+            module(&config, &["--disable-up-arrow"]),
+            r#"# Generated by `atuin init --print-home-manager`. home-manager writes
+# config.toml from `settings`, so move your own out of the way first.
+{
+  programs.atuin = {
+    enable = true;
+    flags = [ "--disable-up-arrow" ];
+    settings = {
+      auto_sync = true;
+      sync_frequency = "10m";
+      search_mode = "fuzzy";
+      inline_height = 20;
+      frequency_score_multiplier = 1.0;
+      history_filter = [ "^secret" "^ls$" ];
+      sync = {
+        records = true;
+      };
+      keys = {
+        ctrl-r = "cycle-filter-mode";
+      };
+      dotfiles = {
+        aliases = [
+          {
+            name = "ll";
+          }
+        ];
+      };
+    };
+  };
+}
+"#
+        );
+    }
+
+    #[test]
+    fn the_daemon_runs_as_a_service() {
+        let config: DocumentMut = "[daemon]\nenabled = true\n".parse().unwrap();
+
+        assert!(module(&config, &[]).contains("    daemon.enable = true;\n"));
+    }
+
+    #[test]
+    fn strings_and_names_are_quoted_for_nix() {
+        assert_eq!(string("echo ${HOME} $PATH"), r#""echo \${HOME} $PATH""#);
+        assert_eq!(string("a \"b\"\\n"), r#""a \"b\"\\n""#);
+        assert_eq!(string("two\nlines"), r#""two\nlines""#);
+
+        assert_eq!(attr_name("search_mode"), "search_mode");
+        assert_eq!(attr_name("ctrl-r"), "ctrl-r");
+        assert_eq!(attr_name("in"), r#""in""#);
+        assert_eq!(attr_name("1password"), r#""1password""#);
+        assert_eq!(attr_name("a.b"), r#""a.b""#);
+    }
+}
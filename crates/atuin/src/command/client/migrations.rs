@@ -0,0 +1,169 @@
+use std::path::Path;
+use std::time::Duration;
+
+use clap::Subcommand;
+use eyre::{Result, bail};
+use time::macros::format_description;
+
+use atuin_client::{
+    database::{
+        Sqlite,
+        migrations::{self, MigrationState, MigrationStatus},
+    },
+    settings::Settings,
+};
+
+#[derive(Subcommand, Debug)]
+#[command(infer_subcommands = true)]
+pub enum Cmd {
+    /// List the history database's schema migrations, and when each was applied
+    Status,
+
+    /// Undo the last migration by restoring the backup taken before it. For developing
+    /// migrations: anything recorded since the backup is lost
+    RevertLast {
+        /// Revert without asking for confirmation
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+impl Cmd {
+    pub async fn run(self, settings: &Settings) -> Result<()> {
+        let path = settings.db_path.as_path();
+
+        match self {
+            Self::Status => {
+                // Opened as-is, so this shows what needs applying rather than applying it
+                let db = Sqlite::connect(path, settings.local_timeout).await?;
+
+                print_status(settings, &migrations::status(&db.pool).await?);
+                print_backups(path)
+            }
+
+            Self::RevertLast { force } => {
+                settings.ensure_history_removable()?;
+
+                if daemon_running(settings) {
+                    bail!(
+                        "the daemon has the history database open; stop it with `atuin daemon stop` first"
+                    );
+                }
+
+                let timeout = Duration::from_secs_f64(settings.local_timeout);
+                if !force && !confirm_revert(migrations::revert_last_loses(path, timeout).await?) {
+                    println!("Revert cancelled");
+                    return Ok(());
+                }
+
+                let reverted = migrations::revert_last(path, timeout).await?;
+                println!(
+                    "Reverted {} {}. Running atuin will apply it again\n",
+                    reverted.version, reverted.description
+                );
+
+                let db = Sqlite::connect(path, settings.local_timeout).await?;
+                print_status(settings, &migrations::status(&db.pool).await?);
+                print_backups(path)
+            }
+        }
+    }
+}
+
+/// Whether the daemon is listening, and so may have the history database open
+fn daemon_running(settings: &Settings) -> bool {
+    #[cfg(unix)]
+    {
+        std::os::unix::net::UnixStream::connect(&settings.daemon.socket_path).is_ok()
+    }
+
+    #[cfg(not(unix))]
+    {
+        std::net::TcpStream::connect(("127.0.0.1", settings.daemon.tcp_port)).is_ok()
+    }
+}
+
+fn confirm_revert(lost: u64) -> bool {
+    let entries = if lost == 1 { "entry" } else { "entries" };
+    println!(
+        "This replaces the history database with the backup from before its last migration.\n\
+         The {lost} history {entries} recorded since then will be lost, unless they are in the \
+         record store (`atuin store rebuild history`).\n\
+         Are you sure? [y/N]"
+    );
+
+    let mut input = String::new();
+    if std::io::stdin().read_line(&mut input).is_err() {
+        return false;
+    }
+
+    let input = input.trim().to_lowercase();
+    input == "y" || input == "yes"
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn print_status(settings: &Settings, status: &[MigrationStatus]) {
+    let format = format_description!("[year]-[month]-[day] [hour]:[minute]:[second]");
+
+    let rows = status
+        .iter()
+        .map(|migration| {
+            [
+                migration.version.to_string(),
+                migration.description.clone(),
+                migration.state.as_str().to_string(),
+                migration
+                    .installed_on
+                    .and_then(|at| at.to_offset(settings.timezone.0).format(format).ok())
+                    .unwrap_or_default(),
+                migration
+                    .execution_time
+                    .map(|ns| format!("{:.1}ms", ns as f64 / 1_000_000.0))
+                    .unwrap_or_default(),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    let header = ["VERSION", "DESCRIPTION", "STATE", "APPLIED", "TOOK"].map(String::from);
+    let widths: Vec<usize> = (0..header.len())
+        .map(|col| {
+            std::iter::once(&header)
+                .chain(&rows)
+                .map(|row| row[col].chars().count())
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+
+    for row in std::iter::once(&header).chain(&rows) {
+        let line = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell:width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        println!("{}", line.trim_end());
+    }
+
+    let pending = status
+        .iter()
+        .filter(|m| m.state == MigrationState::Pending)
+        .count();
+    if pending > 0 {
+        println!("\n{pending} pending, applied the next time atuin opens the database");
+    }
+}
+
+fn print_backups(path: &Path) -> Result<()> {
+    let backups = migrations::backups(path)?;
+    if backups.is_empty() {
+        return Ok(());
+    }
+
+    println!("\nBackups taken before migrating:");
+    for backup in backups {
+        println!("  {} (at {})", backup.path.display(), backup.version);
+    }
+
+    Ok(())
+}
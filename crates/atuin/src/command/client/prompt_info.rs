@@ -0,0 +1,133 @@
+use eyre::Result;
+use serde::Serialize;
+use time::OffsetDateTime;
+
+use atuin_client::{prompt, settings::Settings};
+
+#[cfg(feature = "daemon")]
+use super::daemon;
+
+/// What a shell prompt shows about Atuin, printed as one line of JSON.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+struct PromptInfo {
+    /// Exit code of the last command in this session
+    last_exit: Option<i64>,
+    /// How long the last command in this session ran for
+    last_duration_ms: Option<i64>,
+    /// Seconds since the last successful sync, or null if never synced
+    sync_age_secs: Option<i64>,
+    daemon: DaemonStatus,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(not(feature = "daemon"), allow(dead_code))]
+enum DaemonStatus {
+    Running,
+    Stopped,
+    Disabled,
+}
+
+impl PromptInfo {
+    /// `last` is the exit code and duration in nanoseconds of the last command.
+    fn new(
+        last: Option<(i64, i64)>,
+        last_sync: Option<OffsetDateTime>,
+        now: OffsetDateTime,
+        daemon: DaemonStatus,
+    ) -> Self {
+        Self {
+            last_exit: last.map(|(exit, _)| exit),
+            last_duration_ms: last.map(|(_, duration)| duration.max(0) / 1_000_000),
+            sync_age_secs: last_sync
+                .filter(|t| *t > OffsetDateTime::UNIX_EPOCH)
+                .map(|t| (now - t).whole_seconds().max(0)),
+            daemon,
+        }
+    }
+}
+
+pub async fn run(settings: &Settings) -> Result<()> {
+    let session = std::env::var("ATUIN_SESSION").unwrap_or_default();
+    let info = gather(settings, session).await?;
+
+    println!("{}", serde_json::to_string(&info)?);
+
+    Ok(())
+}
+
+async fn gather(settings: &Settings, session: String) -> Result<PromptInfo> {
+    let now = OffsetDateTime::now_utc();
+
+    #[cfg(feature = "daemon")]
+    let daemon_status = if settings.daemon.enabled {
+        match daemon::prompt_info(settings, session.clone()).await {
+            Ok(reply) => {
+                let last = reply
+                    .has_last
+                    .then_some((reply.last_exit, reply.last_duration));
+                let last_sync = OffsetDateTime::from_unix_timestamp(reply.last_sync).ok();
+
+                return Ok(PromptInfo::new(last, last_sync, now, DaemonStatus::Running));
+            }
+            Err(e) => {
+                tracing::debug!(?e, "daemon unavailable, reading prompt info locally");
+                DaemonStatus::Stopped
+            }
+        }
+    } else {
+        DaemonStatus::Disabled
+    };
+
+    #[cfg(not(feature = "daemon"))]
+    let daemon_status = DaemonStatus::Disabled;
+
+    let session = (!session.is_empty()).then_some(session.as_str());
+    let last = prompt::last_command(settings, session).await?.map(|last| {
+        let duration = i64::try_from(last.duration.whole_nanoseconds()).unwrap_or(i64::MAX);
+        (last.exit, duration)
+    });
+
+    let last_sync = prompt::last_sync(settings).await.ok().flatten();
+
+    Ok(PromptInfo::new(last, last_sync, now, daemon_status))
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn serializes_compactly() {
+        let now = datetime!(2026-10-15 12:00 UTC);
+        let info = PromptInfo::new(
+            Some((1, 2_500_000_000)),
+            Some(datetime!(2026-10-15 11:58 UTC)),
+            now,
+            DaemonStatus::Running,
+        );
+
+        assert_eq!(
+            serde_json::to_string(&info).unwrap(),
+            r#"{"last_exit":1,"last_duration_ms":2500,"sync_age_secs":120,"daemon":"running"}"#
+        );
+    }
+
+    #[test]
+    fn never_synced_and_no_history_are_null() {
+        let now = datetime!(2026-10-15 12:00 UTC);
+        let info = PromptInfo::new(
+            None,
+            Some(OffsetDateTime::UNIX_EPOCH),
+            now,
+            DaemonStatus::Disabled,
+        );
+
+        assert_eq!(
+            serde_json::to_string(&info).unwrap(),
+            r#"{"last_exit":null,"last_duration_ms":null,"sync_age_secs":null,"daemon":"disabled"}"#
+        );
+    }
+}
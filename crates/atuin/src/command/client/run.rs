@@ -0,0 +1,295 @@
+#[cfg(unix)]
+use std::io::IsTerminal;
+use std::{
+    io,
+    process::{ExitStatus, Stdio},
+    sync::Mutex,
+    time::Instant,
+};
+
+use clap::Parser;
+use eyre::{Result, WrapErr};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    process::{Child, ChildStderr, ChildStdout, Command},
+};
+
+use atuin_client::{
+    database::{Database, Sqlite},
+    history::{HistoryId, output::OutputBuffer},
+    settings::Settings,
+};
+
+use super::history::{end_history_entry, start_history_entry};
+
+/// Run a command, saving what it prints alongside its history entry.
+///
+/// Output is still shown as it arrives. Run from a shell with Atuin's hooks
+/// the output is attached to the entry for this `atuin run` line; otherwise
+/// the command gets an entry of its own.
+#[derive(Parser, Debug)]
+pub struct Cmd {
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true, required = true)]
+    command: Vec<String>,
+}
+
+impl Cmd {
+    pub async fn run(self, settings: &Settings) -> Result<()> {
+        let (mut child, output) = spawn(&self.command)?;
+
+        // Ctrl-C reaches the command too; stay alive long enough to save
+        // whatever it printed before it stopped.
+        #[cfg(unix)]
+        let _interrupt = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::interrupt())?;
+
+        let hooked = std::env::var("ATUIN_HISTORY_ID")
+            .ok()
+            .filter(|id| !id.is_empty());
+
+        let own = if hooked.is_none() {
+            let command = shlex::try_join(self.command.iter().map(String::as_str))
+                .unwrap_or_else(|_| self.command.join(" "));
+            start_history_entry(settings, &command, None, None).await?
+        } else {
+            None
+        };
+
+        let start = Instant::now();
+        let buffer = Mutex::new(OutputBuffer::new(settings.output.max_bytes));
+
+        let (copied, status) = tokio::join!(output.tee(&buffer), child.wait());
+        copied?;
+        let exit = exit_code(status?);
+
+        if let Some(id) = &own {
+            let duration = u64::try_from(start.elapsed().as_nanos()).unwrap_or(u64::MAX);
+            end_history_entry(settings, id, exit, Some(duration)).await?;
+        }
+
+        // Filtered out of history (or the command was), so there's nothing
+        // to attach the output to.
+        if let Some(id) = hooked.or(own) {
+            let output = buffer.into_inner().expect("no other holders").finish();
+            let db = Sqlite::new(&settings.db_path, settings.local_timeout).await?;
+            db.save_output(&HistoryId(id), &output, settings.output.search_max_bytes)
+                .await?;
+        }
+
+        if exit != 0 {
+            std::process::exit(i32::try_from(exit).unwrap_or(1));
+        }
+
+        Ok(())
+    }
+}
+
+/// Where the command's output is read from.
+enum Output {
+    /// Its stdout and stderr, each passed on to ours
+    Pipes(ChildStdout, ChildStderr),
+    /// A terminal of our own that both go to, so the command still finds itself on one, and
+    /// what it writes to each stays in the order it wrote it
+    #[cfg(unix)]
+    Pty(Pty),
+}
+
+impl Output {
+    /// Pass the output on as it arrives, keeping a copy in `buffer`.
+    async fn tee(self, buffer: &Mutex<OutputBuffer>) -> Result<()> {
+        match self {
+            Self::Pipes(stdout, stderr) => {
+                let (stdout, stderr) = tokio::join!(
+                    tee(stdout, tokio::io::stdout(), buffer),
+                    tee(stderr, tokio::io::stderr(), buffer),
+                );
+                stdout?;
+                stderr?;
+                Ok(())
+            }
+            #[cfg(unix)]
+            Self::Pty(pty) => pty.tee(buffer).await,
+        }
+    }
+}
+
+/// Start `command`. On a terminal, it runs on a pty of its own; otherwise, or where its
+/// stdout and stderr go different ways, on pipes kept as far apart as they were given.
+/// Input is ours either way, so it reads from the terminal as usual.
+fn spawn(command: &[String]) -> Result<(Child, Output)> {
+    let mut cmd = Command::new(&command[0]);
+    cmd.args(&command[1..]).stdin(Stdio::inherit());
+
+    #[cfg(unix)]
+    if io::stdout().is_terminal() && io::stderr().is_terminal() {
+        let (pty, slave) = Pty::open().wrap_err("could not open a pty")?;
+        let child = cmd
+            .stdout(slave.try_clone()?)
+            .stderr(slave)
+            .spawn()
+            .wrap_err_with(|| format!("could not run {}", command[0]))?;
+
+        // Our copies of its end close with `cmd`, so reading ends once the command's do
+        return Ok((child, Output::Pty(pty)));
+    }
+
+    let mut child = cmd
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .wrap_err_with(|| format!("could not run {}", command[0]))?;
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let stderr = child.stderr.take().expect("stderr is piped");
+
+    Ok((child, Output::Pipes(stdout, stderr)))
+}
+
+/// The end of a pty we read what the command writes from.
+#[cfg(unix)]
+struct Pty {
+    master: std::fs::File,
+}
+
+#[cfg(unix)]
+impl Pty {
+    /// A new pty the size of our terminal, and the end of it the command writes to.
+    fn open() -> Result<(Self, std::os::fd::OwnedFd)> {
+        use rustix::{
+            fs::{Mode, OFlags},
+            io::FdFlags,
+            pty::{self, OpenptFlags},
+            termios::{self, OptionalActions, OutputModes},
+        };
+
+        let master = pty::openpt(OpenptFlags::RDWR | OpenptFlags::NOCTTY)?;
+        rustix::io::fcntl_setfd(&master, FdFlags::CLOEXEC)?;
+        pty::grantpt(&master)?;
+        pty::unlockpt(&master)?;
+        let slave = rustix::fs::open(
+            pty::ptsname(&master, Vec::new())?,
+            OFlags::RDWR | OFlags::NOCTTY | OFlags::CLOEXEC,
+            Mode::empty(),
+        )?;
+
+        // Kept as written, to be translated by our own terminal as the command's output
+        // would have been
+        let mut modes = termios::tcgetattr(&slave)?;
+        modes.output_modes.remove(OutputModes::OPOST);
+        termios::tcsetattr(&slave, OptionalActions::Now, &modes)?;
+
+        follow_size(&master);
+
+        Ok((
+            Self {
+                master: master.into(),
+            },
+            slave,
+        ))
+    }
+
+    /// Pass on what the command writes, keeping a copy in `buffer`, and keep the pty the
+    /// size of our terminal meanwhile.
+    async fn tee(self, buffer: &Mutex<OutputBuffer>) -> Result<()> {
+        use tokio::signal::unix::{SignalKind, signal};
+
+        let resizing = self.master.try_clone()?;
+        let mut resized = signal(SignalKind::window_change())?;
+
+        let copied = tee(
+            tokio::fs::File::from_std(self.master),
+            tokio::io::stdout(),
+            buffer,
+        );
+        tokio::pin!(copied);
+        let copied = loop {
+            tokio::select! {
+                copied = &mut copied => break copied,
+                Some(()) = resized.recv() => follow_size(&resizing),
+            }
+        };
+
+        match copied {
+            // What reading a pty gives once everything writing to it has closed it
+            Err(e) if e.raw_os_error() == Some(rustix::io::Errno::IO.raw_os_error()) => Ok(()),
+            copied => Ok(copied?),
+        }
+    }
+}
+
+/// Make the pty `master` is an end of the size of our terminal, as the command would have
+/// found that.
+#[cfg(unix)]
+fn follow_size(master: &impl std::os::fd::AsFd) {
+    if let Ok(size) = rustix::termios::tcgetwinsize(io::stdout()) {
+        let _ = rustix::termios::tcsetwinsize(master, size);
+    }
+}
+
+/// Copy `from` to `to` as it arrives, keeping a copy in `buffer`.
+async fn tee(
+    mut from: impl AsyncRead + Unpin,
+    mut to: impl AsyncWrite + Unpin,
+    buffer: &Mutex<OutputBuffer>,
+) -> io::Result<()> {
+    let mut chunk = vec![0; 8192];
+
+    loop {
+        let n = from.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok(());
+        }
+
+        buffer.lock().expect("not poisoned").push(&chunk[..n]);
+
+        // Whoever reads our output may have gone away (`| head`); keep
+        // draining the command so it isn't blocked on a full pipe.
+        if to.write_all(&chunk[..n]).await.is_ok() {
+            let _ = to.flush().await;
+        }
+    }
+}
+
+/// The exit code a shell would report, including 128 + N for signal N.
+fn exit_code(status: ExitStatus) -> i64 {
+    if let Some(code) = status.code() {
+        return i64::from(code);
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+
+        if let Some(signal) = status.signal() {
+            return 128 + i64::from(signal);
+        }
+    }
+
+    1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn tee_copies_and_captures() {
+        let buffer = Mutex::new(OutputBuffer::new(1024));
+        let mut out = Vec::new();
+
+        tee(&b"hello\nworld\n"[..], &mut out, &buffer)
+            .await
+            .unwrap();
+
+        assert_eq!(out, b"hello\nworld\n");
+        assert_eq!(buffer.into_inner().unwrap().finish().text, "hello\nworld\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn signals_exit_like_a_shell() {
+        use std::os::unix::process::ExitStatusExt;
+
+        assert_eq!(exit_code(ExitStatus::from_raw(2 << 8)), 2);
+        // Killed by SIGINT.
+        assert_eq!(exit_code(ExitStatus::from_raw(2)), 130);
+    }
+}
@@ -1,13 +1,15 @@
 use std::fs::File;
-use std::io::{IsTerminal as _, Write, stderr, stdout};
+use std::io::{IsTerminal as _, Write, stderr, stdin, stdout};
 
 use atuin_common::{string::EscapeNonPrintablePosixExt as _, utils};
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use eyre::Result;
+use time::{OffsetDateTime, format_description::well_known::Rfc3339};
 
 use atuin_client::{
     database::Database,
     database::{OptFilters, current_context},
+    date::parse_date,
     encryption,
     history::{History, store::HistoryStore},
     record::sqlite_store::SqliteStore,
@@ -18,17 +20,39 @@ use atuin_client::{
 use super::history::ListMode;
 
 mod block_ext;
+mod clipboard;
 mod cursor;
+mod date_jump;
 mod duration;
+mod editor;
 mod engines;
+mod everything;
+mod file_paths;
 mod history_list;
 mod inspector;
 mod interactive;
 pub mod keybindings;
+mod list_picker;
+mod open_with;
+mod picker;
+mod placeholders;
+mod query_task;
+mod result_cache;
 mod selection_ext;
+mod session_picker;
+#[cfg(test)]
+mod snapshot;
+mod sources;
 mod syntax;
+mod tasks;
+mod template;
+mod theme_picker;
+mod urls;
 
-pub use duration::format_duration_into;
+pub use duration::{format_duration, format_duration_into};
+pub use file_paths::find as find_paths;
+pub use history_list::one_line;
+use picker::PickerFormat;
 
 #[allow(clippy::struct_excessive_bools, clippy::struct_field_names)]
 #[derive(Parser, Debug)]
@@ -57,6 +81,11 @@ pub struct Cmd {
     #[arg(long)]
     after: Option<String>,
 
+    /// Search history as it was at this date: only results from before it are included, and
+    /// relative `--before`/`--after` dates are measured from it
+    #[arg(long)]
+    at: Option<String>,
+
     /// How many entries to return at most
     #[arg(long)]
     limit: Option<i64>,
@@ -65,9 +94,18 @@ pub struct Cmd {
     #[arg(long)]
     offset: Option<i64>,
 
-    /// Open interactive search UI
-    #[arg(long, short)]
-    interactive: bool,
+    /// Open interactive search UI. On its own, falls back to listing matches when there's no
+    /// terminal to draw it on
+    #[arg(
+        long,
+        short,
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_value = "never",
+        default_missing_value = "auto"
+    )]
+    interactive: Interactive,
 
     /// Allow overriding filter mode over config
     #[arg(long)]
@@ -126,8 +164,8 @@ pub struct Cmd {
     #[arg(allow_hyphen_values = true, num_args = 0..=1)]
     timezone: Option<Timezone>,
 
-    /// Available variables: {command}, {directory}, {duration}, {user}, {host}, {time}, {exit} and
-    /// {relativetime}.
+    /// Available variables: {command}, {directory}, {duration}, {user}, {host}, {time}, {exit},
+    /// {shortid} and {relativetime}.
     ///
     /// Example: --format "{time} - [{duration}] - {directory}$\t{command}"
     #[arg(long, short)]
@@ -147,10 +185,19 @@ pub struct Cmd {
     #[arg(long)]
     include_duplicates: bool,
 
+    /// Search archived history too. In the interactive search, toggle it with `toggle-archive`
+    #[arg(long)]
+    include_archive: bool,
+
     /// File name to write the result to (hidden from help as this is meant to be used from a script)
     #[arg(long, hide = true)]
     result_file: Option<String>,
 
+    /// Wrap a multi-line result in bracketed paste markers, so a tool that types it into a
+    /// terminal hands it to the shell as one paste instead of running it line by line
+    #[arg(long)]
+    bracketed_paste: bool,
+
     /// Filter by the shell that was used to run the command
     ///
     /// If passed multiple times, commands from any of the shells will be shown.
@@ -158,12 +205,70 @@ pub struct Cmd {
     /// `--shell ""` will include commands for which the shell is unknown.
     #[arg(long)]
     shell: Vec<String>,
+
+    /// Filter by the machine the command was run on, by the host part of its hostname
+    ///
+    /// If passed multiple times, commands from any of the hosts will be shown.
+    #[arg(long)]
+    host: Vec<String>,
+
+    /// Print the results for a desktop launcher, each with a title, a subtitle and the command
+    /// as its argument
+    #[arg(
+        long,
+        value_enum,
+        conflicts_with_all = ["interactive", "format", "cmd_only", "human", "print0", "delete", "delete_it_all"]
+    )]
+    picker_format: Option<PickerFormat>,
+}
+
+/// When `atuin search` opens the interactive UI rather than listing matches.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Interactive {
+    /// List matches
+    Never,
+    /// Open the UI if there's a terminal to draw it on, and list matches otherwise
+    Auto,
+    /// Open the UI, failing if there's no terminal
+    Always,
+}
+
+impl Interactive {
+    fn enabled(self) -> bool {
+        match self {
+            Self::Never => false,
+            Self::Auto => has_terminal(),
+            Self::Always => true,
+        }
+    }
+}
+
+/// Whether the UI has a terminal to run in. It draws on stdout, or on the
+/// controlling terminal when stdout is captured, as in `VAR=$(atuin search -i)`.
+fn has_terminal() -> bool {
+    if stdin().is_terminal() && stdout().is_terminal() {
+        return true;
+    }
+
+    #[cfg(unix)]
+    {
+        File::options()
+            .read(true)
+            .write(true)
+            .open("/dev/tty")
+            .is_ok()
+    }
+
+    #[cfg(not(unix))]
+    {
+        stdin().is_terminal() && stderr().is_terminal()
+    }
 }
 
 impl Cmd {
     /// Returns true if this search command will run in interactive (TUI) mode
     pub fn is_interactive(&self) -> bool {
-        self.interactive
+        self.interactive.enabled()
     }
 
     // clippy: please write this instead
@@ -222,6 +327,8 @@ impl Cmd {
         }
         if let Some(filter_mode) = self.filter_mode {
             settings.filter_mode = Some(filter_mode);
+            // An explicit mode is a one-off, and isn't the one the shell session last used
+            settings.search.remember_filter_mode = false;
         }
         if let Some(inline_height) = self.inline_height {
             settings.inline_height = inline_height;
@@ -242,8 +349,19 @@ impl Cmd {
         let host_id = Settings::host_id().await?;
         let history_store = HistoryStore::new(store.clone(), host_id, encryption_key);
 
-        if self.interactive {
-            let item = interactive::history(&query, settings, db, &history_store, theme).await?;
+        if self.interactive.enabled() {
+            let mut item = interactive::history(
+                &query,
+                settings,
+                db,
+                &history_store,
+                theme,
+                self.include_archive,
+            )
+            .await?;
+            if self.bracketed_paste {
+                item = bracketed_paste(&item);
+            }
 
             if let Some(result_file) = self.result_file {
                 let mut file = File::create(result_file)?;
@@ -259,31 +377,46 @@ impl Cmd {
                 eprintln!("{item}");
             }
         } else {
+            let (before, after) =
+                resolve_date_filters(settings, utils::now(), self.before, self.after, self.at)?;
+
             let opt_filter = OptFilters {
                 exit: self.exit,
                 exclude_exit: self.exclude_exit,
                 only_failed: false,
                 cwd: self.cwd,
                 exclude_cwd: self.exclude_cwd,
-                before: self.before,
-                after: self.after,
+                before,
+                after,
                 limit: self.limit,
                 offset: self.offset,
                 reverse: self.reverse,
                 include_duplicates: self.include_duplicates,
                 authors: self.author,
                 shells: self.shell,
+                hosts: self.host,
+                include_archive: self.include_archive,
+                // Scripted searches keep the database's order
+                ..Default::default()
             };
 
             let mut entries =
                 run_non_interactive(settings, opt_filter.clone(), &query, &db).await?;
 
+            if let Some(picker_format) = self.picker_format {
+                // Launchers expect an empty list rather than a failure when nothing matches
+                picker::write(&mut stdout().lock(), picker_format, &entries, utils::now())?;
+                return Ok(());
+            }
+
             if entries.is_empty() {
                 std::process::exit(1)
             }
 
             // if we aren't deleting, print it all
             if self.delete || self.delete_it_all {
+                settings.ensure_history_removable()?;
+
                 // delete it
                 // it only took me _years_ to add this
                 // sorry
@@ -312,6 +445,8 @@ impl Cmd {
                     self.print0,
                     true,
                     tz,
+                    super::history::short_id_len(&db, format, &entries).await?,
+                    &super::history::host_names(settings, &history_store.store, format).await,
                 );
             }
         }
@@ -319,6 +454,37 @@ impl Cmd {
     }
 }
 
+/// Resolve `--before`, `--after` and `--at` to fixed timestamps, reading them in the configured
+/// dialect and timezone and measuring relative dates from `--at` when it's given.
+fn resolve_date_filters(
+    settings: &Settings,
+    now: OffsetDateTime,
+    before: Option<String>,
+    after: Option<String>,
+    at: Option<String>,
+) -> Result<(Option<String>, Option<String>)> {
+    let now = now.to_offset(settings.timezone.0);
+    let at = at
+        .map(|at| parse_date(&at, now, settings.dialect))
+        .transpose()?;
+
+    let reference = at.unwrap_or(now);
+    let parse = |date: Option<String>| {
+        date.map(|date| parse_date(&date, reference, settings.dialect))
+            .transpose()
+    };
+
+    let before = match (parse(before)?, at) {
+        (Some(before), Some(at)) => Some(before.min(at)),
+        (before, at) => before.or(at),
+    };
+    let after = parse(after)?;
+
+    let format = |date: Option<OffsetDateTime>| date.map(|date| date.format(&Rfc3339)).transpose();
+
+    Ok((format(before)?, format(after)?))
+}
+
 // This is supposed to more-or-less mirror the command line version, so ofc
 // it is going to have a lot of args
 async fn run_non_interactive(
@@ -342,9 +508,29 @@ async fn run_non_interactive(
 
     let filter_mode = settings.default_filter_mode(context.git_root.is_some());
 
+    #[cfg(feature = "synonyms")]
+    if settings.search_mode == SearchMode::Synonym {
+        return atuin_client::synonyms::SynonymIndex::default()
+            .search(
+                db,
+                filter_mode,
+                &context,
+                query.join(" ").as_str(),
+                opt_filter,
+            )
+            .await;
+    }
+
+    let search_mode = match settings.search_mode {
+        // Without the synonyms feature, fall back to fuzzy like the interactive search does.
+        // Ranking by frecency is only done interactively; the match is the same as fuzzy.
+        SearchMode::Synonym | SearchMode::Frecency => SearchMode::Fuzzy,
+        mode => mode,
+    };
+
     let results = db
         .search(
-            settings.search_mode,
+            search_mode,
             filter_mode,
             &context,
             query.join(" ").as_str(),
@@ -355,10 +541,61 @@ async fn run_non_interactive(
     Ok(results)
 }
 
+/// Wrap `item` in the markers a terminal sends around pasted text, if it
+/// spans more than one line. Results the shell runs itself are left alone.
+fn bracketed_paste(item: &str) -> String {
+    const START: &str = "\x1b[200~";
+    const END: &str = "\x1b[201~";
+
+    if !item.contains('\n') || item.starts_with(interactive::ACCEPT_PREFIX) {
+        return item.to_string();
+    }
+
+    // A stray end marker would let the rest through as typed input.
+    let item = item.replace(START, "").replace(END, "");
+    format!("{START}{item}{END}")
+}
+
 #[cfg(test)]
 mod tests {
-    use super::Cmd;
+    use super::{Cmd, Interactive, bracketed_paste, resolve_date_filters};
+    use atuin_client::settings::Settings;
     use clap::Parser;
+    use time::macros::datetime;
+
+    #[test]
+    fn multi_line_results_are_pasted_whole() {
+        assert_eq!(bracketed_paste("ls -la"), "ls -la");
+        assert_eq!(
+            bracketed_paste("for f in *; do\n  echo $f\ndone"),
+            "\x1b[200~for f in *; do\n  echo $f\ndone\x1b[201~"
+        );
+        assert_eq!(
+            bracketed_paste("echo\n\x1b[201~rm -rf ~"),
+            "\x1b[200~echo\nrm -rf ~\x1b[201~"
+        );
+        assert_eq!(
+            bracketed_paste("__atuin_accept__:echo a\necho b"),
+            "__atuin_accept__:echo a\necho b"
+        );
+    }
+
+    #[test]
+    fn interactive_takes_an_optional_value() {
+        let cmd = Cmd::try_parse_from(["search", "git"]).unwrap();
+        assert_eq!(cmd.interactive, Interactive::Never);
+
+        // A bare `-i` doesn't swallow the query
+        let cmd = Cmd::try_parse_from(["search", "-i", "git"]).unwrap();
+        assert_eq!(cmd.interactive, Interactive::Auto);
+        assert_eq!(cmd.query, vec!["git".to_string()]);
+
+        let cmd = Cmd::try_parse_from(["search", "--interactive=always", "git"]).unwrap();
+        assert_eq!(cmd.interactive, Interactive::Always);
+
+        let cmd = Cmd::try_parse_from(["search", "--interactive=never"]).unwrap();
+        assert!(!cmd.is_interactive());
+    }
 
     #[test]
     fn search_for_triple_dash() {
@@ -384,4 +621,50 @@ mod tests {
             Cmd::try_parse_from(["search", "--author", "codex", "--author", "ellie"]).unwrap();
         assert_eq!(cmd.author, vec!["codex".to_string(), "ellie".to_string()]);
     }
+
+    #[test]
+    fn at_bounds_results_and_anchors_relative_dates() {
+        let settings = Settings::utc();
+        let now = datetime!(2026-10-15 12:00 UTC);
+
+        let (before, after) = resolve_date_filters(
+            &settings,
+            now,
+            None,
+            Some("2 days ago".to_string()),
+            Some("start of last month".to_string()),
+        )
+        .unwrap();
+
+        assert_eq!(before.as_deref(), Some("2026-09-01T00:00:00Z"));
+        assert_eq!(after.as_deref(), Some("2026-08-30T00:00:00Z"));
+    }
+
+    #[test]
+    fn earlier_of_before_and_at_wins() {
+        let settings = Settings::utc();
+        let now = datetime!(2026-10-15 12:00 UTC);
+
+        let (before, _) = resolve_date_filters(
+            &settings,
+            now,
+            Some("start of last year".to_string()),
+            None,
+            Some("yesterday".to_string()),
+        )
+        .unwrap();
+
+        assert_eq!(before.as_deref(), Some("2025-01-01T00:00:00Z"));
+    }
+
+    #[test]
+    fn ambiguous_dates_are_rejected() {
+        let settings = Settings::utc();
+        let now = datetime!(2026-10-15 12:00 UTC);
+
+        let err = resolve_date_filters(&settings, now, None, Some("friday".to_string()), None)
+            .unwrap_err();
+
+        assert!(err.to_string().contains("last friday"));
+    }
 }
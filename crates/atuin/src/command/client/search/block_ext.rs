@@ -1,3 +1,4 @@
+use atuin_client::settings::FilterMode;
 use ratatui::{
     backend::FromCrossterm,
     layout::Alignment,
@@ -5,6 +6,7 @@ use ratatui::{
     text::Line,
     widgets::{Block, BorderType},
 };
+use time::Date;
 
 use crate::command::client::theme::Theme;
 
@@ -18,9 +20,23 @@ pub fn themed_block(theme: &Theme) -> Block<'static> {
         .border_type(BorderType::Rounded)
 }
 
-/// Creates a themed block with " History " centered title for the main TUI container.
-pub fn titled_block(theme: &Theme) -> Block<'static> {
+/// Creates a themed block with a centered " History · host " title for the main TUI container,
+/// naming the filter mode being searched with, the session's name when it has one, and the day
+/// the results were jumped to.
+pub fn titled_block(
+    theme: &Theme,
+    filter_mode: FilterMode,
+    session_name: Option<&str>,
+    jumped_to: Option<Date>,
+) -> Block<'static> {
+    let mut title = format!(" History · {} ", filter_mode.as_str().to_lowercase());
+    if let Some(name) = session_name {
+        title.push_str(&format!("· {name} "));
+    }
+    if let Some(day) = jumped_to {
+        title.push_str(&format!("· to {day} "));
+    }
     themed_block(theme)
-        .title(Line::from(" History ").style(Style::new().add_modifier(Modifier::BOLD)))
+        .title(Line::from(title).style(Style::new().add_modifier(Modifier::BOLD)))
         .title_alignment(Alignment::Center)
 }
@@ -0,0 +1,67 @@
+//! Copying from the search. Text goes to the terminal as an OSC 52 escape sequence, which
+//! reaches the clipboard of the machine the terminal runs on even over SSH, and to the system
+//! clipboard as well where there is one, for terminals that don't take OSC 52.
+
+use std::io::Write;
+
+use base64::prelude::{BASE64_STANDARD, Engine};
+use eyre::{Result, bail};
+
+/// Copy `text`, failing only when neither the terminal nor the system clipboard could be
+/// given it.
+pub fn copy(text: &str) -> Result<()> {
+    if let (Err(terminal), Err(system)) = (to_terminal(text), to_system(text)) {
+        bail!("{system}, and the terminal couldn't be written to: {terminal}");
+    }
+
+    Ok(())
+}
+
+/// The escape sequence that sets the clipboard to `text`.
+fn osc52(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", BASE64_STANDARD.encode(text))
+}
+
+fn to_terminal(text: &str) -> std::io::Result<()> {
+    // The terminal rather than stdout, which the shell may be capturing
+    #[cfg(unix)]
+    let mut terminal = std::fs::File::options().write(true).open("/dev/tty")?;
+    #[cfg(not(unix))]
+    let mut terminal = std::io::stdout();
+
+    terminal.write_all(osc52(text).as_bytes())?;
+    terminal.flush()
+}
+
+// arboard only works on Windows, Mac, and Linux.
+
+#[cfg(all(
+    feature = "clipboard",
+    any(target_os = "windows", target_os = "macos", target_os = "linux")
+))]
+fn to_system(text: &str) -> Result<()> {
+    let mut ctx = arboard::Clipboard::new()?;
+    ctx.set_text(text)?;
+    // Use the clipboard context to make sure it is saved
+    ctx.get_text()?;
+    Ok(())
+}
+
+#[cfg(not(all(
+    feature = "clipboard",
+    any(target_os = "windows", target_os = "macos", target_os = "linux")
+)))]
+fn to_system(_text: &str) -> Result<()> {
+    bail!("there's no system clipboard")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::osc52;
+
+    #[test]
+    fn text_is_sent_encoded() {
+        assert_eq!(osc52("ls -la"), "\x1b]52;c;bHMgLWxh\x07");
+        assert_eq!(osc52(""), "\x1b]52;c;\x07");
+    }
+}
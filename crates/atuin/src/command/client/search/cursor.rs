@@ -24,11 +24,31 @@ fn chars_within(
 pub struct Cursor {
     source: String,
     index: usize,
+    /// Earlier `(source, index)` states, most recent last.
+    undo: Vec<(String, usize)>,
+    /// Where the last [`Cursor::insert`] left the cursor, so a run of typed
+    /// characters is undone as one edit.
+    typing_at: Option<usize>,
+    /// Killed text, most recent last.
+    kill_ring: Vec<String>,
+    /// What the last [`Cursor::yank`] inserted, and how far back in the kill
+    /// ring it came from, for [`Cursor::yank_pop`].
+    yanked: Option<(Range<usize>, usize)>,
 }
 
+/// How many kills [`Cursor::yank_pop`] can cycle back through.
+const KILL_RING_SIZE: usize = 16;
+
 impl From<String> for Cursor {
     fn from(source: String) -> Self {
-        Self { source, index: 0 }
+        Self {
+            source,
+            index: 0,
+            undo: Vec::new(),
+            typing_at: None,
+            kill_ring: Vec::new(),
+            yanked: None,
+        }
     }
 }
 
@@ -121,11 +141,6 @@ impl Cursor {
         &self.source[..self.index]
     }
 
-    /// Returns the currently selected [`char`]
-    pub fn char(&self) -> Option<char> {
-        self.source[self.index..].chars().next()
-    }
-
     pub fn right(&mut self) {
         if self.index < self.source.len() {
             loop {
@@ -228,17 +243,125 @@ impl Cursor {
         self.index = chars.iter().take(char_idx).map(|c| c.len_utf8()).sum();
     }
 
+    /// Run `f`, remembering the state before it for [`Cursor::undo`] if it
+    /// changed the text.
+    fn edit<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> T {
+        let before = (self.source.clone(), self.index);
+        self.yanked = None;
+        let result = f(self);
+
+        self.typing_at = None;
+        if self.source != before.0 {
+            self.undo.push(before);
+        }
+        result
+    }
+
     pub fn insert(&mut self, c: char) {
+        if self.typing_at != Some(self.index) {
+            self.undo.push((self.source.clone(), self.index));
+        }
+
         self.source.insert(self.index, c);
         self.index += c.len_utf8();
+        self.typing_at = Some(self.index);
+        self.yanked = None;
+    }
+
+    /// Insert pasted text as one edit. Line breaks, and the indentation and
+    /// continuation backslashes around them, become single spaces so a
+    /// multi-line snippet stays a one-line query.
+    pub fn paste(&mut self, text: &str) {
+        let text = if text.contains(['\n', '\r']) {
+            text.lines()
+                .map(|line| line.trim().trim_end_matches('\\').trim_end())
+                .filter(|line| !line.is_empty())
+                .join(" ")
+        } else {
+            text.to_string()
+        };
+        let text = text.replace(char::is_control, " ");
+
+        self.edit(|this| {
+            this.source.insert_str(this.index, &text);
+            this.index += text.len();
+        });
     }
 
-    pub fn remove(&mut self) -> Option<char> {
-        if self.index < self.source.len() {
-            Some(self.source.remove(self.index))
-        } else {
-            None
+    /// Remove `range`, saving it to the kill ring for [`Cursor::yank`].
+    fn kill(&mut self, range: Range<usize>) {
+        self.edit(|this| {
+            let killed: String = this.source.drain(range.clone()).collect();
+            this.index = range.start;
+
+            if !killed.is_empty() {
+                if this.kill_ring.len() == KILL_RING_SIZE {
+                    this.kill_ring.remove(0);
+                }
+                this.kill_ring.push(killed);
+            }
+        });
+    }
+
+    /// Whether anything has been killed that [`Cursor::yank`] could insert.
+    pub fn has_killed(&self) -> bool {
+        !self.kill_ring.is_empty()
+    }
+
+    /// Insert the most recently killed text.
+    pub fn yank(&mut self) {
+        let Some(text) = self.kill_ring.last().cloned() else {
+            return;
+        };
+
+        let start = self.index;
+        self.edit(|this| {
+            this.source.insert_str(start, &text);
+            this.index += text.len();
+        });
+        self.yanked = Some((start..self.index, 0));
+    }
+
+    /// Straight after a yank, swap what was yanked for the kill before it.
+    pub fn yank_pop(&mut self) {
+        let Some((range, back)) = self.yanked.clone() else {
+            return;
+        };
+        if range.end != self.index || self.kill_ring.len() < 2 {
+            return;
         }
+
+        let back = (back + 1) % self.kill_ring.len();
+        let text = self.kill_ring[self.kill_ring.len() - 1 - back].clone();
+        self.edit(|this| {
+            this.source.replace_range(range.clone(), &text);
+            this.index = range.start + text.len();
+        });
+        self.yanked = Some((range.start..self.index, back));
+    }
+
+    /// Go back to how the text was before the last edit. Returns false if
+    /// there was nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        self.typing_at = None;
+        let Some((source, index)) = self.undo.pop() else {
+            return false;
+        };
+        self.source = source;
+        self.index = index;
+        true
+    }
+
+    /// Replace all the text as one edit, with the cursor after it.
+    pub fn replace(&mut self, text: String) {
+        self.edit(|this| {
+            this.source = text;
+            this.index = this.source.len();
+        });
+    }
+
+    pub fn remove(&mut self) -> Option<char> {
+        self.edit(|this| (this.index < this.source.len()).then(|| this.source.remove(this.index)))
     }
 
     pub fn remove_next_word(&mut self, word_chars: &str, word_jump_mode: WordJumpMode) {
@@ -247,7 +370,7 @@ impl Cursor {
             word_jump_mode,
         };
         let next_index = word_jumper.get_next_word_pos(&self.source, self.index);
-        self.source.replace_range(self.index..next_index, "");
+        self.kill(self.index..next_index);
     }
 
     pub fn remove_prev_word(&mut self, word_chars: &str, word_jump_mode: WordJumpMode) {
@@ -256,8 +379,17 @@ impl Cursor {
             word_jump_mode,
         };
         let next_index = word_jumper.get_prev_word_pos(&self.source, self.index);
-        self.source.replace_range(next_index..self.index, "");
-        self.index = next_index;
+        self.kill(next_index..self.index);
+    }
+
+    /// Remove whitespace before the cursor, then everything back to the
+    /// previous whitespace (readline's `ctrl-w`).
+    pub fn remove_prev_whitespace_word(&mut self) {
+        let start = self.source[..self.index]
+            .trim_end_matches(char::is_whitespace)
+            .trim_end_matches(|c: char| !c.is_whitespace())
+            .len();
+        self.kill(start..self.index);
     }
 
     pub fn back(&mut self) -> Option<char> {
@@ -265,18 +397,15 @@ impl Cursor {
     }
 
     pub fn clear(&mut self) {
-        self.source.clear();
-        self.index = 0;
+        self.kill(0..self.source.len());
     }
 
     pub fn clear_to_start(&mut self) {
-        self.source.replace_range(..self.index, "");
-        self.index = 0;
+        self.kill(0..self.index);
     }
 
     pub fn clear_to_end(&mut self) {
-        self.source.replace_range(self.index.., "");
-        self.index = self.source.len();
+        self.kill(self.index..self.source.len());
     }
 
     pub fn end(&mut self) {
@@ -440,4 +569,102 @@ mod cursor_tests {
         assert_eq!(c.substring(), "öaöbögöh");
         assert_eq!(c.as_str(), "öaöbögöhöcödöeöfö");
     }
+
+    #[test]
+    fn remove_prev_whitespace_word() {
+        let mut c = Cursor::from(String::from("git commit -m öö  "));
+        c.end();
+        c.remove_prev_whitespace_word();
+        assert_eq!(c.as_str(), "git commit -m ");
+        c.remove_prev_whitespace_word();
+        c.remove_prev_whitespace_word();
+        assert_eq!(c.as_str(), "git ");
+        c.remove_prev_whitespace_word();
+        c.remove_prev_whitespace_word();
+        assert_eq!(c.as_str(), "");
+    }
+
+    #[test]
+    fn undo_reverts_one_edit_at_a_time() {
+        let mut c = Cursor::from(String::new());
+        for ch in "cargo".chars() {
+            c.insert(ch);
+        }
+        c.insert(' ');
+        for ch in "tset".chars() {
+            c.insert(ch);
+        }
+        c.remove_prev_word("abcdefghijklmnopqrstuvwxyz", WordJumpMode::Emacs);
+        assert_eq!(c.as_str(), "cargo ");
+
+        assert!(c.undo());
+        assert_eq!(c.as_str(), "cargo tset");
+        assert_eq!(c.position(), 10);
+
+        // Typed without moving the cursor, so it all goes at once.
+        assert!(c.undo());
+        assert_eq!(c.as_str(), "");
+        assert!(!c.undo());
+    }
+
+    #[test]
+    fn undo_splits_typing_at_cursor_moves() {
+        let mut c = Cursor::from(String::new());
+        c.insert('b');
+        c.start();
+        c.insert('a');
+        c.end();
+        // Nothing was cleared, so there's nothing to undo for it.
+        c.clear_to_end();
+        assert_eq!(c.as_str(), "ab");
+        c.back();
+        c.back();
+
+        assert!(c.undo());
+        assert_eq!(c.as_str(), "a");
+        assert!(c.undo());
+        assert_eq!(c.as_str(), "ab");
+        assert!(c.undo());
+        assert_eq!(c.as_str(), "b");
+        assert!(c.undo());
+        assert_eq!(c.as_str(), "");
+    }
+
+    #[test]
+    fn yank_inserts_killed_text() {
+        let mut c = Cursor::from(String::from("docker ps -a"));
+        c.end();
+        c.remove_prev_whitespace_word();
+        c.remove_prev_whitespace_word();
+        c.clear();
+        assert_eq!(c.as_str(), "");
+
+        c.yank();
+        assert_eq!(c.as_str(), "docker ");
+        c.yank_pop();
+        assert_eq!(c.as_str(), "ps ");
+        c.yank_pop();
+        assert_eq!(c.as_str(), "-a");
+        c.yank_pop();
+        assert_eq!(c.as_str(), "docker ");
+
+        // Only straight after a yank.
+        c.insert('x');
+        c.yank_pop();
+        assert_eq!(c.as_str(), "docker x");
+    }
+
+    #[test]
+    fn paste_keeps_the_query_on_one_line() {
+        let mut c = Cursor::from(String::new());
+        c.paste("docker run \\\n    --rm \\\r\n    alpine\n");
+        assert_eq!(c.as_str(), "docker run --rm alpine");
+        assert_eq!(c.position(), c.as_str().len());
+
+        c.paste("\tx");
+        assert_eq!(c.as_str(), "docker run --rm alpine x");
+
+        assert!(c.undo());
+        assert_eq!(c.as_str(), "docker run --rm alpine");
+    }
 }
@@ -0,0 +1,180 @@
+//! Moving through the results by day: jumping to a date typed in, so the list starts at the
+//! history of that day, and skipping to the day before or after the selected entry's.
+
+use atuin_client::{
+    date::{DateError, parse_date},
+    history::History,
+    settings::{Dialect, Timezone},
+};
+use ratatui::{
+    Frame,
+    backend::FromCrossterm,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Clear, Paragraph},
+};
+use time::{Date, Duration, OffsetDateTime, Time};
+use unicode_width::UnicodeWidthStr;
+
+use super::block_ext::themed_block;
+use crate::command::client::theme::{Meaning, Theme};
+
+const LABEL: &str = "Jump to: ";
+const HINT: &str = "2024-03-15, last friday, 3 weeks ago. Empty for the latest";
+
+/// The day `input` names, read like `atuin search --before`, or `None` when it's empty, to go
+/// back to the latest history.
+pub fn parse_day(
+    input: &str,
+    now: OffsetDateTime,
+    tz: Timezone,
+    dialect: Dialect,
+) -> Result<Option<Date>, DateError> {
+    if input.trim().is_empty() {
+        return Ok(None);
+    }
+
+    let now = now.to_offset(tz.0);
+    Ok(Some(
+        parse_date(input, now, dialect)?.to_offset(tz.0).date(),
+    ))
+}
+
+/// The instant `day` ends in `tz`, which the results are bounded by to start with that day.
+pub fn end_of(day: Date, tz: Timezone) -> OffsetDateTime {
+    (day + Duration::days(1))
+        .with_time(Time::MIDNIGHT)
+        .assume_offset(tz.0)
+}
+
+fn day_of(h: &History, tz: Timezone) -> Date {
+    h.timestamp.to_offset(tz.0).date()
+}
+
+/// The first result after `selected` run on an earlier day than it: with the newest first,
+/// the latest entry of the day before.
+pub fn earlier_day(results: &[History], selected: usize, tz: Timezone) -> Option<usize> {
+    let day = day_of(results.get(selected)?, tz);
+    (selected + 1..results.len()).find(|&i| day_of(&results[i], tz) < day)
+}
+
+/// The nearest result before `selected` run on a later day than it: with the newest first,
+/// the earliest entry of the day after.
+pub fn later_day(results: &[History], selected: usize, tz: Timezone) -> Option<usize> {
+    let day = day_of(results.get(selected)?, tz);
+    (0..selected.min(results.len()))
+        .rev()
+        .find(|&i| day_of(&results[i], tz) > day)
+}
+
+/// Asking for the day to jump to.
+#[derive(Default)]
+pub struct DatePrompt {
+    input: String,
+}
+
+impl DatePrompt {
+    pub fn value(&self) -> &str {
+        &self.input
+    }
+
+    pub fn insert(&mut self, c: char) {
+        self.input.push(c);
+    }
+
+    pub fn backspace(&mut self) {
+        self.input.pop();
+    }
+
+    pub fn draw(&self, f: &mut Frame, area: Rect, theme: &Theme) {
+        let width = (LABEL.len() + UnicodeWidthStr::width(self.value()))
+            .max(HINT.len())
+            .max(30)
+            + 4;
+        let [area] =
+            Layout::horizontal([Constraint::Length(u16::try_from(width).unwrap_or(u16::MAX))])
+                .flex(Flex::Center)
+                .areas(area);
+        let [area] = Layout::vertical([Constraint::Length(4)])
+            .flex(Flex::Center)
+            .areas(area);
+
+        let annotation = Style::from_crossterm(theme.as_style(Meaning::Annotation));
+        let lines = vec![
+            Line::from(vec![
+                Span::styled(LABEL, Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(self.value().to_string()),
+            ]),
+            Line::styled(HINT, annotation),
+        ];
+        let prompt = Paragraph::new(lines)
+            .style(Style::from_crossterm(theme.get_base()))
+            .block(themed_block(theme).title(" Date "));
+
+        f.render_widget(Clear, area);
+        f.render_widget(prompt, area);
+
+        let cursor = LABEL.len() + UnicodeWidthStr::width(self.value());
+        f.set_cursor_position((
+            (area.x + 1 + u16::try_from(cursor).unwrap_or(u16::MAX))
+                .min(area.right().saturating_sub(2)),
+            area.y + 1,
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::{UtcOffset, macros::datetime};
+
+    use super::*;
+
+    fn entry(at: OffsetDateTime) -> History {
+        History::import().timestamp(at).command("ls").build().into()
+    }
+
+    #[test]
+    fn days_are_read_in_the_timezone() {
+        let now = datetime!(2026-10-16 23:30 UTC);
+        let utc = Timezone(UtcOffset::UTC);
+        let ahead = Timezone(UtcOffset::from_hms(2, 0, 0).unwrap());
+
+        assert_eq!(
+            parse_day("2024-03-15", now, utc, Dialect::Uk).unwrap(),
+            Some(datetime!(2024-03-15 00:00 UTC).date())
+        );
+        assert_eq!(
+            parse_day("today", now, ahead, Dialect::Uk).unwrap(),
+            Some(datetime!(2026-10-17 00:00 UTC).date())
+        );
+        assert_eq!(parse_day(" ", now, utc, Dialect::Uk).unwrap(), None);
+        assert!(parse_day("the other day", now, utc, Dialect::Uk).is_err());
+
+        assert_eq!(
+            end_of(datetime!(2024-03-15 00:00 UTC).date(), ahead),
+            datetime!(2024-03-15 22:00 UTC)
+        );
+    }
+
+    #[test]
+    fn days_are_skipped_to_their_boundaries() {
+        let utc = Timezone(UtcOffset::UTC);
+        // Newest first, as the results are listed
+        let results = [
+            entry(datetime!(2026-10-16 09:00 UTC)),
+            entry(datetime!(2026-10-15 18:00 UTC)),
+            entry(datetime!(2026-10-15 09:00 UTC)),
+            entry(datetime!(2026-10-12 12:00 UTC)),
+        ];
+
+        assert_eq!(earlier_day(&results, 0, utc), Some(1));
+        assert_eq!(earlier_day(&results, 1, utc), Some(3));
+        assert_eq!(earlier_day(&results, 3, utc), None);
+
+        assert_eq!(later_day(&results, 3, utc), Some(2));
+        assert_eq!(later_day(&results, 1, utc), Some(0));
+        assert_eq!(later_day(&results, 0, utc), None);
+        assert_eq!(later_day(&results, 9, utc), None);
+    }
+}
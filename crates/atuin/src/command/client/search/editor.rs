@@ -0,0 +1,80 @@
+use std::process::{Command, Stdio};
+
+use eyre::{OptionExt, Result, WrapErr, bail};
+
+/// The user's editor, as `$VISUAL` or `$EDITOR` would run it.
+pub fn editor() -> String {
+    std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string())
+}
+
+/// Let the user change `command` in `editor` before it goes back to the shell.
+///
+/// Only trailing newlines are dropped, so multi-line commands survive the trip. Emptying the
+/// file gives back an empty string, which the shell treats as a cancelled search.
+pub fn edit_command(editor: &str, command: &str) -> Result<String> {
+    let file = tempfile::Builder::new()
+        .prefix("atuin-command-")
+        .suffix(".sh")
+        .tempfile()?;
+    std::fs::write(file.path(), format!("{command}\n"))?;
+
+    let parts = shlex::split(editor).ok_or_eyre("failed to parse editor command")?;
+    let (program, args) = parts.split_first().ok_or_eyre("no editor command found")?;
+
+    let status = Command::new(program)
+        .args(args)
+        .arg(file.path())
+        .stdin(terminal())
+        .stdout(terminal())
+        .stderr(terminal())
+        .status()
+        .wrap_err_with(|| format!("failed to run {program}"))?;
+    if !status.success() {
+        bail!("{program} exited with {status}");
+    }
+
+    let content = std::fs::read_to_string(file.path())?;
+    Ok(content.trim_end_matches(['\n', '\r']).to_string())
+}
+
+/// Where the editor, or anything else run from the search, should draw. The shell widgets
+/// capture our stdout to read the chosen command, so it gets the controlling terminal instead,
+/// when there is one.
+pub(super) fn terminal() -> Stdio {
+    #[cfg(unix)]
+    if let Ok(tty) = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+    {
+        return tty.into();
+    }
+
+    Stdio::inherit()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edits_come_back() {
+        let edited = edit_command(
+            r#"sh -c 'printf "git push --force\n\n" > "$1"' sh"#,
+            "git push",
+        )
+        .unwrap();
+        assert_eq!(edited, "git push --force");
+
+        let unchanged = edit_command("true", "for f in *\ndo echo $f\ndone").unwrap();
+        assert_eq!(unchanged, "for f in *\ndo echo $f\ndone");
+    }
+
+    #[test]
+    fn failed_editors_are_errors() {
+        assert!(edit_command("false", "ls").is_err());
+        assert!(edit_command("", "ls").is_err());
+    }
+}
@@ -1,19 +1,27 @@
+use std::sync::Arc;
+
 use async_trait::async_trait;
 use atuin_client::{
     database::{Context, Database, OptFilters},
     history::{AUTHOR_FILTER_ALL_USER, History, HistoryId},
+    ordering::ContextBoost,
     settings::{FilterMode, SearchMode, Settings},
 };
 use eyre::Result;
+use time::{OffsetDateTime, format_description::well_known::Rfc3339};
+use tokio::sync::Mutex;
 
 use super::cursor::Cursor;
 
 #[cfg(feature = "daemon")]
 pub mod daemon;
 pub mod db;
+pub mod frecency;
+pub mod regex;
 pub mod skim;
+#[cfg(feature = "synonyms")]
+pub mod synonyms;
 
-#[allow(unused)] // settings is only used if daemon feature is enabled
 pub fn engine(search_mode: SearchMode, settings: &Settings) -> Box<dyn SearchEngine> {
     match search_mode {
         SearchMode::Skim => Box::new(skim::Search::new()) as Box<_>,
@@ -24,18 +32,85 @@ pub fn engine(search_mode: SearchMode, settings: &Settings) -> Box<dyn SearchEng
             // Fall back to fuzzy mode if daemon feature is not enabled
             Box::new(db::Search(SearchMode::Fuzzy)) as Box<_>
         }
+        #[cfg(feature = "synonyms")]
+        SearchMode::Synonym => Box::new(synonyms::Search::default()) as Box<_>,
+        #[cfg(not(feature = "synonyms"))]
+        SearchMode::Synonym => {
+            // Fall back to fuzzy mode if synonyms feature is not enabled
+            Box::new(db::Search(SearchMode::Fuzzy)) as Box<_>
+        }
+        SearchMode::Frecency => Box::new(frecency::Search::new(settings)) as Box<_>,
+        SearchMode::Regex => Box::new(regex::Search::default()) as Box<_>,
         mode => Box::new(db::Search(mode)) as Box<_>,
     }
 }
 
+/// An engine for queries running in the background, one at a time.
+pub type SharedEngine = Arc<Mutex<Box<dyn SearchEngine>>>;
+
+/// How many results a query finds at most.
+pub const RESULT_LIMIT: usize = 200;
+
+/// How many results are shown before the rest are found, enough to fill the window on any
+/// terminal.
+pub const FIRST_PAGE: usize = 50;
+
+pub fn shared(search_mode: SearchMode, settings: &Settings) -> SharedEngine {
+    Arc::new(Mutex::new(engine(search_mode, settings)))
+}
+
 pub struct SearchState {
     pub input: Cursor,
     pub filter_mode: FilterMode,
     pub context: Context,
     pub custom_context: Option<HistoryId>,
+    /// Whether archived history is searched too, or `None` if there's no archive
+    pub include_archive: Option<bool>,
+    /// How much results run near `context` are favoured
+    pub boost: ContextBoost,
+    /// Whether commands run in containers are left out, unless the query asks for them
+    pub exclude_containers: bool,
+    /// Only history from before this, after jumping to a date
+    pub before: Option<OffsetDateTime>,
+    /// How many results to find, if fewer than [`RESULT_LIMIT`]
+    pub limit: Option<usize>,
 }
 
 impl SearchState {
+    /// A copy of what's being searched for, without the input's editing history, to query
+    /// with in the background.
+    pub(crate) fn snapshot(&self) -> Self {
+        Self {
+            input: Cursor::from(self.input.as_str().to_string()),
+            filter_mode: self.filter_mode,
+            context: self.context.clone(),
+            custom_context: self.custom_context.clone(),
+            include_archive: self.include_archive,
+            boost: self.boost,
+            exclude_containers: self.exclude_containers,
+            before: self.before,
+            limit: self.limit,
+        }
+    }
+
+    /// Just the first page of what's being searched for.
+    pub(crate) fn first_page(&self) -> Self {
+        Self {
+            limit: Some(FIRST_PAGE),
+            ..self.snapshot()
+        }
+    }
+
+    /// The `limit` filter to query the database with.
+    pub(crate) fn limit(&self) -> Option<i64> {
+        i64::try_from(self.limit.unwrap_or(RESULT_LIMIT)).ok()
+    }
+
+    /// `before` as the search's `before` filter takes it.
+    pub(crate) fn before_filter(&self) -> Option<String> {
+        self.before.and_then(|before| before.format(&Rfc3339).ok())
+    }
+
     pub(crate) fn rotate_filter_mode(&mut self, settings: &Settings, offset: isize) {
         let mut i = settings
             .search
@@ -79,8 +154,12 @@ pub trait SearchEngine: Send + Sync + 'static {
                     &state.context,
                     "",
                     OptFilters {
-                        limit: Some(200),
+                        limit: state.limit(),
                         authors: vec![AUTHOR_FILTER_ALL_USER.to_string()],
+                        include_archive: state.include_archive == Some(true),
+                        context_boost: state.boost,
+                        exclude_containers: state.exclude_containers,
+                        before: state.before_filter(),
                         ..Default::default()
                     },
                 )
@@ -91,5 +170,13 @@ pub trait SearchEngine: Send + Sync + 'static {
             self.full_query(state, db).await
         }
     }
+
+    /// Whether finding fewer results is quicker, so a first page of them is worth showing
+    /// before the rest. Engines that rank everything they match before cutting it short gain
+    /// nothing by it.
+    fn pages(&self) -> bool {
+        false
+    }
+
     fn get_highlight_indices(&self, command: &str, search_input: &str) -> Vec<usize>;
 }
@@ -1,7 +1,10 @@
+use std::time::Duration;
+
 use async_trait::async_trait;
 use atuin_client::{
-    database::{Database, OptFilters},
+    database::{Database, OptFilters, normalize_exclusions, split_query_filters},
     history::{AUTHOR_FILTER_ALL_USER, History},
+    ordering::boost_context,
     settings::{SearchMode, Settings},
 };
 use atuin_daemon::client::{DaemonClientErrorKind, SearchClient, classify_error};
@@ -16,6 +19,46 @@ use uuid::Uuid;
 use super::{SearchEngine, SearchState};
 use crate::command::client::daemon;
 
+/// How long to wait for the daemon's warm first page before asking the database instead.
+const FIRST_PAGE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// The results for an empty query, from the daemon's cache of warm first pages. Returns
+/// `None` when the daemon isn't enabled, can't answer quickly, or doesn't serve this query,
+/// and the caller should ask the database.
+pub async fn first_page(settings: &Settings, state: &SearchState) -> Option<Vec<History>> {
+    // The daemon doesn't search the archive
+    if !settings.daemon.enabled
+        || !state.input.as_str().is_empty()
+        || state.include_archive == Some(true)
+    {
+        return None;
+    }
+
+    let request = async {
+        #[cfg(unix)]
+        let mut client = SearchClient::new(settings.daemon.socket_path.clone()).await?;
+
+        #[cfg(not(unix))]
+        let mut client = SearchClient::new(settings.daemon.tcp_port).await?;
+
+        client
+            .first_page(state.filter_mode, state.context.clone())
+            .await
+    };
+
+    match tokio::time::timeout(FIRST_PAGE_TIMEOUT, request).await {
+        Ok(Ok(page)) => Some(boost_context(page, &state.context, state.boost)),
+        Ok(Err(e)) => {
+            debug!("daemon couldn't serve the first page, using the database: {e}");
+            None
+        }
+        Err(_) => {
+            debug!("daemon was too slow serving the first page, using the database");
+            None
+        }
+    }
+}
+
 pub struct Search {
     client: Option<SearchClient>,
     query_id: u64,
@@ -78,6 +121,11 @@ impl Search {
         query.starts_with("r/") || query.contains(" r/")
     }
 
+    /// Check if query contains filters like `exit:0`, which the daemon doesn't understand
+    fn contains_query_filter(query: &str) -> bool {
+        !split_query_filters(query).0.is_empty()
+    }
+
     #[instrument(skip_all, level = Level::TRACE, name = "daemon_db_fallback")]
     async fn fallback_to_db_search(
         &self,
@@ -91,8 +139,11 @@ impl Search {
                 &state.context,
                 state.input.as_str(),
                 OptFilters {
-                    limit: Some(200),
+                    limit: state.limit(),
                     authors: vec![AUTHOR_FILTER_ALL_USER.to_string()],
+                    context_boost: state.boost,
+                    exclude_containers: state.exclude_containers,
+                    before: state.before_filter(),
                     ..Default::default()
                 },
             )
@@ -102,10 +153,21 @@ impl Search {
     }
 
     #[instrument(skip_all, level = Level::TRACE, name = "hydrate_from_db", fields(count = ids.len()))]
-    async fn hydrate_from_db(&self, db: &dyn Database, ids: &[String]) -> Result<Vec<History>> {
+    async fn hydrate_from_db(
+        &self,
+        db: &dyn Database,
+        ids: &[String],
+        exclude_containers: bool,
+    ) -> Result<Vec<History>> {
         let placeholders: Vec<String> = ids.iter().map(|id| format!("'{id}'")).collect();
+        // The daemon doesn't know where commands were run, so containers are left out here
+        let containers = if exclude_containers {
+            " AND id NOT IN (SELECT id FROM history_container)"
+        } else {
+            ""
+        };
         let sql_query = format!(
-            "SELECT * FROM history WHERE id IN ({}) ORDER BY timestamp DESC",
+            "SELECT * FROM history WHERE id IN ({}){containers} ORDER BY timestamp DESC",
             placeholders.join(",")
         );
         Ok(db.query_history(&sql_query).await?)
@@ -120,13 +182,22 @@ impl SearchEngine for Search {
         state: &SearchState,
         db: &mut dyn Database,
     ) -> Result<Vec<History>> {
-        let query = state.input.as_str().to_string();
+        // The daemon matches with fzf's syntax, which only excludes with `!`
+        let query = normalize_exclusions(state.input.as_str()).into_owned();
 
         // Fall back to database for regex queries (Nucleo doesn't support regex)
         if Self::contains_regex_pattern(&query) {
             debug!(query = %query, "[daemon-client] regex detected, falling back to db");
             return self.fallback_to_db_search(state, db).await;
         }
+        if Self::contains_query_filter(&query) {
+            debug!(query = %query, "[daemon-client] filters detected, falling back to db");
+            return self.fallback_to_db_search(state, db).await;
+        }
+        if state.before.is_some() {
+            debug!("[daemon-client] jumped to a date, falling back to db");
+            return self.fallback_to_db_search(state, db).await;
+        }
 
         let query_id = self.next_query_id();
 
@@ -205,7 +276,9 @@ impl SearchEngine for Search {
         }
 
         // // Hydrate from local database
-        let results = self.hydrate_from_db(db, &ids).await?;
+        let results = self
+            .hydrate_from_db(db, &ids, state.exclude_containers)
+            .await?;
 
         // // Reorder results to match the order from the daemon (which is ranked by relevance)
         let ordered_results = span!(Level::TRACE, "reorder_results").in_scope(|| {
@@ -224,18 +297,19 @@ impl SearchEngine for Search {
             "[daemon-client]"
         );
 
-        Ok(ordered_results)
+        Ok(boost_context(ordered_results, &state.context, state.boost))
     }
 
     #[instrument(skip_all, level = Level::TRACE, name = "daemon_highlight")]
     fn get_highlight_indices(&self, command: &str, search_input: &str) -> Vec<usize> {
-        // Use fulltext highlighting for regex queries
-        if Self::contains_regex_pattern(search_input) {
+        // Use fulltext highlighting for the queries the database answered
+        if Self::contains_regex_pattern(search_input) || Self::contains_query_filter(search_input) {
             return super::db::get_highlight_indices_fulltext(command, search_input);
         }
 
         let mut matcher = Matcher::new(Config::DEFAULT);
-        let pattern = Pattern::parse(search_input, CaseMatching::Smart, Normalization::Smart);
+        let search_input = normalize_exclusions(search_input);
+        let pattern = Pattern::parse(&search_input, CaseMatching::Smart, Normalization::Smart);
 
         let mut indices: Vec<u32> = Vec::new();
         let mut haystack_buf = Vec::new();
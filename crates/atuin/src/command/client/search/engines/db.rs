@@ -3,7 +3,10 @@ use async_trait::async_trait;
 use atuin_client::{
     database::Database,
     database::OptFilters,
-    database::{QueryToken, QueryTokenizer},
+    database::{
+        QueryToken, QueryTokenizer, split_excluded_terms, split_query_filters,
+        strip_output_qualifier,
+    },
     history::{AUTHOR_FILTER_ALL_USER, History},
     settings::SearchMode,
 };
@@ -30,8 +33,12 @@ impl SearchEngine for Search {
                 &state.context,
                 state.input.as_str(),
                 OptFilters {
-                    limit: Some(200),
+                    limit: state.limit(),
                     authors: vec![AUTHOR_FILTER_ALL_USER.to_string()],
+                    include_archive: state.include_archive == Some(true),
+                    context_boost: state.boost,
+                    exclude_containers: state.exclude_containers,
+                    before: state.before_filter(),
                     ..Default::default()
                 },
             )
@@ -41,16 +48,26 @@ impl SearchEngine for Search {
         Ok(results)
     }
 
+    // The database stops scanning once it has found enough
+    fn pages(&self) -> bool {
+        true
+    }
+
     #[instrument(skip_all, level = Level::TRACE, name = "db_highlight")]
     fn get_highlight_indices(&self, command: &str, search_input: &str) -> Vec<usize> {
-        if self.0 == SearchMode::Prefix {
+        let (_, search_input) = split_query_filters(search_input);
+        let search_input = search_input.as_ref();
+        // The terms matched the output, not the command.
+        if self.0 == SearchMode::Prefix || strip_output_qualifier(search_input).is_some() {
             return vec![];
         } else if self.0 == SearchMode::FullText {
             return get_highlight_indices_fulltext(command, search_input);
         }
+        // Excluded terms aren't in the results, so there's nothing of them to highlight
+        let (search_input, _) = split_excluded_terms(search_input);
         let mut fzf = FzfV2::new();
         let mut parser = FzfParser::new();
-        let query = parser.parse(search_input);
+        let query = parser.parse(&search_input);
         let mut ranges: Vec<Range<usize>> = Vec::new();
         let _ = fzf.distance_and_ranges(query, command, &mut ranges);
 
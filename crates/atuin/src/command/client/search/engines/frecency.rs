@@ -0,0 +1,273 @@
+use std::collections::HashSet;
+
+use async_trait::async_trait;
+use atuin_client::{
+    database::{
+        Database, allowed_by_filters, contains_term, exclude_containers, split_excluded_terms,
+        split_query_filters,
+    },
+    history::{History, is_known_agent},
+    ordering::boost_context,
+    settings::Settings,
+};
+use atuin_common::utils;
+use eyre::Result;
+use fuzzy_matcher::{FuzzyMatcher, skim::SkimMatcherV2};
+use time::OffsetDateTime;
+use tokio::task::yield_now;
+use tracing::{Level, instrument};
+
+use super::{SearchEngine, SearchState, skim::in_filter};
+
+const LIMIT: usize = 200;
+
+/// Fuzzy matching, ranked like zoxide ranks directories: by how often each command was run,
+/// decayed by how long ago it was last run.
+pub struct Search {
+    all_history: Vec<(History, i32)>,
+    /// The commands the query's `user:` and `container:` filters allow, if it has any
+    run_by: Option<HashSet<String>>,
+    engine: SkimMatcherV2,
+    half_life: f64,
+    frequency_weight: f64,
+}
+
+impl Search {
+    pub fn new(settings: &Settings) -> Self {
+        Search {
+            all_history: vec![],
+            run_by: None,
+            engine: SkimMatcherV2::default(),
+            half_life: settings.search.frecency_half_life,
+            frequency_weight: settings.search.frecency_frequency_weight,
+        }
+    }
+
+    /// The score of a command run `count` times, most recently `age_days` ago.
+    #[allow(clippy::cast_lossless)]
+    fn score(&self, count: i32, age_days: f64) -> f64 {
+        let frequency = (count.max(1) as f64).powf(self.frequency_weight);
+        let recency = if self.half_life > 0.0 {
+            0.5_f64.powf(age_days.max(0.0) / self.half_life)
+        } else {
+            1.0
+        };
+
+        frequency * recency
+    }
+
+    async fn rank(&self, state: &SearchState, now: OffsetDateTime) -> Vec<History> {
+        let (filters, query) = split_query_filters(state.input.as_str());
+        let (query, excluded) = split_excluded_terms(&query);
+        let query = query.as_ref();
+        let mut ranked = Vec::new();
+
+        for (i, (history, count)) in self.all_history.iter().enumerate() {
+            if i % 256 == 0 {
+                yield_now().await;
+            }
+            if is_known_agent(&history.author)
+                || !in_filter(state, history)
+                || !filters.iter().all(|f| f.matches(history))
+                || self
+                    .run_by
+                    .as_ref()
+                    .is_some_and(|commands| !commands.contains(&history.command))
+                || excluded
+                    .iter()
+                    .any(|term| contains_term(&history.command, term))
+            {
+                continue;
+            }
+            if !query.is_empty() && self.engine.fuzzy_match(&history.command, query).is_none() {
+                continue;
+            }
+
+            let age_days = (now - history.timestamp).as_seconds_f64() / 86_400.0;
+            ranked.push((self.score(*count, age_days), history));
+        }
+
+        // Highest score first, and the most recent first among equals
+        ranked.sort_by(|(a, ha), (b, hb)| {
+            b.total_cmp(a).then_with(|| hb.timestamp.cmp(&ha.timestamp))
+        });
+
+        ranked
+            .into_iter()
+            .take(LIMIT)
+            .map(|(_, history)| history.clone())
+            .collect()
+    }
+}
+
+#[async_trait]
+impl SearchEngine for Search {
+    #[instrument(skip_all, level = Level::TRACE, name = "frecency_search", fields(query = %state.input.as_str()))]
+    async fn full_query(
+        &mut self,
+        state: &SearchState,
+        db: &mut dyn Database,
+    ) -> Result<Vec<History>> {
+        if self.all_history.is_empty() {
+            self.all_history = db.all_with_count().await?;
+        }
+        let (mut filters, _) = split_query_filters(state.input.as_str());
+        if state.exclude_containers {
+            exclude_containers(&mut filters);
+        }
+        self.run_by = allowed_by_filters(db, &filters).await?;
+
+        let results = self.rank(state, utils::now()).await;
+        Ok(boost_context(results, &state.context, state.boost))
+    }
+
+    // An empty query is ranked too, rather than listed newest first.
+    async fn query(&mut self, state: &SearchState, db: &mut dyn Database) -> Result<Vec<History>> {
+        self.full_query(state, db).await
+    }
+
+    #[instrument(skip_all, level = Level::TRACE, name = "frecency_highlight")]
+    fn get_highlight_indices(&self, command: &str, search_input: &str) -> Vec<usize> {
+        let (_, search_input) = split_query_filters(search_input);
+        let (search_input, _) = split_excluded_terms(&search_input);
+        let (_, indices) = self
+            .engine
+            .fuzzy_indices(command, &search_input)
+            .unwrap_or_default();
+        indices
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use atuin_client::{
+        database::Context,
+        ordering::ContextBoost,
+        settings::{FilterMode, Settings},
+    };
+    use time::Duration;
+
+    use super::*;
+    use crate::command::client::search::cursor::Cursor;
+
+    fn history(command: &str, age: Duration, now: OffsetDateTime) -> History {
+        History::import()
+            .timestamp(now - age)
+            .command(command)
+            .cwd("/")
+            .build()
+            .into()
+    }
+
+    fn state(input: &str) -> SearchState {
+        SearchState {
+            input: Cursor::from(input.to_string()),
+            filter_mode: FilterMode::Global,
+            context: Context {
+                session: String::new(),
+                cwd: "/".to_string(),
+                hostname: String::new(),
+                host_id: String::new(),
+                git_root: None,
+            },
+            custom_context: None,
+            include_archive: None,
+            boost: ContextBoost::default(),
+            exclude_containers: false,
+            before: None,
+            limit: None,
+        }
+    }
+
+    fn search(settings: &Settings, all_history: Vec<(History, i32)>) -> Search {
+        Search {
+            all_history,
+            ..Search::new(settings)
+        }
+    }
+
+    fn commands(results: &[History]) -> Vec<&str> {
+        results.iter().map(|h| h.command.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn often_run_commands_outrank_recent_ones() {
+        let now = OffsetDateTime::now_utc();
+        let settings = Settings::utc();
+        let search = search(
+            &settings,
+            vec![
+                (history("git status", Duration::days(2), now), 50),
+                (history("git stash", Duration::minutes(5), now), 1),
+                (history("ls", Duration::minutes(1), now), 3),
+            ],
+        );
+
+        let results = search.rank(&state("gst"), now).await;
+        assert_eq!(commands(&results), ["git status", "git stash"]);
+
+        let results = search.rank(&state(""), now).await;
+        assert_eq!(commands(&results), ["git status", "ls", "git stash"]);
+    }
+
+    #[tokio::test]
+    async fn old_commands_decay() {
+        let now = OffsetDateTime::now_utc();
+        let mut settings = Settings::utc();
+        let all_history = vec![
+            (history("make", Duration::days(70), now), 50),
+            (history("cargo build", Duration::days(1), now), 2),
+        ];
+
+        let results = search(&settings, all_history.clone())
+            .rank(&state(""), now)
+            .await;
+        assert_eq!(commands(&results), ["cargo build", "make"]);
+
+        // Without the decay, only the count matters
+        settings.search.frecency_half_life = 0.0;
+        let results = search(&settings, all_history).rank(&state(""), now).await;
+        assert_eq!(commands(&results), ["make", "cargo build"]);
+    }
+
+    #[tokio::test]
+    async fn frequency_can_be_ignored() {
+        let now = OffsetDateTime::now_utc();
+        let mut settings = Settings::utc();
+        let all_history = vec![
+            (history("make", Duration::days(3), now), 50),
+            (history("cargo build", Duration::days(1), now), 2),
+        ];
+
+        let results = search(&settings, all_history.clone())
+            .rank(&state(""), now)
+            .await;
+        assert_eq!(commands(&results), ["make", "cargo build"]);
+
+        settings.search.frecency_frequency_weight = 0.0;
+        let results = search(&settings, all_history).rank(&state(""), now).await;
+        assert_eq!(commands(&results), ["cargo build", "make"]);
+    }
+
+    #[tokio::test]
+    async fn excluded_terms_are_left_out() {
+        let now = OffsetDateTime::now_utc();
+        let settings = Settings::utc();
+        let search = search(
+            &settings,
+            vec![
+                (history("cargo test", Duration::days(1), now), 5),
+                (history("cargo build", Duration::days(1), now), 3),
+                (history("cargo bench", Duration::days(1), now), 1),
+            ],
+        );
+
+        let results = search.rank(&state("cargo !test -bench"), now).await;
+        assert_eq!(commands(&results), ["cargo build"]);
+
+        assert_eq!(
+            search.get_highlight_indices("cargo build", "car !test"),
+            [0, 1, 2]
+        );
+    }
+}
@@ -0,0 +1,155 @@
+use async_trait::async_trait;
+use atuin_client::{
+    database::{Database, OptFilters},
+    history::{AUTHOR_FILTER_ALL_USER, History},
+    settings::SearchMode,
+};
+use eyre::Result;
+use regex::Regex;
+use tracing::{Level, instrument};
+
+use super::{SearchEngine, SearchState};
+
+/// The whole input is a regular expression, matched against commands in the database.
+///
+/// An input that doesn't compile is an error, carrying the [`regex::Error`], so the search can
+/// say what's wrong with it and keep the last results on screen while the user finishes typing.
+#[derive(Default)]
+pub struct Search {
+    /// The last pattern searched for, kept for highlighting
+    pattern: Option<Regex>,
+}
+
+#[async_trait]
+impl SearchEngine for Search {
+    #[instrument(skip_all, level = Level::TRACE, name = "regex_search", fields(query = %state.input.as_str()))]
+    async fn full_query(
+        &mut self,
+        state: &SearchState,
+        db: &mut dyn Database,
+    ) -> Result<Vec<History>> {
+        let pattern = Regex::new(state.input.as_str())?;
+
+        let results = db
+            .search(
+                SearchMode::Regex,
+                state.filter_mode,
+                &state.context,
+                pattern.as_str(),
+                OptFilters {
+                    limit: state.limit(),
+                    authors: vec![AUTHOR_FILTER_ALL_USER.to_string()],
+                    include_archive: state.include_archive == Some(true),
+                    context_boost: state.boost,
+                    exclude_containers: state.exclude_containers,
+                    before: state.before_filter(),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        self.pattern = Some(pattern);
+
+        Ok(results)
+    }
+
+    // The database stops scanning once it has found enough
+    fn pages(&self) -> bool {
+        true
+    }
+
+    #[instrument(skip_all, level = Level::TRACE, name = "regex_highlight")]
+    fn get_highlight_indices(&self, command: &str, search_input: &str) -> Vec<usize> {
+        match &self.pattern {
+            Some(pattern) if pattern.as_str() == search_input => {
+                highlight_indices(pattern, command)
+            }
+            _ => Regex::new(search_input)
+                .map(|pattern| highlight_indices(&pattern, command))
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// The bytes of `command` that `pattern` matched. With capture groups, only what the groups
+/// captured is highlighted, so a pattern can pick out the part of a command that matters.
+fn highlight_indices(pattern: &Regex, command: &str) -> Vec<usize> {
+    let mut indices: Vec<usize> = pattern
+        .captures_iter(command)
+        .flat_map(|captures| {
+            let groups: Vec<_> = captures.iter().skip(1).flatten().collect();
+            if groups.is_empty() {
+                captures.get(0).into_iter().collect()
+            } else {
+                groups
+            }
+        })
+        .flat_map(|m| m.range())
+        .collect();
+    indices.sort_unstable();
+    indices.dedup();
+    indices
+}
+
+/// What's wrong with a pattern, in a line short enough for the search's help bar.
+pub fn describe_error(error: &regex::Error) -> String {
+    match error {
+        // The full message repeats the pattern over several lines, pointing at the problem
+        regex::Error::Syntax(message) => {
+            let reason = message
+                .lines()
+                .rev()
+                .find_map(|line| line.strip_prefix("error: "))
+                .unwrap_or(message);
+            format!("Invalid regex: {reason}")
+        }
+        _ => format!("Invalid regex: {error}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn highlighted<'a>(pattern: &str, command: &'a str) -> Vec<&'a str> {
+        let pattern = Regex::new(pattern).unwrap();
+        let indices = highlight_indices(&pattern, command);
+
+        // Group the indices back into runs, to compare them as strings
+        let mut runs: Vec<&str> = Vec::new();
+        let mut run_start = None;
+        for (i, &index) in indices.iter().enumerate() {
+            let start = *run_start.get_or_insert(index);
+            if indices.get(i + 1) != Some(&(index + 1)) {
+                runs.push(&command[start..=index]);
+                run_start = None;
+            }
+        }
+        runs
+    }
+
+    #[test]
+    fn highlights_whole_matches_without_groups() {
+        assert_eq!(
+            highlighted("git pu[a-z]+", "git pull && git push"),
+            ["git pull", "git push"]
+        );
+        assert_eq!(highlighted("o+", "foo boo"), ["oo", "oo"]);
+        assert_eq!(highlighted("^ls", "ls -la"), ["ls"]);
+    }
+
+    #[test]
+    fn highlights_only_captures_with_groups() {
+        assert_eq!(
+            highlighted(r"ssh \w+@([\w.]+)", "ssh ellie@atuin.sh"),
+            ["atuin.sh"]
+        );
+        // Groups that didn't take part in the match are skipped
+        assert_eq!(highlighted("(a)|(b)", "ab"), ["ab"]);
+    }
+
+    #[test]
+    fn invalid_patterns_are_described_briefly() {
+        let error = Regex::new(&String::from("ls (")).unwrap_err();
+        assert_eq!(describe_error(&error), "Invalid regex: unclosed group");
+    }
+}
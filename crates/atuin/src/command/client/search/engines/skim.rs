@@ -1,11 +1,16 @@
-use std::path::Path;
+use std::{collections::HashSet, path::Path};
 
 use async_trait::async_trait;
 use atuin_client::{
-    database::Database,
-    history::{History, is_known_agent},
+    database::{
+        Database, allowed_by_filters, contains_term, exclude_containers, split_excluded_terms,
+        split_query_filters,
+    },
+    history::{History, host_of, is_known_agent},
+    ordering::boost_context,
     settings::FilterMode,
 };
+use atuin_common::utils;
 use eyre::Result;
 use fuzzy_matcher::{FuzzyMatcher, skim::SkimMatcherV2};
 use itertools::Itertools;
@@ -42,14 +47,30 @@ impl SearchEngine for Search {
             self.all_history = load_all_history(db).await;
         }
 
-        Ok(fuzzy_search(&self.engine, state, &self.all_history).await)
+        let (mut filters, _) = split_query_filters(state.input.as_str());
+        if state.exclude_containers {
+            exclude_containers(&mut filters);
+        }
+        let run_by = allowed_by_filters(db, &filters).await?;
+
+        let results = fuzzy_search(
+            &self.engine,
+            state,
+            &self.all_history,
+            run_by.as_ref(),
+            utils::now(),
+        )
+        .await;
+        Ok(boost_context(results, &state.context, state.boost))
     }
 
     #[instrument(skip_all, level = Level::TRACE, name = "skim_highlight")]
     fn get_highlight_indices(&self, command: &str, search_input: &str) -> Vec<usize> {
+        let (_, search_input) = split_query_filters(search_input);
+        let (search_input, _) = split_excluded_terms(&search_input);
         let (_, indices) = self
             .engine
-            .fuzzy_indices(command, search_input)
+            .fuzzy_indices(command, &search_input)
             .unwrap_or_default();
         indices
     }
@@ -66,11 +87,14 @@ async fn fuzzy_search(
     engine: &SkimMatcherV2,
     state: &SearchState,
     all_history: &[(History, i32)],
+    run_by: Option<&HashSet<String>>,
+    now: OffsetDateTime,
 ) -> Vec<History> {
     let mut set = Vec::with_capacity(200);
     let mut ranks = Vec::with_capacity(200);
-    let query = state.input.as_str();
-    let now = OffsetDateTime::now_utc();
+    let (filters, query) = split_query_filters(state.input.as_str());
+    let (query, excluded) = split_excluded_terms(&query);
+    let query = query.as_ref();
 
     for (i, (history, count)) in all_history.iter().enumerate() {
         if i % 256 == 0 {
@@ -79,69 +103,17 @@ async fn fuzzy_search(
         if is_known_agent(&history.author) {
             continue;
         }
-        let context = &state.context;
-        let git_root = context
-            .git_root
-            .as_ref()
-            .and_then(|git_root| git_root.to_str())
-            .unwrap_or(&context.cwd);
-        match state.filter_mode {
-            FilterMode::Global => {}
-            // we aggregate host by ',' separating them
-            FilterMode::Host
-                if history
-                    .hostname
-                    .split(',')
-                    .contains(&context.hostname.as_str()) => {}
-            // we aggregate session by concattenating them.
-            // sessions are 32 byte simple uuid formats
-            FilterMode::Session
-                if history
-                    .session
-                    .as_bytes()
-                    .chunks(32)
-                    .contains(&context.session.as_bytes()) => {}
-            // SessionPreload: include current session + global history from before session start
-            FilterMode::SessionPreload => {
-                let is_current_session = {
-                    history
-                        .session
-                        .as_bytes()
-                        .chunks(32)
-                        .any(|chunk| chunk == context.session.as_bytes())
-                };
-
-                if !is_current_session {
-                    let Ok(uuid) = uuid::Uuid::parse_str(&context.session) else {
-                        warn!("failed to parse session id '{}'", context.session);
-                        continue;
-                    };
-                    let Some(timestamp) = uuid.get_timestamp() else {
-                        warn!(
-                            "failed to get timestamp from uuid '{}'",
-                            uuid.as_hyphenated()
-                        );
-                        continue;
-                    };
-                    let (seconds, nanos) = timestamp.to_unix();
-                    let Ok(session_start) = time::OffsetDateTime::from_unix_timestamp_nanos(
-                        i128::from(seconds) * 1_000_000_000 + i128::from(nanos),
-                    ) else {
-                        warn!(
-                            "failed to create OffsetDateTime from second: {seconds}, nanosecond: {nanos}"
-                        );
-                        continue;
-                    };
-
-                    if history.timestamp >= session_start {
-                        continue;
-                    }
-                }
-            }
-            // we aggregate directory by ':' separating them
-            FilterMode::Directory if history.cwd.split(':').contains(&context.cwd.as_str()) => {}
-            FilterMode::Workspace if history.cwd.split(':').contains(&git_root) => {}
-            _ => continue,
+        if !in_filter(state, history)
+            || !filters.iter().all(|f| f.matches(history))
+            || run_by.is_some_and(|commands| !commands.contains(&history.command))
+        {
+            continue;
+        }
+        if excluded
+            .iter()
+            .any(|term| contains_term(&history.command, term))
+        {
+            continue;
         }
         #[allow(clippy::cast_lossless, clippy::cast_precision_loss)]
         if let Some((score, indices)) = engine.fuzzy_indices(&history.command, query) {
@@ -213,6 +185,87 @@ async fn fuzzy_search(
     set
 }
 
+/// Whether `history`, one of the grouped rows from `all_with_count`, passes the filter mode.
+pub(super) fn in_filter(state: &SearchState, history: &History) -> bool {
+    if state
+        .before
+        .is_some_and(|before| history.timestamp >= before)
+    {
+        return false;
+    }
+
+    let context = &state.context;
+    let git_root = context
+        .git_root
+        .as_ref()
+        .and_then(|git_root| git_root.to_str())
+        .unwrap_or(&context.cwd);
+    match state.filter_mode {
+        FilterMode::Global => {}
+        // we aggregate host by ',' separating them
+        FilterMode::Host
+            if history
+                .hostname
+                .split(',')
+                .contains(&context.hostname.as_str()) => {}
+        FilterMode::OtherHosts
+            if history.hostname.split(',').any(|hostname| {
+                !host_of(hostname).eq_ignore_ascii_case(host_of(&context.hostname))
+            }) => {}
+        // we aggregate session by concattenating them.
+        // sessions are 32 byte simple uuid formats
+        FilterMode::Session
+            if history
+                .session
+                .as_bytes()
+                .chunks(32)
+                .contains(&context.session.as_bytes()) => {}
+        // SessionPreload: include current session + global history from before session start
+        FilterMode::SessionPreload => {
+            let is_current_session = {
+                history
+                    .session
+                    .as_bytes()
+                    .chunks(32)
+                    .any(|chunk| chunk == context.session.as_bytes())
+            };
+
+            if !is_current_session {
+                let Ok(uuid) = uuid::Uuid::parse_str(&context.session) else {
+                    warn!("failed to parse session id '{}'", context.session);
+                    return false;
+                };
+                let Some(timestamp) = uuid.get_timestamp() else {
+                    warn!(
+                        "failed to get timestamp from uuid '{}'",
+                        uuid.as_hyphenated()
+                    );
+                    return false;
+                };
+                let (seconds, nanos) = timestamp.to_unix();
+                let Ok(session_start) = time::OffsetDateTime::from_unix_timestamp_nanos(
+                    i128::from(seconds) * 1_000_000_000 + i128::from(nanos),
+                ) else {
+                    warn!(
+                        "failed to create OffsetDateTime from second: {seconds}, nanosecond: {nanos}"
+                    );
+                    return false;
+                };
+
+                if history.timestamp >= session_start {
+                    return false;
+                }
+            }
+        }
+        // we aggregate directory by ':' separating them
+        FilterMode::Directory if history.cwd.split(':').contains(&context.cwd.as_str()) => {}
+        FilterMode::Workspace if history.cwd.split(':').contains(&git_root) => {}
+        _ => return false,
+    }
+
+    true
+}
+
 fn path_dist(a: &Path, b: &Path) -> usize {
     let mut a: Vec<_> = a.components().collect();
     let b: Vec<_> = b.components().collect();
@@ -0,0 +1,47 @@
+use async_trait::async_trait;
+use atuin_client::{
+    database::{Database, OptFilters},
+    history::{AUTHOR_FILTER_ALL_USER, History},
+    synonyms::SynonymIndex,
+};
+use eyre::Result;
+use tracing::{Level, instrument};
+
+use super::{SearchEngine, SearchState};
+
+#[derive(Default)]
+pub struct Search {
+    index: SynonymIndex,
+}
+
+#[async_trait]
+impl SearchEngine for Search {
+    #[instrument(skip_all, level = Level::TRACE, name = "synonym_search", fields(query = %state.input.as_str()))]
+    async fn full_query(
+        &mut self,
+        state: &SearchState,
+        db: &mut dyn Database,
+    ) -> Result<Vec<History>> {
+        self.index
+            .search(
+                db,
+                state.filter_mode,
+                &state.context,
+                state.input.as_str(),
+                OptFilters {
+                    limit: state.limit(),
+                    authors: vec![AUTHOR_FILTER_ALL_USER.to_string()],
+                    context_boost: state.boost,
+                    exclude_containers: state.exclude_containers,
+                    before: state.before_filter(),
+                    ..Default::default()
+                },
+            )
+            .await
+    }
+
+    fn get_highlight_indices(&self, _command: &str, _search_input: &str) -> Vec<usize> {
+        // Matches are on synonyms, so there are no characters to point at.
+        vec![]
+    }
+}
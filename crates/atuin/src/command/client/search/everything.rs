@@ -0,0 +1,583 @@
+//! Searching what's synced alongside the history: scripts, aliases, dotfile variables and
+//! key-value pairs, and the commands from any `[[sources]]`, along with the tasks of the
+//! project searched from. An input starting with `>` searches all of these instead of the
+//! history, or just one kind of them when its first word names it, as in `>alias git`.
+
+use std::cmp::Ordering;
+use std::time::Duration;
+
+use atuin_client::{
+    history::store::HistoryStore,
+    settings::{FilterMode, Settings, SourceRanking},
+};
+use atuin_common::{paths, utils};
+use atuin_dotfiles::store::{AliasStore, var::VarStore};
+use eyre::Result;
+use futures_util::future::join_all;
+use fuzzy_matcher::{FuzzyMatcher, skim::SkimMatcherV2};
+use ratatui::{
+    Frame,
+    backend::FromCrossterm,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, List, ListDirection, ListItem, ListState},
+};
+use tokio::task::JoinHandle;
+
+use super::sources::{Cache, Candidate, External, Provider};
+use super::tasks::Tasks;
+use crate::command::client::theme::{Meaning, Theme};
+
+/// What an input starts with to search everything else.
+pub const PREFIX: char = '>';
+
+/// The kv namespace that goes unnamed, as with `atuin kv`.
+const DEFAULT_NAMESPACE: &str = "default";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Snippet,
+    Alias,
+    Var,
+    Kv,
+    /// A command from one of the `[[sources]]`
+    Source,
+    /// A target, recipe or script of the project searched from
+    Task,
+}
+
+impl Kind {
+    const ALL: [Self; 6] = [
+        Self::Snippet,
+        Self::Alias,
+        Self::Var,
+        Self::Kv,
+        Self::Source,
+        Self::Task,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Snippet => "snippet",
+            Self::Alias => "alias",
+            Self::Var => "var",
+            Self::Kv => "kv",
+            Self::Source => "source",
+            Self::Task => "task",
+        }
+    }
+
+    /// Whether `word` names the kind after the `>`. Snippets are the scripts, so go by either.
+    fn named(self, word: &str) -> bool {
+        word == self.as_str() || (self == Self::Snippet && word == "script")
+    }
+}
+
+/// The input to search the next of the history, everything, and each kind in turn, for what
+/// `input` searches for.
+pub fn cycle(input: &str) -> String {
+    let Some((kind, query)) = parse(input) else {
+        return format!("{PREFIX}{input}");
+    };
+    let next = match kind {
+        None => Kind::ALL.first(),
+        Some(kind) => Kind::ALL.iter().skip_while(|k| **k != kind).nth(1),
+    };
+
+    match next {
+        Some(next) => format!("{PREFIX}{} {query}", next.as_str()),
+        None => query.to_string(),
+    }
+}
+
+/// What an input starting with `>` asks for: the kind to search, if its first word names one,
+/// and what to search for. `None` for any other input, which searches the history.
+pub fn parse(input: &str) -> Option<(Option<Kind>, &str)> {
+    let rest = input.strip_prefix(PREFIX)?;
+    let (word, query) = rest.split_once(' ').unwrap_or((rest, ""));
+
+    Some(match Kind::ALL.into_iter().find(|kind| kind.named(word)) {
+        Some(kind) => (Some(kind), query.trim()),
+        None => (None, rest.trim()),
+    })
+}
+
+/// Something synced that isn't history.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Item {
+    pub kind: Kind,
+    pub name: String,
+    /// What it stands for, or what it's for, shown beside the name
+    pub value: String,
+    /// The name of the source it's from, or the tool running a task, which badges it rather
+    /// than its kind
+    pub source: Option<String>,
+}
+
+impl Item {
+    /// What accepting it puts on the command line: how to run a snippet or an alias, how to
+    /// refer to a variable, and the value of a kv pair.
+    pub fn command(&self) -> String {
+        match self.kind {
+            Kind::Snippet => format!("atuin scripts run {}", self.name),
+            Kind::Alias => self.name.clone(),
+            Kind::Var => format!("${}", self.name),
+            Kind::Kv => self.value.clone(),
+            Kind::Source | Kind::Task => self.name.clone(),
+        }
+    }
+
+    /// What it's badged with in the list.
+    pub fn badge(&self) -> &str {
+        self.source.as_deref().unwrap_or(self.kind.as_str())
+    }
+
+    /// Whether accepting it runs it, as accepting history does. Variables and values are only
+    /// ever put on the command line, to be used in something.
+    pub fn runs(&self) -> bool {
+        matches!(
+            self.kind,
+            Kind::Snippet | Kind::Alias | Kind::Source | Kind::Task
+        )
+    }
+}
+
+/// Everything synced that `>` can search, after the tasks of the project it's searched from.
+/// Whatever can't be read is left out.
+pub async fn load(settings: &Settings, history_store: &HistoryStore) -> Vec<Item> {
+    let mut items = tasks().await;
+
+    for (kind, loaded) in [
+        (Kind::Snippet, snippets(settings).await),
+        (Kind::Alias, aliases(history_store).await),
+        (Kind::Var, vars(history_store).await),
+        (Kind::Kv, kv(settings).await),
+    ] {
+        match loaded {
+            Ok(loaded) => items.extend(loaded),
+            Err(e) => tracing::warn!("could not load {}s to search: {e}", kind.as_str()),
+        }
+    }
+
+    items.extend(sources(settings).await);
+
+    items
+}
+
+/// [`load`] running in the background, as the sources alone can take seconds, which typing
+/// mustn't wait for. Dropping it stops it.
+pub struct Loading(JoinHandle<Vec<Item>>);
+
+impl Loading {
+    pub fn spawn(settings: &Settings, history_store: &HistoryStore) -> Self {
+        let settings = settings.clone();
+        let history_store = history_store.clone();
+        Self(tokio::spawn(async move {
+            load(&settings, &history_store).await
+        }))
+    }
+
+    /// Wait for everything to be read, taking the task once it has. Never returns while
+    /// there's none.
+    pub async fn finished(task: &mut Option<Self>) -> Vec<Item> {
+        let Some(running) = task.as_mut() else {
+            return std::future::pending().await;
+        };
+
+        let items = (&mut running.0).await.unwrap_or_else(|e| {
+            tracing::warn!("could not load what to search: {e}");
+            Vec::new()
+        });
+        *task = None;
+
+        items
+    }
+}
+
+impl Drop for Loading {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// The commands the `[[sources]]` suggest from where the search runs, each source run at once.
+async fn sources(settings: &Settings) -> Vec<Item> {
+    let cache = Cache::new(paths::cache_dir().join("sources"));
+    let cwd = utils::get_current_dir();
+    let now = time::OffsetDateTime::now_utc();
+
+    let providers: Vec<_> = settings
+        .sources
+        .iter()
+        .map(|source| {
+            (
+                External(source.clone()),
+                Duration::from_secs(source.cache_ttl),
+            )
+        })
+        .collect();
+    let found = join_all(
+        providers
+            .iter()
+            .map(|(provider, ttl)| cache.candidates(provider, &cwd, *ttl, now)),
+    )
+    .await;
+
+    providers
+        .iter()
+        .zip(found)
+        .flat_map(|((provider, _), found)| provided(Kind::Source, provider, found))
+        .collect()
+}
+
+/// The tasks of the project searched from.
+async fn tasks() -> Vec<Item> {
+    let cwd = utils::get_current_dir();
+    let found = join_all(Tasks::ALL.iter().map(|tasks| tasks.candidates(&cwd))).await;
+
+    Tasks::ALL
+        .iter()
+        .zip(found)
+        .flat_map(|(tasks, found)| provided(Kind::Task, tasks, found))
+        .collect()
+}
+
+/// What `provider` found, as items of `kind`, or none if it couldn't find them.
+fn provided(kind: Kind, provider: &dyn Provider, found: Result<Vec<Candidate>>) -> Vec<Item> {
+    let found = found.unwrap_or_else(|e| {
+        tracing::warn!("could not load the commands of {}: {e}", provider.name());
+        Vec::new()
+    });
+
+    found
+        .into_iter()
+        .map(|candidate| Item {
+            kind,
+            name: candidate.command,
+            value: candidate.description,
+            source: Some(provider.name().to_string()),
+        })
+        .collect()
+}
+
+async fn snippets(settings: &Settings) -> Result<Vec<Item>> {
+    let db = atuin_scripts::database::Database::new(settings.scripts.db_path.clone(), 1.0).await?;
+
+    Ok(db
+        .list()
+        .await?
+        .into_iter()
+        .map(|script| Item {
+            kind: Kind::Snippet,
+            value: if script.description.is_empty() {
+                script.script.lines().next().unwrap_or_default().to_string()
+            } else {
+                script.description
+            },
+            name: script.name,
+            source: None,
+        })
+        .collect())
+}
+
+async fn aliases(history_store: &HistoryStore) -> Result<Vec<Item>> {
+    let store = AliasStore::new(
+        history_store.store.clone(),
+        history_store.host_id,
+        history_store.encryption_key,
+    );
+
+    Ok(store
+        .aliases()
+        .await?
+        .into_iter()
+        .map(|alias| Item {
+            kind: Kind::Alias,
+            name: alias.name,
+            value: alias.value,
+            source: None,
+        })
+        .collect())
+}
+
+async fn vars(history_store: &HistoryStore) -> Result<Vec<Item>> {
+    let store = VarStore::new(
+        history_store.store.clone(),
+        history_store.host_id,
+        history_store.encryption_key,
+    );
+
+    Ok(store
+        .vars()
+        .await?
+        .into_iter()
+        .map(|var| Item {
+            kind: Kind::Var,
+            name: var.name,
+            value: var.value,
+            source: None,
+        })
+        .collect())
+}
+
+async fn kv(settings: &Settings) -> Result<Vec<Item>> {
+    let db = atuin_kv::database::Database::new(settings.kv.db_path.clone(), 1.0).await?;
+
+    Ok(db
+        .list(None)
+        .await?
+        .into_iter()
+        .map(|entry| Item {
+            kind: Kind::Kv,
+            name: if entry.namespace == DEFAULT_NAMESPACE {
+                entry.key
+            } else {
+                format!("{}/{}", entry.namespace, entry.key)
+            },
+            value: entry.value,
+            source: None,
+        })
+        .collect())
+}
+
+/// The items of `kind`, or of any kind, that match `query`, ranked as `ranking` has it in
+/// `mode`: the pinned first, then the best matches, each weighed by where it's from. With no
+/// query, all of them, heaviest first and otherwise in the order they were loaded. What
+/// weighs nothing is left out, unless its kind is asked for.
+#[allow(clippy::cast_precision_loss)]
+pub fn search(
+    items: &[Item],
+    kind: Option<Kind>,
+    query: &str,
+    ranking: &SourceRanking,
+    mode: FilterMode,
+) -> Vec<Item> {
+    let matcher = SkimMatcherV2::default();
+    let mut ranked: Vec<(usize, f64, &Item)> = items
+        .iter()
+        .filter(|item| kind.is_none_or(|kind| item.kind == kind))
+        .filter_map(|item| {
+            let names = [item.badge(), item.kind.as_str()];
+            let weight = match ranking.weight(mode, &names) {
+                weight if weight > 0.0 => weight,
+                _ if kind.is_some() => 1.0,
+                _ => return None,
+            };
+
+            let score = if query.is_empty() {
+                1
+            } else {
+                // A match on the name counts for more than one on what it stands for
+                let name = matcher
+                    .fuzzy_match(&item.name, query)
+                    .map(|score| score * 2);
+                let value = matcher.fuzzy_match(&item.value, query);
+                name.max(value)?
+            };
+            let pinned = ranking.pinned(mode, &names).unwrap_or(usize::MAX);
+
+            Some((pinned, score as f64 * weight, item))
+        })
+        .collect();
+    ranked.sort_by(|a, b| {
+        a.0.cmp(&b.0)
+            .then_with(|| b.1.total_cmp(&a.1))
+            .then_with(|| {
+                if query.is_empty() {
+                    Ordering::Equal
+                } else {
+                    a.2.name.cmp(&b.2.name)
+                }
+            })
+    });
+
+    ranked
+        .into_iter()
+        .map(|(_, _, item)| item.clone())
+        .collect()
+}
+
+/// The title over the items, naming the kind searched.
+pub fn title(kind: Option<Kind>) -> String {
+    format!(" Synced · {} ", kind.map_or("everything", Kind::as_str))
+}
+
+/// Draw `items` where the results go, with the `selected` one highlighted.
+pub fn draw(
+    f: &mut Frame,
+    area: Rect,
+    items: &[Item],
+    selected: usize,
+    invert: bool,
+    block: Option<Block<'_>>,
+    theme: &Theme,
+) {
+    let kind_style = Style::from_crossterm(theme.as_style(Meaning::Annotation));
+    // Tasks stand out, being what's runnable right where the search is
+    let task_style = Style::from_crossterm(theme.as_style(Meaning::Guidance));
+    let rows: Vec<ListItem> = items
+        .iter()
+        .map(|item| {
+            ListItem::new(Line::from(vec![
+                Span::styled(
+                    format!(" {:<8}", item.badge()),
+                    if item.kind == Kind::Task {
+                        task_style
+                    } else {
+                        kind_style
+                    },
+                ),
+                Span::styled(
+                    item.name.clone(),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
+                Span::raw("  "),
+                Span::styled(item.value.clone(), kind_style),
+            ]))
+        })
+        .collect();
+
+    let mut list = List::new(rows)
+        .style(Style::from_crossterm(theme.get_base()))
+        .highlight_style(Style::from_crossterm(theme.as_style(Meaning::Selection)));
+    if invert {
+        list = list.direction(ListDirection::BottomToTop);
+    }
+    if let Some(block) = block {
+        list = list.block(block);
+    }
+
+    let mut state = ListState::default().with_selected(Some(selected));
+    f.render_stateful_widget(list, area, &mut state);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(kind: Kind, name: &str, value: &str) -> Item {
+        Item {
+            kind,
+            name: name.to_string(),
+            value: value.to_string(),
+            source: None,
+        }
+    }
+
+    #[test]
+    fn scopes_are_named_after_the_prefix() {
+        assert_eq!(parse("git status"), None);
+        assert_eq!(parse(">"), Some((None, "")));
+        assert_eq!(parse(">deploy"), Some((None, "deploy")));
+        assert_eq!(parse(">alias gs"), Some((Some(Kind::Alias), "gs")));
+        assert_eq!(parse(">alias"), Some((Some(Kind::Alias), "")));
+        assert_eq!(
+            parse(">script deploy"),
+            Some((Some(Kind::Snippet), "deploy"))
+        );
+        assert_eq!(
+            parse(">snippet  deploy "),
+            Some((Some(Kind::Snippet), "deploy"))
+        );
+        assert_eq!(parse(">kv token"), Some((Some(Kind::Kv), "token")));
+        assert_eq!(parse(">source up"), Some((Some(Kind::Source), "up")));
+        assert_eq!(parse(">task test"), Some((Some(Kind::Task), "test")));
+    }
+
+    #[test]
+    fn matches_on_names_come_first() {
+        let items = [
+            item(Kind::Kv, "editor", "gs-config"),
+            item(Kind::Alias, "gs", "git status"),
+            item(Kind::Var, "EDITOR", "hx"),
+        ];
+
+        let ranking = SourceRanking::default();
+        let found = search(&items, None, "gs", &ranking, FilterMode::Global);
+        assert_eq!(found[0].name, "gs");
+        assert_eq!(found[1].name, "editor");
+        assert_eq!(found.len(), 2);
+
+        let found = search(&items, Some(Kind::Var), "", &ranking, FilterMode::Global);
+        assert_eq!(found, [item(Kind::Var, "EDITOR", "hx")]);
+    }
+
+    #[test]
+    fn ranking_weighs_and_pins_by_source() {
+        let items = [
+            item(Kind::Alias, "deploy", "kubectl apply"),
+            item(Kind::Kv, "deploy-token", "abc123"),
+            Item {
+                source: Some("make".to_string()),
+                ..item(Kind::Task, "make deploy", "")
+            },
+        ];
+        let ranking: SourceRanking = serde_json::from_str(
+            r#"{"weights": {"kv": 0}, "filter_modes": {"directory": {"pinned": ["task"]}}}"#,
+        )
+        .unwrap();
+        let names = |found: Vec<Item>| found.into_iter().map(|i| i.name).collect::<Vec<_>>();
+
+        assert_eq!(
+            names(search(&items, None, "", &ranking, FilterMode::Global)),
+            ["deploy", "make deploy"]
+        );
+        assert_eq!(
+            names(search(
+                &items,
+                None,
+                "deploy",
+                &ranking,
+                FilterMode::Directory
+            )),
+            ["make deploy", "deploy"]
+        );
+        // Asked for by name, what weighs nothing is still found
+        assert_eq!(
+            names(search(
+                &items,
+                Some(Kind::Kv),
+                "",
+                &ranking,
+                FilterMode::Global
+            )),
+            ["deploy-token"]
+        );
+    }
+
+    #[test]
+    fn cycling_steps_through_the_kinds() {
+        assert_eq!(cycle("deploy"), ">deploy");
+        assert_eq!(cycle(">deploy"), ">snippet deploy");
+        assert_eq!(cycle(">snippet deploy"), ">alias deploy");
+        assert_eq!(cycle(">source"), ">task ");
+        assert_eq!(cycle(">task deploy"), "deploy");
+    }
+
+    #[test]
+    fn accepting_depends_on_the_kind() {
+        let snippet = item(Kind::Snippet, "deploy", "Ship it");
+        assert_eq!(snippet.command(), "atuin scripts run deploy");
+        assert!(snippet.runs());
+
+        assert_eq!(item(Kind::Alias, "gs", "git status").command(), "gs");
+
+        let var = item(Kind::Var, "EDITOR", "hx");
+        assert_eq!(var.command(), "$EDITOR");
+        assert!(!var.runs());
+
+        let kv = item(Kind::Kv, "token", "abc123");
+        assert_eq!(kv.command(), "abc123");
+        assert!(!kv.runs());
+
+        let make = Item {
+            source: Some("make".to_string()),
+            ..item(Kind::Source, "make build", "")
+        };
+        assert_eq!(make.command(), "make build");
+        assert_eq!(make.badge(), "make");
+        assert!(make.runs());
+        assert_eq!(kv.badge(), "kv");
+    }
+}
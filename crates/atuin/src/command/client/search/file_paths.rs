@@ -0,0 +1,131 @@
+//! Paths in commands, such as the file a command edited, which may have gone since it ran.
+//!
+//! A word counts as a path if it has a `/` in it, starts with `~` or `.`, or names something
+//! in the directory the command ran in. Flags, URLs and words with globs or variables in them
+//! don't, though the value of `--flag=value` or `key=value` can.
+
+use std::path::{Component, Path, PathBuf};
+
+use atuin_common::paths::home_dir;
+
+/// A path in a command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FoundPath {
+    /// The path as the command has it.
+    pub written: String,
+    /// Where it points, from the directory the command ran in.
+    pub resolved: PathBuf,
+}
+
+impl FoundPath {
+    pub fn exists(&self) -> bool {
+        self.resolved.exists()
+    }
+}
+
+/// The paths in `command`, run from `cwd`, in order, each once.
+pub fn find(command: &str, cwd: &str) -> Vec<FoundPath> {
+    let words = shlex::split(command)
+        .unwrap_or_else(|| command.split_whitespace().map(str::to_string).collect());
+
+    let mut paths: Vec<FoundPath> = Vec::new();
+    for (n, word) in words.iter().enumerate() {
+        let word = match word.split_once('=') {
+            Some((_, value)) => value,
+            None if word.starts_with('-') => continue,
+            None => word.as_str(),
+        };
+        if word.is_empty()
+            || word.contains("://")
+            || word.contains(['$', '*', '?', '`', '{'])
+            || paths.iter().any(|p| p.written == word)
+        {
+            continue;
+        }
+
+        let resolved = resolve(word, cwd);
+        let looks_like_a_path = word.contains('/') || word.starts_with(['~', '.']);
+        // The program is only a path when it's run by one, like `./configure`
+        let is_path = if n == 0 {
+            word.contains('/')
+        } else {
+            looks_like_a_path || resolved.exists()
+        };
+        if is_path {
+            paths.push(FoundPath {
+                written: word.to_string(),
+                resolved,
+            });
+        }
+    }
+    paths
+}
+
+/// Where `path` points from `cwd`, with `~` for the home directory, and without any `.` or
+/// `..` in the way.
+fn resolve(path: &str, cwd: &str) -> PathBuf {
+    let joined = match path.strip_prefix('~') {
+        Some("") => home_dir(),
+        Some(rest) if rest.starts_with('/') => home_dir().join(&rest[1..]),
+        _ => Path::new(cwd).join(path),
+    };
+
+    let mut resolved = PathBuf::new();
+    for component in joined.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            component => resolved.push(component),
+        }
+    }
+    resolved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn written(paths: &[FoundPath]) -> Vec<&str> {
+        paths.iter().map(|p| p.written.as_str()).collect()
+    }
+
+    #[test]
+    fn paths_are_found() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("notes.md"), "").unwrap();
+        let cwd = dir.path().to_str().unwrap();
+
+        let paths = find(
+            "./build.sh -o out/app --config=~/.apprc notes.md release notes.md",
+            cwd,
+        );
+        assert_eq!(
+            written(&paths),
+            ["./build.sh", "out/app", "~/.apprc", "notes.md"]
+        );
+        assert_eq!(paths[0].resolved, dir.path().join("build.sh"));
+        assert_eq!(paths[1].resolved, dir.path().join("out/app"));
+        assert_eq!(paths[2].resolved, home_dir().join(".apprc"));
+        assert!(paths[3].exists());
+        assert!(!paths[1].exists());
+
+        assert_eq!(
+            find("cat ../a.txt", "/home/me/src")[0].resolved,
+            Path::new("/home/me/a.txt")
+        );
+        assert_eq!(
+            written(&find("dd if=/dev/zero of=/tmp/disk.img bs=1M", "/")),
+            ["/dev/zero", "/tmp/disk.img"]
+        );
+    }
+
+    #[test]
+    fn other_words_are_not_paths() {
+        assert!(find("ls", "/").is_empty());
+        assert!(find("curl https://example.com/a/b", "/").is_empty());
+        assert!(find("rm *.log $HOME/x -rf", "/").is_empty());
+        assert!(find("cargo test --release", "/").is_empty());
+    }
+}
@@ -1,11 +1,13 @@
+use std::collections::HashMap;
 use std::time::Duration;
 
 use super::duration::format_duration;
 use super::engines::SearchEngine;
-use super::selection_ext::get_selection_style;
+use super::selection_ext::{apply_selection, get_selection_style};
 use super::syntax;
 use atuin_client::{
-    history::History,
+    history::{History, HistoryId, host_of},
+    host::HostNames,
     settings::{UiColumn, UiColumnType},
     theme::{Meaning, Theme},
 };
@@ -18,11 +20,42 @@ use itertools::Itertools;
 use ratatui::{
     backend::FromCrossterm,
     buffer::Buffer,
-    layout::Rect,
-    style::Style,
+    layout::{Position, Rect},
+    style::{Modifier, Style},
     widgets::{Block, StatefulWidget, Widget},
 };
 use time::OffsetDateTime;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Stands in for line breaks when a multi-line command is shown on one line.
+pub const LINE_BREAK_MARKER: char = '⏎';
+
+/// Shown beside entries marked for acting on together.
+pub const MARKED_MARKER: char = '*';
+/// Beside entries run on another host
+pub const OTHER_HOST_MARKER: char = '@';
+/// Beside pinned commands
+pub const PINNED_MARKER: char = '★';
+/// Before a run listed under its command's row
+pub const RUN_MARKER: &str = "↳ ";
+
+/// How many columns the selected command moves each time it's scrolled sideways.
+const SCROLL_STEP: usize = 8;
+
+/// `command` as a single line: whitespace collapsed, control characters
+/// escaped, and line breaks shown as [`LINE_BREAK_MARKER`] so multi-line
+/// commands stand out.
+pub fn one_line(command: &str) -> String {
+    command
+        .lines()
+        .map(|line| {
+            line.escape_non_printable()
+                .split_ascii_whitespace()
+                .join(" ")
+        })
+        .filter(|line| !line.is_empty())
+        .join(&format!(" {LINE_BREAK_MARKER} "))
+}
 
 pub struct HistoryHighlighter<'a> {
     pub engine: &'a dyn SearchEngine,
@@ -47,6 +80,18 @@ pub struct HistoryList<'a> {
     syntax_highlight: bool,
     /// Columns to display (in order, after the left padding)
     columns: &'a [UiColumn],
+    /// Friendly names for the host column
+    host_names: Option<&'a HostNames>,
+    /// Whether to number the rows the numeric shortcuts reach
+    numeric_shortcuts: bool,
+    /// The host to compare entries with, to point out those run elsewhere
+    local_host: Option<&'a str>,
+    /// Commands pinned above the rest
+    pinned: &'a [String],
+    /// Whether the selected command wraps onto more rows rather than being shortened
+    wrap_selected: bool,
+    /// How many times each command was run, to show beside it
+    run_counts: Option<&'a HashMap<String, i64>>,
 }
 
 #[derive(Default)]
@@ -54,6 +99,30 @@ pub struct ListState {
     offset: usize,
     selected: usize,
     max_entries: usize,
+    /// Entries marked for acting on together, in the order they were marked.
+    /// Kept as entries rather than rows, so they survive searching again.
+    marked: Vec<History>,
+    /// How many columns the selected command is scrolled sideways, to read the end of one
+    /// too long for the list.
+    scroll: usize,
+    /// How far the selected command could scroll when it was last drawn.
+    max_scroll: usize,
+    /// Where the list was last drawn, and which way up, to find the entry under the mouse.
+    area: Rect,
+    inverted: bool,
+    /// How many entries were last drawn, and how many rows the selected one took.
+    drawn: usize,
+    selected_rows: u16,
+    /// The result whose runs are listed under it, if one is.
+    expanded: Option<Expanded>,
+}
+
+/// A result with its runs listed under it.
+pub struct Expanded {
+    /// The result's own entry
+    pub group: HistoryId,
+    /// Its runs, in the rows below it
+    pub runs: Vec<HistoryId>,
 }
 
 impl ListState {
@@ -69,9 +138,107 @@ impl ListState {
         self.offset
     }
 
+    /// Select the entry at `index`, which starts out scrolled back to its start.
     pub fn select(&mut self, index: usize) {
+        if index != self.selected {
+            self.scroll = 0;
+        }
         self.selected = index;
     }
+
+    /// Back to the top of the list, keeping the marks
+    pub fn reset(&mut self) {
+        self.offset = 0;
+        self.selected = 0;
+        self.scroll = 0;
+    }
+
+    pub fn scroll(&self) -> usize {
+        self.scroll
+    }
+
+    /// Scroll the selected command to show more of its start.
+    pub fn scroll_left(&mut self) {
+        self.scroll = self.scroll.saturating_sub(SCROLL_STEP);
+    }
+
+    /// Scroll the selected command to show more of its end, as far as there is more.
+    pub fn scroll_right(&mut self) {
+        self.scroll = (self.scroll + SCROLL_STEP).min(self.max_scroll);
+    }
+
+    /// The entry drawn at `column` and `row` of the screen when the list was last drawn,
+    /// if there was one there.
+    pub fn index_at(&self, column: u16, row: u16) -> Option<usize> {
+        if !self.area.contains(Position::new(column, row)) {
+            return None;
+        }
+        // Rows count up from the bottom of the list unless it's inverted
+        let mut rows_in = if self.inverted {
+            row - self.area.top()
+        } else {
+            self.area.bottom() - row - 1
+        };
+        for index in self.offset..self.offset + self.drawn {
+            let rows = if index == self.selected {
+                self.selected_rows
+            } else {
+                1
+            };
+            if rows_in < rows {
+                return Some(index);
+            }
+            rows_in -= rows;
+        }
+        None
+    }
+
+    /// List `runs` under the row of `group`, in place of any listed before.
+    pub fn expand(&mut self, group: HistoryId, runs: Vec<HistoryId>) {
+        self.expanded = Some(Expanded { group, runs });
+    }
+
+    /// Stop listing runs under a result, returning which it was.
+    pub fn collapse(&mut self) -> Option<Expanded> {
+        self.expanded.take()
+    }
+
+    pub fn expanded(&self) -> Option<&Expanded> {
+        self.expanded.as_ref()
+    }
+
+    /// Whether `entry` is listed as one of the runs under a result
+    pub fn is_run(&self, entry: &History) -> bool {
+        self.expanded
+            .as_ref()
+            .is_some_and(|e| e.runs.contains(&entry.id))
+    }
+
+    pub fn marked(&self) -> &[History] {
+        &self.marked
+    }
+
+    pub fn is_marked(&self, entry: &History) -> bool {
+        self.marked.iter().any(|m| m.id == entry.id)
+    }
+
+    /// Mark `entry`, or unmark it if it already is
+    pub fn toggle_mark(&mut self, entry: &History) {
+        if self.is_marked(entry) {
+            self.marked.retain(|m| m.id != entry.id);
+        } else {
+            self.marked.push(entry.clone());
+        }
+    }
+
+    /// Keep only the marks of entries `keep` accepts, as when some are deleted
+    pub fn retain_marked(&mut self, keep: impl FnMut(&History) -> bool) {
+        self.marked.retain(keep);
+    }
+
+    pub fn take_marked(&mut self) -> Vec<History> {
+        std::mem::take(&mut self.marked)
+    }
 }
 
 impl StatefulWidget for HistoryList<'_> {
@@ -84,20 +251,42 @@ impl StatefulWidget for HistoryList<'_> {
             inner_area
         });
 
+        state.area = list_area;
+        state.inverted = self.inverted;
+        state.drawn = 0;
         if list_area.width < 1 || list_area.height < 1 || self.history.is_empty() {
             return;
         }
         let list_height = list_area.height as usize;
 
-        let (start, end) = self.get_items_bounds(state.selected, state.offset, list_height);
+        let selected_rows = if self.wrap_selected {
+            self.history
+                .get(state.selected)
+                .map_or(1, |h| self.rows_for(h, state, list_area.width))
+                .min(list_area.height)
+        } else {
+            1
+        };
+
+        let (start, end) = self.get_items_bounds(
+            state.selected,
+            state.offset,
+            list_height,
+            usize::from(selected_rows),
+        );
         state.offset = start;
         state.max_entries = end - start;
+        state.max_scroll = 0;
+        state.drawn = end.min(self.history.len()).saturating_sub(start);
+        state.selected_rows = selected_rows;
 
         let mut s = DrawState {
             buf,
             list_area,
             x: 0,
             y: 0,
+            index: start,
+            selected_rows,
             state,
             inverted: self.inverted,
             now: &self.now,
@@ -105,9 +294,21 @@ impl StatefulWidget for HistoryList<'_> {
             history_highlighter: self.history_highlighter,
             syntax_highlight: self.syntax_highlight,
             columns: self.columns,
+            host_names: self.host_names,
+            numeric_shortcuts: self.numeric_shortcuts,
+            local_host: self.local_host,
+            pinned: self.pinned,
+            run_counts: self.run_counts,
         };
 
-        for item in self.history.iter().skip(state.offset).take(end - start) {
+        for (index, item) in self
+            .history
+            .iter()
+            .enumerate()
+            .skip(start)
+            .take(end - start)
+        {
+            s.index = index;
             s.render_row(item);
 
             // reset line
@@ -137,6 +338,12 @@ impl<'a> HistoryList<'a> {
             history_highlighter,
             syntax_highlight,
             columns,
+            host_names: None,
+            numeric_shortcuts: false,
+            local_host: None,
+            pinned: &[],
+            wrap_selected: false,
+            run_counts: None,
         }
     }
 
@@ -145,7 +352,96 @@ impl<'a> HistoryList<'a> {
         self
     }
 
-    fn get_items_bounds(&self, selected: usize, offset: usize, height: usize) -> (usize, usize) {
+    pub fn host_names(mut self, host_names: &'a HostNames) -> Self {
+        self.host_names = Some(host_names);
+        self
+    }
+
+    pub fn numeric_shortcuts(mut self, numeric_shortcuts: bool) -> Self {
+        self.numeric_shortcuts = numeric_shortcuts;
+        self
+    }
+
+    /// Mark the entries that weren't run on `hostname`
+    pub fn local_host(mut self, hostname: &'a str) -> Self {
+        self.local_host = Some(hostname);
+        self
+    }
+
+    /// Mark the entries whose command is in `pinned`
+    pub fn pinned(mut self, pinned: &'a [String]) -> Self {
+        self.pinned = pinned;
+        self
+    }
+
+    /// Wrap the selected command onto as many rows as it needs
+    pub fn wrap_selected(mut self, wrap_selected: bool) -> Self {
+        self.wrap_selected = wrap_selected;
+        self
+    }
+
+    /// Show how many times each command in `run_counts` was run, when it was more than once
+    pub fn run_counts(mut self, run_counts: &'a HashMap<String, i64>) -> Self {
+        self.run_counts = Some(run_counts);
+        self
+    }
+
+    /// Where the command column starts in a row `width` wide, if there is one.
+    fn command_x(&self, width: u16) -> Option<u16> {
+        // As laid out by `DrawState::render_row`
+        let padding_width: u16 = if self.numeric_shortcuts { 2 } else { 1 };
+        let fixed_width: u16 = self
+            .columns
+            .iter()
+            .filter(|c| !c.expand)
+            .map(|c| c.width + 1)
+            .sum();
+        let expand_width = width.saturating_sub(padding_width + fixed_width);
+
+        let mut x = padding_width;
+        for column in self.columns {
+            if column.column_type == UiColumnType::Command {
+                return Some(x.min(width));
+            }
+            let column_width = if column.expand {
+                expand_width
+            } else {
+                column.width
+            };
+            x = x.saturating_add(column_width + 1);
+        }
+        None
+    }
+
+    /// How many rows `h` takes when it's selected and wrapped, in a list `width` wide.
+    fn rows_for(&self, h: &History, state: &ListState, width: u16) -> u16 {
+        // The command wraps beside its badge, if it has one
+        let badge_width = badge(h, state, self.run_counts)
+            .map_or(0, |(badge, _)| UnicodeWidthStr::width(badge.as_str()));
+        let Some(avail) = self
+            .command_x(width)
+            .map(|x| usize::from(width - x).saturating_sub(badge_width))
+            .filter(|&avail| avail > 0)
+        else {
+            return 1;
+        };
+        let command_width = UnicodeWidthStr::width(one_line(&h.command).as_str());
+        u16::try_from(command_width.div_ceil(avail).max(1)).unwrap_or(u16::MAX)
+    }
+
+    /// The entries to draw, from the first to one past the last, in a list `height` rows high
+    /// where the selected entry takes `selected_rows` of them and every other entry one.
+    fn get_items_bounds(
+        &self,
+        selected: usize,
+        offset: usize,
+        height: usize,
+        selected_rows: usize,
+    ) -> (usize, usize) {
+        // The rows the selected entry takes beyond its first leave room for fewer entries
+        let height = height
+            .saturating_sub(selected_rows.saturating_sub(1))
+            .max(1);
         let offset = offset.min(self.history.len().saturating_sub(1));
 
         // let max_scroll_space = height.min(10).min(self.history.len() - selected);
@@ -170,29 +466,57 @@ struct DrawState<'a> {
     list_area: Rect,
     x: u16,
     y: u16,
-    state: &'a ListState,
+    /// The entry being drawn
+    index: usize,
+    /// How many rows the selected entry takes
+    selected_rows: u16,
+    state: &'a mut ListState,
     inverted: bool,
     now: &'a dyn Fn() -> OffsetDateTime,
     theme: &'a Theme,
     history_highlighter: HistoryHighlighter<'a>,
     syntax_highlight: bool,
     columns: &'a [UiColumn],
+    host_names: Option<&'a HostNames>,
+    numeric_shortcuts: bool,
+    local_host: Option<&'a str>,
+    pinned: &'a [String],
+    run_counts: Option<&'a HashMap<String, i64>>,
 }
 
 impl DrawState<'_> {
     /// Check if current row is selected
     fn is_selected(&self) -> bool {
-        self.y as usize + self.state.offset == self.state.selected()
+        self.index == self.state.selected()
+    }
+
+    /// The numeric shortcut that returns the current row, if one reaches it
+    fn shortcut(&self) -> Option<usize> {
+        self.index
+            .checked_sub(self.state.selected())
+            .filter(|n| (1..=9).contains(n))
     }
 
     /// Render a complete row for a history item based on configured columns.
     fn render_row(&mut self, h: &History) {
-        // Draw left padding (1 space)
-        self.left_padding();
+        let rows = if self.is_selected() {
+            self.selected_rows
+        } else {
+            1
+        };
+        // The rows of a wrapped entry are counted from the bottom of the list when it isn't
+        // inverted, so its first row is the last of them
+        let last_row = self.y + rows - 1;
+        if !self.inverted {
+            self.y = last_row;
+        }
+
+        // Draw left padding (1 space), which shows whether the entry is marked
+        self.left_padding(h);
 
         // Calculate the width for the expanding column
         // Fixed columns use their configured width + 1 (trailing space)
-        let padding_width: u16 = 1;
+        let padding_width: u16 = if self.numeric_shortcuts { 2 } else { 1 };
         let fixed_width: u16 = self
             .columns
             .iter()
@@ -229,11 +553,70 @@ impl DrawState<'_> {
 
         // Fill remaining row width with selection background if selected
         self.fill_row_remainder();
+        self.y = last_row;
+    }
+
+    /// Move on to the next row of a wrapped entry, drawing blank columns up to `indent`.
+    fn wrap_row(&mut self, indent: u16) {
+        self.fill_row_remainder();
+        if self.inverted {
+            self.y += 1;
+        } else {
+            self.y -= 1;
+        }
+        self.x = 0;
+        self.draw(&" ".repeat(usize::from(indent)), Style::default());
     }
 
-    /// Draw 1 space left padding
-    fn left_padding(&mut self) {
-        self.draw(" ", Style::default());
+    /// Whether `h` was run on a host other than this one
+    fn other_host(&self, h: &History) -> bool {
+        self.local_host
+            .is_some_and(|local| !host_of(&h.hostname).eq_ignore_ascii_case(host_of(local)))
+    }
+
+    /// Draw 1 space left padding, or the marker for marked entries. With numeric shortcuts on
+    /// the padding is 2 wide, and numbers the rows they reach. Pinned entries, then entries from
+    /// other hosts, are marked in the last column of the padding, if it's free.
+    fn left_padding(&mut self, h: &History) {
+        let pinned = self.pinned.contains(&h.command);
+        let other_host = self.other_host(h);
+
+        if self.state.is_marked(h) {
+            let style = self.theme.as_style(Meaning::Important);
+            self.draw(
+                &MARKED_MARKER.to_string(),
+                Style::from_crossterm(style).add_modifier(Modifier::BOLD),
+            );
+        } else if let Some(n) = self.shortcut().filter(|_| self.numeric_shortcuts) {
+            let style = self.theme.as_style(Meaning::Shortcut);
+            self.draw(&n.to_string(), Style::from_crossterm(style));
+        } else if pinned && !self.numeric_shortcuts {
+            self.pinned_marker();
+        } else if other_host && !self.numeric_shortcuts {
+            self.other_host_marker();
+        } else {
+            self.draw(" ", Style::default());
+        }
+
+        if self.numeric_shortcuts {
+            if pinned {
+                self.pinned_marker();
+            } else if other_host {
+                self.other_host_marker();
+            } else {
+                self.draw(" ", Style::default());
+            }
+        }
+    }
+
+    fn other_host_marker(&mut self) {
+        let style = self.theme.as_style(Meaning::OtherHost);
+        self.draw(&OTHER_HOST_MARKER.to_string(), Style::from_crossterm(style));
+    }
+
+    fn pinned_marker(&mut self) {
+        let style = self.theme.as_style(Meaning::Pinned);
+        self.draw(&PINNED_MARKER.to_string(), Style::from_crossterm(style));
     }
 
     /// Fill remaining row width with selection background (for selected rows)
@@ -251,12 +634,15 @@ impl DrawState<'_> {
     }
 
     fn duration(&mut self, h: &History, width: u16) {
-        let style = self.theme.as_style(if h.success() {
-            Meaning::AlertInfo
-        } else {
-            Meaning::AlertError
-        });
         let duration = Duration::from_nanos(u64::try_from(h.duration).unwrap_or(0));
+        // Themes with a duration gradient color by how long it took, others by the outcome
+        let style = self.theme.duration_style(duration).unwrap_or_else(|| {
+            self.theme.as_style(if h.success() {
+                Meaning::AlertInfo
+            } else {
+                Meaning::AlertError
+            })
+        });
         let formatted = format_duration(duration);
         let w = width as usize;
         // Right-align within the column, ellipsizing if it somehow overflows.
@@ -296,12 +682,7 @@ impl DrawState<'_> {
     fn command(&mut self, h: &History, _width: u16) {
         let style = self.theme.as_style(Meaning::Base);
 
-        // Build the normalized command string (whitespace-collapsed, control chars escaped)
-        let normalized: String = h
-            .command
-            .escape_non_printable()
-            .split_ascii_whitespace()
-            .join(" ");
+        let normalized = one_line(&h.command);
 
         let highlight_indices = self.history_highlighter.get_highlight_indices(&normalized);
 
@@ -311,29 +692,66 @@ impl DrawState<'_> {
             Vec::new()
         };
 
+        if let Some((badge, meaning)) = badge(h, self.state, self.run_counts) {
+            self.draw(&badge, Style::from_crossterm(self.theme.as_style(meaning)));
+        }
+
         // Calculate the available width for the command text.
         // `self.x` is already past the indicator and any preceding columns,
         // so the remaining width is how far we can draw.
         let avail = (self.list_area.width.saturating_sub(self.x)) as usize;
+        let wrapped = self.is_selected() && self.selected_rows > 1;
 
-        // Truncate long commands from the middle to show both start and end,
-        // so users can identify commands even in narrow terminals (issue #3596).
-        let ellipsized =
-            normalized.ellipsize(Measure::Columns(avail), Pos::Middle, Indicator::UNICODE);
-        let display = ellipsized.to_string();
-        for (i, ch) in display.char_indices() {
+        let scroll = if wrapped {
+            0
+        } else if self.is_selected() {
+            let width = UnicodeWidthStr::width(normalized.as_str());
+            if width > avail {
+                self.state.max_scroll = width - avail.saturating_sub(1);
+            }
+            self.state.scroll.min(self.state.max_scroll)
+        } else {
+            0
+        };
+
+        // Each output cell with the source byte it came from
+        let cells: Vec<(Option<usize>, char)> = if wrapped {
+            wrapped_cells(&normalized, avail * usize::from(self.selected_rows))
+        } else if scroll > 0 {
+            scrolled(&normalized, scroll, avail)
+        } else {
+            // Truncate long commands from the middle to show both start and end,
+            // so users can identify commands even in narrow terminals (issue #3596).
+            let ellipsized =
+                normalized.ellipsize(Measure::Columns(avail), Pos::Middle, Indicator::UNICODE);
+            ellipsized
+                .to_string()
+                .char_indices()
+                .map(|(i, ch)| (ellipsized.source_index(i), ch))
+                .collect()
+        };
+        let indent = self.x;
+        let mut rows_left = self.selected_rows - 1;
+        for (source_byte, ch) in cells {
+            let ch_width = UnicodeWidthChar::width(ch).unwrap_or(0);
+            let overflows = usize::from(self.x) + ch_width > usize::from(self.list_area.width);
+            if wrapped && rows_left > 0 && overflows {
+                self.wrap_row(indent);
+                rows_left -= 1;
+            }
             if self.x > self.list_area.width {
                 return;
             }
-            // Map each output cell back to its source byte and test the existing
-            // highlight set; a cell on the spliced ellipsis maps to None and is
-            // never highlighted (this is why the "…" never gets the highlight style).
-            let source_byte = ellipsized.source_index(i);
+            // Test each cell's source byte against the existing highlight set; a
+            // cell on a spliced ellipsis maps to None and is never highlighted
+            // (this is why the "…" never gets the highlight style).
             let highlighted = source_byte.is_some_and(|b| highlight_indices.contains(&b));
             // Search matches take precedence and use the dedicated Highlight
             // color; otherwise fall back to this byte's syntax color, or Base.
             let char_style = if highlighted {
                 self.theme.as_style(Meaning::Highlight)
+            } else if ch == LINE_BREAK_MARKER {
+                self.theme.as_style(Meaning::Guidance)
             } else {
                 source_byte
                     .and_then(|b| syntax.get(b))
@@ -382,12 +800,14 @@ impl DrawState<'_> {
         self.draw(&display, Style::from_crossterm(style));
     }
 
-    /// Render the host column (just the hostname)
+    /// Render the host column (the machine's friendly name, or just the hostname)
     fn host(&mut self, h: &History, width: u16) {
         let style = self.theme.as_style(Meaning::Annotation);
         let w = width as usize;
         // Database stores hostname as "hostname:username"
-        let host = h.hostname.split(':').next().unwrap_or(&h.hostname);
+        let host = self
+            .host_names
+            .map_or_else(|| host_of(&h.hostname), |names| names.display(&h.hostname));
         let display = host.pad_ellipsize(
             Measure::Columns(w),
             Pos::End,
@@ -433,15 +853,573 @@ impl DrawState<'_> {
             self.list_area.bottom() - self.y - 1
         };
 
-        // Apply selection background color to selected row
         if self.is_selected() {
-            let selection_style = get_selection_style(self.theme);
-            if let Some(bg) = selection_style.bg {
-                style = style.bg(bg);
-            }
+            style = apply_selection(style, self.theme);
         }
 
         let w = (self.list_area.width - self.x) as usize;
         self.x += self.buf.set_stringn(cx, cy, s, w, style).0 - cx;
     }
 }
+
+/// What's drawn before the command of `h`: the marker of a run listed under its command's row,
+/// or how many times the command was run, if more than once.
+fn badge(
+    h: &History,
+    state: &ListState,
+    run_counts: Option<&HashMap<String, i64>>,
+) -> Option<(String, Meaning)> {
+    if state.is_run(h) {
+        return Some((RUN_MARKER.to_string(), Meaning::Guidance));
+    }
+    run_counts
+        .and_then(|counts| counts.get(&h.command))
+        .filter(|&&count| count > 1)
+        .map(|count| (format!("{count}× "), Meaning::Annotation))
+}
+
+/// The cells of `command` wrapped onto rows of `room` columns in all, ending in an ellipsis if it
+/// doesn't fit. Each cell comes with the byte of `command` it shows, if any.
+fn wrapped_cells(command: &str, room: usize) -> Vec<(Option<usize>, char)> {
+    let mut cells: Vec<(Option<usize>, char)> = command
+        .char_indices()
+        .map(|(i, ch)| (Some(i), ch))
+        .collect();
+    if UnicodeWidthStr::width(command) <= room {
+        return cells;
+    }
+
+    let mut used = 0;
+    let fits = cells
+        .iter()
+        .take_while(|&&(_, ch)| {
+            used += UnicodeWidthChar::width(ch).unwrap_or(0);
+            used < room
+        })
+        .count();
+    cells.truncate(fits);
+    cells.push((None, '…'));
+    cells
+}
+
+/// The cells of `command` shown in `width` columns once it's scrolled `scroll` columns in: an
+/// ellipsis for what's scrolled past, then as much as fits, ellipsized at the end if there's
+/// more. Each cell comes with the byte of `command` it shows, if any.
+fn scrolled(command: &str, scroll: usize, width: usize) -> Vec<(Option<usize>, char)> {
+    let mut skipped = 0;
+    let rest: Vec<(usize, char)> = command
+        .char_indices()
+        .skip_while(|&(_, ch)| {
+            let skip = skipped < scroll;
+            skipped += UnicodeWidthChar::width(ch).unwrap_or(0);
+            skip
+        })
+        .collect();
+
+    let rest_width: usize = rest
+        .iter()
+        .map(|&(_, ch)| UnicodeWidthChar::width(ch).unwrap_or(0))
+        .sum();
+    let room = if rest_width + 1 > width {
+        width.saturating_sub(2)
+    } else {
+        width.saturating_sub(1)
+    };
+
+    let mut cells = vec![(None, '…')];
+    let mut used = 0;
+    for (i, ch) in rest {
+        let w = UnicodeWidthChar::width(ch).unwrap_or(0);
+        if used + w > room {
+            cells.push((None, '…'));
+            break;
+        }
+        cells.push((Some(i), ch));
+        used += w;
+    }
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use atuin_client::{
+        history::History,
+        settings::{SearchMode, Settings, UiColumn, UiColumnType},
+        theme::{Meaning, ThemeManager},
+    };
+    use ratatui::{backend::TestBackend, prelude::*};
+    use rstest::rstest;
+    use time::{Duration, OffsetDateTime, macros::datetime};
+
+    use super::{
+        HistoryHighlighter, HistoryList, ListState, MARKED_MARKER, PINNED_MARKER, RUN_MARKER,
+        one_line, scrolled, wrapped_cells,
+    };
+    use crate::command::client::search::{engines, snapshot::assert_snapshot};
+
+    const NOW: OffsetDateTime = datetime!(2026-10-15 12:00 UTC);
+
+    fn entry(command: &str, ago: Duration, duration: Duration, exit: i64) -> History {
+        let mut h: History = History::import()
+            .timestamp(NOW - ago)
+            .command(command)
+            .cwd("/home/ellie/src/atuin")
+            .hostname("laptop:ellie")
+            .exit(exit)
+            .build()
+            .into();
+        h.duration = i64::try_from(duration.whole_nanoseconds()).unwrap();
+        h
+    }
+
+    /// A mix of what tends to break rendering: failures, wide characters,
+    /// multiple lines and commands far longer than the terminal.
+    fn fixture() -> Vec<History> {
+        vec![
+            entry("cargo test", Duration::seconds(5), Duration::seconds(42), 0),
+            entry(
+                "cargo build --release",
+                Duration::minutes(3),
+                Duration::seconds(95),
+                101,
+            ),
+            entry(
+                "echo '你好，世界' 🦀",
+                Duration::hours(2),
+                Duration::milliseconds(3),
+                0,
+            ),
+            entry(
+                "cat <<EOF\nhello\nEOF",
+                Duration::days(1),
+                Duration::milliseconds(1),
+                0,
+            ),
+            entry(
+                &format!(
+                    "curl -fsSL https://example.com/{}",
+                    "very/long/path/".repeat(10)
+                ),
+                Duration::days(40),
+                Duration::seconds(1),
+                0,
+            ),
+            entry(
+                "ls -la",
+                Duration::days(400),
+                Duration::microseconds(900),
+                0,
+            ),
+        ]
+    }
+
+    #[rstest]
+    fn history_list_snapshot(
+        #[values("default", "autumn", "(none)")] theme: &str,
+        #[values(40, 100)] width: u16,
+    ) {
+        let settings = Settings::utc();
+        let mut manager = ThemeManager::new(Some(false), Some(String::new()));
+        let theme_name = theme;
+        let theme = manager.load_theme(theme, None);
+        let engine = engines::engine(SearchMode::Fuzzy, &settings);
+        let history = fixture();
+        let now = || NOW;
+
+        let mut terminal = Terminal::new(TestBackend::new(width, 8)).unwrap();
+        terminal
+            .draw(|f| {
+                let list = HistoryList::new(
+                    &history,
+                    false,
+                    &now,
+                    theme,
+                    HistoryHighlighter {
+                        engine: &*engine,
+                        search_input: "cargo",
+                    },
+                    false,
+                    &settings.ui.columns,
+                );
+                let mut state = ListState::default();
+                state.select(1);
+                f.render_stateful_widget(list, f.area(), &mut state);
+            })
+            .unwrap();
+
+        let theme_name = theme_name.trim_matches(['(', ')']);
+        assert_snapshot(
+            &format!("history_list_{theme_name}_{width}"),
+            terminal.backend().buffer(),
+        );
+    }
+
+    #[test]
+    fn marks_toggle_and_show_beside_entries() {
+        let settings = Settings::utc();
+        let mut manager = ThemeManager::new(Some(false), Some(String::new()));
+        let theme = manager.load_theme("default", None);
+        let engine = engines::engine(SearchMode::Fuzzy, &settings);
+        let history = fixture();
+        let now = || NOW;
+
+        let mut state = ListState::default();
+        state.toggle_mark(&history[2]);
+        state.toggle_mark(&history[0]);
+        state.toggle_mark(&history[4]);
+        state.toggle_mark(&history[4]);
+
+        // In the order they were marked
+        let marked: Vec<_> = state.marked().iter().map(|h| h.command.as_str()).collect();
+        assert_eq!(marked, [history[2].command.as_str(), "cargo test"]);
+
+        let mut terminal = Terminal::new(TestBackend::new(40, 8)).unwrap();
+        terminal
+            .draw(|f| {
+                let list = HistoryList::new(
+                    &history,
+                    false,
+                    &now,
+                    theme,
+                    HistoryHighlighter {
+                        engine: &*engine,
+                        search_input: "",
+                    },
+                    false,
+                    &settings.ui.columns,
+                );
+                f.render_stateful_widget(list, f.area(), &mut state);
+            })
+            .unwrap();
+
+        let buffer = terminal.backend().buffer();
+        let markers = (0..8)
+            .filter(|&y| buffer[(0, y)].symbol() == MARKED_MARKER.to_string())
+            .count();
+        assert_eq!(markers, 2);
+
+        // Searching again changes the rows, but not which entries are marked
+        state.reset();
+        state.retain_marked(|h| h.command != "cargo test");
+        assert!(state.is_marked(&history[2]));
+        assert!(!state.is_marked(&history[0]));
+    }
+
+    #[test]
+    fn numeric_shortcuts_number_the_rows_after_the_selection() {
+        let settings = Settings::utc();
+        let mut manager = ThemeManager::new(Some(false), Some(String::new()));
+        let theme = manager.load_theme("default", None);
+        let engine = engines::engine(SearchMode::Fuzzy, &settings);
+        let history = fixture();
+        let now = || NOW;
+
+        let mut state = ListState::default();
+        state.select(1);
+        state.toggle_mark(&history[3]);
+
+        let mut terminal = Terminal::new(TestBackend::new(40, 8)).unwrap();
+        terminal
+            .draw(|f| {
+                let list = HistoryList::new(
+                    &history,
+                    false,
+                    &now,
+                    theme,
+                    HistoryHighlighter {
+                        engine: &*engine,
+                        search_input: "",
+                    },
+                    false,
+                    &settings.ui.columns,
+                )
+                .numeric_shortcuts(true);
+                f.render_stateful_widget(list, f.area(), &mut state);
+            })
+            .unwrap();
+
+        // The list grows upwards, so row 0 is at the bottom
+        let buffer = terminal.backend().buffer();
+        let gutter: String = (2..8).rev().map(|y| buffer[(0, y)].symbol()).collect();
+        assert_eq!(gutter, "  1*34");
+        assert_eq!(
+            buffer[(0, 3)].fg,
+            Style::from_crossterm(theme.as_style(Meaning::Shortcut))
+                .fg
+                .unwrap_or_default()
+        );
+    }
+
+    #[test]
+    fn entries_from_other_hosts_are_marked() {
+        let settings = Settings::utc();
+        let mut manager = ThemeManager::new(Some(false), Some(String::new()));
+        let theme = manager.load_theme("default", None);
+        let engine = engines::engine(SearchMode::Fuzzy, &settings);
+        let mut history = fixture();
+        history[1].hostname = "server:ellie".to_string();
+        history[2].hostname = "LAPTOP:root".to_string();
+        history[3].hostname = "server:ellie".to_string();
+        let now = || NOW;
+
+        let render = |numeric_shortcuts: bool, state: &mut ListState| {
+            let mut terminal = Terminal::new(TestBackend::new(40, 8)).unwrap();
+            terminal
+                .draw(|f| {
+                    let list = HistoryList::new(
+                        &history,
+                        false,
+                        &now,
+                        theme,
+                        HistoryHighlighter {
+                            engine: &*engine,
+                            search_input: "",
+                        },
+                        false,
+                        &settings.ui.columns,
+                    )
+                    .numeric_shortcuts(numeric_shortcuts)
+                    .local_host("laptop:ellie");
+                    f.render_stateful_widget(list, f.area(), state);
+                })
+                .unwrap();
+            let buffer = terminal.backend().buffer().clone();
+            // The list grows upwards, so row 0 is at the bottom
+            let gutter = |x| (2..8).rev().map(|y| buffer[(x, y)].symbol()).collect();
+            (gutter(0), gutter(1))
+        };
+
+        // Another user on this host doesn't count, and marks take the gutter
+        let mut state = ListState::default();
+        state.toggle_mark(&history[3]);
+        let (gutter, _): (String, String) = render(false, &mut state);
+        assert_eq!(gutter, " @ *  ");
+
+        // With shortcuts, the marker moves to the second column
+        let mut state = ListState::default();
+        let (digits, markers): (String, String) = render(true, &mut state);
+        assert_eq!(digits, " 12345");
+        assert_eq!(markers, " @ @  ");
+    }
+
+    #[test]
+    fn pinned_commands_are_marked() {
+        let settings = Settings::utc();
+        let mut manager = ThemeManager::new(Some(false), Some(String::new()));
+        let theme = manager.load_theme("default", None);
+        let engine = engines::engine(SearchMode::Fuzzy, &settings);
+        let mut history = fixture();
+        history[1].hostname = "server:ellie".to_string();
+        history[2].hostname = "server:ellie".to_string();
+        let pinned = vec![
+            "cargo test".to_string(),
+            "cargo build --release".to_string(),
+        ];
+        let now = || NOW;
+
+        let mut terminal = Terminal::new(TestBackend::new(40, 8)).unwrap();
+        terminal
+            .draw(|f| {
+                let list = HistoryList::new(
+                    &history,
+                    false,
+                    &now,
+                    theme,
+                    HistoryHighlighter {
+                        engine: &*engine,
+                        search_input: "",
+                    },
+                    false,
+                    &settings.ui.columns,
+                )
+                .local_host("laptop:ellie")
+                .pinned(&pinned);
+                f.render_stateful_widget(list, f.area(), &mut ListState::default());
+            })
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+
+        // A pin outranks another host
+        let gutter: String = (2..8).rev().map(|y| buffer[(0, y)].symbol()).collect();
+        assert_eq!(gutter, format!("{PINNED_MARKER}{PINNED_MARKER}@   "));
+        assert_eq!(
+            buffer[(0, 7)].fg,
+            Style::from_crossterm(theme.as_style(Meaning::Pinned))
+                .fg
+                .unwrap_or_default()
+        );
+    }
+
+    #[test]
+    fn multi_line_commands_are_marked() {
+        assert_eq!(one_line("ls  -la"), "ls -la");
+        assert_eq!(
+            one_line("cat <<EOF\n  hello\nEOF\n"),
+            "cat <<EOF ⏎ hello ⏎ EOF"
+        );
+        assert_eq!(
+            one_line("cargo build \\\r\n    --release"),
+            "cargo build \\ ⏎ --release"
+        );
+        assert_eq!(one_line("echo\ta\0"), "echo^Ia^@");
+    }
+
+    #[test]
+    fn scrolling_shows_the_rest_of_a_long_command() {
+        let text =
+            |cells: Vec<(Option<usize>, char)>| cells.iter().map(|c| c.1).collect::<String>();
+
+        assert_eq!(text(scrolled("git commit -m message", 4, 10)), "…commit -…");
+        assert_eq!(
+            text(scrolled("git commit -m message", 12, 10)),
+            "…m message"
+        );
+        assert_eq!(
+            scrolled("ls -la", 3, 10),
+            vec![(None, '…'), (Some(3), '-'), (Some(4), 'l'), (Some(5), 'a')]
+        );
+
+        let mut state = ListState {
+            max_scroll: 12,
+            ..ListState::default()
+        };
+        state.scroll_right();
+        state.scroll_right();
+        assert_eq!(state.scroll(), 12);
+        state.scroll_left();
+        assert_eq!(state.scroll(), 4);
+
+        // Another entry starts from the beginning
+        state.select(1);
+        assert_eq!(state.scroll(), 0);
+    }
+
+    #[test]
+    fn wrapping_shows_all_of_the_selected_command() {
+        let text =
+            |cells: Vec<(Option<usize>, char)>| cells.iter().map(|c| c.1).collect::<String>();
+        assert_eq!(text(wrapped_cells("abcdef", 6)), "abcdef");
+        assert_eq!(text(wrapped_cells("abcdef", 4)), "abc…");
+
+        let settings = Settings::utc();
+        let mut manager = ThemeManager::new(Some(false), Some(String::new()));
+        let theme = manager.load_theme("default", None);
+        let engine = engines::engine(SearchMode::Fuzzy, &settings);
+        let history = [
+            entry("ls", Duration::minutes(1), Duration::seconds(1), 0),
+            entry(
+                "echo 0123456789abcdefghijklmnopqrstuvwxyz",
+                Duration::minutes(2),
+                Duration::seconds(1),
+                0,
+            ),
+            entry("pwd", Duration::minutes(3), Duration::seconds(1), 0),
+        ];
+        let columns = [UiColumn::new(UiColumnType::Command)];
+        let now = || NOW;
+
+        let mut terminal = Terminal::new(TestBackend::new(20, 8)).unwrap();
+        let mut state = ListState::default();
+        state.select(1);
+        terminal
+            .draw(|f| {
+                let list = HistoryList::new(
+                    &history,
+                    false,
+                    &now,
+                    theme,
+                    HistoryHighlighter {
+                        engine: &*engine,
+                        search_input: "",
+                    },
+                    false,
+                    &columns,
+                )
+                .wrap_selected(true);
+                f.render_stateful_widget(list, f.area(), &mut state);
+            })
+            .unwrap();
+
+        let buffer = terminal.backend().buffer();
+        let row = |y| {
+            (0..20)
+                .map(|x| buffer[(x, y)].symbol())
+                .collect::<String>()
+                .trim_end()
+                .to_string()
+        };
+        // From the bottom up, with the selected command reading down its three rows
+        assert_eq!(row(7), " ls");
+        assert_eq!(row(6), " xyz");
+        assert_eq!(row(5), " efghijklmnopqrstuvw");
+        assert_eq!(row(4), " echo 0123456789abcd");
+        assert_eq!(row(3), " pwd");
+        assert_eq!(state.max_entries(), 6);
+
+        // Clicks find the entry under them, on any of its rows
+        assert_eq!(state.index_at(4, 7), Some(0));
+        assert_eq!(state.index_at(4, 6), Some(1));
+        assert_eq!(state.index_at(0, 4), Some(1));
+        assert_eq!(state.index_at(4, 3), Some(2));
+        assert_eq!(state.index_at(4, 2), None);
+        assert_eq!(state.index_at(20, 7), None);
+    }
+
+    #[test]
+    fn grouped_commands_show_their_counts_and_runs() {
+        let settings = Settings::utc();
+        let mut manager = ThemeManager::new(Some(false), Some(String::new()));
+        let theme = manager.load_theme("default", None);
+        let engine = engines::engine(SearchMode::Fuzzy, &settings);
+        let history = [
+            entry("make test", Duration::minutes(1), Duration::seconds(1), 0),
+            entry("make test", Duration::hours(1), Duration::seconds(1), 2),
+            entry("ls", Duration::hours(2), Duration::seconds(1), 0),
+        ];
+        let run_counts = HashMap::from([("make test".to_string(), 12), ("ls".to_string(), 1)]);
+        let columns = [UiColumn::new(UiColumnType::Command)];
+        let now = || NOW;
+
+        let mut terminal = Terminal::new(TestBackend::new(20, 3)).unwrap();
+        let mut state = ListState::default();
+        state.expand(history[0].id.clone(), vec![history[1].id.clone()]);
+        assert!(state.is_run(&history[1]));
+        assert!(!state.is_run(&history[0]));
+        terminal
+            .draw(|f| {
+                let list = HistoryList::new(
+                    &history,
+                    true,
+                    &now,
+                    theme,
+                    HistoryHighlighter {
+                        engine: &*engine,
+                        search_input: "",
+                    },
+                    false,
+                    &columns,
+                )
+                .run_counts(&run_counts);
+                f.render_stateful_widget(list, f.area(), &mut state);
+            })
+            .unwrap();
+
+        let buffer = terminal.backend().buffer();
+        let row = |y| {
+            (0..20)
+                .map(|x| buffer[(x, y)].symbol())
+                .collect::<String>()
+                .trim_end()
+                .to_string()
+        };
+        assert_eq!(row(0), " 12× make test");
+        assert_eq!(row(1), format!(" {RUN_MARKER}make test"));
+        assert_eq!(row(2), " ls");
+
+        let expanded = state.collapse().unwrap();
+        assert_eq!(expanded.group, history[0].id);
+        assert!(state.expanded().is_none());
+    }
+}
@@ -2,7 +2,8 @@ use std::time::Duration;
 use time::macros::format_description;
 
 use atuin_client::{
-    history::{History, HistoryStats},
+    history::{History, HistoryStats, output::CapturedOutput},
+    host::HostNames,
     settings::{Settings, Timezone},
 };
 use atuin_common::string::EscapeNonPrintablePosixExt as _;
@@ -12,15 +13,17 @@ use ratatui::{
     layout::Rect,
     prelude::{Constraint, Direction, Layout},
     style::Style,
-    text::{Span, Text},
+    text::{Line, Span, Text},
     widgets::{Bar, BarChart, BarGroup, Block, Borders, Padding, Paragraph, Row, Table},
 };
 
 use super::block_ext::themed_block;
 use super::duration::format_duration;
+use super::history_list::one_line;
 
 use super::super::theme::{Meaning, Theme};
 use super::interactive::{Compactness, to_compactness};
+use super::keybindings::HistoryField;
 
 #[allow(clippy::cast_sign_loss)]
 fn u64_or_zero(num: i64) -> u64 {
@@ -56,10 +59,19 @@ pub fn draw_commands(
         })
         .split(parent);
 
-    let command = Paragraph::new(Text::from(Span::styled(
-        history.command.escape_non_printable(),
-        Style::from_crossterm(theme.as_style(Meaning::Important)),
-    )))
+    // Multi-line commands keep their lines where there's room for them.
+    let style = Style::from_crossterm(theme.as_style(Meaning::Important));
+    let command = Paragraph::new(if compact {
+        Text::from(Span::styled(one_line(&history.command), style))
+    } else {
+        Text::from(
+            history
+                .command
+                .lines()
+                .map(|line| Line::styled(line.escape_non_printable().into_owned(), style))
+                .collect::<Vec<_>>(),
+        )
+    })
     .block(if compact {
         Block::new()
             .borders(Borders::NONE)
@@ -74,7 +86,7 @@ pub fn draw_commands(
 
     let previous = Paragraph::new(stats.previous.clone().map_or_else(
         || "[No previous command]".to_string(),
-        |prev| prev.command.escape_non_printable().into_owned(),
+        |prev| one_line(&prev.command),
     ))
     .block(if compact {
         Block::new()
@@ -92,7 +104,7 @@ pub fn draw_commands(
     // compacted, it makes it more obviously control text.
     let next = Paragraph::new(stats.next.clone().map_or_else(
         || "[No next command]".to_string(),
-        |next| next.command.escape_non_printable().into_owned(),
+        |next| one_line(&next.command),
     ))
     .block(if compact {
         Block::new()
@@ -118,13 +130,20 @@ pub fn draw_stats_table(
     tz: Timezone,
     stats: &HistoryStats,
     theme: &Theme,
+    host_names: &HostNames,
 ) {
     let duration = Duration::from_nanos(u64_or_zero(history.duration));
     let avg_duration = Duration::from_nanos(stats.average_duration);
     let (host, user) = history.hostname.split_once(':').unwrap_or(("", ""));
+    let name = host_names.display(host);
+    let host = if name == host {
+        host.to_string()
+    } else {
+        format!("{name} ({host})")
+    };
 
     let rows = [
-        Row::new(vec!["Host".to_string(), host.to_string()]),
+        Row::new(vec!["Host".to_string(), host]),
         Row::new(vec!["User".to_string(), user.to_string()]),
         Row::new(vec![
             "Time".to_string(),
@@ -283,6 +302,134 @@ fn draw_stats_charts(f: &mut Frame<'_>, parent: Rect, stats: &HistoryStats, them
     f.render_widget(duration_over_time, layout[2]);
 }
 
+/// The end of the command's captured output, as much as fits.
+pub fn draw_output(f: &mut Frame<'_>, parent: Rect, output: &CapturedOutput, theme: &Theme) {
+    let title = if output.truncated {
+        format!("Output (truncated, {} bytes)", output.total_bytes)
+    } else {
+        "Output".to_string()
+    };
+
+    let height = usize::from(parent.height.saturating_sub(2));
+    let lines: Vec<&str> = output.text.lines().collect();
+    let shown = lines[lines.len().saturating_sub(height)..]
+        .iter()
+        .map(|line| Line::from(line.escape_non_printable().into_owned()))
+        .collect::<Vec<_>>();
+
+    let paragraph = Paragraph::new(shown).block(
+        themed_block(theme)
+            .title(title)
+            .borders(Borders::ALL)
+            .style(Style::from_crossterm(theme.as_style(Meaning::Base)))
+            .padding(Padding::horizontal(1)),
+    );
+
+    f.render_widget(paragraph, parent);
+}
+
+/// `field` of `history`, as the details tab shows it and it's copied.
+pub fn field_value(
+    history: &History,
+    field: HistoryField,
+    tz: Timezone,
+    host_names: &HostNames,
+) -> String {
+    let (host, user) = history.hostname.split_once(':').unwrap_or(("", ""));
+    let format = format_description!(
+        "[year]-[month]-[day] [hour]:[minute]:[second] [offset_hour sign:mandatory]:[offset_minute]"
+    );
+    let time = |offset| {
+        history
+            .timestamp
+            .to_offset(offset)
+            .format(&format)
+            .unwrap_or_default()
+    };
+
+    match field {
+        HistoryField::Command => history.command.clone(),
+        HistoryField::Directory => history.cwd.clone(),
+        HistoryField::Host => {
+            let name = host_names.display(host);
+            if name == host {
+                host.to_string()
+            } else {
+                format!("{name} ({host})")
+            }
+        }
+        HistoryField::User => user.to_string(),
+        HistoryField::Session => history.session.clone(),
+        HistoryField::Time => time(tz.0),
+        HistoryField::TimeUtc => time(time::UtcOffset::UTC),
+        HistoryField::Duration if history.duration < 0 => "unknown".to_string(),
+        HistoryField::Duration => {
+            format_duration(Duration::from_nanos(u64_or_zero(history.duration)))
+        }
+        HistoryField::Exit => history.exit.to_string(),
+        HistoryField::Author => history.author.clone(),
+        HistoryField::Id => history.id.0.clone(),
+    }
+}
+
+/// Everything stored about the entry, one field a row, for the details tab.
+pub fn draw_details(
+    f: &mut Frame<'_>,
+    parent: Rect,
+    history: &History,
+    stats: &HistoryStats,
+    theme: &Theme,
+    tz: Timezone,
+    host_names: &HostNames,
+) {
+    let label = |field| match field {
+        HistoryField::Command => "Command",
+        HistoryField::Directory => "Directory",
+        HistoryField::Host => "Host",
+        HistoryField::User => "User",
+        HistoryField::Session => "Session",
+        HistoryField::Time => "Time",
+        HistoryField::TimeUtc => "Time (UTC)",
+        HistoryField::Duration => "Duration",
+        HistoryField::Exit => "Exit",
+        HistoryField::Author => "Author",
+        HistoryField::Id => "Id",
+    };
+
+    let mut rows: Vec<Row> = HistoryField::ALL
+        .into_iter()
+        .map(|field| {
+            let value = match field {
+                HistoryField::Command => one_line(&history.command),
+                _ => field_value(history, field, tz, host_names),
+            };
+            Row::new(vec![label(field).to_string(), value])
+        })
+        .collect();
+    let optional = [
+        ("Intent", history.intent.clone()),
+        ("Shell", history.shell.clone()),
+        ("Sync", stats.sync.map(|sync| sync.as_str().to_string())),
+    ];
+    rows.extend(
+        optional
+            .into_iter()
+            .filter_map(|(label, value)| Some(Row::new(vec![label.to_string(), value?]))),
+    );
+
+    let widths = [Constraint::Length(12), Constraint::Min(0)];
+    let table = Table::new(rows, widths).column_spacing(1).block(
+        themed_block(theme)
+            .title("Details")
+            .borders(Borders::ALL)
+            .style(Style::from_crossterm(theme.as_style(Meaning::Base)))
+            .padding(Padding::horizontal(1)),
+    );
+
+    f.render_widget(table, parent);
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn draw(
     f: &mut Frame<'_>,
     chunk: Rect,
@@ -291,12 +438,13 @@ pub fn draw(
     settings: &Settings,
     theme: &Theme,
     tz: Timezone,
+    host_names: &HostNames,
 ) {
     let compactness = to_compactness(f, settings);
 
     match compactness {
         Compactness::Ultracompact => draw_ultracompact(f, chunk, history, stats, theme),
-        _ => draw_full(f, chunk, history, stats, theme, tz),
+        _ => draw_full(f, chunk, history, stats, theme, tz, host_names),
     }
 }
 
@@ -317,10 +465,23 @@ pub fn draw_full(
     stats: &HistoryStats,
     theme: &Theme,
     tz: Timezone,
+    host_names: &HostNames,
 ) {
     let vert_layout = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Ratio(1, 5), Constraint::Ratio(4, 5)])
+        .constraints(if stats.output.is_some() {
+            [
+                Constraint::Ratio(1, 5),
+                Constraint::Ratio(2, 5),
+                Constraint::Ratio(2, 5),
+            ]
+        } else {
+            [
+                Constraint::Ratio(1, 5),
+                Constraint::Ratio(4, 5),
+                Constraint::Length(0),
+            ]
+        })
         .split(chunk);
 
     let stats_layout = Layout::default()
@@ -329,19 +490,27 @@ pub fn draw_full(
         .split(vert_layout[1]);
 
     draw_commands(f, vert_layout[0], history, stats, false, theme);
-    draw_stats_table(f, stats_layout[0], history, tz, stats, theme);
+    draw_stats_table(f, stats_layout[0], history, tz, stats, theme, host_names);
     draw_stats_charts(f, stats_layout[1], stats, theme);
+
+    if let Some(output) = &stats.output {
+        draw_output(f, vert_layout[2], output, theme);
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::draw_ultracompact;
+    use super::super::keybindings::HistoryField;
+    use super::super::snapshot::assert_snapshot;
+    use super::{draw_details, draw_full, draw_ultracompact, field_value};
     use atuin_client::{
-        history::{History, HistoryId, HistoryStats},
+        history::{History, HistoryId, HistoryStats, SyncStatus, output::CapturedOutput},
+        host::HostNames,
+        settings::Timezone,
         theme::ThemeManager,
     };
     use ratatui::{backend::TestBackend, prelude::*};
-    use time::OffsetDateTime;
+    use time::{OffsetDateTime, UtcOffset};
 
     fn mock_history_stats() -> (History, HistoryStats) {
         let history = History {
@@ -394,6 +563,8 @@ mod tests {
             exits: Vec::new(),
             day_of_week: Vec::new(),
             duration_over_time: Vec::new(),
+            output: None,
+            sync: None,
         };
         (history, stats)
     }
@@ -454,4 +625,191 @@ mod tests {
             "raw NUL byte leaked to the terminal"
         );
     }
+
+    #[test]
+    fn multi_line_commands_keep_their_lines() {
+        let backend = TestBackend::new(80, 30);
+        let mut terminal = Terminal::new(backend).expect("Could not create terminal");
+        let chunk = Rect::new(0, 0, 80, 30);
+        let (mut history, mut stats) = mock_history_stats();
+        history.command = "cat <<EOF\nhello\nEOF".to_string();
+        stats.previous.as_mut().unwrap().command = "ls\npwd".to_string();
+
+        let mut manager = ThemeManager::new(Some(true), Some(String::new()));
+        let theme = manager.load_theme("(none)", None);
+        let _ = terminal.draw(|f| {
+            draw_full(
+                f,
+                chunk,
+                &history,
+                &stats,
+                theme,
+                Timezone(UtcOffset::UTC),
+                &HostNames::default(),
+            );
+        });
+
+        let buffer = terminal.backend().buffer();
+        let rows: Vec<String> = (0..buffer.area.height)
+            .map(|y| {
+                (0..buffer.area.width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect()
+            })
+            .collect();
+
+        let row_of = |text: &str| rows.iter().position(|row| row.contains(text));
+        let first = row_of("cat <<EOF").expect("command is shown");
+        assert_eq!(row_of("hello"), Some(first + 1));
+        assert!(rows[first + 2].contains("│ EOF"), "{}", rows[first + 2]);
+        assert!(rows.iter().any(|row| row.contains("ls ⏎ pwd")));
+        assert!(!rows.iter().any(|row| row.contains("^J")));
+    }
+
+    #[test]
+    fn captured_output_is_shown_when_present() {
+        let backend = TestBackend::new(80, 30);
+        let mut terminal = Terminal::new(backend).expect("Could not create terminal");
+        let chunk = Rect::new(0, 0, 80, 30);
+        let (history, mut stats) = mock_history_stats();
+        stats.output = Some(CapturedOutput {
+            text: "Compiling atuin\nerror: could not compile\n".to_string(),
+            total_bytes: 40,
+            truncated: false,
+        });
+
+        let mut manager = ThemeManager::new(Some(true), Some(String::new()));
+        let theme = manager.load_theme("(none)", None);
+        let _ = terminal.draw(|f| {
+            draw_full(
+                f,
+                chunk,
+                &history,
+                &stats,
+                theme,
+                Timezone(UtcOffset::UTC),
+                &HostNames::default(),
+            );
+        });
+
+        let rendered: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(ratatui::buffer::Cell::symbol)
+            .collect();
+
+        assert!(rendered.contains("Output"));
+        assert!(rendered.contains("error: could not compile"));
+    }
+
+    #[test]
+    fn details_show_every_field() {
+        let backend = TestBackend::new(80, 20);
+        let mut terminal = Terminal::new(backend).expect("Could not create terminal");
+        let chunk = Rect::new(0, 0, 80, 20);
+        let (mut history, mut stats) = mock_history_stats();
+        history.timestamp = time::macros::datetime!(2026-10-15 11:58:20 UTC);
+        history.hostname = "laptop:ellie".to_string();
+        stats.sync = Some(SyncStatus::Pending);
+
+        let mut manager = ThemeManager::new(Some(true), Some(String::new()));
+        let theme = manager.load_theme("(none)", None);
+        let tz = Timezone(UtcOffset::from_hms(2, 0, 0).unwrap());
+        let _ = terminal.draw(|f| {
+            draw_details(f, chunk, &history, &stats, theme, tz, &HostNames::default());
+        });
+
+        let rendered: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(ratatui::buffer::Cell::symbol)
+            .collect();
+
+        for shown in [
+            "/bin/cmd",
+            "/toot",
+            "laptop",
+            "ellie",
+            "sesh1",
+            "2026-10-15 13:58:20 +02:00",
+            "2026-10-15 11:58:20 +00:00",
+            "test1",
+            "waiting for the next sync",
+        ] {
+            assert!(rendered.contains(shown), "{shown} is missing");
+        }
+        // Nothing is shown for what wasn't recorded
+        assert!(!rendered.contains("Intent"));
+    }
+
+    #[test]
+    fn fields_are_copied_in_full() {
+        let (mut history, _) = mock_history_stats();
+        history.command = "cat <<EOF\nhello\nEOF".to_string();
+        history.duration = -1;
+        let tz = Timezone(UtcOffset::UTC);
+        let names = HostNames::default();
+
+        assert_eq!(
+            field_value(&history, HistoryField::Command, tz, &names),
+            "cat <<EOF\nhello\nEOF"
+        );
+        assert_eq!(
+            field_value(&history, HistoryField::Duration, tz, &names),
+            "unknown"
+        );
+        assert_eq!(field_value(&history, HistoryField::Id, tz, &names), "test1");
+    }
+
+    #[rstest::rstest]
+    fn stats_snapshot(
+        #[values("default", "autumn", "(none)")] theme: &str,
+        #[values(80, 120)] width: u16,
+    ) {
+        let backend = TestBackend::new(width, 30);
+        let mut terminal = Terminal::new(backend).expect("Could not create terminal");
+        let chunk = Rect::new(0, 0, width, 30);
+        let (mut history, mut stats) = mock_history_stats();
+        history.timestamp = time::macros::datetime!(2026-10-15 11:58:20 UTC);
+        history.duration = 1_250_000_000;
+        history.hostname = "laptop:ellie".to_string();
+        history.command = "cargo build --release -p atuin && echo 完成".to_string();
+        stats.total = 42;
+        stats.average_duration = 980_000_000;
+        stats.exits = vec![(0, 38), (1, 3), (101, 1)];
+        stats.day_of_week = vec![
+            ("1".to_string(), 12),
+            ("3".to_string(), 20),
+            ("6".to_string(), 10),
+        ];
+        stats.duration_over_time = vec![
+            ("01-09-2026".to_string(), 1_500_000_000),
+            ("01-08-2026".to_string(), 900_000_000),
+            ("01-10-2026".to_string(), 1_250_000_000),
+        ];
+
+        let mut manager = ThemeManager::new(Some(true), Some(String::new()));
+        let loaded = manager.load_theme(theme, None);
+        let _ = terminal.draw(|f| {
+            draw_full(
+                f,
+                chunk,
+                &history,
+                &stats,
+                loaded,
+                Timezone(UtcOffset::UTC),
+                &HostNames::default(),
+            );
+        });
+
+        let theme = if theme == "(none)" { "none" } else { theme };
+        assert_snapshot(
+            &format!("stats_{theme}_{width}"),
+            terminal.backend().buffer(),
+        );
+    }
 }
@@ -1,36 +1,64 @@
 use std::{
+    borrow::Cow,
+    collections::HashMap,
     io::{IsTerminal, Write, stdout},
-    time::Duration,
+    path::Path,
+    time::{Duration, Instant},
 };
 
 #[cfg(unix)]
 use std::io::Read as _;
 
-use atuin_common::{shell::Shell, string::EscapeNonPrintablePosixExt as _};
+use atuin_common::{shell::Shell, string::EscapeNonPrintablePosixExt as _, utils};
 use eyre::Result;
 use futures_util::FutureExt;
+use itertools::Itertools;
+use regex::RegexSet;
 use semver::Version;
-use time::OffsetDateTime;
+use time::{Date, OffsetDateTime};
 use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 use super::{
     block_ext::{themed_block, titled_block},
+    clipboard,
     cursor::Cursor,
-    engines::{SearchEngine, SearchState},
-    history_list::{HistoryList, ListState},
+    date_jump::{self, DatePrompt},
+    editor,
+    engines::{SearchEngine, SearchState, SharedEngine},
+    everything, file_paths,
+    history_list::{HistoryList, ListState, one_line},
+    list_picker::ListPicker,
+    open_with::{self, OpenWithMenu},
+    placeholders::Placeholders,
+    query_task::{Finished, QueryTask},
+    result_cache::{QueryKey, ResultCache},
+    session_picker::SessionPicker,
+    syntax,
+    template::{Template, TemplatePrompt},
+    theme_picker::ThemePicker,
+    urls::{self, UrlPicker, UrlUse},
 };
+use atuin_history::summary::SessionSummary;
+
 use atuin_client::{
-    database::{Context, Database, current_context},
-    history::{History, HistoryId, HistoryStats, store::HistoryStore},
+    database::{Context, Database, OptFilters, current_context, is_query_filter},
+    error::ClientError,
+    history::{History, HistoryId, HistoryStats, SyncStatus, store::HistoryStore},
+    host::{HostNames, HostStore},
+    network::{self, Offline},
+    ordering::ContextBoost,
     settings::{
-        CursorStyle, ExitMode, FilterMode, KeymapMode, PreviewStrategy, SearchMode, Settings,
-        UiColumn,
+        CopyFormat, CursorStyle, ExitMode, FilterMode, JoinMarked, KeymapMode, PreviewStrategy,
+        SearchMode, Settings, SourceRanking, UiColumn, UiColumnType,
     },
+    theme::watcher::ThemeWatcher,
 };
 
+use crate::command::client::config;
 use crate::command::client::search::history_list::HistoryHighlighter;
-use crate::command::client::search::keybindings::KeymapSet;
-use crate::command::client::theme::{Meaning, Theme};
+use crate::command::client::search::keybindings::{HistoryField, KeymapSet};
+use crate::command::client::theme::{Meaning, Theme, ThemeManager};
+use crate::crash;
 use crate::{VERSION, command::client::search::engines};
 
 use ratatui::{
@@ -38,10 +66,10 @@ use ratatui::{
     backend::{CrosstermBackend, FromCrossterm},
     crossterm::{
         cursor::SetCursorStyle,
-        event::{self, Event, KeyEvent, MouseEvent},
+        event::{self, Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent},
         execute, queue, terminal,
     },
-    layout::{Alignment, Constraint, Direction, Layout},
+    layout::{Alignment, Constraint, Direction, Flex, Layout},
     prelude::*,
     style::{Modifier, Style},
     text::{Line, Span, Text},
@@ -56,19 +84,67 @@ use ratatui::crossterm::event::{
 #[cfg(windows)]
 use windows_sys::Win32::System::Console::{GetConsoleOutputCP, SetConsoleOutputCP};
 
-const TAB_TITLES: [&str; 2] = ["Search", "Inspect"];
+const TAB_TITLES: [&str; 3] = ["Search", "Inspect", "Details"];
+
+// How many of the previous command's most frequent follow-ups get boosted.
+const SEQUENCE_BOOST_LIMIT: usize = 3;
+
+// The narrowest the command can be in the normal layout; below it, only the
+// query and the top few results are drawn.
+const MIN_COMMAND_WIDTH: u16 = 10;
+
+// How many results the layout for tiny terminals shows.
+const MINIMAL_RESULTS: u16 = 3;
+
+// How many related commands are looked up for the selected one.
+const RELATED_LIMIT: usize = 20;
+
+// The narrowest the results can be for the related commands to be drawn beside them.
+const RELATED_MIN_WIDTH: u16 = 80;
+
+// How many runs of a command are listed after it at most.
+const GROUP_LIMIT: usize = 100;
+
+// How many of the most recent sessions are offered to pick from.
+const SESSION_LIMIT: usize = 20;
+
+// The longest between two clicks on a result for them to accept it.
+const DOUBLE_CLICK: Duration = Duration::from_millis(500);
+
+/// Marks a result the shell integration should run rather than put on the prompt.
+pub const ACCEPT_PREFIX: &str = "__atuin_accept__:";
 
 pub enum InputAction {
     Accept(usize),
     AcceptInspecting,
     Copy(usize),
+    RerunInContext(usize),
+    Edit(usize),
+    OpenWith(usize),
+    RunOpenWith,
+    UseUrl(usize, UrlUse),
+    InsertPath(usize),
+    ReturnPath(String),
+    /// Accept what a `>` search found at this index
+    AcceptItem(usize),
+    ChangeDirectory(usize),
     Delete(usize),
     DeleteAllMatching(usize),
+    ToggleMark(usize),
+    TogglePin(usize),
+    ToggleGroup(usize),
+    PickSession,
+    EarlierDay(usize),
+    LaterDay(usize),
+    AcceptMarked,
+    CopyMarked,
+    DeleteMarked,
     ReturnOriginal,
     ReturnQuery,
     Continue,
     Redraw,
     SwitchContext(Option<usize>),
+    CopyField(HistoryField),
 }
 
 #[derive(Clone)]
@@ -120,6 +196,8 @@ pub struct State {
     /// Total history count; None until the background count query finishes.
     history_count: Option<i64>,
     update_needed: Option<Version>,
+    /// Set once the background network check finds Atuin is offline.
+    offline: Option<Offline>,
     results_state: ListState,
     switched_search_mode: bool,
     search_mode: SearchMode,
@@ -131,6 +209,16 @@ pub struct State {
     tab_index: usize,
     pending_vim_key: Option<char>,
     original_input_empty: bool,
+    /// Shown in place of the help line until the next key press.
+    warning: Option<String>,
+    /// The accepted entry's index and its placeholders, while they're being
+    /// filled in.
+    placeholders: Option<(usize, Placeholders)>,
+    /// The accepted entry's index and the prompt for its template's fields, while they're
+    /// being asked for.
+    template: Option<(usize, TemplatePrompt)>,
+    /// Whether the preview of the selected command is shown, starting from `show_preview`.
+    show_preview: bool,
 
     pub inspecting_state: InspectingState,
 
@@ -138,6 +226,56 @@ pub struct State {
     search: SearchState,
     engine: Box<dyn SearchEngine>,
     now: Box<dyn Fn() -> OffsetDateTime + Send>,
+
+    /// Commands that most often followed the one run before opening the search.
+    successors: Vec<(String, i64)>,
+    /// Commands pinned above everything else they match, in the order they were pinned.
+    pinned: Vec<String>,
+    /// Friendly names for the machines results were run on.
+    host_names: HostNames,
+    /// The names given to sessions, by their ID.
+    session_names: HashMap<String, String>,
+    result_cache: ResultCache,
+    /// Created the first time the theme picker opens, and kept for the theme it chose.
+    theme_picker: Option<ThemePicker>,
+    /// The "open with" menu, while it's open.
+    open_with: Option<OpenWithMenu>,
+    /// Choosing between the URLs of a command that has several.
+    url_picker: Option<UrlPicker>,
+    /// Choosing between the paths of a command that has several.
+    path_picker: Option<ListPicker>,
+    /// Choosing one of the recent sessions to search.
+    session_picker: Option<SessionPicker>,
+    /// Asking for the day to jump to.
+    date_prompt: Option<DatePrompt>,
+    /// The day the results were jumped to, which they start from.
+    jumped_to: Option<Date>,
+    /// The result last clicked on, and when, to tell a double-click.
+    last_click: Option<(usize, Instant)>,
+    /// Whether the commands run around the selected one are shown, starting from
+    /// `ui.show_related`.
+    show_related: bool,
+    /// The commands most often run right before or after the selected one, and how often.
+    related: Vec<(String, i64)>,
+    /// How many times each result's command was run, with `search.group_duplicates`.
+    run_counts: HashMap<String, i64>,
+    /// How long the query for the results shown took, for the status row.
+    query_time: Option<Duration>,
+    /// The engine queries run on, which one running in the background holds. `engine` is
+    /// another of the same kind, to highlight the results with meanwhile.
+    searcher: SharedEngine,
+    /// The query for what's typed, while it runs in the background.
+    searching: Option<QueryTask>,
+    /// The query for what's typed, while it waits for typing to pause with
+    /// `search.debounce_ms`: the query whose results are showing, and when it's due.
+    waiting: Option<(QueryKey, Instant)>,
+    /// The scripts, aliases, variables and kv pairs a `>` search looks through, read the first
+    /// time one is typed.
+    synced: Option<Vec<everything::Item>>,
+    /// Reading them in the background, until they're all read.
+    loading: Option<everything::Loading>,
+    /// What a `>` search found, listed in place of the history while the input starts with it.
+    items: Option<Vec<everything::Item>>,
 }
 
 #[derive(Clone, Copy)]
@@ -160,24 +298,209 @@ impl State {
         db: &mut dyn Database,
         smart_sort: bool,
     ) -> Result<Vec<History>> {
-        let results = self.engine.query(&self.search, db).await?;
+        // Whatever is running in the background is for an older query
+        self.searching = None;
+        self.waiting = None;
+
+        let start = Instant::now();
+        let key = self.query_key();
+        let results = if let Some(results) = self.result_cache.get(&key) {
+            results
+        } else {
+            let results = self.searcher.lock().await.query(&self.search, db).await?;
+            self.result_cache.insert(key, results.clone());
+            results
+        };
+        self.query_time = Some(start.elapsed());
+
+        Ok(self.show_results(results, smart_sort))
+    }
+
+    /// The results for what's typed if it was queried lately, or `None` having started
+    /// querying it in the background, leaving the results showing up until that's done. With a
+    /// `debounce`, the query waits that long for typing to pause first. With a `>` search,
+    /// there are none, and what it found is listed instead, ranked by `ranking`.
+    fn run_query(
+        &mut self,
+        previous: QueryKey,
+        db: &dyn Database,
+        smart_sort: bool,
+        debounce: Duration,
+        ranking: &SourceRanking,
+    ) -> Option<Vec<History>> {
+        // Still from the results showing, if this replaces a query that never finished
+        let previous = self
+            .waiting
+            .take()
+            .map(|(previous, _)| previous)
+            .or_else(|| self.searching.take().map(|task| task.previous().clone()))
+            .unwrap_or(previous);
+
+        if let Some((kind, query)) = everything::parse(self.search.input.as_str()) {
+            let items = everything::search(
+                self.synced.as_deref().unwrap_or_default(),
+                kind,
+                query,
+                ranking,
+                self.search.filter_mode,
+            );
+            let results = self.show_results(Vec::new(), smart_sort);
+            self.results_len = items.len();
+            self.items = Some(items);
+            return Some(results);
+        }
+        self.items = None;
+
+        let start = Instant::now();
+        let key = self.query_key();
+        if let Some(results) = self.result_cache.get(&key) {
+            self.query_time = Some(start.elapsed());
+            return Some(self.show_results(results, smart_sort));
+        }
+
+        if debounce.is_zero() {
+            self.spawn_query(previous, db);
+        } else {
+            self.waiting = Some((previous, Instant::now() + debounce));
+        }
+
+        None
+    }
+
+    /// Start querying what's typed in the background, once typing has paused if it was
+    /// waiting to.
+    fn spawn_query(&mut self, previous: QueryKey, db: &dyn Database) {
+        self.waiting = None;
+        self.searching = Some(QueryTask::spawn(
+            self.searcher.clone(),
+            self.search.snapshot(),
+            self.query_key(),
+            previous,
+            db,
+        ));
+    }
+
+    /// Whether the results showing are for an older query than what's typed.
+    fn is_searching(&self) -> bool {
+        self.searching.is_some() || self.waiting.is_some()
+    }
+
+    /// The results of the query that ran in the background, if they're for what's typed
+    /// still. Once they're all found, they're kept for when it is again either way. The entry
+    /// selected in the first page of them, `shown`, stays selected among the rest.
+    fn query_finished(
+        &mut self,
+        finished: Finished,
+        shown: &[History],
+        smart_sort: bool,
+    ) -> Result<Option<Vec<History>>> {
+        let results = finished.results?;
+        if finished.complete {
+            self.result_cache
+                .insert(finished.key.clone(), results.clone());
+        }
+        if finished.key != self.query_key() {
+            return Ok(None);
+        }
+
+        let selected = (finished.previous == finished.key)
+            .then(|| shown.get(self.results_state.selected()))
+            .flatten()
+            .map(|h| h.id.clone());
+
+        self.query_time = Some(finished.took);
+        let results = self.show_results(results, smart_sort);
+        if let Some(id) = selected
+            && let Some(pos) = results.iter().position(|h| h.id == id)
+        {
+            self.results_state.select(pos);
+        }
+
+        Ok(Some(results))
+    }
+
+    /// Keep the results showing through the errors a query can hit while it's being typed,
+    /// saying what went wrong. Any other error ends the search.
+    fn query_failed(&mut self, e: eyre::Report) -> Result<()> {
+        // Another process has the database; keep what's on screen, and try again when the
+        // query next changes
+        if ClientError::find(&e) == Some(ClientError::DbLocked) {
+            self.warning = Some(ClientError::DbLocked.to_string());
+            return Ok(());
+        }
+
+        // Likewise while a regex is half typed, saying what's wrong with it
+        if let Some(e) = e.downcast_ref::<regex::Error>() {
+            self.warning = Some(engines::regex::describe_error(e));
+            return Ok(());
+        }
+
+        Err(e)
+    }
+
+    /// In custom context mode, when no filter is applied, highlight the entry which was used
+    /// to enter the context when changing modes. This helps to find your way around.
+    fn select_context_entry(&mut self, previous: &QueryKey, results: &[History]) {
+        if self.search.input.as_str().is_empty()
+            && (previous.custom_context != self.search.custom_context
+                || previous.filter_mode != self.search.filter_mode)
+            && let Some(history_id) = &self.search.custom_context
+            && let Some(pos) = results.iter().position(|entry| entry.id == *history_id)
+        {
+            self.results_state.select(pos);
+        }
+    }
+
+    /// What the current results depend on. When it changes, they need querying again.
+    fn query_key(&self) -> QueryKey {
+        QueryKey {
+            input: self.search.input.as_str().to_owned(),
+            filter_mode: self.search.filter_mode,
+            search_mode: self.search_mode,
+            custom_context: self.search.custom_context.clone(),
+            include_archive: self.search.include_archive,
+            before: self.search.before,
+        }
+    }
+
+    /// The results to open with. With the daemon running these come from its cache of warm
+    /// first pages, so the first frame doesn't wait on a cold database.
+    async fn first_results(
+        &mut self,
+        settings: &Settings,
+        db: &mut dyn Database,
+    ) -> Result<Vec<History>> {
+        #[cfg(feature = "daemon")]
+        let start = Instant::now();
+        #[cfg(feature = "daemon")]
+        if let Some(results) = engines::daemon::first_page(settings, &self.search).await {
+            self.query_time = Some(start.elapsed());
+            self.result_cache.insert(self.query_key(), results.clone());
+            return Ok(self.show_results(results, settings.smart_sort));
+        }
+
+        self.query_results(db, settings.smart_sort).await
+    }
 
+    /// Start over on a new set of results: select the first, and rank them.
+    fn show_results(&mut self, results: Vec<History>, smart_sort: bool) -> Vec<History> {
         self.inspecting_state = InspectingState {
             current: None,
             next: None,
             previous: None,
         };
+        self.results_state.collapse();
         self.results_state.select(0);
         self.results_len = results.len();
 
-        if smart_sort {
-            Ok(atuin_history::sort::sort(
-                self.search.input.as_str(),
-                results,
-            ))
+        let results = if smart_sort {
+            atuin_history::sort::sort(self.search.input.as_str(), results, (self.now)())
         } else {
-            Ok(results)
-        }
+            results
+        };
+
+        let results = atuin_history::sort::boost_successors(results, &self.successors);
+        atuin_history::sort::pin_first(results, &self.pinned)
     }
 
     fn handle_input(&mut self, settings: &Settings, input: &Event) -> InputAction {
@@ -185,6 +508,8 @@ impl State {
             Event::Key(k) => self.handle_key_input(settings, k),
             Event::Mouse(m) => self.handle_mouse_input(*m, settings.invert),
             Event::Paste(d) => self.handle_paste_input(d),
+            // Reflow everything for the new size, clearing what the old one left
+            Event::Resize(..) => InputAction::Redraw,
             _ => InputAction::Continue,
         }
     }
@@ -199,15 +524,52 @@ impl State {
             | (event::MouseEventKind::ScrollUp, false) => {
                 self.scroll_up(1);
             }
+            (event::MouseEventKind::Down(MouseButton::Left), _) => {
+                return self.click(input.column, input.row);
+            }
             _ => {}
         }
         InputAction::Continue
     }
 
-    fn handle_paste_input(&mut self, input: &str) -> InputAction {
-        for i in input.chars() {
-            self.search.input.insert(i);
+    /// Select the result at `column` and `row`, or accept it if it was just clicked on too.
+    /// Clicks do nothing while a menu or prompt is open over the list, or another tab shows.
+    fn click(&mut self, column: u16, row: u16) -> InputAction {
+        let covered = self.template.is_some()
+            || self.placeholders.is_some()
+            || self.theme_picker.as_ref().is_some_and(ThemePicker::is_open)
+            || self.open_with.is_some()
+            || self.url_picker.is_some()
+            || self.path_picker.is_some()
+            || self.session_picker.is_some()
+            || self.date_prompt.is_some();
+        let Some(index) = self
+            .results_state
+            .index_at(column, row)
+            .filter(|&i| i < self.results_len && self.tab_index == 0 && !covered)
+        else {
+            self.last_click = None;
+            return InputAction::Continue;
+        };
+
+        let now = Instant::now();
+        let double = self
+            .last_click
+            .is_some_and(|(last, at)| last == index && now.duration_since(at) <= DOUBLE_CLICK);
+        if double {
+            self.last_click = None;
+            self.accept = true;
+            return InputAction::Accept(index);
         }
+
+        self.last_click = Some((index, now));
+        self.inspecting_state.reset();
+        self.results_state.select(index);
+        InputAction::Continue
+    }
+
+    fn handle_paste_input(&mut self, input: &str) -> InputAction {
+        self.search.input.paste(input);
         InputAction::Continue
     }
 
@@ -266,7 +628,7 @@ impl State {
 
     /// Select the keymap for the current mode (ignoring prefix).
     fn mode_keymap(&self) -> &super::keybindings::Keymap {
-        if self.tab_index == 1 {
+        if self.tab_index != 0 {
             &self.keymaps.inspector
         } else {
             match self.keymap_mode {
@@ -288,6 +650,241 @@ impl State {
             )
     }
 
+    /// Keys while the theme picker is open. Moving the selection previews the
+    /// theme; enter keeps it, and saves it to the config file.
+    fn handle_theme_picker_key(&mut self, input: &KeyEvent) -> InputAction {
+        let Some(picker) = self.theme_picker.as_mut() else {
+            return InputAction::Continue;
+        };
+        let ctrl = input.modifiers.contains(KeyModifiers::CONTROL);
+
+        match input.code {
+            KeyCode::Esc => picker.cancel(),
+            KeyCode::Char('c' | 'g') if ctrl => picker.cancel(),
+            KeyCode::Up | KeyCode::BackTab => picker.select(-1),
+            KeyCode::Char('p') if ctrl => picker.select(-1),
+            KeyCode::Char('k') if !ctrl => picker.select(-1),
+            KeyCode::Down | KeyCode::Tab => picker.select(1),
+            KeyCode::Char('n') if ctrl => picker.select(1),
+            KeyCode::Char('j') if !ctrl => picker.select(1),
+            KeyCode::Enter => {
+                picker.choose();
+                if let Err(e) = config::set_value("theme.name", picker.selected_name()) {
+                    self.warning = Some(format!("Couldn't save the theme: {e}"));
+                }
+            }
+            _ => {}
+        }
+
+        InputAction::Continue
+    }
+
+    /// Keys while the "open with" menu is open. Enter runs the selected command.
+    fn handle_open_with_key(&mut self, input: &KeyEvent) -> InputAction {
+        let Some(menu) = self.open_with.as_mut() else {
+            return InputAction::Continue;
+        };
+        let ctrl = input.modifiers.contains(KeyModifiers::CONTROL);
+
+        match input.code {
+            KeyCode::Esc => self.open_with = None,
+            KeyCode::Char('c' | 'g') if ctrl => self.open_with = None,
+            KeyCode::Up | KeyCode::BackTab => menu.select(-1),
+            KeyCode::Char('p') if ctrl => menu.select(-1),
+            KeyCode::Char('k') if !ctrl => menu.select(-1),
+            KeyCode::Down | KeyCode::Tab => menu.select(1),
+            KeyCode::Char('n') if ctrl => menu.select(1),
+            KeyCode::Char('j') if !ctrl => menu.select(1),
+            KeyCode::Enter => return InputAction::RunOpenWith,
+            _ => {}
+        }
+
+        InputAction::Continue
+    }
+
+    /// Keys while choosing a URL. Enter opens or copies it.
+    fn handle_url_picker_key(&mut self, input: &KeyEvent) -> InputAction {
+        let Some(picker) = self.url_picker.as_mut() else {
+            return InputAction::Continue;
+        };
+        let ctrl = input.modifiers.contains(KeyModifiers::CONTROL);
+
+        match input.code {
+            KeyCode::Esc => self.url_picker = None,
+            KeyCode::Char('c' | 'g') if ctrl => self.url_picker = None,
+            KeyCode::Up | KeyCode::BackTab => picker.select(-1),
+            KeyCode::Char('p') if ctrl => picker.select(-1),
+            KeyCode::Char('k') if !ctrl => picker.select(-1),
+            KeyCode::Down | KeyCode::Tab => picker.select(1),
+            KeyCode::Char('n') if ctrl => picker.select(1),
+            KeyCode::Char('j') if !ctrl => picker.select(1),
+            KeyCode::Enter => {
+                let url = picker.url().to_string();
+                let then = picker.then();
+                self.url_picker = None;
+                self.use_url(&url, then);
+            }
+            _ => {}
+        }
+
+        InputAction::Continue
+    }
+
+    /// Keys while choosing a path. Enter puts it on the prompt.
+    fn handle_path_picker_key(&mut self, input: &KeyEvent) -> InputAction {
+        let Some(picker) = self.path_picker.as_mut() else {
+            return InputAction::Continue;
+        };
+        let ctrl = input.modifiers.contains(KeyModifiers::CONTROL);
+
+        match input.code {
+            KeyCode::Esc => self.path_picker = None,
+            KeyCode::Char('c' | 'g') if ctrl => self.path_picker = None,
+            KeyCode::Up | KeyCode::BackTab => picker.select(-1),
+            KeyCode::Char('p') if ctrl => picker.select(-1),
+            KeyCode::Char('k') if !ctrl => picker.select(-1),
+            KeyCode::Down | KeyCode::Tab => picker.select(1),
+            KeyCode::Char('n') if ctrl => picker.select(1),
+            KeyCode::Char('j') if !ctrl => picker.select(1),
+            KeyCode::Enter => {
+                let path = picker.selected().to_string();
+                self.path_picker = None;
+                return InputAction::ReturnPath(path);
+            }
+            _ => {}
+        }
+
+        InputAction::Continue
+    }
+
+    /// Keys while choosing a session. Enter searches its history, as from its last command.
+    fn handle_session_picker_key(&mut self, input: &KeyEvent) -> InputAction {
+        let Some(picker) = self.session_picker.as_mut() else {
+            return InputAction::Continue;
+        };
+        let ctrl = input.modifiers.contains(KeyModifiers::CONTROL);
+
+        match input.code {
+            KeyCode::Esc => self.session_picker = None,
+            KeyCode::Char('c' | 'g') if ctrl => self.session_picker = None,
+            KeyCode::Up | KeyCode::BackTab => picker.select(-1),
+            KeyCode::Char('p') if ctrl => picker.select(-1),
+            KeyCode::Char('k') if !ctrl => picker.select(-1),
+            KeyCode::Down | KeyCode::Tab => picker.select(1),
+            KeyCode::Char('n') if ctrl => picker.select(1),
+            KeyCode::Char('j') if !ctrl => picker.select(1),
+            KeyCode::Enter => {
+                let last = picker.last().clone();
+                self.session_picker = None;
+                self.search.custom_context = Some(last.id.clone());
+                self.search.context = Context::from_history(&last);
+                self.search.filter_mode = FilterMode::Session;
+                self.search.input = Cursor::from(String::new());
+                self.results_state.reset();
+                self.tab_index = 0;
+            }
+            _ => {}
+        }
+
+        InputAction::Continue
+    }
+
+    /// Keys while asking for the day to jump to. Enter bounds the results by the end of it,
+    /// so they start from that day, or with nothing typed goes back to the latest.
+    fn handle_date_prompt_key(&mut self, settings: &Settings, input: &KeyEvent) -> InputAction {
+        let Some(prompt) = self.date_prompt.as_mut() else {
+            return InputAction::Continue;
+        };
+        let ctrl = input.modifiers.contains(KeyModifiers::CONTROL);
+
+        match input.code {
+            KeyCode::Esc => self.date_prompt = None,
+            KeyCode::Char('c' | 'g') if ctrl => self.date_prompt = None,
+            KeyCode::Enter => {
+                let tz = settings.timezone;
+                match date_jump::parse_day(prompt.value(), (self.now)(), tz, settings.dialect) {
+                    Ok(day) => {
+                        self.date_prompt = None;
+                        self.jumped_to = day;
+                        self.search.before = day.map(|day| date_jump::end_of(day, tz));
+                        self.tab_index = 0;
+                    }
+                    // The prompt stays open to correct it
+                    Err(e) => self.warning = Some(e.to_string()),
+                }
+            }
+            KeyCode::Backspace => prompt.backspace(),
+            KeyCode::Char(c) if !ctrl => prompt.insert(c),
+            _ => {}
+        }
+
+        InputAction::Continue
+    }
+
+    /// Open or copy `url`, and say how that went.
+    fn use_url(&mut self, url: &str, then: UrlUse) {
+        self.warning = Some(match then {
+            UrlUse::Open => match urls::open(url) {
+                Ok(()) => format!("Opened {url}"),
+                Err(e) => format!("Couldn't open {url}: {e}"),
+            },
+            UrlUse::Copy => match clipboard::copy(url) {
+                Ok(()) => format!("Copied {url}"),
+                Err(e) => format!("Couldn't copy {url}: {e}"),
+            },
+        });
+    }
+
+    /// Keys while filling in the placeholders of an accepted command. These
+    /// aren't configurable: it's a short-lived prompt, not a keymap mode.
+    fn handle_placeholder_key(&mut self, input: &KeyEvent) -> InputAction {
+        let Some((index, placeholders)) = self.placeholders.as_mut() else {
+            return InputAction::Continue;
+        };
+        let ctrl = input.modifiers.contains(KeyModifiers::CONTROL);
+
+        match input.code {
+            KeyCode::Esc => self.placeholders = None,
+            KeyCode::Char('c' | 'g') if ctrl => self.placeholders = None,
+            KeyCode::Enter | KeyCode::Tab => {
+                if !placeholders.next() {
+                    return InputAction::Accept(*index);
+                }
+            }
+            KeyCode::BackTab => placeholders.previous(),
+            KeyCode::Backspace => placeholders.backspace(),
+            KeyCode::Char(c) if !ctrl => placeholders.insert(c),
+            _ => {}
+        }
+
+        InputAction::Continue
+    }
+
+    /// Keys while asking for the fields of an accepted template, which like placeholders
+    /// aren't configurable.
+    fn handle_template_key(&mut self, input: &KeyEvent) -> InputAction {
+        let Some((index, prompt)) = self.template.as_mut() else {
+            return InputAction::Continue;
+        };
+        let ctrl = input.modifiers.contains(KeyModifiers::CONTROL);
+
+        match input.code {
+            KeyCode::Esc => self.template = None,
+            KeyCode::Char('c' | 'g') if ctrl => self.template = None,
+            KeyCode::Enter | KeyCode::Tab => {
+                if !prompt.next() {
+                    return InputAction::Accept(*index);
+                }
+            }
+            KeyCode::BackTab => prompt.previous(),
+            KeyCode::Backspace => prompt.backspace(),
+            KeyCode::Char(c) if !ctrl => prompt.insert(c),
+            _ => {}
+        }
+
+        InputAction::Continue
+    }
+
     fn handle_key_input(&mut self, settings: &Settings, input: &KeyEvent) -> InputAction {
         use super::keybindings::Action;
         use super::keybindings::EvalContext;
@@ -300,6 +897,32 @@ impl State {
 
         // Reset switched_search_mode at start of each key event
         self.switched_search_mode = false;
+        self.warning = None;
+
+        if self.template.is_some() {
+            return self.handle_template_key(input);
+        }
+        if self.placeholders.is_some() {
+            return self.handle_placeholder_key(input);
+        }
+        if self.theme_picker.as_ref().is_some_and(ThemePicker::is_open) {
+            return self.handle_theme_picker_key(input);
+        }
+        if self.open_with.is_some() {
+            return self.handle_open_with_key(input);
+        }
+        if self.url_picker.is_some() {
+            return self.handle_url_picker_key(input);
+        }
+        if self.path_picker.is_some() {
+            return self.handle_path_picker_key(input);
+        }
+        if self.session_picker.is_some() {
+            return self.handle_session_picker_key(input);
+        }
+        if self.date_prompt.is_some() {
+            return self.handle_date_prompt_key(settings, input);
+        }
 
         // Build evaluation context from current state
         let ctx = EvalContext {
@@ -310,6 +933,7 @@ impl State {
             results_len: self.results_len,
             original_input_empty: self.original_input_empty,
             has_context: self.search.custom_context.is_some(),
+            kill_ring_empty: !self.search.input.has_killed(),
         };
 
         // Convert KeyEvent to SingleKey
@@ -395,6 +1019,98 @@ impl State {
         }
     }
 
+    /// Whether the search tab's actions apply to marked entries rather than
+    /// the selected one. The inspector always acts on the entry it shows.
+    fn has_marks(&self) -> bool {
+        self.tab_index == 0 && !self.results_state.marked().is_empty()
+    }
+
+    /// Whether `action` would accept a command matching `blocked`, which may only be copied
+    /// or edited.
+    fn blocks_accepting(
+        &self,
+        action: &InputAction,
+        results: &[History],
+        inspecting: Option<&History>,
+        blocked: &RegexSet,
+    ) -> bool {
+        let is_blocked =
+            |entry: Option<&History>| entry.is_some_and(|entry| blocked.is_match(&entry.command));
+
+        match action {
+            _ if blocked.is_empty() => false,
+            InputAction::Accept(index) => is_blocked(results.get(*index)),
+            InputAction::AcceptInspecting => is_blocked(inspecting),
+            InputAction::AcceptItem(index) => self
+                .item(*index)
+                .is_some_and(|item| blocked.is_match(&item.command())),
+            InputAction::RerunInContext(index) if self.tab_index != 0 => {
+                is_blocked(inspecting.or_else(|| results.get(*index)))
+            }
+            InputAction::RerunInContext(index) => is_blocked(results.get(*index)),
+            InputAction::AcceptMarked => self
+                .results_state
+                .marked()
+                .iter()
+                .any(|entry| is_blocked(Some(entry))),
+            _ => false,
+        }
+    }
+
+    /// What a `>` search found at `index`.
+    fn item(&self, index: usize) -> Option<&everything::Item> {
+        self.items.as_ref()?.get(index)
+    }
+
+    /// What `action` does while a `>` search lists scripts, aliases and such, or `None` if it's
+    /// no different. They can be moved through and accepted, but nothing that needs a history
+    /// entry can be done to them.
+    fn item_action(&mut self, action: &super::keybindings::Action) -> Option<InputAction> {
+        use crate::command::client::search::keybindings::Action;
+
+        match action {
+            Action::Accept => {
+                self.accept = true;
+                Some(InputAction::AcceptItem(self.results_state.selected()))
+            }
+            Action::ReturnSelection => Some(InputAction::AcceptItem(self.results_state.selected())),
+            Action::AcceptNth(n) | Action::ReturnSelectionNth(n) => {
+                let index = self.nth_result(*n)?;
+                self.accept = matches!(action, Action::AcceptNth(_));
+                Some(InputAction::AcceptItem(index))
+            }
+            Action::Copy
+            | Action::RerunInContext
+            | Action::EditCommand
+            | Action::OpenWith
+            | Action::OpenUrl
+            | Action::CopyUrl
+            | Action::InsertPath
+            | Action::AcceptCd
+            | Action::Delete
+            | Action::DeleteAll
+            | Action::SwitchContext
+            | Action::ToggleTab
+            | Action::ToggleDetails
+            | Action::ToggleMark
+            | Action::TogglePin
+            | Action::ToggleGroup
+            | Action::JumpToDate
+            | Action::ScrollDayUp
+            | Action::ScrollDayDown
+            | Action::InspectPrevious
+            | Action::InspectNext
+            | Action::CopyField(_) => Some(InputAction::Continue),
+            _ => None,
+        }
+    }
+
+    /// The row the `n`th numeric shortcut picks, `n` rows past the selection, if there's a
+    /// result there. Keys for rows without a number do nothing, rather than leave the search.
+    fn nth_result(&self, n: u8) -> Option<usize> {
+        Some(self.results_state.selected() + usize::from(n)).filter(|&i| i < self.results_len)
+    }
+
     fn scroll_down(&mut self, scroll_len: usize) {
         let i = self.results_state.selected().saturating_sub(scroll_len);
         self.inspecting_state.reset();
@@ -425,6 +1141,12 @@ impl State {
     ) -> InputAction {
         use crate::command::client::search::keybindings::Action;
 
+        if self.items.is_some()
+            && let Some(action) = self.item_action(action)
+        {
+            return action;
+        }
+
         match action {
             // -- Cursor movement --
             Action::CursorLeft => {
@@ -482,15 +1204,7 @@ impl State {
                 InputAction::Continue
             }
             Action::DeleteToWordBoundary => {
-                // ctrl-w: remove trailing whitespace, then delete to word boundary
-                while matches!(self.search.input.back(), Some(c) if c.is_whitespace()) {}
-                while self.search.input.left() {
-                    if self.search.input.char().unwrap().is_whitespace() {
-                        self.search.input.right();
-                        break;
-                    }
-                    self.search.input.remove();
-                }
+                self.search.input.remove_prev_whitespace_word();
                 InputAction::Continue
             }
             Action::ClearLine => {
@@ -505,6 +1219,18 @@ impl State {
                 self.search.input.clear_to_end();
                 InputAction::Continue
             }
+            Action::Undo => {
+                self.search.input.undo();
+                InputAction::Continue
+            }
+            Action::Yank => {
+                self.search.input.yank();
+                InputAction::Continue
+            }
+            Action::YankPop => {
+                self.search.input.yank_pop();
+                InputAction::Continue
+            }
 
             // -- List navigation (invert-aware) --
             Action::SelectNext => {
@@ -625,29 +1351,55 @@ impl State {
                 self.inspecting_state.reset();
                 InputAction::Continue
             }
+            Action::ScrollLeft => {
+                self.results_state.scroll_left();
+                InputAction::Continue
+            }
+            Action::ScrollRight => {
+                self.results_state.scroll_right();
+                InputAction::Continue
+            }
 
             // -- Commands --
             Action::Accept => {
-                if self.tab_index == 1 {
+                if self.tab_index != 0 {
                     return InputAction::AcceptInspecting;
                 }
                 self.accept = true;
+                if self.has_marks() {
+                    return InputAction::AcceptMarked;
+                }
                 InputAction::Accept(self.results_state.selected())
             }
-            Action::AcceptNth(n) => {
-                self.accept = true;
-                InputAction::Accept(self.results_state.selected() + *n as usize)
-            }
+            Action::AcceptNth(n) => match self.nth_result(*n) {
+                Some(index) => {
+                    self.accept = true;
+                    InputAction::Accept(index)
+                }
+                None => InputAction::Continue,
+            },
             Action::ReturnSelection => {
-                if self.tab_index == 1 {
+                if self.tab_index != 0 {
                     return InputAction::AcceptInspecting;
                 }
+                if self.has_marks() {
+                    return InputAction::AcceptMarked;
+                }
                 InputAction::Accept(self.results_state.selected())
             }
-            Action::ReturnSelectionNth(n) => {
-                InputAction::Accept(self.results_state.selected() + *n as usize)
-            }
+            Action::ReturnSelectionNth(n) => self
+                .nth_result(*n)
+                .map_or(InputAction::Continue, InputAction::Accept),
+            Action::Copy if self.has_marks() => InputAction::CopyMarked,
             Action::Copy => InputAction::Copy(self.results_state.selected()),
+            Action::RerunInContext => InputAction::RerunInContext(self.results_state.selected()),
+            Action::EditCommand => InputAction::Edit(self.results_state.selected()),
+            Action::OpenWith => InputAction::OpenWith(self.results_state.selected()),
+            Action::OpenUrl => InputAction::UseUrl(self.results_state.selected(), UrlUse::Open),
+            Action::CopyUrl => InputAction::UseUrl(self.results_state.selected(), UrlUse::Copy),
+            Action::InsertPath => InputAction::InsertPath(self.results_state.selected()),
+            Action::AcceptCd => InputAction::ChangeDirectory(self.results_state.selected()),
+            Action::Delete if self.has_marks() => InputAction::DeleteMarked,
             Action::Delete => InputAction::Delete(self.results_state.selected()),
             Action::DeleteAll => InputAction::DeleteAllMatching(self.results_state.selected()),
             Action::ReturnOriginal => InputAction::ReturnOriginal,
@@ -662,6 +1414,7 @@ impl State {
                 self.switched_search_mode = true;
                 self.search_mode = self.search_mode.next(settings);
                 self.engine = engines::engine(self.search_mode, settings);
+                self.searcher = engines::shared(self.search_mode, settings);
                 InputAction::Continue
             }
             Action::SwitchContext => {
@@ -669,7 +1422,67 @@ impl State {
             }
             Action::ClearContext => InputAction::SwitchContext(None),
             Action::ToggleTab => {
-                self.tab_index = (self.tab_index + 1) % TAB_TITLES.len();
+                // From the details tab too, straight back to the search
+                self.tab_index = usize::from(self.tab_index == 0);
+                InputAction::Continue
+            }
+            Action::ToggleMark => {
+                if self.tab_index != 0 {
+                    return InputAction::Continue;
+                }
+                InputAction::ToggleMark(self.results_state.selected())
+            }
+            Action::TogglePin => InputAction::TogglePin(self.results_state.selected()),
+            Action::ToggleGroup => {
+                if self.tab_index != 0 {
+                    return InputAction::Continue;
+                }
+                if !settings.search.group_duplicates {
+                    self.warning = Some(
+                        "Set search.group_duplicates to list the runs of a command".to_string(),
+                    );
+                    return InputAction::Continue;
+                }
+                InputAction::ToggleGroup(self.results_state.selected())
+            }
+            Action::PickSession => InputAction::PickSession,
+            Action::CycleSource => {
+                let input = everything::cycle(self.search.input.as_str());
+                self.search.input.replace(input);
+                InputAction::Continue
+            }
+            Action::JumpToDate => {
+                self.date_prompt = Some(DatePrompt::default());
+                InputAction::Continue
+            }
+            // Up is to older days, unless the results are inverted
+            Action::ScrollDayUp if settings.invert => {
+                InputAction::LaterDay(self.results_state.selected())
+            }
+            Action::ScrollDayUp => InputAction::EarlierDay(self.results_state.selected()),
+            Action::ScrollDayDown if settings.invert => {
+                InputAction::EarlierDay(self.results_state.selected())
+            }
+            Action::ScrollDayDown => InputAction::LaterDay(self.results_state.selected()),
+            Action::TogglePreview => {
+                self.show_preview = !self.show_preview;
+                InputAction::Redraw
+            }
+            Action::ToggleRelated => {
+                self.show_related = !self.show_related;
+                InputAction::Redraw
+            }
+            Action::SelectTheme => {
+                self.theme_picker
+                    .get_or_insert_with(|| ThemePicker::new(settings))
+                    .open();
+                InputAction::Continue
+            }
+            Action::ToggleArchive => {
+                match &mut self.search.include_archive {
+                    Some(include) => *include = !*include,
+                    None => self.warning = Some("No history has been archived".to_string()),
+                }
                 InputAction::Continue
             }
 
@@ -728,6 +1541,11 @@ impl State {
                 self.inspecting_state.move_to_next();
                 InputAction::Redraw
             }
+            Action::ToggleDetails => {
+                self.tab_index = if self.tab_index == 2 { 1 } else { 2 };
+                InputAction::Continue
+            }
+            Action::CopyField(field) => InputAction::CopyField(*field),
 
             // -- Special --
             Action::Noop => InputAction::Continue,
@@ -736,8 +1554,10 @@ impl State {
 
     #[allow(clippy::cast_possible_truncation)]
     #[allow(clippy::bool_to_int_with_if)]
+    #[allow(clippy::too_many_arguments)]
     fn calc_preview_height(
         settings: &Settings,
+        show_preview: bool,
         results: &[History],
         selected: usize,
         tab_index: usize,
@@ -745,7 +1565,7 @@ impl State {
         border_size: u16,
         preview_width: u16,
     ) -> u16 {
-        if settings.show_preview
+        if show_preview
             && settings.preview.strategy == PreviewStrategy::Auto
             && tab_index == 0
             && !results.is_empty()
@@ -780,7 +1600,7 @@ impl State {
             } else {
                 1
             }
-        } else if settings.show_preview
+        } else if show_preview
             && settings.preview.strategy == PreviewStrategy::Static
             && tab_index == 0
         {
@@ -799,9 +1619,9 @@ impl State {
                         .sum(),
                 )
             }) + border_size * 2
-        } else if settings.show_preview && settings.preview.strategy == PreviewStrategy::Fixed {
+        } else if show_preview && settings.preview.strategy == PreviewStrategy::Fixed {
             settings.max_preview_height + border_size * 2
-        } else if !matches!(compactness, Compactness::Full) || tab_index == 1 {
+        } else if !matches!(compactness, Compactness::Full) || tab_index != 0 {
             0
         } else {
             1
@@ -826,6 +1646,28 @@ impl State {
             f.render_widget(Clear, area);
         }
         self.draw_inner(f, area, results, stats, inspecting, settings, theme);
+
+        if let Some(picker) = self.theme_picker.as_ref().filter(|p| p.is_open()) {
+            picker.draw(f, area);
+        }
+        if let Some(menu) = &self.open_with {
+            menu.draw(f, area, theme);
+        }
+        if let Some(picker) = &self.url_picker {
+            picker.draw(f, area, theme);
+        }
+        if let Some(picker) = &self.path_picker {
+            picker.draw(f, area, theme);
+        }
+        if let Some(picker) = &self.session_picker {
+            picker.draw(f, area, theme);
+        }
+        if let Some(prompt) = &self.date_prompt {
+            prompt.draw(f, area, theme);
+        }
+        if let Some((_, prompt)) = &self.template {
+            prompt.draw(f, area, theme);
+        }
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -847,21 +1689,52 @@ impl State {
             Compactness::Full => 1,
             _ => 0,
         };
-        let preview_width = area.width.saturating_sub(2);
-        let preview_height = Self::calc_preview_height(
-            settings,
-            results,
-            self.results_state.selected(),
-            self.tab_index,
-            compactness,
-            border_size,
-            preview_width,
-        );
+        #[allow(clippy::cast_possible_truncation)]
+        let prefix_width = settings
+            .ui
+            .columns
+            .iter()
+            .take_while(|col| !col.expand)
+            .map(|col| col.width + 1)
+            .sum::<u16>()
+            + 1; // 1 space left padding
+        #[allow(clippy::cast_possible_truncation)]
+        let prefix_width = std::cmp::max(prefix_width, "[ SRCH: FULLTXT ] ".len() as u16);
         let show_help =
             settings.show_help && (matches!(compactness, Compactness::Full) || area.height > 1);
         // This is an OR, as it seems more likely for someone to wish to override
         // tabs unexpectedly being missed, than unexpectedly present.
         let show_tabs = settings.show_tabs && !matches!(compactness, Compactness::Ultracompact);
+        let show_status = settings.show_status && !matches!(compactness, Compactness::Ultracompact);
+
+        // Everything but the preview, which gives way when space runs short
+        let min_height = match compactness {
+            Compactness::Ultracompact => u16::from(show_help) + 1,
+            _ => {
+                u16::from(show_help)
+                    + u16::from(show_tabs)
+                    + u16::from(show_status)
+                    + 2
+                    + border_size * 2
+            }
+        };
+        let min_width = prefix_width + MIN_COMMAND_WIDTH + 2 + border_size * 2;
+        if area.height < min_height || area.width < min_width {
+            self.draw_minimal(f, area, results, settings, theme);
+            return;
+        }
+
+        let preview_width = area.width.saturating_sub(2);
+        let preview_height = Self::calc_preview_height(
+            settings,
+            self.show_preview,
+            results,
+            self.results_state.selected(),
+            self.tab_index,
+            compactness,
+            border_size,
+            preview_width,
+        );
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .margin(0)
@@ -869,11 +1742,12 @@ impl State {
             .constraints::<&[Constraint]>(
                 if invert {
                     [
-                        Constraint::Length(1 + border_size),               // input
-                        Constraint::Min(1),                                // results list
-                        Constraint::Length(preview_height),                // preview
-                        Constraint::Length(if show_tabs { 1 } else { 0 }), // tabs
-                        Constraint::Length(if show_help { 1 } else { 0 }), // header (sic)
+                        Constraint::Length(if show_status { 1 } else { 0 }), // status
+                        Constraint::Length(1 + border_size),                 // input
+                        Constraint::Min(1),                                  // results list
+                        Constraint::Length(preview_height),                  // preview
+                        Constraint::Length(if show_tabs { 1 } else { 0 }),   // tabs
+                        Constraint::Length(if show_help { 1 } else { 0 }),   // header (sic)
                     ]
                 } else {
                     match compactness {
@@ -883,6 +1757,7 @@ impl State {
                             Constraint::Min(1),                                // results list
                             Constraint::Length(0),
                             Constraint::Length(0),
+                            Constraint::Length(0),
                         ],
                         _ => [
                             Constraint::Length(if show_help { 1 } else { 0 }), // header
@@ -890,6 +1765,7 @@ impl State {
                             Constraint::Min(1),                                // results list
                             Constraint::Length(1 + border_size),               // input
                             Constraint::Length(preview_height),                // preview
+                            Constraint::Length(if show_status { 1 } else { 0 }), // status
                         ],
                     }
                 }
@@ -897,11 +1773,12 @@ impl State {
             )
             .split(area);
 
-        let input_chunk = if invert { chunks[0] } else { chunks[3] };
-        let results_list_chunk = if invert { chunks[1] } else { chunks[2] };
-        let preview_chunk = if invert { chunks[2] } else { chunks[4] };
-        let tabs_chunk = if invert { chunks[3] } else { chunks[1] };
-        let header_chunk = if invert { chunks[4] } else { chunks[0] };
+        let input_chunk = if invert { chunks[1] } else { chunks[3] };
+        let results_list_chunk = chunks[2];
+        let preview_chunk = if invert { chunks[3] } else { chunks[4] };
+        let tabs_chunk = if invert { chunks[4] } else { chunks[1] };
+        let header_chunk = if invert { chunks[5] } else { chunks[0] };
+        let status_chunk = if invert { chunks[0] } else { chunks[5] };
 
         // TODO: this should be split so that we have one interactive search container that is
         // EITHER a search box or an inspector. But I'm not doing that now, way too much atm.
@@ -942,28 +1819,71 @@ impl State {
         let help = self.build_help(settings, theme);
         f.render_widget(help, header_chunks[1]);
 
-        let stats_tab = self.build_stats(theme);
+        // Without the status row, say a query's running where the history count goes
+        let stats_tab = self.build_stats(theme, !show_status && self.is_searching());
         f.render_widget(stats_tab, header_chunks[2]);
 
+        if show_status {
+            let (count, modes) = self.build_status(theme);
+            let modes_width = u16::try_from(modes.width()).unwrap_or(u16::MAX);
+            let [count_chunk, modes_chunk] =
+                Layout::horizontal([Constraint::Min(0), Constraint::Length(modes_width)])
+                    .areas(status_chunk);
+            f.render_widget(count, count_chunk);
+            f.render_widget(modes, modes_chunk);
+        }
+
         match self.tab_index {
+            0 if self.items.is_some() => self.draw_items(f, results_list_chunk, style, theme),
             0 => {
                 let history_highlighter = HistoryHighlighter {
                     engine: self.engine.as_ref(),
                     search_input: self.search.input.as_str(),
                 };
+                let (results_list_chunk, related_chunk) = if self.show_related
+                    && matches!(compactness, Compactness::Full)
+                    && results_list_chunk.width >= RELATED_MIN_WIDTH
+                {
+                    let [list, related] = Layout::horizontal([
+                        Constraint::Min(0),
+                        Constraint::Length(results_list_chunk.width / 3),
+                    ])
+                    .areas(results_list_chunk);
+                    (list, Some(related))
+                } else {
+                    (results_list_chunk, None)
+                };
+                if let Some(related_chunk) = related_chunk {
+                    f.render_widget(self.build_related(invert, theme), related_chunk);
+                }
+
                 let results_list = Self::build_results_list(
                     style,
+                    self.search.filter_mode,
+                    self.session_name(),
+                    self.jumped_to,
                     results,
                     &self.now,
                     theme,
                     history_highlighter,
                     settings.ui.syntax_highlight,
                     &settings.ui.columns,
-                );
+                )
+                .host_names(&self.host_names)
+                .numeric_shortcuts(settings.show_numeric_shortcuts)
+                .pinned(&self.pinned)
+                .run_counts(&self.run_counts)
+                .wrap_selected(settings.ui.wrap_selected);
+                // Everything is from another host there, so there's nothing to point out
+                let results_list = if self.search.filter_mode == FilterMode::OtherHosts {
+                    results_list
+                } else {
+                    results_list.local_host(&self.search.context.hostname)
+                };
                 f.render_stateful_widget(results_list, results_list_chunk, &mut self.results_state);
             }
 
-            1 => {
+            1 | 2 => {
                 if results.is_empty() {
                     let message = Paragraph::new("Nothing to inspect")
                         .block(
@@ -980,15 +1900,29 @@ impl State {
                         Some(inspecting) => inspecting,
                         None => &results[self.results_state.selected()],
                     };
-                    super::inspector::draw(
-                        f,
-                        results_list_chunk,
-                        inspecting,
-                        &stats.expect("Drawing inspector, but no stats"),
-                        settings,
-                        theme,
-                        settings.timezone,
-                    );
+                    let stats = stats.expect("Drawing inspector, but no stats");
+                    if self.tab_index == 2 {
+                        super::inspector::draw_details(
+                            f,
+                            results_list_chunk,
+                            inspecting,
+                            &stats,
+                            theme,
+                            settings.timezone,
+                            &self.host_names,
+                        );
+                    } else {
+                        super::inspector::draw(
+                            f,
+                            results_list_chunk,
+                            inspecting,
+                            &stats,
+                            settings,
+                            theme,
+                            settings.timezone,
+                            &self.host_names,
+                        );
+                    }
                 }
 
                 // HACK: I'm following up with abstracting this into the UI container, with a
@@ -1016,19 +1950,9 @@ impl State {
                 compactness,
                 preview_width,
                 preview_chunk.width.into(),
+                settings.ui.syntax_highlight,
                 theme,
             );
-            #[allow(clippy::cast_possible_truncation)]
-            let prefix_width = settings
-                .ui
-                .columns
-                .iter()
-                .take_while(|col| !col.expand)
-                .map(|col| col.width + 1)
-                .sum::<u16>()
-                + 1; // 1 space left padding
-            #[allow(clippy::cast_possible_truncation)]
-            let min_prefix_width = "[ SRCH: FULLTXT ] ".len() as u16;
             self.draw_preview(
                 f,
                 style,
@@ -1036,10 +1960,77 @@ impl State {
                 compactness,
                 preview_chunk,
                 preview,
-                std::cmp::max(prefix_width, min_prefix_width),
+                prefix_width,
+                theme,
+            );
+        }
+    }
+
+    /// The layout for terminals too small for the normal one: the query, and
+    /// as many of the top results as fit, up to [`MINIMAL_RESULTS`].
+    #[allow(clippy::cast_possible_truncation)]
+    fn draw_minimal(
+        &mut self,
+        f: &mut Frame,
+        area: Rect,
+        results: &[History],
+        settings: &Settings,
+        theme: &Theme,
+    ) {
+        if area.is_empty() {
+            return;
+        }
+
+        let results_height = area.height.saturating_sub(1).min(MINIMAL_RESULTS);
+        let [input_chunk, results_chunk] = if settings.invert {
+            Layout::vertical([Constraint::Length(1), Constraint::Length(results_height)])
+                .areas(area)
+        } else {
+            let [results_chunk, input_chunk] =
+                Layout::vertical([Constraint::Length(results_height), Constraint::Length(1)])
+                    .flex(Flex::End)
+                    .areas(area);
+            [input_chunk, results_chunk]
+        };
+
+        if self.tab_index == 0 && self.items.is_some() {
+            let style = StyleState {
+                compactness: Compactness::Ultracompact,
+                invert: settings.invert,
+                inner_width: results_chunk.width.into(),
+            };
+            self.draw_items(f, results_chunk, style, theme);
+        } else if self.tab_index == 0 {
+            let columns = [UiColumn::new(UiColumnType::Command)];
+            let results_list = HistoryList::new(
+                results,
+                settings.invert,
+                &self.now,
                 theme,
+                HistoryHighlighter {
+                    engine: self.engine.as_ref(),
+                    search_input: self.search.input.as_str(),
+                },
+                settings.ui.syntax_highlight,
+                &columns,
             );
+            f.render_stateful_widget(results_list, results_chunk, &mut self.results_state);
         }
+
+        let (query, before_cursor) = match &self.placeholders {
+            Some((_, placeholders)) => (
+                placeholders.command(),
+                &placeholders.command()[..placeholders.current().end],
+            ),
+            None => (self.search.input.as_str(), self.search.input.substring()),
+        };
+        f.render_widget(Paragraph::new(format!("> {query}")), input_chunk);
+
+        let cursor_x = input_chunk.x + 2 + UnicodeWidthStr::width(before_cursor) as u16;
+        f.set_cursor_position((
+            cursor_x.min(input_chunk.right().saturating_sub(1)),
+            input_chunk.y,
+        ));
     }
 
     #[allow(clippy::cast_possible_truncation, clippy::too_many_arguments)]
@@ -1059,7 +2050,12 @@ impl State {
 
         f.render_widget(preview, preview_chunk);
 
-        let extra_width = UnicodeWidthStr::width(self.search.input.substring());
+        let extra_width = match &self.placeholders {
+            Some((_, placeholders)) => {
+                UnicodeWidthStr::width(&placeholders.command()[..placeholders.current().end])
+            }
+            None => UnicodeWidthStr::width(self.search.input.substring()),
+        };
 
         let cursor_offset = match compactness {
             Compactness::Full => 1,
@@ -1073,7 +2069,13 @@ impl State {
     }
 
     fn build_title(&self, theme: &Theme) -> Paragraph<'_> {
-        let title = if self.update_needed.is_some() {
+        let title = if self.offline.is_some() {
+            let warn_style: Style = Style::from_crossterm(theme.as_style(Meaning::AlertWarn));
+            Paragraph::new(Text::from(Span::styled(
+                format!("Atuin v{VERSION} - OFFLINE"),
+                warn_style.add_modifier(Modifier::BOLD),
+            )))
+        } else if self.update_needed.is_some() {
             let error_style: Style = Style::from_crossterm(theme.get_error());
             Paragraph::new(Text::from(Span::styled(
                 format!("Atuin v{VERSION} - UPDATE"),
@@ -1081,8 +2083,13 @@ impl State {
             )))
         } else {
             let style: Style = Style::from_crossterm(theme.as_style(Meaning::Base));
+            let title = if self.search.include_archive == Some(true) {
+                format!("Atuin v{VERSION} - ARCHIVE")
+            } else {
+                format!("Atuin v{VERSION}")
+            };
             Paragraph::new(Text::from(Span::styled(
-                format!("Atuin v{VERSION}"),
+                title,
                 style.add_modifier(Modifier::BOLD),
             )))
         };
@@ -1091,6 +2098,57 @@ impl State {
 
     #[allow(clippy::unused_self)]
     fn build_help(&self, settings: &Settings, theme: &Theme) -> Paragraph<'_> {
+        if self.template.is_some() {
+            return Paragraph::new(Text::from(Line::from(vec![
+                Span::raw("type a value, "),
+                Span::styled("<tab>", Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(": next, "),
+                Span::styled("<shift-tab>", Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(": previous, "),
+                Span::styled("<esc>", Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(": back"),
+            ])))
+            .style(Style::from_crossterm(theme.as_style(Meaning::Annotation)))
+            .alignment(Alignment::Center);
+        }
+
+        if let Some((_, placeholders)) = &self.placeholders {
+            let (n, total) = placeholders.position();
+            return Paragraph::new(Text::from(Line::from(vec![
+                Span::raw(format!("placeholder {n}/{total}: type to replace, ")),
+                Span::styled("<tab>", Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(": next, "),
+                Span::styled("<esc>", Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(": back"),
+            ])))
+            .style(Style::from_crossterm(theme.as_style(Meaning::Annotation)))
+            .alignment(Alignment::Center);
+        }
+
+        if let Some(warning) = &self.warning {
+            return Paragraph::new(warning.as_str())
+                .style(Style::from_crossterm(theme.as_style(Meaning::AlertWarn)))
+                .alignment(Alignment::Center);
+        }
+
+        if self.has_marks() {
+            let marked = self.results_state.marked().len();
+            return Paragraph::new(Text::from(Line::from(vec![
+                Span::raw(format!("{marked} marked, ")),
+                Span::styled("<enter>", Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(if settings.enter_accept {
+                    ": run all"
+                } else {
+                    ": edit all"
+                }),
+                Span::raw(", "),
+                Span::styled("<esc>", Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(": exit"),
+            ])))
+            .style(Style::from_crossterm(theme.as_style(Meaning::Annotation)))
+            .alignment(Alignment::Center);
+        }
+
         match self.tab_index {
             // search
             0 => Paragraph::new(Text::from(Line::from(vec![
@@ -1098,7 +2156,11 @@ impl State {
                 Span::raw(": exit"),
                 Span::raw(", "),
                 Span::styled("<tab>", Style::default().add_modifier(Modifier::BOLD)),
-                Span::raw(": edit"),
+                Span::raw(if settings.search.multi_select {
+                    ": mark"
+                } else {
+                    ": edit"
+                }),
                 Span::raw(", "),
                 Span::styled("<enter>", Style::default().add_modifier(Modifier::BOLD)),
                 Span::raw(if settings.enter_accept {
@@ -1120,6 +2182,23 @@ impl State {
                 Span::raw(", "),
                 Span::styled("<ctrl-d>", Style::default().add_modifier(Modifier::BOLD)),
                 Span::raw(": delete"),
+                Span::raw(", "),
+                Span::styled("<i>", Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(": details"),
+            ]))),
+
+            2 => Paragraph::new(Text::from(Line::from(vec![
+                Span::styled("<esc>", Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(": exit"),
+                Span::raw(", "),
+                Span::styled("<i>", Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(": stats"),
+                Span::raw(", "),
+                Span::styled(
+                    "<c/d/h/u/s/t/x>",
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(": copy a field"),
             ]))),
 
             _ => unreachable!("invalid tab index"),
@@ -1128,18 +2207,49 @@ impl State {
         .alignment(Alignment::Center)
     }
 
-    fn build_stats(&self, theme: &Theme) -> Paragraph<'_> {
-        Paragraph::new(Text::from(Span::raw(
+    fn build_stats(&self, theme: &Theme, searching: bool) -> Paragraph<'_> {
+        let text = if searching {
+            "searching…".to_string()
+        } else {
             self.history_count
-                .map_or_else(String::new, |count| format!("history count: {count}")),
-        )))
-        .style(Style::from_crossterm(theme.as_style(Meaning::Annotation)))
-        .alignment(Alignment::Right)
+                .map_or_else(String::new, |count| format!("history count: {count}"))
+        };
+        Paragraph::new(Text::from(Span::raw(text)))
+            .style(Style::from_crossterm(theme.as_style(Meaning::Annotation)))
+            .alignment(Alignment::Right)
+    }
+
+    /// The status row: how many results the query found and how long it took, and the search
+    /// and filter modes it ran with.
+    fn build_status(&self, theme: &Theme) -> (Paragraph<'_>, Line<'_>) {
+        let style = Style::from_crossterm(theme.as_style(Meaning::Annotation));
+        let results = match self.results_len {
+            1 => "1 result".to_string(),
+            n => format!("{n} results"),
+        };
+        let count = match self.query_time {
+            _ if self.is_searching() => format!(" {results} · searching…"),
+            Some(took) => format!(" {results} in {} ms", took.as_millis()),
+            None => format!(" {results}"),
+        };
+        let modes = format!(
+            "{} · {} ",
+            self.search_mode.as_str().to_lowercase(),
+            self.search.filter_mode.as_str().to_lowercase()
+        );
+
+        (
+            Paragraph::new(count).style(style),
+            Line::styled(modes, style),
+        )
     }
 
     #[allow(clippy::too_many_arguments)]
     fn build_results_list<'a>(
         style: StyleState,
+        filter_mode: FilterMode,
+        session_name: Option<&str>,
+        jumped_to: Option<Date>,
         results: &'a [History],
         now: &'a dyn Fn() -> OffsetDateTime,
         theme: &'a Theme,
@@ -1167,7 +2277,8 @@ impl State {
                     )
                 } else {
                     results_list.block(
-                        titled_block(theme).borders(Borders::TOP | Borders::LEFT | Borders::RIGHT),
+                        titled_block(theme, filter_mode, session_name, jumped_to)
+                            .borders(Borders::TOP | Borders::LEFT | Borders::RIGHT),
                     )
                 }
             }
@@ -1175,6 +2286,79 @@ impl State {
         }
     }
 
+    /// What a `>` search found, where the results go.
+    fn draw_items(&self, f: &mut Frame, area: Rect, style: StyleState, theme: &Theme) {
+        let kind = everything::parse(self.search.input.as_str()).and_then(|(kind, _)| kind);
+        let block = match style.compactness {
+            Compactness::Full if style.invert => Some(
+                themed_block(theme)
+                    .borders(Borders::LEFT | Borders::RIGHT)
+                    .title(format!("{:─>width$}", "", width = style.inner_width - 2)),
+            ),
+            Compactness::Full => Some(
+                themed_block(theme)
+                    .title(
+                        Line::from(everything::title(kind))
+                            .style(Style::new().add_modifier(Modifier::BOLD)),
+                    )
+                    .title_alignment(Alignment::Center)
+                    .borders(Borders::TOP | Borders::LEFT | Borders::RIGHT),
+            ),
+            _ => None,
+        };
+
+        everything::draw(
+            f,
+            area,
+            self.items.as_deref().unwrap_or_default(),
+            self.results_state.selected(),
+            style.invert,
+            block,
+            theme,
+        );
+    }
+
+    /// The commands run right before or after the selected one, beside the results.
+    fn build_related(&self, invert: bool, theme: &Theme) -> Paragraph<'_> {
+        let count_style = Style::from_crossterm(theme.as_style(Meaning::Annotation));
+        // The first is the most frequent, so has the widest count
+        let width = self
+            .related
+            .first()
+            .map_or(0, |(_, count)| count.to_string().len());
+        let lines: Vec<Line> = if self.related.is_empty() {
+            vec![Line::styled(" Nothing yet", count_style)]
+        } else {
+            self.related
+                .iter()
+                .map(|(command, count)| {
+                    Line::from(vec![
+                        Span::styled(format!(" {count:>width$} "), count_style),
+                        Span::raw(one_line(command)),
+                    ])
+                })
+                .collect()
+        };
+
+        let borders = if invert {
+            Borders::LEFT | Borders::RIGHT
+        } else {
+            Borders::TOP | Borders::LEFT | Borders::RIGHT
+        };
+        Paragraph::new(lines).block(themed_block(theme).borders(borders).title(" Run with "))
+    }
+
+    /// The name of the session being searched, when only one is and it has a name.
+    fn session_name(&self) -> Option<&str> {
+        matches!(
+            self.search.filter_mode,
+            FilterMode::Session | FilterMode::SessionPreload
+        )
+        .then(|| self.session_names.get(&self.search.context.session))
+        .flatten()
+        .map(String::as_str)
+    }
+
     fn build_input(&self, style: StyleState, prefix_width: u16, theme: &Theme) -> Paragraph<'_> {
         let (pref, mode) = if self.prefix {
             ("", "PREFIX")
@@ -1189,13 +2373,34 @@ impl State {
         let mode_width = usize::from(prefix_width) - pref.len() - 3;
         // sanity check to ensure we don't exceed the layout limits
         debug_assert!(mode_width >= mode.len(), "mode name '{mode}' is too long!");
-        let input = format!(" [{pref}{mode:^mode_width$}] {}", self.search.input.as_str());
-        let input = Paragraph::new(input);
+        let prefix = format!(" [{pref}{mode:^mode_width$}] ");
+        let input = match &self.placeholders {
+            Some((_, placeholders)) => {
+                let command = placeholders.command();
+                let current = placeholders.current();
+                Paragraph::new(Line::from(vec![
+                    Span::raw(prefix),
+                    Span::raw(&command[..current.start]),
+                    Span::styled(
+                        &command[current.clone()],
+                        Style::default().add_modifier(Modifier::REVERSED),
+                    ),
+                    Span::raw(&command[current.end..]),
+                ]))
+            }
+            None => Paragraph::new(self.input_line(prefix, theme)),
+        };
         match style.compactness {
             Compactness::Full => {
                 if style.invert {
                     input.block(
-                        titled_block(theme).borders(Borders::LEFT | Borders::RIGHT | Borders::TOP),
+                        titled_block(
+                            theme,
+                            self.search.filter_mode,
+                            self.session_name(),
+                            self.jumped_to,
+                        )
+                        .borders(Borders::LEFT | Borders::RIGHT | Borders::TOP),
                     )
                 } else {
                     input.block(
@@ -1209,28 +2414,61 @@ impl State {
         }
     }
 
+    /// The selected command in full, wrapped to the preview's width and syntax highlighted
+    /// when `syntax_highlight` is on.
+    /// The search input, with filters like `exit:0` picked out from the words to search for.
+    fn input_line(&self, prefix: String, theme: &Theme) -> Line<'_> {
+        let filter_style = Style::from_crossterm(theme.as_style(Meaning::SearchFilter));
+        let mut spans = vec![Span::raw(prefix)];
+        for (i, word) in self.search.input.as_str().split(' ').enumerate() {
+            if i > 0 {
+                spans.push(Span::raw(" "));
+            }
+            // A regex is matched as written, filters and all
+            if self.search_mode != SearchMode::Regex && is_query_filter(word) {
+                spans.push(Span::styled(word, filter_style));
+            } else {
+                spans.push(Span::raw(word));
+            }
+        }
+        Line::from(spans)
+    }
+
     fn build_preview(
         &self,
         results: &[History],
         compactness: Compactness,
         preview_width: u16,
         chunk_width: usize,
+        syntax_highlight: bool,
         theme: &Theme,
     ) -> Paragraph<'_> {
-        let selected = self.results_state.selected();
-        let command = if results.is_empty() {
-            String::new()
-        } else {
-            let s = &results[selected].command;
-            let mut lines = Vec::new();
-            for line in s.split('\n') {
-                let line = line.escape_non_printable();
+        let mut lines = Vec::new();
+        if let Some(entry) = results.get(self.results_state.selected()) {
+            let syntax = if syntax_highlight {
+                syntax::classify(&entry.command, entry.shell.as_deref())
+            } else {
+                Vec::new()
+            };
+
+            let mut line_start = 0;
+            for line in entry.command.split('\n') {
+                let escaped = line.escape_non_printable();
+                // Escaping moves the bytes along, so only an unescaped line lines up with
+                // the syntax of the whole command
+                let line_syntax = match escaped {
+                    Cow::Borrowed(_) => syntax.get(line_start..line_start + line.len()),
+                    Cow::Owned(_) => None,
+                }
+                .unwrap_or_default();
+                line_start += line.len() + 1;
+
                 let mut width = 0;
                 let mut start = 0;
-                for (idx, ch) in line.char_indices() {
+                for (idx, ch) in escaped.char_indices() {
                     let w = ch.width().unwrap_or(0); // None for control chars which should not happen
                     if width + w > preview_width.into() {
-                        lines.push(line[start..idx].to_owned());
+                        lines.push(Self::preview_line(&escaped, start..idx, line_syntax, theme));
                         start = idx;
                         width = w;
                     } else {
@@ -1238,22 +2476,85 @@ impl State {
                     }
                 }
                 if width != 0 {
-                    lines.push(line[start..].to_owned());
+                    lines.push(Self::preview_line(
+                        &escaped,
+                        start..escaped.len(),
+                        line_syntax,
+                        theme,
+                    ));
                 }
             }
-            lines.join("\n")
-        };
+        }
 
         match compactness {
-            Compactness::Full => Paragraph::new(command).block(
-                themed_block(theme)
+            Compactness::Full => {
+                let mut block = themed_block(theme)
                     .borders(Borders::BOTTOM | Borders::LEFT | Borders::RIGHT)
-                    .title(format!("{:─>width$}", "", width = chunk_width - 2)),
-            ),
-            _ => Paragraph::new(command)
+                    .title(format!("{:─>width$}", "", width = chunk_width - 2));
+                if let Some(note) = results
+                    .get(self.results_state.selected())
+                    .and_then(|entry| Self::paths_note(entry, theme))
+                {
+                    block = block.title_bottom(note);
+                }
+                Paragraph::new(lines).block(block)
+            }
+            _ => Paragraph::new(lines)
                 .style(Style::from_crossterm(theme.as_style(Meaning::Annotation))),
         }
     }
+
+    /// Whether the paths in `entry` are still there, for the bottom of the preview. `None`
+    /// if it has none.
+    fn paths_note(entry: &History, theme: &Theme) -> Option<Line<'static>> {
+        let paths = file_paths::find(&entry.command, &entry.cwd);
+        if paths.is_empty() {
+            return None;
+        }
+
+        let missing: Vec<&str> = paths
+            .iter()
+            .filter(|path| !path.exists())
+            .map(|path| path.written.as_str())
+            .collect();
+        let (note, meaning) = match (missing.len(), paths.len()) {
+            (0, 1) => (" ✓ path exists ".to_string(), Meaning::Annotation),
+            (0, n) => (format!(" ✓ {n} paths exist "), Meaning::Annotation),
+            _ => (
+                format!(" ✗ gone: {} ", missing.join(", ")),
+                Meaning::AlertWarn,
+            ),
+        };
+        Some(Line::styled(note, Style::from_crossterm(theme.as_style(meaning))).right_aligned())
+    }
+
+    /// One row of the preview: `range` of `line`, in spans of the same syntax meaning.
+    fn preview_line(
+        line: &str,
+        range: std::ops::Range<usize>,
+        syntax: &[Meaning],
+        theme: &Theme,
+    ) -> Line<'static> {
+        if syntax.is_empty() {
+            return Line::from(line[range].to_string());
+        }
+
+        let spans = line[range.clone()]
+            .char_indices()
+            .chunk_by(|&(i, _)| syntax.get(range.start + i).copied())
+            .into_iter()
+            .map(|(meaning, chars)| {
+                let text: String = chars.map(|(_, ch)| ch).collect();
+                match meaning {
+                    Some(meaning) if meaning != Meaning::Base => {
+                        Span::styled(text, Style::from_crossterm(theme.as_style(meaning)))
+                    }
+                    _ => Span::raw(text),
+                }
+            })
+            .collect::<Vec<_>>();
+        Line::from(spans)
+    }
 }
 
 /// The writer used for terminal output - either stdout or /dev/tty
@@ -1498,6 +2799,8 @@ impl Stdout {
             ),
         )?;
 
+        crash::terminal_taken(!inline_mode);
+
         Ok(Self {
             writer,
             inline_mode,
@@ -1508,6 +2811,8 @@ impl Stdout {
 
 impl Drop for Stdout {
     fn drop(&mut self) {
+        crash::terminal_released();
+
         #[cfg(not(target_os = "windows"))]
         if let Err(e) = execute!(self.writer, PopKeyboardEnhancementFlags) {
             tracing::error!(?e, "Failed to pop keyboard enhancement flags");
@@ -1580,6 +2885,14 @@ fn compute_popup_placement(
     (Rect::new(0, popup_y, popup_w, popup_h), scroll)
 }
 
+/// Wait until `due`, or forever without one.
+async fn until(due: Option<Instant>) {
+    match due {
+        Some(due) => tokio::time::sleep_until(due.into()).await,
+        None => std::future::pending().await,
+    }
+}
+
 // for now, it works. But it'd be great if it were more easily readable, and
 // modular. I'd like to add some more stats and stuff at some point
 #[allow(
@@ -1587,12 +2900,31 @@ fn compute_popup_placement(
     clippy::too_many_lines,
     clippy::cognitive_complexity
 )]
+/// The filter mode this shell session last searched with, if it's still one that can be used
+/// here.
+async fn remembered_filter_mode(settings: &Settings, context: &Context) -> Option<FilterMode> {
+    if !settings.search.remember_filter_mode {
+        return None;
+    }
+    let mode = Settings::meta_store()
+        .await
+        .ok()?
+        .session_filter_mode(&context.session)
+        .await
+        .ok()??;
+
+    let usable = settings.search.filters.contains(&mode)
+        && (mode != FilterMode::Workspace || (settings.workspaces && context.git_root.is_some()));
+    usable.then_some(mode)
+}
+
 pub async fn history(
     query: &[String],
     settings: &Settings,
     mut db: impl Database,
     history_store: &HistoryStore,
     theme: &Theme,
+    include_archive: bool,
 ) -> Result<String> {
     let inline_height = if settings.shell_up_key_binding {
         settings
@@ -1660,7 +2992,12 @@ pub async fn history(
 
     let popup_mode = saved_screen.is_some();
 
-    let stdout = Stdout::new(inline_height > 0, settings.no_mouse)?;
+    // The mouse is only taken from the terminal when asked for, as that stops it selecting
+    // text to copy
+    let stdout = Stdout::new(
+        inline_height > 0,
+        settings.no_mouse || !settings.search.mouse,
+    )?;
 
     // In popup mode, clear the popup region on the physical terminal before
     // ratatui takes over. Ratatui's diff-based rendering compares against an
@@ -1724,8 +3061,8 @@ pub async fn history(
     input.end();
 
     let settings2 = settings.clone();
-    let update_needed = tokio::spawn(async move { settings2.needs_update().await }).fuse();
-    tokio::pin!(update_needed);
+    let network_status = tokio::spawn(async move { network_status(&settings2).await }).fuse();
+    tokio::pin!(network_status);
 
     // Counting history is a full table scan, which can take a while on a large,
     // cold database - don't hold up the first frame for it.
@@ -1735,6 +3072,34 @@ pub async fn history(
 
     let initial_context = current_context().await?;
 
+    let successors = if settings.search.sequence_boost {
+        previous_command_successors(&db, &initial_context).await
+    } else {
+        Vec::new()
+    };
+
+    let pinned = db.pinned_commands().await.unwrap_or_else(|e| {
+        tracing::warn!("could not load pinned commands: {e}");
+        Vec::new()
+    });
+
+    let host_names = HostStore::new(
+        history_store.store.clone(),
+        history_store.host_id,
+        history_store.encryption_key,
+    )
+    .names()
+    .await
+    .unwrap_or_else(|e| {
+        tracing::warn!("could not load host names, showing hostnames instead: {e}");
+        HostNames::default()
+    });
+
+    let session_names = db.session_names().await.unwrap_or_else(|e| {
+        tracing::warn!("could not load session names: {e}");
+        HashMap::new()
+    });
+
     let search_mode = if settings.shell_up_key_binding {
         settings
             .search_mode_shell_up_key_binding
@@ -1742,14 +3107,20 @@ pub async fn history(
     } else {
         settings.search_mode
     };
-    let default_filter_mode = settings
+    let default_filter_mode = match settings
         .filter_mode_shell_up_key_binding
         .filter(|_| settings.shell_up_key_binding)
-        .unwrap_or_else(|| settings.default_filter_mode(initial_context.git_root.is_some()));
+    {
+        Some(mode) => mode,
+        None => remembered_filter_mode(settings, &initial_context)
+            .await
+            .unwrap_or_else(|| settings.default_filter_mode(initial_context.git_root.is_some())),
+    };
     let mut app = State {
         history_count: None,
         results_state: ListState::default(),
         update_needed: None,
+        offline: None,
         switched_search_mode: false,
         search_mode,
         tab_index: 0,
@@ -1764,8 +3135,14 @@ pub async fn history(
             filter_mode: default_filter_mode,
             context: initial_context.clone(),
             custom_context: None,
+            include_archive: db.has_archive().then_some(include_archive),
+            boost: ContextBoost::from_settings(&settings.search),
+            exclude_containers: settings.search.exclude_containers,
+            before: None,
+            limit: None,
         },
         engine: engines::engine(search_mode, settings),
+        searcher: engines::shared(search_mode, settings),
         results_len: 0,
         accept: false,
         keymap_mode: match settings.keymap_mode {
@@ -1774,14 +3151,40 @@ pub async fn history(
         },
         current_cursor: None,
         now: if settings.prefers_reduced_motion {
-            let now = OffsetDateTime::now_utc();
+            let now = utils::now();
             Box::new(move || now)
         } else {
-            Box::new(OffsetDateTime::now_utc)
+            Box::new(utils::now)
         },
         prefix: false,
         pending_vim_key: None,
         original_input_empty: original_query.is_empty(),
+        warning: None,
+        placeholders: None,
+        template: None,
+        show_preview: settings.show_preview,
+        successors,
+        pinned,
+        host_names,
+        session_names,
+        result_cache: ResultCache::default(),
+        theme_picker: None,
+        open_with: None,
+        url_picker: None,
+        path_picker: None,
+        session_picker: None,
+        date_prompt: None,
+        jumped_to: None,
+        last_click: None,
+        show_related: settings.ui.show_related,
+        related: Vec::new(),
+        run_counts: HashMap::new(),
+        query_time: None,
+        searching: None,
+        waiting: None,
+        synced: None,
+        loading: None,
+        items: None,
     };
 
     app.initialize_keymap_cursor(settings);
@@ -1797,15 +3200,60 @@ pub async fn history(
         app.draw(f, &[], None, None, settings, theme, popup_mode);
     })?;
 
-    let mut results = app.query_results(&mut db, settings.smart_sort).await?;
+    let mut results = app.first_results(settings, &mut db).await?;
+
+    // Theme files are read again as they're saved, so a theme being written shows as it is
+    let theme_watcher = ThemeManager::new(settings.theme.debug, None).watch();
+
+    // When this machine last synced, to tell whether an inspected entry has been since
+    let last_sync = if settings.logged_in().await.unwrap_or(false) {
+        Settings::last_sync().await.ok()
+    } else {
+        None
+    };
 
     let mut stats: Option<HistoryStats> = None;
     // The id of the history entry `stats` was computed for, so the render loop
     // only hits the database when the inspected entry actually changes.
     let mut stats_for: Option<HistoryId> = None;
+    // The command `app.related` was looked up for, likewise.
+    let mut related_for: Option<String> = None;
+    // The query and number of results `app.run_counts` was counted for.
+    let mut counted_for: Option<(QueryKey, usize)> = None;
     let mut inspecting: Option<History> = None;
+    // The entry whose directory a rerun-in-context or accept-cd goes to.
+    let mut directory_of: Option<History> = None;
+    // The command an edit-command opens in the editor.
+    let mut to_edit: Option<String> = None;
     let accept;
     let result = 'render: loop {
+        if theme_watcher
+            .as_ref()
+            .is_some_and(ThemeWatcher::take_changed)
+        {
+            app.theme_picker
+                .get_or_insert_with(|| ThemePicker::new(settings))
+                .reload();
+        }
+
+        // A theme being previewed, chosen or reloaded, through the theme picker
+        let picked = app.theme_picker.as_ref().map(ThemePicker::theme);
+        let theme = picked.as_deref().unwrap_or(theme);
+
+        if settings.search.group_duplicates {
+            let counting = (app.query_key(), results.len());
+            if counted_for.as_ref() != Some(&counting) {
+                let commands: Vec<String> =
+                    results.iter().map(|h| h.command.clone()).unique().collect();
+                // Only a hint, so a failed count just shows none
+                app.run_counts = db
+                    .run_counts(&commands, app.search.filter_mode, &app.search.context)
+                    .await
+                    .unwrap_or_default();
+                counted_for = Some(counting);
+            }
+        }
+
         terminal.draw(|f| {
             app.draw(
                 f,
@@ -1818,12 +3266,12 @@ pub async fn history(
             );
         })?;
 
-        let initial_input = app.search.input.as_str().to_owned();
-        let initial_filter_mode = app.search.filter_mode;
-        let initial_search_mode = app.search_mode;
-        let initial_custom_context = app.search.custom_context.clone();
+        let initial_query = app.query_key();
 
         let event_ready = tokio::task::spawn_blocking(|| event::poll(Duration::from_millis(250)));
+        let mut finished = None;
+        let mut loaded = false;
+        let due = app.waiting.as_ref().map(|(_, due)| *due);
 
         tokio::select! {
             event_ready = event_ready => {
@@ -1831,6 +3279,21 @@ pub async fn history(
                     loop {
                         match app.handle_input(settings, &event::read()?) {
                             InputAction::Continue => {},
+                            action if app.blocks_accepting(&action, &results, inspecting.as_ref(), &settings.blocked_commands) => {
+                                app.accept = false;
+                                app.warning = Some("This command is blocked from being accepted: copy or edit it instead".to_string());
+                            },
+                            InputAction::AcceptItem(index) => {
+                                if app.item(index).is_some() {
+                                    accept = app.accept;
+                                    break 'render InputAction::AcceptItem(index);
+                                }
+                            },
+                            InputAction::Delete(_) | InputAction::DeleteAllMatching(_) | InputAction::DeleteMarked
+                                if settings.compliance.append_only =>
+                            {
+                                app.warning = settings.ensure_history_removable().err().map(|e| e.to_string());
+                            },
                             InputAction::Delete(index) => {
                                 if results.is_empty() {
                                     break;
@@ -1843,9 +3306,11 @@ pub async fn history(
                                 }
 
                                 let entry = results.remove(index);
+                                app.results_state.retain_marked(|m| m.id != entry.id);
 
                                 let ids = history_store.delete_entries([entry]).await?;
                                 history_store.build_all(&db, &ids).await?;
+                                app.result_cache.clear();
 
                                 app.tab_index  = 0;
                             },
@@ -1869,33 +3334,274 @@ pub async fn history(
 
                                 let ids = history_store.delete_entries(all_matching).await?;
                                 history_store.build_all(&db, &ids).await?;
+                                app.result_cache.clear();
 
                                 app.results_len = results.len();
-                                app.results_state = ListState::default();
+                                app.results_state.reset();
+                                app.results_state.retain_marked(|m| m.command != command);
                                 app.inspecting_state.reset();
                                 app.tab_index = 0;
                             },
+                            InputAction::ToggleMark(index) => {
+                                if let Some(entry) = results.get(index) {
+                                    app.results_state.toggle_mark(entry);
+                                    // On to the next older entry, ready to mark that too
+                                    app.scroll_up(1);
+                                }
+                            },
+                            InputAction::TogglePin(index) => {
+                                let entry = if app.tab_index != 0 {
+                                    inspecting.as_ref().or_else(|| results.get(index))
+                                } else {
+                                    results.get(index)
+                                };
+
+                                if let Some(entry) = entry.cloned() {
+                                    let pinned = db.toggle_pin(&entry.command).await?;
+                                    app.pinned = db.pinned_commands().await?;
+                                    app.warning = Some(if pinned { "Pinned" } else { "Unpinned" }.to_string());
+
+                                    // Rank again, staying on the entry wherever it's moved to
+                                    let inspecting_state = app.inspecting_state.clone();
+                                    results = app.query_results(&mut db, settings.smart_sort).await?;
+                                    app.inspecting_state = inspecting_state;
+                                    if let Some(pos) = results.iter().position(|e| e.id == entry.id) {
+                                        app.results_state.select(pos);
+                                    }
+                                }
+                            },
+                            InputAction::ToggleGroup(index) => {
+                                if let Some(entry) = results.get(index).cloned() {
+                                    let collapsed = app.results_state.collapse();
+                                    if let Some(collapsed) = &collapsed {
+                                        results.retain(|e| !collapsed.runs.contains(&e.id));
+                                        app.results_len = results.len();
+                                    }
+
+                                    match collapsed {
+                                        // Hiding the runs listed, back on the command they're of
+                                        Some(collapsed) if collapsed.group == entry.id || collapsed.runs.contains(&entry.id) => {
+                                            if let Some(pos) = results.iter().position(|e| e.id == collapsed.group) {
+                                                app.results_state.select(pos);
+                                            }
+                                        },
+                                        _ => {
+                                            let runs: Vec<History> = db
+                                                .runs(&entry.command, app.search.filter_mode, &app.search.context, GROUP_LIMIT)
+                                                .await?
+                                                .into_iter()
+                                                .filter(|h| h.id != entry.id)
+                                                .collect();
+                                            let pos = results.iter().position(|e| e.id == entry.id);
+                                            if let Some(pos) = pos.filter(|_| !runs.is_empty()) {
+                                                app.results_state.expand(entry.id.clone(), runs.iter().map(|h| h.id.clone()).collect());
+                                                results.splice(pos + 1..pos + 1, runs);
+                                                app.results_len = results.len();
+                                                app.results_state.select(pos);
+                                            } else {
+                                                app.warning = Some("This is the only run of this command".to_string());
+                                            }
+                                        },
+                                    }
+                                }
+                            },
+                            InputAction::EarlierDay(index) => {
+                                match date_jump::earlier_day(&results, index, settings.timezone) {
+                                    Some(earlier) => {
+                                        app.inspecting_state.reset();
+                                        app.results_state.select(earlier);
+                                    },
+                                    None => app.warning = Some("No earlier day in these results".to_string()),
+                                }
+                            },
+                            InputAction::LaterDay(index) => {
+                                match date_jump::later_day(&results, index, settings.timezone) {
+                                    Some(later) => {
+                                        app.inspecting_state.reset();
+                                        app.results_state.select(later);
+                                    },
+                                    None => app.warning = Some("No later day in these results".to_string()),
+                                }
+                            },
+                            InputAction::PickSession => {
+                                let mut sessions = Vec::new();
+                                for session in db.recent_sessions(SESSION_LIMIT).await? {
+                                    let history = db.session_history(&session).await?;
+                                    if let (Some(summary), Some(last)) = (SessionSummary::compute(settings, &session, &history), history.last()) {
+                                        sessions.push((summary, last.clone()));
+                                    }
+                                }
+
+                                app.session_picker = SessionPicker::new(sessions, &app.session_names, settings.timezone);
+                                if app.session_picker.is_none() {
+                                    app.warning = Some("No sessions to pick from".to_string());
+                                }
+                            },
+                            InputAction::DeleteMarked => {
+                                let marked = app.results_state.take_marked();
+                                results.retain(|e| !marked.iter().any(|m| m.id == e.id));
+
+                                let ids = history_store.delete_entries(marked).await?;
+                                history_store.build_all(&db, &ids).await?;
+                                app.result_cache.clear();
+
+                                app.results_len = results.len();
+                                app.results_state.select(
+                                    app.results_state.selected().min(app.results_len.saturating_sub(1)),
+                                );
+                                app.inspecting_state.reset();
+                            },
                             InputAction::SwitchContext(index) => {
                                 if let Some(index) = index && let Some(entry) = results.get(index) {
                                     app.search.custom_context = Some(entry.id.clone());
                                     app.search.context = Context::from_history(entry);
                                     app.search.filter_mode = FilterMode::Session;
                                     app.search.input = Cursor::from(String::new());
-                                    app.results_state = ListState::default();
+                                    app.results_state.reset();
                                 } else {
                                     app.search.custom_context = None;
                                     app.search.context = initial_context.clone();
                                     app.search.filter_mode = default_filter_mode;
                                 }
                             },
+                            InputAction::CopyField(field) => {
+                                let selected = app.results_state.selected();
+                                if let Some(entry) = inspecting.as_ref().or_else(|| results.get(selected)) {
+                                    let value = super::inspector::field_value(entry, field, settings.timezone, &app.host_names);
+                                    let name = field.name().replace('-', " ");
+                                    app.warning = Some(match clipboard::copy(&value) {
+                                        Ok(()) => format!("Copied the {name}"),
+                                        Err(e) => format!("Couldn't copy the {name}: {e}"),
+                                    });
+                                }
+                            },
+                            action @ (InputAction::RerunInContext(index)
+                            | InputAction::ChangeDirectory(index)) => {
+                                let entry = if app.tab_index != 0 {
+                                    inspecting.as_ref().or_else(|| results.get(index))
+                                } else {
+                                    results.get(index)
+                                };
+
+                                if let Some(entry) = entry {
+                                    if Path::new(&entry.cwd).is_dir() {
+                                        directory_of = Some(entry.clone());
+                                        accept = app.accept;
+                                        break 'render action;
+                                    }
+                                    app.warning = Some(format!("{} no longer exists", entry.cwd));
+                                }
+                            },
+                            InputAction::Edit(index) => {
+                                let entry = if app.tab_index != 0 {
+                                    inspecting.as_ref().or_else(|| results.get(index))
+                                } else {
+                                    results.get(index)
+                                };
+
+                                if let Some(entry) = entry {
+                                    to_edit = Some(entry.command.clone());
+                                    accept = false;
+                                    break 'render InputAction::Edit(index);
+                                }
+                            },
+                            InputAction::OpenWith(index) => {
+                                let entry = if app.tab_index != 0 {
+                                    inspecting.as_ref().or_else(|| results.get(index))
+                                } else {
+                                    results.get(index)
+                                };
+
+                                if let Some(entry) = entry {
+                                    if !Path::new(&entry.cwd).is_dir() {
+                                        app.warning = Some(format!("{} no longer exists", entry.cwd));
+                                    } else if let Some(menu) = OpenWithMenu::new(entry, &settings.open_with) {
+                                        app.open_with = Some(menu);
+                                    } else {
+                                        app.warning = Some("Nothing in open_with applies to this entry".to_string());
+                                    }
+                                }
+                            },
+                            InputAction::UseUrl(index, then) => {
+                                let entry = if app.tab_index != 0 {
+                                    inspecting.as_ref().or_else(|| results.get(index))
+                                } else {
+                                    results.get(index)
+                                };
+
+                                if let Some(entry) = entry {
+                                    let mut found = urls::find(&entry.command);
+                                    match found.len() {
+                                        0 => app.warning = Some("No URLs in this command".to_string()),
+                                        1 => app.use_url(&found.swap_remove(0), then),
+                                        _ => app.url_picker = Some(UrlPicker::new(found, then)),
+                                    }
+                                }
+                            },
+                            InputAction::InsertPath(index) => {
+                                let entry = if app.tab_index != 0 {
+                                    inspecting.as_ref().or_else(|| results.get(index))
+                                } else {
+                                    results.get(index)
+                                };
+
+                                if let Some(entry) = entry {
+                                    let mut found: Vec<String> = file_paths::find(&entry.command, &entry.cwd)
+                                        .into_iter()
+                                        .map(|path| path.resolved.to_string_lossy().into_owned())
+                                        .collect();
+                                    match found.len() {
+                                        0 => app.warning = Some("No paths in this command".to_string()),
+                                        1 => {
+                                            accept = false;
+                                            break 'render InputAction::ReturnPath(found.swap_remove(0));
+                                        },
+                                        _ => app.path_picker = Some(ListPicker::new("Insert", found)),
+                                    }
+                                }
+                            },
+                            action @ InputAction::ReturnPath(_) => {
+                                accept = false;
+                                break 'render action;
+                            },
+                            InputAction::RunOpenWith => {
+                                accept = false;
+                                break 'render InputAction::RunOpenWith;
+                            },
                             InputAction::Redraw => {
-                                if !popup_mode {
+                                if popup_mode {
+                                    // The popup keeps its place, but mustn't hang off a
+                                    // terminal that shrank under it
+                                    let (cols, rows) = terminal::size()?;
+                                    let visible = popup_rect.intersection(Rect::new(0, 0, cols, rows));
+                                    if visible != terminal.get_frame().area() {
+                                        terminal.resize(visible)?;
+                                    }
+                                } else {
+                                    terminal.autoresize()?;
                                     terminal.clear()?;
                                 }
                                 terminal.draw(|f| {
                                     app.draw(f, &results, stats.clone(), inspecting.as_ref(), settings, theme, popup_mode);
                                 })?;
                             },
+                            InputAction::Accept(index) if app.template.is_none() && app.placeholders.is_none() && (settings.placeholders.templates || settings.placeholders.enabled) => {
+                                let entry = results.get(index);
+                                let template = entry
+                                    .filter(|_| settings.placeholders.templates)
+                                    .and_then(|entry| Template::parse(&entry.command));
+                                let found = entry.filter(|_| settings.placeholders.enabled).and_then(|entry| {
+                                    Placeholders::detect(&entry.command, &settings.placeholders.patterns)
+                                });
+                                if let Some(template) = template {
+                                    app.template = Some((index, TemplatePrompt::new(template)));
+                                } else if let Some(found) = found {
+                                    app.placeholders = Some((index, found));
+                                } else {
+                                    accept = app.accept;
+                                    break 'render InputAction::Accept(index);
+                                }
+                            },
                             r => {
                                 accept = app.accept;
                                 break 'render r;
@@ -1907,38 +3613,73 @@ pub async fn history(
                     }
                 }
             }
-            update_needed = &mut update_needed => {
+            network_status = &mut network_status => {
                 // Don't fail interactive search if update check fails
                 // The update check is a nice-to-have feature, not critical
-                app.update_needed = update_needed.ok().flatten();
+                if let Ok((offline, update_needed)) = network_status {
+                    app.offline = offline;
+                    app.update_needed = update_needed;
+                }
             }
             history_count = &mut history_count => {
                 app.history_count = history_count.ok().and_then(Result::ok);
             }
+            done = QueryTask::finished(&mut app.searching) => {
+                finished = Some(done);
+            }
+            synced = everything::Loading::finished(&mut app.loading) => {
+                app.synced = Some(synced);
+                loaded = true;
+            }
+            () = until(due) => {}
+        }
+
+        if let Some(finished) = finished {
+            let previous = finished.previous.clone();
+            match app.query_finished(finished, &results, settings.smart_sort) {
+                Ok(Some(r)) => {
+                    results = r;
+                    app.select_context_entry(&previous, &results);
+                }
+                Ok(None) => {}
+                Err(e) => app.query_failed(e)?,
+            }
         }
 
-        if initial_input != app.search.input.as_str()
-            || initial_filter_mode != app.search.filter_mode
-            || initial_search_mode != app.search_mode
-            || initial_custom_context != app.search.custom_context
+        // What's synced besides history is only read once it's searched for, and searched
+        // again once it's read
+        if app.synced.is_none()
+            && app.loading.is_none()
+            && everything::parse(app.search.input.as_str()).is_some()
         {
-            results = app.query_results(&mut db, settings.smart_sort).await?;
+            app.loading = Some(everything::Loading::spawn(settings, history_store));
         }
 
-        // In custom context mode, when no filter is applied, highlight the entry which was used
-        // to enter the context when changing modes. This helps to find your way around.
-        if app.search.custom_context.is_some()
-            && app.search.input.as_str().is_empty()
-            && (initial_custom_context != app.search.custom_context
-                || initial_filter_mode != app.search.filter_mode)
-            && let Some(history_id) = app.search.custom_context.clone()
-            && let Some(pos) = results.iter().position(|entry| entry.id == history_id)
+        if (loaded || initial_query != app.query_key())
+            && let Some(r) = app.run_query(
+                initial_query.clone(),
+                &db,
+                settings.smart_sort,
+                Duration::from_millis(settings.search.debounce_ms),
+                &settings.search.sources,
+            )
         {
-            app.results_state.select(pos);
+            results = r;
+            app.select_context_entry(&initial_query, &results);
         }
 
-        let inspecting_id = app.inspecting_state.clone().current;
-        // If inspecting ID is not the current inspecting History, update it.
+        // Typing has paused for long enough to query what's typed
+        if app
+            .waiting
+            .as_ref()
+            .is_some_and(|(_, due)| *due <= Instant::now())
+            && let Some((previous, _)) = app.waiting.take()
+        {
+            app.spawn_query(previous, &db);
+        }
+
+        let inspecting_id = app.inspecting_state.clone().current;
+        // If inspecting ID is not the current inspecting History, update it.
         match inspecting_id {
             Some(inspecting_id) => {
                 if inspecting.is_none() || inspecting_id != inspecting.clone().unwrap().id {
@@ -1965,7 +3706,8 @@ pub async fn history(
                 // input event and poll timeout, and stats() is several queries.
                 stats
             } else {
-                let stats = db.stats(&selected).await?;
+                let mut stats = db.stats(&selected).await?;
+                stats.sync = Some(SyncStatus::of(&selected, last_sync));
                 stats_for = Some(selected.id.clone());
                 app.inspecting_state.current = Some(selected.id);
                 app.inspecting_state.previous = match stats.previous.clone() {
@@ -1982,10 +3724,36 @@ pub async fn history(
             stats_for = None;
             None
         };
+
+        if app.show_related && app.tab_index == 0 {
+            let selected = results
+                .get(app.results_state.selected())
+                .map(|h| h.command.clone());
+            if selected != related_for {
+                app.related = match &selected {
+                    // Only a hint, so a failed lookup just shows nothing
+                    Some(command) => db.related(command, RELATED_LIMIT).await.unwrap_or_default(),
+                    None => Vec::new(),
+                };
+                related_for = selected;
+            }
+        }
     };
 
     app.finalize_keymap_cursor(settings);
 
+    // Rerunning in another command's context switches modes for its own sake
+    if settings.search.remember_filter_mode
+        && app.search.custom_context.is_none()
+        && app.search.filter_mode != default_filter_mode
+        && let Ok(meta) = Settings::meta_store().await
+    {
+        // Not worth failing the search over: the next one starts in the default mode
+        let _ = meta
+            .save_session_filter_mode(&initial_context.session, app.search.filter_mode)
+            .await;
+    }
+
     if popup_mode {
         // In popup mode, restore the screen area that was covered by the popup.
         // This must happen before Stdout is dropped (which disables raw mode).
@@ -2001,13 +3769,12 @@ pub async fn history(
         terminal.set_cursor_position(origin)?;
     }
 
-    let accept = accept
-        && matches!(
-            Shell::from_env(),
-            Shell::Zsh | Shell::Fish | Shell::Bash | Shell::Xonsh | Shell::Nu | Shell::Powershell
-        );
-
-    let accept_prefix = "__atuin_accept__:";
+    let shell = Shell::from_env();
+    let shell_can_accept = matches!(
+        shell,
+        Shell::Zsh | Shell::Fish | Shell::Bash | Shell::Xonsh | Shell::Nu | Shell::Powershell
+    );
+    let accept = accept && shell_can_accept;
 
     match result {
         InputAction::AcceptInspecting => {
@@ -2016,7 +3783,7 @@ pub async fn history(
                     let mut command = result.command;
 
                     if accept {
-                        command = String::from(accept_prefix) + &command;
+                        command = String::from(ACCEPT_PREFIX) + &command;
                     }
 
                     // index is in bounds so we return that entry
@@ -2026,21 +3793,96 @@ pub async fn history(
             }
         }
         InputAction::Accept(index) if index < results.len() => {
-            let mut command = results.swap_remove(index).command;
+            let mut command = match (app.template.take(), app.placeholders.take()) {
+                (Some((_, prompt)), _) => prompt.command(),
+                (None, Some((_, placeholders))) => placeholders.command().to_string(),
+                (None, None) => results.swap_remove(index).command,
+            };
 
             if is_command_chaining {
                 command = format!("{} {}", original_query.trim_end(), command);
             } else if accept {
-                command = String::from(accept_prefix) + &command;
+                command = String::from(ACCEPT_PREFIX) + &command;
             }
 
             // index is in bounds so we return that entry
             Ok(command)
         }
         InputAction::ReturnOriginal => Ok(String::new()),
+        InputAction::RerunInContext(_) => Ok(directory_of.map_or_else(String::new, |entry| {
+            let command = in_directory(&shell, &entry.cwd, &entry.command);
+            // Always run: putting the compound line back on the prompt would
+            // leave the user to tidy it up before they could edit the command.
+            if shell_can_accept {
+                String::from(ACCEPT_PREFIX) + &command
+            } else {
+                command
+            }
+        })),
+        InputAction::Edit(_) => {
+            // Hand the terminal back before the editor takes it over
+            drop(terminal);
+            to_edit.map_or_else(
+                || Ok(String::new()),
+                |command| editor::edit_command(&editor::editor(), &command),
+            )
+        }
+        InputAction::RunOpenWith => {
+            // Like the editor, the command gets the terminal to itself
+            drop(terminal);
+            if let Some(menu) = app.open_with.take() {
+                open_with::run(menu.command(), menu.cwd())?;
+            }
+            Ok(String::new())
+        }
+        InputAction::ReturnPath(path) => Ok(path_word(&shell, &path)),
+        InputAction::AcceptItem(index) => Ok(app.item(index).map_or_else(String::new, |item| {
+            let command = item.command();
+            if accept && item.runs() {
+                String::from(ACCEPT_PREFIX) + &command
+            } else {
+                command
+            }
+        })),
+        InputAction::ChangeDirectory(_) => Ok(directory_of.map_or_else(String::new, |entry| {
+            let command = cd_to(&shell, &entry.cwd);
+            if shell_can_accept {
+                String::from(ACCEPT_PREFIX) + &command
+            } else {
+                command
+            }
+        })),
+        InputAction::AcceptMarked => {
+            let mut command = join_marked(
+                &shell,
+                settings.search.join_marked,
+                app.results_state.marked(),
+            );
+
+            if is_command_chaining {
+                command = format!("{} {}", original_query.trim_end(), command);
+            } else if accept {
+                command = String::from(ACCEPT_PREFIX) + &command;
+            }
+
+            Ok(command)
+        }
         InputAction::Copy(index) => {
-            let cmd = results.swap_remove(index).command;
-            if let Err(e) = set_clipboard(cmd) {
+            let entry = results.swap_remove(index);
+            let text = copy_text(&shell, settings.search.copy_format, &entry);
+            if let Err(e) = clipboard::copy(&text) {
+                tracing::warn!(?e, "failed to copy to clipboard");
+            }
+            Ok(String::new())
+        }
+        InputAction::CopyMarked => {
+            let text = app
+                .results_state
+                .marked()
+                .iter()
+                .map(|entry| copy_text(&shell, settings.search.copy_format, entry))
+                .join("\n");
+            if let Err(e) = clipboard::copy(&text) {
                 tracing::warn!(?e, "failed to copy to clipboard");
             }
             Ok(String::new())
@@ -2055,48 +3897,241 @@ pub async fn history(
         | InputAction::Redraw
         | InputAction::Delete(_)
         | InputAction::DeleteAllMatching(_)
-        | InputAction::SwitchContext(_) => {
+        | InputAction::ToggleMark(_)
+        | InputAction::TogglePin(_)
+        | InputAction::ToggleGroup(_)
+        | InputAction::PickSession
+        | InputAction::EarlierDay(_)
+        | InputAction::LaterDay(_)
+        | InputAction::DeleteMarked
+        | InputAction::SwitchContext(_)
+        | InputAction::OpenWith(_)
+        | InputAction::UseUrl(..)
+        | InputAction::InsertPath(_)
+        | InputAction::CopyField(_) => {
             unreachable!("should have been handled!")
         }
     }
 }
 
-// cli-clipboard only works on Windows, Mac, and Linux.
-
-#[cfg(all(
-    feature = "clipboard",
-    any(target_os = "windows", target_os = "macos", target_os = "linux")
-))]
-fn set_clipboard(s: String) -> Result<(), arboard::Error> {
-    let mut ctx = arboard::Clipboard::new()?;
-    ctx.set_text(s)?;
-    // Use the clipboard context to make sure it is saved
-    ctx.get_text()?;
-    Ok(())
+/// `command`, run from `cwd`, as one line for `shell`.
+fn in_directory(shell: &Shell, cwd: &str, command: &str) -> String {
+    let separator = match shell {
+        Shell::Nu | Shell::Powershell => ";",
+        _ => " &&",
+    };
+
+    format!("{}{separator} {command}", cd_to(shell, cwd))
+}
+
+/// The marked commands, in the order they were marked, as one command line
+/// for `shell`.
+fn join_marked(shell: &Shell, join: JoinMarked, marked: &[History]) -> String {
+    let separator = match join {
+        JoinMarked::Newline => "\n",
+        JoinMarked::And => match shell {
+            Shell::Nu | Shell::Powershell => "; ",
+            _ => " && ",
+        },
+    };
+
+    marked
+        .iter()
+        .map(|h| h.command.as_str())
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+/// `entry` as it's copied, written as `format` says.
+fn copy_text(shell: &Shell, format: CopyFormat, entry: &History) -> String {
+    match format {
+        CopyFormat::Raw => entry.command.clone(),
+        CopyFormat::Escaped => quoted(shell, &entry.command),
+        CopyFormat::Cd => in_directory(shell, &entry.cwd, &entry.command),
+    }
+}
+
+/// Changes to `cwd` in `shell`.
+fn cd_to(shell: &Shell, cwd: &str) -> String {
+    let cwd = quoted(shell, cwd);
+    match shell {
+        Shell::Nu | Shell::Fish | Shell::Xonsh => format!("cd {cwd}"),
+        Shell::Powershell => format!("Set-Location -LiteralPath {cwd}"),
+        Shell::Sh | Shell::Bash | Shell::Zsh | Shell::Unknown => format!("cd -- {cwd}"),
+    }
+}
+
+/// `value` as one word for `shell`, whatever is in it.
+fn quoted(shell: &Shell, value: &str) -> String {
+    match shell {
+        Shell::Nu => {
+            // A raw string ends at a quote followed by as many #s as it starts with, so it
+            // takes one more than follow any quote inside
+            let inside = value
+                .match_indices('\'')
+                .map(|(i, _)| value[i + 1..].bytes().take_while(|b| *b == b'#').count())
+                .max()
+                .unwrap_or(0);
+            let hashes = "#".repeat(inside + 1);
+            format!("r{hashes}'{value}'{hashes}")
+        }
+        Shell::Powershell => format!("'{}'", value.replace('\'', "''")),
+        Shell::Fish | Shell::Xonsh => {
+            format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+        }
+        Shell::Sh | Shell::Bash | Shell::Zsh | Shell::Unknown => {
+            format!("'{}'", value.replace('\'', "'\\''"))
+        }
+    }
+}
+
+/// `path` for the prompt of `shell`, quoted only if it needs to be.
+fn path_word(shell: &Shell, path: &str) -> String {
+    let plain = !path.is_empty()
+        && path.chars().all(|c| {
+            c.is_alphanumeric() || matches!(c, '/' | '.' | '_' | '-' | '+' | ',' | '@' | ':')
+        });
+    if plain {
+        path.to_string()
+    } else {
+        quoted(shell, path)
+    }
+}
+
+/// Whether Atuin is offline, and otherwise whether there's an update. The network is only checked
+/// if something here would use it: update checks, or syncing when logged in.
+async fn network_status(settings: &Settings) -> (Option<Offline>, Option<Version>) {
+    let uses_network = settings.update_check || settings.logged_in().await.unwrap_or(false);
+    if !uses_network && !settings.offline {
+        return (None, None);
+    }
+
+    if let Some(offline) = network::check(settings).await {
+        return (Some(offline), None);
+    }
+
+    (None, settings.needs_update().await)
 }
 
-#[cfg(not(all(
-    feature = "clipboard",
-    any(target_os = "windows", target_os = "macos", target_os = "linux")
-)))]
-fn set_clipboard(_s: String) -> Result<(), std::convert::Infallible> {
-    Ok(())
+/// Look up the most frequent follow-ups of the last command run in this session.
+///
+/// This is purely a ranking hint, so any failure just means no boost.
+async fn previous_command_successors(db: &impl Database, context: &Context) -> Vec<(String, i64)> {
+    let previous = db
+        .search(
+            SearchMode::FullText,
+            FilterMode::Session,
+            context,
+            "",
+            OptFilters {
+                limit: Some(1),
+                include_duplicates: true,
+                ..Default::default()
+            },
+        )
+        .await;
+
+    let Ok(Some(previous)) = previous.map(|mut h| h.pop()) else {
+        return Vec::new();
+    };
+
+    db.successors(&previous.command, SEQUENCE_BOOST_LIMIT)
+        .await
+        .unwrap_or_default()
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
+
     use atuin_client::database::Context;
     use atuin_client::history::History;
+    use atuin_client::host::HostNames;
+    use atuin_client::ordering::ContextBoost;
     use atuin_client::settings::{
-        FilterMode, KeymapMode, Preview, PreviewStrategy, SearchMode, Settings,
+        FilterMode, KeymapMode, Preview, PreviewStrategy, SearchMode, Settings, SourceRanking,
     };
+    use regex::RegexSet;
     use time::OffsetDateTime;
 
     use crate::command::client::search::engines::{self, SearchState};
     use crate::command::client::search::history_list::ListState;
+    use crate::command::client::search::result_cache::ResultCache;
 
     use super::{Compactness, InspectingState, KeymapSet, State};
 
+    /// A `State` as a search starts with, for tests to set what they need on.
+    fn test_state(settings: &Settings) -> State {
+        State {
+            history_count: Some(0),
+            update_needed: None,
+            offline: None,
+            results_state: ListState::default(),
+            switched_search_mode: false,
+            search_mode: SearchMode::Fuzzy,
+            results_len: 0,
+            accept: false,
+            keymap_mode: KeymapMode::Auto,
+            prefix: false,
+            current_cursor: None,
+            tab_index: 0,
+            pending_vim_key: None,
+            original_input_empty: false,
+            warning: None,
+            placeholders: None,
+            template: None,
+            show_preview: true,
+            successors: Vec::new(),
+            pinned: Vec::new(),
+            host_names: HostNames::default(),
+            session_names: HashMap::new(),
+            result_cache: ResultCache::default(),
+            theme_picker: None,
+            open_with: None,
+            url_picker: None,
+            path_picker: None,
+            session_picker: None,
+            date_prompt: None,
+            jumped_to: None,
+            last_click: None,
+            show_related: false,
+            related: Vec::new(),
+            run_counts: HashMap::new(),
+            query_time: None,
+            searching: None,
+            waiting: None,
+            synced: None,
+            loading: None,
+            items: None,
+            inspecting_state: InspectingState {
+                current: None,
+                next: None,
+                previous: None,
+            },
+            keymaps: KeymapSet::defaults(settings),
+            search: SearchState {
+                input: String::new().into(),
+                filter_mode: FilterMode::Global,
+                context: Context {
+                    session: String::new(),
+                    cwd: String::new(),
+                    hostname: String::new(),
+                    host_id: String::new(),
+                    git_root: None,
+                },
+                custom_context: None,
+                include_archive: None,
+                boost: ContextBoost::default(),
+                exclude_containers: false,
+                before: None,
+                limit: None,
+            },
+            engine: engines::engine(SearchMode::Fuzzy, settings),
+            searcher: engines::shared(SearchMode::Fuzzy, settings),
+            now: Box::new(OffsetDateTime::now_utc),
+        }
+    }
+
     #[test]
     #[allow(clippy::too_many_lines)]
     fn calc_preview_height_test() {
@@ -2161,6 +4196,7 @@ mod tests {
         // the selected command does not require a preview
         let no_preview = State::calc_preview_height(
             &settings_preview_auto,
+            true,
             &results,
             0_usize,
             0_usize,
@@ -2171,6 +4207,7 @@ mod tests {
         // the selected command requires 2 lines
         let preview_h2 = State::calc_preview_height(
             &settings_preview_auto,
+            true,
             &results,
             1_usize,
             0_usize,
@@ -2181,6 +4218,7 @@ mod tests {
         // the selected command requires 3 lines
         let preview_h3 = State::calc_preview_height(
             &settings_preview_auto,
+            true,
             &results,
             2_usize,
             0_usize,
@@ -2191,6 +4229,7 @@ mod tests {
         // the selected command requires a preview of 1 line (happens when the command is between preview_width-19 and preview_width)
         let preview_one_line = State::calc_preview_height(
             &settings_preview_auto,
+            true,
             &results,
             0_usize,
             0_usize,
@@ -2201,6 +4240,7 @@ mod tests {
         // the selected command requires 3 lines, but we have a max preview height limit of 2
         let preview_limit_at_2 = State::calc_preview_height(
             &settings_preview_auto_h2,
+            true,
             &results,
             2_usize,
             0_usize,
@@ -2211,6 +4251,7 @@ mod tests {
         // the longest command requires 3 lines
         let preview_static_h3 = State::calc_preview_height(
             &settings_preview_h4,
+            true,
             &results,
             1_usize,
             0_usize,
@@ -2221,6 +4262,7 @@ mod tests {
         // the longest command requires 10 lines, but we have a max preview height limit of 4
         let preview_static_limit_at_4 = State::calc_preview_height(
             &settings_preview_h4,
+            true,
             &results,
             1_usize,
             0_usize,
@@ -2231,6 +4273,7 @@ mod tests {
         // the longest command requires 10 lines, but we have a max preview height of 15 and a fixed preview strategy
         let settings_preview_fixed = State::calc_preview_height(
             &settings_preview_fixed,
+            true,
             &results,
             1_usize,
             0_usize,
@@ -2255,46 +4298,46 @@ mod tests {
     #[test]
     fn state_scroll_up_underflow() {
         let settings = Settings::utc();
-        let mut state = State {
-            history_count: Some(0),
-            update_needed: None,
-            results_state: ListState::default(),
-            switched_search_mode: false,
-            search_mode: SearchMode::Fuzzy,
-            results_len: 0,
-            accept: false,
-            keymap_mode: KeymapMode::Auto,
-            prefix: false,
-            current_cursor: None,
-            tab_index: 0,
-            pending_vim_key: None,
-            original_input_empty: false,
-            inspecting_state: InspectingState {
-                current: None,
-                next: None,
-                previous: None,
-            },
-            keymaps: KeymapSet::defaults(&settings),
-            search: SearchState {
-                input: String::new().into(),
-                filter_mode: FilterMode::Directory,
-                context: Context {
-                    session: String::new(),
-                    cwd: String::new(),
-                    hostname: String::new(),
-                    host_id: String::new(),
-                    git_root: None,
-                },
-                custom_context: None,
-            },
-            engine: engines::engine(SearchMode::Fuzzy, &settings),
-            now: Box::new(OffsetDateTime::now_utc),
-        };
+        let mut state = test_state(&settings);
+        state.search.filter_mode = FilterMode::Directory;
 
         state.scroll_up(1);
         state.scroll_down(1);
     }
 
+    #[test]
+    fn title_shows_offline_over_update() {
+        use atuin_client::{network::Offline, theme::ThemeManager};
+        use ratatui::{backend::TestBackend, prelude::*};
+
+        let settings = Settings::utc();
+        let state = State {
+            update_needed: Some(semver::Version::new(100, 0, 0)),
+            offline: Some(Offline::Unreachable),
+            ..test_state(&settings)
+        };
+
+        let mut manager = ThemeManager::new(Some(false), Some(String::new()));
+        let theme = manager.load_theme("default", None);
+
+        let mut terminal = Terminal::new(TestBackend::new(40, 1)).unwrap();
+        terminal
+            .draw(|f| f.render_widget(state.build_title(theme), f.area()))
+            .unwrap();
+
+        let title: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(ratatui::buffer::Cell::symbol)
+            .collect();
+        assert_eq!(
+            title.trim_end(),
+            format!("Atuin v{} - OFFLINE", crate::VERSION)
+        );
+    }
+
     #[allow(clippy::too_many_lines)]
     #[test]
     fn test_accept_keybindings() {
@@ -2313,38 +4356,9 @@ mod tests {
 
         let mut state = State {
             history_count: Some(1),
-            update_needed: None,
-            results_state: ListState::default(),
-            switched_search_mode: false,
-            search_mode: SearchMode::Fuzzy,
             results_len: 1,
-            accept: false,
             keymap_mode: KeymapMode::Emacs,
-            prefix: false,
-            current_cursor: None,
-            tab_index: 0,
-            pending_vim_key: None,
-            original_input_empty: false,
-            inspecting_state: InspectingState {
-                current: None,
-                next: None,
-                previous: None,
-            },
-            keymaps: KeymapSet::defaults(&settings),
-            search: SearchState {
-                input: String::new().into(),
-                filter_mode: FilterMode::Global,
-                context: Context {
-                    session: String::new(),
-                    cwd: String::new(),
-                    hostname: String::new(),
-                    host_id: String::new(),
-                    git_root: None,
-                },
-                custom_context: None,
-            },
-            engine: engines::engine(SearchMode::Fuzzy, &settings),
-            now: Box::new(OffsetDateTime::now_utc),
+            ..test_state(&settings)
         };
 
         let tab_event = KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE);
@@ -2432,38 +4446,9 @@ mod tests {
 
         let mut state = State {
             history_count: Some(100),
-            update_needed: None,
-            results_state: ListState::default(),
-            switched_search_mode: false,
-            search_mode: SearchMode::Fuzzy,
             results_len: 100,
-            accept: false,
             keymap_mode: KeymapMode::VimNormal,
-            prefix: false,
-            current_cursor: None,
-            tab_index: 0,
-            pending_vim_key: None,
-            original_input_empty: false,
-            inspecting_state: InspectingState {
-                current: None,
-                next: None,
-                previous: None,
-            },
-            keymaps: KeymapSet::defaults(&settings),
-            search: SearchState {
-                input: String::new().into(),
-                filter_mode: FilterMode::Global,
-                context: Context {
-                    session: String::new(),
-                    cwd: String::new(),
-                    hostname: String::new(),
-                    host_id: String::new(),
-                    git_root: None,
-                },
-                custom_context: None,
-            },
-            engine: engines::engine(SearchMode::Fuzzy, &settings),
-            now: Box::new(OffsetDateTime::now_utc),
+            ..test_state(&settings)
         };
 
         // Start in the middle of the list
@@ -2491,38 +4476,9 @@ mod tests {
 
         let mut state = State {
             history_count: Some(100),
-            update_needed: None,
-            results_state: ListState::default(),
-            switched_search_mode: false,
-            search_mode: SearchMode::Fuzzy,
             results_len: 100,
-            accept: false,
             keymap_mode: KeymapMode::VimNormal,
-            prefix: false,
-            current_cursor: None,
-            tab_index: 0,
-            pending_vim_key: None,
-            original_input_empty: false,
-            inspecting_state: InspectingState {
-                current: None,
-                next: None,
-                previous: None,
-            },
-            keymaps: KeymapSet::defaults(&settings),
-            search: SearchState {
-                input: String::new().into(),
-                filter_mode: FilterMode::Global,
-                context: Context {
-                    session: String::new(),
-                    cwd: String::new(),
-                    hostname: String::new(),
-                    host_id: String::new(),
-                    git_root: None,
-                },
-                custom_context: None,
-            },
-            engine: engines::engine(SearchMode::Fuzzy, &settings),
-            now: Box::new(OffsetDateTime::now_utc),
+            ..test_state(&settings)
         };
 
         state.results_state.select(50);
@@ -2546,38 +4502,9 @@ mod tests {
 
         let mut state = State {
             history_count: Some(100),
-            update_needed: None,
-            results_state: ListState::default(),
-            switched_search_mode: false,
-            search_mode: SearchMode::Fuzzy,
             results_len: 100,
-            accept: false,
             keymap_mode: KeymapMode::VimNormal,
-            prefix: false,
-            current_cursor: None,
-            tab_index: 0,
-            pending_vim_key: None,
-            original_input_empty: false,
-            inspecting_state: InspectingState {
-                current: None,
-                next: None,
-                previous: None,
-            },
-            keymaps: KeymapSet::defaults(&settings),
-            search: SearchState {
-                input: String::new().into(),
-                filter_mode: FilterMode::Global,
-                context: Context {
-                    session: String::new(),
-                    cwd: String::new(),
-                    hostname: String::new(),
-                    host_id: String::new(),
-                    git_root: None,
-                },
-                custom_context: None,
-            },
-            engine: engines::engine(SearchMode::Fuzzy, &settings),
-            now: Box::new(OffsetDateTime::now_utc),
+            ..test_state(&settings)
         };
 
         state.results_state.select(50);
@@ -2597,38 +4524,9 @@ mod tests {
 
         let mut state = State {
             history_count: Some(100),
-            update_needed: None,
-            results_state: ListState::default(),
-            switched_search_mode: false,
-            search_mode: SearchMode::Fuzzy,
             results_len: 100,
-            accept: false,
             keymap_mode: KeymapMode::VimNormal,
-            prefix: false,
-            current_cursor: None,
-            tab_index: 0,
-            pending_vim_key: None,
-            original_input_empty: false,
-            inspecting_state: InspectingState {
-                current: None,
-                next: None,
-                previous: None,
-            },
-            keymaps: KeymapSet::defaults(&settings),
-            search: SearchState {
-                input: String::new().into(),
-                filter_mode: FilterMode::Global,
-                context: Context {
-                    session: String::new(),
-                    cwd: String::new(),
-                    hostname: String::new(),
-                    host_id: String::new(),
-                    git_root: None,
-                },
-                custom_context: None,
-            },
-            engine: engines::engine(SearchMode::Fuzzy, &settings),
-            now: Box::new(OffsetDateTime::now_utc),
+            ..test_state(&settings)
         };
 
         state.results_state.select(50);
@@ -2661,38 +4559,9 @@ mod tests {
 
         let mut state = State {
             history_count: Some(100),
-            update_needed: None,
-            results_state: ListState::default(),
-            switched_search_mode: false,
-            search_mode: SearchMode::Fuzzy,
             results_len: 100,
-            accept: false,
             keymap_mode: KeymapMode::VimNormal,
-            prefix: false,
-            current_cursor: None,
-            tab_index: 0,
-            pending_vim_key: None,
-            original_input_empty: false,
-            inspecting_state: InspectingState {
-                current: None,
-                next: None,
-                previous: None,
-            },
-            keymaps: KeymapSet::defaults(&settings),
-            search: SearchState {
-                input: String::new().into(),
-                filter_mode: FilterMode::Global,
-                context: Context {
-                    session: String::new(),
-                    cwd: String::new(),
-                    hostname: String::new(),
-                    host_id: String::new(),
-                    git_root: None,
-                },
-                custom_context: None,
-            },
-            engine: engines::engine(SearchMode::Fuzzy, &settings),
-            now: Box::new(OffsetDateTime::now_utc),
+            ..test_state(&settings)
         };
 
         state.results_state.select(50);
@@ -2726,38 +4595,9 @@ mod tests {
         let settings = Settings::utc();
         let mut state = State {
             history_count: Some(i64::try_from(results_len).unwrap()),
-            update_needed: None,
-            results_state: ListState::default(),
-            switched_search_mode: false,
-            search_mode: SearchMode::Fuzzy,
             results_len,
-            accept: false,
             keymap_mode: KeymapMode::Emacs,
-            prefix: false,
-            current_cursor: None,
-            tab_index: 0,
-            pending_vim_key: None,
-            original_input_empty: false,
-            inspecting_state: InspectingState {
-                current: None,
-                next: None,
-                previous: None,
-            },
-            keymaps: KeymapSet::defaults(&settings),
-            search: SearchState {
-                input: String::new().into(),
-                filter_mode: FilterMode::Global,
-                context: Context {
-                    session: String::new(),
-                    cwd: String::new(),
-                    hostname: String::new(),
-                    host_id: String::new(),
-                    git_root: None,
-                },
-                custom_context: None,
-            },
-            engine: engines::engine(SearchMode::Fuzzy, &settings),
-            now: Box::new(OffsetDateTime::now_utc),
+            ..test_state(&settings)
         };
         state.results_state.select(selected);
         state
@@ -2846,6 +4686,39 @@ mod tests {
         assert!(!state.accept);
     }
 
+    #[test]
+    fn blocked_commands_cant_be_accepted() {
+        let state = make_executor_state(2, 0);
+        let results = vec![
+            History::import()
+                .timestamp(OffsetDateTime::UNIX_EPOCH)
+                .command("terraform destroy -auto-approve")
+                .build()
+                .into(),
+            History::import()
+                .timestamp(OffsetDateTime::UNIX_EPOCH)
+                .command("terraform plan")
+                .build()
+                .into(),
+        ];
+        let blocked = RegexSet::new(["^terraform destroy"]).unwrap();
+
+        let blocks = |action| state.blocks_accepting(&action, &results, None, &blocked);
+        assert!(blocks(super::InputAction::Accept(0)));
+        assert!(blocks(super::InputAction::RerunInContext(0)));
+        assert!(!blocks(super::InputAction::Accept(1)));
+        assert!(!blocks(super::InputAction::Copy(0)));
+        assert!(!blocks(super::InputAction::Edit(0)));
+
+        // Nothing is blocked without patterns
+        assert!(!state.blocks_accepting(
+            &super::InputAction::Accept(0),
+            &results,
+            None,
+            &RegexSet::empty()
+        ));
+    }
+
     #[test]
     fn execute_accept_nth() {
         use crate::command::client::search::keybindings::Action;
@@ -2856,6 +4729,23 @@ mod tests {
         assert!(matches!(result, super::InputAction::Accept(8)));
     }
 
+    #[test]
+    fn execute_nth_past_the_results_does_nothing() {
+        use crate::command::client::search::keybindings::Action;
+
+        let mut state = make_executor_state(7, 5);
+        let settings = Settings::utc();
+        let result = state.execute_action(&Action::ReturnSelectionNth(1), &settings);
+        assert!(matches!(result, super::InputAction::Accept(6)));
+
+        let result = state.execute_action(&Action::ReturnSelectionNth(2), &settings);
+        assert!(matches!(result, super::InputAction::Continue));
+
+        let result = state.execute_action(&Action::AcceptNth(2), &settings);
+        assert!(matches!(result, super::InputAction::Continue));
+        assert!(!state.accept);
+    }
+
     #[test]
     fn execute_scroll_to_top_no_invert() {
         use crate::command::client::search::keybindings::Action;
@@ -2906,6 +4796,44 @@ mod tests {
         assert_eq!(state.tab_index, 0);
     }
 
+    #[test]
+    fn execute_toggle_details() {
+        use crate::command::client::search::keybindings::{Action, HistoryField};
+
+        let mut state = make_executor_state(100, 0);
+        let settings = Settings::utc();
+        state.execute_action(&Action::ToggleTab, &settings);
+        state.execute_action(&Action::ToggleDetails, &settings);
+        assert_eq!(state.tab_index, 2);
+        // The details tab has the inspector's keys
+        assert!(std::ptr::eq(state.mode_keymap(), &state.keymaps.inspector));
+
+        let result = state.execute_action(&Action::CopyField(HistoryField::Session), &settings);
+        assert!(matches!(
+            result,
+            super::InputAction::CopyField(HistoryField::Session)
+        ));
+
+        state.execute_action(&Action::ToggleDetails, &settings);
+        assert_eq!(state.tab_index, 1);
+        state.execute_action(&Action::ToggleDetails, &settings);
+        state.execute_action(&Action::ToggleTab, &settings);
+        assert_eq!(state.tab_index, 0);
+    }
+
+    #[test]
+    fn execute_toggle_preview() {
+        use crate::command::client::search::keybindings::Action;
+
+        let mut state = make_executor_state(100, 0);
+        let settings = Settings::utc();
+        assert!(state.show_preview);
+        state.execute_action(&Action::TogglePreview, &settings);
+        assert!(!state.show_preview);
+        state.execute_action(&Action::TogglePreview, &settings);
+        assert!(state.show_preview);
+    }
+
     #[test]
     fn execute_enter_prefix_mode() {
         use crate::command::client::search::keybindings::Action;
@@ -2976,6 +4904,236 @@ mod tests {
         );
     }
 
+    #[test]
+    fn template_keys_fill_in_each_field() {
+        use super::{Template, TemplatePrompt};
+        use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+        let mut state = make_executor_state(100, 7);
+        let settings = Settings::utc();
+        let template = Template::parse("git switch {{branch}} && git pull {{remote:origin}}");
+        state.template = Some((7, TemplatePrompt::new(template.unwrap())));
+
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        for code in [
+            KeyCode::Char('m'),
+            KeyCode::Char('x'),
+            KeyCode::Backspace,
+            KeyCode::Tab,
+        ] {
+            let result = state.handle_key_input(&settings, &key(code));
+            assert!(matches!(result, super::InputAction::Continue));
+        }
+        assert_eq!(
+            state.search.input.as_str(),
+            "",
+            "keys must not reach the search input"
+        );
+
+        let result = state.handle_key_input(&settings, &key(KeyCode::Enter));
+        assert!(matches!(result, super::InputAction::Accept(7)));
+        let (_, prompt) = state.template.as_ref().unwrap();
+        assert_eq!(prompt.command(), "git switch m && git pull origin");
+
+        state.handle_key_input(&settings, &key(KeyCode::Esc));
+        assert!(state.template.is_none());
+    }
+
+    #[test]
+    fn open_with_keys_pick_a_command() {
+        use super::OpenWithMenu;
+        use atuin_client::settings::OpenWith;
+        use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+        let mut state = make_executor_state(100, 7);
+        let settings = Settings::utc();
+        let entry: History = History::import()
+            .timestamp(OffsetDateTime::UNIX_EPOCH)
+            .command("make")
+            .cwd("/")
+            .build()
+            .into();
+        let commands = ["ranger {cwd}", "code {cwd}"].map(|command| OpenWith {
+            name: command.to_string(),
+            command: command.to_string(),
+        });
+        state.open_with = OpenWithMenu::new(&entry, &commands);
+
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        let result = state.handle_key_input(&settings, &key(KeyCode::Char('j')));
+        assert!(matches!(result, super::InputAction::Continue));
+        assert_eq!(state.search.input.as_str(), "");
+
+        let result = state.handle_key_input(&settings, &key(KeyCode::Enter));
+        assert!(matches!(result, super::InputAction::RunOpenWith));
+        assert_eq!(state.open_with.as_ref().unwrap().command(), "code /");
+
+        state.handle_key_input(&settings, &key(KeyCode::Esc));
+        assert!(state.open_with.is_none());
+    }
+
+    #[test]
+    fn url_picker_keys_choose_a_url() {
+        use super::{UrlPicker, UrlUse};
+        use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+        let mut state = make_executor_state(100, 7);
+        let settings = Settings::utc();
+        let urls = vec![
+            "https://a.example".to_string(),
+            "https://b.example".to_string(),
+        ];
+        state.url_picker = Some(UrlPicker::new(urls, UrlUse::Copy));
+
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        let result = state.handle_key_input(&settings, &key(KeyCode::Down));
+        assert!(matches!(result, super::InputAction::Continue));
+        assert_eq!(
+            state.url_picker.as_ref().unwrap().url(),
+            "https://b.example"
+        );
+        assert_eq!(state.search.input.as_str(), "");
+
+        state.handle_key_input(&settings, &key(KeyCode::Esc));
+        assert!(state.url_picker.is_none());
+    }
+
+    #[test]
+    fn path_picker_keys_insert_a_path() {
+        use super::ListPicker;
+        use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+        let mut state = make_executor_state(100, 7);
+        let settings = Settings::utc();
+        let paths = vec!["/tmp/a".to_string(), "/tmp/b".to_string()];
+        state.path_picker = Some(ListPicker::new("Insert", paths));
+
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        let result = state.handle_key_input(&settings, &key(KeyCode::Char('j')));
+        assert!(matches!(result, super::InputAction::Continue));
+        assert_eq!(state.search.input.as_str(), "");
+
+        let result = state.handle_key_input(&settings, &key(KeyCode::Enter));
+        assert!(matches!(result, super::InputAction::ReturnPath(path) if path == "/tmp/b"));
+        assert!(state.path_picker.is_none());
+    }
+
+    #[test]
+    fn session_picker_keys_search_a_session() {
+        use super::{Cursor, SessionPicker, SessionSummary};
+        use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+        let mut state = make_executor_state(100, 7);
+        let settings = Settings::utc();
+        let last: History = History::import()
+            .timestamp(time::macros::datetime!(2026-10-16 09:00 UTC))
+            .command("cargo test")
+            .cwd("/home/ellie/src/atuin")
+            .session("abc")
+            .build()
+            .into();
+        let summary =
+            SessionSummary::compute(&settings, "abc", std::slice::from_ref(&last)).unwrap();
+        state.session_picker = SessionPicker::new(
+            vec![(summary, last.clone())],
+            &HashMap::new(),
+            settings.timezone,
+        );
+        state.search.input = Cursor::from("cargo".to_string());
+
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        let result = state.handle_key_input(&settings, &key(KeyCode::Enter));
+        assert!(matches!(result, super::InputAction::Continue));
+        assert!(state.session_picker.is_none());
+        assert_eq!(state.search.filter_mode, FilterMode::Session);
+        assert_eq!(state.search.context.session, "abc");
+        assert_eq!(state.search.custom_context, Some(last.id));
+        assert_eq!(state.search.input.as_str(), "");
+    }
+
+    #[test]
+    fn date_prompt_keys_jump_to_a_day() {
+        use super::{Cursor, DatePrompt};
+        use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+        use time::macros::datetime;
+
+        let mut state = make_executor_state(100, 7);
+        let settings = Settings::utc();
+        state.date_prompt = Some(DatePrompt::default());
+        state.search.input = Cursor::from("cargo".to_string());
+
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        for c in "2024-03-1x".chars() {
+            state.handle_key_input(&settings, &key(KeyCode::Char(c)));
+        }
+        state.handle_key_input(&settings, &key(KeyCode::Backspace));
+        state.handle_key_input(&settings, &key(KeyCode::Char('5')));
+        let result = state.handle_key_input(&settings, &key(KeyCode::Enter));
+        assert!(matches!(result, super::InputAction::Continue));
+        assert!(state.date_prompt.is_none());
+        assert_eq!(
+            state.jumped_to,
+            Some(datetime!(2024-03-15 00:00 UTC).date())
+        );
+        assert_eq!(state.search.before, Some(datetime!(2024-03-16 00:00 UTC)));
+        // The query is kept, searched from that day back
+        assert_eq!(state.search.input.as_str(), "cargo");
+
+        // Nothing typed goes back to the latest
+        state.date_prompt = Some(DatePrompt::default());
+        state.handle_key_input(&settings, &key(KeyCode::Enter));
+        assert!(state.jumped_to.is_none());
+        assert!(state.search.before.is_none());
+    }
+
+    #[test]
+    fn execute_scroll_day() {
+        use crate::command::client::search::keybindings::Action;
+
+        let mut state = make_executor_state(100, 7);
+        let mut settings = Settings::utc();
+        let result = state.execute_action(&Action::ScrollDayUp, &settings);
+        assert!(matches!(result, super::InputAction::EarlierDay(7)));
+        let result = state.execute_action(&Action::ScrollDayDown, &settings);
+        assert!(matches!(result, super::InputAction::LaterDay(7)));
+
+        settings.invert = true;
+        let result = state.execute_action(&Action::ScrollDayUp, &settings);
+        assert!(matches!(result, super::InputAction::LaterDay(7)));
+    }
+
+    #[test]
+    fn placeholder_keys_fill_in_the_accepted_command() {
+        use super::Placeholders;
+        use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+        let mut state = make_executor_state(100, 7);
+        let settings = Settings::utc();
+        let found =
+            Placeholders::detect("cp /tmp/a /tmp/b", &settings.placeholders.patterns).unwrap();
+        state.placeholders = Some((7, found));
+
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        for code in [KeyCode::Char('s'), KeyCode::Tab, KeyCode::Char('d')] {
+            let result = state.handle_key_input(&settings, &key(code));
+            assert!(matches!(result, super::InputAction::Continue));
+        }
+        assert_eq!(
+            state.search.input.as_str(),
+            "",
+            "keys must not reach the search input"
+        );
+
+        let result = state.handle_key_input(&settings, &key(KeyCode::Enter));
+        assert!(matches!(result, super::InputAction::Accept(7)));
+        let (_, placeholders) = state.placeholders.as_ref().unwrap();
+        assert_eq!(placeholders.command(), "cp s d");
+
+        // Esc gives up on the placeholders and goes back to searching.
+        state.handle_key_input(&settings, &key(KeyCode::Esc));
+        assert!(state.placeholders.is_none());
+    }
+
     #[test]
     fn execute_exit_returns_based_on_exit_mode() {
         use crate::command::client::search::keybindings::Action;
@@ -3013,6 +5171,92 @@ mod tests {
         assert!(matches!(result, super::InputAction::Copy(7)));
     }
 
+    #[test]
+    fn execute_rerun_in_context() {
+        use crate::command::client::search::keybindings::Action;
+
+        let mut state = make_executor_state(100, 7);
+        let settings = Settings::utc();
+        let result = state.execute_action(&Action::RerunInContext, &settings);
+        assert!(matches!(result, super::InputAction::RerunInContext(7)));
+    }
+
+    #[test]
+    fn execute_edit_command() {
+        use crate::command::client::search::keybindings::Action;
+
+        let mut state = make_executor_state(100, 7);
+        let settings = Settings::utc();
+        let result = state.execute_action(&Action::EditCommand, &settings);
+        assert!(matches!(result, super::InputAction::Edit(7)));
+        assert!(!state.accept);
+    }
+
+    #[test]
+    fn execute_accept_cd() {
+        use crate::command::client::search::keybindings::Action;
+
+        let mut state = make_executor_state(100, 3);
+        let settings = Settings::utc();
+        let result = state.execute_action(&Action::AcceptCd, &settings);
+        assert!(matches!(result, super::InputAction::ChangeDirectory(3)));
+    }
+
+    #[test]
+    fn rerun_changes_directory_first() {
+        use super::{cd_to, in_directory};
+        use atuin_common::shell::Shell;
+
+        let cwd = "/home/me/it's here";
+        assert_eq!(
+            in_directory(&Shell::Zsh, cwd, "make"),
+            r"cd -- '/home/me/it'\''s here' && make"
+        );
+        assert_eq!(
+            in_directory(&Shell::Fish, cwd, "make"),
+            r"cd '/home/me/it\'s here' && make"
+        );
+        assert_eq!(
+            in_directory(&Shell::Powershell, cwd, "make"),
+            "Set-Location -LiteralPath '/home/me/it''s here'; make"
+        );
+        assert_eq!(
+            in_directory(&Shell::Nu, cwd, "make"),
+            "cd r#'/home/me/it's here'#; make"
+        );
+        assert_eq!(cd_to(&Shell::Bash, "/tmp"), "cd -- '/tmp'");
+    }
+
+    #[test]
+    fn inserted_paths_are_quoted_when_they_need_it() {
+        use super::path_word;
+        use atuin_common::shell::Shell;
+
+        assert_eq!(
+            path_word(&Shell::Zsh, "/home/me/notes-2024.md"),
+            "/home/me/notes-2024.md"
+        );
+        assert_eq!(
+            path_word(&Shell::Zsh, "/home/me/my notes.md"),
+            "'/home/me/my notes.md'"
+        );
+        assert_eq!(
+            path_word(&Shell::Nu, "/home/me/my notes.md"),
+            "r#'/home/me/my notes.md'#"
+        );
+    }
+
+    #[test]
+    fn nu_raw_strings_outlast_what_they_quote() {
+        use super::quoted;
+        use atuin_common::shell::Shell;
+
+        assert_eq!(quoted(&Shell::Nu, "it's"), "r#'it's'#");
+        assert_eq!(quoted(&Shell::Nu, "a'#b"), "r##'a'#b'##");
+        assert_eq!(quoted(&Shell::Nu, "'## and '#"), "r###''## and '#'###");
+        assert_eq!(quoted(&Shell::Nu, "#'"), "r#'#''#");
+    }
+
     #[test]
     fn execute_delete() {
         use crate::command::client::search::keybindings::Action;
@@ -3023,6 +5267,136 @@ mod tests {
         assert!(matches!(result, super::InputAction::Delete(7)));
     }
 
+    #[test]
+    fn execute_toggle_pin() {
+        use crate::command::client::search::keybindings::Action;
+
+        let mut state = make_executor_state(100, 7);
+        let settings = Settings::utc();
+        let result = state.execute_action(&Action::TogglePin, &settings);
+        assert!(matches!(result, super::InputAction::TogglePin(7)));
+    }
+
+    #[test]
+    fn named_sessions_are_shown_when_searching_them() {
+        let mut state = make_executor_state(10, 0);
+        state.session_names = HashMap::from([(
+            state.search.context.session.clone(),
+            "incident-4123".to_string(),
+        )]);
+
+        state.search.filter_mode = FilterMode::Global;
+        assert_eq!(state.session_name(), None);
+        state.search.filter_mode = FilterMode::Session;
+        assert_eq!(state.session_name(), Some("incident-4123"));
+
+        state.search.context.session = "another".to_string();
+        assert_eq!(state.session_name(), None);
+    }
+
+    #[test]
+    fn execute_toggle_group() {
+        use crate::command::client::search::keybindings::Action;
+
+        let mut state = make_executor_state(100, 7);
+        let mut settings = Settings::utc();
+        let result = state.execute_action(&Action::ToggleGroup, &settings);
+        assert!(matches!(result, super::InputAction::Continue));
+        assert!(state.warning.is_some());
+
+        settings.search.group_duplicates = true;
+        let result = state.execute_action(&Action::ToggleGroup, &settings);
+        assert!(matches!(result, super::InputAction::ToggleGroup(7)));
+    }
+
+    #[test]
+    fn execute_with_marks_acts_on_them() {
+        use crate::command::client::search::keybindings::Action;
+
+        let mut state = make_executor_state(100, 7);
+        let settings = Settings::utc();
+        let result = state.execute_action(&Action::ToggleMark, &settings);
+        assert!(matches!(result, super::InputAction::ToggleMark(7)));
+
+        let entry: History = History::capture()
+            .timestamp(OffsetDateTime::now_utc())
+            .command("make")
+            .cwd("/")
+            .build()
+            .into();
+        state.results_state.toggle_mark(&entry);
+
+        let result = state.execute_action(&Action::Accept, &settings);
+        assert!(matches!(result, super::InputAction::AcceptMarked));
+        let result = state.execute_action(&Action::ReturnSelection, &settings);
+        assert!(matches!(result, super::InputAction::AcceptMarked));
+        let result = state.execute_action(&Action::Copy, &settings);
+        assert!(matches!(result, super::InputAction::CopyMarked));
+        let result = state.execute_action(&Action::Delete, &settings);
+        assert!(matches!(result, super::InputAction::DeleteMarked));
+
+        // The inspector still acts on the entry it shows
+        state.tab_index = 1;
+        let result = state.execute_action(&Action::Delete, &settings);
+        assert!(matches!(result, super::InputAction::Delete(7)));
+    }
+
+    #[test]
+    fn marked_commands_join_for_the_shell() {
+        use super::join_marked;
+        use atuin_client::settings::JoinMarked;
+        use atuin_common::shell::Shell;
+
+        let marked: Vec<History> = ["cargo build", "cargo test"]
+            .into_iter()
+            .map(|command| {
+                History::capture()
+                    .timestamp(OffsetDateTime::now_utc())
+                    .command(command)
+                    .cwd("/")
+                    .build()
+                    .into()
+            })
+            .collect();
+
+        assert_eq!(
+            join_marked(&Shell::Zsh, JoinMarked::And, &marked),
+            "cargo build && cargo test"
+        );
+        assert_eq!(
+            join_marked(&Shell::Nu, JoinMarked::And, &marked),
+            "cargo build; cargo test"
+        );
+        assert_eq!(
+            join_marked(&Shell::Bash, JoinMarked::Newline, &marked),
+            "cargo build\ncargo test"
+        );
+    }
+
+    #[test]
+    fn copies_are_written_in_the_chosen_format() {
+        use super::copy_text;
+        use atuin_client::settings::CopyFormat;
+        use atuin_common::shell::Shell;
+
+        let entry: History = History::capture()
+            .timestamp(OffsetDateTime::now_utc())
+            .command("echo 'hi'")
+            .cwd("/home/me/src")
+            .build()
+            .into();
+
+        assert_eq!(copy_text(&Shell::Zsh, CopyFormat::Raw, &entry), "echo 'hi'");
+        assert_eq!(
+            copy_text(&Shell::Zsh, CopyFormat::Escaped, &entry),
+            r"'echo '\''hi'\'''"
+        );
+        assert_eq!(
+            copy_text(&Shell::Fish, CopyFormat::Cd, &entry),
+            "cd '/home/me/src' && echo 'hi'"
+        );
+    }
+
     #[test]
     fn execute_switch_context() {
         use crate::command::client::search::keybindings::Action;
@@ -3164,39 +5538,12 @@ mod tests {
 
         let mut state = State {
             history_count: Some(100),
-            update_needed: None,
-            results_state: ListState::default(),
-            switched_search_mode: false,
-            search_mode: SearchMode::Fuzzy,
             results_len: 100,
-            accept: false,
             keymap_mode: KeymapMode::Emacs,
-            prefix: false,
-            current_cursor: None,
-            tab_index: 0,
-            pending_vim_key: None,
-            original_input_empty: false,
-            inspecting_state: InspectingState {
-                current: None,
-                next: None,
-                previous: None,
-            },
             keymaps: KeymapSet::from_settings(&settings),
-            search: SearchState {
-                input: "test query".to_string().into(),
-                filter_mode: FilterMode::Global,
-                context: Context {
-                    session: String::new(),
-                    cwd: String::new(),
-                    hostname: String::new(),
-                    host_id: String::new(),
-                    git_root: None,
-                },
-                custom_context: None,
-            },
-            engine: engines::engine(SearchMode::Fuzzy, &settings),
-            now: Box::new(OffsetDateTime::now_utc),
+            ..test_state(&settings)
         };
+        state.search.input = "test query".to_string().into();
 
         let tab_event = KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE);
         let result = state.handle_key_input(&settings, &tab_event);
@@ -3205,4 +5552,285 @@ mod tests {
             "Tab configured as return-query should return InputAction::ReturnQuery"
         );
     }
+
+    #[rstest::rstest]
+    fn preview_snapshot(
+        #[values(Compactness::Full, Compactness::Compact)] compactness: Compactness,
+        #[values(30, 80)] width: u16,
+    ) {
+        use atuin_client::theme::ThemeManager;
+        use ratatui::{backend::TestBackend, prelude::*};
+
+        use crate::command::client::search::snapshot::assert_snapshot;
+
+        let settings = Settings::utc();
+        let state = State {
+            history_count: Some(1),
+            results_len: 1,
+            keymap_mode: KeymapMode::Emacs,
+            ..test_state(&settings)
+        };
+
+        // Long enough to wrap, with wide characters straddling the wrap point
+        // and a control character that has to be escaped.
+        let results = vec![
+            History::import()
+                .timestamp(OffsetDateTime::UNIX_EPOCH)
+                .command(
+                    "docker run --rm -it -v \"$PWD\":/src 画像を作る --tag 世界 \\\n\t--flag\u{7}",
+                )
+                .build()
+                .into(),
+        ];
+
+        let mut manager = ThemeManager::new(Some(false), Some(String::new()));
+        let theme = manager.load_theme("default", None);
+        let preview_width = match compactness {
+            Compactness::Full => width - 4,
+            _ => width - 2,
+        };
+
+        let mut terminal = Terminal::new(TestBackend::new(width, 6)).unwrap();
+        terminal
+            .draw(|f| {
+                let preview = state.build_preview(
+                    &results,
+                    compactness,
+                    preview_width,
+                    width.into(),
+                    false,
+                    theme,
+                );
+                f.render_widget(preview, f.area());
+            })
+            .unwrap();
+
+        let compactness = match compactness {
+            Compactness::Full => "full",
+            _ => "compact",
+        };
+        assert_snapshot(
+            &format!("preview_{compactness}_{width}"),
+            terminal.backend().buffer(),
+        );
+    }
+
+    fn numbered_results(count: usize) -> Vec<History> {
+        (0..count)
+            .map(|i| {
+                History::import()
+                    .timestamp(OffsetDateTime::UNIX_EPOCH)
+                    .command(format!("echo {i}"))
+                    .build()
+                    .into()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn tiny_terminals_draw_without_panicking() {
+        use atuin_client::theme::ThemeManager;
+        use ratatui::{backend::TestBackend, prelude::*};
+
+        let mut manager = ThemeManager::new(Some(false), Some(String::new()));
+        let theme = manager.load_theme("default", None);
+        let results = numbered_results(5);
+
+        for invert in [false, true] {
+            let settings = Settings {
+                invert,
+                ..Settings::utc()
+            };
+            for width in 0..=60 {
+                for height in 0..=16 {
+                    let mut state = make_executor_state(results.len(), 0);
+                    let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+                    terminal
+                        .draw(|f| {
+                            state.draw(f, &results, None, None, &settings, theme, false);
+                        })
+                        .unwrap();
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn clicks_select_and_double_clicks_accept() {
+        use atuin_client::theme::ThemeManager;
+        use ratatui::crossterm::event::{
+            Event, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+        };
+        use ratatui::{backend::TestBackend, prelude::*};
+
+        let settings = Settings::utc();
+        let mut manager = ThemeManager::new(Some(false), Some(String::new()));
+        let theme = manager.load_theme("default", None);
+        let results = numbered_results(5);
+        let mut state = make_executor_state(results.len(), 0);
+
+        let mut terminal = Terminal::new(TestBackend::new(40, 14)).unwrap();
+        terminal
+            .draw(|f| state.draw(f, &results, None, None, &settings, theme, false))
+            .unwrap();
+
+        let buffer = terminal.backend().buffer();
+        let row = (0..14)
+            .find(|&y| {
+                (0..40)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect::<String>()
+                    .contains("echo 3")
+            })
+            .unwrap();
+        let click = |column| {
+            Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Down(MouseButton::Left),
+                column,
+                row,
+                modifiers: KeyModifiers::NONE,
+            })
+        };
+
+        let result = state.handle_input(&settings, &click(20));
+        assert!(matches!(result, super::InputAction::Continue));
+        assert_eq!(state.results_state.selected(), 3);
+
+        let result = state.handle_input(&settings, &click(21));
+        assert!(matches!(result, super::InputAction::Accept(3)));
+        assert!(state.accept);
+    }
+
+    #[test]
+    fn related_commands_show_beside_wide_results() {
+        use atuin_client::theme::ThemeManager;
+        use ratatui::{backend::TestBackend, prelude::*};
+
+        let settings = Settings::utc();
+        let mut manager = ThemeManager::new(Some(false), Some(String::new()));
+        let theme = manager.load_theme("default", None);
+        let results = numbered_results(5);
+        let mut state = make_executor_state(results.len(), 0);
+        state.show_related = true;
+        state.related = vec![("git add .".to_string(), 12), ("git push".to_string(), 3)];
+
+        let screen = |state: &mut State, width| {
+            let mut terminal = Terminal::new(TestBackend::new(width, 14)).unwrap();
+            terminal
+                .draw(|f| state.draw(f, &results, None, None, &settings, theme, false))
+                .unwrap();
+            let buffer = terminal.backend().buffer();
+            (0..14)
+                .map(|y| (0..width).map(|x| buffer[(x, y)].symbol()).collect())
+                .collect::<Vec<String>>()
+                .join("\n")
+        };
+
+        let wide = screen(&mut state, 90);
+        assert!(wide.contains("Run with"));
+        assert!(wide.contains("12 git add ."));
+        assert!(wide.contains(" 3 git push"));
+        assert!(!screen(&mut state, 60).contains("Run with"));
+    }
+
+    #[test]
+    fn the_status_row_shows_the_query() {
+        use atuin_client::theme::ThemeManager;
+        use ratatui::{backend::TestBackend, prelude::*};
+
+        let mut manager = ThemeManager::new(Some(false), Some(String::new()));
+        let theme = manager.load_theme("default", None);
+        let results = numbered_results(5);
+        let mut state = make_executor_state(results.len(), 0);
+        state.query_time = Some(std::time::Duration::from_millis(12));
+
+        let screen = |state: &mut State, settings: &Settings| {
+            let mut terminal = Terminal::new(TestBackend::new(60, 14)).unwrap();
+            terminal
+                .draw(|f| state.draw(f, &results, None, None, settings, theme, false))
+                .unwrap();
+            let buffer = terminal.backend().buffer();
+            (0..14)
+                .map(|y| (0..60).map(|x| buffer[(x, y)].symbol()).collect())
+                .collect::<Vec<String>>()
+                .join("\n")
+        };
+
+        let mut settings = Settings::utc();
+        let shown = screen(&mut state, &settings);
+        assert!(shown.contains("5 results in 12 ms"));
+        assert!(shown.contains("fuzzy · global"));
+
+        settings.show_status = false;
+        assert!(!screen(&mut state, &settings).contains("5 results"));
+    }
+
+    #[rstest::rstest]
+    fn minimal_layout_snapshot(#[values(false, true)] invert: bool) {
+        use atuin_client::theme::ThemeManager;
+        use ratatui::{backend::TestBackend, prelude::*};
+
+        use crate::command::client::search::snapshot::assert_snapshot;
+
+        let settings = Settings {
+            invert,
+            ..Settings::utc()
+        };
+        let mut manager = ThemeManager::new(Some(false), Some(String::new()));
+        let theme = manager.load_theme("default", None);
+        let results = numbered_results(5);
+        let mut state = make_executor_state(results.len(), 1);
+        state.search.input = "echo".to_string().into();
+
+        let mut terminal = Terminal::new(TestBackend::new(20, 5)).unwrap();
+        terminal
+            .draw(|f| state.draw(f, &results, None, None, &settings, theme, false))
+            .unwrap();
+
+        let name = if invert {
+            "minimal_inverted"
+        } else {
+            "minimal"
+        };
+        assert_snapshot(name, terminal.backend().buffer());
+    }
+
+    #[tokio::test]
+    async fn debounced_queries_wait_for_typing_to_pause() {
+        use crate::command::client::search::query_task::QueryTask;
+
+        let db = atuin_client::database::Sqlite::new("sqlite::memory:", 2.0)
+            .await
+            .unwrap();
+        let debounce = std::time::Duration::from_millis(100);
+        let ranking = SourceRanking::default();
+        let mut state = make_executor_state(0, 0);
+        let showing = state.query_key();
+
+        state.search.input = "car".to_string().into();
+        assert!(
+            state
+                .run_query(showing.clone(), &db, false, debounce, &ranking)
+                .is_none()
+        );
+        assert!(state.searching.is_none());
+        assert!(state.is_searching());
+
+        // Typing on waits longer, for what's typed by then, still from the results showing
+        let typed = state.query_key();
+        state.search.input = "cargo".to_string().into();
+        assert!(
+            state
+                .run_query(typed, &db, false, debounce, &ranking)
+                .is_none()
+        );
+        let (previous, _) = state.waiting.take().unwrap();
+        assert_eq!(previous, showing);
+
+        state.spawn_query(previous, &db);
+        let finished = QueryTask::finished(&mut state.searching).await;
+        assert_eq!(finished.key, state.query_key());
+        assert_eq!(finished.previous, showing);
+        assert!(!state.is_searching());
+    }
 }
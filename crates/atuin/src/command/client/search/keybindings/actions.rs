@@ -2,6 +2,56 @@ use std::fmt;
 
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
+/// A field of a history entry, as the details tab of the inspector shows it and the
+/// `copy-<field>` actions copy it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryField {
+    Command,
+    Directory,
+    Host,
+    User,
+    Session,
+    Time,
+    TimeUtc,
+    Duration,
+    Exit,
+    Author,
+    Id,
+}
+
+impl HistoryField {
+    pub const ALL: [HistoryField; 11] = [
+        HistoryField::Command,
+        HistoryField::Directory,
+        HistoryField::Host,
+        HistoryField::User,
+        HistoryField::Session,
+        HistoryField::Time,
+        HistoryField::TimeUtc,
+        HistoryField::Duration,
+        HistoryField::Exit,
+        HistoryField::Author,
+        HistoryField::Id,
+    ];
+
+    /// The kebab-case name, as it follows `copy-` in an action.
+    pub fn name(self) -> &'static str {
+        match self {
+            HistoryField::Command => "command",
+            HistoryField::Directory => "directory",
+            HistoryField::Host => "host",
+            HistoryField::User => "user",
+            HistoryField::Session => "session",
+            HistoryField::Time => "time",
+            HistoryField::TimeUtc => "time-utc",
+            HistoryField::Duration => "duration",
+            HistoryField::Exit => "exit",
+            HistoryField::Author => "author",
+            HistoryField::Id => "id",
+        }
+    }
+}
+
 /// All possible actions that can be triggered by a keybinding.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Action {
@@ -23,6 +73,9 @@ pub enum Action {
     ClearLine,
     ClearToStart,
     ClearToEnd,
+    Undo,
+    Yank,
+    YankPop,
 
     // List navigation
     SelectNext,
@@ -31,11 +84,16 @@ pub enum Action {
     ScrollHalfPageDown,
     ScrollPageUp,
     ScrollPageDown,
+    ScrollDayUp,
+    ScrollDayDown,
+    JumpToDate,
     ScrollToTop,
     ScrollToBottom,
     ScrollToScreenTop,
     ScrollToScreenMiddle,
     ScrollToScreenBottom,
+    ScrollLeft,
+    ScrollRight,
 
     // Commands — accept selection and execute immediately
     Accept,
@@ -45,6 +103,13 @@ pub enum Action {
     ReturnSelectionNth(u8),
     // Commands — other
     Copy,
+    RerunInContext,
+    EditCommand,
+    OpenWith,
+    OpenUrl,
+    CopyUrl,
+    InsertPath,
+    AcceptCd,
     Delete,
     DeleteAll,
     ReturnOriginal,
@@ -56,6 +121,15 @@ pub enum Action {
     SwitchContext,
     ClearContext,
     ToggleTab,
+    ToggleArchive,
+    ToggleMark,
+    TogglePin,
+    TogglePreview,
+    ToggleRelated,
+    ToggleGroup,
+    PickSession,
+    CycleSource,
+    SelectTheme,
 
     // Mode changes
     VimEnterNormal,
@@ -70,6 +144,8 @@ pub enum Action {
     // Inspector
     InspectPrevious,
     InspectNext,
+    ToggleDetails,
+    CopyField(HistoryField),
 
     // Special
     Noop,
@@ -91,6 +167,11 @@ impl Action {
         {
             return Ok(Action::ReturnSelectionNth(n));
         }
+        if let Some(rest) = s.strip_prefix("copy-")
+            && let Some(field) = HistoryField::ALL.into_iter().find(|f| f.name() == rest)
+        {
+            return Ok(Action::CopyField(field));
+        }
 
         match s {
             "cursor-left" => Ok(Action::CursorLeft),
@@ -109,6 +190,9 @@ impl Action {
             "clear-line" => Ok(Action::ClearLine),
             "clear-to-start" => Ok(Action::ClearToStart),
             "clear-to-end" => Ok(Action::ClearToEnd),
+            "undo" => Ok(Action::Undo),
+            "yank" => Ok(Action::Yank),
+            "yank-pop" => Ok(Action::YankPop),
 
             "select-next" => Ok(Action::SelectNext),
             "select-previous" => Ok(Action::SelectPrevious),
@@ -116,15 +200,27 @@ impl Action {
             "scroll-half-page-down" => Ok(Action::ScrollHalfPageDown),
             "scroll-page-up" => Ok(Action::ScrollPageUp),
             "scroll-page-down" => Ok(Action::ScrollPageDown),
+            "scroll-day-up" => Ok(Action::ScrollDayUp),
+            "scroll-day-down" => Ok(Action::ScrollDayDown),
+            "jump-to-date" => Ok(Action::JumpToDate),
             "scroll-to-top" => Ok(Action::ScrollToTop),
             "scroll-to-bottom" => Ok(Action::ScrollToBottom),
             "scroll-to-screen-top" => Ok(Action::ScrollToScreenTop),
             "scroll-to-screen-middle" => Ok(Action::ScrollToScreenMiddle),
             "scroll-to-screen-bottom" => Ok(Action::ScrollToScreenBottom),
+            "scroll-left" => Ok(Action::ScrollLeft),
+            "scroll-right" => Ok(Action::ScrollRight),
 
             "accept" => Ok(Action::Accept),
             "return-selection" => Ok(Action::ReturnSelection),
             "copy" => Ok(Action::Copy),
+            "rerun-in-context" => Ok(Action::RerunInContext),
+            "edit-command" => Ok(Action::EditCommand),
+            "open-with" => Ok(Action::OpenWith),
+            "open-url" => Ok(Action::OpenUrl),
+            "copy-url" => Ok(Action::CopyUrl),
+            "insert-path" => Ok(Action::InsertPath),
+            "accept-cd" => Ok(Action::AcceptCd),
             "delete" => Ok(Action::Delete),
             "delete-all" => Ok(Action::DeleteAll),
             "return-original" => Ok(Action::ReturnOriginal),
@@ -136,6 +232,15 @@ impl Action {
             "switch-context" => Ok(Action::SwitchContext),
             "clear-context" => Ok(Action::ClearContext),
             "toggle-tab" => Ok(Action::ToggleTab),
+            "toggle-archive" => Ok(Action::ToggleArchive),
+            "toggle-mark" => Ok(Action::ToggleMark),
+            "toggle-pin" => Ok(Action::TogglePin),
+            "toggle-preview" => Ok(Action::TogglePreview),
+            "toggle-related" => Ok(Action::ToggleRelated),
+            "toggle-group" => Ok(Action::ToggleGroup),
+            "pick-session" => Ok(Action::PickSession),
+            "cycle-source" => Ok(Action::CycleSource),
+            "select-theme" => Ok(Action::SelectTheme),
 
             "vim-enter-normal" => Ok(Action::VimEnterNormal),
             "vim-enter-insert" => Ok(Action::VimEnterInsert),
@@ -148,6 +253,7 @@ impl Action {
 
             "inspect-previous" => Ok(Action::InspectPrevious),
             "inspect-next" => Ok(Action::InspectNext),
+            "toggle-details" => Ok(Action::ToggleDetails),
 
             "noop" => Ok(Action::Noop),
 
@@ -174,6 +280,9 @@ impl Action {
             Action::ClearLine => "clear-line".to_string(),
             Action::ClearToStart => "clear-to-start".to_string(),
             Action::ClearToEnd => "clear-to-end".to_string(),
+            Action::Undo => "undo".to_string(),
+            Action::Yank => "yank".to_string(),
+            Action::YankPop => "yank-pop".to_string(),
 
             Action::SelectNext => "select-next".to_string(),
             Action::SelectPrevious => "select-previous".to_string(),
@@ -181,17 +290,29 @@ impl Action {
             Action::ScrollHalfPageDown => "scroll-half-page-down".to_string(),
             Action::ScrollPageUp => "scroll-page-up".to_string(),
             Action::ScrollPageDown => "scroll-page-down".to_string(),
+            Action::ScrollDayUp => "scroll-day-up".to_string(),
+            Action::ScrollDayDown => "scroll-day-down".to_string(),
+            Action::JumpToDate => "jump-to-date".to_string(),
             Action::ScrollToTop => "scroll-to-top".to_string(),
             Action::ScrollToBottom => "scroll-to-bottom".to_string(),
             Action::ScrollToScreenTop => "scroll-to-screen-top".to_string(),
             Action::ScrollToScreenMiddle => "scroll-to-screen-middle".to_string(),
             Action::ScrollToScreenBottom => "scroll-to-screen-bottom".to_string(),
+            Action::ScrollLeft => "scroll-left".to_string(),
+            Action::ScrollRight => "scroll-right".to_string(),
 
             Action::Accept => "accept".to_string(),
             Action::AcceptNth(n) => format!("accept-{n}"),
             Action::ReturnSelection => "return-selection".to_string(),
             Action::ReturnSelectionNth(n) => format!("return-selection-{n}"),
             Action::Copy => "copy".to_string(),
+            Action::RerunInContext => "rerun-in-context".to_string(),
+            Action::EditCommand => "edit-command".to_string(),
+            Action::OpenWith => "open-with".to_string(),
+            Action::OpenUrl => "open-url".to_string(),
+            Action::CopyUrl => "copy-url".to_string(),
+            Action::InsertPath => "insert-path".to_string(),
+            Action::AcceptCd => "accept-cd".to_string(),
             Action::Delete => "delete".to_string(),
             Action::DeleteAll => "delete-all".to_string(),
             Action::ReturnOriginal => "return-original".to_string(),
@@ -203,6 +324,15 @@ impl Action {
             Action::SwitchContext => "switch-context".to_string(),
             Action::ClearContext => "clear-context".to_string(),
             Action::ToggleTab => "toggle-tab".to_string(),
+            Action::ToggleArchive => "toggle-archive".to_string(),
+            Action::ToggleMark => "toggle-mark".to_string(),
+            Action::TogglePin => "toggle-pin".to_string(),
+            Action::TogglePreview => "toggle-preview".to_string(),
+            Action::ToggleRelated => "toggle-related".to_string(),
+            Action::ToggleGroup => "toggle-group".to_string(),
+            Action::PickSession => "pick-session".to_string(),
+            Action::CycleSource => "cycle-source".to_string(),
+            Action::SelectTheme => "select-theme".to_string(),
 
             Action::VimEnterNormal => "vim-enter-normal".to_string(),
             Action::VimEnterInsert => "vim-enter-insert".to_string(),
@@ -215,6 +345,8 @@ impl Action {
 
             Action::InspectPrevious => "inspect-previous".to_string(),
             Action::InspectNext => "inspect-next".to_string(),
+            Action::ToggleDetails => "toggle-details".to_string(),
+            Action::CopyField(field) => format!("copy-{}", field.name()),
 
             Action::Noop => "noop".to_string(),
         }
@@ -278,6 +410,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_copy_field() {
+        assert_eq!(Action::from_str("copy").unwrap(), Action::Copy);
+        assert_eq!(
+            Action::from_str("copy-directory").unwrap(),
+            Action::CopyField(HistoryField::Directory)
+        );
+        assert_eq!(
+            Action::from_str("copy-time-utc").unwrap(),
+            Action::CopyField(HistoryField::TimeUtc)
+        );
+        assert!(Action::from_str("copy-everything").is_err());
+    }
+
     #[test]
     fn parse_unknown_action() {
         assert!(Action::from_str("unknown-action").is_err());
@@ -297,6 +443,23 @@ mod tests {
             Action::ReturnSelectionNth(3),
             Action::VimSearchInsert,
             Action::ScrollToScreenMiddle,
+            Action::ScrollRight,
+            Action::ScrollDayUp,
+            Action::JumpToDate,
+            Action::ToggleMark,
+            Action::TogglePin,
+            Action::TogglePreview,
+            Action::ToggleRelated,
+            Action::ToggleGroup,
+            Action::PickSession,
+            Action::CycleSource,
+            Action::EditCommand,
+            Action::OpenWith,
+            Action::CopyUrl,
+            Action::InsertPath,
+            Action::SelectTheme,
+            Action::ToggleDetails,
+            Action::CopyField(HistoryField::TimeUtc),
         ];
         for action in actions {
             let s = action.as_str();
@@ -14,6 +14,7 @@ pub enum ConditionAtom {
     NoResults,
     HasResults,
     HasContext,
+    KillRingEmpty,
 }
 
 /// Boolean expression tree over condition atoms.
@@ -54,6 +55,8 @@ pub struct EvalContext {
     pub original_input_empty: bool,
     /// Whether we use a search context of a command from the history.
     pub has_context: bool,
+    /// Whether nothing has been killed from the input that could be yanked.
+    pub kill_ring_empty: bool,
 }
 
 // ---------------------------------------------------------------------------
@@ -75,6 +78,7 @@ impl ConditionAtom {
             ConditionAtom::NoResults => ctx.results_len == 0,
             ConditionAtom::HasResults => ctx.results_len > 0,
             ConditionAtom::HasContext => ctx.has_context,
+            ConditionAtom::KillRingEmpty => ctx.kill_ring_empty,
         }
     }
 
@@ -90,6 +94,7 @@ impl ConditionAtom {
             "no-results" => Ok(ConditionAtom::NoResults),
             "has-results" => Ok(ConditionAtom::HasResults),
             "has-context" => Ok(ConditionAtom::HasContext),
+            "kill-ring-empty" => Ok(ConditionAtom::KillRingEmpty),
             _ => Err(format!("unknown condition: {s}")),
         }
     }
@@ -106,6 +111,7 @@ impl ConditionAtom {
             ConditionAtom::NoResults => "no-results",
             ConditionAtom::HasResults => "has-results",
             ConditionAtom::HasContext => "has-context",
+            ConditionAtom::KillRingEmpty => "kill-ring-empty",
         }
     }
 }
@@ -398,6 +404,7 @@ mod tests {
             results_len: len,
             original_input_empty,
             has_context: false,
+            kill_ring_empty: true,
         }
     }
 
@@ -468,6 +475,14 @@ mod tests {
         assert!(ConditionAtom::HasContext.evaluate(&context));
     }
 
+    #[test]
+    fn atom_kill_ring_empty() {
+        let mut context = ctx(0, 0, 0, 0, 0);
+        assert!(ConditionAtom::KillRingEmpty.evaluate(&context));
+        context.kill_ring_empty = false;
+        assert!(!ConditionAtom::KillRingEmpty.evaluate(&context));
+    }
+
     #[rstest]
     #[case::cursor_at_start("cursor-at-start")]
     #[case::cursor_at_end("cursor-at-end")]
@@ -477,6 +492,7 @@ mod tests {
     #[case::list_at_start("list-at-start")]
     #[case::no_results("no-results")]
     #[case::has_results("has-results")]
+    #[case::kill_ring_empty("kill-ring-empty")]
     fn atom_parse_round_trip(#[case] s: &str) {
         let c = ConditionAtom::from_str(s).unwrap();
         assert_eq!(c.as_str(), s);
@@ -3,7 +3,7 @@ use std::collections::HashMap;
 use atuin_client::settings::{KeyBindingConfig, Settings};
 use tracing::warn;
 
-use super::actions::Action;
+use super::actions::{Action, HistoryField};
 use super::conditions::{ConditionAtom, ConditionExpr};
 use super::key::KeyInput;
 use super::keymap::{KeyBinding, KeyRule, Keymap};
@@ -62,17 +62,33 @@ pub struct KeymapSet {
 // ---------------------------------------------------------------------------
 
 /// Add the bindings that are common to all search-tab keymaps:
-/// ctrl-c, ctrl-g, ctrl-o, and tab.
+/// ctrl-c, ctrl-g, ctrl-o, alt-enter, alt-c, shift-left, shift-right, alt-pageup, alt-pagedown
+/// and tab.
 ///
 /// Note: `esc`/`ctrl-[` are NOT included here because their behavior differs
 /// between emacs (exit), vim-normal (exit), and vim-insert (enter normal mode).
-fn add_common_bindings(km: &mut Keymap) {
+fn add_common_bindings(km: &mut Keymap, settings: &Settings) {
     km.bind(key("ctrl-c"), Action::ReturnOriginal);
     km.bind(key("ctrl-g"), Action::ReturnOriginal);
     km.bind(key("ctrl-o"), Action::ToggleTab);
+    km.bind(key("alt-enter"), Action::RerunInContext);
+    km.bind(key("alt-c"), Action::AcceptCd);
 
-    // Tab: always returns selection without executing (unlike Enter which respects enter_accept)
-    km.bind(key("tab"), Action::ReturnSelection);
+    // Scroll the selected command sideways, to read the end of a long one
+    km.bind(key("shift-left"), Action::ScrollLeft);
+    km.bind(key("shift-right"), Action::ScrollRight);
+
+    // Skip through the results a day at a time
+    km.bind(key("alt-pageup"), Action::ScrollDayUp);
+    km.bind(key("alt-pagedown"), Action::ScrollDayDown);
+
+    // Tab: returns selection without executing (unlike Enter which respects enter_accept),
+    // or marks it with search.multi_select
+    if settings.search.multi_select {
+        km.bind(key("tab"), Action::ToggleMark);
+    } else {
+        km.bind(key("tab"), Action::ReturnSelection);
+    }
 }
 
 /// Returns `Accept` or `ReturnSelection` based on the `enter_accept` setting.
@@ -103,7 +119,7 @@ fn accept_action(settings: &Settings) -> Action {
 #[allow(clippy::too_many_lines)]
 pub fn default_emacs_keymap(settings: &Settings) -> Keymap {
     let mut km = Keymap::new();
-    add_common_bindings(&mut km);
+    add_common_bindings(&mut km, settings);
 
     let accept = accept_action(settings);
 
@@ -177,8 +193,17 @@ pub fn default_emacs_keymap(settings: &Settings) -> Keymap {
     km.bind(key("enter"), accept.clone());
     km.bind(key("ctrl-m"), accept);
 
-    // --- Copy ---
-    km.bind(key("ctrl-y"), Action::Copy);
+    // --- Copy / yank ---
+    // ctrl-y yanks back text killed from the query, as in readline; until
+    // something has been killed it copies the selected command.
+    km.bind_conditional(
+        key("ctrl-y"),
+        vec![
+            KeyRule::when(ConditionAtom::KillRingEmpty, Action::Copy),
+            KeyRule::always(Action::Yank),
+        ],
+    );
+    km.bind(key("alt-y"), Action::YankPop);
 
     // --- Numeric shortcuts (alt-1..9 by default, ctrl-1..9 if ctrl_n_shortcuts) ---
     // These return the selection without executing, regardless of enter_accept.
@@ -225,7 +250,8 @@ pub fn default_emacs_keymap(settings: &Settings) -> Keymap {
         ],
     );
     km.bind(key("ctrl-w"), Action::DeleteToWordBoundary);
-    km.bind(key("ctrl-u"), Action::ClearLine);
+    km.bind(key("ctrl-u"), Action::ClearToStart);
+    km.bind(key("ctrl-z"), Action::Undo);
 
     // --- Search mode ---
     km.bind(key("ctrl-r"), Action::CycleFilterMode);
@@ -235,7 +261,15 @@ pub fn default_emacs_keymap(settings: &Settings) -> Keymap {
     km.bind(key("ctrl-n"), Action::SelectNext);
     km.bind(key("ctrl-j"), Action::SelectNext);
     km.bind(key("ctrl-p"), Action::SelectPrevious);
-    km.bind(key("ctrl-k"), Action::SelectPrevious);
+    // ctrl-k kills to the end of the line, unless there's nothing left to
+    // kill, so it still pairs with ctrl-j for moving through results.
+    km.bind_conditional(
+        key("ctrl-k"),
+        vec![
+            KeyRule::when(ConditionAtom::CursorAtEnd, Action::SelectPrevious),
+            KeyRule::always(Action::ClearToEnd),
+        ],
+    );
 
     // --- Redraw ---
     km.bind(key("ctrl-l"), Action::Redraw);
@@ -254,7 +288,7 @@ pub fn default_emacs_keymap(settings: &Settings) -> Keymap {
 /// Build the default vim-normal keymap.
 pub fn default_vim_normal_keymap(settings: &Settings) -> Keymap {
     let mut km = Keymap::new();
-    add_common_bindings(&mut km);
+    add_common_bindings(&mut km, settings);
 
     // esc / ctrl-[ → exit (vim-normal exits, unlike vim-insert)
     km.bind(key("esc"), Action::Exit);
@@ -284,7 +318,9 @@ pub fn default_vim_normal_keymap(settings: &Settings) -> Keymap {
     km.bind(key("x"), Action::DeleteCharAfter);
     km.bind(key("d d"), Action::ClearLine);
     km.bind(key("D"), Action::ClearToEnd);
+    km.bind(key("u"), Action::Undo);
     km.bind(key("C"), Action::VimChangeToEnd);
+    km.bind(key("v"), Action::EditCommand);
 
     // --- Mode switching ---
     km.bind(key("?"), Action::VimSearchInsert);
@@ -377,10 +413,28 @@ pub fn default_inspector_keymap(settings: &Settings) -> Keymap {
         Action::ReturnSelection
     };
     km.bind(key("enter"), accept);
+    km.bind(key("alt-enter"), Action::RerunInContext);
+    km.bind(key("alt-c"), Action::AcceptCd);
 
     // Inspector-specific: delete history entry
     km.bind(key("ctrl-d"), Action::Delete);
 
+    km.bind(key("p"), Action::TogglePin);
+
+    // Details tab, and copying single fields of the entry from it
+    km.bind(key("i"), Action::ToggleDetails);
+    for (k, field) in [
+        ("c", HistoryField::Command),
+        ("d", HistoryField::Directory),
+        ("h", HistoryField::Host),
+        ("u", HistoryField::User),
+        ("s", HistoryField::Session),
+        ("t", HistoryField::Time),
+        ("x", HistoryField::Exit),
+    ] {
+        km.bind(key(k), Action::CopyField(field));
+    }
+
     // Inspector navigation
     km.bind(key("up"), Action::InspectPrevious);
     km.bind(key("down"), Action::InspectNext);
@@ -410,6 +464,22 @@ pub fn default_prefix_keymap() -> Keymap {
     km.bind(key("d"), Action::Delete);
     km.bind(key("D"), Action::DeleteAll);
     km.bind(key("a"), Action::CursorStart);
+    km.bind(key("y"), Action::Copy);
+    km.bind(key("o"), Action::ToggleArchive);
+    km.bind(key("m"), Action::ToggleMark);
+    km.bind(key("p"), Action::TogglePin);
+    km.bind(key("v"), Action::TogglePreview);
+    km.bind(key("r"), Action::ToggleRelated);
+    km.bind(key("g"), Action::ToggleGroup);
+    km.bind(key("s"), Action::PickSession);
+    km.bind(key("j"), Action::JumpToDate);
+    km.bind(key("e"), Action::EditCommand);
+    km.bind(key("w"), Action::OpenWith);
+    km.bind(key("u"), Action::OpenUrl);
+    km.bind(key("U"), Action::CopyUrl);
+    km.bind(key("f"), Action::InsertPath);
+    km.bind(key("t"), Action::SelectTheme);
+    km.bind(key(">"), Action::CycleSource);
     km.bind_conditional(
         key("c"),
         vec![
@@ -544,6 +614,7 @@ mod tests {
             results_len: len,
             original_input_empty: false,
             has_context: false,
+            kill_ring_empty: true,
         }
     }
 
@@ -578,6 +649,20 @@ mod tests {
     #[case::ctrl_n_selects_next_no_exit_condition("ctrl-n", 0, 0, 0, 10, Action::SelectNext)]
     #[case::prefix_key_enters_prefix("ctrl-a", 0, 0, 0, 10, Action::EnterPrefixMode)]
     #[case::home_cursor_start("home", 5, 10, 0, 10, Action::CursorStart)]
+    #[case::alt_b_word_left("alt-b", 5, 10, 0, 10, Action::CursorWordLeft)]
+    #[case::alt_f_word_right("alt-f", 5, 10, 0, 10, Action::CursorWordRight)]
+    #[case::shift_left_scrolls_left("shift-left", 5, 10, 0, 10, Action::ScrollLeft)]
+    #[case::shift_right_scrolls_right("shift-right", 5, 10, 0, 10, Action::ScrollRight)]
+    #[case::alt_pageup_scrolls_a_day_up("alt-pageup", 0, 0, 0, 10, Action::ScrollDayUp)]
+    #[case::alt_pagedown_scrolls_a_day_down("alt-pagedown", 0, 0, 0, 10, Action::ScrollDayDown)]
+    #[case::ctrl_w_deletes_word("ctrl-w", 5, 10, 0, 10, Action::DeleteToWordBoundary)]
+    #[case::ctrl_u_kills_to_start("ctrl-u", 5, 10, 0, 10, Action::ClearToStart)]
+    #[case::ctrl_k_kills_to_end("ctrl-k", 5, 10, 0, 10, Action::ClearToEnd)]
+    #[case::ctrl_k_at_end_selects_previous("ctrl-k", 10, 10, 5, 10, Action::SelectPrevious)]
+    #[case::ctrl_z_undoes("ctrl-z", 5, 10, 0, 10, Action::Undo)]
+    // nothing killed yet → copy
+    #[case::ctrl_y_copies("ctrl-y", 0, 0, 0, 10, Action::Copy)]
+    #[case::alt_y_yank_pops("alt-y", 5, 10, 0, 10, Action::YankPop)]
     fn emacs_keymap_resolves(
         #[case] k: &str,
         #[case] cursor: usize,
@@ -591,6 +676,14 @@ mod tests {
         assert_eq!(km.resolve(&key(k), &ctx), Some(expected));
     }
 
+    #[test]
+    fn emacs_ctrl_y_yanks_once_something_is_killed() {
+        let km = default_emacs_keymap(&default_settings());
+        let mut ctx = make_ctx(0, 0, 0, 10);
+        ctx.kill_ring_empty = false;
+        assert_eq!(km.resolve(&key("ctrl-y"), &ctx), Some(Action::Yank));
+    }
+
     #[test]
     fn emacs_enter_accept_true_uses_accept() {
         let mut settings = default_settings();
@@ -601,6 +694,25 @@ mod tests {
         assert_eq!(km.resolve(&key("tab"), &ctx), Some(Action::ReturnSelection));
     }
 
+    #[test]
+    fn multi_select_tab_marks() {
+        let mut settings = default_settings();
+        settings.search.multi_select = true;
+        let ctx = make_ctx(0, 0, 0, 10);
+
+        for km in [
+            default_emacs_keymap(&settings),
+            default_vim_normal_keymap(&settings),
+            default_vim_insert_keymap(&settings),
+        ] {
+            assert_eq!(km.resolve(&key("tab"), &ctx), Some(Action::ToggleMark));
+        }
+
+        // Nothing to mark while inspecting a single entry
+        let km = default_inspector_keymap(&settings);
+        assert_eq!(km.resolve(&key("tab"), &ctx), Some(Action::ReturnSelection));
+    }
+
     // -- Vim Normal keymap tests --
 
     #[rstest]
@@ -615,6 +727,8 @@ mod tests {
     #[case::big_g_scroll_to_bottom("G", 0, 0, 50, 100, Action::ScrollToBottom)]
     #[case::numeric_returns_selection("3", 0, 0, 0, 10, Action::ReturnSelectionNth(3))]
     #[case::ctrl_u_half_page_up("ctrl-u", 0, 0, 50, 100, Action::ScrollHalfPageUp)]
+    #[case::u_undoes("u", 0, 0, 0, 10, Action::Undo)]
+    #[case::v_edits_command("v", 0, 0, 0, 10, Action::EditCommand)]
     #[case::screen_jump_top("H", 0, 0, 50, 100, Action::ScrollToScreenTop)]
     #[case::screen_jump_middle("M", 0, 0, 50, 100, Action::ScrollToScreenMiddle)]
     #[case::screen_jump_bottom("L", 0, 0, 50, 100, Action::ScrollToScreenBottom)]
@@ -674,6 +788,11 @@ mod tests {
     // enter_accept=false → ReturnSelection
     #[case::tab_returns_selection("tab", 0, 0, 0, 10, Action::ReturnSelection)]
     #[case::prefix_key_enters_prefix("ctrl-a", 0, 0, 0, 10, Action::EnterPrefixMode)]
+    #[case::alt_enter_reruns_in_context("alt-enter", 0, 0, 0, 10, Action::RerunInContext)]
+    #[case::alt_c_accepts_cd("alt-c", 0, 0, 0, 10, Action::AcceptCd)]
+    #[case::i_toggles_details("i", 0, 0, 0, 10, Action::ToggleDetails)]
+    #[case::p_toggles_pin("p", 0, 0, 0, 10, Action::TogglePin)]
+    #[case::d_copies_directory("d", 0, 0, 0, 10, Action::CopyField(HistoryField::Directory))]
     fn inspector_keymap_resolves(
         #[case] k: &str,
         #[case] cursor: usize,
@@ -692,6 +811,22 @@ mod tests {
     #[rstest]
     #[case::d_deletes("d", 0, 0, 0, 10, Some(Action::Delete))]
     #[case::a_cursor_start("a", 0, 0, 0, 10, Some(Action::CursorStart))]
+    #[case::y_copies("y", 0, 0, 0, 10, Some(Action::Copy))]
+    #[case::o_toggles_archive("o", 0, 0, 0, 10, Some(Action::ToggleArchive))]
+    #[case::m_toggles_mark("m", 0, 0, 0, 10, Some(Action::ToggleMark))]
+    #[case::p_toggles_pin("p", 0, 0, 0, 10, Some(Action::TogglePin))]
+    #[case::v_toggles_preview("v", 0, 0, 0, 10, Some(Action::TogglePreview))]
+    #[case::r_toggles_related("r", 0, 0, 0, 10, Some(Action::ToggleRelated))]
+    #[case::g_toggles_group("g", 0, 0, 0, 10, Some(Action::ToggleGroup))]
+    #[case::s_picks_session("s", 0, 0, 0, 10, Some(Action::PickSession))]
+    #[case::j_jumps_to_date("j", 0, 0, 0, 10, Some(Action::JumpToDate))]
+    #[case::e_edits_command("e", 0, 0, 0, 10, Some(Action::EditCommand))]
+    #[case::w_opens_with("w", 0, 0, 0, 10, Some(Action::OpenWith))]
+    #[case::u_opens_url("u", 0, 0, 0, 10, Some(Action::OpenUrl))]
+    #[case::shift_u_copies_url("U", 0, 0, 0, 10, Some(Action::CopyUrl))]
+    #[case::f_inserts_path("f", 0, 0, 0, 10, Some(Action::InsertPath))]
+    #[case::t_selects_theme("t", 0, 0, 0, 10, Some(Action::SelectTheme))]
+    #[case::gt_cycles_source(">", 0, 0, 0, 10, Some(Action::CycleSource))]
     #[case::unknown_key_returns_none("x", 0, 0, 0, 10, None)]
     fn prefix_keymap_resolves(
         #[case] k: &str,
@@ -1060,6 +1195,7 @@ mod tests {
             results_len: 10,
             original_input_empty: true,
             has_context: false,
+            kill_ring_empty: true,
         };
         assert_eq!(
             set.emacs.resolve(&key("esc"), &ctx_original_empty),
@@ -1076,6 +1212,7 @@ mod tests {
             results_len: 10,
             original_input_empty: false,
             has_context: false,
+            kill_ring_empty: true,
         };
         assert_eq!(
             set.emacs.resolve(&key("esc"), &ctx_original_not_empty),
@@ -128,6 +128,7 @@ mod tests {
             results_len: len,
             original_input_empty: false,
             has_context: false,
+            kill_ring_empty: true,
         }
     }
 
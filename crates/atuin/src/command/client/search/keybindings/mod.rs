@@ -4,7 +4,7 @@ pub mod defaults;
 pub mod key;
 pub mod keymap;
 
-pub use actions::Action;
+pub use actions::{Action, HistoryField};
 #[allow(unused_imports)]
 pub use conditions::{ConditionAtom, ConditionExpr, EvalContext};
 pub use defaults::KeymapSet;
@@ -0,0 +1,96 @@
+//! A short list to choose one of, drawn over the search, such as the URLs of a command when it
+//! has several.
+
+use ratatui::{
+    Frame,
+    backend::FromCrossterm,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::Style,
+    text::Line,
+    widgets::{Clear, List, ListItem, ListState},
+};
+
+use super::block_ext::themed_block;
+use crate::command::client::theme::{Meaning, Theme};
+
+pub struct ListPicker {
+    title: String,
+    items: Vec<String>,
+    selected: usize,
+}
+
+impl ListPicker {
+    /// `items` mustn't be empty.
+    pub fn new(title: impl Into<String>, items: Vec<String>) -> Self {
+        Self {
+            title: title.into(),
+            items,
+            selected: 0,
+        }
+    }
+
+    /// Move the selection by `offset`, wrapping round.
+    pub fn select(&mut self, offset: isize) {
+        let len = self.items.len();
+        self.selected = (self.selected + len).wrapping_add_signed(offset) % len;
+    }
+
+    pub fn selected(&self) -> &str {
+        &self.items[self.selected]
+    }
+
+    /// Where the item selected is in the list.
+    pub fn index(&self) -> usize {
+        self.selected
+    }
+
+    pub fn draw(&self, f: &mut Frame, area: Rect, theme: &Theme) {
+        let title = format!(" {} ", self.title);
+        let width = self
+            .items
+            .iter()
+            .map(|item| item.chars().count())
+            .max()
+            .unwrap_or_default()
+            .max(title.chars().count())
+            + 4;
+        let [area] =
+            Layout::horizontal([Constraint::Length(u16::try_from(width).unwrap_or(u16::MAX))])
+                .flex(Flex::Center)
+                .areas(area);
+        let [area] = Layout::vertical([Constraint::Length(
+            u16::try_from(self.items.len() + 2).unwrap_or(u16::MAX),
+        )])
+        .flex(Flex::Center)
+        .areas(area);
+
+        let items: Vec<_> = self
+            .items
+            .iter()
+            .map(|item| ListItem::new(Line::from(format!(" {item}"))))
+            .collect();
+        let list = List::new(items)
+            .style(Style::from_crossterm(theme.get_base()))
+            .highlight_style(Style::from_crossterm(theme.as_style(Meaning::Selection)))
+            .block(themed_block(theme).title(title));
+
+        let mut state = ListState::default().with_selected(Some(self.selected));
+        f.render_widget(Clear, area);
+        f.render_stateful_widget(list, area, &mut state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ListPicker;
+
+    #[test]
+    fn the_selection_wraps_round() {
+        let mut picker = ListPicker::new("Pick", vec!["a".to_string(), "b".to_string()]);
+        assert_eq!(picker.selected(), "a");
+        picker.select(-1);
+        assert_eq!(picker.selected(), "b");
+        picker.select(1);
+        assert_eq!(picker.selected(), "a");
+    }
+}
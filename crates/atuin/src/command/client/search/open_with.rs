@@ -0,0 +1,248 @@
+//! The "open with" menu: commands from the `[[open_with]]` settings, run for the selected
+//! entry, such as opening its directory in an editor or a file manager.
+
+use std::borrow::Cow;
+use std::process::Command;
+
+use atuin_client::{history::History, settings::OpenWith};
+use eyre::{Result, WrapErr, bail};
+use ratatui::{
+    Frame,
+    backend::FromCrossterm,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::Style,
+    text::Line,
+    widgets::{Clear, List, ListItem, ListState},
+};
+
+use super::block_ext::themed_block;
+use super::editor::terminal;
+use super::file_paths;
+use crate::command::client::theme::{Meaning, Theme};
+
+/// The commands that apply to one entry, with its details filled in.
+pub struct OpenWithMenu {
+    cwd: String,
+    /// Each command's name, and what it runs.
+    choices: Vec<(String, String)>,
+    selected: usize,
+}
+
+impl OpenWithMenu {
+    /// `None` if none of `commands` applies to `entry`. Those using `{files}` only apply
+    /// when some of the entry's arguments are files.
+    pub fn new(entry: &History, commands: &[OpenWith]) -> Option<Self> {
+        let files = matched_files(&entry.command, &entry.cwd);
+        let choices: Vec<_> = commands
+            .iter()
+            .filter(|open| !files.is_empty() || !open.command.contains("{files}"))
+            .map(|open| {
+                let command = expand(&open.command, &entry.cwd, &files, &entry.command);
+                (open.name.clone(), command)
+            })
+            .collect();
+
+        (!choices.is_empty()).then(|| Self {
+            cwd: entry.cwd.clone(),
+            choices,
+            selected: 0,
+        })
+    }
+
+    /// Move the selection by `offset`, wrapping round.
+    pub fn select(&mut self, offset: isize) {
+        let len = self.choices.len();
+        self.selected = (self.selected + len).wrapping_add_signed(offset) % len;
+    }
+
+    /// The selected command, ready to run.
+    pub fn command(&self) -> &str {
+        &self.choices[self.selected].1
+    }
+
+    pub fn cwd(&self) -> &str {
+        &self.cwd
+    }
+
+    pub fn draw(&self, f: &mut Frame, area: Rect, theme: &Theme) {
+        let width = self
+            .choices
+            .iter()
+            .map(|(name, _)| name.chars().count())
+            .max()
+            .unwrap_or_default()
+            .max(" Open with ".len())
+            + 4;
+        let [area] =
+            Layout::horizontal([Constraint::Length(u16::try_from(width).unwrap_or(u16::MAX))])
+                .flex(Flex::Center)
+                .areas(area);
+        let [area] = Layout::vertical([Constraint::Length(
+            u16::try_from(self.choices.len() + 2).unwrap_or(u16::MAX),
+        )])
+        .flex(Flex::Center)
+        .areas(area);
+
+        let items: Vec<_> = self
+            .choices
+            .iter()
+            .map(|(name, _)| ListItem::new(Line::from(format!(" {name}"))))
+            .collect();
+        let list = List::new(items)
+            .style(Style::from_crossterm(theme.get_base()))
+            .highlight_style(Style::from_crossterm(theme.as_style(Meaning::Selection)))
+            .block(themed_block(theme).title(" Open with "));
+
+        let mut state = ListState::default().with_selected(Some(self.selected));
+        f.render_widget(Clear, area);
+        f.render_stateful_widget(list, area, &mut state);
+    }
+}
+
+/// The paths in `command` that are files in `cwd`, as they were written.
+fn matched_files(command: &str, cwd: &str) -> Vec<String> {
+    file_paths::find(command, cwd)
+        .into_iter()
+        .filter(|path| path.resolved.is_file())
+        .map(|path| path.written)
+        .collect()
+}
+
+/// `template` with `{cwd}`, `{files}` and `{command}` replaced, each quoted for `sh`.
+/// Other braces are left as they are.
+fn expand(template: &str, cwd: &str, files: &[String], command: &str) -> String {
+    let mut expanded = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(open) = rest.find('{') {
+        expanded.push_str(&rest[..open]);
+        rest = &rest[open..];
+
+        let value = if let Some(after) = rest.strip_prefix("{cwd}") {
+            rest = after;
+            quote(cwd).into_owned()
+        } else if let Some(after) = rest.strip_prefix("{files}") {
+            rest = after;
+            files
+                .iter()
+                .map(|file| quote(file))
+                .collect::<Vec<_>>()
+                .join(" ")
+        } else if let Some(after) = rest.strip_prefix("{command}") {
+            rest = after;
+            quote(command).into_owned()
+        } else {
+            rest = &rest[1..];
+            "{".to_string()
+        };
+        expanded.push_str(&value);
+    }
+    expanded.push_str(rest);
+
+    expanded
+}
+
+fn quote(value: &str) -> Cow<'_, str> {
+    // Only a nul can't be quoted, and no path or command has one
+    shlex::try_quote(value).unwrap_or(Cow::Borrowed("''"))
+}
+
+/// Run `command` with `sh` from `cwd`, on the terminal.
+pub fn run(command: &str, cwd: &str) -> Result<()> {
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .current_dir(cwd)
+        .stdin(terminal())
+        .stdout(terminal())
+        .stderr(terminal())
+        .status()
+        .wrap_err_with(|| format!("failed to run {command}"))?;
+    if !status.success() {
+        bail!("{command} exited with {status}");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(command: &str, cwd: &str) -> History {
+        History::import()
+            .timestamp(time::OffsetDateTime::UNIX_EPOCH)
+            .command(command)
+            .cwd(cwd)
+            .build()
+            .into()
+    }
+
+    #[test]
+    fn details_are_quoted() {
+        let files = ["notes.md".to_string(), "my file.txt".to_string()];
+        assert_eq!(
+            expand(
+                "code {cwd} && vim {files} # {command} {other}",
+                "/home/me/my project",
+                &files,
+                "cat notes.md",
+            ),
+            "code '/home/me/my project' && vim notes.md 'my file.txt' # 'cat notes.md' {other}"
+        );
+        assert_eq!(expand("{{cwd}", "/tmp", &[], ""), "{/tmp");
+    }
+
+    #[test]
+    fn only_existing_files_are_matched() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("Cargo.toml"), "").unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        let cwd = dir.path().to_str().unwrap();
+
+        assert_eq!(
+            matched_files("cat -n Cargo.toml src missing.rs Cargo.toml", cwd),
+            ["Cargo.toml"]
+        );
+        assert!(matched_files("Cargo.toml", cwd).is_empty());
+    }
+
+    #[test]
+    fn commands_for_files_need_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("notes.md"), "").unwrap();
+        let cwd = dir.path().to_str().unwrap();
+        let commands = [
+            OpenWith {
+                name: "Directory".to_string(),
+                command: "ranger {cwd}".to_string(),
+            },
+            OpenWith {
+                name: "Files".to_string(),
+                command: "vim {files}".to_string(),
+            },
+        ];
+
+        let mut menu = OpenWithMenu::new(&entry("cat notes.md", cwd), &commands).unwrap();
+        assert_eq!(menu.cwd(), cwd);
+        menu.select(-1);
+        assert_eq!(menu.command(), "vim notes.md");
+        menu.select(1);
+        assert!(menu.command().starts_with("ranger "));
+
+        let menu = OpenWithMenu::new(&entry("ls", cwd), &commands).unwrap();
+        assert_eq!(menu.choices.len(), 1);
+        assert!(OpenWithMenu::new(&entry("ls", cwd), &commands[1..]).is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn commands_run_in_the_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let cwd = dir.path().to_str().unwrap();
+
+        run("touch opened", cwd).unwrap();
+        assert!(dir.path().join("opened").is_file());
+        assert!(run("false", cwd).is_err());
+    }
+}
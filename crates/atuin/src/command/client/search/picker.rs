@@ -0,0 +1,199 @@
+//! Output for desktop launchers (`atuin search --picker-format`).
+//!
+//! Each result becomes an item with a title (the command on one line), a
+//! subtitle (when and where it ran) and an argument (the command itself, to
+//! paste or run), written in the format the launcher reads, so an integration
+//! only needs to pipe `atuin search` into it.
+
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::time::Duration;
+
+use atuin_client::history::History;
+use atuin_common::string::EscapeNonPrintablePosixExt as _;
+use clap::ValueEnum;
+use serde::Serialize;
+use time::OffsetDateTime;
+
+use super::{format_duration, history_list::one_line};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum PickerFormat {
+    /// An Alfred script filter's JSON: `{"items": [...]}`
+    Alfred,
+    /// Rows for a rofi script, with the command in each row's `info`
+    Rofi,
+    /// One JSON object per line, for Raycast, wofi or your own scripts
+    JsonLines,
+}
+
+#[derive(Debug, Serialize)]
+struct Item {
+    uid: String,
+    title: String,
+    subtitle: String,
+    arg: String,
+}
+
+impl Item {
+    fn new(history: &History, now: OffsetDateTime) -> Self {
+        let ago = Duration::try_from(now - history.timestamp).unwrap_or_default();
+        let mut subtitle = format!(
+            "{} ago · {}",
+            format_duration(ago),
+            history.cwd.escape_non_printable()
+        );
+        if history.exit > 0 {
+            let _ = write!(subtitle, " · exit {}", history.exit);
+        }
+
+        Self {
+            uid: history.id.0.clone(),
+            title: one_line(&history.command),
+            subtitle,
+            arg: history.command.trim().to_string(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct AlfredItems<'a> {
+    items: &'a [Item],
+}
+
+/// Escape `arg` to travel inside a rofi row, which can't hold newlines or the
+/// `\0` and `\x1f` separators. `printf '%b' "$ROFI_INFO"` undoes it.
+fn rofi_info(arg: &str) -> String {
+    let mut out = String::with_capacity(arg.len());
+    for c in arg.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_ascii_control() => {
+                let _ = write!(out, "\\0{:03o}", u32::from(c));
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Write `entries`, best match first, for the launcher that reads `format`.
+pub fn write(
+    out: &mut impl Write,
+    format: PickerFormat,
+    entries: &[History],
+    now: OffsetDateTime,
+) -> io::Result<()> {
+    let items: Vec<Item> = entries.iter().map(|h| Item::new(h, now)).collect();
+
+    match format {
+        PickerFormat::Alfred => {
+            serde_json::to_writer(&mut *out, &AlfredItems { items: &items })?;
+            writeln!(out)?;
+        }
+        PickerFormat::JsonLines => {
+            for item in &items {
+                serde_json::to_writer(&mut *out, item)?;
+                writeln!(out)?;
+            }
+        }
+        PickerFormat::Rofi => {
+            for item in &items {
+                writeln!(
+                    out,
+                    "{}\0info\x1f{}\x1fmeta\x1f{}",
+                    item.title,
+                    rofi_info(&item.arg),
+                    item.subtitle
+                )?;
+            }
+        }
+    }
+
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use atuin_client::history::History;
+    use time::macros::datetime;
+
+    use super::{PickerFormat, write};
+
+    fn entries() -> Vec<History> {
+        let mut build: History = History::import()
+            .timestamp(datetime!(2026-10-15 11:58 UTC))
+            .command("cargo build")
+            .cwd("/src/atuin")
+            .exit(101)
+            .duration(0)
+            .build()
+            .into();
+        build.id = "0001".to_string().into();
+
+        let mut heredoc: History = History::import()
+            .timestamp(datetime!(2026-10-15 09:00 UTC))
+            .command("cat <<EOF\n\"hi\"\\there\nEOF")
+            .cwd("/tmp")
+            .exit(0)
+            .duration(0)
+            .build()
+            .into();
+        heredoc.id = "0002".to_string().into();
+
+        vec![build, heredoc]
+    }
+
+    fn render(format: PickerFormat) -> String {
+        let mut out = Vec::new();
+        write(
+            &mut out,
+            format,
+            &entries(),
+            datetime!(2026-10-15 12:00 UTC),
+        )
+        .unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn alfred_script_filter() {
+        let out = render(PickerFormat::Alfred);
+        let json: serde_json::Value = serde_json::from_str(&out).unwrap();
+
+        assert_eq!(
+            json["items"][0],
+            serde_json::json!({
+                "uid": "0001",
+                "title": "cargo build",
+                "subtitle": "2m ago · /src/atuin · exit 101",
+                "arg": "cargo build",
+            })
+        );
+        assert_eq!(json["items"][1]["title"], "cat <<EOF ⏎ \"hi\"\\there ⏎ EOF");
+        assert_eq!(json["items"][1]["arg"], "cat <<EOF\n\"hi\"\\there\nEOF");
+    }
+
+    #[test]
+    fn json_lines_keep_multi_line_commands_on_one_line() {
+        let out = render(PickerFormat::JsonLines);
+        let lines: Vec<&str> = out.lines().collect();
+
+        assert_eq!(lines.len(), 2);
+        let heredoc: serde_json::Value = serde_json::from_str(lines[1]).unwrap();
+        assert_eq!(heredoc["uid"], "0002");
+        assert_eq!(heredoc["subtitle"], "3h ago · /tmp");
+        assert_eq!(heredoc["arg"], "cat <<EOF\n\"hi\"\\there\nEOF");
+    }
+
+    #[test]
+    fn rofi_rows() {
+        assert_eq!(
+            render(PickerFormat::Rofi),
+            "cargo build\0info\x1fcargo build\x1fmeta\x1f2m ago · /src/atuin · exit 101\n\
+             cat <<EOF ⏎ \"hi\"\\there ⏎ EOF\0info\x1fcat <<EOF\\n\"hi\"\\\\there\\nEOF\x1fmeta\x1f3h ago · /tmp\n"
+        );
+    }
+}
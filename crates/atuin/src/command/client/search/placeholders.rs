@@ -0,0 +1,180 @@
+use std::ops::Range;
+
+use regex::Regex;
+
+/// A command with the parts that likely need changing before it's run again
+/// (UUIDs, dates, temporary paths), stepped through one at a time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Placeholders {
+    command: String,
+    spans: Vec<Range<usize>>,
+    current: usize,
+    /// Whether anything has been typed over the current placeholder yet.
+    edited: bool,
+}
+
+impl Placeholders {
+    /// `None` if nothing in `command` matches any of `patterns`.
+    pub fn detect(command: &str, patterns: &[Regex]) -> Option<Self> {
+        let mut spans: Vec<Range<usize>> = patterns
+            .iter()
+            .flat_map(|re| re.find_iter(command).map(|m| m.range()))
+            .filter(|span| !span.is_empty())
+            .collect();
+        spans.sort_by_key(|span| (span.start, std::cmp::Reverse(span.end)));
+
+        // Overlapping matches (a date inside a temp path) are one placeholder.
+        let mut merged: Vec<Range<usize>> = Vec::new();
+        for span in spans {
+            match merged.last_mut() {
+                Some(last) if span.start < last.end => last.end = last.end.max(span.end),
+                _ => merged.push(span),
+            }
+        }
+
+        (!merged.is_empty()).then(|| Self {
+            command: command.to_string(),
+            spans: merged,
+            current: 0,
+            edited: false,
+        })
+    }
+
+    pub fn command(&self) -> &str {
+        &self.command
+    }
+
+    /// The placeholder being replaced, as a byte range of [`Self::command`].
+    pub fn current(&self) -> Range<usize> {
+        self.spans[self.current].clone()
+    }
+
+    /// Which placeholder is being replaced, counting from 1, and how many there are.
+    pub fn position(&self) -> (usize, usize) {
+        (self.current + 1, self.spans.len())
+    }
+
+    /// Type `c`. The first character typed replaces the placeholder.
+    pub fn insert(&mut self, c: char) {
+        let span = self.current();
+        let at = if self.edited {
+            span.end
+        } else {
+            self.edited = true;
+            self.replace_current(span.start, "");
+            span.start
+        };
+
+        let mut buf = [0; 4];
+        self.replace_current(at, c.encode_utf8(&mut buf));
+    }
+
+    pub fn backspace(&mut self) {
+        let span = self.current();
+        if !self.edited {
+            self.edited = true;
+            self.replace_current(span.start, "");
+        } else if let Some(c) = self.command[span.clone()].chars().next_back() {
+            self.replace_current(span.end - c.len_utf8(), "");
+        }
+    }
+
+    /// Move to the next placeholder, returning false if this was the last.
+    pub fn next(&mut self) -> bool {
+        if self.current + 1 == self.spans.len() {
+            return false;
+        }
+        self.current += 1;
+        self.edited = false;
+        true
+    }
+
+    pub fn previous(&mut self) {
+        if self.current > 0 {
+            self.current -= 1;
+            self.edited = true;
+        }
+    }
+
+    /// Replace the end of the current placeholder, from `from`, with `text`,
+    /// shifting the placeholders after it.
+    fn replace_current(&mut self, from: usize, text: &str) {
+        let span = self.current();
+        self.command.replace_range(from..span.end, text);
+
+        let new_end = from + text.len();
+        let shift = new_end.cast_signed() - span.end.cast_signed();
+        self.spans[self.current].end = new_end;
+        for later in &mut self.spans[self.current + 1..] {
+            later.start = later.start.saturating_add_signed(shift);
+            later.end = later.end.saturating_add_signed(shift);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use atuin_client::settings::DEFAULT_PLACEHOLDER_PATTERNS;
+
+    use super::*;
+
+    fn defaults() -> Vec<Regex> {
+        DEFAULT_PLACEHOLDER_PATTERNS
+            .iter()
+            .map(|p| Regex::new(p).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn finds_the_default_placeholders() {
+        let command =
+            "kubectl logs 1b4e28ba-2fa1-11d2-883f-0016d3cca427 --since 2024-01-31 > /tmp/out.log";
+        let p = Placeholders::detect(command, &defaults()).unwrap();
+
+        let found: Vec<&str> = p.spans.iter().map(|s| &command[s.clone()]).collect();
+        assert_eq!(
+            found,
+            vec![
+                "1b4e28ba-2fa1-11d2-883f-0016d3cca427",
+                "2024-01-31",
+                "/tmp/out.log"
+            ]
+        );
+    }
+
+    #[test]
+    fn plain_commands_have_none() {
+        assert_eq!(Placeholders::detect("cargo build", &defaults()), None);
+    }
+
+    #[test]
+    fn overlapping_matches_are_one_placeholder() {
+        let p = Placeholders::detect("cat /tmp/2024-01-31/log", &defaults()).unwrap();
+        assert_eq!(p.position(), (1, 1));
+        assert_eq!(&p.command()[p.current()], "/tmp/2024-01-31/log");
+    }
+
+    #[test]
+    fn typing_replaces_each_placeholder_in_turn() {
+        let mut p = Placeholders::detect("cp /tmp/a /tmp/b", &defaults()).unwrap();
+
+        for c in "src".chars() {
+            p.insert(c);
+        }
+        assert!(p.next());
+
+        p.insert('x');
+        p.insert('y');
+        p.backspace();
+        assert!(!p.next());
+
+        assert_eq!(p.command(), "cp src x");
+    }
+
+    #[test]
+    fn skipping_keeps_the_original() {
+        let mut p = Placeholders::detect("rm /tmp/a", &defaults()).unwrap();
+        assert!(!p.next());
+        assert_eq!(p.command(), "rm /tmp/a");
+    }
+}
@@ -0,0 +1,228 @@
+//! Queries run in the background, so typing isn't held up by a slow one on a large database.
+//! Starting another query cancels the one running, as its results would only be replaced.
+//!
+//! Where the engine finds fewer results quicker, the first page of them comes first, to show
+//! while the rest are found.
+
+use std::time::{Duration, Instant};
+
+use atuin_client::{database::Database, history::History};
+use eyre::{Result, eyre};
+use tokio::{
+    sync::mpsc::{self, UnboundedReceiver},
+    task::JoinHandle,
+};
+
+use super::engines::{FIRST_PAGE, SearchState, SharedEngine};
+use super::result_cache::QueryKey;
+
+/// A query running in the background. Dropping it cancels it.
+pub struct QueryTask {
+    key: QueryKey,
+    /// The query whose results were showing when this one started, or this one once its first
+    /// page is
+    previous: QueryKey,
+    started: Instant,
+    /// The results as they're found, and whether they're all of them
+    pages: UnboundedReceiver<(Result<Vec<History>>, bool)>,
+    handle: JoinHandle<()>,
+}
+
+/// Results a query found.
+pub struct Finished {
+    pub key: QueryKey,
+    pub previous: QueryKey,
+    pub took: Duration,
+    pub results: Result<Vec<History>>,
+    /// Whether these are all the results, rather than the first page of them
+    pub complete: bool,
+}
+
+impl QueryTask {
+    pub fn spawn(
+        engine: SharedEngine,
+        search: SearchState,
+        key: QueryKey,
+        previous: QueryKey,
+        db: &dyn Database,
+    ) -> Self {
+        let mut db = db.clone_boxed();
+        let (sender, pages) = mpsc::unbounded_channel();
+        let handle = tokio::spawn(async move {
+            let mut engine = engine.lock().await;
+
+            if engine.pages() {
+                match engine.query(&search.first_page(), db.as_mut()).await {
+                    // Short of a page, so there's no more to find
+                    Ok(page) if page.len() < FIRST_PAGE => {
+                        let _ = sender.send((Ok(page), true));
+                        return;
+                    }
+                    Ok(page) => {
+                        let _ = sender.send((Ok(page), false));
+                    }
+                    Err(e) => {
+                        let _ = sender.send((Err(e), true));
+                        return;
+                    }
+                }
+            }
+
+            let _ = sender.send((engine.query(&search, db.as_mut()).await, true));
+        });
+
+        Self {
+            key,
+            previous,
+            started: Instant::now(),
+            pages,
+            handle,
+        }
+    }
+
+    pub fn previous(&self) -> &QueryKey {
+        &self.previous
+    }
+
+    /// Wait for the query in `task` to find results, taking it once it has found them all.
+    /// Never returns while there's none.
+    pub async fn finished(task: &mut Option<Self>) -> Finished {
+        let Some(running) = task.as_mut() else {
+            return std::future::pending().await;
+        };
+
+        let (results, complete) = running
+            .pages
+            .recv()
+            .await
+            .unwrap_or_else(|| (Err(eyre!("the query stopped before finishing")), true));
+        let finished = Finished {
+            key: running.key.clone(),
+            previous: running.previous.clone(),
+            took: running.started.elapsed(),
+            results,
+            complete,
+        };
+
+        if complete {
+            *task = None;
+        } else {
+            running.previous = running.key.clone();
+        }
+
+        finished
+    }
+}
+
+impl Drop for QueryTask {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use atuin_client::{
+        database::{Context, Sqlite},
+        ordering::ContextBoost,
+        settings::{FilterMode, SearchMode, Settings},
+    };
+
+    use super::*;
+    use crate::command::client::search::engines;
+
+    fn key(input: &str) -> QueryKey {
+        QueryKey {
+            input: input.to_string(),
+            filter_mode: FilterMode::Global,
+            search_mode: SearchMode::Fuzzy,
+            custom_context: None,
+            include_archive: None,
+            before: None,
+        }
+    }
+
+    fn search(input: &str) -> SearchState {
+        SearchState {
+            input: input.to_string().into(),
+            filter_mode: FilterMode::Global,
+            context: Context {
+                session: String::new(),
+                cwd: String::new(),
+                hostname: String::new(),
+                host_id: String::new(),
+                git_root: None,
+            },
+            custom_context: None,
+            include_archive: None,
+            boost: ContextBoost::default(),
+            exclude_containers: false,
+            before: None,
+            limit: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn queries_finish_in_the_background() {
+        let db = Sqlite::new("sqlite::memory:", 2.0).await.unwrap();
+        let history: History = History::import()
+            .timestamp(time::OffsetDateTime::now_utc())
+            .command("cargo build")
+            .build()
+            .into();
+        db.save(&history).await.unwrap();
+
+        let engine = engines::shared(SearchMode::Fuzzy, &Settings::utc());
+        let mut task = Some(QueryTask::spawn(
+            engine,
+            search("cargo"),
+            key("cargo"),
+            key(""),
+            &db,
+        ));
+
+        let finished = QueryTask::finished(&mut task).await;
+        assert!(task.is_none());
+        assert_eq!(finished.key, key("cargo"));
+        assert_eq!(finished.previous, key(""));
+        assert_eq!(finished.results.unwrap()[0].command, "cargo build");
+        // Short of a page, so these were all of them
+        assert!(finished.complete);
+    }
+
+    #[tokio::test]
+    async fn the_first_page_comes_before_the_rest() {
+        let db = Sqlite::new("sqlite::memory:", 2.0).await.unwrap();
+        let now = time::OffsetDateTime::now_utc();
+        for i in 0..FIRST_PAGE + 10 {
+            let history: History = History::import()
+                .timestamp(now)
+                .command(format!("cargo test {i}"))
+                .build()
+                .into();
+            db.save(&history).await.unwrap();
+        }
+
+        let engine = engines::shared(SearchMode::Fuzzy, &Settings::utc());
+        let mut task = Some(QueryTask::spawn(
+            engine,
+            search("cargo"),
+            key("cargo"),
+            key(""),
+            &db,
+        ));
+
+        let first = QueryTask::finished(&mut task).await;
+        assert!(!first.complete);
+        assert_eq!(first.previous, key(""));
+        assert_eq!(first.results.unwrap().len(), FIRST_PAGE);
+        assert!(task.is_some());
+
+        // By the time the rest are found, the first page is what's showing
+        let rest = QueryTask::finished(&mut task).await;
+        assert!(rest.complete);
+        assert_eq!(rest.previous, key("cargo"));
+        assert_eq!(rest.results.unwrap().len(), FIRST_PAGE + 10);
+        assert!(task.is_none());
+    }
+}
@@ -0,0 +1,127 @@
+use std::collections::VecDeque;
+
+use atuin_client::{
+    history::{History, HistoryId},
+    settings::{FilterMode, SearchMode},
+};
+use time::OffsetDateTime;
+
+/// How many queries' results to keep. Enough to backspace through a long query.
+const CAPACITY: usize = 32;
+
+/// Everything the results of a query depend on. The context only changes along with
+/// `custom_context`, so that stands in for it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryKey {
+    pub input: String,
+    pub filter_mode: FilterMode,
+    pub search_mode: SearchMode,
+    pub custom_context: Option<HistoryId>,
+    pub include_archive: Option<bool>,
+    pub before: Option<OffsetDateTime>,
+}
+
+/// The results of recent queries, most recently used first, so going back to a query (by
+/// backspacing, or cycling round the filter modes) doesn't run it again.
+#[derive(Default)]
+pub struct ResultCache {
+    entries: VecDeque<(QueryKey, Vec<History>)>,
+}
+
+impl ResultCache {
+    pub fn get(&mut self, key: &QueryKey) -> Option<Vec<History>> {
+        let pos = self.entries.iter().position(|(k, _)| k == key)?;
+        let entry = self.entries.remove(pos)?;
+        let results = entry.1.clone();
+        self.entries.push_front(entry);
+        Some(results)
+    }
+
+    pub fn insert(&mut self, key: QueryKey, results: Vec<History>) {
+        self.entries.retain(|(k, _)| *k != key);
+        self.entries.push_front((key, results));
+        self.entries.truncate(CAPACITY);
+    }
+
+    /// Forget everything, once history has changed under the cached results.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(input: &str) -> QueryKey {
+        QueryKey {
+            input: input.to_string(),
+            filter_mode: FilterMode::Global,
+            search_mode: SearchMode::Fuzzy,
+            custom_context: None,
+            include_archive: None,
+            before: None,
+        }
+    }
+
+    fn results(command: &str) -> Vec<History> {
+        vec![
+            History::import()
+                .timestamp(time::OffsetDateTime::now_utc())
+                .command(command)
+                .build()
+                .into(),
+        ]
+    }
+
+    #[test]
+    fn backspacing_finds_earlier_results() {
+        let mut cache = ResultCache::default();
+        cache.insert(key("g"), results("git"));
+        cache.insert(key("gi"), results("git"));
+        cache.insert(key("gir"), Vec::new());
+
+        assert_eq!(cache.get(&key("gi")).unwrap()[0].command, "git");
+        assert_eq!(cache.get(&key("g")).unwrap()[0].command, "git");
+        assert!(cache.get(&key("gi ")).is_none());
+    }
+
+    #[test]
+    fn results_depend_on_more_than_the_input() {
+        let mut cache = ResultCache::default();
+        cache.insert(key("ls"), results("ls"));
+
+        let directory = QueryKey {
+            filter_mode: FilterMode::Directory,
+            ..key("ls")
+        };
+        let archive = QueryKey {
+            include_archive: Some(true),
+            ..key("ls")
+        };
+        let jumped = QueryKey {
+            before: Some(time::macros::datetime!(2024-03-16 00:00 UTC)),
+            ..key("ls")
+        };
+        assert!(cache.get(&directory).is_none());
+        assert!(cache.get(&archive).is_none());
+        assert!(cache.get(&jumped).is_none());
+
+        cache.clear();
+        assert!(cache.get(&key("ls")).is_none());
+    }
+
+    #[test]
+    fn least_recently_used_results_are_dropped() {
+        let mut cache = ResultCache::default();
+        for i in 0..CAPACITY {
+            cache.insert(key(&i.to_string()), Vec::new());
+        }
+        assert!(cache.get(&key("0")).is_some());
+        cache.insert(key("new"), Vec::new());
+
+        assert!(cache.get(&key("0")).is_some());
+        assert!(cache.get(&key("1")).is_none());
+        assert!(cache.get(&key("new")).is_some());
+    }
+}
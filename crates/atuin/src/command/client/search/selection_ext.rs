@@ -1,32 +1,21 @@
-use ratatui::style::{Color, Style};
+use ratatui::{backend::FromCrossterm, style::Style};
 
 use crate::command::client::theme::Theme;
 
-/// Converts crossterm Color to ratatui Color
-fn convert_color(color: crossterm::style::Color) -> Color {
-    match color {
-        crossterm::style::Color::Rgb { r, g, b } => Color::Rgb(r, g, b),
-        crossterm::style::Color::AnsiValue(v) => Color::Indexed(v),
-        crossterm::style::Color::Black => Color::Black,
-        crossterm::style::Color::DarkGrey => Color::DarkGray,
-        crossterm::style::Color::Red | crossterm::style::Color::DarkRed => Color::Red,
-        crossterm::style::Color::Green | crossterm::style::Color::DarkGreen => Color::Green,
-        crossterm::style::Color::Yellow | crossterm::style::Color::DarkYellow => Color::Yellow,
-        crossterm::style::Color::Blue | crossterm::style::Color::DarkBlue => Color::Blue,
-        crossterm::style::Color::Magenta | crossterm::style::Color::DarkMagenta => Color::Magenta,
-        crossterm::style::Color::Cyan | crossterm::style::Color::DarkCyan => Color::Cyan,
-        crossterm::style::Color::White => Color::White,
-        crossterm::style::Color::Grey => Color::Gray,
-        crossterm::style::Color::Reset => Color::Reset,
-    }
+/// The theme's style for the selected row: its background, any text color, and any
+/// attributes, such as the reversed colors used where there's no background to show
+pub fn get_selection_style(theme: &Theme) -> Style {
+    Style::from_crossterm(theme.get_selection())
 }
 
-/// Returns a ratatui Style with the selection background color from theme
-pub fn get_selection_style(theme: &Theme) -> Style {
-    let content_style = theme.get_selection();
-    let mut style = Style::default();
-    if let Some(bg) = content_style.background_color {
-        style = style.bg(convert_color(bg));
+/// `style` as drawn on the selected row. The selection's text color only goes to text
+/// without a color of its own, so matches and syntax colors still show.
+pub fn apply_selection(style: Style, theme: &Theme) -> Style {
+    let selection = get_selection_style(theme);
+    Style {
+        fg: style.fg.or(selection.fg),
+        bg: selection.bg.or(style.bg),
+        ..style
     }
-    style
+    .add_modifier(selection.add_modifier)
 }
@@ -0,0 +1,159 @@
+//! The recent sessions, each summed up on a line, to choose one to search the history of.
+
+use std::collections::HashMap;
+
+use atuin_client::{history::History, settings::Timezone};
+use atuin_history::summary::SessionSummary;
+use ratatui::{Frame, layout::Rect};
+use time::macros::format_description;
+
+use super::{duration::format_duration, list_picker::ListPicker};
+use crate::command::client::theme::Theme;
+
+/// How many of the tools a session used are named on its line.
+const TOOLS_SHOWN: usize = 3;
+
+/// `summary` on a line: its name, or the start of its ID, when it started and for how long,
+/// what ran and what failed, and the tools used most.
+pub fn one_line(summary: &SessionSummary, name: Option<&str>, tz: Timezone) -> String {
+    let format = format_description!("[year]-[month]-[day] [hour repr:24]:[minute]");
+    let name = name.map_or_else(|| summary.session.chars().take(8).collect(), str::to_string);
+    let start = summary
+        .start
+        .to_offset(tz.0)
+        .format(format)
+        .unwrap_or_default();
+
+    let mut line = format!(
+        "{name}  {start}  {}  {} commands, {} failed",
+        format_duration(summary.duration()),
+        summary.commands,
+        summary.failures
+    );
+    let tools: Vec<&str> = summary
+        .tools
+        .iter()
+        .take(TOOLS_SHOWN)
+        .map(|(tool, _)| tool.as_str())
+        .collect();
+    if !tools.is_empty() {
+        line.push_str("  ");
+        line.push_str(&tools.join(", "));
+    }
+    line
+}
+
+/// Choosing one of the recent sessions.
+pub struct SessionPicker {
+    list: ListPicker,
+    /// The last command of each session, in the order listed, to search from
+    last: Vec<History>,
+}
+
+impl SessionPicker {
+    /// The sessions with their history, newest first. `None` if there are none.
+    pub fn new(
+        sessions: Vec<(SessionSummary, History)>,
+        names: &HashMap<String, String>,
+        tz: Timezone,
+    ) -> Option<Self> {
+        if sessions.is_empty() {
+            return None;
+        }
+
+        let (lines, last): (Vec<String>, Vec<History>) = sessions
+            .into_iter()
+            .map(|(summary, last)| {
+                let name = names.get(&summary.session).map(String::as_str);
+                (one_line(&summary, name, tz), last)
+            })
+            .unzip();
+
+        Some(Self {
+            list: ListPicker::new("Sessions", lines),
+            last,
+        })
+    }
+
+    /// Move the selection by `offset`, wrapping round.
+    pub fn select(&mut self, offset: isize) {
+        self.list.select(offset);
+    }
+
+    /// The last command of the session selected.
+    pub fn last(&self) -> &History {
+        &self.last[self.list.index()]
+    }
+
+    pub fn draw(&self, f: &mut Frame, area: Rect, theme: &Theme) {
+        self.list.draw(f, area, theme);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use atuin_client::history::History;
+    use atuin_client::settings::{Settings, Timezone};
+    use time::{Duration, UtcOffset, macros::datetime};
+
+    use super::{SessionPicker, SessionSummary, one_line};
+
+    fn entry(session: &str, command: &str, minute: i64, exit: i64) -> History {
+        History::import()
+            .timestamp(datetime!(2026-10-16 09:00 UTC) + Duration::minutes(minute))
+            .command(command)
+            .cwd("/home/ellie")
+            .exit(exit)
+            .duration(0)
+            .session(session)
+            .build()
+            .into()
+    }
+
+    fn summary(session: &str, history: &[History]) -> SessionSummary {
+        SessionSummary::compute(&Settings::utc(), session, history).unwrap()
+    }
+
+    #[test]
+    fn sessions_are_summed_up_on_a_line() {
+        let history = [
+            entry("0192d4a3b1c2", "git pull", 0, 0),
+            entry("0192d4a3b1c2", "cargo build", 10, 101),
+            entry("0192d4a3b1c2", "git push", 95, 0),
+        ];
+        let summary = summary("0192d4a3b1c2", &history);
+        let utc = Timezone(UtcOffset::UTC);
+
+        assert_eq!(
+            one_line(&summary, None, utc),
+            "0192d4a3  2026-10-16 09:00  1h  3 commands, 1 failed  git, cargo"
+        );
+        assert_eq!(
+            one_line(&summary, Some("release"), utc),
+            "release  2026-10-16 09:00  1h  3 commands, 1 failed  git, cargo"
+        );
+    }
+
+    #[test]
+    fn the_picked_session_is_searched_from_its_last_command() {
+        let first = [entry("abc", "ls", 0, 0), entry("abc", "pwd", 1, 0)];
+        let second = [entry("def", "top", 0, 0)];
+        let mut picker = SessionPicker::new(
+            vec![
+                (summary("abc", &first), first[1].clone()),
+                (summary("def", &second), second[0].clone()),
+            ],
+            &HashMap::new(),
+            Timezone(UtcOffset::UTC),
+        )
+        .unwrap();
+
+        assert_eq!(picker.last().command, "pwd");
+        picker.select(1);
+        assert_eq!(picker.last().session, "def");
+
+        assert!(SessionPicker::new(vec![], &HashMap::new(), Timezone(UtcOffset::UTC)).is_none());
+    }
+}
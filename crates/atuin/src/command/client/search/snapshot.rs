@@ -0,0 +1,103 @@
+//! Snapshot tests for rendered widgets.
+//!
+//! [`assert_snapshot`] compares a rendered buffer with `snapshots/<name>.snap`
+//! next to this file. A snapshot holds the text of each row, then every run of
+//! styled cells, so a change in colour or selection background fails a test
+//! just like a change in layout does.
+//!
+//! Run the tests with `UPDATE_SNAPSHOTS=1` to write new or changed snapshots,
+//! then review the diff before committing them.
+
+use std::{fmt::Write as _, fs, path::PathBuf};
+
+use ratatui::{
+    buffer::{Buffer, Cell},
+    style::{Color, Modifier},
+};
+use unicode_width::UnicodeWidthStr;
+
+const UPDATE_ENV: &str = "UPDATE_SNAPSHOTS";
+
+fn path(name: &str) -> PathBuf {
+    [
+        env!("CARGO_MANIFEST_DIR"),
+        "src/command/client/search/snapshots",
+        &format!("{name}.snap"),
+    ]
+    .iter()
+    .collect()
+}
+
+fn styled(cell: &Cell) -> Option<(Color, Color, Modifier)> {
+    let style = (cell.fg, cell.bg, cell.modifier);
+    (style != (Color::Reset, Color::Reset, Modifier::empty())).then_some(style)
+}
+
+/// Render `buffer` as snapshot text.
+pub fn render(buffer: &Buffer) -> String {
+    let area = buffer.area;
+    let mut out = String::new();
+
+    for y in area.top()..area.bottom() {
+        let mut row = String::new();
+        // Wide characters fill the cells after them, which hold nothing of
+        // their own.
+        let mut covered = 0;
+        for x in area.left()..area.right() {
+            let symbol = buffer[(x, y)].symbol();
+            if covered > 0 {
+                covered -= 1;
+                continue;
+            }
+            row.push_str(symbol);
+            covered = symbol.width().saturating_sub(1);
+        }
+        writeln!(out, "{row:?}").unwrap();
+    }
+
+    out.push('\n');
+    for y in area.top()..area.bottom() {
+        let mut x = area.left();
+        while x < area.right() {
+            let Some(style) = styled(&buffer[(x, y)]) else {
+                x += 1;
+                continue;
+            };
+            let start = x;
+            while x < area.right() && styled(&buffer[(x, y)]) == Some(style) {
+                x += 1;
+            }
+            let (fg, bg, modifier) = style;
+            writeln!(out, "{y:>2} {start:>3}..{x:<3} {fg} on {bg} {modifier:?}").unwrap();
+        }
+    }
+
+    out
+}
+
+/// Check that `buffer` renders the same as the snapshot called `name`.
+#[track_caller]
+pub fn assert_snapshot(name: &str, buffer: &Buffer) {
+    let path = path(name);
+    let actual = render(buffer);
+
+    if std::env::var_os(UPDATE_ENV).is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, actual).unwrap();
+        return;
+    }
+
+    let Ok(expected) = fs::read_to_string(&path) else {
+        panic!(
+            "no snapshot at {}; run the tests with {UPDATE_ENV}=1 to create it\n\n{actual}",
+            path.display()
+        );
+    };
+
+    assert!(
+        expected == actual,
+        "{name} no longer matches {}; run the tests with {UPDATE_ENV}=1 to update it\n\n\
+         expected:\n{expected}\nactual:\n{actual}",
+        path.display()
+    );
+}
@@ -0,0 +1,260 @@
+//! Commands suggested by sources outside the history: a team's runbooks, the recipes of the
+//! justfile in the directory, the plugins kubectl has. Each source is a command from the
+//! `[[sources]]` settings printing the commands as JSON lines, searched with `>` alongside
+//! what's synced. Sources can be slow, so what they print is cached for a while per directory.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use atuin_client::settings::Source;
+use eyre::{Result, WrapErr, bail};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use tokio::process::Command;
+
+/// How long a source is given to print its commands.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A command a source suggests.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Candidate {
+    pub command: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+/// Somewhere commands to search come from.
+#[async_trait]
+pub trait Provider: Send + Sync {
+    /// What its commands are badged with
+    fn name(&self) -> &str;
+
+    /// The commands it suggests when searching from `cwd`.
+    async fn candidates(&self, cwd: &str) -> Result<Vec<Candidate>>;
+}
+
+/// A source from the settings, run for its commands.
+pub struct External(pub Source);
+
+#[async_trait]
+impl Provider for External {
+    fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    async fn candidates(&self, cwd: &str) -> Result<Vec<Candidate>> {
+        let output = shell(&self.0.command)
+            .current_dir(cwd)
+            .stdin(Stdio::null())
+            .kill_on_drop(true)
+            .output();
+        let output = tokio::time::timeout(TIMEOUT, output)
+            .await
+            .wrap_err_with(|| format!("`{}` took too long", self.0.command))?
+            .wrap_err_with(|| format!("could not run `{}`", self.0.command))?;
+
+        if !output.status.success() {
+            bail!(
+                "`{}` failed with {}: {}",
+                self.0.command,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        parse(&String::from_utf8_lossy(&output.stdout))
+    }
+}
+
+/// `command` run by the system's shell: `sh`, or `cmd` on Windows.
+fn shell(command: &str) -> Command {
+    let mut shell = if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C");
+        cmd
+    } else {
+        let mut sh = Command::new("sh");
+        sh.arg("-c");
+        sh
+    };
+    shell.arg(command);
+    shell
+}
+
+/// The commands in a source's output, a JSON object per line. Blank lines are skipped.
+pub fn parse(output: &str) -> Result<Vec<Candidate>> {
+    output
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line).wrap_err_with(|| format!("line {} isn't a command", i + 1))
+        })
+        .collect()
+}
+
+/// What a source printed in a directory, and when.
+#[derive(Deserialize, Serialize)]
+struct Entry {
+    fetched: i64,
+    candidates: Vec<Candidate>,
+}
+
+/// What sources printed, kept in a file per source.
+pub struct Cache {
+    dir: PathBuf,
+}
+
+impl Cache {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// The file `name` is cached in. Names can hold anything, so what's safe in a file name
+    /// is kept to recognise it by, and a hash of the whole name keeps ones like `make targets`
+    /// and `make_targets` apart.
+    fn path(&self, name: &str) -> PathBuf {
+        let safe: String = name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        let hash = xxhash_rust::xxh3::xxh3_64(name.as_bytes());
+        self.dir.join(format!("{safe}-{hash:016x}.json"))
+    }
+
+    /// What `provider` suggests from `cwd`, as it last printed if that was within `ttl`.
+    /// What it printed anywhere longer ago than that is dropped.
+    pub async fn candidates(
+        &self,
+        provider: &dyn Provider,
+        cwd: &str,
+        ttl: Duration,
+        now: OffsetDateTime,
+    ) -> Result<Vec<Candidate>> {
+        let path = self.path(provider.name());
+        let mut entries: HashMap<String, Entry> = fs_err::read_to_string(&path)
+            .ok()
+            .and_then(|cached| serde_json::from_str(&cached).ok())
+            .unwrap_or_default();
+
+        let ttl = i64::try_from(ttl.as_secs()).unwrap_or(i64::MAX);
+        if let Some(entry) = entries.get(cwd)
+            && now.unix_timestamp() - entry.fetched < ttl
+        {
+            return Ok(entry.candidates.clone());
+        }
+
+        let candidates = provider.candidates(cwd).await?;
+        // Directories not searched from lately would only be fetched again, so aren't kept
+        entries.retain(|_, entry| now.unix_timestamp() - entry.fetched < ttl);
+        entries.insert(
+            cwd.to_string(),
+            Entry {
+                fetched: now.unix_timestamp(),
+                candidates: candidates.clone(),
+            },
+        );
+
+        // Only the next search is slower if this can't be kept
+        let written = fs_err::create_dir_all(&self.dir)
+            .and_then(|()| fs_err::write(&path, serde_json::to_string(&entries)?));
+        if let Err(e) = written {
+            tracing::warn!("could not cache the commands of {}: {e}", provider.name());
+        }
+
+        Ok(candidates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    struct Counted {
+        runs: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl Provider for Counted {
+        fn name(&self) -> &str {
+            "make targets"
+        }
+
+        async fn candidates(&self, cwd: &str) -> Result<Vec<Candidate>> {
+            self.runs.fetch_add(1, Ordering::SeqCst);
+            Ok(vec![Candidate {
+                command: "make build".to_string(),
+                description: cwd.to_string(),
+            }])
+        }
+    }
+
+    #[test]
+    fn output_is_read_a_command_a_line() {
+        let output = r#"{"command": "kubectl ctx", "description": "Switch contexts"}
+
+{"command": "kubectl ns"}
+"#;
+
+        assert_eq!(
+            parse(output).unwrap(),
+            [
+                Candidate {
+                    command: "kubectl ctx".to_string(),
+                    description: "Switch contexts".to_string(),
+                },
+                Candidate {
+                    command: "kubectl ns".to_string(),
+                    description: String::new(),
+                },
+            ]
+        );
+
+        let e = parse("{\"command\": \"ls\"}\nls -la").unwrap_err();
+        assert_eq!(e.to_string(), "line 2 isn't a command");
+    }
+
+    #[tokio::test]
+    async fn output_is_cached_per_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Cache::new(dir.path().to_path_buf());
+        let provider = Counted {
+            runs: AtomicUsize::new(0),
+        };
+        let ttl = Duration::from_secs(60);
+        let now = OffsetDateTime::now_utc();
+
+        let found = cache.candidates(&provider, "/src", ttl, now).await.unwrap();
+        assert_eq!(found[0].description, "/src");
+        cache.candidates(&provider, "/src", ttl, now).await.unwrap();
+        assert_eq!(provider.runs.load(Ordering::SeqCst), 1);
+
+        cache.candidates(&provider, "/tmp", ttl, now).await.unwrap();
+        assert_eq!(provider.runs.load(Ordering::SeqCst), 2);
+
+        let later = now + time::Duration::minutes(2);
+        cache
+            .candidates(&provider, "/src", ttl, later)
+            .await
+            .unwrap();
+        assert_eq!(provider.runs.load(Ordering::SeqCst), 3);
+
+        // By then, what was printed in /tmp is stale, and gone
+        let cached = fs_err::read_to_string(cache.path(provider.name())).unwrap();
+        let entries: HashMap<String, Entry> = serde_json::from_str(&cached).unwrap();
+        assert_eq!(entries.keys().collect::<Vec<_>>(), ["/src"]);
+    }
+
+    #[test]
+    fn sources_with_similar_names_are_cached_apart() {
+        let cache = Cache::new(PathBuf::from("/cache"));
+
+        assert_ne!(cache.path("make targets"), cache.path("make_targets"));
+        assert_eq!(cache.path("make targets"), cache.path("make targets"));
+    }
+}
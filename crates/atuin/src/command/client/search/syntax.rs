@@ -73,6 +73,15 @@ fn walk(node: tree_sitter::Node, src: &[u8], meanings: &mut [Meaning]) {
             Some(Meaning::SyntaxVariable)
         }
         "word" if src.get(node.start_byte()) == Some(&b'-') => Some(Meaning::SyntaxFlag),
+        // Fish parses the `~` of `~/notes` apart from the rest of the path
+        "home_dir_expansion" => Some(Meaning::SyntaxPath),
+        // The command itself was filled in by its parent; `./configure` stays a command
+        "word"
+            if meanings.get(node.start_byte()) != Some(&Meaning::SyntaxCommand)
+                && looks_like_path(&src[node.byte_range()]) =>
+        {
+            Some(Meaning::SyntaxPath)
+        }
         // Anonymous tokens made of operator characters: `|`, `&&`, `;`, `$(`, ...
         k if !node.is_named()
             && !k.is_empty()
@@ -114,6 +123,22 @@ fn walk(node: tree_sitter::Node, src: &[u8], meanings: &mut [Meaning]) {
     }
 }
 
+/// Whether a plain word reads as a path: anything starting at `/`, `~` or `.` with a
+/// separator, or with a separator somewhere in it. URLs aren't paths.
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn looks_like_path(word: &[u8]) -> bool {
+    if word.windows(3).any(|w| w == b"://") {
+        return false;
+    }
+
+    matches!(word, b"~" | b"." | b"..")
+        || word.starts_with(b"/")
+        || word.starts_with(b"~/")
+        || word.starts_with(b"./")
+        || word.starts_with(b"../")
+        || word.contains(&b'/')
+}
+
 #[cfg(all(test, any(target_os = "linux", target_os = "macos")))]
 mod tests {
     use super::{Meaning, classify};
@@ -129,6 +154,7 @@ mod tests {
                 Meaning::SyntaxOperator => 'o',
                 Meaning::SyntaxVariable => 'v',
                 Meaning::SyntaxComment => '#',
+                Meaning::SyntaxPath => 'p',
                 _ => 'a',
             })
             .collect()
@@ -174,6 +200,18 @@ mod tests {
         assert_eq!(render("echo foo#bar"), "ccccaaaaaaaa");
     }
 
+    #[test]
+    fn paths() {
+        assert_eq!(render("cat /etc/hosts"), "cccapppppppppp");
+        assert_eq!(render("cd ~/code ../x ."), "ccappppppappppap");
+        assert_eq!(render("vim src/main.rs"), "cccappppppppppp");
+        assert_eq!(
+            render("./configure --prefix=/usr"),
+            "cccccccccccafffffffffffff"
+        );
+        assert_eq!(render("curl https://atuin.sh"), "ccccaaaaaaaaaaaaaaaaa");
+    }
+
     #[test]
     fn fish_uses_the_fish_grammar() {
         assert_eq!(render_fish("set -x PATH $PATH"), "cccaffaaaaaavvvvv");
@@ -182,6 +220,7 @@ mod tests {
             "ccccaoccccoaoaccccaaaa"
         );
         assert_eq!(render_fish(r#"echo "hi $name""#), "ccccassssvvvvvs");
+        assert_eq!(render_fish("cat ~/notes /tmp/x"), "cccapppppppapppppp");
     }
 
     #[test]
@@ -0,0 +1,300 @@
+//! The tasks of the project searched from: the targets of its Makefile, the recipes of its
+//! justfile and the scripts of its package.json, each a command to run it.
+
+use std::path::Path;
+
+use async_trait::async_trait;
+use eyre::{Result, WrapErr};
+use serde_json::Value;
+
+use super::sources::{Candidate, Provider};
+
+/// A kind of file a project keeps its tasks in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tasks {
+    Make,
+    Just,
+    Package,
+}
+
+impl Tasks {
+    pub const ALL: [Self; 3] = [Self::Make, Self::Just, Self::Package];
+
+    /// What the file can be called, in the order the tool looks for them.
+    fn files(self) -> &'static [&'static str] {
+        match self {
+            Self::Make => &["GNUmakefile", "makefile", "Makefile"],
+            Self::Just => &["justfile", "Justfile", ".justfile"],
+            Self::Package => &["package.json"],
+        }
+    }
+}
+
+#[async_trait]
+impl Provider for Tasks {
+    fn name(&self) -> &str {
+        match self {
+            Self::Make => "make",
+            Self::Just => "just",
+            Self::Package => "package",
+        }
+    }
+
+    async fn candidates(&self, cwd: &str) -> Result<Vec<Candidate>> {
+        let dir = Path::new(cwd);
+        let Some(path) = self
+            .files()
+            .iter()
+            .map(|file| dir.join(file))
+            .find(|path| path.is_file())
+        else {
+            return Ok(Vec::new());
+        };
+        let contents = tokio::fs::read_to_string(&path)
+            .await
+            .wrap_err_with(|| format!("could not read {}", path.display()))?;
+
+        match self {
+            Self::Make => Ok(make_targets(&contents)),
+            Self::Just => Ok(just_recipes(&contents)),
+            Self::Package => package_scripts(&contents, package_runner(dir))
+                .wrap_err_with(|| format!("could not parse {}", path.display())),
+        }
+    }
+}
+
+fn candidate(command: String, description: impl Into<String>) -> Candidate {
+    Candidate {
+        command,
+        description: description.into(),
+    }
+}
+
+/// The text of a comment line, or `None` for any other line.
+fn comment(line: &str) -> Option<&str> {
+    line.strip_prefix('#')
+        .map(|text| text.trim_start_matches('#').trim())
+}
+
+/// The targets a Makefile names, skipping special targets like `.PHONY`, pattern rules and
+/// those built from variables. Each is described by a `##` comment after it, or the comment
+/// on the line before.
+pub fn make_targets(makefile: &str) -> Vec<Candidate> {
+    let mut targets: Vec<Candidate> = Vec::new();
+    let mut above = None;
+
+    for line in makefile.lines() {
+        if let Some(text) = comment(line) {
+            above = Some(text);
+            continue;
+        }
+        let doc = above.take();
+
+        // Recipes are indented
+        if line.starts_with([' ', '\t']) {
+            continue;
+        }
+        let (line, trailing) = line
+            .split_once("##")
+            .map_or((line, None), |(line, doc)| (line, Some(doc.trim())));
+        let Some((names, rest)) = line.split_once(':') else {
+            continue;
+        };
+        // Variables are set with `=`, `:=` or `::=`
+        if rest.starts_with([':', '=']) || names.contains('=') {
+            continue;
+        }
+
+        for name in names.split_whitespace() {
+            if name.starts_with('.')
+                || name.contains(['%', '$'])
+                || targets.iter().any(|t| t.command == format!("make {name}"))
+            {
+                continue;
+            }
+            targets.push(candidate(
+                format!("make {name}"),
+                trailing.or(doc).unwrap_or_default(),
+            ));
+        }
+    }
+
+    targets
+}
+
+/// The recipes a justfile has, leaving out private ones. Each is described by the comment on
+/// the line before it, as `just --list` does.
+pub fn just_recipes(justfile: &str) -> Vec<Candidate> {
+    let mut recipes = Vec::new();
+    let mut above = None;
+    let mut private = false;
+
+    for line in justfile.lines() {
+        if let Some(text) = comment(line) {
+            above = Some(text);
+            continue;
+        }
+        // Attributes go between a recipe's comment and the recipe
+        if let Some(attribute) = line.strip_prefix('[') {
+            private |= attribute.starts_with("private");
+            continue;
+        }
+        let doc = above.take();
+        let is_private = std::mem::take(&mut private);
+
+        if line.starts_with([' ', '\t']) || line.contains(":=") {
+            continue;
+        }
+        let Some((head, _)) = line.split_once(':') else {
+            continue;
+        };
+        let mut words = head.trim_start_matches('@').split_whitespace();
+        let Some(name) = words.next() else {
+            continue;
+        };
+        if matches!(name, "alias" | "export" | "import" | "mod" | "set")
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+            || name.starts_with('_')
+            || is_private
+        {
+            continue;
+        }
+
+        recipes.push(candidate(format!("just {name}"), doc.unwrap_or_default()));
+    }
+
+    recipes
+}
+
+/// How a project's package scripts are run, going by its lockfile.
+fn package_runner(dir: &Path) -> &'static str {
+    [
+        ("pnpm-lock.yaml", "pnpm run"),
+        ("yarn.lock", "yarn run"),
+        ("bun.lock", "bun run"),
+        ("bun.lockb", "bun run"),
+    ]
+    .into_iter()
+    .find(|(lockfile, _)| dir.join(lockfile).is_file())
+    .map_or("npm run", |(_, runner)| runner)
+}
+
+/// The scripts in a package.json, run with `runner`, each described by what it runs.
+pub fn package_scripts(package: &str, runner: &str) -> Result<Vec<Candidate>> {
+    let package: Value = serde_json::from_str(package)?;
+    let Some(scripts) = package.get("scripts").and_then(Value::as_object) else {
+        return Ok(Vec::new());
+    };
+
+    Ok(scripts
+        .iter()
+        .map(|(name, script)| {
+            candidate(
+                format!("{runner} {name}"),
+                script.as_str().unwrap_or_default(),
+            )
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commands(candidates: &[Candidate]) -> Vec<(&str, &str)> {
+        candidates
+            .iter()
+            .map(|c| (c.command.as_str(), c.description.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn makefile_targets_are_found() {
+        let makefile = "\
+CARGO := cargo
+PREFIX = /usr/local
+
+.PHONY: build test
+
+# Build everything
+build: deps
+\t$(CARGO) build
+
+test: build ## Run the tests
+\t$(CARGO) test
+
+%.o: %.c
+\tcc -c $<
+
+$(PREFIX)/bin/atuin: build
+install lint:
+";
+
+        assert_eq!(
+            commands(&make_targets(makefile)),
+            [
+                ("make build", "Build everything"),
+                ("make test", "Run the tests"),
+                ("make install", ""),
+                ("make lint", ""),
+            ]
+        );
+    }
+
+    #[test]
+    fn justfile_recipes_are_found() {
+        let justfile = "\
+set shell := [\"bash\", \"-c\"]
+version := `git describe`
+alias b := build
+
+# Build the binary
+build:
+    cargo build
+
+[private]
+helper:
+    echo hidden
+
+_setup:
+    echo hidden
+
+# Cut a release
+[confirm]
+@release bump='patch': build
+    cargo release {{bump}}
+";
+
+        assert_eq!(
+            commands(&just_recipes(justfile)),
+            [
+                ("just build", "Build the binary"),
+                ("just release", "Cut a release")
+            ]
+        );
+    }
+
+    #[test]
+    fn package_scripts_are_found() {
+        let package = r#"{
+            "name": "web",
+            "scripts": { "dev": "vite", "build": "vite build" }
+        }"#;
+
+        let scripts = package_scripts(package, "pnpm run").unwrap();
+        let mut found = commands(&scripts);
+        found.sort_unstable();
+        assert_eq!(
+            found,
+            [("pnpm run build", "vite build"), ("pnpm run dev", "vite")]
+        );
+        assert!(
+            package_scripts(r#"{"name": "web"}"#, "npm run")
+                .unwrap()
+                .is_empty()
+        );
+        assert!(package_scripts("{", "npm run").is_err());
+    }
+}
@@ -0,0 +1,310 @@
+//! Commands saved with named fields, like `git switch {{branch}}`, which are asked for before
+//! the command is returned.
+//!
+//! A field is a name of letters, digits, `_` and `-` in double braces, optionally followed by
+//! `:` and a default: `{{port:8080}}`. Anything else in braces, such as the `{{.State}}` of a
+//! `docker inspect --format`, is left as it is.
+
+use ratatui::{
+    Frame,
+    backend::FromCrossterm,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Clear, Paragraph},
+};
+use unicode_width::UnicodeWidthStr;
+
+use super::block_ext::themed_block;
+use crate::command::client::theme::{Meaning, Theme};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Text(String),
+    /// An index into [`Template::fields`].
+    Field(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    pub name: String,
+    pub default: Option<String>,
+}
+
+/// A command split into its text and fields. A field used more than once is asked for once.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    segments: Vec<Segment>,
+    fields: Vec<Field>,
+}
+
+impl Template {
+    /// `None` if `command` has no fields.
+    pub fn parse(command: &str) -> Option<Self> {
+        let mut segments = Vec::new();
+        let mut fields: Vec<Field> = Vec::new();
+        let mut text = String::new();
+        let mut rest = command;
+
+        while let Some(open) = rest.find("{{") {
+            let after = &rest[open + 2..];
+            let Some((field, len)) = after.find("}}").and_then(|close| {
+                let field = parse_field(&after[..close])?;
+                Some((field, close + 2))
+            }) else {
+                text.push_str(&rest[..open + 2]);
+                rest = after;
+                continue;
+            };
+
+            text.push_str(&rest[..open]);
+            if !text.is_empty() {
+                segments.push(Segment::Text(std::mem::take(&mut text)));
+            }
+
+            let index = match fields.iter().position(|f| f.name == field.name) {
+                Some(index) => {
+                    // The first default given for a field is the one used
+                    if fields[index].default.is_none() {
+                        fields[index].default = field.default;
+                    }
+                    index
+                }
+                None => {
+                    fields.push(field);
+                    fields.len() - 1
+                }
+            };
+            segments.push(Segment::Field(index));
+            rest = &after[len..];
+        }
+        text.push_str(rest);
+        if !text.is_empty() {
+            segments.push(Segment::Text(text));
+        }
+
+        (!fields.is_empty()).then_some(Self { segments, fields })
+    }
+
+    pub fn fields(&self) -> &[Field] {
+        &self.fields
+    }
+
+    /// The command with each field replaced by its value in `values`, in the order of
+    /// [`Self::fields`].
+    pub fn expand(&self, values: &[String]) -> String {
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Text(text) => text.as_str(),
+                Segment::Field(index) => values.get(*index).map_or("", String::as_str),
+            })
+            .collect()
+    }
+}
+
+/// The inside of `{{...}}`, if it's a field.
+fn parse_field(inner: &str) -> Option<Field> {
+    let (name, default) = match inner.split_once(':') {
+        Some((name, default)) => (name.trim(), Some(default.to_string())),
+        None => (inner.trim(), None),
+    };
+
+    let mut chars = name.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-'));
+
+    valid.then(|| Field {
+        name: name.to_string(),
+        default,
+    })
+}
+
+/// Asking for the value of each field of an accepted template in turn.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplatePrompt {
+    template: Template,
+    values: Vec<String>,
+    current: usize,
+}
+
+impl TemplatePrompt {
+    pub fn new(template: Template) -> Self {
+        let values = vec![String::new(); template.fields.len()];
+        Self {
+            template,
+            values,
+            current: 0,
+        }
+    }
+
+    pub fn field(&self) -> &Field {
+        &self.template.fields[self.current]
+    }
+
+    /// What's been typed for the field being asked for.
+    pub fn value(&self) -> &str {
+        &self.values[self.current]
+    }
+
+    /// Which field is being asked for, counting from 1, and how many there are.
+    pub fn position(&self) -> (usize, usize) {
+        (self.current + 1, self.values.len())
+    }
+
+    pub fn insert(&mut self, c: char) {
+        self.values[self.current].push(c);
+    }
+
+    pub fn backspace(&mut self) {
+        self.values[self.current].pop();
+    }
+
+    /// Move to the next field, returning false if this was the last.
+    pub fn next(&mut self) -> bool {
+        if self.current + 1 == self.values.len() {
+            return false;
+        }
+        self.current += 1;
+        true
+    }
+
+    pub fn previous(&mut self) {
+        self.current = self.current.saturating_sub(1);
+    }
+
+    /// The command, with the fields left empty filled with their defaults.
+    pub fn command(&self) -> String {
+        let values: Vec<String> = self
+            .values
+            .iter()
+            .zip(&self.template.fields)
+            .map(|(value, field)| match &field.default {
+                Some(default) if value.is_empty() => default.clone(),
+                _ => value.clone(),
+            })
+            .collect();
+        self.template.expand(&values)
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn draw(&self, f: &mut Frame, area: Rect, theme: &Theme) {
+        let field = self.field();
+        let (n, total) = self.position();
+        let label = format!("{}: ", field.name);
+        let hint = field
+            .default
+            .as_deref()
+            .filter(|_| self.value().is_empty())
+            .unwrap_or_default();
+        let command = self.command();
+
+        let width = [
+            UnicodeWidthStr::width(command.as_str()),
+            label.len() + UnicodeWidthStr::width(self.value()).max(hint.len()),
+            30,
+        ]
+        .into_iter()
+        .max()
+        .unwrap_or_default()
+            + 4;
+        let [area] =
+            Layout::horizontal([Constraint::Length(width.min(area.width as usize) as u16)])
+                .flex(Flex::Center)
+                .areas(area);
+        let [area] = Layout::vertical([Constraint::Length(4)])
+            .flex(Flex::Center)
+            .areas(area);
+
+        let annotation = Style::from_crossterm(theme.as_style(Meaning::Annotation));
+        let lines = vec![
+            Line::from(vec![
+                Span::styled(label.clone(), Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(self.value().to_string()),
+                Span::styled(hint.to_string(), annotation),
+            ]),
+            Line::styled(command, annotation),
+        ];
+        let prompt = Paragraph::new(lines)
+            .style(Style::from_crossterm(theme.get_base()))
+            .block(themed_block(theme).title(format!(" {n}/{total} ")));
+
+        f.render_widget(Clear, area);
+        f.render_widget(prompt, area);
+
+        let cursor = label.len() + UnicodeWidthStr::width(self.value());
+        f.set_cursor_position((
+            (area.x + 1 + cursor as u16).min(area.right().saturating_sub(2)),
+            area.y + 1,
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fields_are_found_once_each() {
+        let template =
+            Template::parse("git push {{ remote:origin }} {{branch}}:{{branch}}").unwrap();
+
+        assert_eq!(
+            template.fields(),
+            [
+                Field {
+                    name: "remote".to_string(),
+                    default: Some("origin".to_string()),
+                },
+                Field {
+                    name: "branch".to_string(),
+                    default: None,
+                },
+            ]
+        );
+        assert_eq!(
+            template.expand(&["upstream".to_string(), "main".to_string()]),
+            "git push upstream main:main"
+        );
+    }
+
+    #[test]
+    fn other_braces_are_left_alone() {
+        assert_eq!(Template::parse("cargo build"), None);
+        assert_eq!(
+            Template::parse("docker inspect -f '{{.State.Status}}' web"),
+            None
+        );
+        assert_eq!(Template::parse("echo {{ json . }} {{"), None);
+
+        let template = Template::parse("docker inspect -f '{{.Id}}' {{container}}").unwrap();
+        assert_eq!(
+            template.expand(&["web".to_string()]),
+            "docker inspect -f '{{.Id}}' web"
+        );
+    }
+
+    #[test]
+    fn the_prompt_fills_in_each_field() {
+        let mut prompt =
+            TemplatePrompt::new(Template::parse("ssh {{user:root}}@{{host}}").unwrap());
+
+        assert_eq!(prompt.field().name, "user");
+        assert!(prompt.next());
+        for c in "db1".chars() {
+            prompt.insert(c);
+        }
+        prompt.insert('x');
+        prompt.backspace();
+        assert_eq!(prompt.position(), (2, 2));
+        assert!(!prompt.next());
+
+        assert_eq!(prompt.command(), "ssh root@db1");
+
+        prompt.previous();
+        prompt.insert('a');
+        assert_eq!(prompt.command(), "ssh a@db1");
+    }
+}
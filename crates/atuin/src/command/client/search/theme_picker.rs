@@ -0,0 +1,195 @@
+use std::sync::Arc;
+
+use atuin_client::{
+    settings::Settings,
+    theme::{ColorSupport, ThemeManager},
+};
+use ratatui::{
+    Frame,
+    backend::FromCrossterm,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::Style,
+    text::Line,
+    widgets::{Clear, List, ListItem, ListState},
+};
+
+use super::block_ext::themed_block;
+use crate::command::client::theme::{Meaning, Theme};
+
+/// Choosing a theme from inside the search, with each one previewed on the screen behind the
+/// list as it's selected.
+///
+/// The picker stays around once closed, holding the theme in use, so a theme chosen earlier in
+/// the session is the one to go back to on cancel.
+pub struct ThemePicker {
+    themes: ThemeManager,
+    max_depth: Option<u8>,
+    names: Vec<String>,
+    selected: usize,
+    /// The theme in use, selected before the picker was last opened.
+    chosen: usize,
+    preview: Arc<Theme>,
+    open: bool,
+}
+
+impl ThemePicker {
+    pub fn new(settings: &Settings) -> Self {
+        let mut themes = ThemeManager::new(settings.theme.debug, None)
+            .with_color_support(ColorSupport::detect());
+        let mut names = themes.available_themes();
+        let chosen = names
+            .iter()
+            .position(|name| *name == settings.theme.name)
+            .unwrap_or_else(|| {
+                names.push(settings.theme.name.clone());
+                names.len() - 1
+            });
+        let preview = Arc::new(
+            themes
+                .load_theme(&settings.theme.name, settings.theme.max_depth)
+                .clone(),
+        );
+
+        Self {
+            themes,
+            max_depth: settings.theme.max_depth,
+            names,
+            selected: chosen,
+            chosen,
+            preview,
+            open: false,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn open(&mut self) {
+        self.open = true;
+    }
+
+    /// The theme to draw with: the one being previewed, or the one chosen.
+    pub fn theme(&self) -> Arc<Theme> {
+        self.preview.clone()
+    }
+
+    pub fn selected_name(&self) -> &str {
+        &self.names[self.selected]
+    }
+
+    /// Move the selection by `offset`, wrapping round, and preview the theme there.
+    pub fn select(&mut self, offset: isize) {
+        let len = self.names.len();
+        self.selected = (self.selected + len).wrapping_add_signed(offset) % len;
+        self.load_selected();
+    }
+
+    /// Keep the selected theme, and close.
+    pub fn choose(&mut self) {
+        self.chosen = self.selected;
+        self.open = false;
+    }
+
+    /// Go back to the theme in use before the picker opened, and close.
+    pub fn cancel(&mut self) {
+        if self.selected != self.chosen {
+            self.selected = self.chosen;
+            self.load_selected();
+        }
+        self.open = false;
+    }
+
+    /// Read the selected theme again, as its file has changed.
+    pub fn reload(&mut self) {
+        self.themes.forget_loaded();
+        self.load_selected();
+    }
+
+    fn load_selected(&mut self) {
+        let name = &self.names[self.selected];
+        self.preview = Arc::new(self.themes.load_theme(name, self.max_depth).clone());
+    }
+
+    pub fn draw(&self, f: &mut Frame, area: Rect) {
+        let theme = &self.preview;
+        let width = self
+            .names
+            .iter()
+            .map(|name| name.chars().count())
+            .max()
+            .unwrap_or_default()
+            .max(" Theme ".len())
+            + 4;
+        let [area] =
+            Layout::horizontal([Constraint::Length(u16::try_from(width).unwrap_or(u16::MAX))])
+                .flex(Flex::Center)
+                .areas(area);
+        let [area] = Layout::vertical([Constraint::Length(
+            u16::try_from(self.names.len() + 2).unwrap_or(u16::MAX),
+        )])
+        .flex(Flex::Center)
+        .areas(area);
+
+        let items: Vec<_> = self
+            .names
+            .iter()
+            .map(|name| ListItem::new(Line::from(format!(" {name}"))))
+            .collect();
+        let list = List::new(items)
+            .style(Style::from_crossterm(theme.get_base()))
+            .highlight_style(Style::from_crossterm(theme.as_style(Meaning::Selection)))
+            .block(themed_block(theme).title(" Theme "));
+
+        let mut state = ListState::default().with_selected(Some(self.selected));
+        f.render_widget(Clear, area);
+        f.render_stateful_widget(list, area, &mut state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cancelling_goes_back_to_the_chosen_theme() {
+        let mut settings = Settings::utc();
+        settings.theme.name = "default".to_string();
+        let mut picker = ThemePicker::new(&settings);
+        let default = picker.selected_name().to_string();
+
+        picker.open();
+        picker.select(1);
+        let previewed = picker.selected_name().to_string();
+        assert_ne!(previewed, default);
+        assert_eq!(
+            picker.theme().name,
+            picker.themes.load_theme(&previewed, None).name
+        );
+
+        picker.cancel();
+        assert!(!picker.is_open());
+        assert_eq!(picker.selected_name(), default);
+
+        picker.open();
+        picker.select(-1);
+        picker.choose();
+        picker.open();
+        picker.select(1);
+        picker.cancel();
+        assert_ne!(picker.selected_name(), default);
+    }
+
+    #[test]
+    fn selection_wraps() {
+        let settings = Settings::utc();
+        let mut picker = ThemePicker::new(&settings);
+        let len = picker.names.len();
+
+        picker.selected = 0;
+        picker.select(-1);
+        assert_eq!(picker.selected, len - 1);
+        picker.select(1);
+        assert_eq!(picker.selected, 0);
+    }
+}
@@ -0,0 +1,177 @@
+//! Links in commands, such as the dashboard a `curl` was pointed at, to open in the browser or
+//! copy from the search.
+
+use std::process::{Command, Stdio};
+use std::sync::LazyLock;
+
+use eyre::{Result, WrapErr};
+use ratatui::{Frame, layout::Rect};
+use regex::Regex;
+
+use super::list_picker::ListPicker;
+use crate::command::client::theme::Theme;
+
+static URL_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"\b(?:https?|ftp|file)://[^\s'"<>`]+"#).expect("URL pattern is valid")
+});
+
+/// The URLs in `command`, in order, each once. Punctuation after a URL, like the full stop
+/// of a sentence or the bracket closing around it, isn't part of it.
+pub fn find(command: &str) -> Vec<String> {
+    let mut urls: Vec<String> = Vec::new();
+    for found in URL_RE.find_iter(command) {
+        let url = trim_end(found.as_str());
+        if !urls.iter().any(|u| u == url) {
+            urls.push(url.to_string());
+        }
+    }
+    urls
+}
+
+fn trim_end(mut url: &str) -> &str {
+    loop {
+        let trimmed = url.trim_end_matches(['.', ',', ';', ':', '!', '?']);
+        // Brackets the URL opened are part of it, as in a wiki link to `Rust_(language)`
+        let trimmed = match trimmed.chars().last() {
+            Some(close @ (')' | ']' | '}')) => {
+                let open = match close {
+                    ')' => '(',
+                    ']' => '[',
+                    _ => '{',
+                };
+                if trimmed.matches(open).count() < trimmed.matches(close).count() {
+                    &trimmed[..trimmed.len() - 1]
+                } else {
+                    trimmed
+                }
+            }
+            _ => trimmed,
+        };
+
+        if trimmed == url {
+            return url;
+        }
+        url = trimmed;
+    }
+}
+
+/// The program that opens a URL in the default browser, and the arguments it needs first.
+fn opener() -> (&'static str, &'static [&'static str]) {
+    if cfg!(target_os = "macos") {
+        ("open", &[])
+    } else if cfg!(windows) {
+        ("rundll32", &["url.dll,FileProtocolHandler"])
+    } else {
+        ("xdg-open", &[])
+    }
+}
+
+/// Open `url` in the default browser, without waiting for it.
+pub fn open(url: &str) -> Result<()> {
+    let (program, args) = opener();
+    Command::new(program)
+        .args(args)
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .wrap_err_with(|| format!("failed to run {program}"))?;
+
+    Ok(())
+}
+
+/// What's done with the URL picked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UrlUse {
+    Open,
+    Copy,
+}
+
+/// Choosing one of the URLs of a command that has several.
+pub struct UrlPicker {
+    list: ListPicker,
+    then: UrlUse,
+}
+
+impl UrlPicker {
+    pub fn new(urls: Vec<String>, then: UrlUse) -> Self {
+        let title = match then {
+            UrlUse::Open => "Open",
+            UrlUse::Copy => "Copy",
+        };
+        Self {
+            list: ListPicker::new(title, urls),
+            then,
+        }
+    }
+
+    /// Move the selection by `offset`, wrapping round.
+    pub fn select(&mut self, offset: isize) {
+        self.list.select(offset);
+    }
+
+    pub fn url(&self) -> &str {
+        self.list.selected()
+    }
+
+    pub fn then(&self) -> UrlUse {
+        self.then
+    }
+
+    pub fn draw(&self, f: &mut Frame, area: Rect, theme: &Theme) {
+        self.list.draw(f, area, theme);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn urls_are_found() {
+        assert_eq!(
+            find(
+                "curl -H 'Accept: application/json' 'https://grafana.example.com/d/abc?from=now-7d&to=now'"
+            ),
+            ["https://grafana.example.com/d/abc?from=now-7d&to=now"]
+        );
+        assert_eq!(
+            find(
+                "git clone http://example.com/a.git && open https://example.com/a http://example.com/a.git"
+            ),
+            ["http://example.com/a.git", "https://example.com/a"]
+        );
+        assert!(find("ssh example.com").is_empty());
+        assert!(find("echo https://").is_empty());
+    }
+
+    #[test]
+    fn trailing_punctuation_is_dropped() {
+        assert_eq!(
+            find("echo \"see (https://example.com/docs).\""),
+            ["https://example.com/docs"]
+        );
+        assert_eq!(
+            find("xdg-open https://en.wikipedia.org/wiki/Rust_(language),"),
+            ["https://en.wikipedia.org/wiki/Rust_(language)"]
+        );
+    }
+
+    #[test]
+    fn the_picker_wraps_round() {
+        let mut picker = UrlPicker::new(
+            vec![
+                "https://a.example".to_string(),
+                "https://b.example".to_string(),
+            ],
+            UrlUse::Copy,
+        );
+        assert_eq!(picker.url(), "https://a.example");
+        picker.select(-1);
+        assert_eq!(picker.url(), "https://b.example");
+        picker.select(1);
+        assert_eq!(picker.url(), "https://a.example");
+        assert_eq!(picker.then(), UrlUse::Copy);
+    }
+}
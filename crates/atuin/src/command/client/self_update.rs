@@ -0,0 +1,264 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use clap::Args;
+use eyre::{Context, Result, bail};
+use semver::Version;
+use time::OffsetDateTime;
+
+use atuin_client::{settings::Settings, update};
+
+/// The platform this binary was built for, as its release archive is named
+const TARGET: &str = env!("ATUIN_TARGET");
+
+/// Update Atuin to the latest release
+#[derive(Args, Debug)]
+pub struct Cmd {
+    /// Only check for a newer release. If there is one, print its version; otherwise exit with
+    /// status 1. Checks at most once an hour, so it's quick enough to run from a prompt
+    #[arg(long)]
+    check: bool,
+
+    /// Install the latest release even if it isn't newer than this one
+    #[arg(long, conflicts_with = "check")]
+    force: bool,
+}
+
+/// What else installed this binary, and so should be the one to update it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PackageManager {
+    Cargo,
+    Homebrew,
+    MacPorts,
+    Nix,
+    Scoop,
+    System,
+}
+
+impl PackageManager {
+    fn detect(exe: &Path) -> Option<Self> {
+        let path = exe.to_string_lossy().replace('\\', "/");
+
+        if path.starts_with("/nix/store/") {
+            Some(Self::Nix)
+        } else if path.contains("/Cellar/")
+            || path.starts_with("/opt/homebrew/")
+            || path.starts_with("/home/linuxbrew/")
+        {
+            Some(Self::Homebrew)
+        } else if path.starts_with("/opt/local/") {
+            Some(Self::MacPorts)
+        } else if path.contains("/.cargo/bin/") {
+            Some(Self::Cargo)
+        } else if path.contains("/scoop/apps/") {
+            Some(Self::Scoop)
+        } else if (path.starts_with("/usr/") && !path.starts_with("/usr/local/"))
+            || path.starts_with("/bin/")
+        {
+            Some(Self::System)
+        } else {
+            None
+        }
+    }
+
+    fn how_to_update(self) -> &'static str {
+        match self {
+            Self::Cargo => "installed with cargo; update it with `cargo install --locked atuin`",
+            Self::Homebrew => "installed with Homebrew; update it with `brew upgrade atuin`",
+            Self::MacPorts => "installed with MacPorts; update it with `sudo port upgrade atuin`",
+            Self::Nix => "installed with Nix; update it through your Nix configuration",
+            Self::Scoop => "installed with Scoop; update it with `scoop update atuin`",
+            Self::System => "installed by your system; update it with your package manager",
+        }
+    }
+}
+
+impl Cmd {
+    pub async fn run(self, settings: &Settings) -> Result<()> {
+        let current = Version::parse(env!("CARGO_PKG_VERSION"))?;
+
+        if self.check {
+            let Some(latest) = newer_version(settings, &current).await? else {
+                std::process::exit(1);
+            };
+
+            println!("{latest}");
+            return Ok(());
+        }
+
+        let exe = std::env::current_exe()
+            .and_then(fs_err::canonicalize)
+            .wrap_err("could not locate the atuin executable")?;
+
+        if let Some(manager) = PackageManager::detect(&exe) {
+            bail!("atuin at {} was {}", exe.display(), manager.how_to_update());
+        }
+
+        if cfg!(windows) {
+            bail!(
+                "atuin can't update itself on Windows yet; run the installer again to update it: https://docs.atuin.sh/guide/installation/"
+            );
+        }
+
+        let release = update::latest_release(settings).await?;
+        let latest = release.version()?;
+        save_latest_version(&latest).await?;
+
+        if latest <= current && !self.force {
+            println!("atuin {current} is up to date");
+            return Ok(());
+        }
+
+        println!("Downloading atuin {latest}...");
+        let binary = update::download(settings, &release, TARGET).await?;
+
+        replace(&exe, &binary, &latest)?;
+
+        println!("Updated atuin {current} → {latest}");
+        if let Some(url) = &release.html_url {
+            println!("Release notes: {url}");
+        }
+        if settings.daemon.enabled {
+            println!("Run `atuin daemon restart` to start the new version of the daemon");
+        }
+
+        Ok(())
+    }
+}
+
+/// The latest version if it's newer than `current`, looked up at most once an hour
+async fn newer_version(settings: &Settings, current: &Version) -> Result<Option<Version>> {
+    let last_check = Settings::last_version_check().await?;
+    let cached = Settings::meta_store()
+        .await?
+        .latest_version()
+        .await?
+        .and_then(|v| Version::parse(&v).ok());
+
+    let latest = match cached {
+        Some(latest) if (OffsetDateTime::now_utc() - last_check).whole_hours() < 1 => latest,
+        _ => {
+            let latest = update::latest_release(settings).await?.version()?;
+            save_latest_version(&latest).await?;
+            latest
+        }
+    };
+
+    Ok((latest > *current).then_some(latest))
+}
+
+async fn save_latest_version(latest: &Version) -> Result<()> {
+    Settings::save_version_check_time().await?;
+    Settings::meta_store()
+        .await?
+        .save_latest_version(&latest.to_string())
+        .await
+}
+
+/// Swap `exe` for `binary` in one step, so there's always a working atuin there: write it next
+/// to `exe`, check it runs and is the version we expect, then rename it over `exe`.
+fn replace(exe: &Path, binary: &[u8], version: &Version) -> Result<()> {
+    let dir = exe
+        .parent()
+        .map_or_else(|| PathBuf::from("."), Path::to_path_buf);
+
+    let mut new = tempfile::Builder::new()
+        .prefix(".atuin-update")
+        .tempfile_in(&dir)
+        .wrap_err_with(|| format!("could not write to {}", dir.display()))?;
+    new.write_all(binary)?;
+    new.as_file().sync_all()?;
+
+    // Close it, as it can't be run while it's open for writing
+    let new = new.into_temp_path();
+
+    let permissions = fs_err::metadata(exe)?.permissions();
+    fs_err::set_permissions(&new, permissions)?;
+
+    let output = std::process::Command::new(&new)
+        .arg("--version")
+        .output()
+        .wrap_err("the downloaded atuin doesn't run")?;
+    // `atuin 18.19.0 (<commit>)`
+    let reported = String::from_utf8_lossy(&output.stdout);
+    let reported_version = reported.split_whitespace().nth(1);
+    if !output.status.success() || reported_version != Some(&version.to_string()) {
+        bail!("the downloaded atuin is not {version}: it says {reported:?}");
+    }
+
+    new.persist(exe)
+        .wrap_err_with(|| format!("could not replace {}", exe.display()))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use rstest::rstest;
+
+    use super::PackageManager;
+
+    #[rstest]
+    #[case(
+        "/nix/store/0zbff7h1vbhwb7k8pmr7y5kscz0m9b0j-atuin-18.4.0/bin/atuin",
+        Some(PackageManager::Nix)
+    )]
+    #[case(
+        "/opt/homebrew/Cellar/atuin/18.4.0/bin/atuin",
+        Some(PackageManager::Homebrew)
+    )]
+    #[case(
+        "/usr/local/Cellar/atuin/18.4.0/bin/atuin",
+        Some(PackageManager::Homebrew)
+    )]
+    #[case(
+        "/home/linuxbrew/.linuxbrew/Cellar/atuin/18.4.0/bin/atuin",
+        Some(PackageManager::Homebrew)
+    )]
+    #[case("/opt/local/bin/atuin", Some(PackageManager::MacPorts))]
+    #[case("/home/ellie/.cargo/bin/atuin", Some(PackageManager::Cargo))]
+    #[case(
+        "C:\\Users\\ellie\\scoop\\apps\\atuin\\current\\atuin.exe",
+        Some(PackageManager::Scoop)
+    )]
+    #[case("/usr/bin/atuin", Some(PackageManager::System))]
+    #[case("/home/ellie/.atuin/bin/atuin", None)]
+    #[case("/usr/local/bin/atuin", None)]
+    fn detects_package_managers(#[case] exe: &str, #[case] expected: Option<PackageManager>) {
+        assert_eq!(PackageManager::detect(Path::new(exe)), expected);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn replaces_the_binary_only_with_the_expected_version() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let exe = dir.path().join("atuin");
+        fs_err::write(&exe, "#!/bin/sh\necho atuin 18.0.0\n").unwrap();
+        fs_err::set_permissions(&exe, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let version = semver::Version::new(18, 19, 0);
+
+        let wrong = b"#!/bin/sh\necho atuin 17.0.0\n";
+        assert!(super::replace(&exe, wrong, &version).is_err());
+        assert!(
+            fs_err::read_to_string(&exe)
+                .unwrap()
+                .contains("atuin 18.0.0")
+        );
+
+        let right = b"#!/bin/sh\necho atuin 18.19.0 \\(abc123\\)\n";
+        super::replace(&exe, right, &version).unwrap();
+        assert_eq!(fs_err::read(&exe).unwrap(), right);
+        assert_eq!(
+            fs_err::metadata(&exe).unwrap().permissions().mode() & 0o777,
+            0o755
+        );
+
+        // Nothing is left behind
+        assert_eq!(fs_err::read_dir(dir.path()).unwrap().count(), 1);
+    }
+}
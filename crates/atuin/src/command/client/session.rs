@@ -0,0 +1,177 @@
+use std::collections::{BTreeMap, HashMap};
+
+use clap::Subcommand;
+use eyre::{Context, Result, bail};
+use time::macros::format_description;
+
+use atuin_client::{
+    database::{Database, current_context, query_context},
+    encryption,
+    record::sqlite_store::SqliteStore,
+    session::SessionStore,
+    settings::{Settings, Timezone},
+};
+use atuin_history::summary::SessionSummary;
+
+use super::search::format_duration;
+
+#[derive(Subcommand, Debug)]
+#[command(infer_subcommands = true)]
+pub enum Cmd {
+    /// Name a shell session, to find its history again with `session:<name>` in a search
+    Name {
+        /// The new name, like `incident-4123`. Leave out to take the session's name away
+        name: Option<String>,
+
+        /// The ID of the session to name, rather than the current one
+        #[arg(long)]
+        session: Option<String>,
+    },
+
+    /// List the sessions that have names
+    #[command(alias = "ls")]
+    List {
+        /// Print the sessions as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Sum up what went on in a session: how long it ran, where, with what, and what failed
+    Summarize {
+        /// The session's ID or name. Leave out for the current session
+        session: Option<String>,
+
+        /// Print the summary as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+impl Cmd {
+    pub async fn run(
+        self,
+        settings: &Settings,
+        db: &impl Database,
+        store: SqliteStore,
+    ) -> Result<()> {
+        let encryption_key: [u8; 32] = encryption::load_key(settings)
+            .context("could not load encryption key")?
+            .into();
+
+        let host_id = Settings::host_id().await?;
+        let session_store = SessionStore::new(store, host_id, encryption_key);
+
+        match self {
+            Self::Name { name, session } => {
+                let session = match session {
+                    Some(session) => session,
+                    None => current_context().await?.session,
+                };
+                session_store.name(&session, name.as_deref()).await?;
+
+                // Searches filter by the copy in the history database, so it can't wait for a sync
+                session_store.build(db).await?;
+
+                match name
+                    .as_deref()
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                {
+                    Some(name) => println!("Named session {session} {name}"),
+                    None => println!("Cleared the name of session {session}"),
+                }
+
+                Ok(())
+            }
+
+            Self::List { json } => {
+                let current = query_context().await?.session;
+                let names: BTreeMap<String, String> =
+                    session_store.names().await?.into_iter().collect();
+
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&names)?);
+                } else {
+                    for (session, name) in &names {
+                        let marker = if *session == current {
+                            "  <- CURRENT SESSION"
+                        } else {
+                            ""
+                        };
+                        println!("{session}  {name}{marker}");
+                    }
+                }
+
+                Ok(())
+            }
+
+            Self::Summarize { session, json } => {
+                let names = session_store.names().await?;
+                let session = match session {
+                    Some(session) => find_session(&names, &session),
+                    None => current_context().await?.session,
+                };
+
+                let history = db.session_history(&session).await?;
+                let Some(summary) = SessionSummary::compute(settings, &session, &history) else {
+                    bail!("no history in session {session}, see `atuin session list`");
+                };
+
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&summary)?);
+                } else {
+                    print_summary(&summary, names.get(&session), settings.timezone)?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The ID of the session called `query`, ignoring case, or `query` itself if none is.
+fn find_session(names: &HashMap<String, String>, query: &str) -> String {
+    names
+        .iter()
+        .find(|(_, name)| name.eq_ignore_ascii_case(query))
+        .map_or_else(|| query.to_string(), |(session, _)| session.clone())
+}
+
+fn print_summary(summary: &SessionSummary, name: Option<&String>, tz: Timezone) -> Result<()> {
+    let format = format_description!("[year]-[month]-[day] [hour repr:24]:[minute]:[second]");
+
+    match name {
+        Some(name) => println!("Session      {} ({name})", summary.session),
+        None => println!("Session      {}", summary.session),
+    }
+    println!(
+        "Started      {}",
+        summary.start.to_offset(tz.0).format(format)?
+    );
+    println!(
+        "Ended        {} ({})",
+        summary.end.to_offset(tz.0).format(format)?,
+        format_duration(summary.duration())
+    );
+    println!(
+        "Commands     {}, {} failed",
+        summary.commands, summary.failures
+    );
+    println!("First        {}", summary.first_command);
+    println!("Last         {}", summary.last_command);
+    println!(
+        "Tools        {}",
+        summary
+            .tools
+            .iter()
+            .map(|(tool, count)| format!("{tool} ({count})"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    println!(
+        "Directories  {}",
+        summary.directories.join("\n             ")
+    );
+
+    Ok(())
+}
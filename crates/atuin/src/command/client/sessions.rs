@@ -0,0 +1,420 @@
+//! `atuin sessions`: the recent sessions on every host.
+//!
+//! Lists each session with its name, where it ran and how much it ran, and sums up the
+//! selected one underneath. Enter replays the session: every command it ran, in order, with
+//! how long it took, how it exited and where.
+
+use std::collections::HashMap;
+use std::io::{IsTerminal, stdout};
+
+use clap::Args;
+use eyre::{Result, bail};
+use ratatui::{
+    Frame, Terminal,
+    backend::CrosstermBackend,
+    crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Layout, Rect},
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Paragraph, Row, Table, TableState, Wrap},
+};
+use time::{OffsetDateTime, macros::format_description};
+
+use atuin_client::{
+    database::{Database, SessionStats, query_context},
+    history::{History, host_of},
+    settings::{Settings, Timezone},
+};
+use atuin_history::summary::SessionSummary;
+
+use super::search::format_duration;
+use super::store::browse::{TerminalGuard, bold, highlight, move_selection, pane};
+
+/// How many of the tools the selected session used are named in its summary
+const TOOLS_SHOWN: usize = 5;
+
+#[derive(Args, Debug)]
+pub struct Cmd {
+    /// How many of the most recent sessions to list
+    #[arg(long, default_value_t = 200)]
+    limit: usize,
+}
+
+impl Cmd {
+    pub async fn run(&self, settings: &Settings, db: &impl Database) -> Result<()> {
+        if !stdout().is_terminal() {
+            bail!("`atuin sessions` needs a terminal, try `atuin session summarize` instead");
+        }
+
+        let sessions = db.session_stats(self.limit).await?;
+        if sessions.is_empty() {
+            println!("No sessions in the history yet");
+            return Ok(());
+        }
+
+        let mut screen = Sessions {
+            settings,
+            current: query_context().await?.session,
+            names: db.session_names().await?,
+            sessions,
+            state: TableState::default().with_selected(0),
+            history: Vec::new(),
+            summary: None,
+            replay: None,
+        };
+        screen.load_selected(db).await?;
+
+        let _guard = TerminalGuard::new()?;
+        let mut terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
+
+        loop {
+            terminal.draw(|f| screen.draw(f))?;
+
+            let event = tokio::task::spawn_blocking(event::read).await??;
+            if let Event::Key(key) = event
+                && key.kind != KeyEventKind::Release
+                && !screen.handle_key(key.code, key.modifiers, db).await?
+            {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A session's commands, being stepped through.
+struct Replay {
+    title: String,
+    history: Vec<History>,
+    state: TableState,
+}
+
+struct Sessions<'a> {
+    settings: &'a Settings,
+    /// The session `atuin sessions` runs in
+    current: String,
+    names: HashMap<String, String>,
+
+    sessions: Vec<SessionStats>,
+    state: TableState,
+
+    /// The history of the selected session, oldest first, and what it sums up to
+    history: Vec<History>,
+    summary: Option<SessionSummary>,
+
+    replay: Option<Replay>,
+}
+
+impl Sessions<'_> {
+    fn selected(&self) -> &SessionStats {
+        &self.sessions[self.state.selected().unwrap_or_default()]
+    }
+
+    /// The session's name, or the start of its ID
+    fn label(&self, session: &str) -> String {
+        self.names
+            .get(session)
+            .cloned()
+            .unwrap_or_else(|| session.chars().take(8).collect())
+    }
+
+    async fn load_selected(&mut self, db: &impl Database) -> Result<()> {
+        let session = self.selected().session.clone();
+        self.history = db.session_history(&session).await?;
+        self.summary = SessionSummary::compute(self.settings, &session, &self.history);
+
+        Ok(())
+    }
+
+    fn open(&mut self) {
+        if self.history.is_empty() {
+            return;
+        }
+
+        let selected = self.selected();
+        let title = format!(" {} · {} ", self.label(&selected.session), hosts(selected));
+
+        self.replay = Some(Replay {
+            title,
+            history: self.history.clone(),
+            state: TableState::default().with_selected(0),
+        });
+    }
+
+    /// Returns false once the screen should close
+    async fn handle_key(
+        &mut self,
+        code: KeyCode,
+        modifiers: KeyModifiers,
+        db: &impl Database,
+    ) -> Result<bool> {
+        if modifiers.contains(KeyModifiers::CONTROL) && code == KeyCode::Char('c') {
+            return Ok(false);
+        }
+
+        let step: isize = match code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Left | KeyCode::Char('h')
+                if self.replay.is_some() =>
+            {
+                self.replay = None;
+                return Ok(true);
+            }
+            KeyCode::Esc | KeyCode::Char('q') => return Ok(false),
+            KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => {
+                if self.replay.is_none() {
+                    self.open();
+                }
+                return Ok(true);
+            }
+            KeyCode::Up | KeyCode::Char('k') => -1,
+            KeyCode::Down | KeyCode::Char('j') => 1,
+            KeyCode::PageUp => -20,
+            KeyCode::PageDown => 20,
+            KeyCode::Home | KeyCode::Char('g') => isize::MIN,
+            KeyCode::End | KeyCode::Char('G') => isize::MAX,
+            _ => return Ok(true),
+        };
+
+        if let Some(replay) = &mut self.replay {
+            move_selection(&mut replay.state, replay.history.len(), step);
+            return Ok(true);
+        }
+
+        let before = self.state.selected();
+        move_selection(&mut self.state, self.sessions.len(), step);
+        if self.state.selected() != before {
+            self.load_selected(db).await?;
+        }
+
+        Ok(true)
+    }
+
+    fn draw(&mut self, f: &mut Frame) {
+        let [main, help] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(f.area());
+
+        let help_text = if self.replay.is_some() {
+            "↑↓ move · esc back · ctrl-c quit"
+        } else {
+            "↑↓ move · enter replay · q quit"
+        };
+        f.render_widget(
+            Paragraph::new(help_text).style(Style::new().add_modifier(Modifier::DIM)),
+            help,
+        );
+
+        if let Some(replay) = &mut self.replay {
+            draw_replay(f, main, replay, self.settings.timezone);
+            return;
+        }
+
+        let [list, summary] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(6)]).areas(main);
+        self.draw_sessions(f, list);
+        self.draw_summary(f, summary);
+    }
+
+    fn draw_sessions(&mut self, f: &mut Frame, area: Rect) {
+        let tz = self.settings.timezone;
+        let rows = self.sessions.iter().map(|stats| {
+            let mut label = self.label(&stats.session);
+            if stats.session == self.current {
+                label.push_str(" (current)");
+            }
+
+            Row::new([
+                label,
+                hosts(stats),
+                format_time(stats.start, tz),
+                format_duration((stats.last - stats.start).try_into().unwrap_or_default()),
+                stats.commands.to_string(),
+                stats.failures.to_string(),
+            ])
+        });
+
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Fill(1),
+                Constraint::Fill(1),
+                Constraint::Length(16),
+                Constraint::Length(8),
+                Constraint::Length(8),
+                Constraint::Length(6),
+            ],
+        )
+        .header(
+            Row::new(["Session", "Host", "Started", "Length", "Commands", "Failed"]).style(bold()),
+        )
+        .block(pane(" Sessions ", true))
+        .row_highlight_style(highlight(true));
+
+        f.render_stateful_widget(table, area, &mut self.state);
+    }
+
+    fn draw_summary(&self, f: &mut Frame, area: Rect) {
+        let lines = self.summary.as_ref().map_or_else(Vec::new, summary_lines);
+        let title = format!(" {} ", self.selected().session);
+
+        f.render_widget(
+            Paragraph::new(lines)
+                .block(pane(&title, false))
+                .wrap(Wrap { trim: false }),
+            area,
+        );
+    }
+}
+
+fn draw_replay(f: &mut Frame, area: Rect, replay: &mut Replay, tz: Timezone) {
+    let mut previous_cwd = None;
+    let rows = replay.history.iter().map(|h| {
+        // Only show where a command ran when it moved
+        let cwd = if previous_cwd == Some(&h.cwd) {
+            String::new()
+        } else {
+            h.cwd.clone()
+        };
+        previous_cwd = Some(&h.cwd);
+
+        let took = if h.duration < 0 {
+            String::new()
+        } else {
+            format_duration(std::time::Duration::from_nanos(h.duration.unsigned_abs()))
+        };
+        let row = Row::new([
+            format_clock(h.timestamp, tz),
+            took,
+            h.exit.to_string(),
+            cwd,
+            h.command.clone(),
+        ]);
+
+        if h.success() {
+            row
+        } else {
+            row.style(Style::new().add_modifier(Modifier::BOLD))
+        }
+    });
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(8),
+            Constraint::Length(6),
+            Constraint::Length(4),
+            Constraint::Percentage(25),
+            Constraint::Fill(1),
+        ],
+    )
+    .header(Row::new(["Time", "Took", "Exit", "Directory", "Command"]).style(bold()))
+    .block(
+        pane(&replay.title, true).title_bottom(
+            Line::from(format!(
+                " {}/{} ",
+                replay.state.selected().map_or(0, |i| i + 1),
+                replay.history.len()
+            ))
+            .right_aligned(),
+        ),
+    )
+    .row_highlight_style(highlight(true));
+
+    f.render_stateful_widget(table, area, &mut replay.state);
+}
+
+/// The hosts a session ran on, without the users
+fn hosts(stats: &SessionStats) -> String {
+    let mut hosts: Vec<&str> = stats.hostnames.iter().map(|h| host_of(h)).collect();
+    hosts.sort_unstable();
+    hosts.dedup();
+    hosts.join(", ")
+}
+
+fn summary_lines(summary: &SessionSummary) -> Vec<Line<'static>> {
+    let tools = summary
+        .tools
+        .iter()
+        .take(TOOLS_SHOWN)
+        .map(|(tool, count)| format!("{tool} ({count})"))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    vec![
+        Line::from(format!("First        {}", summary.first_command)),
+        Line::from(format!("Last         {}", summary.last_command)),
+        Line::from(format!("Tools        {tools}")),
+        Line::from(format!("Directories  {}", summary.directories.join(", "))),
+    ]
+}
+
+fn format_time(time: OffsetDateTime, tz: Timezone) -> String {
+    time.to_offset(tz.0)
+        .format(format_description!("[year]-[month]-[day] [hour]:[minute]"))
+        .unwrap_or_default()
+}
+
+fn format_clock(time: OffsetDateTime, tz: Timezone) -> String {
+    time.to_offset(tz.0)
+        .format(format_description!("[hour]:[minute]:[second]"))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use atuin_client::history::History;
+    use atuin_client::settings::Settings;
+    use time::{Duration, macros::datetime};
+
+    use super::*;
+
+    #[test]
+    fn hosts_are_named_once_without_users() {
+        let stats = SessionStats {
+            session: "abc".to_string(),
+            hostnames: vec![
+                "server:root".to_string(),
+                "laptop:ellie".to_string(),
+                "server:ellie".to_string(),
+            ],
+            commands: 3,
+            failures: 0,
+            start: datetime!(2026-10-16 09:00 UTC),
+            last: datetime!(2026-10-16 10:00 UTC),
+        };
+
+        assert_eq!(hosts(&stats), "laptop, server");
+    }
+
+    #[test]
+    fn the_summary_names_the_tools_used_most() {
+        let history: Vec<History> = ["git pull", "cargo build", "git push"]
+            .into_iter()
+            .zip(0..)
+            .map(|(command, minute)| {
+                History::import()
+                    .timestamp(datetime!(2026-10-16 09:00 UTC) + Duration::minutes(minute))
+                    .command(command)
+                    .cwd("/home/ellie/src/atuin")
+                    .session("abc")
+                    .build()
+                    .into()
+            })
+            .collect();
+        let summary = SessionSummary::compute(&Settings::utc(), "abc", &history).unwrap();
+
+        let lines: Vec<String> = summary_lines(&summary)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                "First        git pull",
+                "Last         git push",
+                "Tools        git (2), cargo (1)",
+                "Directories  /home/ellie/src/atuin",
+            ]
+        );
+    }
+}
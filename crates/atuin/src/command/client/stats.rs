@@ -5,9 +5,10 @@ use time::{Duration, OffsetDateTime, Time};
 
 use atuin_client::{
     database::{Database, current_context},
-    settings::{FilterMode, Settings},
+    settings::{Dialect, FilterMode, Settings, Timezone},
     theme::Theme,
 };
+use atuin_common::utils;
 
 use atuin_history::stats::{compute, pretty_print};
 
@@ -23,6 +24,52 @@ fn parse_ngram_size(s: &str) -> Result<usize, String> {
     Ok(value)
 }
 
+/// The start and end of the period named by `words`, or `None` for all time.
+/// Periods are whole days in `now`'s offset, counting back from `now`.
+fn period_range(
+    words: &str,
+    now: OffsetDateTime,
+    dialect: Dialect,
+) -> Result<Option<(OffsetDateTime, OffsetDateTime)>> {
+    let last_night = now.replace_time(Time::MIDNIGHT);
+
+    let range = if words == "all" {
+        None
+    } else if words.trim() == "today" {
+        let start = last_night;
+        let end = start + Duration::days(1);
+        Some((start, end))
+    } else if words.trim() == "month" {
+        let end = last_night;
+        let start = end - Duration::days(31);
+        Some((start, end))
+    } else if words.trim() == "week" {
+        let end = last_night;
+        let start = end - Duration::days(7);
+        Some((start, end))
+    } else if words.trim() == "year" {
+        let end = last_night;
+        let start = end - Duration::days(365);
+        Some((start, end))
+    } else {
+        let start = parse_date_string(words, now, dialect.into())?;
+        // Phrases without a time ("yesterday", "2 days ago") keep the current time of day,
+        // so land on the same day read from last night, just at midnight. Those start at
+        // midnight so they cover the calendar day, while "2 hours ago" or "friday 3pm" don't.
+        let date_only = parse_date_string(words, last_night, dialect.into())?
+            == start.replace_time(Time::MIDNIGHT);
+        let start = if date_only {
+            start.replace_time(Time::MIDNIGHT)
+        } else {
+            start
+        };
+        let end = start + Duration::days(1);
+        Some((start, end))
+    };
+
+    Ok(range)
+}
+
 fn period_long_help() -> String {
     format!(
         "Compute statistics for the specified period, leave blank for statistics since the beginning. See [this]({}) for more details.",
@@ -48,6 +95,20 @@ pub struct Cmd {
     /// Filter commands by scope [global, host, session, directory, workspace]
     #[arg(long = "filter-mode")]
     filter_mode: Option<FilterMode>,
+
+    /// Bucket days in another timezone other than the configured default.
+    ///
+    /// This option takes one of the following kinds of values:
+    ///
+    /// - the special value "local" (or "l") which refers to the system time zone
+    /// - an offset from UTC (e.g. "+9", "-2:30")
+    #[arg(
+        long,
+        visible_alias = "tz",
+        verbatim_doc_comment,
+        allow_hyphen_values = true
+    )]
+    timezone: Option<Timezone>,
 }
 
 impl Cmd {
@@ -63,32 +124,8 @@ impl Cmd {
         // but stats only ever scopes to one at a time.
         let filter = self.filter_mode.map(|f| vec![f]).unwrap_or_default();
 
-        let now = OffsetDateTime::now_utc().to_offset(settings.timezone.0);
-        let last_night = now.replace_time(Time::MIDNIGHT);
-
-        let range = if words.as_str() == "all" {
-            None
-        } else if words.trim() == "today" {
-            let start = last_night;
-            let end = start + Duration::days(1);
-            Some((start, end))
-        } else if words.trim() == "month" {
-            let end = last_night;
-            let start = end - Duration::days(31);
-            Some((start, end))
-        } else if words.trim() == "week" {
-            let end = last_night;
-            let start = end - Duration::days(7);
-            Some((start, end))
-        } else if words.trim() == "year" {
-            let end = last_night;
-            let start = end - Duration::days(365);
-            Some((start, end))
-        } else {
-            let start = parse_date_string(&words, now, settings.dialect.into())?;
-            let end = start + Duration::days(1);
-            Some((start, end))
-        };
+        let tz = self.timezone.unwrap_or(settings.timezone);
+        let range = period_range(&words, utils::now().to_offset(tz.0), settings.dialect)?;
 
         let history = db
             .list(filter.as_slice(), &context, None, false, false, range)
@@ -103,3 +140,59 @@ impl Cmd {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn periods_are_measured_from_now() {
+        let now = datetime!(2026-10-15 15:30 +2);
+
+        assert_eq!(period_range("all", now, Dialect::Uk).unwrap(), None);
+        assert_eq!(
+            period_range("today", now, Dialect::Uk).unwrap(),
+            Some((datetime!(2026-10-15 0:00 +2), datetime!(2026-10-16 0:00 +2)))
+        );
+        assert_eq!(
+            period_range("week", now, Dialect::Uk).unwrap(),
+            Some((datetime!(2026-10-08 0:00 +2), datetime!(2026-10-15 0:00 +2)))
+        );
+        assert_eq!(
+            period_range("yesterday", now, Dialect::Uk).unwrap(),
+            Some((datetime!(2026-10-14 0:00 +2), datetime!(2026-10-15 0:00 +2)))
+        );
+
+        // A time that happens to be now's is still a time
+        assert_eq!(
+            period_range("last fri 15:30", now, Dialect::Uk).unwrap(),
+            Some((
+                datetime!(2026-10-09 15:30 +2),
+                datetime!(2026-10-10 15:30 +2)
+            ))
+        );
+        assert_eq!(
+            period_range("2 hours ago", now, Dialect::Uk).unwrap(),
+            Some((
+                datetime!(2026-10-15 13:30 +2),
+                datetime!(2026-10-16 13:30 +2)
+            ))
+        );
+
+        // At midnight, a day is still the whole of it, and hours are still hours
+        let midnight = datetime!(2026-10-15 0:00 +2);
+        assert_eq!(
+            period_range("yesterday", midnight, Dialect::Uk).unwrap(),
+            Some((datetime!(2026-10-14 0:00 +2), datetime!(2026-10-15 0:00 +2)))
+        );
+        assert_eq!(
+            period_range("2 hours ago", midnight, Dialect::Uk).unwrap(),
+            Some((
+                datetime!(2026-10-14 22:00 +2),
+                datetime!(2026-10-15 22:00 +2)
+            ))
+        );
+    }
+}
@@ -3,6 +3,8 @@ use eyre::Result;
 
 use atuin_client::{
     database::Database,
+    encryption,
+    host::HostStore,
     record::{sqlite_store::SqliteStore, store::Store},
     settings::Settings,
 };
@@ -15,6 +17,7 @@ mod push;
 #[cfg(feature = "sync")]
 mod pull;
 
+pub(super) mod browse;
 mod purge;
 mod rebuild;
 mod rekey;
@@ -26,6 +29,9 @@ pub enum Cmd {
     /// Print the current status of the record store
     Status,
 
+    /// Browse the raw records in the store interactively
+    Browse(browse::Browse),
+
     /// Rebuild a store (eg atuin store rebuild history)
     Rebuild(rebuild::Rebuild),
 
@@ -55,7 +61,8 @@ impl Cmd {
         store: SqliteStore,
     ) -> Result<()> {
         match self {
-            Self::Status => self.status(store).await,
+            Self::Status => self.status(settings, store).await,
+            Self::Browse(browse) => browse.run(settings, store).await,
             Self::Rebuild(rebuild) => rebuild.run(settings, store, database).await,
             Self::Rekey(rekey) => rekey.run(settings, store).await,
             Self::Verify(verify) => verify.run(settings, store).await,
@@ -69,18 +76,32 @@ impl Cmd {
         }
     }
 
-    pub async fn status(&self, store: SqliteStore) -> Result<()> {
+    pub async fn status(&self, settings: &Settings, store: SqliteStore) -> Result<()> {
         let host_id = Settings::host_id().await?;
         let offset = UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC);
 
+        // Names are a nicety - the status is still worth printing without them
+        let hosts = match encryption::load_key(settings) {
+            Ok(key) => HostStore::new(store.clone(), host_id, key.into())
+                .hosts()
+                .await
+                .unwrap_or_default(),
+            Err(_) => Vec::new(),
+        };
+
         let status = store.status().await?;
 
         // TODO: should probs build some data structure and then pretty-print it or smth
         for (host, st) in status.hosts.iter().sorted_by_key(|(h, _)| *h) {
+            let name = hosts
+                .iter()
+                .find(|info| info.id == *host)
+                .map_or_else(String::new, |info| format!(" ({})", info.display_name()));
+
             let host_string = if host == &host_id {
-                format!("host: {} <- CURRENT HOST", host.0.as_hyphenated())
+                format!("host: {}{name} <- CURRENT HOST", host.0.as_hyphenated())
             } else {
-                format!("host: {}", host.0.as_hyphenated())
+                format!("host: {}{name}", host.0.as_hyphenated())
             };
 
             println!("{host_string}");
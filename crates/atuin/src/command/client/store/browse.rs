@@ -0,0 +1,621 @@
+//! `atuin store browse`: a viewer over the raw record store.
+//!
+//! Lists every (host, tag) store on the left and the records in the selected one on the
+//! right. Enter decrypts a record and shows what it decodes to, for debugging sync without
+//! poking at the sqlite database by hand.
+
+use std::fmt::Write as _;
+use std::io::{IsTerminal, stdout};
+
+use clap::Args;
+use eyre::{Result, bail};
+use itertools::Itertools;
+use ratatui::{
+    Frame, Terminal,
+    backend::CrosstermBackend,
+    crossterm::{
+        event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+        execute, terminal,
+    },
+    layout::{Constraint, Layout, Rect},
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Clear, Paragraph, Row, Table, TableState, Wrap},
+};
+use time::{OffsetDateTime, macros::format_description};
+
+use atuin_client::{
+    encryption,
+    history::store::HistoryRecord,
+    host::{HostRecord, HostStore},
+    record::{encryption::PASETO_V4, sqlite_store::SqliteStore, store::Store},
+    session::SessionRecord,
+    settings::{Settings, Timezone},
+};
+use atuin_common::record::{DecryptedData, EncryptedData, HostId, Record};
+use atuin_dotfiles::store::{AliasRecord, var::VarRecord};
+use atuin_kv::store::record::KvRecord;
+use atuin_scripts::store::record::ScriptRecord;
+
+use crate::crash;
+
+/// How many records to read from the store at a time
+const PAGE_SIZE: u64 = 500;
+
+#[derive(Args, Debug)]
+pub struct Browse {
+    /// Only show stores with this tag (eg history)
+    #[arg(long)]
+    pub tag: Option<String>,
+}
+
+impl Browse {
+    pub async fn run(&self, settings: &Settings, store: SqliteStore) -> Result<()> {
+        if !stdout().is_terminal() {
+            bail!("`atuin store browse` needs a terminal, try `atuin store status` instead");
+        }
+
+        let host_id = Settings::host_id().await?;
+
+        // Browsing works without the key, only decrypting needs it
+        let key: Option<[u8; 32]> = encryption::load_key(settings).ok().map(Into::into);
+
+        let hosts = match key {
+            Some(key) => HostStore::new(store.clone(), host_id, key)
+                .hosts()
+                .await
+                .unwrap_or_default(),
+            None => Vec::new(),
+        };
+
+        let status = store.status().await?;
+        let stores = status
+            .hosts
+            .iter()
+            .flat_map(|(host, tags)| {
+                let name = hosts.iter().find(|info| info.id == *host).map_or_else(
+                    || host.0.as_simple().to_string(),
+                    atuin_client::host::HostInfo::display_name,
+                );
+
+                tags.iter().map(move |(tag, idx)| Tagged {
+                    host: *host,
+                    host_name: name.clone(),
+                    current: *host == host_id,
+                    tag: tag.clone(),
+                    records: idx + 1,
+                })
+            })
+            .filter(|tagged| self.tag.as_ref().is_none_or(|tag| tagged.tag == *tag))
+            .sorted_by(|a, b| {
+                (!a.current, &a.host_name, &a.tag).cmp(&(!b.current, &b.host_name, &b.tag))
+            })
+            .collect::<Vec<_>>();
+
+        if stores.is_empty() {
+            match &self.tag {
+                Some(tag) => println!("No records tagged {tag:?} in the store"),
+                None => println!("The record store is empty"),
+            }
+            return Ok(());
+        }
+
+        let mut browser = Browser {
+            store,
+            key,
+            tz: settings.timezone,
+            stores,
+            stores_state: TableState::default().with_selected(0),
+            records: Vec::new(),
+            records_state: TableState::default(),
+            all_loaded: false,
+            detail: None,
+            focus: Focus::Stores,
+        };
+        browser.load_records().await?;
+
+        let _guard = TerminalGuard::new()?;
+        let mut terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
+
+        loop {
+            terminal.draw(|f| browser.draw(f))?;
+
+            let event = tokio::task::spawn_blocking(event::read).await??;
+            if let Event::Key(key) = event
+                && key.kind != KeyEventKind::Release
+                && !browser.handle_key(key.code, key.modifiers).await?
+            {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The records one host has written under one tag.
+struct Tagged {
+    host: HostId,
+    host_name: String,
+    current: bool,
+    tag: String,
+    records: u64,
+}
+
+/// A decrypted record, being read.
+struct Detail {
+    title: String,
+    text: String,
+    scroll: u16,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Focus {
+    Stores,
+    Records,
+}
+
+struct Browser {
+    store: SqliteStore,
+    key: Option<[u8; 32]>,
+    tz: Timezone,
+
+    stores: Vec<Tagged>,
+    stores_state: TableState,
+
+    /// The records of the selected store, read so far
+    records: Vec<Record<EncryptedData>>,
+    records_state: TableState,
+    all_loaded: bool,
+
+    detail: Option<Detail>,
+    focus: Focus,
+}
+
+impl Browser {
+    fn selected_store(&self) -> &Tagged {
+        &self.stores[self.stores_state.selected().unwrap_or_default()]
+    }
+
+    /// Read the next page of the selected store's records
+    async fn load_more(&mut self) -> Result<()> {
+        if self.all_loaded {
+            return Ok(());
+        }
+
+        let tagged = self.selected_store();
+        let from = self.records.last().map_or(0, |record| record.idx + 1);
+        let page = self
+            .store
+            .next(tagged.host, &tagged.tag, from, PAGE_SIZE)
+            .await?;
+
+        self.all_loaded = (page.len() as u64) < PAGE_SIZE;
+        self.records.extend(page);
+
+        Ok(())
+    }
+
+    async fn load_records(&mut self) -> Result<()> {
+        self.records.clear();
+        self.all_loaded = false;
+        self.load_more().await?;
+        self.records_state = TableState::default().with_selected(0);
+
+        Ok(())
+    }
+
+    fn open(&mut self) {
+        let Some(record) = self
+            .records_state
+            .selected()
+            .and_then(|idx| self.records.get(idx))
+        else {
+            return;
+        };
+
+        let title = format!(" {} #{} ", record.tag, record.idx);
+        let text = self.key.map_or_else(
+            || "No encryption key, so records can't be decrypted".to_string(),
+            |key| match record.clone().decrypt::<PASETO_V4>(&key) {
+                Ok(decrypted) => describe(&decrypted),
+                Err(e) => format!(
+                    "Could not decrypt this record with the current key: {e}\n\n\
+                     Run `atuin store verify` to check the rest of the store."
+                ),
+            },
+        );
+
+        self.detail = Some(Detail {
+            title,
+            text,
+            scroll: 0,
+        });
+    }
+
+    /// Returns false once the browser should close
+    async fn handle_key(&mut self, code: KeyCode, modifiers: KeyModifiers) -> Result<bool> {
+        if modifiers.contains(KeyModifiers::CONTROL) && code == KeyCode::Char('c') {
+            return Ok(false);
+        }
+
+        if let Some(detail) = &mut self.detail {
+            match code {
+                KeyCode::Esc | KeyCode::Char('q') | KeyCode::Enter => self.detail = None,
+                KeyCode::Up | KeyCode::Char('k') => detail.scroll = detail.scroll.saturating_sub(1),
+                KeyCode::Down | KeyCode::Char('j') => {
+                    detail.scroll = detail.scroll.saturating_add(1);
+                }
+                KeyCode::PageUp => detail.scroll = detail.scroll.saturating_sub(10),
+                KeyCode::PageDown => detail.scroll = detail.scroll.saturating_add(10),
+                _ => {}
+            }
+            return Ok(true);
+        }
+
+        let step: isize = match code {
+            KeyCode::Esc | KeyCode::Char('q') => return Ok(false),
+            KeyCode::Tab | KeyCode::BackTab => {
+                self.focus = match self.focus {
+                    Focus::Stores => Focus::Records,
+                    Focus::Records => Focus::Stores,
+                };
+                return Ok(true);
+            }
+            KeyCode::Right | KeyCode::Char('l') => {
+                self.focus = Focus::Records;
+                return Ok(true);
+            }
+            KeyCode::Left | KeyCode::Char('h') => {
+                self.focus = Focus::Stores;
+                return Ok(true);
+            }
+            KeyCode::Enter => {
+                match self.focus {
+                    Focus::Stores => self.focus = Focus::Records,
+                    Focus::Records => self.open(),
+                }
+                return Ok(true);
+            }
+            KeyCode::Up | KeyCode::Char('k') => -1,
+            KeyCode::Down | KeyCode::Char('j') => 1,
+            KeyCode::PageUp => -20,
+            KeyCode::PageDown => 20,
+            KeyCode::Home | KeyCode::Char('g') => isize::MIN,
+            KeyCode::End | KeyCode::Char('G') => isize::MAX,
+            _ => return Ok(true),
+        };
+
+        match self.focus {
+            Focus::Stores => {
+                let before = self.stores_state.selected();
+                move_selection(&mut self.stores_state, self.stores.len(), step);
+                if self.stores_state.selected() != before {
+                    self.load_records().await?;
+                }
+            }
+            Focus::Records => {
+                // Jumping to the end means reading the whole store
+                if step == isize::MAX {
+                    while !self.all_loaded {
+                        self.load_more().await?;
+                    }
+                }
+
+                move_selection(&mut self.records_state, self.records.len(), step);
+
+                if self.records_state.selected() == Some(self.records.len().saturating_sub(1)) {
+                    self.load_more().await?;
+                }
+            }
+        }
+
+        Ok(true)
+    }
+
+    fn draw(&mut self, f: &mut Frame) {
+        let [main, help] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(f.area());
+        let [stores_area, records_area] =
+            Layout::horizontal([Constraint::Percentage(35), Constraint::Percentage(65)])
+                .areas(main);
+
+        self.draw_stores(f, stores_area);
+        self.draw_records(f, records_area);
+
+        let help_text = if self.detail.is_some() {
+            "↑↓ scroll · esc close"
+        } else {
+            "↑↓ move · tab switch pane · enter decrypt · q quit"
+        };
+        f.render_widget(
+            Paragraph::new(help_text).style(Style::new().add_modifier(Modifier::DIM)),
+            help,
+        );
+
+        if let Some(detail) = &self.detail {
+            let area = centered(main, 90, 90);
+            let paragraph = Paragraph::new(detail.text.as_str())
+                .block(
+                    Block::new()
+                        .borders(Borders::ALL)
+                        .title(detail.title.as_str()),
+                )
+                .wrap(Wrap { trim: false })
+                .scroll((detail.scroll, 0));
+
+            f.render_widget(Clear, area);
+            f.render_widget(paragraph, area);
+        }
+    }
+
+    fn draw_stores(&mut self, f: &mut Frame, area: Rect) {
+        let rows = self.stores.iter().map(|tagged| {
+            let host = if tagged.current {
+                format!("{} (current)", tagged.host_name)
+            } else {
+                tagged.host_name.clone()
+            };
+            Row::new([host, tagged.tag.clone(), tagged.records.to_string()])
+        });
+
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Fill(1),
+                Constraint::Length(18),
+                Constraint::Length(8),
+            ],
+        )
+        .header(Row::new(["Host", "Tag", "Records"]).style(bold()))
+        .block(pane(" Stores ", self.focus == Focus::Stores))
+        .row_highlight_style(highlight(self.focus == Focus::Stores));
+
+        f.render_stateful_widget(table, area, &mut self.stores_state);
+    }
+
+    fn draw_records(&mut self, f: &mut Frame, area: Rect) {
+        let tagged = self.selected_store();
+        let title = format!(" {} · {} ", tagged.host_name, tagged.tag);
+        let host = tagged.host.0.as_hyphenated().to_string();
+
+        let rows = self.records.iter().map(|record| {
+            Row::new([
+                record.idx.to_string(),
+                record.id.0.as_simple().to_string(),
+                record.version.clone(),
+                format_timestamp(record.timestamp, self.tz),
+                format_size(encrypted_size(record)),
+            ])
+        });
+
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(7),
+                Constraint::Fill(1),
+                Constraint::Length(7),
+                Constraint::Length(19),
+                Constraint::Length(9),
+            ],
+        )
+        .header(Row::new(["Idx", "ID", "Version", "Created", "Size"]).style(bold()))
+        .block(
+            pane(&title, self.focus == Focus::Records)
+                .title_bottom(Line::from(format!(" host {host} ")).right_aligned()),
+        )
+        .row_highlight_style(highlight(self.focus == Focus::Records));
+
+        f.render_stateful_widget(table, area, &mut self.records_state);
+    }
+}
+
+pub(crate) fn move_selection(state: &mut TableState, len: usize, step: isize) {
+    if len == 0 {
+        state.select(None);
+        return;
+    }
+
+    let current = state.selected().unwrap_or_default();
+    let next = current.saturating_add_signed(step).min(len - 1);
+    state.select(Some(next));
+}
+
+pub(crate) fn pane(title: &str, focused: bool) -> Block<'_> {
+    let block = Block::new().borders(Borders::ALL).title(title);
+    if focused {
+        block.border_style(bold())
+    } else {
+        block.border_style(Style::new().add_modifier(Modifier::DIM))
+    }
+}
+
+pub(crate) fn bold() -> Style {
+    Style::new().add_modifier(Modifier::BOLD)
+}
+
+pub(crate) fn highlight(focused: bool) -> Style {
+    if focused {
+        Style::new().add_modifier(Modifier::REVERSED)
+    } else {
+        Style::new().add_modifier(Modifier::UNDERLINED)
+    }
+}
+
+/// A `width`% by `height`% box in the middle of `area`
+fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let [area] = Layout::vertical([Constraint::Percentage(height)])
+        .flex(ratatui::layout::Flex::Center)
+        .areas(area);
+    let [area] = Layout::horizontal([Constraint::Percentage(width)])
+        .flex(ratatui::layout::Flex::Center)
+        .areas(area);
+    area
+}
+
+fn encrypted_size(record: &Record<EncryptedData>) -> usize {
+    record.data.data.len() + record.data.content_encryption_key.len()
+}
+
+fn format_size(bytes: usize) -> String {
+    #[allow(clippy::cast_precision_loss)]
+    match bytes {
+        0..1024 => format!("{bytes} B"),
+        1024..1_048_576 => format!("{:.1} KiB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MiB", bytes as f64 / 1_048_576.0),
+    }
+}
+
+fn format_timestamp(nanos: u64, tz: Timezone) -> String {
+    OffsetDateTime::from_unix_timestamp_nanos(i128::from(nanos))
+        .ok()
+        .and_then(|time| {
+            time.to_offset(tz.0)
+                .format(format_description!(
+                    "[year]-[month]-[day] [hour]:[minute]:[second]"
+                ))
+                .ok()
+        })
+        .unwrap_or_else(|| nanos.to_string())
+}
+
+/// A decrypted record's metadata and contents, decoded if we know how to.
+fn describe(record: &Record<DecryptedData>) -> String {
+    let mut out = String::new();
+
+    let _ = writeln!(out, "id:       {}", record.id.0.as_hyphenated());
+    let _ = writeln!(out, "host:     {}", record.host.id.0.as_hyphenated());
+    let _ = writeln!(out, "tag:      {}", record.tag);
+    let _ = writeln!(out, "idx:      {}", record.idx);
+    let _ = writeln!(out, "version:  {}", record.version);
+    let _ = writeln!(out, "created:  {} ns", record.timestamp);
+    let _ = writeln!(out, "size:     {}", format_size(record.data.0.len()));
+    out.push('\n');
+
+    match decode(&record.tag, &record.version, &record.data) {
+        Some(Ok(decoded)) => out.push_str(&decoded),
+        Some(Err(e)) => {
+            let _ = writeln!(out, "could not decode: {e}\n");
+            out.push_str(&hex_dump(&record.data.0));
+        }
+        None => out.push_str(&hex_dump(&record.data.0)),
+    }
+
+    out
+}
+
+/// The record decoded, or `None` for tags we don't know
+fn decode(tag: &str, version: &str, data: &DecryptedData) -> Option<Result<String>> {
+    // keep in sync with the stores in crate::sync::build
+    let decoded = match tag {
+        "history" => HistoryRecord::deserialize(data, version).map(|r| format!("{r:#?}")),
+        "host" => HostRecord::deserialize(data, version).map(|r| format!("{r:#?}")),
+        "session" => SessionRecord::deserialize(data, version).map(|r| format!("{r:#?}")),
+        "kv" => KvRecord::deserialize(data, version).map(|r| format!("{r:#?}")),
+        "script" => ScriptRecord::deserialize(data, version).map(|r| format!("{r:#?}")),
+        "config-shell-alias" => AliasRecord::deserialize(data, version).map(|r| format!("{r:#?}")),
+        "dotfiles-var" => VarRecord::deserialize(data, version).map(|r| format!("{r:#?}")),
+        _ => return None,
+    };
+
+    Some(decoded)
+}
+
+/// Bytes as rows of hex, with any printable ASCII alongside
+fn hex_dump(bytes: &[u8]) -> String {
+    let mut out = String::new();
+
+    for (row, chunk) in bytes.chunks(16).enumerate() {
+        let hex = chunk.iter().map(|b| format!("{b:02x}")).join(" ");
+        let ascii: String = chunk
+            .iter()
+            .map(|&b| {
+                if b.is_ascii_graphic() || b == b' ' {
+                    char::from(b)
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+        let _ = writeln!(out, "{:08x}  {hex:<47}  {ascii}", row * 16);
+    }
+
+    out
+}
+
+/// Raw mode and the alternate screen, for as long as the browser is open.
+pub(crate) struct TerminalGuard;
+
+impl TerminalGuard {
+    pub(crate) fn new() -> Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(stdout(), terminal::EnterAlternateScreen)?;
+        crash::terminal_taken(true);
+        Ok(Self)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        crash::terminal_released();
+
+        if let Err(e) = execute!(stdout(), terminal::LeaveAlternateScreen) {
+            tracing::error!(?e, "Failed to leave alt screen mode");
+        }
+
+        if let Err(e) = terminal::disable_raw_mode() {
+            tracing::error!(?e, "Failed to disable raw mode");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use atuin_common::record::Host;
+
+    use super::*;
+
+    fn record(tag: &str, version: &str, data: Vec<u8>) -> Record<DecryptedData> {
+        Record::builder()
+            .host(Host::new(HostId(atuin_common::utils::uuid_v7())))
+            .version(version.to_string())
+            .tag(tag.to_string())
+            .idx(0)
+            .data(DecryptedData(data))
+            .build()
+    }
+
+    #[test]
+    fn describes_known_records() {
+        let kv = KvRecord::builder()
+            .namespace("default".to_string())
+            .key("editor".to_string())
+            .value(Some("hx".to_string()))
+            .build();
+        let text = describe(&record("kv", "v1", kv.serialize().unwrap().0));
+
+        assert!(text.contains("tag:      kv"), "{text}");
+        assert!(text.contains("key: \"editor\""), "{text}");
+    }
+
+    #[test]
+    fn dumps_unknown_and_corrupt_records() {
+        let text = describe(&record("mystery", "v0", b"hello, world!\x00\x01".to_vec()));
+        assert!(
+            text.contains(
+                "00000000  68 65 6c 6c 6f 2c 20 77 6f 72 6c 64 21 00 01     hello, world!.."
+            ),
+            "{text}"
+        );
+
+        let text = describe(&record("kv", "v1", vec![0xc1]));
+        assert!(text.contains("could not decode"), "{text}");
+    }
+
+    #[test]
+    fn sizes_are_readable() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(3 * 1_048_576), "3.0 MiB");
+    }
+}
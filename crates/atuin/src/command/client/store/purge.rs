@@ -12,6 +12,8 @@ pub struct Purge {}
 
 impl Purge {
     pub async fn run(&self, settings: &Settings, store: SqliteStore) -> Result<()> {
+        settings.ensure_history_removable()?;
+
         println!("Purging local records that cannot be decrypted");
 
         let key = load_key(settings)?;
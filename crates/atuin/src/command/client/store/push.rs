@@ -6,6 +6,7 @@ use uuid::Uuid;
 use atuin_client::{
     api_client::Client,
     encryption::load_key,
+    network::RequestKind,
     record::sync::Operation,
     record::{sqlite_store::SqliteStore, sync},
     settings::Settings,
@@ -47,7 +48,7 @@ impl Push {
                 &settings.sync_address,
                 settings.sync_auth_token().await?,
                 settings.network_connect_timeout,
-                settings.network_timeout * 10, // we may be deleting a lot of data... so up the
+                settings.network_timeout_for(RequestKind::Sync) * 10, // we may be deleting a lot of data... so up the
                 // timeout
                 &settings.extra_headers,
             )
@@ -11,8 +11,12 @@ use atuin_client::{
     record::sqlite_store::SqliteStore, settings::Settings,
 };
 
+/// The stores `atuin store rebuild` knows how to rebuild.
+const TAGS: [&str; 3] = ["history", "dotfiles", "scripts"];
+
 #[derive(Args, Debug)]
 pub struct Rebuild {
+    #[arg(value_parser = clap::builder::PossibleValuesParser::new(TAGS))]
     pub tag: String,
 }
 
@@ -1,17 +1,26 @@
+use std::fs::{File, OpenOptions, TryLockError};
+use std::process::Stdio;
+
 use clap::Subcommand;
-use eyre::{Result, WrapErr};
+use colored::Colorize;
+use eyre::{Result, WrapErr, bail};
 
 use atuin_client::{
     database::Database,
     encryption,
     history::store::HistoryStore,
-    record::{sqlite_store::SqliteStore, store::Store, sync},
+    record::{
+        sqlite_store::SqliteStore,
+        store::Store,
+        sync::{self, CLOCK_SKEW_WARNING},
+    },
     settings::Settings,
 };
+use atuin_common::paths;
 
 mod status;
 
-use crate::command::client::account;
+use crate::command::client::{account, search::format_duration};
 
 #[derive(Subcommand, Debug)]
 #[command(infer_subcommands = true)]
@@ -21,6 +30,11 @@ pub enum Cmd {
         /// Force re-download everything
         #[arg(long, short)]
         force: bool,
+
+        /// Only sync if an automatic sync is due and the server can be reached, printing nothing.
+        /// Run in the background after each command
+        #[arg(long, hide = true, conflicts_with = "force")]
+        background: bool,
     },
 
     /// Login to the configured server
@@ -40,7 +54,11 @@ pub enum Cmd {
     },
 
     /// Display the sync status
-    Status,
+    Status {
+        /// Print the status as JSON
+        #[arg(long)]
+        json: bool,
+    },
 }
 
 impl Cmd {
@@ -51,11 +69,14 @@ impl Cmd {
         store: SqliteStore,
     ) -> Result<()> {
         match self {
-            Self::Sync { force } => run(&settings, force, db, store).await,
+            Self::Sync {
+                background: true, ..
+            } => background(&settings, db, store).await,
+            Self::Sync { force, .. } => run(&settings, force, db, store).await,
             Self::Login(l) => l.run(&settings, &store).await,
             Self::Logout => account::logout::run().await,
             Self::Register(r) => r.run(&settings, &store).await,
-            Self::Status => status::run(&settings).await,
+            Self::Status { json } => status::run(&settings, store, json).await,
             Self::Key { base64 } => {
                 use atuin_client::encryption::{encode_key, load_key};
                 let key = load_key(&settings).wrap_err("could not load encryption key")?;
@@ -80,6 +101,10 @@ async fn run(
     db: &impl Database,
     store: SqliteStore,
 ) -> Result<()> {
+    if settings.offline {
+        bail!("not syncing, as offline mode is on");
+    }
+
     let encryption_key: [u8; 32] = encryption::load_key(settings)
         .context("could not load encryption key")?
         .into();
@@ -125,5 +150,115 @@ async fn run(
         force
     );
 
+    if let Some(warning) = Settings::clock_skew().await?.and_then(clock_skew_warning) {
+        eprintln!("{} {warning}", "Warning:".yellow().bold());
+    }
+
+    Ok(())
+}
+
+/// Held by the background sync while it runs, so that only one runs at a time, as they'd
+/// only race each other on the databases. `None` if another sync holds it.
+fn background_lock() -> Result<Option<File>> {
+    let dir = paths::state_dir();
+    fs_err::create_dir_all(&dir)?;
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(dir.join("sync.lock"))?;
+
+    match file.try_lock() {
+        Ok(()) => Ok(Some(file)),
+        Err(TryLockError::WouldBlock) => Ok(None),
+        Err(TryLockError::Error(e)) => Err(e.into()),
+    }
+}
+
+/// Start `atuin sync --background` in a process of its own, without waiting for it, unless
+/// one is still running
+pub(super) async fn spawn_background() -> Result<()> {
+    if background_lock()?.is_none() {
+        return Ok(());
+    }
+    Settings::save_sync_started_time().await?;
+
+    let mut cmd = std::process::Command::new(std::env::current_exe()?);
+    cmd.args(["sync", "--background"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+
+    // Out of the shell's process group, so that ^C at the prompt doesn't stop it
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+
+    cmd.spawn().wrap_err("could not start a background sync")?;
+    Ok(())
+}
+
+/// The automatic sync after a command is recorded
+async fn background(settings: &Settings, db: &impl Database, store: SqliteStore) -> Result<()> {
+    // Started after another command while one was already syncing
+    let Some(_lock) = background_lock()? else {
+        return Ok(());
+    };
+
+    if !settings.should_sync().await? {
+        return Ok(());
+    }
+
+    let encryption_key: [u8; 32] = encryption::load_key(settings)
+        .context("could not load encryption key")?
+        .into();
+
+    let (_, downloaded) = sync::sync(settings, &store, &encryption_key).await?;
+    Settings::save_sync_time().await?;
+
+    crate::sync::build(settings, &store, db, Some(&downloaded)).await?;
+
+    // Off the shell's hot path, so a good time to keep the history database small
+    super::history::archive_history(settings, &settings.archive).await?;
+
     Ok(())
 }
+
+/// A warning to show if the sync server's clock is `skew` ahead of ours (or
+/// behind, if negative) by enough to matter.
+fn clock_skew_warning(skew: time::Duration) -> Option<String> {
+    if skew.abs() <= CLOCK_SKEW_WARNING {
+        return None;
+    }
+
+    let by = format_duration(skew.unsigned_abs());
+    let direction = if skew.is_positive() {
+        "ahead of"
+    } else {
+        "behind"
+    };
+
+    Some(format!(
+        "the sync server's clock is {by} {direction} this machine's. \
+         Times of history from other machines will be off by as much; \
+         check that both clocks are set correctly"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_warns_about_noticeable_skew() {
+        assert_eq!(clock_skew_warning(time::Duration::seconds(-30)), None);
+
+        let warning = clock_skew_warning(time::Duration::minutes(5)).unwrap();
+        assert!(warning.contains("5m ahead of"), "{warning}");
+
+        let warning = clock_skew_warning(-time::Duration::hours(2)).unwrap();
+        assert!(warning.contains("2h behind"), "{warning}");
+    }
+}
@@ -1,9 +1,40 @@
 use crate::{SHA, VERSION};
-use atuin_client::{api_client, settings::Settings};
+use atuin_client::{
+    api_client, encryption,
+    host::{HostInfo, HostStore},
+    network::RequestKind,
+    record::sqlite_store::SqliteStore,
+    settings::Settings,
+};
 use colored::Colorize;
-use eyre::{Result, bail};
+use eyre::{Context, Result, bail};
+use serde::Serialize;
+use time::format_description::well_known::Rfc3339;
 
-pub async fn run(settings: &Settings) -> Result<()> {
+use super::clock_skew_warning;
+
+#[derive(Debug, Serialize)]
+struct Status {
+    version: &'static str,
+    address: String,
+    username: String,
+    /// Unset when never synced
+    last_sync: Option<String>,
+    /// How many seconds the server's clock was ahead of this machine's at the
+    /// last sync, negative if behind. Unset when not yet measured
+    clock_skew_secs: Option<i64>,
+    /// The machines syncing with this account
+    hosts: Vec<StatusHost>,
+}
+
+#[derive(Debug, Serialize)]
+struct StatusHost {
+    #[serde(flatten)]
+    host: HostInfo,
+    current: bool,
+}
+
+pub async fn run(settings: &Settings, store: SqliteStore, json: bool) -> Result<()> {
     if !settings.logged_in().await? {
         bail!("You are not logged in to a sync server - cannot show sync status");
     }
@@ -12,12 +43,43 @@ pub async fn run(settings: &Settings) -> Result<()> {
         &settings.sync_address,
         settings.sync_auth_token().await?,
         settings.network_connect_timeout,
-        settings.network_timeout,
+        settings.network_timeout_for(RequestKind::Sync),
         &settings.extra_headers,
     )?;
 
     let me = client.me().await?;
     let last_sync = Settings::last_sync().await?;
+    let clock_skew = Settings::clock_skew().await?;
+
+    let encryption_key: [u8; 32] = encryption::load_key(settings)
+        .context("could not load encryption key")?
+        .into();
+    let host_id = Settings::host_id().await?;
+    let hosts = HostStore::new(store, host_id, encryption_key)
+        .hosts()
+        .await?;
+
+    if json {
+        let status = Status {
+            version: VERSION,
+            address: settings.sync_address.to_string(),
+            username: me.username,
+            last_sync: (last_sync > time::OffsetDateTime::UNIX_EPOCH)
+                .then(|| last_sync.format(&Rfc3339))
+                .transpose()?,
+            clock_skew_secs: clock_skew.map(time::Duration::whole_seconds),
+            hosts: hosts
+                .into_iter()
+                .map(|host| StatusHost {
+                    current: host.id == host_id,
+                    host,
+                })
+                .collect(),
+        };
+        println!("{}", serde_json::to_string_pretty(&status)?);
+
+        return Ok(());
+    }
 
     println!("Atuin v{VERSION} - Build rev {SHA}\n");
 
@@ -34,5 +96,32 @@ pub async fn run(settings: &Settings) -> Result<()> {
         println!("Username: {}", me.username);
     }
 
+    println!("{}", "[Hosts]".green());
+    for host in &hosts {
+        let about = host
+            .details
+            .as_ref()
+            .map(|details| {
+                [details.os.as_str(), details.shell.as_str()]
+                    .into_iter()
+                    .filter(|part| !part.is_empty())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })
+            .filter(|about| !about.is_empty())
+            .map(|about| format!(" ({about})"))
+            .unwrap_or_default();
+
+        if host.id == host_id {
+            println!("{}{about} <- CURRENT HOST", host.display_name());
+        } else {
+            println!("{}{about}", host.display_name());
+        }
+    }
+
+    if let Some(warning) = clock_skew.and_then(clock_skew_warning) {
+        println!("\n{} {warning}", "Warning:".yellow().bold());
+    }
+
     Ok(())
 }
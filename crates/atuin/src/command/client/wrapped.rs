@@ -140,9 +140,7 @@ impl WrappedStats {
             }
 
             // Track hourly distribution
-            let local_time = entry
-                .timestamp
-                .to_offset(time::UtcOffset::current_local_offset().unwrap_or(settings.timezone.0));
+            let local_time = entry.timestamp.to_offset(settings.timezone.0);
             let hour = format!("{:02}:00", local_time.time().hour());
             *hours.entry(hour).or_default() += 1;
         }
@@ -3,6 +3,18 @@ use clap_complete::{Generator, Shell, generate, generate_to};
 use clap_complete_nushell::Nushell;
 use eyre::Result;
 
+// Completion of values that depend on your history and config (history IDs, theme names),
+// added to the generated scripts for the shells that can run a command to complete a value.
+// Each one asks the hidden `atuin complete` command for candidates.
+const BASH_DYNAMIC: &str = include_str!("../shell/completions/atuin.bash");
+const FISH_DYNAMIC: &str = include_str!("../shell/completions/atuin.fish");
+const ZSH_DYNAMIC: &str = include_str!("../shell/completions/atuin.zsh");
+
+// How the script clap generates for zsh ends: it completes straight away if zsh autoloaded the
+// file, and registers the completion otherwise. The dynamic part replaces it, to register its own
+// function instead.
+const ZSH_REGISTRATION: &str = "if [ \"$funcstack[1]\" = \"_atuin\" ]; then";
+
 // clap put nushell completions into a separate package due to the maintainers
 // being a little less committed to support them.
 // This means we have to do a tiny bit of legwork to combine these completions
@@ -13,6 +25,7 @@ pub enum GenShell {
     Bash,
     Elvish,
     Fish,
+    #[value(alias = "nu")]
     Nushell,
     PowerShell,
     Zsh,
@@ -36,11 +49,27 @@ impl Generator for GenShell {
     fn generate(&self, cmd: &clap::Command, buf: &mut dyn std::io::prelude::Write) {
         match self {
             // clap_complete
-            Self::Bash => Shell::Bash.generate(cmd, buf),
+            Self::Bash => {
+                Shell::Bash.generate(cmd, buf);
+                write_script(buf, BASH_DYNAMIC);
+            }
             Self::Elvish => Shell::Elvish.generate(cmd, buf),
-            Self::Fish => Shell::Fish.generate(cmd, buf),
+            Self::Fish => {
+                Shell::Fish.generate(cmd, buf);
+                write_script(buf, FISH_DYNAMIC);
+            }
             Self::PowerShell => Shell::PowerShell.generate(cmd, buf),
-            Self::Zsh => Shell::Zsh.generate(cmd, buf),
+            Self::Zsh => {
+                let mut script = Vec::new();
+                Shell::Zsh.generate(cmd, &mut script);
+                let script = String::from_utf8_lossy(&script);
+                let script = script
+                    .rfind(ZSH_REGISTRATION)
+                    .map_or(&*script, |end| &script[..end]);
+
+                write_script(buf, script);
+                write_script(buf, ZSH_DYNAMIC);
+            }
 
             // clap_complete_nushell
             Self::Nushell => Nushell.generate(cmd, buf),
@@ -48,6 +77,11 @@ impl Generator for GenShell {
     }
 }
 
+fn write_script(buf: &mut dyn std::io::prelude::Write, script: &str) {
+    buf.write_all(script.as_bytes())
+        .expect("failed to write completion file");
+}
+
 #[derive(Debug, Parser)]
 pub struct Cmd {
     /// Set the shell for generating completions
@@ -82,3 +116,43 @@ impl Cmd {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::{GenShell, ZSH_REGISTRATION};
+
+    fn script(shell: &GenShell) -> String {
+        let mut buf = Vec::new();
+        clap_complete::generate(
+            shell.clone(),
+            &mut crate::Atuin::command(),
+            "atuin",
+            &mut buf,
+        );
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn dynamic_completion_is_hooked_in() {
+        let bash = script(&GenShell::Bash);
+        assert!(bash.contains("complete -F _atuin_dynamic"));
+        assert!(bash.trim_end().ends_with("fi"));
+
+        let fish = script(&GenShell::Fish);
+        assert!(fish.contains("(atuin complete history-id -- (commandline -ct) 2>/dev/null)"));
+
+        // The dynamic part takes over registering the completion from clap's
+        let zsh = script(&GenShell::Zsh);
+        assert_eq!(zsh.matches(ZSH_REGISTRATION).count(), 1);
+        assert!(zsh.contains("compdef _atuin_dynamic atuin"));
+        assert!(!zsh.contains("compdef _atuin atuin"));
+    }
+
+    #[test]
+    fn store_names_are_completed() {
+        let zsh = script(&GenShell::Zsh);
+        assert!(zsh.contains("(history dotfiles scripts)"), "{zsh}");
+    }
+}
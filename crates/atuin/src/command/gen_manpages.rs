@@ -0,0 +1,53 @@
+use std::path::PathBuf;
+
+use clap::{CommandFactory, Parser};
+use eyre::{Context, Result};
+
+#[derive(Debug, Parser)]
+pub struct Cmd {
+    /// The directory to write the man pages to, one per command
+    dir: PathBuf,
+}
+
+impl Cmd {
+    pub fn run(self) -> Result<()> {
+        fs_err::create_dir_all(&self.dir)?;
+
+        clap_mangen::generate_to(crate::Atuin::command(), &self.dir)
+            .wrap_err_with(|| format!("could not write man pages to {}", self.dir.display()))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Cmd;
+
+    #[test]
+    fn writes_a_page_per_command() {
+        let dir = tempfile::tempdir().unwrap();
+        Cmd {
+            dir: dir.path().join("man1"),
+        }
+        .run()
+        .unwrap();
+
+        let page = |name: &str| fs_err::read_to_string(dir.path().join("man1").join(name));
+
+        assert!(page("atuin.1").unwrap().contains(".TH atuin 1"));
+
+        #[cfg(feature = "client")]
+        {
+            assert!(
+                page("atuin-search.1")
+                    .unwrap()
+                    .contains("\\-\\-interactive")
+            );
+            assert!(page("atuin-history-start.1").is_ok());
+
+            // Hidden commands stay out of the manual
+            assert!(page("atuin-complete.1").is_err());
+        }
+    }
+}
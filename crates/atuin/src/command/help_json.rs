@@ -0,0 +1,224 @@
+//! `atuin --help-json`: the whole command line as JSON.
+//!
+//! Every visible command, with its options, arguments and subcommands, as
+//! clap knows them. Packagers and tools can generate docs or completions from
+//! it rather than keeping their own copy of the interface in sync.
+
+use std::io::{self, Write};
+
+use clap::{Arg, ArgAction, Command};
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+struct CommandJson {
+    name: String,
+    /// The full invocation, such as `atuin history start`
+    path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    about: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    long_about: Option<String>,
+    aliases: Vec<String>,
+    args: Vec<ArgJson>,
+    subcommands: Vec<CommandJson>,
+}
+
+#[derive(Debug, Serialize)]
+#[allow(clippy::struct_excessive_bools)]
+struct ArgJson {
+    id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    long: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    short: Option<char>,
+    aliases: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    help: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    long_help: Option<String>,
+    positional: bool,
+    required: bool,
+    /// Whether the argument takes a value, as opposed to being a flag
+    takes_value: bool,
+    /// Whether the argument can be given more than once
+    multiple: bool,
+    global: bool,
+    value_names: Vec<String>,
+    default_values: Vec<String>,
+    possible_values: Vec<PossibleValueJson>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    env: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct PossibleValueJson {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    help: Option<String>,
+}
+
+impl CommandJson {
+    fn new(cmd: &Command, parent: Option<&str>) -> Self {
+        let name = cmd.get_name().to_string();
+        let path = parent.map_or_else(|| name.clone(), |parent| format!("{parent} {name}"));
+
+        Self {
+            version: cmd.get_version().map(ToString::to_string),
+            about: cmd.get_about().map(ToString::to_string),
+            long_about: cmd.get_long_about().map(ToString::to_string),
+            aliases: cmd.get_visible_aliases().map(ToString::to_string).collect(),
+            args: cmd
+                .get_arguments()
+                .filter(|arg| !arg.is_hide_set())
+                .map(ArgJson::new)
+                .collect(),
+            subcommands: cmd
+                .get_subcommands()
+                .filter(|sub| !sub.is_hide_set())
+                .map(|sub| Self::new(sub, Some(&path)))
+                .collect(),
+            name,
+            path,
+        }
+    }
+}
+
+impl ArgJson {
+    fn new(arg: &Arg) -> Self {
+        let takes_value = arg.get_num_args().is_some_and(|n| n.takes_values());
+
+        // Flags have a value name and a default of their own too, that never show up on the
+        // command line
+        let (value_names, default_values, possible_values) = if takes_value {
+            (
+                arg.get_value_names()
+                    .unwrap_or_default()
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect(),
+                arg.get_default_values()
+                    .iter()
+                    .map(|v| v.to_string_lossy().into_owned())
+                    .collect(),
+                arg.get_possible_values()
+                    .iter()
+                    .filter(|v| !v.is_hide_set())
+                    .map(|v| PossibleValueJson {
+                        name: v.get_name().to_string(),
+                        help: v.get_help().map(ToString::to_string),
+                    })
+                    .collect(),
+            )
+        } else {
+            Default::default()
+        };
+
+        Self {
+            id: arg.get_id().to_string(),
+            long: arg.get_long().map(ToString::to_string),
+            short: arg.get_short(),
+            aliases: arg
+                .get_visible_aliases()
+                .unwrap_or_default()
+                .into_iter()
+                .map(ToString::to_string)
+                .collect(),
+            help: arg.get_help().map(ToString::to_string),
+            long_help: arg.get_long_help().map(ToString::to_string),
+            positional: arg.is_positional(),
+            required: arg.is_required_set(),
+            takes_value,
+            multiple: matches!(arg.get_action(), ArgAction::Append | ArgAction::Count)
+                || arg.get_num_args().is_some_and(|n| n.max_values() > 1),
+            global: arg.is_global_set(),
+            value_names,
+            default_values,
+            possible_values,
+            env: arg.get_env().map(|env| env.to_string_lossy().into_owned()),
+        }
+    }
+}
+
+/// Write the tree of `cmd` and its subcommands as pretty-printed JSON.
+pub fn write(out: &mut impl Write, cmd: Command) -> io::Result<()> {
+    // Build it first, to get the generated `--help` and `--version` and the global arguments
+    // propagated to every subcommand, as they are when parsing
+    let mut cmd = cmd.disable_help_subcommand(true);
+    cmd.build();
+
+    serde_json::to_writer_pretty(&mut *out, &CommandJson::new(&cmd, None))?;
+    writeln!(out)?;
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::write;
+
+    fn tree() -> serde_json::Value {
+        let mut out = Vec::new();
+        write(&mut out, crate::Atuin::command()).unwrap();
+        serde_json::from_slice(&out).unwrap()
+    }
+
+    fn find<'a>(list: &'a serde_json::Value, key: &str, name: &str) -> &'a serde_json::Value {
+        list.as_array()
+            .unwrap()
+            .iter()
+            .find(|item| item[key] == name)
+            .unwrap_or_else(|| panic!("no {name} in {list}"))
+    }
+
+    #[test]
+    fn describes_the_root() {
+        let tree = tree();
+
+        assert_eq!(tree["name"], "atuin");
+        assert_eq!(tree["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(tree["about"], "Magical shell history");
+
+        let shell = find(
+            &find(&tree["subcommands"], "name", "gen-completions")["args"],
+            "id",
+            "shell",
+        );
+        assert_eq!(shell["long"], "shell");
+        assert_eq!(shell["short"], "s");
+        assert_eq!(shell["takes_value"], true);
+        assert_eq!(
+            find(&shell["possible_values"], "name", "zsh")["name"],
+            "zsh"
+        );
+    }
+
+    #[cfg(feature = "client")]
+    #[test]
+    fn describes_nested_commands() {
+        let tree = tree();
+
+        let history = find(&tree["subcommands"], "name", "history");
+        let start = find(&history["subcommands"], "name", "start");
+        assert_eq!(start["path"], "atuin history start");
+
+        let command = find(&start["args"], "id", "command");
+        assert_eq!(command["positional"], true);
+        assert_eq!(command["multiple"], true);
+
+        let help = find(&start["args"], "id", "help");
+        assert_eq!(help["takes_value"], false);
+        assert_eq!(help["value_names"], serde_json::json!([]));
+
+        // Hidden commands are left out, as they are from the man pages
+        assert!(
+            !tree["subcommands"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .any(|cmd| cmd["name"] == "complete")
+        );
+    }
+}
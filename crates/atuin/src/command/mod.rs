@@ -11,6 +11,8 @@ mod client;
 mod contributors;
 
 mod gen_completions;
+mod gen_manpages;
+pub mod help_json;
 
 mod external;
 
@@ -35,12 +37,15 @@ pub enum AtuinCmd {
     /// Generate shell completions
     GenCompletions(gen_completions::Cmd),
 
+    /// Generate man pages
+    GenManpages(gen_manpages::Cmd),
+
     #[command(external_subcommand)]
     External(Vec<String>),
 }
 
 impl AtuinCmd {
-    pub fn run(self) -> Result<()> {
+    pub fn run(self, offline: bool) -> Result<()> {
         // set umask before we potentially open/create files
         // or in other words, 077. Do not allow any access to any other user.
         // Keep the previous umask so pty-proxy can restore it in the shell it
@@ -57,7 +62,7 @@ impl AtuinCmd {
 
         match self {
             #[cfg(feature = "client")]
-            Self::Client(client) => client.run(),
+            Self::Client(client) => client.run(offline),
 
             #[cfg(all(feature = "pty-proxy", unix))]
             Self::PtyProxy(proxy) => {
@@ -80,6 +85,7 @@ impl AtuinCmd {
                 Ok(())
             }
             Self::GenCompletions(gen_completions) => gen_completions.run(),
+            Self::GenManpages(gen_manpages) => gen_manpages.run(),
             Self::External(args) => external::run(&args),
         }
     }
@@ -0,0 +1,169 @@
+//! What happens when atuin panics.
+//!
+//! The search TUI and the store browser put the terminal into raw mode, usually on the
+//! alternate screen, and undo that when their guards are dropped. A panic on another thread
+//! never reaches those guards, and leaves the user typing blind into a terminal they have to
+//! `reset`. So the guards also register the modes they turn on here, and the panic hook turns
+//! them off again before anything else.
+//!
+//! The panic itself goes to `crash.log` in the log directory, with a one-line pointer on
+//! stderr, so the details survive whatever the shell does to the screen afterwards.
+
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    panic::PanicHookInfo,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use ratatui::crossterm::{cursor, event, execute, terminal};
+
+const CRASH_LOG: &str = "crash.log";
+
+/// Whether the terminal is in raw mode, and if so whether it's on the alternate screen too.
+/// Everything else the guards turn on is harmless to turn off when it isn't on, but leaving
+/// an alternate screen that was never entered moves the cursor.
+static ACTIVE: Mutex<Option<bool>> = Mutex::new(None);
+
+/// Record that the terminal is in raw mode, until [`terminal_released`] is called.
+pub fn terminal_taken(alternate_screen: bool) {
+    *ACTIVE
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner) = Some(alternate_screen);
+}
+
+/// Record that the terminal is back to normal.
+pub fn terminal_released() {
+    *ACTIVE
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner) = None;
+}
+
+/// Install the panic hook. Panic reports go to `crash.log` in `log_dir`.
+pub fn install(log_dir: &Path) {
+    let log_dir = log_dir.to_path_buf();
+    let previous = std::panic::take_hook();
+
+    std::panic::set_hook(Box::new(move |info| {
+        let restored = restore_terminal();
+
+        match write_report(&log_dir, info) {
+            Ok(path) => {
+                eprintln!("atuin crashed: {}", message(info));
+                eprintln!(
+                    "The details are in {}. Please include them if you report this at \
+                     https://github.com/atuinsh/atuin/issues",
+                    path.display()
+                );
+            }
+            Err(_) => previous(info),
+        }
+
+        // The TUI can't carry on with the terminal restored under it, and a panic on a
+        // background thread wouldn't stop it otherwise.
+        if restored {
+            std::process::exit(1);
+        }
+    }));
+}
+
+/// Turn off whatever terminal modes are on. Returns whether there were any.
+fn restore_terminal() -> bool {
+    let Some(alternate_screen) = ACTIVE
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .take()
+    else {
+        return false;
+    };
+
+    let mut out = terminal_writer();
+
+    // Best effort: there's nothing useful to do with a failure here.
+    #[cfg(not(target_os = "windows"))]
+    let _ = execute!(out, event::PopKeyboardEnhancementFlags);
+    if alternate_screen {
+        let _ = execute!(out, terminal::LeaveAlternateScreen);
+    }
+    let _ = execute!(
+        out,
+        event::DisableMouseCapture,
+        event::DisableBracketedPaste,
+        cursor::Show
+    );
+    let _ = terminal::disable_raw_mode();
+
+    true
+}
+
+/// The terminal itself, since the shell widgets capture stdout and sometimes stderr.
+fn terminal_writer() -> Box<dyn Write> {
+    #[cfg(unix)]
+    if let Ok(tty) = OpenOptions::new().write(true).open("/dev/tty") {
+        return Box::new(tty);
+    }
+
+    Box::new(std::io::stderr())
+}
+
+fn message<'a>(info: &'a PanicHookInfo<'_>) -> &'a str {
+    info.payload_as_str().unwrap_or("unknown panic")
+}
+
+/// Append a report of the panic to the crash log, and return its path.
+fn write_report(log_dir: &Path, info: &PanicHookInfo) -> std::io::Result<PathBuf> {
+    let location = info
+        .location()
+        .map_or_else(|| "unknown location".to_string(), ToString::to_string);
+    let thread = std::thread::current();
+
+    append_report(
+        log_dir,
+        &format!(
+            "atuin {} panicked on thread {} at {location}:\n{}\n\n{}",
+            crate::LONG_VERSION,
+            thread.name().unwrap_or("<unnamed>"),
+            message(info),
+            std::backtrace::Backtrace::force_capture()
+        ),
+    )
+}
+
+fn append_report(log_dir: &Path, report: &str) -> std::io::Result<PathBuf> {
+    std::fs::create_dir_all(log_dir)?;
+    let path = log_dir.join(CRASH_LOG);
+
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+    let now = time::OffsetDateTime::now_utc()
+        .format(&time::format_description::well_known::Rfc3339)
+        .unwrap_or_default();
+    writeln!(file, "--- {now}\n{report}\n")?;
+
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_are_appended() {
+        let dir = tempfile::tempdir().unwrap();
+        let logs = dir.path().join("logs");
+
+        let path = append_report(&logs, "first").unwrap();
+        append_report(&logs, "second").unwrap();
+
+        let log = std::fs::read_to_string(path).unwrap();
+        assert_eq!(log.matches("--- ").count(), 2);
+        assert!(log.find("first").unwrap() < log.find("second").unwrap());
+    }
+
+    #[test]
+    fn nothing_to_restore_when_the_terminal_is_free() {
+        terminal_taken(false);
+        terminal_released();
+        assert!(!restore_terminal());
+    }
+}
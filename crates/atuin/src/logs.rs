@@ -1,4 +1,4 @@
-use atuin_common::logs::{FileConfig, LogConfig, StderrConfig};
+use atuin_common::logs::{FileConfig, LogConfig, LogFormat, StderrConfig};
 use std::fs::OpenOptions;
 use std::io::IsTerminal;
 use tracing::Level;
@@ -102,11 +102,12 @@ where
 
     let file_layer = config.file.as_ref().map(|file| {
         let writer = make_file_writer(file)?;
-        let layer = fmt::layer()
-            .with_writer(writer)
-            .with_ansi(false)
-            .with_filter(filter.clone());
-        Ok::<_, FileWriterError>(layer)
+        let layer = fmt::layer().with_writer(writer).with_ansi(false);
+        let layer = match file.format {
+            LogFormat::Json => layer.json().boxed(),
+            LogFormat::Text => layer.boxed(),
+        };
+        Ok::<_, FileWriterError>(layer.with_filter(filter.clone()))
     });
 
     let stderr_layer = config.stderr.as_ref().map(|stderr| {
@@ -138,6 +139,25 @@ where
         Some(layer)
     });
 
+    // Spans from the daemon's components and syncs, whatever the log files show
+    #[cfg(feature = "otel")]
+    let (otel_layer, otel_error) = match config.otel.as_ref().map(|otel| {
+        atuin_common::otel::init(otel, "atuin-daemon")
+            .map(|layer| layer.with_filter(LevelFilter::INFO))
+    }) {
+        Some(Ok(layer)) => (Some(layer), None),
+        Some(Err(e)) => (None, Some(e.to_string())),
+        None => (None, None),
+    };
+    #[cfg(not(feature = "otel"))]
+    let (otel_layer, otel_error) = (
+        None::<LevelFilter>,
+        config
+            .otel
+            .as_ref()
+            .map(|_| "atuin was built without the otel feature".to_string()),
+    );
+
     let (file_layer, file_error) = match file_layer.transpose() {
         Ok(layer) => (layer, None),
         Err(e) => (None, Some(e)),
@@ -148,6 +168,7 @@ where
         .with(file_layer)
         .with(stderr_layer)
         .with(span_layer)
+        .with(otel_layer)
         .try_init()
     {
         if has_stderr_layer || cfg!(debug_assertions) {
@@ -156,6 +177,10 @@ where
         return;
     }
 
+    if let Some(e) = otel_error {
+        tracing::warn!("not exporting traces: {e}");
+    }
+
     if let Some(e) = file_error {
         if has_stderr_layer {
             tracing::warn!("failed to initialize log file: {e}");
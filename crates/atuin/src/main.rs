@@ -1,14 +1,17 @@
 #![warn(clippy::pedantic, clippy::nursery)]
 #![allow(clippy::use_self, clippy::missing_const_for_fn)] // not 100% reliable
 
-use clap::Parser;
 use clap::builder::Styles;
 use clap::builder::styling::{AnsiColor, Effects};
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser};
 use eyre::Result;
 
 use command::AtuinCmd;
 
 mod command;
+#[cfg(feature = "client")]
+pub(crate) mod crash;
 pub(crate) mod logs;
 #[cfg(feature = "client")]
 pub(crate) mod shell;
@@ -47,18 +50,68 @@ const STYLES: Styles = Styles::styled()
     long_version = LONG_VERSION,
     help_template(HELP_TEMPLATE),
     styles = STYLES,
+    arg_required_else_help = true,
 )]
+#[allow(clippy::struct_field_names)]
 struct Atuin {
+    /// Print every command and its options as JSON
+    #[arg(long, exclusive = true)]
+    help_json: bool,
+
+    /// Don't use the network: skip syncing and update checks
+    #[arg(long, global = true)]
+    offline: bool,
+
+    /// Turn off colors, in the output and in the search
+    #[arg(long, global = true)]
+    no_color: bool,
+
+    /// Never write to the databases, sync or record history, for looking through a copy of
+    /// someone else's database safely. Also set with ATUIN_READONLY
+    #[arg(long, global = true)]
+    read_only: bool,
+
     #[command(subcommand)]
-    atuin: AtuinCmd,
+    atuin: Option<AtuinCmd>,
 }
 
 impl Atuin {
     fn run(self) -> Result<()> {
-        self.atuin.run()
+        if self.no_color {
+            crossterm::style::force_color_output(false);
+        }
+        #[cfg(feature = "client")]
+        if self.read_only {
+            atuin_client::read_only::enable();
+        }
+
+        match (self.help_json, self.atuin) {
+            (true, None) => {
+                command::help_json::write(&mut std::io::stdout().lock(), Self::command())?;
+                Ok(())
+            }
+            (true, Some(_)) => Self::command()
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    "--help-json can't be used with a subcommand",
+                )
+                .exit(),
+            (false, Some(atuin)) => atuin.run(self.offline),
+            (false, None) => Self::command()
+                .error(ErrorKind::MissingSubcommand, "a subcommand is required")
+                .exit(),
+        }
     }
 }
 
 fn main() -> Result<()> {
-    Atuin::parse().run()
+    let result = Atuin::parse().run();
+
+    // Say what to do about the failures we can name, ahead of the usual chain of causes
+    #[cfg(feature = "sync")]
+    if let Err(e) = &result {
+        print_error::print_client_error(e);
+    }
+
+    result
 }
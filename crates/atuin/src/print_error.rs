@@ -1,6 +1,6 @@
 use std::io::IsTerminal;
 
-use atuin_client::record::sync::SyncError;
+use atuin_client::{error::ClientError, record::sync::SyncError};
 use colored::Colorize;
 use crossterm::terminal;
 
@@ -50,6 +50,14 @@ pub fn format_sync_error(e: SyncError) -> eyre::Report {
     e.into()
 }
 
+/// Paint the banner for a failure the client library can name, with what to do about it.
+/// Anything else is left to the usual error report.
+pub fn print_client_error(report: &eyre::Report) {
+    if let Some(e) = ClientError::find(report) {
+        print_error(e.title(), &e.hint());
+    }
+}
+
 fn wrap_text(text: &str, width: usize) -> Vec<String> {
     let mut out = Vec::new();
     for paragraph in text.split('\n') {
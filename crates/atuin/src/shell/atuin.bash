@@ -73,9 +73,12 @@ __atuin_preexec() {
     # attaching state can dynamically change.
     __atuin_update_preexec_backend
 
-    local id
-    id=$(ATUIN_SHELL=bash atuin history start --hook -- "$1" 2>/dev/null)
-    export ATUIN_HISTORY_ID=$id
+    local out
+    # Prints the ID, then a monotonic clock reading for `history end`
+    out=$(ATUIN_SHELL=bash atuin history start --hook --clock -- "$1" 2>/dev/null)
+    export ATUIN_HISTORY_ID=${out%% *}
+    __atuin_preexec_clock=${out#"$ATUIN_HISTORY_ID"}
+    __atuin_preexec_clock=${__atuin_preexec_clock# }
     [[ -n ${__atuin_skip_osc133:-} ]] || __atuin_osc133_command_executed
     __atuin_preexec_time=${EPOCHREALTIME-}
 }
@@ -129,7 +132,7 @@ __atuin_precmd() {
     fi
 
     [[ -n ${__atuin_skip_osc133:-} ]] || __atuin_osc133_command_finished "$EXIT"
-    (atuin history end --hook --exit "$EXIT" ${duration:+"--duration=$duration"} -- "$ATUIN_HISTORY_ID" >/dev/null 2>&1 &)
+    (atuin history end --hook --exit "$EXIT" ${duration:+"--duration=$duration"} ${__atuin_preexec_clock:+"--started-clock=$__atuin_preexec_clock"} -- "$ATUIN_HISTORY_ID" >/dev/null 2>&1 &)
     export ATUIN_HISTORY_ID=""
 }
 
@@ -20,7 +20,10 @@ end
 
 function _atuin_preexec --on-event fish_preexec
     if not test -n "$fish_private_mode"
-        set -g ATUIN_HISTORY_ID (ATUIN_SHELL=fish atuin history start --hook -- "$argv[1]" 2>/dev/null)
+        # Prints the ID, then a monotonic clock reading for `history end`
+        set -l out (ATUIN_SHELL=fish atuin history start --hook --clock -- "$argv[1]" 2>/dev/null | string split -m 1 ' ')
+        set -g ATUIN_HISTORY_ID $out[1]
+        set -g __atuin_preexec_clock $out[2]
         _atuin_osc133_command_executed
     end
 end
@@ -30,11 +33,12 @@ function _atuin_postexec --on-event fish_postexec
 
     if test -n "$ATUIN_HISTORY_ID"
         _atuin_osc133_command_finished $s
-        atuin history end --hook --exit $s -- $ATUIN_HISTORY_ID &>/dev/null &
+        atuin history end --hook --exit $s --started-clock=$__atuin_preexec_clock -- $ATUIN_HISTORY_ID &>/dev/null &
         disown
     end
 
     set --erase ATUIN_HISTORY_ID
+    set --erase __atuin_preexec_clock
 end
 
 # Check if tmux popup is available (tmux >= 3.2)
@@ -0,0 +1,27 @@
+
+# Values that depend on your history and config come from `atuin complete`.
+_atuin_dynamic() {
+    local cur="${COMP_WORDS[COMP_CWORD]}"
+    local prev="${COMP_WORDS[COMP_CWORD-1]}"
+    local kind=""
+
+    if [[ ${COMP_WORDS[1]} == history && ${COMP_WORDS[2]} == end && $COMP_CWORD -ge 3 && $cur != -* && $prev != -* ]]; then
+        kind=history-id
+    elif [[ ${COMP_WORDS[1]} == config && ${COMP_WORDS[2]} == set && $prev == theme.name && $COMP_CWORD -eq 4 ]]; then
+        kind=theme
+    fi
+
+    if [[ -n $kind ]]; then
+        local IFS=$'\n'
+        COMPREPLY=($(atuin complete "$kind" -- "$cur" 2>/dev/null | cut -f1))
+        return 0
+    fi
+
+    _atuin "$@"
+}
+
+if [[ "${BASH_VERSINFO[0]}" -eq 4 && "${BASH_VERSINFO[1]}" -ge 4 || "${BASH_VERSINFO[0]}" -gt 4 ]]; then
+    complete -F _atuin_dynamic -o nosort -o bashdefault -o default atuin
+else
+    complete -F _atuin_dynamic -o bashdefault -o default atuin
+fi
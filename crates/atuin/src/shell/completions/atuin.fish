@@ -0,0 +1,4 @@
+
+# Values that depend on your history and config come from `atuin complete`.
+complete -c atuin -n "__fish_seen_subcommand_from history; and __fish_seen_subcommand_from end" -f -a "(atuin complete history-id -- (commandline -ct) 2>/dev/null)"
+complete -c atuin -n "__fish_seen_subcommand_from config; and __fish_seen_subcommand_from set; and __fish_seen_subcommand_from theme.name" -f -a "(atuin complete theme -- (commandline -ct) 2>/dev/null)"
@@ -3,8 +3,8 @@ use atuin_scripts::store::ScriptStore;
 use eyre::{Context, Result};
 
 use atuin_client::{
-    database::Database, history::store::HistoryStore, record::sqlite_store::SqliteStore,
-    settings::Settings,
+    database::Database, history::store::HistoryStore, host::HostStore,
+    record::sqlite_store::SqliteStore, session::SessionStore, settings::Settings,
 };
 use atuin_common::record::RecordId;
 use atuin_kv::store::KvStore;
@@ -36,30 +36,41 @@ pub async fn build(
     let var_store = VarStore::new(store.clone(), host_id, encryption_key);
     let kv_store = KvStore::new(store.clone(), kv_db, host_id, encryption_key);
     let script_store = ScriptStore::new(store.clone(), host_id, encryption_key);
+    let host_store = HostStore::new(store.clone(), host_id, encryption_key);
+    let session_store = SessionStore::new(store.clone(), host_id, encryption_key);
 
     // A failure in one store should not stop the others from building - build as much as
     // possible, and warn about the rest.
     if let Err(e) = history_store.build_all(db, downloaded).await {
-        eprintln!("Warning: failed to build history: {e}");
+        tracing::warn!("failed to build history: {e}");
+    }
+
+    if let Err(e) = session_store.build(db).await {
+        tracing::warn!("failed to build session names: {e}");
     }
 
     if let Err(e) = alias_store.build().await {
-        eprintln!("Warning: failed to build aliases: {e}");
+        tracing::warn!("failed to build aliases: {e}");
     }
 
     if let Err(e) = var_store.build().await {
-        eprintln!("Warning: failed to build vars: {e}");
+        tracing::warn!("failed to build vars: {e}");
     }
 
     if let Err(e) = kv_store.build().await {
-        eprintln!("Warning: failed to build kv: {e}");
+        tracing::warn!("failed to build kv: {e}");
     }
 
     let script_db =
         atuin_scripts::database::Database::new(settings.scripts.db_path.clone(), 1.0).await?;
 
     if let Err(e) = script_store.build(script_db).await {
-        eprintln!("Warning: failed to build scripts: {e}");
+        tracing::warn!("failed to build scripts: {e}");
+    }
+
+    // Goes up with the next sync, if this machine is new or has changed
+    if let Err(e) = host_store.register().await {
+        tracing::warn!("failed to register host: {e}");
     }
 
     Ok(())
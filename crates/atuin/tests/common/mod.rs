@@ -39,6 +39,7 @@ pub async fn start_server(path: &str) -> (url::Url, oneshot::Sender<()>, JoinHan
             read_db_uri: None,
         },
         metrics: atuin_server::settings::Metrics::default(),
+        otel: Default::default(),
         fake_version: None,
     };
 